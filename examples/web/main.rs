@@ -0,0 +1,63 @@
+//! Renders a lit cube into the `<canvas id="vavo">` element of `index.html`.
+//!
+//! Build it for the web and generate the JS bindings with
+//! [`wasm-bindgen`](https://github.com/rustwasm/wasm-bindgen):
+//! ```sh
+//! cargo build --release --example web --target wasm32-unknown-unknown
+//! wasm-bindgen --target web --no-typescript --out-dir examples/web/pkg \
+//!     target/wasm32-unknown-unknown/release/examples/web.wasm
+//! ```
+//! Then serve the `examples/web` directory with any static file server and open `index.html`.
+//! On native targets the example opens a regular window.
+
+use vavo::{math::shapes::Cube, prelude::*, window::config::WindowConfig};
+
+fn main() {
+    App::build()
+        .set_resource(WindowConfig {
+            title: "vavo web".to_string(),
+            canvas: Some("vavo".to_string()),
+            ..Default::default()
+        })
+        .add_plugins(DefaultPlugins)
+        .add_startup_system(setup_system)
+        .add_system(rotate_system)
+        .run();
+}
+
+fn setup_system(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<Material>>,
+) {
+    commands
+        .spawn_empty()
+        .insert(Camera::default())
+        .insert(Camera3D::default())
+        .insert(Projection::perspective())
+        .insert(
+            Transform::new()
+                .with_translation(Vec3::new(0.0, 2.0, 6.0))
+                .looking_at(Vec3::ZERO, Vec3::Y),
+        );
+
+    commands
+        .spawn_empty()
+        .insert(PointLight::default())
+        .insert(Transform::new().with_translation(Vec3::new(2.0, 4.0, 3.0)));
+
+    commands
+        .spawn_empty()
+        .insert(meshes.add(Mesh::from(Cube::new(1.5))))
+        .insert(materials.add(Material {
+            base_color: color::ORANGE,
+            ..Default::default()
+        }))
+        .insert(Transform::new());
+}
+
+fn rotate_system(time: Res<Time>, mut query: Query<&mut Transform, With<Handle<Mesh>>>) {
+    for transform in query.iter_mut() {
+        transform.rotate_y(time.delta());
+    }
+}
@@ -1,19 +1,26 @@
 use std::any::{TypeId, type_name};
+use std::rc::Rc;
 
 use winit::dpi::PhysicalSize;
 use winit::event::ElementState;
 use winit::keyboard::PhysicalKey;
 
+use crate::console::{CommandArgs, CommandOutput, register_command};
 use crate::core::graph::RenderGraph;
 use crate::ecs::state::systems::register_state_events;
 use crate::event::{Event, apply_events};
-use crate::prelude::{FixedTime, Resource};
+use crate::network::register_replicated;
+use crate::prelude::{Component, FixedTime, Resource, Texture};
 use crate::reflect::{Reflect, registry::ReflectTypeRegistry};
+use crate::render_assets::{
+    BindGroup, BindGroupLayouts, Buffer, Pipeline, PipelineCache, RenderAssets, StagingBelt,
+};
 use crate::renderer::newtype::{
-    RenderSurface, RenderSurfaceConfiguration, RenderSurfaceTexture, RenderSurfaceTextureView,
+    RenderDevice, RenderSurface, RenderSurfaceConfiguration, RenderSurfaceTexture,
+    RenderSurfaceTextureView,
 };
 use crate::system::{IntoSchedulerLocation, IntoSystem, PhaseLabel, Scheduler, SystemParam, phase};
-use crate::window::AppHandler;
+use crate::window::{AppHandler, WindowSurfaces, render_windows};
 
 use crate::ecs::state::{NextState, State, States, systems::apply_state_transition};
 use crate::ecs::world::World;
@@ -21,6 +28,10 @@ use crate::event::{Events, KeyboardInput, MouseInput};
 
 use super::Plugin;
 use super::input::{Input, KeyCode, MouseButton};
+use super::plugin::{AddedPlugin, PluginGroup, PluginId};
+
+/// Function which drives the app after [`App::run`] is called, see [`App::set_runner`]
+type Runner = Box<dyn FnOnce(&mut App)>;
 
 pub struct App {
     scheduler: Scheduler,
@@ -30,6 +41,11 @@ pub struct App {
 
     known_states: Vec<TypeId>,
     known_events: Vec<TypeId>,
+    plugins: Vec<AddedPlugin>,
+    /// Custom runner, the winit event loop is used if None
+    runner: Option<Runner>,
+    /// Whether [`Self::setup`] already ran
+    is_setup: bool,
     pub type_registry: ReflectTypeRegistry,
 }
 
@@ -42,6 +58,9 @@ impl App {
             world: World::new(),
             known_states: Vec::new(),
             known_events: Vec::new(),
+            plugins: Vec::new(),
+            runner: None,
+            is_setup: false,
             type_registry: ReflectTypeRegistry::new(),
         }
     }
@@ -95,6 +114,41 @@ impl App {
         self
     }
 
+    /// Register component `C` as reflectable and serializable, so it's saved and loaded with
+    /// [`DynamicScene`](crate::assets::scene::DynamicScene)s
+    #[cfg(feature = "serialize")]
+    pub fn register_serializable<C>(&mut self) -> &mut Self
+    where
+        C: Component + Reflect + serde::Serialize + serde::de::DeserializeOwned,
+    {
+        self.type_registry.register_serializable::<C>();
+        self
+    }
+
+    /// Replicate component `C` from the server to clients, it has to be registered on both sides.
+    /// Clients insert `C::default()` before applying the first received value. See the
+    /// [network module](crate::network).
+    pub fn replicate<C: Component + Reflect + Default>(&mut self) -> &mut Self {
+        register_replicated::<C>(self);
+        self
+    }
+
+    /// Add a command to the developer console, which parses arguments `A` from the command line
+    /// and prints the output of `command`. It can be registered before or after the
+    /// [`ConsolePlugin`](crate::console::ConsolePlugin). See the
+    /// [console module](crate::console).
+    ///
+    /// # Panics
+    /// Panics if a command with the same name is already registered or built in.
+    pub fn register_command<A: CommandArgs, O: CommandOutput>(
+        &mut self,
+        name: impl Into<String>,
+        command: impl FnMut(&mut World, A) -> O + Send + Sync + 'static,
+    ) -> &mut Self {
+        register_command(self, name.into(), command);
+        self
+    }
+
     /// Add new resource with a default value to the app if it doesn't exist
     pub fn init_resource<R: Resource + Default>(&mut self) -> &mut Self {
         if !self.world.resources.contains::<R>() {
@@ -143,12 +197,107 @@ impl App {
         self
     }
 
+    /// Add a sync point after the layer at `location`, commands queued by the systems before it
+    /// are applied there instead of at the end of the phase. See
+    /// [`SchedulerChanges::sync_point`](crate::system::SchedulerChanges::sync_point).
+    pub fn add_sync_point(&mut self, location: impl IntoSchedulerLocation) -> &mut Self {
+        self.scheduler.pending_changes.sync_point(location);
+        self
+    }
+
+    /// Set whether `phase` applies commands after every layer, as if each had a
+    /// [sync point](Self::add_sync_point)
+    pub fn sync_layers(&mut self, phase: impl PhaseLabel, enabled: bool) -> &mut Self {
+        self.scheduler.pending_changes.sync_layers(phase, enabled);
+        self
+    }
+
     /// Add a plugin to the app
-    pub fn add_plugin(&mut self, plugin: impl Plugin) -> &mut Self {
-        plugin.build(self);
+    pub fn add_plugin<P: Plugin>(&mut self, plugin: P) -> &mut Self {
+        self.add_boxed_plugin(PluginId::of::<P>(), Box::new(plugin));
         self
     }
 
+    /// Add all enabled plugins of a plugin group to the app
+    pub fn add_plugins(&mut self, group: impl PluginGroup) -> &mut Self {
+        group.build().add_to(self);
+        self
+    }
+
+    pub(crate) fn add_boxed_plugin(&mut self, id: PluginId, plugin: Box<dyn Plugin>) {
+        plugin.build(self);
+
+        // the first instance of a plugin type receives the lifecycle calls
+        if !self.has_plugin_id(id) {
+            self.plugins.push(AddedPlugin {
+                id,
+                dependencies: plugin.dependencies(),
+                plugin: Rc::from(plugin),
+            });
+        }
+    }
+
+    #[inline]
+    fn has_plugin_id(&self, id: PluginId) -> bool {
+        self.plugins.iter().any(|added| added.id == id)
+    }
+
+    /// Returns true if plugin `P` was added to the app
+    pub fn has_plugin<P: Plugin>(&self) -> bool {
+        self.has_plugin_id(PluginId::of::<P>())
+    }
+
+    /// Panics if any plugin is missing one of its dependencies
+    fn check_plugin_dependencies(&self) {
+        let mut missing = Vec::new();
+        for plugin in &self.plugins {
+            for dependency in &plugin.dependencies {
+                if !self.has_plugin_id(*dependency) {
+                    missing.push(format!(
+                        "{} requires {}",
+                        plugin.id.name(),
+                        dependency.name()
+                    ));
+                }
+            }
+        }
+
+        if !missing.is_empty() {
+            panic!("Missing plugin dependencies: {}", missing.join(", "));
+        }
+    }
+
+    /// Returns true if all plugins are ready to be finished
+    pub fn plugins_ready(&self) -> bool {
+        self.plugins.iter().all(|added| added.plugin.ready(self))
+    }
+
+    /// Call [`Plugin::finish`] on all plugins, in the order they were added
+    fn finish_plugins(&mut self) {
+        // plugins added during `finish` are finished as well
+        let mut index = 0;
+        while let Some(added) = self.plugins.get(index) {
+            let plugin = Rc::clone(&added.plugin);
+            plugin.finish(self);
+            index += 1;
+        }
+    }
+
+    /// Call [`Plugin::cleanup`] on all plugins, in reverse order. Custom runners should call it
+    /// before they return.
+    pub fn cleanup_plugins(&mut self) {
+        let plugins = self
+            .plugins
+            .iter()
+            .rev()
+            .map(|added| Rc::clone(&added.plugin))
+            .collect::<Vec<_>>();
+
+        for plugin in plugins {
+            plugin.cleanup(self);
+        }
+    }
+
     /// Get a mutable reference to the render graph. Use [Self::reborrow] in combination with this.
     ///
     /// # Safety
@@ -183,16 +332,32 @@ impl App {
             .phase_before(RenderGraphPhase, phase::PostRender);
 
         let execute_render_graph_system = |world: &mut World, render_graph: &mut RenderGraph| {
-            render_graph.execute(world);
-            world.flush_commands();
+            // nothing to render into, e.g. in a headless runner
+            if !world.resources.contains::<RenderSurfaceTextureView>() {
+                return;
+            }
+
+            render_windows(world, render_graph);
         };
 
         self.scheduler
             .add_system(execute_render_graph_system.build(), RenderGraphPhase);
     }
 
+    /// Finish the plugins and run the startup phases. Custom runners should call it once all
+    /// plugins are [ready](Self::plugins_ready), before the first [`update`](Self::update).
+    pub fn setup(&mut self) {
+        if self.is_setup {
+            return;
+        }
+
+        self.is_setup = true;
+        self.finish_plugins();
+        self.startup();
+    }
+
     /// Initialize the app and run startup phases
-    pub(crate) fn startup(&mut self) {
+    fn startup(&mut self) {
         self.initialize();
 
         self.scheduler
@@ -201,17 +366,97 @@ impl App {
             .execute_phase(&mut self.world, phase::Startup);
     }
 
+    /// Returns the system scheduler
+    #[inline]
+    pub(crate) fn scheduler_mut(&mut self) -> &mut Scheduler {
+        &mut self.scheduler
+    }
+
     /// Resize the app
     pub(crate) fn resize(&mut self, size: PhysicalSize<u32>) {
         self.render_graph.resize(size);
     }
 
-    /// Run the app event loop
+    /// Drops render assets and graph node data created with a lost device, so they are recreated
+    /// with the current one
+    pub(crate) fn invalidate_render_assets(&mut self) {
+        let resources = &mut self.world.resources;
+        resources.get_mut::<RenderAssets<Buffer>>().clear();
+        resources.get_mut::<RenderAssets<BindGroup>>().clear();
+        resources.get_mut::<RenderAssets<Pipeline>>().clear();
+        resources.get_mut::<RenderAssets<Texture>>().clear();
+        resources.insert(BindGroupLayouts::default());
+        if resources.contains::<StagingBelt>() {
+            resources.insert(StagingBelt::new());
+        }
+        if let Some(mut pipelines) = resources.try_get_mut::<PipelineCache>() {
+            pipelines.clear();
+        }
+
+        self.render_graph.invalidate();
+    }
+
+    /// Replace the winit event loop with a custom runner, called by [`Self::run`]. The runner
+    /// drives the app with [`Self::setup`], [`Self::update`] and [`Self::cleanup_plugins`].
+    ///
+    /// # Usage
+    /// ```ignore
+    /// // headless simulation of 600 frames
+    /// app.set_runner(|app| {
+    ///     app.setup();
+    ///     for _ in 0..600 {
+    ///         app.update();
+    ///     }
+    ///     app.cleanup_plugins();
+    /// });
+    /// ```
+    pub fn set_runner(&mut self, runner: impl FnOnce(&mut App) + 'static) -> &mut Self {
+        self.runner = Some(Box::new(runner));
+        self
+    }
+
+    /// Run the app with its runner, by default the winit event loop
     pub fn run(&mut self) {
-        let (event_loop, mut app) = AppHandler::init(self);
+        self.check_plugin_dependencies();
+
+        match self.runner.take() {
+            Some(runner) => runner(self),
+            None => Self::winit_runner(self),
+        }
+    }
+
+    /// Default runner, which creates a window and renders to it
+    fn winit_runner(app: &mut App) {
+        let (event_loop, mut app) = AppHandler::init(app);
         event_loop.run_app(&mut app).unwrap();
     }
 
+    /// Execute one full scheduler pipeline without a window or surface, e.g. in tests or custom
+    /// runners. The first call runs [`Self::setup`] if it didn't run yet. Render graph nodes are
+    /// skipped, use [`Self::execute_scheduler`] to render to a surface.
+    ///
+    /// # Usage
+    /// ```ignore
+    /// let mut app = App::build();
+    /// app.add_plugin(TimePlugin).add_system(gravity_system);
+    ///
+    /// let id = app.world.spawn();
+    /// app.world.insert_component(id, Transform::new(), false);
+    /// for _ in 0..10 {
+    ///     app.update();
+    /// }
+    ///
+    /// let mut query = app.world.query::<&Transform>();
+    /// assert!(query.get(id).unwrap().translation.y < 0.0);
+    /// ```
+    pub fn update(&mut self) {
+        self.setup();
+
+        self.world.update();
+        self.scheduler.execute_pipeline(&mut self.world);
+        self.world.flush_commands();
+    }
+
     /// Execute the system scheduler for one frame
     #[inline]
     pub fn execute_scheduler(&mut self) -> Result<(), wgpu::SurfaceError> {
@@ -251,6 +496,11 @@ impl App {
         self.world.resources.insert(surface_texture);
         self.world.resources.insert(surface_texture_view);
 
+        if let Some(mut surfaces) = self.world.resources.try_get_mut::<WindowSurfaces>() {
+            let device = self.world.resources.get::<RenderDevice>();
+            surfaces.acquire(&device);
+        }
+
         Ok(())
     }
 
@@ -267,6 +517,9 @@ impl App {
         surface_texture.unwrap().present();
 
         self.world.resources.remove::<RenderSurfaceTextureView>();
+        if let Some(mut surfaces) = self.world.resources.try_get_mut::<WindowSurfaces>() {
+            surfaces.present();
+        }
     }
 
     /// Handle keyboard input
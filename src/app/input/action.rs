@@ -0,0 +1,461 @@
+//! Input actions, which name what the player wants to do and are bound to keys and mouse buttons
+//! that can be changed at runtime, e.g. from a settings menu.
+//!
+//! ```ignore
+//! #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+//! enum PlayerAction {
+//!     Jump,
+//!     Shoot,
+//! }
+//!
+//! app.add_plugin(ActionMapPlugin::new(
+//!     ActionMap::new()
+//!         .with(PlayerAction::Jump, KeyCode::Space)
+//!         .with(PlayerAction::Shoot, MouseButton::Left),
+//! ));
+//!
+//! fn jump(actions: Res<ActionMap<PlayerAction>>) {
+//!     if actions.just_pressed(&PlayerAction::Jump) {
+//!         // ...
+//!     }
+//! }
+//! ```
+//!
+//! ## Rebinding
+//!
+//! [`ActionMap::listen`] binds the next pressed key or mouse button to an action and sends an
+//! [`ActionRebound`] event with the actions already using it, so a menu can warn about conflicts.
+//! [`ActionMap::conflicts`] lists every input bound to multiple actions. Bindings are saved to a
+//! string with [`ActionMap::save_bindings`] and restored with [`ActionMap::load_bindings`].
+//! ```text
+//! Jump = Space
+//! Shoot = MouseLeft, KeyF
+//! ```
+
+use std::{
+    collections::HashSet,
+    fmt::{self, Debug, Display},
+    hash::Hash,
+    str::FromStr,
+};
+
+use crate::{
+    app::{App, Plugin},
+    event::{Event, EventWriter},
+    prelude::{Res, ResMut},
+    system::phase,
+};
+
+use super::{Input, KeyCode, MouseButton};
+
+/// Type which can be used as an action in an [`ActionMap`], implemented for every type with the
+/// required traits. Its [`Debug`] output is the name used by [`ActionMap::save_bindings`].
+pub trait Action: Debug + Clone + Eq + Hash + Send + Sync + 'static {}
+
+impl<A: Debug + Clone + Eq + Hash + Send + Sync + 'static> Action for A {}
+
+/// Key or mouse button an action is bound to. Written as the key name, e.g. `KeyA` or `Space`, or
+/// `MouseLeft`, `MouseRight`, `MouseMiddle`, `MouseBack`, `MouseForward` and `Mouse<n>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputBinding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+}
+
+impl InputBinding {
+    /// Returns true if the input is held down
+    pub fn pressed(&self, keys: &Input<KeyCode>, mouse: &Input<MouseButton>) -> bool {
+        match self {
+            Self::Key(key) => keys.pressed(*key),
+            Self::Mouse(button) => mouse.pressed(*button),
+        }
+    }
+
+    /// Returns true if the input was pressed this frame
+    pub fn just_pressed(&self, keys: &Input<KeyCode>, mouse: &Input<MouseButton>) -> bool {
+        match self {
+            Self::Key(key) => keys.just_pressed(*key),
+            Self::Mouse(button) => mouse.just_pressed(*button),
+        }
+    }
+}
+
+impl From<KeyCode> for InputBinding {
+    fn from(key: KeyCode) -> Self {
+        Self::Key(key)
+    }
+}
+
+impl From<MouseButton> for InputBinding {
+    fn from(button: MouseButton) -> Self {
+        Self::Mouse(button)
+    }
+}
+
+impl Display for InputBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Key(key) => write!(f, "{:?}", key),
+            Self::Mouse(MouseButton::Left) => write!(f, "MouseLeft"),
+            Self::Mouse(MouseButton::Right) => write!(f, "MouseRight"),
+            Self::Mouse(MouseButton::Middle) => write!(f, "MouseMiddle"),
+            Self::Mouse(MouseButton::Back) => write!(f, "MouseBack"),
+            Self::Mouse(MouseButton::Forward) => write!(f, "MouseForward"),
+            Self::Mouse(MouseButton::Other(button)) => write!(f, "Mouse{}", button),
+        }
+    }
+}
+
+impl FromStr for InputBinding {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let button = match name {
+            "MouseLeft" => MouseButton::Left,
+            "MouseRight" => MouseButton::Right,
+            "MouseMiddle" => MouseButton::Middle,
+            "MouseBack" => MouseButton::Back,
+            "MouseForward" => MouseButton::Forward,
+            _ => match name.strip_prefix("Mouse").map(str::parse) {
+                Some(Ok(button)) => MouseButton::Other(button),
+                _ => {
+                    return key_from_name(name)
+                        .map(Self::Key)
+                        .ok_or_else(|| format!("Unknown input '{}'", name));
+                }
+            },
+        };
+        Ok(Self::Mouse(button))
+    }
+}
+
+/// Sent by the [`ActionMapPlugin`] when a listened action was bound, see [`ActionMap::listen`]
+pub struct ActionRebound<A: Action> {
+    pub action: A,
+    pub binding: InputBinding,
+    /// Other actions bound to the same input
+    pub conflicts: Vec<A>,
+}
+
+impl<A: Action> Event for ActionRebound<A> {}
+
+/// Bindings of actions `A` and their state in the current frame. Used as a resource, added by the
+/// [`ActionMapPlugin`].
+#[derive(crate::macros::Resource)]
+pub struct ActionMap<A: Action> {
+    /// Actions in the order they were bound, kept for a stable saved order
+    bindings: Vec<(A, Vec<InputBinding>)>,
+    pressed: HashSet<A>,
+    just_pressed: HashSet<A>,
+    listening: Option<A>,
+    /// Key which cancels [`listen`](Self::listen) instead of being bound, `Escape` by default
+    pub cancel_key: Option<KeyCode>,
+}
+
+impl<A: Action> Default for ActionMap<A> {
+    fn default() -> Self {
+        Self {
+            bindings: Vec::new(),
+            pressed: HashSet::new(),
+            just_pressed: HashSet::new(),
+            listening: None,
+            cancel_key: Some(KeyCode::Escape),
+        }
+    }
+}
+
+impl<A: Action> Clone for ActionMap<A> {
+    fn clone(&self) -> Self {
+        Self {
+            bindings: self.bindings.clone(),
+            pressed: self.pressed.clone(),
+            just_pressed: self.just_pressed.clone(),
+            listening: self.listening.clone(),
+            cancel_key: self.cancel_key,
+        }
+    }
+}
+
+impl<A: Action> ActionMap<A> {
+    /// Create new map without bindings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns self with `binding` added to `action`
+    #[must_use]
+    pub fn with(mut self, action: A, binding: impl Into<InputBinding>) -> Self {
+        self.bind(action, binding);
+        self
+    }
+
+    /// Add `binding` to `action`, an action can have multiple bindings
+    pub fn bind(&mut self, action: A, binding: impl Into<InputBinding>) {
+        let binding = binding.into();
+        let bindings = self.entry(action);
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// Remove `binding` from `action`
+    pub fn unbind(&mut self, action: &A, binding: InputBinding) {
+        if let Some((_, bindings)) = self.bindings.iter_mut().find(|(a, _)| a == action) {
+            bindings.retain(|b| *b != binding);
+        }
+    }
+
+    /// Replace the bindings of `action`
+    pub fn set_bindings(&mut self, action: A, bindings: Vec<InputBinding>) {
+        *self.entry(action) = bindings;
+    }
+
+    /// Returns the bindings of `action`
+    pub fn bindings(&self, action: &A) -> &[InputBinding] {
+        self.bindings
+            .iter()
+            .find(|(a, _)| a == action)
+            .map_or(&[], |(_, bindings)| bindings)
+    }
+
+    /// Returns every action of the map, including actions without bindings
+    pub fn actions(&self) -> impl Iterator<Item = &A> {
+        self.bindings.iter().map(|(action, _)| action)
+    }
+
+    /// Returns true if any input of `action` is held down
+    #[inline]
+    pub fn pressed(&self, action: &A) -> bool {
+        self.pressed.contains(action)
+    }
+
+    /// Returns true if `action` started being pressed this frame
+    #[inline]
+    pub fn just_pressed(&self, action: &A) -> bool {
+        self.just_pressed.contains(action)
+    }
+
+    /// Returns the actions bound to `binding`
+    pub fn actions_bound_to(&self, binding: InputBinding) -> Vec<A> {
+        self.bindings
+            .iter()
+            .filter(|(_, bindings)| bindings.contains(&binding))
+            .map(|(action, _)| action.clone())
+            .collect()
+    }
+
+    /// Returns every input bound to more than one action, with those actions
+    pub fn conflicts(&self) -> Vec<(InputBinding, Vec<A>)> {
+        let mut conflicts: Vec<(InputBinding, Vec<A>)> = Vec::new();
+        for (_, bindings) in &self.bindings {
+            for binding in bindings {
+                if conflicts.iter().any(|(b, _)| b == binding) {
+                    continue;
+                }
+
+                let actions = self.actions_bound_to(*binding);
+                if actions.len() > 1 {
+                    conflicts.push((*binding, actions));
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// Bind the next pressed key or mouse button to `action`, replacing its bindings. Actions
+    /// aren't pressed while listening. Pressing the [`cancel_key`](Self::cancel_key) stops
+    /// listening without a change.
+    pub fn listen(&mut self, action: A) {
+        self.listening = Some(action);
+    }
+
+    /// Stop listening without a change
+    pub fn cancel_listen(&mut self) {
+        self.listening = None;
+    }
+
+    /// Returns the action waiting for an input, see [`listen`](Self::listen)
+    #[inline]
+    pub fn listening(&self) -> Option<&A> {
+        self.listening.as_ref()
+    }
+
+    /// Returns the bindings as a string, one action per line, e.g. `Jump = Space, MouseLeft`
+    pub fn save_bindings(&self) -> String {
+        let mut text = String::new();
+        for (action, bindings) in &self.bindings {
+            let bindings = bindings
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            text.push_str(&format!("{:?} = {}\n", action, bindings));
+        }
+        text
+    }
+
+    /// Replace the bindings with ones saved by [`save_bindings`](Self::save_bindings). Actions
+    /// are matched by name, so they have to be in the map already, unknown actions are skipped.
+    /// Nothing is changed if the text is invalid.
+    pub fn load_bindings(&mut self, text: &str) -> Result<(), String> {
+        let mut loaded = Vec::new();
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let (name, bindings) = line
+                .split_once('=')
+                .ok_or_else(|| format!("Expected 'action = inputs', got '{}'", line))?;
+
+            let bindings = bindings
+                .split(',')
+                .map(str::trim)
+                .filter(|binding| !binding.is_empty())
+                .map(str::parse)
+                .collect::<Result<Vec<InputBinding>, _>>()?;
+
+            let name = name.trim();
+            match self
+                .actions()
+                .find(|action| format!("{:?}", action) == name)
+            {
+                Some(action) => loaded.push((action.clone(), bindings)),
+                None => tracing::warn!("Skipping bindings of unknown action '{}'", name),
+            }
+        }
+
+        for (action, bindings) in loaded {
+            self.set_bindings(action, bindings);
+        }
+        Ok(())
+    }
+
+    /// Returns the bindings of `action`, adding it if it doesn't exist
+    fn entry(&mut self, action: A) -> &mut Vec<InputBinding> {
+        let index = match self.bindings.iter().position(|(a, _)| *a == action) {
+            Some(index) => index,
+            None => {
+                self.bindings.push((action, Vec::new()));
+                self.bindings.len() - 1
+            }
+        };
+        &mut self.bindings[index].1
+    }
+
+    /// Update the pressed actions, or bind the listened action
+    fn update(
+        &mut self,
+        keys: &Input<KeyCode>,
+        mouse: &Input<MouseButton>,
+    ) -> Option<ActionRebound<A>> {
+        self.pressed.clear();
+        self.just_pressed.clear();
+
+        if let Some(action) = self.listening.clone() {
+            if self.cancel_key.is_some_and(|key| keys.just_pressed(key)) {
+                self.listening = None;
+                return None;
+            }
+
+            let binding = keys
+                .get_just_pressed()
+                .next()
+                .map(|key| InputBinding::Key(*key))
+                .or_else(|| {
+                    mouse
+                        .get_just_pressed()
+                        .next()
+                        .map(|button| InputBinding::Mouse(*button))
+                })?;
+
+            self.listening = None;
+            self.set_bindings(action.clone(), vec![binding]);
+            let conflicts = self
+                .actions_bound_to(binding)
+                .into_iter()
+                .filter(|other| *other != action)
+                .collect();
+
+            return Some(ActionRebound {
+                action,
+                binding,
+                conflicts,
+            });
+        }
+
+        for (action, bindings) in &self.bindings {
+            if bindings.iter().any(|b| b.pressed(keys, mouse)) {
+                self.pressed.insert(action.clone());
+            }
+            if bindings.iter().any(|b| b.just_pressed(keys, mouse)) {
+                self.just_pressed.insert(action.clone());
+            }
+        }
+        None
+    }
+}
+
+/// System which updates the pressed actions of the [`ActionMap`]
+fn update_action_map_system<A: Action>(
+    mut actions: ResMut<ActionMap<A>>,
+    keys: Res<Input<KeyCode>>,
+    mouse: Res<Input<MouseButton>>,
+    mut rebound: EventWriter<ActionRebound<A>>,
+) {
+    if let Some(event) = actions.update(&keys, &mouse) {
+        rebound.write(event);
+    }
+}
+
+/// Adds the [`ActionMap`] resource and updates it at the start of every frame, requires the
+/// [`InputPlugin`](super::InputPlugin)
+pub struct ActionMapPlugin<A: Action> {
+    actions: ActionMap<A>,
+}
+
+impl<A: Action> ActionMapPlugin<A> {
+    /// Create the plugin with the default bindings of the app
+    pub fn new(actions: ActionMap<A>) -> Self {
+        Self { actions }
+    }
+}
+
+impl<A: Action> Plugin for ActionMapPlugin<A> {
+    fn build(&self, app: &mut App) {
+        app.set_resource(self.actions.clone())
+            .register_event::<ActionRebound<A>>()
+            .register_system(update_action_map_system::<A>, phase::First);
+    }
+}
+
+/// Returns the key named like its [`KeyCode`] variant
+#[rustfmt::skip]
+fn key_from_name(name: &str) -> Option<KeyCode> {
+    macro_rules! match_keys {
+        ($($key:ident),* $(,)?) => {
+            match name {
+                $(stringify!($key) => Some(KeyCode::$key),)*
+                _ => None,
+            }
+        };
+    }
+
+    match_keys![
+        Backquote, Backslash, BracketLeft, BracketRight, Comma, Digit0, Digit1, Digit2, Digit3, Digit4,
+        Digit5, Digit6, Digit7, Digit8, Digit9, Equal, IntlBackslash, IntlRo, IntlYen, KeyA, KeyB, KeyC,
+        KeyD, KeyE, KeyF, KeyG, KeyH, KeyI, KeyJ, KeyK, KeyL, KeyM, KeyN, KeyO, KeyP, KeyQ, KeyR, KeyS,
+        KeyT, KeyU, KeyV, KeyW, KeyX, KeyY, KeyZ, Minus, Period, Quote, Semicolon, Slash, AltLeft,
+        AltRight, Backspace, CapsLock, ContextMenu, ControlLeft, ControlRight, Enter, SuperLeft,
+        SuperRight, ShiftLeft, ShiftRight, Space, Tab, Convert, KanaMode, Lang1, Lang2, Lang3, Lang4,
+        Lang5, NonConvert, Delete, End, Help, Home, Insert, PageDown, PageUp, ArrowDown, ArrowLeft,
+        ArrowRight, ArrowUp, NumLock, Numpad0, Numpad1, Numpad2, Numpad3, Numpad4, Numpad5, Numpad6,
+        Numpad7, Numpad8, Numpad9, NumpadAdd, NumpadBackspace, NumpadClear, NumpadClearEntry,
+        NumpadComma, NumpadDecimal, NumpadDivide, NumpadEnter, NumpadEqual, NumpadHash, NumpadMemoryAdd,
+        NumpadMemoryClear, NumpadMemoryRecall, NumpadMemoryStore, NumpadMemorySubtract, NumpadMultiply,
+        NumpadParenLeft, NumpadParenRight, NumpadStar, NumpadSubtract, Escape, Fn, FnLock, PrintScreen,
+        ScrollLock, Pause, BrowserBack, BrowserFavorites, BrowserForward, BrowserHome, BrowserRefresh,
+        BrowserSearch, BrowserStop, Eject, LaunchApp1, LaunchApp2, LaunchMail, MediaPlayPause,
+        MediaSelect, MediaStop, MediaTrackNext, MediaTrackPrevious, Power, Sleep, AudioVolumeDown,
+        AudioVolumeMute, AudioVolumeUp, WakeUp, Meta, Hyper, Turbo, Abort, Resume, Suspend, Again, Copy,
+        Cut, Find, Open, Paste, Props, Select, Undo, Hiragana, Katakana, F1, F2, F3, F4, F5, F6, F7, F8,
+        F9, F10, F11, F12, F13, F14, F15, F16, F17, F18, F19, F20, F21, F22, F23, F24, F25, F26, F27,
+        F28, F29, F30, F31, F32, F33, F34, F35,
+    ]
+}
@@ -0,0 +1,238 @@
+//! Gamepad input through [gilrs](https://docs.rs/gilrs).
+//!
+//! Buttons of every connected gamepad are pressed in the [`Input<GamepadButton>`] resource, and
+//! stick and trigger positions are stored in [`GamepadAxes`], with the axes of the gamepad which
+//! moved them last.
+//!
+//! ```ignore
+//! fn jump(buttons: Res<Input<GamepadButton>>, axes: Res<GamepadAxes>) {
+//!     if buttons.just_pressed(GamepadButton::South) {
+//!         // ...
+//!     }
+//!     let movement = axes.left_stick();
+//! }
+//! ```
+
+use std::{collections::HashMap, sync::Mutex};
+
+use glam::Vec2;
+
+use crate::{
+    macros::Resource,
+    prelude::{Res, ResMut},
+};
+
+use super::{Input, InputData};
+
+/// Button of a gamepad, named by its position on the gamepad so `South` is `A` on Xbox and `Cross`
+/// on PlayStation controllers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadButton {
+    South,
+    East,
+    North,
+    West,
+    LeftBumper,
+    LeftTrigger,
+    RightBumper,
+    RightTrigger,
+    Select,
+    Start,
+    Mode,
+    LeftThumb,
+    RightThumb,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+impl InputData for GamepadButton {}
+
+impl GamepadButton {
+    /// Returns the button of a gilrs button, `None` for buttons which aren't on common gamepads
+    fn from_gilrs(button: gilrs::Button) -> Option<Self> {
+        use gilrs::Button;
+
+        Some(match button {
+            Button::South => Self::South,
+            Button::East => Self::East,
+            Button::North => Self::North,
+            Button::West => Self::West,
+            Button::LeftTrigger => Self::LeftBumper,
+            Button::LeftTrigger2 => Self::LeftTrigger,
+            Button::RightTrigger => Self::RightBumper,
+            Button::RightTrigger2 => Self::RightTrigger,
+            Button::Select => Self::Select,
+            Button::Start => Self::Start,
+            Button::Mode => Self::Mode,
+            Button::LeftThumb => Self::LeftThumb,
+            Button::RightThumb => Self::RightThumb,
+            Button::DPadUp => Self::DPadUp,
+            Button::DPadDown => Self::DPadDown,
+            Button::DPadLeft => Self::DPadLeft,
+            Button::DPadRight => Self::DPadRight,
+            _ => return None,
+        })
+    }
+}
+
+/// Axis of a gamepad, sticks go from `-1.0` to `1.0` with y pointing up, triggers from `0.0` to
+/// `1.0`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+impl GamepadAxis {
+    /// Returns the axis of a gilrs axis, `None` for axes which aren't on common gamepads
+    fn from_gilrs(axis: gilrs::Axis) -> Option<Self> {
+        use gilrs::Axis;
+
+        Some(match axis {
+            Axis::LeftStickX => Self::LeftStickX,
+            Axis::LeftStickY => Self::LeftStickY,
+            Axis::RightStickX => Self::RightStickX,
+            Axis::RightStickY => Self::RightStickY,
+            Axis::LeftZ => Self::LeftTrigger,
+            Axis::RightZ => Self::RightTrigger,
+            _ => return None,
+        })
+    }
+}
+
+/// Positions of the gamepad axes. Used as a resource.
+///
+/// Values closer to zero than the [`deadzone`](Self::deadzone) are read as zero, so sticks which
+/// don't center perfectly don't drift.
+#[derive(Resource, Debug)]
+pub struct GamepadAxes {
+    /// Distance from zero under which axes are read as zero
+    pub deadzone: f32,
+    values: HashMap<GamepadAxis, f32>,
+}
+
+impl Default for GamepadAxes {
+    fn default() -> Self {
+        Self {
+            deadzone: 0.15,
+            values: HashMap::new(),
+        }
+    }
+}
+
+impl GamepadAxes {
+    /// Returns the position of `axis`, zero inside the deadzone
+    pub fn get(&self, axis: GamepadAxis) -> f32 {
+        let value = self.values.get(&axis).copied().unwrap_or_default();
+        if value.abs() < self.deadzone {
+            0.0
+        } else {
+            value
+        }
+    }
+
+    /// Returns the position of the left stick, zero inside the deadzone
+    pub fn left_stick(&self) -> Vec2 {
+        self.stick(GamepadAxis::LeftStickX, GamepadAxis::LeftStickY)
+    }
+
+    /// Returns the position of the right stick, zero inside the deadzone
+    pub fn right_stick(&self) -> Vec2 {
+        self.stick(GamepadAxis::RightStickX, GamepadAxis::RightStickY)
+    }
+
+    /// Returns the position of a stick, the deadzone is a circle so diagonals aren't cut off
+    fn stick(&self, x: GamepadAxis, y: GamepadAxis) -> Vec2 {
+        let value = Vec2::new(
+            self.values.get(&x).copied().unwrap_or_default(),
+            self.values.get(&y).copied().unwrap_or_default(),
+        );
+        if value.length() < self.deadzone {
+            Vec2::ZERO
+        } else {
+            value
+        }
+    }
+}
+
+/// Connection to the platform gamepad API, polled by [`gamepad_input_system`]
+#[derive(Resource)]
+pub(super) struct GamepadBackend(Mutex<gilrs::Gilrs>);
+
+// without atomics the web is single threaded, like wgpu's `fragile-send-sync-non-atomic-wasm`
+#[cfg(all(target_arch = "wasm32", not(target_feature = "atomics")))]
+unsafe impl Send for GamepadBackend {}
+#[cfg(all(target_arch = "wasm32", not(target_feature = "atomics")))]
+unsafe impl Sync for GamepadBackend {}
+
+impl GamepadBackend {
+    /// Connect to the gamepad API, `None` if the platform doesn't support gamepads
+    pub(super) fn new() -> Option<Self> {
+        match gilrs::Gilrs::new() {
+            Ok(gilrs) => Some(Self(Mutex::new(gilrs))),
+            Err(err) => {
+                tracing::warn!("Gamepad input is unavailable: {err}");
+                None
+            }
+        }
+    }
+}
+
+/// System which reads gamepad events into [`Input<GamepadButton>`] and [`GamepadAxes`], runs at
+/// the start of the First phase
+pub(super) fn gamepad_input_system(
+    backend: Option<Res<GamepadBackend>>,
+    mut buttons: ResMut<Input<GamepadButton>>,
+    mut axes: ResMut<GamepadAxes>,
+) {
+    let Some(backend) = backend else {
+        return;
+    };
+    let mut gilrs = backend
+        .0
+        .lock()
+        .expect("Gamepad backend should not be poisoned");
+
+    while let Some(gilrs::Event { event, .. }) = gilrs.next_event() {
+        match event {
+            gilrs::EventType::ButtonPressed(button, _) => {
+                if let Some(button) = GamepadButton::from_gilrs(button) {
+                    buttons.press(button);
+                }
+            }
+            gilrs::EventType::ButtonReleased(button, _) => {
+                if let Some(button) = GamepadButton::from_gilrs(button) {
+                    buttons.release(button);
+                }
+            }
+            gilrs::EventType::AxisChanged(axis, value, _) => {
+                if let Some(axis) = GamepadAxis::from_gilrs(axis) {
+                    axes.values.insert(axis, value);
+                }
+            }
+            // triggers are reported as buttons with a value on most gamepads
+            gilrs::EventType::ButtonChanged(gilrs::Button::LeftTrigger2, value, _) => {
+                axes.values.insert(GamepadAxis::LeftTrigger, value);
+            }
+            gilrs::EventType::ButtonChanged(gilrs::Button::RightTrigger2, value, _) => {
+                axes.values.insert(GamepadAxis::RightTrigger, value);
+            }
+            // a disconnected gamepad doesn't hold anything down
+            gilrs::EventType::Disconnected => {
+                let pressed = buttons.get_pressed().copied().collect::<Vec<_>>();
+                for button in pressed {
+                    buttons.release(button);
+                }
+                axes.values.clear();
+            }
+            _ => {}
+        }
+    }
+    gilrs.inc();
+}
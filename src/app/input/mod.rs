@@ -1,10 +1,17 @@
+mod action;
+mod gamepad;
+
 use std::{collections::HashSet, hash::Hash};
 
+pub use action::{Action, ActionMap, ActionMapPlugin, ActionRebound, InputBinding};
+pub use gamepad::{GamepadAxes, GamepadAxis, GamepadButton};
+
 pub use winit::{event::MouseButton, keyboard::KeyCode};
 
 use crate::{prelude::ResMut, system::phase};
 
 use super::{App, Plugin};
+use gamepad::{GamepadBackend, gamepad_input_system};
 
 /// A type which can be used as input data in the [`Input`](Input) resource.
 trait InputData: Eq + Hash + Copy + Send + Sync + 'static {}
@@ -65,29 +72,48 @@ impl<I: InputData> Input<I> {
     pub fn just_pressed(&self, key: I) -> bool {
         self.just_pressed.contains(&key)
     }
+
+    /// Returns every held down input
+    pub fn get_pressed(&self) -> impl Iterator<Item = &I> {
+        self.storage.iter()
+    }
+
+    /// Returns every input pressed this frame
+    pub fn get_just_pressed(&self) -> impl Iterator<Item = &I> {
+        self.just_pressed.iter()
+    }
 }
 
 /// UI input clearing system for just pressed inputs.
 fn clear_just_pressed_inputs(
     mut key_input: ResMut<Input<KeyCode>>,
     mut mouse_input: ResMut<Input<MouseButton>>,
+    mut gamepad_input: ResMut<Input<GamepadButton>>,
 ) {
     key_input.clear_just_pressed();
     mouse_input.clear_just_pressed();
+    gamepad_input.clear_just_pressed();
 }
 
-/// Adds `Input<KeyCode>` and `Input<MouseButton>` resources to enable keyboard and mouse input
-/// handling.
+/// Adds `Input<KeyCode>`, `Input<MouseButton>`, `Input<GamepadButton>` and [`GamepadAxes`]
+/// resources to enable keyboard, mouse and gamepad input handling.
 ///
 /// # Note
-/// These can also be handled through events, by using `KeyboardInput` and `MouseInput` event types.
+/// Keyboard and mouse input can also be handled through events, by using `KeyboardInput` and
+/// `MouseInput` event types.
 pub struct InputPlugin;
 
 impl Plugin for InputPlugin {
     fn build(&self, app: &mut App) {
         app.world.resources.insert(Input::<KeyCode>::new());
         app.world.resources.insert(Input::<MouseButton>::new());
+        app.world.resources.insert(Input::<GamepadButton>::new());
+        app.world.resources.insert(GamepadAxes::default());
+        if let Some(backend) = GamepadBackend::new() {
+            app.world.resources.insert(backend);
+        }
 
-        app.register_system(clear_just_pressed_inputs, phase::Last);
+        app.register_system(gamepad_input_system, phase::First)
+            .register_system(clear_just_pressed_inputs, phase::Last);
     }
 }
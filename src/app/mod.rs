@@ -3,4 +3,4 @@ pub mod input;
 mod plugin;
 
 pub use app::App;
-pub use plugin::Plugin;
+pub use plugin::{Plugin, PluginGroup, PluginGroupBuilder, PluginId};
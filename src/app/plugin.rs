@@ -1,3 +1,8 @@
+use std::{
+    any::{TypeId, type_name},
+    rc::Rc,
+};
+
 use super::App;
 
 /// Plugin is a way to extend the functionality of the App, usually by adding systems or resources
@@ -5,6 +10,190 @@ use super::App;
 /// immediately
 ///
 /// Only the `build` method is required to be implemented.
-pub trait Plugin {
+///
+/// # Lifecycle
+/// 1. `build` when the plugin is added
+/// 2. `ready` is polled once the window and GPU exist, until it returns true for all plugins
+/// 3. `finish` after all plugins are ready, before the startup systems run
+/// 4. `cleanup` when the app exits
+pub trait Plugin: 'static {
     fn build(&self, app: &mut App);
+
+    /// Returns false while the plugin is waiting for some setup, e.g. an async task started in
+    /// `build`. Startup is delayed until every plugin is ready.
+    fn ready(&self, _app: &App) -> bool {
+        true
+    }
+
+    /// Finish the setup, called once after all plugins are built and ready. Resources inserted by
+    /// other plugins and the GPU resources are available here.
+    fn finish(&self, _app: &mut App) {}
+
+    /// Tear down the plugin, called once when the app exits, in reverse order of adding
+    fn cleanup(&self, _app: &mut App) {}
+
+    /// Plugins which have to be added to the app for this plugin to work. They don't have to be
+    /// added before this one, dependencies are checked when the app starts running.
+    ///
+    /// ```ignore
+    /// fn dependencies(&self) -> Vec<PluginId> {
+    ///     vec![PluginId::of::<RenderPlugin>()]
+    /// }
+    /// ```
+    fn dependencies(&self) -> Vec<PluginId> {
+        Vec::new()
+    }
+}
+
+/// Identifies a plugin type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PluginId {
+    id: TypeId,
+    name: &'static str,
+}
+
+impl PluginId {
+    /// Returns the id of plugin `P`
+    #[inline]
+    pub fn of<P: Plugin>() -> Self {
+        Self {
+            id: TypeId::of::<P>(),
+            name: type_name::<P>(),
+        }
+    }
+
+    /// Returns the type name of the plugin
+    #[inline]
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+/// Plugin added to the app, with its dependencies
+pub(crate) struct AddedPlugin {
+    pub id: PluginId,
+    pub dependencies: Vec<PluginId>,
+    pub plugin: Rc<dyn Plugin>,
+}
+
+/// Group of plugins which are added together, see [`PluginGroupBuilder`] for configuring it.
+///
+/// # Usage
+/// ```ignore
+/// app.add_plugins(DefaultPlugins);
+///
+/// app.add_plugins(
+///     DefaultPlugins
+///         .build()
+///         .disable::<AudioPlugin>()
+///         .set(LogPlugin::default().with_filter("my_game", Level::DEBUG)),
+/// );
+/// ```
+pub trait PluginGroup {
+    /// Returns the plugins of the group, in the order they are added
+    fn build(self) -> PluginGroupBuilder;
+}
+
+/// Plugin in a [`PluginGroupBuilder`]
+struct GroupEntry {
+    id: PluginId,
+    plugin: Box<dyn Plugin>,
+    enabled: bool,
+}
+
+/// Ordered list of plugins in a [`PluginGroup`], where plugins can be disabled or replaced
+/// before they are added to the app.
+#[derive(Default)]
+pub struct PluginGroupBuilder {
+    plugins: Vec<GroupEntry>,
+}
+
+impl PluginGroupBuilder {
+    /// Create an empty group
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn position<P: Plugin>(&self) -> Option<usize> {
+        let id = PluginId::of::<P>();
+        self.plugins.iter().position(|entry| entry.id == id)
+    }
+
+    fn position_or_panic<P: Plugin>(&self) -> usize {
+        self.position::<P>().unwrap_or_else(|| {
+            panic!(
+                "Plugin '{}' is not part of the plugin group",
+                type_name::<P>()
+            )
+        })
+    }
+
+    /// Add a plugin at the end of the group. If the group already contains a plugin of the same
+    /// type, it is replaced in place.
+    #[allow(clippy::should_implement_trait)]
+    pub fn add<P: Plugin>(mut self, plugin: P) -> Self {
+        let entry = GroupEntry {
+            id: PluginId::of::<P>(),
+            plugin: Box::new(plugin),
+            enabled: true,
+        };
+
+        match self.position::<P>() {
+            Some(index) => self.plugins[index] = entry,
+            None => self.plugins.push(entry),
+        }
+        self
+    }
+
+    /// Replace the plugin of the same type, e.g. to change its configuration.
+    ///
+    /// # Panics
+    /// If the group doesn't contain a plugin of type `P`
+    pub fn set<P: Plugin>(mut self, plugin: P) -> Self {
+        let index = self.position_or_panic::<P>();
+        self.plugins[index].plugin = Box::new(plugin);
+        self
+    }
+
+    /// Disable plugin `P`, it won't be added to the app.
+    ///
+    /// # Panics
+    /// If the group doesn't contain a plugin of type `P`
+    pub fn disable<P: Plugin>(mut self) -> Self {
+        let index = self.position_or_panic::<P>();
+        self.plugins[index].enabled = false;
+        self
+    }
+
+    /// Enable plugin `P` if it was disabled.
+    ///
+    /// # Panics
+    /// If the group doesn't contain a plugin of type `P`
+    pub fn enable<P: Plugin>(mut self) -> Self {
+        let index = self.position_or_panic::<P>();
+        self.plugins[index].enabled = true;
+        self
+    }
+
+    /// Returns true if the group contains plugin `P` and it's enabled
+    pub fn is_enabled<P: Plugin>(&self) -> bool {
+        self.position::<P>()
+            .is_some_and(|index| self.plugins[index].enabled)
+    }
+
+    /// Add all enabled plugins to `app`
+    pub(crate) fn add_to(self, app: &mut App) {
+        for entry in self.plugins {
+            if entry.enabled {
+                app.add_boxed_plugin(entry.id, entry.plugin);
+            }
+        }
+    }
+}
+
+impl PluginGroup for PluginGroupBuilder {
+    #[inline]
+    fn build(self) -> PluginGroupBuilder {
+        self
+    }
 }
@@ -0,0 +1,241 @@
+//! Asset groups, which load a list of assets over multiple frames and report their progress, e.g.
+//! for loading screens.
+//!
+//! ```ignore
+//! app.add_plugin(AssetGroupPlugin)
+//!     .add_startup_system(|mut groups: ResMut<AssetGroups>| {
+//!         groups.load_group(
+//!             AssetGroup::new("level")
+//!                 .with::<Mesh>("assets/models/level.obj")
+//!                 .with::<Image>("assets/textures/level.png"),
+//!         );
+//!     })
+//!     .register_system(update_loading_bar.run_if(in_state(GameState::Loading)), phase::Update)
+//!     .register_system(
+//!         start_game
+//!             .run_if(in_state(GameState::Loading))
+//!             .run_if(asset_group_loaded("level")),
+//!         phase::Update,
+//!     );
+//!
+//! fn update_loading_bar(groups: Res<AssetGroups>, mut bars: Query<&mut Node, With<LoadingBar>>) {
+//!     for bar in bars.iter_mut() {
+//!         bar.width = Val::Percent(groups.progress("level") * 100.0);
+//!     }
+//! }
+//! ```
+//!
+//! Loaded paths are cached by the [`AssetLoader`], their handles can be retrieved with
+//! [`AssetLoader::get`].
+
+use std::collections::{HashMap, VecDeque};
+
+use web_time::{Duration, Instant};
+
+use crate::{
+    prelude::*,
+    system::{IntoSystemCondition, phase},
+};
+
+use super::LoadableAsset;
+
+/// Loads an entry, returns false if it isn't loaded yet and should be checked again later
+type LoadFn = Box<dyn FnMut(&mut AssetLoader, &mut Resources) -> bool + Send + Sync>;
+
+/// Named list of assets, loaded with [`AssetGroups::load_group`]
+pub struct AssetGroup {
+    name: String,
+    entries: Vec<LoadFn>,
+}
+
+impl AssetGroup {
+    /// Create new empty group
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            entries: Vec::new(),
+        }
+    }
+
+    /// Returns self with asset `A` at `path`, which is loaded with the [`AssetLoader`]
+    #[must_use]
+    pub fn with<A: LoadableAsset>(mut self, path: impl Into<String>) -> Self {
+        let path = path.into();
+        self.entries.push(Box::new(move |loader, resources| {
+            loader.load::<A>(&path, resources);
+            true
+        }));
+        self
+    }
+
+    /// Returns self with an asset which is added to [`Assets<A>`] by other code, the group waits
+    /// until it exists
+    #[must_use]
+    pub fn with_handle<A: Asset>(mut self, handle: Handle<A>) -> Self {
+        self.entries.push(Box::new(move |_, resources| {
+            resources
+                .try_get::<Assets<A>>()
+                .is_some_and(|assets| assets.get(&handle).is_some())
+        }));
+        self
+    }
+
+    /// Returns the name of the group
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the amount of assets in the group
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the group has no assets
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Loading progress of a group
+struct GroupProgress {
+    pending: VecDeque<LoadFn>,
+    total: usize,
+}
+
+impl GroupProgress {
+    fn progress(&self) -> f32 {
+        if self.total == 0 {
+            return 1.0;
+        }
+        (self.total - self.pending.len()) as f32 / self.total as f32
+    }
+}
+
+/// Asset groups which are loading or were loaded. Used as a resource, added by the
+/// [`AssetGroupPlugin`].
+#[derive(Resource)]
+pub struct AssetGroups {
+    /// Time spent loading assets per frame, at least one asset is loaded every frame
+    pub frame_budget: Duration,
+    groups: HashMap<String, GroupProgress>,
+}
+
+impl Default for AssetGroups {
+    fn default() -> Self {
+        Self {
+            frame_budget: Duration::from_millis(8),
+            groups: HashMap::new(),
+        }
+    }
+}
+
+impl AssetGroups {
+    /// Start loading `group` in the next [`phase::First`]. If a group with the same name exists,
+    /// the assets are added to it.
+    pub fn load_group(&mut self, group: AssetGroup) {
+        let progress = self
+            .groups
+            .entry(group.name)
+            .or_insert_with(|| GroupProgress {
+                pending: VecDeque::new(),
+                total: 0,
+            });
+        progress.total += group.entries.len();
+        progress.pending.extend(group.entries);
+    }
+
+    /// Returns the fraction of loaded assets in the group, from 0 to 1. Returns 0 if the group
+    /// doesn't exist.
+    pub fn progress(&self, name: &str) -> f32 {
+        self.groups.get(name).map_or(0.0, GroupProgress::progress)
+    }
+
+    /// Returns the fraction of loaded assets in all groups, from 0 to 1
+    pub fn total_progress(&self) -> f32 {
+        let total = self.groups.values().map(|group| group.total).sum::<usize>();
+        if total == 0 {
+            return 1.0;
+        }
+        let pending = self
+            .groups
+            .values()
+            .map(|group| group.pending.len())
+            .sum::<usize>();
+        (total - pending) as f32 / total as f32
+    }
+
+    /// Returns true if every asset of the group is loaded, or false if the group doesn't exist
+    pub fn is_loaded(&self, name: &str) -> bool {
+        self.groups
+            .get(name)
+            .is_some_and(|group| group.pending.is_empty())
+    }
+
+    /// Returns true if any group has assets left to load
+    pub fn is_loading(&self) -> bool {
+        self.groups.values().any(|group| !group.pending.is_empty())
+    }
+
+    /// Stop tracking the group, its remaining assets are not loaded. Loaded assets stay in their
+    /// [`Assets`] storage.
+    pub fn remove_group(&mut self, name: &str) {
+        self.groups.remove(name);
+    }
+
+    /// Load pending assets until the frame budget runs out
+    fn update(&mut self, loader: &mut AssetLoader, resources: &mut Resources) {
+        let start = Instant::now();
+        let mut loaded_any = false;
+
+        for group in self.groups.values_mut() {
+            // every entry is tried at most once per frame, handles might not exist yet
+            for _ in 0..group.pending.len() {
+                if loaded_any && start.elapsed() >= self.frame_budget {
+                    return;
+                }
+
+                let mut load = group.pending.pop_front().expect("entry should exist");
+                if load(loader, resources) {
+                    loaded_any = true;
+                } else {
+                    group.pending.push_back(load);
+                }
+            }
+        }
+    }
+}
+
+/// Creates a [Condition](IntoSystemCondition) which evaluates to true if every asset of group
+/// `name` is loaded, or false if the group doesn't exist
+pub fn asset_group_loaded(
+    name: impl Into<String>,
+) -> impl IntoSystemCondition<Option<Res<AssetGroups>>> {
+    let name = name.into();
+    let closure =
+        move |groups: Option<Res<AssetGroups>>| groups.is_some_and(|g| g.is_loaded(&name));
+    closure.build()
+}
+
+/// System which loads the pending assets of the [`AssetGroups`]
+fn load_asset_groups_system(world: &mut World) {
+    let mut groups = world.resources.get_mut::<AssetGroups>();
+    if !groups.is_loading() {
+        return;
+    }
+
+    let mut loader = world.resources.get_mut::<AssetLoader>();
+    groups.update(&mut loader, &mut world.resources);
+}
+
+/// Adds the [`AssetGroups`] resource and loads its groups at the start of every frame
+pub struct AssetGroupPlugin;
+
+impl Plugin for AssetGroupPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AssetGroups>()
+            .register_system(load_asset_groups_system, phase::First);
+    }
+}
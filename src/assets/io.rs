@@ -0,0 +1,111 @@
+//! File access used by the asset loaders.
+//!
+//! On native targets files are read from the file system. On the web there is no synchronous file
+//! access, so files have to be downloaded with [`fetch`] before they are loaded, usually before
+//! the app runs. Paths are resolved relative to the page URL.
+//!
+//! ```ignore
+//! wasm_bindgen_futures::spawn_local(async {
+//!     vavo::assets::io::fetch(&["assets/models/cube.obj", "assets/textures/cube.png"])
+//!         .await
+//!         .expect("Failed to fetch assets");
+//!
+//!     App::build().add_plugins(DefaultPlugins).run();
+//! });
+//! ```
+
+use std::{io, path::Path};
+
+/// Reads the whole file at `path`
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read(path: impl AsRef<Path>) -> io::Result<Vec<u8>> {
+    std::fs::read(path)
+}
+
+/// Returns the contents of a file downloaded by [`fetch`]
+#[cfg(target_arch = "wasm32")]
+pub fn read(path: impl AsRef<Path>) -> io::Result<Vec<u8>> {
+    let key = web::key(path.as_ref());
+    web::FETCHED
+        .lock()
+        .unwrap()
+        .get(&key)
+        .cloned()
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("'{key}' was not fetched, call `vavo::assets::io::fetch` first"),
+            )
+        })
+}
+
+/// Downloads `paths` so they can be loaded by the [`AssetLoader`](super::AssetLoader)
+#[cfg(target_arch = "wasm32")]
+pub async fn fetch(paths: &[&str]) -> io::Result<()> {
+    for path in paths {
+        let bytes = web::fetch_bytes(path).await?;
+        let key = web::key(Path::new(path));
+        web::FETCHED.lock().unwrap().insert(key, bytes);
+    }
+    Ok(())
+}
+
+#[cfg(target_arch = "wasm32")]
+mod web {
+    use std::{
+        collections::HashMap,
+        io,
+        path::{Component, Path},
+        sync::{LazyLock, Mutex},
+    };
+
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_futures::JsFuture;
+
+    /// Fetched files by their normalized path
+    pub(super) static FETCHED: LazyLock<Mutex<HashMap<String, Vec<u8>>>> =
+        LazyLock::new(Default::default);
+
+    /// Normalizes `path`, so `./a/../b` and `b` share a key
+    pub(super) fn key(path: &Path) -> String {
+        let mut parts = Vec::new();
+        for component in path.components() {
+            match component {
+                Component::ParentDir => {
+                    parts.pop();
+                }
+                Component::Normal(part) => parts.push(part.to_string_lossy()),
+                _ => {}
+            }
+        }
+        parts.join("/")
+    }
+
+    fn js_error(path: &str, err: wasm_bindgen::JsValue) -> io::Error {
+        io::Error::other(format!("Failed to fetch '{path}': {err:?}"))
+    }
+
+    pub(super) async fn fetch_bytes(path: &str) -> io::Result<Vec<u8>> {
+        let window = web_sys::window().ok_or_else(|| io::Error::other("No window available"))?;
+
+        let response = JsFuture::from(window.fetch_with_str(path))
+            .await
+            .map_err(|err| js_error(path, err))?
+            .dyn_into::<web_sys::Response>()
+            .map_err(|err| js_error(path, err))?;
+
+        if !response.ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Failed to fetch '{path}': status {}", response.status()),
+            ));
+        }
+
+        let buffer = response.array_buffer().map_err(|err| js_error(path, err))?;
+        let buffer = JsFuture::from(buffer)
+            .await
+            .map_err(|err| js_error(path, err))?;
+
+        Ok(js_sys::Uint8Array::new(&buffer).to_vec())
+    }
+}
@@ -1,13 +1,117 @@
-use std::{any::Any, collections::HashMap, fmt::Debug, path::Path};
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt::Debug,
+    io::Cursor,
+    path::Path,
+};
 
-use crate::prelude::{Color, Image, Material, Mesh, Resources};
+use crate::{
+    app::{App, Plugin},
+    event::{Event, Events},
+    prelude::{AsyncTask, Color, Image, Material, Mesh, Resources, World},
+    system::phase,
+};
 
-use super::{Asset, Assets, Handle};
+use super::{Asset, AssetWatcher, Assets, Handle, io};
 
-#[derive(Debug, Default, crate::macros::Resource)]
+/// Loading state of an asset loaded with the [`AssetLoader`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadState {
+    Loading,
+    Loaded,
+    Failed,
+}
+
+/// Event sent when an asset loaded with [`AssetLoader::load_async`] finishes loading. It has to be
+/// registered with [`App::register_event`], the [`AssetPlugin`] registers it for meshes and images.
+pub enum AssetEvent<A: Asset> {
+    /// The asset was added to its [`Assets`] storage
+    Loaded(Handle<A>),
+    /// The asset could not be loaded, its handle stays empty
+    Failed { handle: Handle<A>, error: String },
+    /// The asset was replaced after its file changed, see [`AssetWatcher`]
+    Modified(Handle<A>),
+}
+
+impl<A: Asset> Event for AssetEvent<A> {}
+
+/// Asset which is loading in a task
+trait PendingLoad: Send + Sync {
+    /// Add the asset to its storage if the task finished, returns its final state if it did
+    fn poll(&mut self, resources: &mut Resources) -> Option<LoadState>;
+
+    /// Returns the type id and handle id of the asset
+    fn key(&self) -> (TypeId, u64);
+
+    /// Returns the path of the asset
+    fn path(&self) -> &str;
+}
+
+struct PendingAsset<A: AsyncLoadableAsset> {
+    handle: Handle<A>,
+    path: String,
+    task: AsyncTask<Result<A, String>>,
+}
+
+impl<A: AsyncLoadableAsset> PendingLoad for PendingAsset<A> {
+    fn poll(&mut self, resources: &mut Resources) -> Option<LoadState> {
+        let result = match self.task.retrieve()? {
+            Ok(result) => result,
+            Err(_) => Err(format!("Loading '{}' panicked", self.path)),
+        };
+
+        let (state, event) = match result {
+            Ok(asset) => {
+                let mut assets = resources.try_get_mut::<Assets<A>>().unwrap_or_else(|| {
+                    panic!(
+                        "Could not find Assets<A> in resources when loading '{}'",
+                        self.path
+                    )
+                });
+                assets.insert(self.handle.clone(), asset);
+                (LoadState::Loaded, AssetEvent::Loaded(self.handle.clone()))
+            }
+            Err(error) => {
+                tracing::error!("Failed to load asset '{}': {}", self.path, error);
+                let handle = self.handle.clone();
+                (LoadState::Failed, AssetEvent::Failed { handle, error })
+            }
+        };
+
+        if let Some(mut events) = resources.try_get_mut::<Events<AssetEvent<A>>>() {
+            events.write(event);
+        }
+        Some(state)
+    }
+
+    fn key(&self) -> (TypeId, u64) {
+        (TypeId::of::<A>(), self.handle.id())
+    }
+
+    fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+#[derive(Default, crate::macros::Resource)]
 pub struct AssetLoader {
     /// Cache of loaded assets, stores Handle<T: LoadableAsset>
     cache: HashMap<String, Box<dyn Any + Send + Sync>>,
+    /// Load states of assets loaded by this loader, by their type and handle id
+    states: HashMap<(TypeId, u64), LoadState>,
+    /// Assets loading in tasks
+    pending: Vec<Box<dyn PendingLoad>>,
+}
+
+impl Debug for AssetLoader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AssetLoader")
+            .field("cache", &self.cache)
+            .field("states", &self.states)
+            .field("pending", &self.pending.len())
+            .finish()
+    }
 }
 
 impl AssetLoader {
@@ -24,6 +128,35 @@ impl AssetLoader {
         }
 
         let asset = A::load(self, resources, path);
+        self.add_loaded(path, asset, resources)
+    }
+
+    /// Same as [`load`](Self::load), but with `settings` changing how the file is loaded. If the
+    /// asset was already loaded from `path`, its handle is returned and the settings are ignored.
+    pub fn load_with_settings<A: LoadableAssetWithSettings>(
+        &mut self,
+        path: &str,
+        settings: A::Settings,
+        resources: &mut Resources,
+    ) -> Handle<A> {
+        if let Some(handle) = self.cache.get(path) {
+            return handle
+                .downcast_ref::<Handle<A>>()
+                .unwrap_or_else(|| panic!("Could not downcast asset handle for '{}'", path))
+                .clone();
+        }
+
+        let asset = A::load_with_settings(self, resources, path, settings);
+        self.add_loaded(path, asset, resources)
+    }
+
+    /// Add an asset loaded from `path` to its storage and cache its handle
+    fn add_loaded<A: LoadableAsset>(
+        &mut self,
+        path: &str,
+        asset: A,
+        resources: &mut Resources,
+    ) -> Handle<A> {
         let mut assets = resources.try_get_mut::<Assets<A>>().unwrap_or_else(|| {
             panic!(
                 "Could not find Assets<A> in resources when loading '{}'",
@@ -34,9 +167,100 @@ impl AssetLoader {
         let handle = assets.add(asset);
         self.cache
             .insert(path.to_string(), Box::new(handle.clone()));
+        self.states
+            .insert((TypeId::of::<A>(), handle.id()), LoadState::Loaded);
+
+        if let Some(mut watcher) = resources.try_get_mut::<AssetWatcher>() {
+            watcher.watch(path, handle.clone());
+        }
 
         handle
     }
+
+    /// Load asset `A` at `path` in a task without blocking. The handle is returned immediately,
+    /// the asset is added to its storage in a later [`phase::First`] by the [`AssetPlugin`]. See
+    /// [`load_state`](Self::load_state) and [`AssetEvent`].
+    ///
+    /// The handle can be used right away, entities with a mesh which is still loading are skipped
+    /// by culling, rendering and picking until it's loaded.
+    pub fn load_async<A: AsyncLoadableAsset>(
+        &mut self,
+        path: &str,
+        resources: &mut Resources,
+    ) -> Handle<A> {
+        if let Some(handle) = self.get::<A>(path) {
+            return handle;
+        }
+
+        let handle = resources
+            .try_get_mut::<Assets<A>>()
+            .unwrap_or_else(|| {
+                panic!(
+                    "Could not find Assets<A> in resources when loading '{}'",
+                    path
+                )
+            })
+            .step_id();
+
+        let task_path = path.to_string();
+        let task =
+            AsyncTask::execute_async(
+                move || async move { read_and_decode::<A>(Path::new(&task_path)) },
+            );
+
+        self.cache
+            .insert(path.to_string(), Box::new(handle.clone()));
+        self.states
+            .insert((TypeId::of::<A>(), handle.id()), LoadState::Loading);
+        self.pending.push(Box::new(PendingAsset {
+            handle: handle.clone(),
+            path: path.to_string(),
+            task,
+        }));
+
+        if let Some(mut watcher) = resources.try_get_mut::<AssetWatcher>() {
+            watcher.watch(path, handle.clone());
+        }
+
+        handle
+    }
+
+    /// Returns the load state of an asset, or None if it wasn't loaded by this loader
+    pub fn load_state<A: Asset>(&self, handle: &Handle<A>) -> Option<LoadState> {
+        self.states.get(&(TypeId::of::<A>(), handle.id())).copied()
+    }
+
+    /// Returns true if any asset is loading in a task
+    #[inline]
+    pub fn is_loading(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Add the assets of finished tasks to their storage
+    fn poll(&mut self, resources: &mut Resources) {
+        let mut index = 0;
+        while index < self.pending.len() {
+            match self.pending[index].poll(resources) {
+                Some(state) => {
+                    let pending = self.pending.swap_remove(index);
+                    self.states.insert(pending.key(), state);
+                    // failed paths can be loaded again
+                    if state == LoadState::Failed {
+                        self.cache.remove(pending.path());
+                    }
+                }
+                None => index += 1,
+            }
+        }
+    }
+
+    /// Returns the handle of the asset loaded from `path`, if it was loaded
+    pub fn get<A: LoadableAsset>(&self, path: &str) -> Option<Handle<A>> {
+        self.cache
+            .get(path)
+            .and_then(|handle| handle.downcast_ref::<Handle<A>>())
+            .cloned()
+    }
 }
 
 /// Trait for assets which can be loaded from a file
@@ -46,6 +270,65 @@ pub trait LoadableAsset: Asset {
         resources: &mut Resources,
         path: P,
     ) -> Self;
+
+    /// Load the asset again after its file changed, used for hot reloading by the
+    /// [`AssetWatcher`]. Unlike `load` it shouldn't panic, the old asset is kept on errors.
+    /// Returns None if the asset can't be reloaded, which is the default.
+    fn reload(
+        _loader: &mut AssetLoader,
+        _resources: &mut Resources,
+        _path: &Path,
+    ) -> Option<Result<Self, String>>
+    where
+        Self: Sized,
+    {
+        None
+    }
+}
+
+/// Trait for assets which can be loaded with settings, e.g. to choose how a file is decoded. Used by
+/// [`AssetLoader::load_with_settings`].
+pub trait LoadableAssetWithSettings: LoadableAsset + Sized {
+    type Settings;
+
+    fn load_with_settings<P: AsRef<Path> + Debug>(
+        loader: &mut AssetLoader,
+        resources: &mut Resources,
+        path: P,
+        settings: Self::Settings,
+    ) -> Self;
+}
+
+/// Trait for assets which can be decoded from the bytes of their file without access to the app,
+/// so they can be loaded on another thread with [`AssetLoader::load_async`]
+pub trait AsyncLoadableAsset: LoadableAsset + Sized {
+    fn decode(bytes: Vec<u8>, path: &Path) -> Result<Self, String>;
+}
+
+/// Read the file at `path` and decode asset `A` from it
+fn read_and_decode<A: AsyncLoadableAsset>(path: &Path) -> Result<A, String> {
+    let bytes = io::read(path).map_err(|err| format!("Could not read '{:?}': {}", path, err))?;
+    A::decode(bytes, path)
+}
+
+/// System which adds the assets of finished [`AssetLoader::load_async`] tasks to their storage
+fn poll_asset_loads_system(world: &mut World) {
+    let mut loader = world.resources.get_mut::<AssetLoader>();
+    if loader.is_loading() {
+        loader.poll(&mut world.resources);
+    }
+}
+
+/// Finishes assets loaded with [`AssetLoader::load_async`] and registers the [`AssetEvent`]s of
+/// meshes and images
+pub struct AssetPlugin;
+
+impl Plugin for AssetPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_event::<AssetEvent<Mesh>>()
+            .register_event::<AssetEvent<Image>>()
+            .register_system(poll_asset_loads_system, phase::First);
+    }
 }
 
 impl LoadableAsset for Material {
@@ -54,16 +337,16 @@ impl LoadableAsset for Material {
         resources: &mut Resources,
         path: P,
     ) -> Self {
-        let (obj_materials, _) = tobj::load_mtl(path.as_ref())
+        let bytes = io::read(path.as_ref())
+            .unwrap_or_else(|_| panic!("Could not read mtl file at '{:?}'", path));
+        let (obj_materials, _) = tobj::load_mtl_buf(&mut Cursor::new(bytes))
             .unwrap_or_else(|_| panic!("Could not load mtl file at '{:?}'", path));
 
-        let mut full_path = std::fs::canonicalize(path.as_ref())
-            .unwrap_or_else(|_| panic!("Could not cannonicalize path '{:?}'", path));
-
-        let mut get_path = |path: &str| {
-            full_path.pop();
-            full_path.push(path);
-            full_path
+        // texture paths are relative to the mtl file
+        let directory = path.as_ref().parent().unwrap_or(Path::new(""));
+        let get_path = |path: &str| {
+            directory
+                .join(path)
                 .to_str()
                 .expect("Could not convert path to string")
                 .to_string()
@@ -78,11 +361,11 @@ impl LoadableAsset for Material {
                     .unwrap_or_default(),
                 base_color_texture: mat
                     .diffuse_texture
-                    .map(|path| loader.load(&get_path(path.as_ref()), resources)),
+                    .map(|path| loader.load_async(&get_path(path.as_ref()), resources)),
                 // TODO: check with learnwgpu how to handle normal_texture
                 normal_map_texture: mat
                     .normal_texture
-                    .map(|path| loader.load(&get_path(path.as_ref()), resources)),
+                    .map(|path| loader.load_async(&get_path(path.as_ref()), resources)),
                 perceptual_roughness: mat
                     .shininess
                     .map(|s| (1.0 - s / 100.0).clamp(0.0, 1.0))
@@ -108,15 +391,29 @@ impl LoadableAsset for Material {
 
 impl LoadableAsset for Mesh {
     fn load<P: AsRef<Path> + Debug>(_: &mut AssetLoader, _: &mut Resources, path: P) -> Self {
-        let (models, _) = tobj::load_obj(
-            path.as_ref(),
+        let bytes = io::read(path.as_ref())
+            .unwrap_or_else(|_| panic!("Could not read obj file at '{:?}'", path));
+        Self::decode(bytes, path.as_ref()).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    fn reload(_: &mut AssetLoader, _: &mut Resources, path: &Path) -> Option<Result<Self, String>> {
+        Some(read_and_decode(path))
+    }
+}
+
+impl AsyncLoadableAsset for Mesh {
+    fn decode(bytes: Vec<u8>, path: &Path) -> Result<Self, String> {
+        // materials are loaded separately as a `Material` asset
+        let (models, _) = tobj::load_obj_buf(
+            &mut Cursor::new(bytes),
             &tobj::LoadOptions {
                 single_index: true,
                 triangulate: true,
                 ..Default::default()
             },
+            |_| Err(tobj::LoadError::OpenFileFailed),
         )
-        .unwrap_or_else(|_| panic!("Could not load obj file at '{:?}'", path));
+        .map_err(|err| format!("Could not load obj file at '{:?}': {}", path, err))?;
 
         if models.len() > 1 {
             // TODO: handle multiple models in obj file
@@ -125,7 +422,7 @@ impl LoadableAsset for Mesh {
         let model = models
             .into_iter()
             .next()
-            .unwrap_or_else(|| panic!("No models found in obj file at '{:?}'", path));
+            .ok_or_else(|| format!("No models found in obj file at '{:?}'", path))?;
         let model_mesh = model.mesh;
 
         let mut colors = Vec::new();
@@ -165,7 +462,7 @@ impl LoadableAsset for Mesh {
             }
         }
 
-        Mesh {
+        Ok(Mesh {
             topology: wgpu::PrimitiveTopology::TriangleList,
             colors: if colors.is_empty() {
                 None
@@ -184,14 +481,28 @@ impl LoadableAsset for Mesh {
             } else {
                 Some(model_mesh.indices)
             },
-        }
+        })
     }
 }
 
 impl LoadableAsset for Image {
     fn load<P: AsRef<Path> + Debug>(_: &mut AssetLoader, _: &mut Resources, path: P) -> Self {
-        let image = image::open(path.as_ref())
-            .unwrap_or_else(|_| panic!("Could not open image at '{:?}'", path))
+        let bytes = io::read(path.as_ref())
+            .unwrap_or_else(|_| panic!("Could not read image at '{:?}'", path));
+        Self::decode(bytes, path.as_ref()).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    fn reload(_: &mut AssetLoader, _: &mut Resources, path: &Path) -> Option<Result<Self, String>> {
+        Some(read_and_decode(path))
+    }
+}
+
+impl AsyncLoadableAsset for Image {
+    /// Decodes the image and generates its mip levels, in the loading task when loaded with
+    /// [`AssetLoader::load_async`]
+    fn decode(bytes: Vec<u8>, path: &Path) -> Result<Self, String> {
+        let image = image::load_from_memory(&bytes)
+            .map_err(|err| format!("Could not open image at '{:?}': {}", path, err))?
             .to_rgba8();
 
         let (width, height) = image.dimensions();
@@ -202,6 +513,8 @@ impl LoadableAsset for Image {
             depth_or_array_layers: 1,
         };
 
-        Image::new_with_defaults(data, size)
+        let mut image = Image::new_with_defaults(data, size);
+        image.generate_mipmaps();
+        Ok(image)
     }
 }
@@ -1,12 +1,20 @@
+mod group;
 mod handle;
+pub mod io;
 mod loader;
 pub mod scene;
 mod shader;
+mod watcher;
 
+pub use group::{AssetGroup, AssetGroupPlugin, AssetGroups, asset_group_loaded};
 pub use handle::Handle;
-pub use loader::{AssetLoader, LoadableAsset};
-pub use scene::{Scene, SceneProto};
+pub use loader::{
+    AssetEvent, AssetLoader, AssetPlugin, AsyncLoadableAsset, LoadState, LoadableAsset,
+    LoadableAssetWithSettings,
+};
+pub use scene::{Prefab, PrefabInstance, PrefabRef, Scene, SceneProto};
 pub use shader::{Shader, ShaderLoader};
+pub use watcher::{AssetWatcher, HotReloadPlugin};
 
 use std::collections::HashMap;
 
@@ -14,7 +22,19 @@ pub trait Asset: Send + Sync + 'static {}
 
 /// Name component, mainly used for scene nodes but can be used as a standalone component to easily
 /// identify entities in the ECS
-#[derive(vavo_macros::Component, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[derive(
+    vavo_macros::Component,
+    vavo_macros::Reflect,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Default,
+)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct Name(String);
 
 impl Name {
@@ -86,4 +106,14 @@ impl<A: Asset> Assets<A> {
     pub fn remove(&mut self, id: &Handle<A>) -> Option<A> {
         self.storage.remove(id)
     }
+
+    /// Returns an iterator over all assets with their handles
+    pub fn iter(&self) -> impl Iterator<Item = (&Handle<A>, &A)> {
+        self.storage.iter()
+    }
+
+    /// Returns a mutable iterator over all assets with their handles
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&Handle<A>, &mut A)> {
+        self.storage.iter_mut()
+    }
 }
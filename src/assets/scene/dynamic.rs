@@ -0,0 +1,228 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs, io,
+    path::Path,
+};
+
+use ron::{error::SpannedResult, value::RawValue};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    prelude::{Children, EntityId, Parent, World},
+    query::RunQuery,
+    reflect::{Reflect, registry::ReflectTypeRegistry},
+};
+
+/// Snapshot of entities and their serializable components, which can be saved to a `.scene.ron`
+/// file and spawned into a world later. Only components registered with
+/// [`App::register_serializable`](crate::app::App::register_serializable) are saved.
+///
+/// Entity ids are remapped when the scene is spawned, including ids in fields of components.
+/// [`Parent`] and [`Children`] aren't saved as components, the hierarchy is restored from the
+/// saved parent of each entity.
+///
+/// ```ignore
+/// fn save_level(app: &mut App) {
+///     let scene = DynamicScene::from_world(&mut app.world, &app.type_registry);
+///     scene.save("assets/level.scene.ron").expect("Level should be saved");
+/// }
+///
+/// fn load_level(app: &mut App) {
+///     let scene = DynamicScene::load("assets/level.scene.ron").expect("Level should be loaded");
+///     scene.spawn(&mut app.world, &app.type_registry).expect("Level should be valid");
+/// }
+/// ```
+#[derive(Serialize, Deserialize, Default)]
+pub struct DynamicScene {
+    entities: Vec<DynamicEntity>,
+}
+
+/// Saved entity of a [`DynamicScene`]
+#[derive(Serialize, Deserialize)]
+struct DynamicEntity {
+    /// Id of the entity when it was saved
+    entity: EntityId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    parent: Option<EntityId>,
+    /// Serialized components by their short type name
+    components: BTreeMap<String, Box<RawValue>>,
+}
+
+impl DynamicScene {
+    /// Create a scene from all entities of `world` which have a serializable component. The
+    /// parent of an entity is only kept if it's saved too.
+    pub fn from_world(world: &mut World, registry: &ReflectTypeRegistry) -> Self {
+        let mut entities = world.query::<EntityId>().iter_mut();
+        entities.sort_by_key(|entity| entity.index());
+
+        let mut scene = Self::default();
+        for entity in entities {
+            scene.push_entity(world, registry, entity);
+        }
+        scene.remove_missing_parents();
+        scene
+    }
+
+    /// Create a scene from `root` and all its descendants. Descendants without serializable
+    /// components are skipped, their children are saved without a parent.
+    pub fn from_entity(world: &mut World, registry: &ReflectTypeRegistry, root: EntityId) -> Self {
+        let mut scene = Self::default();
+        let mut stack = vec![root];
+
+        while let Some(entity) = stack.pop() {
+            scene.push_entity(world, registry, entity);
+            if let Some(children) = world.entities.get_component::<Children>(entity) {
+                stack.extend(children.ids.iter().rev());
+            }
+        }
+
+        scene.remove_missing_parents();
+        scene
+    }
+
+    /// Returns the amount of saved entities
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    /// Returns true if no entities are saved
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    /// Serialize the components of `entity` and add it to the scene, if it has any serializable
+    /// components
+    fn push_entity(&mut self, world: &mut World, registry: &ReflectTypeRegistry, entity: EntityId) {
+        let mut components = BTreeMap::new();
+        for type_id in world.entities.component_types(entity) {
+            let Some(hooks) = registry.get_serializable(type_id) else {
+                continue;
+            };
+            let Some(value) = world
+                .entities
+                .get_component_untyped(entity, type_id, false)
+                .and_then(|ptr| registry.reflect(ptr, type_id))
+            else {
+                continue;
+            };
+
+            match (hooks.serialize)(value) {
+                Ok(raw) => {
+                    components.insert(hooks.name.to_string(), raw);
+                }
+                Err(err) => tracing::error!("Failed to serialize '{}': {err}", hooks.name),
+            }
+        }
+
+        if components.is_empty() {
+            return;
+        }
+
+        let parent = world
+            .entities
+            .get_component::<Parent>(entity)
+            .map(|parent| parent.id);
+        self.entities.push(DynamicEntity {
+            entity,
+            parent,
+            components,
+        });
+    }
+
+    /// Removes parents which aren't saved in the scene
+    fn remove_missing_parents(&mut self) {
+        let saved: HashSet<EntityId> = self.entities.iter().map(|entity| entity.entity).collect();
+
+        for entity in &mut self.entities {
+            if entity.parent.is_some_and(|parent| !saved.contains(&parent)) {
+                entity.parent = None;
+            }
+        }
+    }
+
+    /// Spawn the scene into `world`, and return the new ids of the saved entities.
+    ///
+    /// Components which aren't registered as serializable are skipped with a warning. Entity ids
+    /// in component fields are replaced with the new ids, ids of entities which aren't part of
+    /// the scene are kept. Nothing is spawned if a component can't be deserialized.
+    pub fn spawn(
+        &self,
+        world: &mut World,
+        registry: &ReflectTypeRegistry,
+    ) -> SpannedResult<HashMap<EntityId, EntityId>> {
+        let mut entities = Vec::with_capacity(self.entities.len());
+        for entity in &self.entities {
+            let mut components = Vec::with_capacity(entity.components.len());
+            for (name, raw) in &entity.components {
+                let Some(hooks) = registry.get_serializable_by_name(name) else {
+                    tracing::warn!("Scene component '{name}' is not registered as serializable");
+                    continue;
+                };
+                components.push((hooks.insert, (hooks.deserialize)(raw)?));
+            }
+            entities.push(components);
+        }
+
+        let entity_map: HashMap<_, _> = self
+            .entities
+            .iter()
+            .map(|entity| (entity.entity, world.spawn()))
+            .collect();
+
+        for (entity, components) in self.entities.iter().zip(entities) {
+            let id = entity_map[&entity.entity];
+            for (insert, mut value) in components {
+                remap_entities(value.as_mut(), &entity_map);
+                insert(world, id, value);
+            }
+        }
+
+        for entity in &self.entities {
+            if let Some(parent) = entity.parent.and_then(|parent| entity_map.get(&parent)) {
+                world.add_child(*parent, entity_map[&entity.entity]);
+            }
+        }
+
+        Ok(entity_map)
+    }
+
+    /// Serialize the scene to a pretty RON string
+    pub fn to_ron(&self) -> ron::Result<String> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+    }
+
+    /// Deserialize a scene from a RON string
+    pub fn from_ron(ron: &str) -> SpannedResult<Self> {
+        ron::from_str(ron)
+    }
+
+    /// Save the scene to a file at `path`, usually with the `.scene.ron` extension
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let contents = self.to_ron().map_err(io::Error::other)?;
+        fs::write(path, contents)
+    }
+
+    /// Load a scene saved with [`save`](Self::save)
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        Self::from_ron(&contents).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
+
+/// Replaces every [`EntityId`] in the reflected fields of `value` with its mapped id
+fn remap_entities(value: &mut dyn Reflect, entity_map: &HashMap<EntityId, EntityId>) {
+    if let Some(entity) = value.downcast_mut::<EntityId>() {
+        if let Some(mapped) = entity_map.get(entity) {
+            *entity = *mapped;
+        }
+        return;
+    }
+
+    let mut index = 0;
+    while let Some(field) = value.field_mut_by_index(index) {
+        remap_entities(field, entity_map);
+        index += 1;
+    }
+}
@@ -1,7 +1,15 @@
+#[cfg(feature = "serialize")]
+mod dynamic;
 mod macros;
+mod prefab;
 mod proto;
+pub mod streaming;
 
+#[cfg(feature = "serialize")]
+pub use dynamic::DynamicScene;
 pub use macros::*;
+pub(crate) use prefab::build_instance;
+pub use prefab::{Prefab, PrefabInstance, PrefabPlugin, PrefabRef};
 pub use proto::Proto;
 
 use crate::prelude::{Component, EntityId, World};
@@ -0,0 +1,278 @@
+use std::{
+    any::TypeId,
+    sync::{
+        Arc,
+        atomic::{AtomicU32, Ordering},
+    },
+};
+
+use crate::{
+    app::{App, Plugin},
+    assets::{Asset, Assets, Handle, Scene},
+    macros::{Component, Resource},
+    prelude::{Children, EntityId, Parent, World},
+    query::{Query, RunQuery},
+    system::phase,
+};
+
+/// Maximum nesting depth of [`PrefabRef`]s, deeper references are assumed to be cyclic
+const MAX_DEPTH: usize = 32;
+
+/// Source of prefab versions, unique across all prefabs so replacing a prefab asset with a new one
+/// also changes the version
+static NEXT_VERSION: AtomicU32 = AtomicU32::new(0);
+
+/// Reusable [`Scene`] asset. Prefabs can reference other prefabs with [`PrefabRef`], and are
+/// spawned with [`EntityCommands::insert_prefab`].
+///
+/// Edits made with [`set_scene`](Prefab::set_scene), or by replacing the asset, are propagated to
+/// all live [instances](PrefabInstance), including instances of prefabs which reference it.
+///
+/// [`EntityCommands::insert_prefab`]: crate::system::commands::EntityCommands::insert_prefab
+pub struct Prefab {
+    scene: Arc<dyn Scene>,
+    version: u32,
+}
+
+impl Asset for Prefab {}
+
+impl Prefab {
+    /// Create a new prefab from a scene, usually built with the [`scene!`](crate::scene) macro
+    pub fn new(scene: impl Scene) -> Self {
+        Self {
+            scene: Arc::new(scene),
+            version: NEXT_VERSION.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+
+    /// Replace the scene of this prefab, live instances are rebuilt
+    pub fn set_scene(&mut self, scene: impl Scene) {
+        *self = Self::new(scene);
+    }
+
+    /// Returns the version of this prefab, which changes with every edit
+    #[inline]
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+}
+
+/// [`Scene`] which builds a [`Prefab`] into the entity, used to nest prefabs in other prefabs or
+/// scenes. Put it in a [`child!`](crate::child) to build the prefab as a child entity.
+///
+/// Since a scene list keeps one scene object per type, a node can only reference one prefab.
+///
+/// ```ignore
+/// let enemy = prefabs.add(Prefab::new(scene![
+///     PrefabRef::new(character.clone()),
+///     Name::new("Enemy"),
+///     child![PrefabRef::new(sword.clone())],
+/// ]));
+/// ```
+#[derive(Clone)]
+pub struct PrefabRef {
+    handle: Handle<Prefab>,
+}
+
+impl PrefabRef {
+    /// Reference the prefab `handle`
+    #[inline]
+    pub fn new(handle: Handle<Prefab>) -> Self {
+        Self { handle }
+    }
+}
+
+impl Scene for PrefabRef {
+    fn build(&self, world: &mut World, entity: EntityId) {
+        let prefab = world
+            .resources
+            .try_get::<Assets<Prefab>>()
+            .and_then(|prefabs| {
+                let prefab = prefabs.get(&self.handle)?;
+                Some((prefab.scene.clone(), prefab.version))
+            });
+
+        // Record the dependency even if the prefab is missing, so adding it triggers a rebuild
+        let version = prefab.as_ref().map(|(_, version)| *version);
+        let depth = match world.resources.try_get_mut::<PrefabBuildContext>() {
+            Some(mut context) => {
+                context.dependencies.push((self.handle.clone(), version));
+                context.depth += 1;
+                context.depth
+            }
+            None => 1,
+        };
+
+        match prefab {
+            Some(_) if depth > MAX_DEPTH => {
+                tracing::error!("Prefab {:?} is nested too deep, is it cyclic?", self.handle)
+            }
+            Some((scene, _)) => scene.build(world, entity),
+            None => tracing::warn!("Prefab {:?} does not exist", self.handle),
+        }
+
+        if let Some(mut context) = world.resources.try_get_mut::<PrefabBuildContext>() {
+            context.depth -= 1;
+        }
+    }
+}
+
+/// Component of an entity spawned from a [`Prefab`], with per-instance overrides which take
+/// precedence over the prefab's components. Inserted by
+/// [`EntityCommands::insert_prefab`](crate::system::commands::EntityCommands::insert_prefab).
+///
+/// When the prefab, or a prefab it references, changes, the instance is rebuilt: the components
+/// and child entities created by the previous build are removed, including any changes made to
+/// them since.
+#[derive(Component)]
+pub struct PrefabInstance {
+    prefab: Handle<Prefab>,
+    overrides: Option<Arc<dyn Scene>>,
+    /// Prefabs used by the last build with their versions, None if the prefab was missing
+    dependencies: Vec<(Handle<Prefab>, Option<u32>)>,
+    /// Components added to the entity by the last build
+    components: Vec<TypeId>,
+    /// Children spawned by the last build
+    children: Vec<EntityId>,
+}
+
+impl PrefabInstance {
+    /// Returns the handle of the instanced prefab
+    #[inline]
+    pub fn prefab(&self) -> &Handle<Prefab> {
+        &self.prefab
+    }
+
+    /// Returns true if the prefab or any prefab it references changed since the last build
+    fn is_outdated(&self, prefabs: &Assets<Prefab>) -> bool {
+        self.dependencies
+            .iter()
+            .any(|(handle, version)| prefabs.get(handle).map(Prefab::version) != *version)
+    }
+}
+
+/// Dependencies collected while building a prefab instance
+#[derive(Resource, Default)]
+struct PrefabBuildContext {
+    dependencies: Vec<(Handle<Prefab>, Option<u32>)>,
+    depth: usize,
+}
+
+/// Build `prefab` and `overrides` into `entity` and insert its [`PrefabInstance`]
+pub(crate) fn build_instance(
+    world: &mut World,
+    entity: EntityId,
+    prefab: Handle<Prefab>,
+    overrides: Option<Arc<dyn Scene>>,
+) {
+    if world.entities.tracking.get_location(entity).is_none() {
+        return;
+    }
+
+    let components_before = world.entities.component_types(entity);
+    let children_before = children_of(world, entity);
+
+    let previous_context = world.resources.remove::<PrefabBuildContext>();
+    world.resources.insert(PrefabBuildContext::default());
+
+    // Scenes don't replace existing components, so the overrides are built first
+    if let Some(overrides) = &overrides {
+        overrides.build(world, entity);
+    }
+    PrefabRef::new(prefab.clone()).build(world, entity);
+
+    let context = world
+        .resources
+        .remove::<PrefabBuildContext>()
+        .expect("build context was inserted");
+    if let Some(previous_context) = previous_context {
+        world.resources.insert(previous_context);
+    }
+
+    let components = world
+        .entities
+        .component_types(entity)
+        .into_iter()
+        .filter(|type_id| {
+            !components_before.contains(type_id)
+                && *type_id != TypeId::of::<Children>()
+                && *type_id != TypeId::of::<Parent>()
+                && *type_id != TypeId::of::<PrefabInstance>()
+        })
+        .collect();
+    let children = children_of(world, entity)
+        .into_iter()
+        .filter(|child| !children_before.contains(child))
+        .collect();
+
+    let instance = PrefabInstance {
+        prefab,
+        overrides,
+        dependencies: context.dependencies,
+        components,
+        children,
+    };
+    world.insert_component(entity, instance, true);
+}
+
+fn children_of(world: &World, entity: EntityId) -> Vec<EntityId> {
+    world
+        .entities
+        .get_component::<Children>(entity)
+        .map(|children| children.ids.clone())
+        .unwrap_or_default()
+}
+
+/// Remove what the last build of the instance created, and build it again
+fn rebuild_instance(world: &mut World, entity: EntityId) {
+    let Some(instance) = world.entities.get_component::<PrefabInstance>(entity) else {
+        return;
+    };
+    let prefab = instance.prefab.clone();
+    let overrides = instance.overrides.clone();
+    let components = instance.components.clone();
+    let children = instance.children.clone();
+
+    for child in children {
+        world.entities.despawn_entity_recursive(child);
+    }
+
+    let current = world.entities.component_types(entity);
+    for type_id in components {
+        if current.contains(&type_id) {
+            world.entities.remove_component(entity, type_id);
+        }
+    }
+
+    build_instance(world, entity, prefab, overrides);
+}
+
+/// Rebuild instances of prefabs which changed
+fn update_prefab_instances_system(world: &mut World) {
+    let outdated: Vec<EntityId> = {
+        let Some(prefabs) = world.resources.try_get::<Assets<Prefab>>() else {
+            return;
+        };
+
+        Query::<(EntityId, &PrefabInstance)>::new(&mut world.entities, *world.tick)
+            .iter_mut()
+            .into_iter()
+            .filter(|(_, instance)| instance.is_outdated(&prefabs))
+            .map(|(entity, _)| entity)
+            .collect()
+    };
+
+    for entity in outdated {
+        rebuild_instance(world, entity);
+    }
+}
+
+/// Adds the [`Prefab`] assets and propagates prefab edits to their instances
+pub struct PrefabPlugin;
+
+impl Plugin for PrefabPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Assets<Prefab>>()
+            .register_system(update_prefab_instances_system, phase::PreUpdate);
+    }
+}
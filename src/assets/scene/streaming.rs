@@ -0,0 +1,298 @@
+//! Streaming of world chunks, for open-world style maps which are too large to be spawned at once.
+//!
+//! A map is split into chunks, each being a [`Prefab`] instance placed with a [`Transform`]. Chunks
+//! closer than [`ChunkStreamer::load_distance`] to any [`StreamingFocus`] entity are spawned, and
+//! despawned once all of them are further than [`ChunkStreamer::unload_distance`]. Spawns and
+//! despawns are spread over frames with a per-frame budget.
+//!
+//! Chunks added with [`ChunkStreamer::add_chunk_with`] build their prefab in a task the first time
+//! they come into range, e.g. to read the chunk from disk without blocking. The prefab is kept for
+//! later loads.
+//!
+//! ```ignore
+//! app.add_plugin(ChunkStreamingPlugin)
+//!     .add_startup_system(|mut streamer: ResMut<ChunkStreamer>, mut commands: Commands| {
+//!         for x in -8..8 {
+//!             for z in -8..8 {
+//!                 let position = Vec3::new(x as f32, 0.0, z as f32) * 64.0;
+//!                 streamer.add_chunk_with(Transform::new().with_translation(position), move || {
+//!                     Ok(Prefab::new(read_chunk(x, z)?))
+//!                 });
+//!             }
+//!         }
+//!
+//!         commands.spawn_empty().insert(Transform::default()).insert(StreamingFocus);
+//!     });
+//! ```
+
+use std::{collections::VecDeque, sync::Arc};
+
+use crate::{macros::Reflect, prelude::*};
+
+use super::PrefabPlugin;
+
+/// Builds the prefab of a chunk in a task
+type ChunkBuilder = Arc<dyn Fn() -> Result<Prefab, String> + Send + Sync>;
+
+/// Adds the [`ChunkStreamer`] and streams its chunks around [`StreamingFocus`] entities. For more
+/// information, see the [streaming module](crate::assets::scene::streaming).
+pub struct ChunkStreamingPlugin;
+
+impl Plugin for ChunkStreamingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ChunkStreamer>()
+            .register_event::<ChunkEvent>()
+            .register_system(update_chunk_streaming_system, phase::PreUpdate);
+    }
+
+    fn dependencies(&self) -> Vec<PluginId> {
+        vec![PluginId::of::<PrefabPlugin>()]
+    }
+}
+
+/// Marker for the entities chunks are streamed around, usually the player or the camera. The
+/// distance to the closest focus is used.
+#[derive(Component, Reflect, Default, Debug, Clone, Copy)]
+pub struct StreamingFocus;
+
+/// Id of a chunk in the [`ChunkStreamer`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChunkId(u32);
+
+/// Component on the root entity of a spawned chunk
+#[derive(Component, Debug, Clone, Copy)]
+pub struct StreamedChunk(pub ChunkId);
+
+/// Event written when a chunk is spawned, despawned or its prefab fails to build
+#[derive(Event, Debug, Clone)]
+pub enum ChunkEvent {
+    /// The chunk was spawned as `entity`, its prefab instance is built when commands are applied
+    Loaded { id: ChunkId, entity: EntityId },
+    /// The chunk went out of range, its entity is despawned within the despawn budget
+    Unloaded { id: ChunkId, entity: EntityId },
+    /// The chunk builder returned an error or panicked, the chunk isn't loaded again
+    Failed { id: ChunkId, error: String },
+}
+
+/// Where the prefab of a chunk comes from
+enum ChunkSource {
+    Prefab(Handle<Prefab>),
+    Builder(ChunkBuilder),
+}
+
+/// Streaming state of a chunk
+enum ChunkState {
+    Unloaded,
+    /// The prefab is built in a task
+    Building(AsyncTask<Result<Prefab, String>>),
+    /// The prefab is ready, the chunk is waiting for the spawn budget
+    Pending,
+    Loaded(EntityId),
+    Failed,
+}
+
+struct Chunk {
+    transform: Transform,
+    source: ChunkSource,
+    /// Prefab of the chunk, set once it was built
+    prefab: Option<Handle<Prefab>>,
+    state: ChunkState,
+}
+
+/// Chunks of the map and the streaming settings. Used as a resource. For more information, see the
+/// [streaming module](crate::assets::scene::streaming).
+#[derive(Resource)]
+pub struct ChunkStreamer {
+    chunks: Vec<Chunk>,
+    /// Entities of unloaded chunks waiting to be despawned
+    despawn_queue: VecDeque<EntityId>,
+    /// Chunks closer than this to a focus are loaded
+    pub load_distance: f32,
+    /// Chunks further than this from every focus are unloaded, should be larger than
+    /// [`load_distance`](Self::load_distance) so chunks on the border don't load and unload
+    /// every frame
+    pub unload_distance: f32,
+    /// Maximum amount of chunks spawned per frame
+    pub spawns_per_frame: usize,
+    /// Maximum amount of chunk entities despawned per frame
+    pub despawns_per_frame: usize,
+}
+
+impl Default for ChunkStreamer {
+    fn default() -> Self {
+        Self {
+            chunks: Vec::new(),
+            despawn_queue: VecDeque::new(),
+            load_distance: 128.0,
+            unload_distance: 160.0,
+            spawns_per_frame: 2,
+            despawns_per_frame: 4,
+        }
+    }
+}
+
+impl ChunkStreamer {
+    /// Add a chunk which spawns an instance of `prefab` with `transform` when in range
+    pub fn add_chunk(&mut self, transform: Transform, prefab: Handle<Prefab>) -> ChunkId {
+        self.push(Chunk {
+            transform,
+            source: ChunkSource::Prefab(prefab.clone()),
+            prefab: Some(prefab),
+            state: ChunkState::Unloaded,
+        })
+    }
+
+    /// Add a chunk whose prefab is built by `builder` in a task, the first time the chunk comes in
+    /// range
+    pub fn add_chunk_with<F>(&mut self, transform: Transform, builder: F) -> ChunkId
+    where
+        F: Fn() -> Result<Prefab, String> + Send + Sync + 'static,
+    {
+        self.push(Chunk {
+            transform,
+            source: ChunkSource::Builder(Arc::new(builder)),
+            prefab: None,
+            state: ChunkState::Unloaded,
+        })
+    }
+
+    fn push(&mut self, chunk: Chunk) -> ChunkId {
+        self.chunks.push(chunk);
+        ChunkId(self.chunks.len() as u32 - 1)
+    }
+
+    /// Returns the entity of the chunk if it's spawned
+    pub fn entity(&self, id: ChunkId) -> Option<EntityId> {
+        match self.chunks.get(id.0 as usize)?.state {
+            ChunkState::Loaded(entity) => Some(entity),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the chunk is spawned
+    #[inline]
+    pub fn is_loaded(&self, id: ChunkId) -> bool {
+        self.entity(id).is_some()
+    }
+
+    /// Returns the amount of spawned chunks
+    pub fn loaded_count(&self) -> usize {
+        self.chunks
+            .iter()
+            .filter(|chunk| matches!(chunk.state, ChunkState::Loaded(_)))
+            .count()
+    }
+
+    /// Returns true if any chunk is building its prefab or waiting to be spawned or despawned
+    pub fn is_streaming(&self) -> bool {
+        !self.despawn_queue.is_empty()
+            || self
+                .chunks
+                .iter()
+                .any(|chunk| matches!(chunk.state, ChunkState::Building(_) | ChunkState::Pending))
+    }
+}
+
+/// Loads and unloads chunks by their distance to the closest [`StreamingFocus`], and despawns
+/// unloaded chunks within the budget
+fn update_chunk_streaming_system(
+    mut commands: Commands,
+    mut streamer: ResMut<ChunkStreamer>,
+    mut prefabs: ResMut<Assets<Prefab>>,
+    mut events: EventWriter<ChunkEvent>,
+    mut focus_query: Query<&GlobalTransform, With<StreamingFocus>>,
+) {
+    let streamer = &mut *streamer;
+
+    // without a focus nothing is streamed, the chunks stay as they are
+    let focuses = focus_query
+        .iter_mut()
+        .into_iter()
+        .map(|transform| transform.translation())
+        .collect::<Vec<_>>();
+    let distance = |position: Vec3| {
+        focuses
+            .iter()
+            .map(|focus| focus.distance(position))
+            .reduce(f32::min)
+    };
+
+    let mut spawns = 0;
+    for (index, chunk) in streamer.chunks.iter_mut().enumerate() {
+        let id = ChunkId(index as u32);
+        let Some(distance) = distance(chunk.transform.translation) else {
+            break;
+        };
+        let in_range = distance <= streamer.load_distance;
+        let out_of_range = distance > streamer.unload_distance;
+
+        if let ChunkState::Building(task) = &mut chunk.state {
+            let Some(result) = task.retrieve() else {
+                continue;
+            };
+
+            match result.unwrap_or_else(|_| Err("Chunk builder panicked".to_string())) {
+                Ok(prefab) => {
+                    chunk.prefab = Some(prefabs.add(prefab));
+                    chunk.state = ChunkState::Pending;
+                }
+                Err(error) => {
+                    tracing::error!("Failed to build chunk {:?}: {}", id, error);
+                    events.write(ChunkEvent::Failed { id, error });
+                    chunk.state = ChunkState::Failed;
+                    continue;
+                }
+            }
+        }
+
+        match chunk.state {
+            ChunkState::Unloaded if in_range => match (&chunk.prefab, &chunk.source) {
+                (Some(_), _) => chunk.state = ChunkState::Pending,
+                (None, ChunkSource::Builder(builder)) => {
+                    let builder = builder.clone();
+                    let task = AsyncTask::execute_async(move || async move { builder() });
+                    chunk.state = ChunkState::Building(task);
+                }
+                (None, ChunkSource::Prefab(_)) => unreachable!("prefab chunks have their prefab"),
+            },
+            // the task keeps running, its result is dropped
+            ChunkState::Building(_) | ChunkState::Pending if out_of_range => {
+                chunk.state = ChunkState::Unloaded;
+            }
+            ChunkState::Pending if spawns < streamer.spawns_per_frame => {
+                let prefab = chunk
+                    .prefab
+                    .clone()
+                    .expect("pending chunks have their prefab");
+                let entity = commands
+                    .spawn_empty()
+                    .insert(chunk.transform)
+                    .insert(StreamedChunk(id))
+                    .insert_prefab(prefab)
+                    .entity_id();
+                chunk.state = ChunkState::Loaded(entity);
+                events.write(ChunkEvent::Loaded { id, entity });
+                spawns += 1;
+            }
+            ChunkState::Loaded(entity) if out_of_range => {
+                streamer.despawn_queue.push_back(entity);
+                chunk.state = ChunkState::Unloaded;
+                events.write(ChunkEvent::Unloaded { id, entity });
+            }
+            _ => {}
+        }
+    }
+
+    let despawns = streamer
+        .despawns_per_frame
+        .min(streamer.despawn_queue.len());
+    for entity in streamer.despawn_queue.drain(..despawns) {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Returns true if no chunk of the [`ChunkStreamer`] is streaming, e.g. to hide a loading screen
+/// once the chunks around the player are spawned
+pub fn chunks_streamed(streamer: Option<Res<ChunkStreamer>>) -> bool {
+    streamer.is_none_or(|streamer| !streamer.is_streaming())
+}
@@ -1,7 +1,9 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, path::Path};
 
 use wgpu::{Device, ShaderSource};
 
+use super::{io, watcher::WatchedFile};
+
 /// Wrapper for a wgpu ShaderModule with a label
 #[derive(Debug)]
 pub struct Shader {
@@ -39,6 +41,8 @@ impl Shader {
 #[derive(Debug, Default, crate::macros::Resource)]
 pub struct ShaderLoader {
     cache: HashMap<String, Shader>,
+    /// Files of shaders loaded with `load_file`, by label
+    files: HashMap<String, WatchedFile>,
 }
 
 impl ShaderLoader {
@@ -63,6 +67,66 @@ impl ShaderLoader {
         )
     }
 
+    /// Load and create a wgsl shader from the file at `path`, returns None if label already exists.
+    /// The shader is recompiled when the file changes if the
+    /// [`HotReloadPlugin`](super::HotReloadPlugin) is added.
+    ///
+    /// # Panics
+    /// Panics if the file can't be read
+    pub fn load_file(
+        &mut self,
+        label: &str,
+        path: impl AsRef<Path>,
+        device: &Device,
+    ) -> Option<&Shader> {
+        let path = path.as_ref();
+        if self.cache.contains_key(label) {
+            return None;
+        }
+
+        let bytes =
+            io::read(path).unwrap_or_else(|_| panic!("Could not read shader at '{:?}'", path));
+        let wgsl = String::from_utf8(bytes)
+            .unwrap_or_else(|_| panic!("Shader at '{:?}' is not valid UTF-8", path));
+
+        self.files.insert(label.to_string(), WatchedFile::new(path));
+        self.load(label, &wgsl, device)
+    }
+
+    /// Recompile the shaders whose files changed, returns true if any shader was replaced.
+    /// Shaders which fail to compile are logged and kept.
+    pub(super) fn reload_changed(&mut self, device: &Device) -> bool {
+        let mut reloaded = false;
+
+        for (label, file) in &mut self.files {
+            if !file.changed() {
+                continue;
+            }
+
+            let wgsl = match io::read(&file.path).map(String::from_utf8) {
+                Ok(Ok(wgsl)) => wgsl,
+                _ => {
+                    tracing::error!("Failed to read shader '{}' at '{:?}'", label, file.path);
+                    continue;
+                }
+            };
+
+            // invalid shaders would trigger the uncaptured error handler, which panics
+            device.push_error_scope(wgpu::ErrorFilter::Validation);
+            let shader = Shader::wgsl(device, label, &wgsl);
+            if let Some(error) = pollster::block_on(device.pop_error_scope()) {
+                tracing::error!("Failed to reload shader '{}': {}", label, error);
+                continue;
+            }
+
+            tracing::info!("Reloaded shader '{}'", label);
+            self.cache.insert(label.clone(), shader);
+            reloaded = true;
+        }
+
+        reloaded
+    }
+
     /// Get a shader by label
     pub fn get(&self, label: &str) -> &Shader {
         if let Some(shader) = self.cache.get(label) {
@@ -0,0 +1,235 @@
+//! Hot reloading of assets whose files change on disk, enabled with the [`HotReloadPlugin`].
+//!
+//! Assets loaded with the [`AssetLoader`] while the plugin is added are watched. When a file
+//! changes, the asset is loaded again and replaced in its [`Assets`] storage, so existing handles
+//! get the new data and an [`AssetEvent::Modified`] is sent. Meshes and images are reloaded,
+//! other assets only if they implement [`LoadableAsset::reload`]. If the file can't be loaded,
+//! e.g. because it's saved halfway, the error is logged and the old asset is kept.
+//!
+//! Shaders loaded with [`ShaderLoader::load_file`] are recompiled the same way and the render
+//! graph pipelines are recreated with them. Shaders with errors are logged and not replaced.
+//!
+//! ```ignore
+//! app.add_plugin(HotReloadPlugin::default())
+//!     .add_startup_system(|world: &mut World| {
+//!         let mut loader = world.resources.get_mut::<AssetLoader>();
+//!         let image = loader.load::<Image>("assets/textures/grass.png", &mut world.resources);
+//!     });
+//! ```
+//!
+//! Files are checked by their modification time, which is not available on the web, so nothing is
+//! reloaded there.
+
+use std::{
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use web_time::Duration;
+
+use crate::{
+    core::graph::RenderGraph,
+    event::Events,
+    prelude::*,
+    render_assets::{BindGroup, Buffer, Pipeline, RenderAssets},
+    renderer::{Texture, newtype::RenderDevice},
+    system::phase,
+};
+
+use super::{AssetEvent, LoadableAsset, ShaderLoader};
+
+/// Reloads a watched asset, returns None if the asset doesn't support reloading
+type ReloadFn = Box<
+    dyn FnMut(&mut AssetLoader, &mut Resources, &Path) -> Option<Result<(), String>> + Send + Sync,
+>;
+
+/// File checked for changes by its modification time
+#[derive(Debug)]
+pub(crate) struct WatchedFile {
+    pub path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl WatchedFile {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        Self {
+            modified: Self::modified(&path),
+            path,
+        }
+    }
+
+    fn modified(path: &Path) -> Option<SystemTime> {
+        std::fs::metadata(path)
+            .and_then(|meta| meta.modified())
+            .ok()
+    }
+
+    /// Returns true if the file changed since the last call
+    pub fn changed(&mut self) -> bool {
+        let modified = Self::modified(&self.path);
+        if modified.is_none() || modified == self.modified {
+            return false;
+        }
+        self.modified = modified;
+        true
+    }
+}
+
+struct WatchedAsset {
+    file: WatchedFile,
+    reload: ReloadFn,
+}
+
+/// Assets reloaded when their files change, added by the [`HotReloadPlugin`]. Assets loaded with
+/// the [`AssetLoader`] are watched automatically.
+#[derive(Resource, Default)]
+pub struct AssetWatcher {
+    assets: Vec<WatchedAsset>,
+}
+
+impl AssetWatcher {
+    /// Reload the asset of `handle` when the file at `path` changes. Assets which don't support
+    /// reloading stop being watched on their first change.
+    pub fn watch<A: LoadableAsset>(&mut self, path: impl AsRef<Path>, handle: Handle<A>) {
+        let reload: ReloadFn = Box::new(move |loader, resources, path| {
+            let asset = match A::reload(loader, resources, path)? {
+                Ok(asset) => asset,
+                Err(error) => return Some(Err(error)),
+            };
+
+            resources
+                .get_mut::<Assets<A>>()
+                .insert(handle.clone(), asset);
+            if let Some(mut events) = resources.try_get_mut::<Events<AssetEvent<A>>>() {
+                events.write(AssetEvent::Modified(handle.clone()));
+            }
+            Some(Ok(()))
+        });
+
+        self.assets.push(WatchedAsset {
+            file: WatchedFile::new(path.as_ref()),
+            reload,
+        });
+    }
+
+    /// Returns the amount of watched assets
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.assets.len()
+    }
+
+    /// Returns true if no assets are watched
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.assets.is_empty()
+    }
+
+    /// Reload the assets whose files changed
+    fn update(&mut self, loader: &mut AssetLoader, resources: &mut Resources) {
+        self.assets.retain_mut(|asset| {
+            if !asset.file.changed() {
+                return true;
+            }
+
+            let path = asset.file.path.display();
+            match (asset.reload)(loader, resources, &asset.file.path) {
+                Some(Ok(())) => tracing::info!("Reloaded asset '{}'", path),
+                Some(Err(error)) => tracing::error!("Failed to reload asset '{}': {}", path, error),
+                None => return false,
+            }
+            true
+        });
+    }
+}
+
+/// System which reloads the watched assets whose files changed
+fn reload_assets_system(world: &mut World) {
+    let mut watcher = world.resources.get_mut::<AssetWatcher>();
+    if watcher.is_empty() {
+        return;
+    }
+
+    let mut loader = world.resources.get_mut::<AssetLoader>();
+    watcher.update(&mut loader, &mut world.resources);
+}
+
+/// System which recompiles the shaders whose files changed and recreates the pipelines using them
+fn reload_shaders_system(
+    graph: &mut RenderGraph,
+    device: Option<Res<RenderDevice>>,
+    mut shader_loader: ResMut<ShaderLoader>,
+    mut pipelines: ResMut<RenderAssets<Pipeline>>,
+) {
+    let Some(device) = device else {
+        return;
+    };
+
+    if shader_loader.reload_changed(&device) {
+        pipelines.clear();
+        graph.invalidate();
+    }
+}
+
+/// System which drops the GPU data of reloaded meshes and images, so it's recreated on its next
+/// use
+fn invalidate_reloaded_system(
+    mut meshes: EventReader<AssetEvent<Mesh>>,
+    mut images: EventReader<AssetEvent<Image>>,
+    mut buffers: ResMut<RenderAssets<Buffer>>,
+    mut textures: ResMut<RenderAssets<Texture>>,
+    mut bind_groups: ResMut<RenderAssets<BindGroup>>,
+) {
+    for event in meshes.read() {
+        if let AssetEvent::Modified(handle) = event {
+            buffers.remove(handle);
+        }
+    }
+
+    let mut reloaded_image = false;
+    for event in images.read() {
+        if let AssetEvent::Modified(handle) = event {
+            textures.remove(handle);
+            reloaded_image = true;
+        }
+    }
+
+    // any bind group, e.g. of a material, can reference the old texture
+    if reloaded_image {
+        bind_groups.clear();
+    }
+}
+
+/// Adds the [`AssetWatcher`] and reloads assets and shaders whose files changed
+pub struct HotReloadPlugin {
+    /// Time between checks of the watched files
+    pub interval: Duration,
+}
+
+impl Default for HotReloadPlugin {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_millis(500),
+        }
+    }
+}
+
+impl Plugin for HotReloadPlugin {
+    fn build(&self, app: &mut App) {
+        #[cfg(target_arch = "wasm32")]
+        tracing::warn!("Hot reloading is not supported on the web");
+
+        app.init_resource::<AssetWatcher>()
+            .register_event::<AssetEvent<Mesh>>()
+            .register_event::<AssetEvent<Image>>()
+            .register_system(
+                reload_assets_system.run_if(on_timer(self.interval)),
+                phase::First,
+            )
+            .register_system(
+                reload_shaders_system.run_if(on_timer(self.interval)),
+                phase::First,
+            )
+            .register_system(invalidate_reloaded_system, phase::PreUpdate);
+    }
+}
@@ -10,12 +10,30 @@
 //! let source: AudioSource = loader.load("assets/sounds/loop.mp3", ctx.resources);
 //! ```
 //!
+//! - Long audio like music can be [streamed](StreamingAudioSource), it's decoded while playing
+//!   instead of at once. Files with one of the [`STREAMING_EXTENSIONS`] are streamed, or choose
+//!   with an [`AudioLoadMode`].
+//! ```ignore
+//! let source = loader.load_with_settings::<AudioSource>(
+//!     "assets/music/theme.mp3",
+//!     AudioLoadMode::Streaming,
+//!     ctx.resources,
+//! );
+//! ```
+//!
 //! - To play the audio, get the [main track](AudioTrack) from the [`AudioManager`] and call `play` on it.
 //! ```ignore
 //! let mut audio = ctx.resources.get_mut::<AudioTrack>().unwrap();
 //! audio.play().set_loop_region(0.0..); // Loop the whole sound
 //! ```
 //!
+//! - For background music use the [`MusicController`], it plays a queue of [`Music`] and
+//!   crossfades between them.
+//! ```ignore
+//! let mut music = ctx.resources.get_mut::<MusicController>().unwrap();
+//! music.play(Music::new(source).looped());
+//! ```
+//!
 //! ## Spatial Audio
 //!
 //! - To play a sound spatially, add a [`SpatialEmitter`] component to an entity. You must have a
@@ -29,6 +47,21 @@
 //! // .. add the emitter to an entity
 //! ```
 //!
+//! - To react to sounds starting, finishing or looping, read the [`AudioPlaybackEvent`]s. It's
+//!   written for the sounds of the [`AudioTrack`] and of every [`SpatialEmitter`].
+//! ```ignore
+//! fn despawn_finished_emitters(mut events: EventReader<AudioPlaybackEvent>, mut commands: Commands) {
+//!     for event in events.read() {
+//!         if let AudioPlaybackEvent::Finished(AudioPlayback { emitter: Some(id), .. }) = event {
+//!             commands.entity(*id).despawn();
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! - To muffle a sound when it's blocked by walls, add an [`AudioOcclusion`] component to the
+//!   emitter and an [`AudioOccluder`] component to the walls.
+//!
 //! ## Audio Tracks
 //!
 //! - To create a new audio track, use the [`AudioManager`]. It's a wrapper around kira's
@@ -42,47 +75,152 @@
 
 mod commands;
 mod manager;
+mod music;
+mod occlusion;
 mod sound;
 mod spatial;
+mod streaming;
 mod track;
 mod update;
 
 pub mod prelude {
     pub use super::AudioSource;
     pub use super::commands::{Easing, PlayCommand, TweenCommand};
-    pub use super::sound::PlaybackState;
+    pub use super::music::{Music, MusicController};
+    pub use super::occlusion::{AudioOccluder, AudioOcclusion};
+    pub use super::sound::{AudioPlayback, AudioPlaybackEvent, PlaybackState};
     pub use super::spatial::{SpatialEmitter, SpatialListener};
+    pub use super::streaming::{AudioLoadMode, STREAMING_EXTENSIONS, StreamingAudioSource};
     pub use super::track::{AudioTrack, MainTrack};
 }
 
-use std::{fmt::Debug, path::Path};
-
-use crate::{assets::LoadableAsset, prelude::*};
+use std::{fmt::Debug, io::Cursor, path::Path, time::Duration};
 
-// TODO: refactor audio once Added<C> and Removed<C> filters are implemented
+use crate::{
+    assets::{AsyncLoadableAsset, LoadableAsset, LoadableAssetWithSettings},
+    prelude::*,
+};
 
 use kira::{sound::static_sound::StaticSoundData, track::TrackBuilder};
 use manager::{AudioManager, AudioManagerSettings};
+use music::update_music;
+use occlusion::update_audio_occlusion;
+use sound::{AudioPlaybackEvent, SoundHandle, SoundSettings, SoundTrack};
+use streaming::{AudioLoadMode, StreamingAudioSource};
 use update::{
-    cleanup_audio_tracks, update_audio_tracks, update_spatial_audio_tracks,
-    update_spatial_listeners,
+    cleanup_audio_tracks, pause_disabled_spatial_audio_tracks, update_audio_tracks,
+    update_spatial_audio_tracks, update_spatial_listeners,
 };
 
 /// Source for an audio file, to play it use [`AudioTrack::play`]
 ///
 /// To load an audio source use the [`AssetLoader`] like so:
 /// ```ignore
-/// let source = asset_loader.load::<AudioSource>("path/to/audio.ogg", resources);
+/// let source = asset_loader.load::<AudioSource>("path/to/audio.wav", resources);
+/// ```
+///
+/// The whole file is decoded when it's loaded, except for files with one of the
+/// [`STREAMING_EXTENSIONS`](prelude::STREAMING_EXTENSIONS), which are
+/// [streamed](StreamingAudioSource). To choose explicitly, load it with an [`AudioLoadMode`]:
+/// ```ignore
+/// let source = asset_loader.load_with_settings::<AudioSource>(
+///     "path/to/music.mp3",
+///     AudioLoadMode::Streaming,
+///     resources,
+/// );
 /// ```
 #[derive(Asset)]
 pub struct AudioSource {
-    source: StaticSoundData,
+    source: AudioSourceData,
+}
+
+/// Decoded or streamed data of an [`AudioSource`]
+enum AudioSourceData {
+    Static(StaticSoundData),
+    Streaming(StreamingAudioSource),
 }
 
 impl AudioSource {
     /// Creates a new audio source from [`kira`](kira)'s StaticSoundData
     pub fn new(source: StaticSoundData) -> Self {
-        Self { source }
+        Self {
+            source: AudioSourceData::Static(source),
+        }
+    }
+
+    /// Creates a new audio source which is decoded while it's playing
+    pub fn streaming(source: StreamingAudioSource) -> Self {
+        Self {
+            source: AudioSourceData::Streaming(source),
+        }
+    }
+
+    /// Returns true if the source is decoded while it's playing
+    #[inline]
+    pub fn is_streaming(&self) -> bool {
+        matches!(self.source, AudioSourceData::Streaming(_))
+    }
+
+    /// Returns the duration of the audio
+    pub fn duration(&self) -> Duration {
+        match &self.source {
+            AudioSourceData::Static(data) => data.duration(),
+            AudioSourceData::Streaming(source) => source.duration(),
+        }
+    }
+
+    /// Play the source on `track` with `settings`
+    pub(crate) fn play(
+        &self,
+        track: &mut impl SoundTrack,
+        settings: SoundSettings,
+    ) -> Result<SoundHandle, String> {
+        match &self.source {
+            AudioSourceData::Static(data) => {
+                let mut data = data.clone().volume(settings.volume);
+                if let Some(loop_start) = settings.loop_start {
+                    data = data.loop_region(loop_start..);
+                }
+                if let Some(tween) = settings.fade_in {
+                    data = data.fade_in_tween(tween);
+                }
+
+                track
+                    .play_sound(data)
+                    .map(SoundHandle::Static)
+                    .map_err(|err| err.to_string())
+            }
+            AudioSourceData::Streaming(source) => {
+                let mut data = source
+                    .sound_data()
+                    .map_err(|err| err.to_string())?
+                    .volume(settings.volume);
+                if let Some(loop_start) = settings.loop_start {
+                    data = data.loop_region(loop_start..);
+                }
+                if let Some(tween) = settings.fade_in {
+                    data = data.fade_in_tween(tween);
+                }
+
+                track
+                    .play_sound(data)
+                    .map(SoundHandle::Streaming)
+                    .map_err(|err| err.to_string())
+            }
+        }
+    }
+
+    /// Decode the audio file at `path` with `mode`
+    fn decode_with_mode(bytes: Vec<u8>, path: &Path, mode: AudioLoadMode) -> Result<Self, String> {
+        if mode.is_streaming(path) {
+            return StreamingAudioSource::new(bytes)
+                .map(AudioSource::streaming)
+                .map_err(|err| format!("Failed to load sound from '{:?}': {}", path, err));
+        }
+
+        StaticSoundData::from_cursor(Cursor::new(bytes))
+            .map(AudioSource::new)
+            .map_err(|err| format!("Failed to load sound from '{:?}': {}", path, err))
     }
 }
 
@@ -91,16 +229,30 @@ pub struct AudioPlugin;
 
 impl Plugin for AudioPlugin {
     fn build(&self, app: &mut App) {
+        app.register_event::<AudioPlaybackEvent>();
+
         let settings = AudioManagerSettings::default();
-        let mut audio_manager = AudioManager::new(settings).expect("Failed to create AudioManager");
+        // without an output device the app still runs, sounds can be loaded but not played
+        let mut audio_manager = match AudioManager::new(settings) {
+            Ok(manager) => manager,
+            Err(err) => {
+                tracing::warn!("Failed to create AudioManager, audio is disabled: {err}");
+                app.init_resource::<Assets<AudioSource>>();
+                return;
+            }
+        };
 
         let sub_track = audio_manager
             .add_sub_track(TrackBuilder::new())
             .expect("Failed to create main sub track");
         let main_track = AudioTrack::<MainTrack>::new(sub_track);
+        let music_track = audio_manager
+            .add_sub_track(TrackBuilder::new())
+            .expect("Failed to create music sub track");
 
         app.set_resource(audio_manager)
             .set_resource(main_track)
+            .set_resource(MusicController::new(music_track))
             .init_resource::<Assets<AudioSource>>()
             // TODO: it has to be in Last stage since thats when GlobalTransform gets updated, once
             // Changed<C> works with a frame delay, it can be moved to the update stage. For now
@@ -108,15 +260,40 @@ impl Plugin for AudioPlugin {
             .register_system(update_spatial_listeners, phase::Last)
             .register_system(update_audio_tracks, phase::Last)
             .register_system(update_spatial_audio_tracks, phase::Last)
-            .register_system(cleanup_audio_tracks, phase::Last);
+            .register_system(update_audio_occlusion, phase::Last)
+            .register_system(pause_disabled_spatial_audio_tracks, phase::Last)
+            .register_system(cleanup_audio_tracks, phase::Last)
+            .register_system(update_music, phase::Last);
     }
 }
 
 impl LoadableAsset for AudioSource {
-    fn load<P: AsRef<Path> + Debug>(_: &mut AssetLoader, _: &mut Resources, path: P) -> Self {
-        match StaticSoundData::from_file(path.as_ref()) {
-            Ok(sound_data) => AudioSource::new(sound_data),
-            Err(err) => panic!("Failed to load sound from '{:?}': {}", path, err),
-        }
+    fn load<P: AsRef<Path> + Debug>(
+        loader: &mut AssetLoader,
+        resources: &mut Resources,
+        path: P,
+    ) -> Self {
+        Self::load_with_settings(loader, resources, path, AudioLoadMode::Auto)
+    }
+}
+
+impl LoadableAssetWithSettings for AudioSource {
+    type Settings = AudioLoadMode;
+
+    fn load_with_settings<P: AsRef<Path> + Debug>(
+        _: &mut AssetLoader,
+        _: &mut Resources,
+        path: P,
+        mode: AudioLoadMode,
+    ) -> Self {
+        let bytes = crate::assets::io::read(path.as_ref())
+            .unwrap_or_else(|err| panic!("Failed to read sound from '{:?}': {}", path, err));
+        Self::decode_with_mode(bytes, path.as_ref(), mode).unwrap_or_else(|err| panic!("{}", err))
+    }
+}
+
+impl AsyncLoadableAsset for AudioSource {
+    fn decode(bytes: Vec<u8>, path: &Path) -> Result<Self, String> {
+        Self::decode_with_mode(bytes, path, AudioLoadMode::Auto)
     }
 }
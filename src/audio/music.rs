@@ -0,0 +1,296 @@
+use std::{collections::VecDeque, time::Duration};
+
+use kira::{Tween, sound::PlaybackState, track::TrackHandle};
+
+use super::{
+    AudioSource,
+    sound::{SoundHandle, SoundSettings},
+};
+use crate::prelude::*;
+
+/// Default [`MusicController::crossfade`]
+pub const DEFAULT_CROSSFADE: Duration = Duration::from_secs(2);
+
+/// Music played by the [`MusicController`]
+#[derive(Debug, Clone)]
+pub struct Music {
+    source: Handle<AudioSource>,
+    /// Position in seconds the music jumps back to when it reaches the end, or None to play once
+    loop_start: Option<f64>,
+    /// Volume in decibels
+    volume: f32,
+}
+
+impl Music {
+    /// Create new music which plays `source` once
+    pub fn new(source: Handle<AudioSource>) -> Self {
+        Self {
+            source,
+            loop_start: None,
+            volume: 0.0,
+        }
+    }
+
+    /// Returns self looping the whole music
+    #[must_use]
+    pub fn looped(mut self) -> Self {
+        self.loop_start = Some(0.0);
+        self
+    }
+
+    /// Returns self with an intro of `intro` seconds, which plays once before the rest of the
+    /// music loops
+    #[must_use]
+    pub fn with_intro(mut self, intro: f64) -> Self {
+        self.loop_start = Some(intro);
+        self
+    }
+
+    /// Returns self with volume in decibels
+    #[must_use]
+    pub fn with_volume(mut self, volume: f32) -> Self {
+        self.volume = volume;
+        self
+    }
+
+    /// Returns the audio source of the music
+    #[inline]
+    pub fn source(&self) -> &Handle<AudioSource> {
+        &self.source
+    }
+
+    /// Returns true if the music loops, it never ends on its own
+    #[inline]
+    pub fn is_looped(&self) -> bool {
+        self.loop_start.is_some()
+    }
+}
+
+/// Music which is currently playing
+struct PlayingMusic {
+    music: Music,
+    sound: SoundHandle,
+    /// Duration of the source in seconds
+    duration: f64,
+}
+
+enum MusicCommand {
+    Play(Music),
+    Skip,
+    Stop,
+}
+
+/// Background music player with a queue of [`Music`], which crossfades between them. Music plays
+/// on its own sub track, so its volume can be changed separately from other sounds. Used as a
+/// resource, added by the [`AudioPlugin`](super::AudioPlugin).
+///
+/// When music which isn't looped ends, the next one in the queue starts. Looped music plays until
+/// [`Self::skip`], [`Self::play`] or [`Self::stop`] is called.
+///
+/// Music is usually long, so it's best [streamed](super::prelude::StreamingAudioSource) instead of
+/// decoded at once, which `.ogg` and `.flac` files are by default.
+///
+/// ```ignore
+/// fn start_music(mut music: ResMut<MusicController>, mut loader: ResMut<AssetLoader>, ...) {
+///     let battle = loader.load::<AudioSource>("assets/music/battle.ogg", resources);
+///     let calm = loader.load::<AudioSource>("assets/music/calm.ogg", resources);
+///
+///     music.play(Music::new(battle).with_intro(12.5));
+///     music.queue(Music::new(calm));
+/// }
+/// ```
+#[derive(Resource)]
+pub struct MusicController {
+    track: TrackHandle,
+    crossfade: Duration,
+    /// Re-queue music after it stops playing
+    repeat: bool,
+    queue: VecDeque<Music>,
+    current: Option<PlayingMusic>,
+    /// Stopped music which is still fading out
+    fading: Vec<SoundHandle>,
+    commands: VecDeque<MusicCommand>,
+}
+
+impl MusicController {
+    pub(crate) fn new(track: TrackHandle) -> Self {
+        Self {
+            track,
+            crossfade: DEFAULT_CROSSFADE,
+            repeat: false,
+            queue: VecDeque::new(),
+            current: None,
+            fading: Vec::new(),
+            commands: VecDeque::new(),
+        }
+    }
+
+    /// Crossfade to `music` now, the queue is kept
+    pub fn play(&mut self, music: Music) {
+        self.commands.push_back(MusicCommand::Play(music));
+    }
+
+    /// Add `music` to the end of the queue
+    pub fn queue(&mut self, music: Music) {
+        self.queue.push_back(music);
+    }
+
+    /// Crossfade to the next music in the queue, or fade out if it's empty
+    pub fn skip(&mut self) {
+        self.commands.push_back(MusicCommand::Skip);
+    }
+
+    /// Fade out the current music and clear the queue
+    pub fn stop(&mut self) {
+        self.commands.push_back(MusicCommand::Stop);
+    }
+
+    /// Remove all queued music, the current music keeps playing
+    pub fn clear_queue(&mut self) {
+        self.queue.clear();
+    }
+
+    /// Returns the queued music, in the order it will play
+    pub fn queued(&self) -> impl Iterator<Item = &Music> {
+        self.queue.iter()
+    }
+
+    /// Returns the music which is currently playing
+    pub fn current(&self) -> Option<&Music> {
+        self.current.as_ref().map(|current| &current.music)
+    }
+
+    /// Returns true if music is playing
+    #[inline]
+    pub fn is_playing(&self) -> bool {
+        self.current.is_some()
+    }
+
+    /// Set the duration of crossfades, zero switches music immediately
+    pub fn set_crossfade(&mut self, crossfade: Duration) {
+        self.crossfade = crossfade;
+    }
+
+    /// Returns the duration of crossfades
+    #[inline]
+    pub fn crossfade(&self) -> Duration {
+        self.crossfade
+    }
+
+    /// Set whether music is queued again after it stops playing, so the queue acts as a
+    /// repeating playlist
+    pub fn set_repeat(&mut self, repeat: bool) {
+        self.repeat = repeat;
+    }
+
+    /// Returns true if music is queued again after it stops playing
+    #[inline]
+    pub fn repeat(&self) -> bool {
+        self.repeat
+    }
+
+    /// Fade the volume of all music to `volume` in decibels over `duration`
+    pub fn set_volume(&mut self, volume: f32, duration: Duration) {
+        self.track.set_volume(volume, Self::tween(duration));
+    }
+
+    /// Fade out and pause the music over `duration`
+    pub fn pause(&mut self, duration: Duration) {
+        self.track.pause(Self::tween(duration));
+    }
+
+    /// Resume the music and fade it in over `duration`
+    pub fn resume(&mut self, duration: Duration) {
+        self.track.resume(Self::tween(duration));
+    }
+
+    fn tween(duration: Duration) -> Tween {
+        Tween {
+            duration,
+            ..Default::default()
+        }
+    }
+
+    /// Apply queued commands and start the next music when the current one ends
+    fn update(&mut self, sources: &Assets<AudioSource>) {
+        self.fading
+            .retain(|sound| sound.state() != PlaybackState::Stopped);
+
+        while let Some(command) = self.commands.pop_front() {
+            match command {
+                MusicCommand::Play(music) => self.crossfade_to(Some(music), sources),
+                MusicCommand::Skip => {
+                    let next = self.queue.pop_front();
+                    self.crossfade_to(next, sources);
+                }
+                MusicCommand::Stop => {
+                    self.crossfade_to(None, sources);
+                    self.queue.clear();
+                }
+            }
+        }
+
+        let Some(current) = &self.current else {
+            if let Some(next) = self.queue.pop_front() {
+                self.crossfade_to(Some(next), sources);
+            }
+            return;
+        };
+
+        // start the next music a crossfade before the end, so they overlap
+        let has_next = !self.queue.is_empty() || self.repeat;
+        let ending = !current.music.is_looped()
+            && current.sound.position() >= current.duration - self.crossfade.as_secs_f64();
+
+        if current.sound.state() == PlaybackState::Stopped || (ending && has_next) {
+            let next = self.queue.pop_front();
+            self.crossfade_to(next, sources);
+        }
+    }
+
+    /// Fade out the current music and fade in `music`
+    fn crossfade_to(&mut self, music: Option<Music>, sources: &Assets<AudioSource>) {
+        let tween = Self::tween(self.crossfade);
+
+        let crossfading = self.current.is_some();
+        if let Some(mut current) = self.current.take() {
+            current.sound.stop(tween);
+            self.fading.push(current.sound);
+
+            if self.repeat {
+                self.queue.push_back(current.music);
+            }
+        }
+
+        let Some(music) = music else {
+            return;
+        };
+        let Some(source) = sources.get(&music.source) else {
+            tracing::warn!("Music source {:?} is not loaded, skipping it", music.source);
+            return;
+        };
+
+        let settings = SoundSettings {
+            volume: music.volume,
+            loop_start: music.loop_start,
+            fade_in: (crossfading && !self.crossfade.is_zero()).then_some(tween),
+        };
+
+        let duration = source.duration().as_secs_f64();
+        match source.play(&mut self.track, settings) {
+            Ok(sound) => {
+                self.current = Some(PlayingMusic {
+                    music,
+                    sound,
+                    duration,
+                })
+            }
+            Err(err) => tracing::error!("Failed to play music: {err}"),
+        }
+    }
+}
+
+/// System which applies [`MusicController`] commands and advances its queue
+pub(crate) fn update_music(mut music: ResMut<MusicController>, sources: Res<Assets<AudioSource>>) {
+    music.update(&sources);
+}
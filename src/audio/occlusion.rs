@@ -0,0 +1,158 @@
+use kira::{
+    Tween,
+    effect::{
+        filter::{FilterBuilder, FilterHandle},
+        volume_control::{VolumeControlBuilder, VolumeControlHandle},
+    },
+    track::SpatialTrackBuilder,
+};
+
+use crate::{math::bounding_volume::WorldBoundingVolume, prelude::*};
+
+use super::AudioTrack;
+
+/// Low-pass cutoff frequency in Hz of unoccluded sounds, above the audible range
+const OPEN_CUTOFF: f32 = 20_000.0;
+
+/// Component which muffles the sounds of a [`SpatialEmitter`] while the line between it and the
+/// [`SpatialListener`] is blocked by an [`AudioOccluder`]. Blocked sounds are turned down and
+/// low-pass filtered, the transition is smoothed so sounds don't cut in and out.
+///
+/// Occluders are tested with their [`WorldBoundingVolume`], which is managed by the
+/// [`FrustumCullingPlugin`](crate::renderer::culling::FrustumCullingPlugin).
+///
+/// ```ignore
+/// world.spawn((emitter, AudioOcclusion::default().with_cutoff(500.0), transform));
+/// world.spawn((wall_mesh, material, AudioOccluder, transform));
+/// ```
+#[derive(Component, Debug, Clone)]
+pub struct AudioOcclusion {
+    /// Volume change in decibels when fully occluded
+    pub volume: f32,
+    /// Low-pass cutoff frequency in Hz when fully occluded
+    pub cutoff: f32,
+    /// Rate of the transition, see [`exp_decay`]
+    pub smoothing: f32,
+    /// Current occlusion, from 0 to 1
+    amount: f32,
+}
+
+impl Default for AudioOcclusion {
+    fn default() -> Self {
+        Self {
+            volume: -12.0,
+            cutoff: 800.0,
+            smoothing: 8.0,
+            amount: 0.0,
+        }
+    }
+}
+
+impl AudioOcclusion {
+    /// Returns self with the volume change in decibels when fully occluded
+    #[must_use]
+    pub fn with_volume(mut self, volume: f32) -> Self {
+        self.volume = volume;
+        self
+    }
+
+    /// Returns self with the low-pass cutoff frequency in Hz when fully occluded
+    #[must_use]
+    pub fn with_cutoff(mut self, cutoff: f32) -> Self {
+        self.cutoff = cutoff;
+        self
+    }
+
+    /// Returns self with the rate of the transition, higher is faster
+    #[must_use]
+    pub fn with_smoothing(mut self, smoothing: f32) -> Self {
+        self.smoothing = smoothing;
+        self
+    }
+
+    /// Returns the current occlusion, from 0 (unblocked) to 1 (fully blocked)
+    #[inline]
+    pub fn amount(&self) -> f32 {
+        self.amount
+    }
+}
+
+/// Marker component for entities which block sounds of emitters with [`AudioOcclusion`]
+#[derive(Component, Default, Debug, Clone, Copy)]
+pub struct AudioOccluder;
+
+/// Effects of a spatial track used for occlusion
+#[derive(Debug)]
+pub(crate) struct OcclusionEffects {
+    filter: FilterHandle,
+    volume: VolumeControlHandle,
+    /// Last applied occlusion amount
+    amount: f32,
+}
+
+impl OcclusionEffects {
+    /// Add the effects to `builder`, they don't change the sound until occlusion is applied
+    pub fn new(builder: &mut SpatialTrackBuilder) -> Self {
+        Self {
+            filter: builder.add_effect(FilterBuilder::new().cutoff(OPEN_CUTOFF as f64)),
+            volume: builder.add_effect(VolumeControlBuilder::new(0.0)),
+            amount: 0.0,
+        }
+    }
+
+    /// Update the effects to the current amount of `occlusion`
+    fn apply(&mut self, occlusion: &AudioOcclusion) {
+        if (self.amount - occlusion.amount).abs() < 1e-3 {
+            return;
+        }
+        self.amount = occlusion.amount;
+
+        // interpolate the cutoff logarithmically, pitch is perceived on a log scale
+        let cutoff = OPEN_CUTOFF.powf(1.0 - self.amount) * occlusion.cutoff.powf(self.amount);
+        self.filter.set_cutoff(cutoff as f64, Tween::default());
+        self.volume
+            .set_volume(occlusion.volume * self.amount, Tween::default());
+    }
+}
+
+/// System which casts rays from the [`SpatialListener`] to emitters with [`AudioOcclusion`], and
+/// muffles the sounds of emitters blocked by an [`AudioOccluder`]
+pub(crate) fn update_audio_occlusion(
+    time: Res<Time>,
+    mut audio: ResMut<AudioTrack>,
+    mut listener_query: Query<(EntityId, &GlobalTransform), With<SpatialListener>>,
+    mut emitter_query: Query<
+        (EntityId, &GlobalTransform, &mut AudioOcclusion),
+        With<SpatialEmitter>,
+    >,
+    mut occluder_query: Query<(EntityId, &WorldBoundingVolume), With<AudioOccluder>>,
+) {
+    let listeners = listener_query.iter_mut();
+    let Some((listener_id, listener_transform)) = listeners.first() else {
+        return;
+    };
+    let listener_position = listener_transform.translation();
+    let occluders = occluder_query.iter_mut();
+
+    for (id, transform, occlusion) in emitter_query.iter_mut() {
+        let offset = transform.translation() - listener_position;
+        let distance = offset.length();
+
+        let ray = Ray::new(listener_position, offset);
+        let blocked = distance > f32::EPSILON
+            && occluders.iter().any(|(occluder_id, volume)| {
+                *occluder_id != id
+                    && occluder_id != listener_id
+                    && ray
+                        .intersect_bounding_volume(volume)
+                        .is_some_and(|hit| hit < distance)
+            });
+
+        let target = if blocked { 1.0 } else { 0.0 };
+        occlusion.amount = exp_decay(occlusion.amount, target, occlusion.smoothing, time.delta());
+
+        if let Some(spatial_track) = audio.spatial_tracks.get_mut(&id) {
+            spatial_track.occlusion.apply(occlusion);
+        }
+    }
+}
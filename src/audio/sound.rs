@@ -1,42 +1,216 @@
 use std::collections::VecDeque;
 
-use kira::sound::static_sound::StaticSoundHandle;
+use kira::{
+    Tween,
+    sound::{
+        FromFileError, IntoOptionalRegion, PlaySoundError, SoundData,
+        static_sound::StaticSoundHandle, streaming::StreamingSoundHandle,
+    },
+    track::{SpatialTrackHandle, TrackHandle},
+};
 
-use super::commands::AudioCommand;
+use super::{AudioSource, commands::AudioCommand};
+use crate::prelude::*;
 
 /// A sound which may or may not be currently playing
-pub(crate) struct Sound(pub(crate) StaticSoundHandle);
+pub(crate) struct Sound {
+    pub(crate) handle: SoundHandle,
+    /// Source the sound was played from, reported in its [`AudioPlaybackEvent`]s
+    source: Handle<AudioSource>,
+    /// Whether the [`AudioPlaybackEvent::Started`] event was written
+    started: bool,
+    /// Playback position in seconds at the last update, used to detect loops
+    position: f64,
+}
 
 pub type PlaybackState = kira::sound::PlaybackState;
 
+/// Handle of a playing static or streaming sound
+pub(crate) enum SoundHandle {
+    Static(StaticSoundHandle),
+    Streaming(StreamingSoundHandle<FromFileError>),
+}
+
+/// Calls the same method on the handle of either kind of sound
+macro_rules! with_handle {
+    ($sound:expr, $handle:ident => $body:expr) => {
+        match $sound {
+            SoundHandle::Static($handle) => $body,
+            SoundHandle::Streaming($handle) => $body,
+        }
+    };
+}
+
+impl SoundHandle {
+    pub fn state(&self) -> PlaybackState {
+        with_handle!(self, handle => handle.state())
+    }
+
+    /// Returns the playback position in seconds
+    pub fn position(&self) -> f64 {
+        with_handle!(self, handle => handle.position())
+    }
+
+    pub fn pause(&mut self, tween: Tween) {
+        with_handle!(self, handle => handle.pause(tween))
+    }
+
+    pub fn resume(&mut self, tween: Tween) {
+        with_handle!(self, handle => handle.resume(tween))
+    }
+
+    pub fn stop(&mut self, tween: Tween) {
+        with_handle!(self, handle => handle.stop(tween))
+    }
+
+    pub fn set_volume(&mut self, volume: f32, tween: Tween) {
+        with_handle!(self, handle => handle.set_volume(volume, tween))
+    }
+
+    pub fn set_panning(&mut self, panning: f32, tween: Tween) {
+        with_handle!(self, handle => handle.set_panning(panning, tween))
+    }
+
+    pub fn set_playback_rate(&mut self, rate: f64, tween: Tween) {
+        with_handle!(self, handle => handle.set_playback_rate(rate, tween))
+    }
+
+    pub fn set_loop_region(&mut self, region: impl IntoOptionalRegion) {
+        with_handle!(self, handle => handle.set_loop_region(region))
+    }
+}
+
+/// Track which sounds can be played on, either a regular or a spatial one
+pub(crate) trait SoundTrack {
+    fn play_sound<D: SoundData>(&mut self, data: D) -> Result<D::Handle, PlaySoundError<D::Error>>;
+}
+
+impl SoundTrack for TrackHandle {
+    fn play_sound<D: SoundData>(&mut self, data: D) -> Result<D::Handle, PlaySoundError<D::Error>> {
+        self.play(data)
+    }
+}
+
+impl SoundTrack for SpatialTrackHandle {
+    fn play_sound<D: SoundData>(&mut self, data: D) -> Result<D::Handle, PlaySoundError<D::Error>> {
+        self.play(data)
+    }
+}
+
+/// Settings applied to sound data before it's played
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct SoundSettings {
+    /// Volume in decibels
+    pub volume: f32,
+    /// Position in seconds the sound jumps back to when it reaches the end
+    pub loop_start: Option<f64>,
+    pub fade_in: Option<Tween>,
+}
+
+/// Sound reported by an [`AudioPlaybackEvent`]
+#[derive(Debug, Clone)]
+pub struct AudioPlayback {
+    /// Source the sound was played from
+    pub source: Handle<AudioSource>,
+    /// Entity of the [`SpatialEmitter`] which played the sound, None for sounds played on the
+    /// [`AudioTrack`]
+    pub emitter: Option<EntityId>,
+}
+
+/// Event written when a sound starts, finishes or loops, so sounds can be chained or emitters
+/// despawned without polling the [`PlaybackState`]. Events are written in the
+/// [`Last`](phase::Last) phase, a sound is reported as started the frame after it was played.
+#[derive(Event, Debug, Clone)]
+pub enum AudioPlaybackEvent {
+    /// The sound started playing
+    Started(AudioPlayback),
+    /// The sound finished playing or was stopped, it's removed from its track
+    Finished(AudioPlayback),
+    /// The sound jumped back to the start of its loop region
+    Looped(AudioPlayback),
+}
+
+impl AudioPlaybackEvent {
+    /// Returns the sound the event is about
+    pub fn playback(&self) -> &AudioPlayback {
+        match self {
+            Self::Started(playback) | Self::Finished(playback) | Self::Looped(playback) => playback,
+        }
+    }
+}
+
 impl Sound {
-    pub fn new(handle: StaticSoundHandle, commands: VecDeque<AudioCommand>) -> Self {
-        let mut sound = Self(handle);
-        commands.into_iter().for_each(|command| sound.apply(command));
+    pub fn new(
+        handle: SoundHandle,
+        source: Handle<AudioSource>,
+        commands: VecDeque<AudioCommand>,
+    ) -> Self {
+        let mut sound = Self {
+            handle,
+            source,
+            started: false,
+            position: 0.0,
+        };
+        commands
+            .into_iter()
+            .for_each(|command| sound.apply(command));
         sound
     }
 
     /// Returns the current playback state of the sound
     pub fn state(&self) -> PlaybackState {
-        self.0.state()
+        self.handle.state()
     }
 
     /// Wheter the sound has finished playing, or has been stopped
     pub fn is_stopped(&self) -> bool {
-        self.0.state() == PlaybackState::Stopped
+        self.handle.state() == PlaybackState::Stopped
+    }
+
+    /// Write the playback events since the last update, returns false if the sound has stopped
+    /// and should be removed
+    pub(crate) fn update(
+        &mut self,
+        emitter: Option<EntityId>,
+        events: &mut EventWriter<AudioPlaybackEvent>,
+    ) -> bool {
+        let playback = || AudioPlayback {
+            source: self.source.clone(),
+            emitter,
+        };
+
+        if !self.started {
+            self.started = true;
+            events.write(AudioPlaybackEvent::Started(playback()));
+        }
+
+        if self.is_stopped() {
+            events.write(AudioPlaybackEvent::Finished(playback()));
+            return false;
+        }
+
+        // the position only goes back when the sound loops, there is no seeking
+        let position = self.handle.position();
+        if position < self.position {
+            events.write(AudioPlaybackEvent::Looped(playback()));
+        }
+        self.position = position;
+
+        true
     }
 
     /// Apply a command to the sound
     pub(crate) fn apply(&mut self, command: AudioCommand) {
+        let handle = &mut self.handle;
         match command {
             AudioCommand::Play(..) => panic!("Play command is not valid for a sound"),
-            AudioCommand::Pause(tween) => self.0.pause(tween),
-            AudioCommand::Resume(tween) => self.0.resume(tween),
-            AudioCommand::Stop(tween) => self.0.stop(tween),
-            AudioCommand::SetVolume(volume, tween) => self.0.set_volume(volume, tween),
-            AudioCommand::SetPanning(panning, tween) => self.0.set_panning(panning, tween),
-            AudioCommand::SetPlaybackRate(rate, tween) => self.0.set_playback_rate(rate, tween),
-            AudioCommand::SetLoopRegion(region) => self.0.set_loop_region(region),
+            AudioCommand::Pause(tween) => handle.pause(tween),
+            AudioCommand::Resume(tween) => handle.resume(tween),
+            AudioCommand::Stop(tween) => handle.stop(tween),
+            AudioCommand::SetVolume(volume, tween) => handle.set_volume(volume, tween),
+            AudioCommand::SetPanning(panning, tween) => handle.set_panning(panning, tween),
+            AudioCommand::SetPlaybackRate(rate, tween) => handle.set_playback_rate(rate, tween),
+            AudioCommand::SetLoopRegion(region) => handle.set_loop_region(region),
         }
     }
 }
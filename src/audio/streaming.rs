@@ -0,0 +1,86 @@
+use std::{io::Cursor, path::Path, sync::Arc, time::Duration};
+
+use kira::sound::{FromFileError, streaming::StreamingSoundData};
+
+/// Extensions of files which an [`AudioSource`](super::AudioSource) streams when loaded with
+/// [`AudioLoadMode::Auto`], usually long music tracks. Other files are decoded at once.
+pub const STREAMING_EXTENSIONS: &[&str] = &["ogg", "flac"];
+
+/// How an [`AudioSource`](super::AudioSource) is loaded, passed to
+/// [`AssetLoader::load_with_settings`](crate::prelude::AssetLoader::load_with_settings)
+///
+/// ```ignore
+/// let music = loader.load_with_settings::<AudioSource>(
+///     "assets/music/theme.mp3",
+///     AudioLoadMode::Streaming,
+///     resources,
+/// );
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AudioLoadMode {
+    /// Stream files with one of the [`STREAMING_EXTENSIONS`], decode the rest at once
+    #[default]
+    Auto,
+    /// Decode the whole file when it's loaded, best for short sounds which play often
+    Static,
+    /// Decode the file while it's playing, see [`StreamingAudioSource`]
+    Streaming,
+}
+
+impl AudioLoadMode {
+    /// Returns true if the file at `path` should be streamed
+    pub fn is_streaming(self, path: &Path) -> bool {
+        match self {
+            Self::Auto => path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| {
+                    STREAMING_EXTENSIONS
+                        .iter()
+                        .any(|streamed| streamed.eq_ignore_ascii_case(extension))
+                }),
+            Self::Static => false,
+            Self::Streaming => true,
+        }
+    }
+}
+
+/// Audio which is decoded while it's playing instead of at once. Only the encoded file is kept in
+/// memory, so long music tracks don't take up hundreds of megabytes. Every playing sound decodes
+/// the file on its own, which costs more than playing a static sound, so it's meant for sounds
+/// which play rarely.
+#[derive(Clone)]
+pub struct StreamingAudioSource {
+    bytes: Arc<[u8]>,
+    duration: Duration,
+}
+
+impl StreamingAudioSource {
+    /// Creates a new streaming source from the bytes of an encoded audio file, returns an error
+    /// if the file can't be decoded
+    pub fn new(bytes: impl Into<Arc<[u8]>>) -> Result<Self, FromFileError> {
+        let bytes = bytes.into();
+        let duration = StreamingSoundData::from_cursor(Cursor::new(bytes.clone()))?.duration();
+        Ok(Self { bytes, duration })
+    }
+
+    /// Returns the duration of the audio
+    #[inline]
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Creates new sound data for a single playback, each sound needs its own decoder
+    pub(crate) fn sound_data(&self) -> Result<StreamingSoundData<FromFileError>, FromFileError> {
+        StreamingSoundData::from_cursor(Cursor::new(self.bytes.clone()))
+    }
+}
+
+impl std::fmt::Debug for StreamingAudioSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StreamingAudioSource")
+            .field("bytes", &self.bytes.len())
+            .field("duration", &self.duration)
+            .finish()
+    }
+}
@@ -8,7 +8,12 @@ use kira::{
     track::{SpatialTrackHandle, TrackHandle},
 };
 
-use super::{AudioSource, PlayCommand, TweenCommand, commands::AudioCommand, sound::Sound};
+use super::{
+    AudioSource, PlayCommand, TweenCommand,
+    commands::AudioCommand,
+    occlusion::OcclusionEffects,
+    sound::{Sound, SoundSettings},
+};
 use crate::prelude::*;
 
 /// Marker for the main [`audio track`](AudioTrack)
@@ -20,6 +25,9 @@ pub struct MainTrack;
 pub(crate) struct SpatialAudioTrack {
     pub(crate) sounds: Vec<Sound>,
     pub(crate) track: SpatialTrackHandle,
+    /// True if the track is paused because its emitter is [`Disabled`]
+    pub(crate) disabled: bool,
+    pub(crate) occlusion: OcclusionEffects,
 }
 
 /// An audio track that can play multiple sounds, you can create multiple tracks. To use the
@@ -53,12 +61,12 @@ impl<R: Resource> AudioTrack<R> {
                         .get(&handle)
                         .expect("Failed to get sound data from assets");
 
-                    let sound = match self.track.play(sound_data.source.clone()) {
+                    let sound = match sound_data.play(&mut self.track, SoundSettings::default()) {
                         Ok(sound) => sound,
                         Err(err) => panic!("Failed to play sound: {}", err),
                     };
 
-                    let sound = Sound::new(sound, commands);
+                    let sound = Sound::new(sound, handle, commands);
                     self.sounds.push(sound);
                 }
 
@@ -144,10 +152,12 @@ impl<R: Resource> AudioTrack<R> {
 }
 
 impl SpatialAudioTrack {
-    pub fn new(track: SpatialTrackHandle) -> Self {
+    pub fn new(track: SpatialTrackHandle, occlusion: OcclusionEffects) -> Self {
         Self {
             track,
             sounds: Vec::new(),
+            disabled: false,
+            occlusion,
         }
     }
 
@@ -164,12 +174,12 @@ impl SpatialAudioTrack {
                         .get(&handle)
                         .expect("Failed to get sound data from assets");
 
-                    let sound = match self.track.play(sound_data.source.clone()) {
+                    let sound = match sound_data.play(&mut self.track, SoundSettings::default()) {
                         Ok(sound) => sound,
                         Err(err) => panic!("Failed to play sound: {}", err),
                     };
 
-                    let sound = Sound::new(sound, commands);
+                    let sound = Sound::new(sound, handle, commands);
                     self.sounds.push(sound);
                 }
 
@@ -2,7 +2,9 @@ use kira::{Tween, track::SpatialTrackBuilder};
 
 use crate::prelude::*;
 
-use super::{AudioManager, track::SpatialAudioTrack};
+use super::{
+    AudioManager, occlusion::OcclusionEffects, sound::AudioPlaybackEvent, track::SpatialAudioTrack,
+};
 
 /// System that updates or initializes the [`spatial listener`](SpatialListener)'s position and orientation.
 pub(crate) fn update_spatial_listeners(
@@ -29,13 +31,25 @@ pub(crate) fn update_spatial_listeners(
     }
 }
 
-/// System which applies all queued audio track commands and updates the audio tracks
+/// System which applies all queued audio track commands and updates the audio tracks. It writes
+/// the [`AudioPlaybackEvent`]s of all sounds, including the ones of spatial tracks, and removes
+/// the sounds which have stopped playing.
 pub(crate) fn update_audio_tracks(
     mut audio: ResMut<AudioTrack>,
     sources: Res<Assets<AudioSource>>,
+    mut events: EventWriter<AudioPlaybackEvent>,
 ) {
     // TODO: currently only the main track is supported
     audio.apply(&sources);
+
+    audio
+        .sounds
+        .retain_mut(|sound| sound.update(None, &mut events));
+    for (id, track) in audio.spatial_tracks.iter_mut() {
+        track
+            .sounds
+            .retain_mut(|sound| sound.update(Some(*id), &mut events));
+    }
 }
 
 /// System that updates or creates a spatial audio track for an [`emitter`](SpatialEmitter).
@@ -87,38 +101,61 @@ pub(crate) fn update_spatial_audio_tracks(
         }
 
         // Create spatial track
-        let builder = SpatialTrackBuilder::new();
+        let mut builder = SpatialTrackBuilder::new();
+        let occlusion = OcclusionEffects::new(&mut builder);
         let track_handle = audio
             .track
             .add_spatial_sub_track(listener_id, transform.translation(), builder)
             .expect("Failed to add spatial sub track");
 
-        let mut spatial_track = SpatialAudioTrack::new(track_handle);
+        let mut spatial_track = SpatialAudioTrack::new(track_handle, occlusion);
         spatial_track.apply(&sources, &mut emitter.commands);
 
         audio.spatial_tracks.insert(id, spatial_track);
     }
 }
 
-/// Removes all sounds that have stopped playing, and or all spatial audio tracks that have no
-/// sounds playing.
+/// Pauses spatial audio tracks of [`Disabled`] emitters, and resumes them once re-enabled.
+pub(crate) fn pause_disabled_spatial_audio_tracks(
+    mut audio: ResMut<AudioTrack>,
+    mut emitter_query: Query<(EntityId, Option<&Disabled>), With<SpatialEmitter>>,
+) {
+    for (id, disabled) in emitter_query.iter_mut() {
+        let Some(spatial_track) = audio.spatial_tracks.get_mut(&id) else {
+            continue;
+        };
+
+        let disabled = disabled.is_some();
+        if spatial_track.disabled == disabled {
+            continue;
+        }
+
+        if disabled {
+            spatial_track.track.pause(Tween::default());
+        } else {
+            spatial_track.track.resume(Tween::default());
+        }
+        spatial_track.disabled = disabled;
+    }
+}
+
+/// Removes all spatial audio tracks that have no sounds playing, or whose emitter was removed.
+/// Stopped sounds are removed by [`update_audio_tracks`], so their
+/// [`Finished`](AudioPlaybackEvent::Finished) event is written first.
+///
+/// Removed emitters are found by looking them up, so no removal detection is needed. Sounds of a
+/// removed emitter are dropped without a finished event.
 pub(crate) fn cleanup_audio_tracks(
     // TODO: currently only the main track is supported
     mut audio: ResMut<AudioTrack>,
-    mut check_emitter_query: Query<&SpatialEmitter>,
+    mut check_emitter_query: Query<EntityId, With<SpatialEmitter>>,
 ) {
-    // Remove stopped sounds from audio track
-    audio.sounds.retain(|sound| !sound.is_stopped());
-
-    // Remove spatial tracks with all sounds stopped
     audio.spatial_tracks.retain(|id, track| {
         // Remove spatial track if emitter component was removed, or entity despawned
         if check_emitter_query.get(*id).is_none() {
             return false;
         }
 
-        track.sounds.retain(|sound| !sound.is_stopped());
-        if track.sounds.is_empty() {}
         !track.sounds.is_empty()
     });
 }
@@ -0,0 +1,176 @@
+use glam::{EulerRot, Quat, Vec2, Vec3};
+
+use crate::{event::EventReader, input::InputPlugin, prelude::*};
+
+use super::smoothing_factor;
+
+/// Plugin which moves cameras with a [`FlyCamera`] component. For more information, see the
+/// [camera controller module](crate::camera_controller).
+pub struct FlyCameraPlugin;
+
+impl Plugin for FlyCameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(fly_camera_system);
+    }
+
+    fn dependencies(&self) -> Vec<PluginId> {
+        vec![PluginId::of::<InputPlugin>()]
+    }
+}
+
+/// Keys used to control a [`FlyCamera`]
+#[derive(Debug, Clone)]
+pub struct FlyCameraKeys {
+    pub forward: KeyCode,
+    pub backward: KeyCode,
+    pub left: KeyCode,
+    pub right: KeyCode,
+    pub up: KeyCode,
+    pub down: KeyCode,
+    /// Multiplies the speed by [`FlyCamera::boost`] while held
+    pub boost: KeyCode,
+    pub roll_left: KeyCode,
+    pub roll_right: KeyCode,
+}
+
+impl Default for FlyCameraKeys {
+    fn default() -> Self {
+        Self {
+            forward: KeyCode::KeyW,
+            backward: KeyCode::KeyS,
+            left: KeyCode::KeyA,
+            right: KeyCode::KeyD,
+            up: KeyCode::Space,
+            down: KeyCode::ShiftLeft,
+            boost: KeyCode::ControlLeft,
+            roll_left: KeyCode::KeyQ,
+            roll_right: KeyCode::KeyE,
+        }
+    }
+}
+
+/// Free flying camera controller, moved with the keyboard and rotated with the mouse.
+///
+/// Without [`roll`](Self::roll) the camera stays upright, it turns around the global `Y` axis,
+/// can't look further than straight up or down, and moves vertically along the global `Y` axis.
+/// With roll, all rotations and movement are relative to the camera.
+#[derive(Component, Debug, Clone)]
+pub struct FlyCamera {
+    /// Movement speed in units per second
+    pub speed: f32,
+    /// Speed multiplier while the [boost key](FlyCameraKeys::boost) is held
+    pub boost: f32,
+    /// Rotation in degrees per pixel of mouse movement
+    pub sensitivity: f32,
+    /// Time in seconds for movement and rotation to catch up with the input, 0 disables smoothing
+    pub smoothing: f32,
+    /// Enables rolling with the [roll keys](FlyCameraKeys::roll_left), and unlocks the camera's
+    /// up axis
+    pub roll: bool,
+    /// Roll speed in degrees per second
+    pub roll_speed: f32,
+    pub keys: FlyCameraKeys,
+    /// Smoothed velocity
+    velocity: Vec3,
+    /// Mouse rotation in degrees which wasn't applied yet
+    pending_rotation: Vec2,
+}
+
+impl Default for FlyCamera {
+    fn default() -> Self {
+        Self {
+            speed: 10.0,
+            boost: 3.0,
+            sensitivity: 0.1,
+            smoothing: 0.05,
+            roll: false,
+            roll_speed: 90.0,
+            keys: FlyCameraKeys::default(),
+            velocity: Vec3::ZERO,
+            pending_rotation: Vec2::ZERO,
+        }
+    }
+}
+
+impl FlyCamera {
+    /// Create a new fly camera with `speed` in units per second
+    pub fn new(speed: f32) -> Self {
+        Self {
+            speed,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the movement direction requested by the keys, in camera space
+    fn input_direction(&self, key_input: &Input<KeyCode>) -> Vec3 {
+        let keys = &self.keys;
+        let axis = |positive: KeyCode, negative: KeyCode| {
+            key_input.pressed(positive) as i32 as f32 - key_input.pressed(negative) as i32 as f32
+        };
+
+        Vec3::new(
+            axis(keys.right, keys.left),
+            axis(keys.up, keys.down),
+            axis(keys.backward, keys.forward),
+        )
+        .normalize_or_zero()
+    }
+}
+
+fn fly_camera_system(
+    time: Res<Time>,
+    key_input: Res<Input<KeyCode>>,
+    mut mouse_motion: EventReader<MouseMotion>,
+    mut query: Query<(&mut Transform, &mut FlyCamera, &Camera)>,
+) {
+    let delta = time.delta();
+    let mouse_delta = mouse_motion
+        .read()
+        .fold(Vec2::ZERO, |sum, motion| sum + motion.delta);
+
+    for (transform, controller, camera) in query.iter_mut() {
+        if !camera.active {
+            continue;
+        }
+
+        let factor = smoothing_factor(controller.smoothing, delta);
+
+        // Movement
+        let direction = controller.input_direction(&key_input);
+        let mut speed = controller.speed;
+        if key_input.pressed(controller.keys.boost) {
+            speed *= controller.boost;
+        }
+
+        controller.velocity = controller.velocity.lerp(direction * speed, factor);
+        let velocity = controller.velocity;
+        let movement = if controller.roll {
+            transform.rotation * velocity
+        } else {
+            transform.rotation * Vec3::new(velocity.x, 0.0, velocity.z) + Vec3::Y * velocity.y
+        };
+        transform.translation += movement * delta;
+
+        // Rotation
+        controller.pending_rotation -= mouse_delta * controller.sensitivity;
+        let rotation = controller.pending_rotation * factor;
+        controller.pending_rotation -= rotation;
+
+        let (yaw, pitch) = (rotation.x.to_radians(), rotation.y.to_radians());
+        if controller.roll {
+            let roll_input = key_input.pressed(controller.keys.roll_left) as i32 as f32
+                - key_input.pressed(controller.keys.roll_right) as i32 as f32;
+            let roll = (roll_input * controller.roll_speed * delta).to_radians();
+
+            transform.rotation *= Quat::from_euler(EulerRot::YXZ, yaw, pitch, roll);
+        } else {
+            let current_pitch = transform.rotation.to_euler(EulerRot::YXZ).1;
+            let max_pitch = 89.0_f32.to_radians();
+            let new_pitch = (current_pitch + pitch).clamp(-max_pitch, max_pitch);
+
+            transform.rotation = Quat::from_rotation_y(yaw) * transform.rotation;
+            transform.rotation *= Quat::from_rotation_x(new_pitch - current_pitch);
+        }
+        transform.rotation = transform.rotation.normalize();
+    }
+}
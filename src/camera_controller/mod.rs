@@ -0,0 +1,67 @@
+//! # Camera controllers
+//! Reusable controllers for cameras, each configured by a component on the camera entity and
+//! driven by its own plugin:
+//! - [`FlyCamera`] with [`FlyCameraPlugin`], free flying camera with smoothing and optional roll
+//! - [`OrbitCamera`] with [`OrbitCameraPlugin`], rotates around and zooms to a focus point
+//! - [`PanCamera`] with [`PanCameraPlugin`], 2D panning and zooming of orthographic cameras
+//!
+//! ## Usage
+//!
+//! - Add the plugin of the controller to the app, none of them are part of the
+//!   [`DefaultPlugins`].
+//! - Insert the controller component on a camera entity. Only [active](Camera::active) cameras
+//!   are controlled.
+//! ```ignore
+//! app.add_plugin(OrbitCameraPlugin);
+//!
+//! commands
+//!     .spawn_empty()
+//!     .insert(Camera::default())
+//!     .insert(Camera3D::default())
+//!     .insert(Projection::perspective())
+//!     .insert(Transform::default())
+//!     .insert(OrbitCamera::new(Vec3::ZERO, 10.0));
+//! ```
+
+mod fly;
+mod orbit;
+mod pan;
+
+pub mod prelude {
+    pub use super::{
+        FlyCamera, FlyCameraKeys, FlyCameraPlugin, OrbitCamera, OrbitCameraPlugin, PanCamera,
+        PanCameraPlugin,
+    };
+}
+
+pub use fly::{FlyCamera, FlyCameraKeys, FlyCameraPlugin};
+pub use orbit::{OrbitCamera, OrbitCameraPlugin};
+pub use pan::{PanCamera, PanCameraPlugin};
+
+use crate::{
+    event::{MouseScrollDelta, MouseWheel},
+    math::exp_decay_factor,
+};
+
+/// Approximate number of pixels in one scroll line, used to normalize touchpad scrolling
+const PIXELS_PER_LINE: f32 = 20.0;
+
+/// Returns the interpolation factor which moves a smoothed value towards its target, `smoothing`
+/// is the time in seconds it takes to cover ~63% of the distance, 0 disables smoothing
+fn smoothing_factor(smoothing: f32, delta: f32) -> f32 {
+    if smoothing <= 0.0 {
+        return 1.0;
+    }
+
+    exp_decay_factor(1.0 / smoothing, delta)
+}
+
+/// Returns the vertical scroll of this frame in lines, positive when scrolling up
+fn scroll_lines<'a>(events: impl Iterator<Item = &'a MouseWheel>) -> f32 {
+    events
+        .map(|event| match event.delta {
+            MouseScrollDelta::LineDelta(_, y) => y,
+            MouseScrollDelta::PixelDelta(position) => position.y as f32 / PIXELS_PER_LINE,
+        })
+        .sum()
+}
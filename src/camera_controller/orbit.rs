@@ -0,0 +1,165 @@
+use glam::{EulerRot, Quat, Vec2, Vec3};
+
+use crate::{event::EventReader, input::InputPlugin, prelude::*};
+
+use super::{scroll_lines, smoothing_factor};
+
+/// Plugin which moves cameras with an [`OrbitCamera`] component. For more information, see the
+/// [camera controller module](crate::camera_controller).
+pub struct OrbitCameraPlugin;
+
+impl Plugin for OrbitCameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(orbit_camera_system);
+    }
+
+    fn dependencies(&self) -> Vec<PluginId> {
+        vec![PluginId::of::<InputPlugin>()]
+    }
+}
+
+/// Camera controller which orbits around a [`focus`](Self::focus) point. Dragging with the
+/// [`orbit_button`](Self::orbit_button) rotates the camera, dragging with the
+/// [`pan_button`](Self::pan_button) moves the focus, and scrolling zooms in and out.
+///
+/// The fields describe the target pose, which the camera reaches after
+/// [`smoothing`](Self::smoothing). They can be changed directly, e.g. to focus on a selected
+/// entity. The camera's [`Transform`] is overwritten every frame.
+#[derive(Component, Debug, Clone)]
+pub struct OrbitCamera {
+    /// Point the camera looks at
+    pub focus: Vec3,
+    /// Distance from the focus
+    pub radius: f32,
+    /// Rotation around the global `Y` axis in radians
+    pub yaw: f32,
+    /// Rotation above (negative) or below (positive) the focus in radians, limited to just under
+    /// 90 degrees
+    pub pitch: f32,
+    pub min_radius: f32,
+    pub max_radius: f32,
+    /// Rotation in degrees per pixel of mouse movement
+    pub sensitivity: f32,
+    /// Fraction of the radius zoomed per scrolled line
+    pub zoom_speed: f32,
+    /// Time in seconds for the camera to catch up with the target pose, 0 disables smoothing
+    pub smoothing: f32,
+    pub orbit_button: MouseButton,
+    pub pan_button: Option<MouseButton>,
+    /// Smoothed focus, radius, yaw and pitch, None before the first update
+    current: Option<(Vec3, f32, f32, f32)>,
+}
+
+impl Default for OrbitCamera {
+    fn default() -> Self {
+        Self {
+            focus: Vec3::ZERO,
+            radius: 10.0,
+            yaw: 0.0,
+            pitch: -30.0_f32.to_radians(),
+            min_radius: 0.1,
+            max_radius: 1000.0,
+            sensitivity: 0.3,
+            zoom_speed: 0.1,
+            smoothing: 0.05,
+            orbit_button: MouseButton::Left,
+            pan_button: Some(MouseButton::Middle),
+            current: None,
+        }
+    }
+}
+
+impl OrbitCamera {
+    /// Create a new orbit camera looking at `focus` from `radius` units away
+    pub fn new(focus: Vec3, radius: f32) -> Self {
+        Self {
+            focus,
+            radius,
+            ..Default::default()
+        }
+    }
+
+    /// Create a new orbit camera positioned at `eye`, looking at `focus`
+    pub fn looking_at(eye: Vec3, focus: Vec3) -> Self {
+        let offset = eye - focus;
+        let radius = offset.length();
+        let (yaw, pitch, _) =
+            Quat::from_rotation_arc(Vec3::Z, offset.normalize_or(Vec3::Z)).to_euler(EulerRot::YXZ);
+
+        Self {
+            focus,
+            radius,
+            yaw,
+            pitch,
+            ..Default::default()
+        }
+    }
+
+    /// Clamp the target pose to the allowed range
+    fn clamp(&mut self) {
+        let max_pitch = 89.0_f32.to_radians();
+        self.pitch = self.pitch.clamp(-max_pitch, max_pitch);
+        self.radius = self.radius.clamp(self.min_radius, self.max_radius);
+    }
+}
+
+fn orbit_camera_system(
+    time: Res<Time>,
+    mouse_input: Res<Input<MouseButton>>,
+    mut mouse_motion: EventReader<MouseMotion>,
+    mut mouse_wheel: EventReader<MouseWheel>,
+    mut query: Query<(&mut Transform, &mut OrbitCamera, &Camera)>,
+) {
+    let mouse_delta = mouse_motion
+        .read()
+        .fold(Vec2::ZERO, |sum, motion| sum + motion.delta);
+    let scroll = scroll_lines(mouse_wheel.read());
+
+    for (transform, controller, camera) in query.iter_mut() {
+        if !camera.active {
+            continue;
+        }
+
+        if mouse_input.pressed(controller.orbit_button) {
+            let rotation = mouse_delta * controller.sensitivity.to_radians();
+            controller.yaw -= rotation.x;
+            controller.pitch -= rotation.y;
+        } else if controller
+            .pan_button
+            .is_some_and(|button| mouse_input.pressed(button))
+        {
+            // Pan speed is relative to the radius, so the focus follows the cursor at any zoom
+            let rotation = Quat::from_euler(EulerRot::YXZ, controller.yaw, controller.pitch, 0.0);
+            let offset = Vec3::new(-mouse_delta.x, mouse_delta.y, 0.0) * controller.radius * 0.002;
+            controller.focus += rotation * offset;
+        }
+
+        controller.radius *= (1.0 - controller.zoom_speed).powf(scroll);
+        controller.clamp();
+
+        // Smooth towards the target pose
+        let target = (
+            controller.focus,
+            controller.radius,
+            controller.yaw,
+            controller.pitch,
+        );
+        let (focus, radius, yaw, pitch) = match controller.current {
+            Some((focus, radius, yaw, pitch)) => {
+                let factor = smoothing_factor(controller.smoothing, time.delta());
+                (
+                    focus.lerp(target.0, factor),
+                    radius + (target.1 - radius) * factor,
+                    yaw + (target.2 - yaw) * factor,
+                    pitch + (target.3 - pitch) * factor,
+                )
+            }
+            None => target,
+        };
+        controller.current = Some((focus, radius, yaw, pitch));
+
+        let rotation = Quat::from_euler(EulerRot::YXZ, yaw, pitch, 0.0);
+        transform.rotation = rotation;
+        transform.translation = focus + rotation * Vec3::Z * radius;
+    }
+}
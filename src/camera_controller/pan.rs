@@ -0,0 +1,112 @@
+use glam::{Vec2, Vec3};
+
+use crate::{event::EventReader, input::InputPlugin, prelude::*};
+
+use super::{scroll_lines, smoothing_factor};
+
+/// Plugin which moves cameras with a [`PanCamera`] component. For more information, see the
+/// [camera controller module](crate::camera_controller).
+pub struct PanCameraPlugin;
+
+impl Plugin for PanCameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(pan_camera_system);
+    }
+
+    fn dependencies(&self) -> Vec<PluginId> {
+        vec![PluginId::of::<InputPlugin>()]
+    }
+}
+
+/// 2D camera controller for cameras with an orthographic [`Projection`]. Dragging with the
+/// [`button`](Self::button) or holding the arrow keys moves the camera in its local `XY` plane,
+/// and scrolling zooms by changing the projection's [`scale`](OrthographicProjection::scale).
+///
+/// Cameras with a perspective projection are ignored.
+#[derive(Component, Debug, Clone)]
+pub struct PanCamera {
+    pub button: MouseButton,
+    /// Arrow key movement in pixels per second, 0 disables the arrow keys
+    pub key_speed: f32,
+    /// Fraction of the scale zoomed per scrolled line
+    pub zoom_speed: f32,
+    pub min_scale: f32,
+    pub max_scale: f32,
+    /// Time in seconds for the zoom to catch up with the scroll wheel, 0 disables smoothing
+    pub smoothing: f32,
+    /// Scale the zoom is moving towards, None before the first update
+    target_scale: Option<f32>,
+    /// Cursor position of the previous frame
+    last_cursor: Option<Vec2>,
+}
+
+impl Default for PanCamera {
+    fn default() -> Self {
+        Self {
+            button: MouseButton::Left,
+            key_speed: 500.0,
+            zoom_speed: 0.1,
+            min_scale: 0.05,
+            max_scale: 20.0,
+            smoothing: 0.05,
+            target_scale: None,
+            last_cursor: None,
+        }
+    }
+}
+
+fn pan_camera_system(
+    time: Res<Time>,
+    window: Res<Window>,
+    key_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    mut mouse_wheel: EventReader<MouseWheel>,
+    mut query: Query<(&mut Transform, &mut Projection, &mut PanCamera, &Camera)>,
+) {
+    let cursor = window.cursor_position();
+    let scroll = scroll_lines(mouse_wheel.read());
+    let window_width = window.size().width.max(1) as f32;
+
+    let axis = |positive: KeyCode, negative: KeyCode| {
+        key_input.pressed(positive) as i32 as f32 - key_input.pressed(negative) as i32 as f32
+    };
+    let key_direction = Vec2::new(
+        axis(KeyCode::ArrowRight, KeyCode::ArrowLeft),
+        axis(KeyCode::ArrowUp, KeyCode::ArrowDown),
+    )
+    .normalize_or_zero();
+
+    for (transform, projection, controller, camera) in query.iter_mut() {
+        let last_cursor = std::mem::replace(&mut controller.last_cursor, cursor);
+        if !camera.active {
+            continue;
+        }
+
+        let Projection::Orthographic(projection) = projection else {
+            continue;
+        };
+
+        // World units covered by one pixel
+        let pixel_size = projection.area.size().x * projection.scale / window_width;
+
+        // Pan
+        let mut movement = key_direction * controller.key_speed * time.delta();
+        if mouse_input.pressed(controller.button)
+            && let (Some(cursor), Some(last_cursor)) = (cursor, last_cursor)
+        {
+            let cursor_delta = cursor - last_cursor;
+            movement += Vec2::new(-cursor_delta.x, cursor_delta.y);
+        }
+        if movement != Vec2::ZERO {
+            transform.translation += transform.rotation * Vec3::from((movement * pixel_size, 0.0));
+        }
+
+        // Zoom
+        let target_scale = controller.target_scale.get_or_insert(projection.scale);
+        *target_scale = (*target_scale * (1.0 - controller.zoom_speed).powf(scroll))
+            .clamp(controller.min_scale, controller.max_scale);
+
+        let factor = smoothing_factor(controller.smoothing, time.delta());
+        projection.scale += (*target_scale - projection.scale) * factor;
+    }
+}
@@ -0,0 +1,415 @@
+use std::{any::TypeId, collections::HashMap, sync::Arc};
+
+use crate::{prelude::*, reflect::Reflect, system::System};
+
+use super::custom::ConsoleCommands;
+
+/// Built-in commands and their arguments
+pub(super) const BUILTIN_COMMANDS: [(&str, &str); 11] = [
+    ("spawn", "[Component...]"),
+    ("despawn", "<entity>"),
+    ("insert", "<entity> <Component...>"),
+    ("get", "<entity> <Component.field>"),
+    ("set", "<entity> <Component.field> <value...>"),
+    ("res", "<Resource.field>"),
+    ("set_res", "<Resource.field> <value...>"),
+    ("state", "<State> [Variant]"),
+    ("run", "<system>"),
+    ("clear", ""),
+    ("help", ""),
+];
+
+/// Inserts the default value of a component into an entity
+pub(super) type Inserter = fn(&mut World, EntityId);
+
+/// Runs `f` on a reflected resource, or returns None if the resource doesn't exist
+pub(super) type ResourceAccess = fn(
+    &mut World,
+    &mut dyn FnMut(&mut dyn Reflect) -> Result<String, String>,
+) -> Option<Result<String, String>>;
+
+/// Returns the current state, or queues a transition to the variant with the given name
+pub(super) type StateAccess =
+    Arc<dyn Fn(&mut World, Option<&str>) -> Result<String, String> + Send + Sync>;
+
+/// Everything the console can access by name, besides the types in the app's type registry
+#[derive(Default)]
+pub(super) struct ConsoleRegistry {
+    pub inserters: HashMap<&'static str, Inserter>,
+    pub resources: HashMap<&'static str, ResourceAccess>,
+    pub states: HashMap<&'static str, StateAccess>,
+    pub systems: HashMap<String, System>,
+}
+
+/// [`Inserter`] for component `C`
+pub(super) fn insert_default<C: Component + Default>(world: &mut World, entity: EntityId) {
+    world.insert_bundle(entity, C::default());
+}
+
+/// [`ResourceAccess`] for resource `R`
+pub(super) fn access_resource<R: Resource + Reflect>(
+    world: &mut World,
+    f: &mut dyn FnMut(&mut dyn Reflect) -> Result<String, String>,
+) -> Option<Result<String, String>> {
+    let mut resource = world.resources.try_get_mut::<R>()?;
+    Some(f(&mut *resource))
+}
+
+/// [`StateAccess`] for state `S`, variants are matched by their debug name
+pub(super) fn access_state<S: States>(variants: Vec<S>) -> StateAccess {
+    Arc::new(move |world, variant| {
+        let Some(variant) = variant else {
+            let state = world
+                .resources
+                .try_get::<State<S>>()
+                .ok_or("State is not registered in the app")?;
+            return Ok(format!("{:?}", state.get()));
+        };
+
+        let value = variants
+            .iter()
+            .find(|value| format!("{:?}", value) == variant)
+            .ok_or_else(|| {
+                let names = variants.iter().map(|value| format!("{:?}", value));
+                format!(
+                    "Unknown variant '{}', expected one of: {}",
+                    variant,
+                    names.collect::<Vec<_>>().join(", ")
+                )
+            })?;
+
+        world
+            .resources
+            .try_get_mut::<NextState<S>>()
+            .ok_or("State is not registered in the app")?
+            .set(*value);
+        Ok(format!("Next state: {:?}", value))
+    })
+}
+
+/// Parses and runs a command line, returns its output
+pub(super) fn execute(
+    app: &mut App,
+    registry: &mut ConsoleRegistry,
+    custom: &mut ConsoleCommands,
+    line: &str,
+) -> Result<String, String> {
+    let tokens = tokenize(line)?;
+    let Some((command, args)) = tokens.split_first() else {
+        return Ok(String::new());
+    };
+
+    match (command.as_str(), args) {
+        ("help", []) => Ok(help(registry, custom)),
+        ("spawn", components) => {
+            let inserters = components
+                .iter()
+                .map(|name| inserter(registry, name))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let entity = app.world.spawn();
+            for insert in inserters {
+                insert(&mut app.world, entity);
+            }
+            Ok(format!("Spawned {}", format_entity(entity)))
+        }
+        ("despawn", [entity]) => {
+            let entity = parse_entity(&mut app.world, entity)?;
+            app.world.despawn(entity);
+            Ok(format!("Despawned {}", format_entity(entity)))
+        }
+        ("insert", [entity, components @ ..]) if !components.is_empty() => {
+            let entity = parse_entity(&mut app.world, entity)?;
+            for name in components {
+                inserter(registry, name)?(&mut app.world, entity);
+            }
+            Ok(String::new())
+        }
+        ("get", [entity, path]) => {
+            let entity = parse_entity(&mut app.world, entity)?;
+            let (name, path) = split_path(path);
+            let type_id = component_type(app, name)?;
+
+            let ptr = app
+                .world
+                .entities
+                .get_component_untyped(entity, type_id, false)
+                .ok_or_else(|| format!("Entity has no component '{}'", name))?;
+            let component = app
+                .type_registry
+                .reflect(ptr, type_id)
+                .expect("type was found in the registry");
+            Ok(format!("{:?}", field(component, &path)?))
+        }
+        ("set", [entity, path, values @ ..]) if !values.is_empty() => {
+            let entity = parse_entity(&mut app.world, entity)?;
+            let (name, path) = split_path(path);
+            let type_id = component_type(app, name)?;
+
+            let ptr = app
+                .world
+                .entities
+                .get_component_untyped(entity, type_id, true)
+                .ok_or_else(|| format!("Entity has no component '{}'", name))?;
+            // Safety: the pointer comes from the component storage of `type_id` and the world is
+            // exclusively borrowed
+            let component = unsafe { app.type_registry.reflect_mut(ptr, type_id) }
+                .expect("type was found in the registry");
+            apply(field_mut(component, &path)?, values)?;
+            Ok(String::new())
+        }
+        ("res", [path]) => {
+            let (name, path) = split_path(path);
+            resource(registry, &mut app.world, name, &mut |resource| {
+                Ok(format!("{:?}", field(resource, &path)?))
+            })
+        }
+        ("set_res", [path, values @ ..]) if !values.is_empty() => {
+            let (name, path) = split_path(path);
+            resource(registry, &mut app.world, name, &mut |resource| {
+                apply(field_mut(resource, &path)?, values)?;
+                Ok(String::new())
+            })
+        }
+        ("state", [name, variant @ ..]) if variant.len() <= 1 => {
+            let access = registry
+                .states
+                .get(name.as_str())
+                .ok_or_else(|| format!("State '{}' is not registered in the console", name))?;
+            access(&mut app.world, variant.first().map(String::as_str))
+        }
+        ("run", [name]) => {
+            let system = registry
+                .systems
+                .get_mut(name)
+                .ok_or_else(|| format!("System '{}' is not registered in the console", name))?;
+            system.run(&mut app.world);
+            system.apply(&mut app.world);
+            app.world.flush_commands();
+            Ok(String::new())
+        }
+        (name, args) => match custom.commands.get_mut(name) {
+            Some(command) => (command.run)(&mut app.world, args),
+            None => Err(format!("Invalid command '{}', see 'help'", line.trim())),
+        },
+    }
+}
+
+/// Returns the list of commands and registered names
+fn help(registry: &ConsoleRegistry, custom: &ConsoleCommands) -> String {
+    let sorted = |mut names: Vec<&str>| {
+        names.sort_unstable();
+        names.join(", ")
+    };
+
+    let mut text = "Commands:".to_string();
+    for (name, usage) in BUILTIN_COMMANDS {
+        text.push_str(format!("\n  {} {}", name, usage).trim_end());
+    }
+    let mut commands = custom.commands.iter().collect::<Vec<_>>();
+    commands.sort_unstable_by_key(|(name, _)| name.as_str());
+    for (name, command) in commands {
+        text.push_str(format!("\n  {} {}", name, command.usage).trim_end());
+    }
+
+    text.push_str(&format!(
+        "\nComponents: {}\nResources: {}\nStates: {}\nSystems: {}",
+        sorted(registry.inserters.keys().copied().collect()),
+        sorted(registry.resources.keys().copied().collect()),
+        sorted(registry.states.keys().copied().collect()),
+        sorted(registry.systems.keys().map(String::as_str).collect()),
+    ));
+    text
+}
+
+fn inserter(registry: &ConsoleRegistry, name: &str) -> Result<Inserter, String> {
+    registry
+        .inserters
+        .get(name)
+        .copied()
+        .ok_or_else(|| format!("Component '{}' is not registered in the console", name))
+}
+
+fn component_type(app: &App, name: &str) -> Result<TypeId, String> {
+    app.type_registry
+        .get_by_name(name)
+        .ok_or_else(|| format!("Type '{}' is not registered", name))
+}
+
+fn resource(
+    registry: &ConsoleRegistry,
+    world: &mut World,
+    name: &str,
+    f: &mut dyn FnMut(&mut dyn Reflect) -> Result<String, String>,
+) -> Result<String, String> {
+    let access = registry
+        .resources
+        .get(name)
+        .ok_or_else(|| format!("Resource '{}' is not registered in the console", name))?;
+    access(world, f).ok_or_else(|| format!("Resource '{}' doesn't exist", name))?
+}
+
+/// Entities are written as `index` or `index`v`generation`, like they are printed
+fn format_entity(entity: EntityId) -> String {
+    format!("{}v{}", entity.index(), entity.generation())
+}
+
+/// Returns the id of a living entity, the generation is optional
+pub(super) fn parse_entity(world: &mut World, token: &str) -> Result<EntityId, String> {
+    let (index, generation) = match token.split_once('v') {
+        Some((index, generation)) => (index, Some(generation)),
+        None => (token, None),
+    };
+    let invalid = || format!("Invalid entity '{}'", token);
+    let index = index.parse::<u32>().map_err(|_| invalid())?;
+    let generation = generation
+        .map(|generation| generation.parse::<u32>().map_err(|_| invalid()))
+        .transpose()?;
+
+    // entities are tracked by index, their current generation is read from their own id
+    let entity = world
+        .entities
+        .get_component_untyped(EntityId::new(index, 0), TypeId::of::<EntityId>(), false)
+        // Safety: the pointer comes from the EntityId storage
+        .map(|ptr| unsafe { *ptr.as_ptr().cast::<EntityId>().as_ref() })
+        .filter(|entity| generation.is_none_or(|generation| entity.generation() == generation))
+        .ok_or_else(|| format!("Entity {} doesn't exist", token))?;
+    Ok(entity)
+}
+
+/// Splits `Type.field.field` into the type name and the field path
+fn split_path(path: &str) -> (&str, Vec<&str>) {
+    let mut segments = path.split('.');
+    let name = segments.next().unwrap_or_default();
+    (name, segments.collect())
+}
+
+/// Returns the index of a field by its name, or the segment itself if it's a number
+fn field_index(value: &dyn Reflect, segment: &str) -> Option<usize> {
+    value
+        .field_names()
+        .iter()
+        .position(|name| *name == segment)
+        .or_else(|| segment.parse().ok())
+}
+
+fn field<'a>(mut value: &'a dyn Reflect, path: &[&str]) -> Result<&'a dyn Reflect, String> {
+    for segment in path {
+        value = field_index(value, segment)
+            .and_then(|index| value.field_by_index(index))
+            .ok_or_else(|| format!("'{}' has no field '{}'", value.type_name(), segment))?;
+    }
+    Ok(value)
+}
+
+fn field_mut<'a>(
+    mut value: &'a mut dyn Reflect,
+    path: &[&str],
+) -> Result<&'a mut dyn Reflect, String> {
+    for segment in path {
+        let type_name = value.type_name();
+        value = field_index(value, segment)
+            .and_then(|index| value.field_mut_by_index(index))
+            .ok_or_else(|| format!("'{}' has no field '{}'", type_name, segment))?;
+    }
+    Ok(value)
+}
+
+/// Parse a leaf value from a token
+macro_rules! parse_leaf {
+    ($($type:ty),+) => {
+        /// Parses `value` into `target`, returns false if `target` is not a primitive or a string
+        fn apply_leaf(target: &mut dyn Reflect, value: &str) -> Result<bool, String> {
+            $(if let Some(target) = target.downcast_mut::<$type>() {
+                *target = value.parse().map_err(|_| {
+                    format!("Expected {}, got '{}'", stringify!($type), value)
+                })?;
+                return Ok(true);
+            })+
+
+            if let Some(target) = target.downcast_mut::<String>() {
+                *target = value.to_string();
+                return Ok(true);
+            }
+            Ok(false)
+        }
+    };
+}
+
+parse_leaf!(
+    u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64, bool, char
+);
+
+/// Writes `values` into `target`, values of types with fields are assigned to their leaf fields
+/// in order, e.g. `1 2 3` for a `Vec3`
+fn apply(target: &mut dyn Reflect, values: &[String]) -> Result<(), String> {
+    let type_name = target.type_name();
+    let mut values = values.iter();
+    let assigned = apply_fields(target, &mut values)?;
+
+    if assigned == 0 {
+        return Err(format!("Cannot assign to '{}'", type_name));
+    }
+    if values.len() != 0 {
+        return Err(format!("Too many values for '{}'", type_name));
+    }
+    Ok(())
+}
+
+/// Returns the amount of assigned leaf values
+fn apply_fields(
+    target: &mut dyn Reflect,
+    values: &mut std::slice::Iter<'_, String>,
+) -> Result<usize, String> {
+    let Some(value) = values.as_slice().first() else {
+        return Ok(0);
+    };
+    if apply_leaf(target, value)? {
+        values.next();
+        return Ok(1);
+    }
+
+    let mut assigned = 0;
+    for index in 0.. {
+        if values.len() == 0 {
+            break;
+        }
+        let Some(field) = target.field_mut_by_index(index) else {
+            break;
+        };
+        assigned += apply_fields(field, values)?;
+    }
+    Ok(assigned)
+}
+
+/// Splits a command line at whitespace and commas, double quoted strings are kept together
+pub(super) fn tokenize(line: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut quoted = false;
+
+    for c in line.chars() {
+        match c {
+            '"' => {
+                if quoted {
+                    tokens.push(std::mem::take(&mut token));
+                }
+                quoted = !quoted;
+            }
+            c if !quoted && (c.is_whitespace() || c == ',') => {
+                if !token.is_empty() {
+                    tokens.push(std::mem::take(&mut token));
+                }
+            }
+            c => token.push(c),
+        }
+    }
+
+    if quoted {
+        return Err("Unterminated string".to_string());
+    }
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
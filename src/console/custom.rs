@@ -0,0 +1,233 @@
+use std::{collections::HashMap, fmt::Display, slice::Iter};
+
+use crate::prelude::*;
+
+use super::command::{BUILTIN_COMMANDS, parse_entity};
+
+/// Runs a registered command with its arguments
+type CommandFn = Box<dyn FnMut(&mut World, &[String]) -> Result<String, String> + Send + Sync>;
+
+/// Command registered with [`App::register_command`]
+pub(super) struct CustomCommand {
+    /// Arguments shown in the usage hint, e.g. `<String> [u32]`
+    pub usage: String,
+    pub run: CommandFn,
+}
+
+/// Commands registered with [`App::register_command`]
+#[derive(Resource, Default)]
+pub(crate) struct ConsoleCommands {
+    pub(super) commands: HashMap<String, CustomCommand>,
+}
+
+/// Register a console command, see [`App::register_command`]
+pub(crate) fn register_command<A: CommandArgs, O: CommandOutput>(
+    app: &mut App,
+    name: String,
+    mut command: impl FnMut(&mut World, A) -> O + Send + Sync + 'static,
+) {
+    assert!(
+        !BUILTIN_COMMANDS.iter().any(|(builtin, _)| *builtin == name),
+        "Console command '{}' is built in",
+        name
+    );
+    assert!(
+        !name.is_empty() && !name.contains(char::is_whitespace),
+        "Console command '{}' has to be a single word",
+        name
+    );
+
+    let command = CustomCommand {
+        usage: A::usage(),
+        run: Box::new(move |world, args| {
+            let mut args = args.iter();
+            let parsed = A::parse(&mut args, world)?;
+            if args.len() > 0 {
+                return Err(format!("Too many arguments, usage: {}", A::usage()));
+            }
+            command(world, parsed).into_output()
+        }),
+    };
+
+    app.init_resource::<ConsoleCommands>();
+    let existing = app
+        .world
+        .resources
+        .get_mut::<ConsoleCommands>()
+        .commands
+        .insert(name.clone(), command);
+    assert!(
+        existing.is_none(),
+        "Console command '{}' is already registered",
+        name
+    );
+}
+
+/// Argument of a console command registered with [`App::register_command`]. Implemented for
+/// numbers, `bool`, `char`, `String` and [`EntityId`], `Option<T>` is an optional argument and
+/// `Vec<T>` takes every remaining argument.
+pub trait CommandArg: Sized {
+    /// Returns the argument shown in the usage hint, e.g. `<u32>`
+    fn usage() -> String;
+
+    /// Parse the argument from the remaining tokens
+    fn parse(args: &mut Iter<'_, String>, world: &mut World) -> Result<Self, String>;
+}
+
+/// Arguments of a console command, implemented for [`CommandArg`] and tuples of it
+pub trait CommandArgs: Sized {
+    /// Returns the arguments shown in the usage hint
+    fn usage() -> String;
+
+    /// Parse the arguments from the tokens after the command name
+    fn parse(args: &mut Iter<'_, String>, world: &mut World) -> Result<Self, String>;
+}
+
+/// Return value of a console command, which is printed to the console
+pub trait CommandOutput {
+    fn into_output(self) -> Result<String, String>;
+}
+
+impl CommandOutput for () {
+    fn into_output(self) -> Result<String, String> {
+        Ok(String::new())
+    }
+}
+
+impl CommandOutput for String {
+    fn into_output(self) -> Result<String, String> {
+        Ok(self)
+    }
+}
+
+impl CommandOutput for &str {
+    fn into_output(self) -> Result<String, String> {
+        Ok(self.to_string())
+    }
+}
+
+impl<T: CommandOutput, E: Display> CommandOutput for Result<T, E> {
+    fn into_output(self) -> Result<String, String> {
+        self.map_err(|err| err.to_string())?.into_output()
+    }
+}
+
+/// Returns the next token, or an error naming the missing argument
+fn next<'a, T: CommandArg>(args: &mut Iter<'a, String>) -> Result<&'a str, String> {
+    args.next()
+        .map(String::as_str)
+        .ok_or_else(|| format!("Missing argument {}", T::usage()))
+}
+
+macro_rules! impl_command_arg {
+    ($($type:ty),*) => {
+        $(
+            impl CommandArg for $type {
+                fn usage() -> String {
+                    format!("<{}>", stringify!($type))
+                }
+
+                fn parse(args: &mut Iter<'_, String>, _: &mut World) -> Result<Self, String> {
+                    let token = next::<Self>(args)?;
+                    token
+                        .parse()
+                        .map_err(|_| format!("Expected {}, got '{}'", stringify!($type), token))
+                }
+            }
+        )*
+    };
+}
+
+impl_command_arg!(
+    u8, u16, u32, u64, usize, i8, i16, i32, i64, isize, f32, f64, bool, char
+);
+
+impl CommandArg for String {
+    fn usage() -> String {
+        "<String>".to_string()
+    }
+
+    fn parse(args: &mut Iter<'_, String>, _: &mut World) -> Result<Self, String> {
+        next::<Self>(args).map(str::to_string)
+    }
+}
+
+impl CommandArg for EntityId {
+    fn usage() -> String {
+        "<entity>".to_string()
+    }
+
+    fn parse(args: &mut Iter<'_, String>, world: &mut World) -> Result<Self, String> {
+        parse_entity(world, next::<Self>(args)?)
+    }
+}
+
+impl<T: CommandArg> CommandArg for Option<T> {
+    fn usage() -> String {
+        format!("[{}]", T::usage().trim_matches(['<', '>']))
+    }
+
+    fn parse(args: &mut Iter<'_, String>, world: &mut World) -> Result<Self, String> {
+        if args.len() == 0 {
+            return Ok(None);
+        }
+        T::parse(args, world).map(Some)
+    }
+}
+
+impl<T: CommandArg> CommandArg for Vec<T> {
+    fn usage() -> String {
+        format!("[{}...]", T::usage().trim_matches(['<', '>']))
+    }
+
+    fn parse(args: &mut Iter<'_, String>, world: &mut World) -> Result<Self, String> {
+        let mut values = Vec::with_capacity(args.len());
+        while args.len() > 0 {
+            values.push(T::parse(args, world)?);
+        }
+        Ok(values)
+    }
+}
+
+impl CommandArgs for () {
+    fn usage() -> String {
+        String::new()
+    }
+
+    fn parse(_: &mut Iter<'_, String>, _: &mut World) -> Result<Self, String> {
+        Ok(())
+    }
+}
+
+impl<T: CommandArg> CommandArgs for T {
+    fn usage() -> String {
+        T::usage()
+    }
+
+    fn parse(args: &mut Iter<'_, String>, world: &mut World) -> Result<Self, String> {
+        T::parse(args, world)
+    }
+}
+
+macro_rules! impl_command_args {
+    ($($arg:ident),*) => {
+        impl<$($arg: CommandArg),*> CommandArgs for ($($arg,)*) {
+            fn usage() -> String {
+                [$($arg::usage()),*].join(" ")
+            }
+
+            fn parse(args: &mut Iter<'_, String>, world: &mut World) -> Result<Self, String> {
+                Ok(($($arg::parse(args, world)?,)*))
+            }
+        }
+    };
+}
+
+impl_command_args!(A);
+impl_command_args!(A, B);
+impl_command_args!(A, B, C);
+impl_command_args!(A, B, C, D);
+impl_command_args!(A, B, C, D, E);
+impl_command_args!(A, B, C, D, E, F);
+impl_command_args!(A, B, C, D, E, F, G);
+impl_command_args!(A, B, C, D, E, F, G, H);
@@ -0,0 +1,553 @@
+//! # Console plugin
+//! In-game developer console, toggled with [`ConsolePlugin::toggle`]. Commands can spawn entities,
+//! read and write reflected component and resource fields, change states and run one-shot
+//! systems.
+//!
+//! ## Usage
+//!
+//! ```ignore
+//! app.add_plugin(
+//!     ConsolePlugin::default()
+//!         .with_component::<Player>()
+//!         .with_resource::<Gravity>()
+//!         .with_state([GameState::Menu, GameState::Playing])
+//!         .with_system("heal", heal_player),
+//! );
+//! ```
+//!
+//! ## Commands
+//!
+//! Components are referenced by their type name and must be registered with
+//! [`App::register_type`], entities are written as `index` or `index`v`generation`.
+//!
+//! - `spawn [Component...]`, `insert <entity> <Component...>` - inserts default values of
+//!   components added with [`ConsolePlugin::with_component`]
+//! - `despawn <entity>`
+//! - `get <entity> <Component.field>`, `set <entity> <Component.field> <value...>`
+//! - `res <Resource.field>`, `set_res <Resource.field> <value...>` - for resources added with
+//!   [`ConsolePlugin::with_resource`]
+//! - `state <State> [Variant]` - prints the state, or queues a transition to the variant
+//! - `run <system>` - runs a system added with [`ConsolePlugin::with_system`]
+//! - `clear`, `help`
+//!
+//! Fields are separated by dots and can be indices, e.g. `Transform.translation.x`. Values with
+//! fields take one value per leaf field, in order, e.g. `set 4 Transform.scale 2 2 2`. Strings
+//! with spaces are written in double quotes.
+//!
+//! ## Custom commands
+//!
+//! Games can add their own commands with [`App::register_command`], arguments are parsed from the
+//! command line with [`CommandArg`]. Tab completes command names and the names of registered
+//! components, resources, states and systems, and the usage of the typed command is shown below
+//! the input line.
+//!
+//! ```ignore
+//! app.register_command("give_item", |world: &mut World, (item, count): (String, Option<u32>)| {
+//!     let count = count.unwrap_or(1);
+//!     // ...
+//!     format!("Gave {} {}", count, item)
+//! });
+//! ```
+
+mod command;
+mod custom;
+
+pub mod prelude {
+    pub use super::{CommandArg, Console, ConsolePlugin};
+}
+
+pub(crate) use custom::register_command;
+pub use custom::{CommandArg, CommandArgs, CommandOutput};
+
+use std::collections::VecDeque;
+
+use winit::{event::KeyEvent, keyboard::PhysicalKey};
+
+use crate::{
+    prelude::*,
+    reflect::{Reflect, registry::short_type_name},
+    system::{System, SystemParam},
+    ui::prelude::*,
+};
+
+use command::{
+    BUILTIN_COMMANDS, ConsoleRegistry, Inserter, ResourceAccess, StateAccess, access_resource,
+    access_state, execute, insert_default, tokenize,
+};
+use custom::ConsoleCommands;
+
+/// Amount of output lines shown in the console
+const VISIBLE_LINES: usize = 14;
+
+/// Builds a one-shot system, the plugin is built by reference
+type SystemFactory = Box<dyn Fn() -> System>;
+
+/// Component which the console can insert
+struct ConsoleComponent {
+    name: &'static str,
+    register: fn(&mut App),
+    inserter: Inserter,
+}
+
+/// Plugin which adds the [`Console`]. For more information, see the
+/// [console module](crate::console).
+pub struct ConsolePlugin {
+    /// Key which opens and closes the console
+    pub toggle: KeyCode,
+    /// Maximum amount of output lines kept
+    pub max_lines: usize,
+    components: Vec<ConsoleComponent>,
+    resources: Vec<(&'static str, ResourceAccess)>,
+    states: Vec<(&'static str, StateAccess)>,
+    systems: Vec<(String, SystemFactory)>,
+}
+
+impl Default for ConsolePlugin {
+    fn default() -> Self {
+        Self {
+            toggle: KeyCode::F1,
+            max_lines: 200,
+            components: Vec::new(),
+            resources: Vec::new(),
+            states: Vec::new(),
+            systems: Vec::new(),
+        }
+    }
+}
+
+impl ConsolePlugin {
+    /// Returns self with new `toggle` key
+    #[must_use]
+    pub fn with_toggle(mut self, toggle: KeyCode) -> Self {
+        self.toggle = toggle;
+        self
+    }
+
+    /// Allow the console to spawn and insert component `C`, it is registered to the type registry
+    #[must_use]
+    pub fn with_component<C: Component + Reflect + Default>(mut self) -> Self {
+        self.components.push(ConsoleComponent {
+            name: short_type_name::<C>(),
+            register: |app| {
+                app.register_type::<C>();
+            },
+            inserter: insert_default::<C>,
+        });
+        self
+    }
+
+    /// Allow the console to read and write the fields of resource `R`
+    #[must_use]
+    pub fn with_resource<R: Resource + Reflect>(mut self) -> Self {
+        self.resources
+            .push((short_type_name::<R>(), access_resource::<R>));
+        self
+    }
+
+    /// Allow the console to change state `S` to one of `variants`, which are referenced by their
+    /// debug name. The state has to be registered in the app.
+    #[must_use]
+    pub fn with_state<S: States>(mut self, variants: impl IntoIterator<Item = S>) -> Self {
+        let variants = variants.into_iter().collect();
+        self.states
+            .push((short_type_name::<S>(), access_state::<S>(variants)));
+        self
+    }
+
+    /// Allow the console to run `system` by `name`. The system runs outside of the scheduler with
+    /// exclusive access to the world, its commands are applied right after it.
+    #[must_use]
+    pub fn with_system<P: SystemParam>(
+        mut self,
+        name: impl Into<String>,
+        system: impl IntoSystem<P> + Clone + 'static,
+    ) -> Self {
+        self.systems
+            .push((name.into(), Box::new(move || system.clone().build())));
+        self
+    }
+}
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        let mut registry = ConsoleRegistry::default();
+        for component in &self.components {
+            (component.register)(app);
+            registry
+                .inserters
+                .insert(component.name, component.inserter);
+        }
+        for (name, access) in &self.resources {
+            registry.resources.insert(name, *access);
+        }
+        for (name, access) in &self.states {
+            registry.states.insert(name, access.clone());
+        }
+        for (name, system) in &self.systems {
+            registry.systems.insert(name.clone(), system());
+        }
+
+        app.set_resource(Console::new(self.toggle, self.max_lines, registry))
+            .register_system(console_input_system, phase::PreUpdate)
+            .register_system(run_console_commands_system, phase::Update)
+            .register_system(update_console_ui_system, phase::PostUpdate);
+    }
+}
+
+/// State of the developer console. Used as a resource.
+///
+/// Commands can also be submitted from systems, they run in the next [`phase::Update`].
+#[derive(Resource)]
+pub struct Console {
+    toggle: KeyCode,
+    max_lines: usize,
+    open: bool,
+    /// Current input line
+    input: String,
+    /// Output lines, oldest first
+    lines: VecDeque<String>,
+    /// Submitted lines, oldest first
+    history: Vec<String>,
+    /// Index into the history while browsing it with the arrow keys
+    history_index: Option<usize>,
+    /// Submitted lines which didn't run yet
+    pending: Vec<String>,
+    registry: ConsoleRegistry,
+    /// Root ui node, if the console is open
+    root: Option<EntityId>,
+    /// Text node with the output and the input line
+    text: Option<EntityId>,
+    /// Whether the text node has to be updated
+    dirty: bool,
+}
+
+impl Console {
+    fn new(toggle: KeyCode, max_lines: usize, registry: ConsoleRegistry) -> Self {
+        Self {
+            toggle,
+            max_lines,
+            open: false,
+            input: String::new(),
+            lines: VecDeque::new(),
+            history: Vec::new(),
+            history_index: None,
+            pending: Vec::new(),
+            registry,
+            root: None,
+            text: None,
+            dirty: false,
+        }
+    }
+
+    /// Whether the console is open, can be used to ignore game input while typing
+    #[inline]
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Open or close the console
+    pub fn set_open(&mut self, open: bool) {
+        self.open = open;
+        self.dirty = true;
+    }
+
+    /// Returns the current input line
+    #[inline]
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    /// Returns the output lines, oldest first
+    pub fn lines(&self) -> impl Iterator<Item = &str> {
+        self.lines.iter().map(String::as_str)
+    }
+
+    /// Add an output line
+    pub fn print(&mut self, line: impl Into<String>) {
+        for line in line.into().lines() {
+            self.lines.push_back(line.to_string());
+        }
+        while self.lines.len() > self.max_lines {
+            self.lines.pop_front();
+        }
+        self.dirty = true;
+    }
+
+    /// Remove every output line
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.dirty = true;
+    }
+
+    /// Queue a command line to run in the next [`phase::Update`], as if it was typed
+    pub fn submit(&mut self, line: impl Into<String>) {
+        let line = line.into();
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            return;
+        }
+
+        if self.history.last().map(String::as_str) != Some(trimmed) {
+            self.history.push(trimmed.to_string());
+        }
+        self.history_index = None;
+
+        if trimmed == "clear" {
+            self.clear();
+        } else {
+            self.pending.push(trimmed.to_string());
+        }
+    }
+
+    /// Replace the input line with the previous (`back`) or next history entry
+    fn browse_history(&mut self, back: bool) {
+        let index = match (self.history_index, back) {
+            (None, true) => self.history.len().checked_sub(1),
+            (None, false) => None,
+            (Some(index), true) => Some(index.saturating_sub(1)),
+            (Some(index), false) => Some(index + 1).filter(|index| *index < self.history.len()),
+        };
+
+        self.history_index = index;
+        self.input = index
+            .map(|index| self.history[index].clone())
+            .unwrap_or_default();
+        self.dirty = true;
+    }
+
+    /// Complete the last word of the input line. If there are multiple candidates, their common
+    /// prefix is completed and the candidates are printed.
+    fn complete(&mut self, custom: Option<&ConsoleCommands>) {
+        let Ok(tokens) = tokenize(&self.input) else {
+            return;
+        };
+        let (word, index) = match tokens.last() {
+            Some(last) if !self.input.ends_with(char::is_whitespace) => {
+                (last.as_str(), tokens.len() - 1)
+            }
+            _ => ("", tokens.len()),
+        };
+        // quoted words can't be completed
+        if !self.input.ends_with(word) {
+            return;
+        }
+
+        let registry = &self.registry;
+        let components = || registry.inserters.keys().copied().collect::<Vec<_>>();
+        let (mut candidates, path) = match (tokens.first().map(String::as_str), index) {
+            (_, 0) => {
+                let builtin = BUILTIN_COMMANDS.iter().map(|(name, _)| *name);
+                let custom = custom.iter().flat_map(|custom| custom.commands.keys());
+                (builtin.chain(custom.map(String::as_str)).collect(), false)
+            }
+            (Some("spawn"), _) | (Some("insert"), 2..) => (components(), false),
+            (Some("get" | "set"), 2) => (components(), true),
+            (Some("res" | "set_res"), 1) => (registry.resources.keys().copied().collect(), true),
+            (Some("state"), 1) => (registry.states.keys().copied().collect(), false),
+            (Some("run"), 1) => (registry.systems.keys().map(String::as_str).collect(), false),
+            _ => return,
+        };
+        candidates.retain(|candidate| candidate.starts_with(word));
+        candidates.sort_unstable();
+
+        let completion = match candidates.as_slice() {
+            [] => return,
+            // paths continue with a field, other words with the next argument
+            [candidate] if path => format!("{}.", candidate),
+            [candidate] => format!("{} ", candidate),
+            [first, rest @ ..] => {
+                let common = rest.iter().fold(first.len(), |len, candidate| {
+                    first
+                        .bytes()
+                        .zip(candidate.bytes())
+                        .take(len)
+                        .take_while(|(a, b)| a == b)
+                        .count()
+                });
+                first[..common].to_string()
+            }
+        };
+
+        let output = (candidates.len() > 1).then(|| candidates.join("  "));
+        self.input.truncate(self.input.len() - word.len());
+        self.input.push_str(&completion);
+        if let Some(output) = output {
+            self.print(output);
+        }
+    }
+
+    /// Returns the usage of the command in the input line
+    fn usage(&self, custom: Option<&ConsoleCommands>) -> Option<String> {
+        let name = self.input.split_whitespace().next()?;
+        let usage = match BUILTIN_COMMANDS
+            .iter()
+            .find(|(builtin, _)| *builtin == name)
+        {
+            Some((_, usage)) => *usage,
+            None => custom?.commands.get(name)?.usage.as_str(),
+        };
+        Some(format!("{} {}", name, usage).trim_end().to_string())
+    }
+
+    /// Handle a pressed key while the console is open
+    fn key_pressed(&mut self, event: &KeyEvent, custom: Option<&ConsoleCommands>) {
+        match event.physical_key {
+            PhysicalKey::Code(KeyCode::Enter | KeyCode::NumpadEnter) => {
+                let line = std::mem::take(&mut self.input);
+                self.submit(line);
+            }
+            PhysicalKey::Code(KeyCode::Backspace) => {
+                self.input.pop();
+            }
+            PhysicalKey::Code(KeyCode::ArrowUp) => self.browse_history(true),
+            PhysicalKey::Code(KeyCode::ArrowDown) => self.browse_history(false),
+            PhysicalKey::Code(KeyCode::Tab) => self.complete(custom),
+            _ => {
+                let text = event.text.as_deref().unwrap_or_default();
+                self.input.extend(text.chars().filter(|c| !c.is_control()));
+            }
+        }
+        self.dirty = true;
+    }
+}
+
+/// System which toggles the console and edits its input line from keyboard events
+fn console_input_system(
+    mut console: ResMut<Console>,
+    custom: Option<Res<ConsoleCommands>>,
+    mut window_events: EventReader<WindowEvent>,
+) {
+    for event in window_events.read() {
+        let WindowEvent::KeyboardInput { event, .. } = event else {
+            continue;
+        };
+        if event.state != ElementState::Pressed {
+            continue;
+        }
+
+        if event.physical_key == PhysicalKey::Code(console.toggle) {
+            if !event.repeat {
+                let open = !console.open;
+                console.set_open(open);
+            }
+        } else if console.open {
+            console.key_pressed(event, custom.as_deref());
+        }
+    }
+}
+
+/// System which runs the submitted command lines and prints their output
+fn run_console_commands_system(app: &mut App) {
+    // the registries are taken out, so systems run by the console can access the console too
+    let (lines, mut registry) = {
+        let mut console = app.world.resources.get_mut::<Console>();
+        if console.pending.is_empty() {
+            return;
+        }
+        let lines = std::mem::take(&mut console.pending);
+        (lines, std::mem::take(&mut console.registry))
+    };
+
+    let mut custom = app
+        .world
+        .resources
+        .try_get_mut::<ConsoleCommands>()
+        .map(|mut custom| std::mem::take(&mut *custom))
+        .unwrap_or_default();
+
+    let mut output = Vec::with_capacity(lines.len());
+    for line in lines {
+        let result = execute(app, &mut registry, &mut custom, &line);
+        output.push((line, result));
+    }
+
+    if let Some(mut commands) = app.world.resources.try_get_mut::<ConsoleCommands>() {
+        *commands = custom;
+    }
+    let mut console = app.world.resources.get_mut::<Console>();
+    console.registry = registry;
+    for (line, result) in output {
+        console.print(format!("> {}", line));
+        match result {
+            Ok(text) if text.is_empty() => {}
+            Ok(text) => console.print(text),
+            Err(err) => console.print(format!("error: {}", err)),
+        }
+    }
+}
+
+/// System which spawns, despawns and updates the console ui
+fn update_console_ui_system(
+    mut commands: Commands,
+    mut console: ResMut<Console>,
+    custom: Option<Res<ConsoleCommands>>,
+    mut texts: Query<&mut Text>,
+) {
+    if !console.open {
+        if let Some(root) = console.root.take() {
+            commands.entity(root).despawn_recursive();
+            console.text = None;
+        }
+        return;
+    }
+
+    if console.root.is_none() {
+        let root = commands
+            .spawn_empty()
+            .insert(Node {
+                position: Position::Absolute,
+                z_index: i32::MAX,
+                width: Val::Vw(100.0),
+                height: Val::Vh(40.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                background_color: Color::new(0.0, 0.0, 0.0, 0.85),
+                ..Default::default()
+            })
+            .entity_id();
+
+        let mut text = None;
+        commands.entity(root).with_children(|p| {
+            let id = p
+                .spawn_empty()
+                .insert(Node {
+                    color: Some(color::WHITE),
+                    background_color: color::TRANSPARENT,
+                    ..Default::default()
+                })
+                .insert(Text::new(""))
+                .entity_id();
+            text = Some(id);
+        });
+
+        console.root = Some(root);
+        console.text = text;
+        console.dirty = true;
+    }
+
+    if !console.dirty {
+        return;
+    }
+
+    // text node is spawned with commands, so it might not exist yet
+    let Some(text) = console.text.and_then(|id| texts.get(id)) else {
+        return;
+    };
+
+    // the console is not clipped, only the latest lines which fit are shown
+    let skip = console.lines.len().saturating_sub(VISIBLE_LINES);
+    let mut content = String::new();
+    for line in console.lines.iter().skip(skip) {
+        content.push_str(line);
+        content.push('\n');
+    }
+    content.push_str("> ");
+    content.push_str(&console.input);
+    content.push('_');
+    if let Some(usage) = console.usage(custom.as_deref()) {
+        content.push_str("\nusage: ");
+        content.push_str(&usage);
+    }
+
+    text.content = content;
+    console.dirty = false;
+}
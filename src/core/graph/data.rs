@@ -1,8 +1,11 @@
+use std::sync::Arc;
+
 use crate::{
     assets::ShaderLoader,
     prelude::{Texture, World},
     render_assets::{
-        IntoRenderAsset, Pipeline, RenderAssetEntry, RenderAssets, pipeline::PipelineBuilder,
+        IntoRenderAsset, Pipeline, PipelineCache, PipelineKey, RenderAssetEntry, RenderAssets,
+        pipeline::PipelineBuilder,
     },
     renderer::newtype::RenderDevice,
 };
@@ -12,6 +15,10 @@ use super::{NodeColorTarget, NodeDepthTarget};
 pub struct NodeData {
     pub(crate) needs_regen: bool,
     pub pipeline: Option<Pipeline>,
+    /// Key of the current pipeline
+    pipeline_key: Option<PipelineKey>,
+    /// Key of the pipeline which replaces the current one once it's compiled
+    pending_pipeline: Option<PipelineKey>,
     pub color_target: Option<ColorTargetData>,
     pub depth_target: Option<DepthTargetData>,
 }
@@ -21,6 +28,8 @@ impl Default for NodeData {
         Self {
             needs_regen: true,
             pipeline: None,
+            pipeline_key: None,
+            pending_pipeline: None,
             color_target: None,
             depth_target: None,
         }
@@ -30,11 +39,15 @@ impl Default for NodeData {
 pub enum ColorTargetData {
     Texture(Texture),
     RAE(RenderAssetEntry<Texture>),
+    /// Texture shared with other transient targets
+    Transient(Arc<Texture>),
 }
 
 pub enum DepthTargetData {
     Texture(Texture),
     RAE(RenderAssetEntry<Texture>),
+    /// Texture shared with other transient targets
+    Transient(Arc<Texture>),
 }
 
 impl NodeData {
@@ -42,13 +55,46 @@ impl NodeData {
         Self::default()
     }
 
+    /// Take the pipeline from the cache. If it's not compiled yet, the current pipeline is kept
+    /// until it is, as long as they are compatible, otherwise the compilation is waited for.
     pub fn generate_pipeline(
         &mut self,
         device: &RenderDevice,
         shader_loader: &ShaderLoader,
         pipeline_builder: &PipelineBuilder,
+        pipelines: &mut PipelineCache,
     ) {
-        self.pipeline = Some(pipeline_builder.finish(device, shader_loader));
+        let key = pipelines.queue(pipeline_builder, device, shader_loader);
+        self.pending_pipeline = None;
+
+        if let Some(pipeline) = pipelines.get(&key) {
+            self.pipeline = Some(pipeline.clone());
+        } else if self.pipeline.is_some()
+            && self
+                .pipeline_key
+                .as_ref()
+                .is_some_and(|current| current.is_compatible(&key))
+        {
+            self.pending_pipeline = Some(key);
+            return;
+        } else {
+            let pipeline = pipelines.get_blocking(key.clone(), &pipeline_builder.label);
+            self.pipeline = Some(pipeline.clone());
+        }
+
+        self.pipeline_key = Some(key);
+    }
+
+    /// Replace the current pipeline with the pending one if it finished compiling
+    pub(crate) fn update_pipeline(&mut self, pipelines: &mut PipelineCache) {
+        let Some(key) = &self.pending_pipeline else {
+            return;
+        };
+
+        if let Some(pipeline) = pipelines.get(key) {
+            self.pipeline = Some(pipeline.clone());
+            self.pipeline_key = self.pending_pipeline.take();
+        }
     }
 
     pub fn generate_color_target(&mut self, world: &mut World, color_target: &NodeColorTarget) {
@@ -68,6 +114,9 @@ impl NodeData {
             NodeColorTarget::Node(_) | NodeColorTarget::Surface => {
                 // handle in the renderer
             }
+            NodeColorTarget::Transient(_) => {
+                // allocated by the graph
+            }
         }
     }
 
@@ -88,6 +137,9 @@ impl NodeData {
             NodeDepthTarget::Node(_) => {
                 // handle in the renderer
             }
+            NodeDepthTarget::Transient(_) => {
+                // allocated by the graph
+            }
         }
     }
 }
@@ -1,7 +1,11 @@
+use std::fmt::Write;
+
 use crate::{
     app::{App, Plugin},
-    core::graph::RenderGraph,
-    prelude::{Res, Time},
+    core::graph::{RenderGraph, RenderStats},
+    plugins::RenderPlugin,
+    prelude::*,
+    renderer::newtype::RenderDevice,
     system::phase,
 };
 
@@ -63,3 +67,186 @@ pub fn print_render_graph_topology(graph: &RenderGraph) {
     println!("\nGraph Nodes in Sequence:");
     println!("  {}", names.join(" -> "));
 }
+
+/// Plugin to debug single frames of the render graph, see [`FrameCapture`]
+pub struct FrameCapturePlugin;
+
+impl Plugin for FrameCapturePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FrameCapture>()
+            .register_system(frame_capture_system, phase::First)
+            .register_system(frame_dump_system, phase::PostRender);
+    }
+
+    fn dependencies(&self) -> Vec<PluginId> {
+        vec![PluginId::of::<RenderPlugin>()]
+    }
+}
+
+/// Triggers GPU captures and render graph dumps of single frames, by pressing the keys or
+/// requesting them from a system. Used as a resource.
+///
+/// A capture records everything submitted from the start of one frame to the start of the next,
+/// with the in-application API of a graphics debugger like RenderDoc. It does nothing if the app
+/// was not launched from the debugger.
+///
+/// A dump logs the render graph in execution order, with the targets, pipeline and draw calls of
+/// every node, see [`render_graph_report`].
+#[derive(Resource)]
+pub struct FrameCapture {
+    /// Key which captures the next frame, None to disable it
+    pub capture_key: Option<KeyCode>,
+    /// Key which dumps the current frame, None to disable it
+    pub dump_key: Option<KeyCode>,
+    capture_requested: bool,
+    capturing: bool,
+    dump_requested: bool,
+}
+
+impl Default for FrameCapture {
+    fn default() -> Self {
+        Self {
+            capture_key: Some(KeyCode::F9),
+            dump_key: Some(KeyCode::F10),
+            capture_requested: false,
+            capturing: false,
+            dump_requested: false,
+        }
+    }
+}
+
+impl FrameCapture {
+    /// Capture the next frame with the attached graphics debugger
+    #[inline]
+    pub fn capture_next_frame(&mut self) {
+        self.capture_requested = true;
+    }
+
+    /// Dump the render graph after it is executed in the current frame
+    #[inline]
+    pub fn dump_frame(&mut self) {
+        self.dump_requested = true;
+    }
+
+    /// Returns true while a frame is being captured
+    #[inline]
+    pub fn is_capturing(&self) -> bool {
+        self.capturing
+    }
+}
+
+/// System which ends the capture of the previous frame and starts a requested one
+fn frame_capture_system(
+    device: Res<RenderDevice>,
+    input: Option<Res<Input<KeyCode>>>,
+    mut capture: ResMut<FrameCapture>,
+) {
+    if let Some(input) = input {
+        if capture
+            .capture_key
+            .is_some_and(|key| input.just_pressed(key))
+        {
+            capture.capture_next_frame();
+        }
+        if capture.dump_key.is_some_and(|key| input.just_pressed(key)) {
+            capture.dump_frame();
+        }
+    }
+
+    // the previous frame was submitted and presented by now
+    if capture.capturing {
+        // Safety: the capture was started on the same device in the previous frame
+        unsafe { device.stop_graphics_debugger_capture() };
+        capture.capturing = false;
+        tracing::info!("Frame capture finished");
+    }
+
+    if capture.capture_requested {
+        // Safety: the capture is stopped at the start of the next frame
+        unsafe { device.start_graphics_debugger_capture() };
+        capture.capture_requested = false;
+        capture.capturing = true;
+    }
+}
+
+/// System which logs a requested render graph dump
+fn frame_dump_system(
+    graph: &mut RenderGraph,
+    stats: Option<Res<RenderStats>>,
+    mut capture: ResMut<FrameCapture>,
+) {
+    if !capture.dump_requested {
+        return;
+    }
+    capture.dump_requested = false;
+
+    // stats are missing until the graph was executed, e.g. in a headless runner
+    let Some(stats) = stats else {
+        tracing::warn!("Render graph was not executed, nothing to dump");
+        return;
+    };
+
+    tracing::info!("{}", render_graph_report(graph, &stats));
+}
+
+/// Returns the nodes of the render graph in execution order, with their targets, pipeline and the
+/// draw calls recorded in the last execution
+pub fn render_graph_report(graph: &RenderGraph, stats: &RenderStats) -> String {
+    let mut report = String::new();
+    let _ = writeln!(report, "Render graph, {} nodes:", graph.sorted.len());
+
+    for (i, node) in graph.sorted.iter().enumerate() {
+        // Safe because we don't mutate the graph
+        let node = unsafe { &**node };
+        let kind = if node.custom_system.is_some() {
+            "custom"
+        } else {
+            "standard"
+        };
+
+        let _ = writeln!(report, "({i}) {} [{kind}]", node.name);
+        let _ = writeln!(report, "  pipeline: {}", node.pipeline_builder.label);
+        let _ = writeln!(
+            report,
+            "  color: {}, load: {:?}",
+            node.color_target, node.color_ops.load
+        );
+        let _ = writeln!(
+            report,
+            "  depth: {}, load: {:?}",
+            node.depth_target,
+            node.depth_ops.map(|ops| ops.load)
+        );
+        if !node.after.is_empty() {
+            let _ = writeln!(report, "  after: {:?}", node.after);
+        }
+
+        match stats.nodes.iter().find(|stats| stats.name == node.name) {
+            Some(node_stats) => {
+                let _ = writeln!(
+                    report,
+                    "  draw calls: {}, bind group changes: {}",
+                    node_stats.draw_calls, node_stats.bind_group_changes
+                );
+            }
+            None => {
+                let _ = writeln!(report, "  not executed");
+            }
+        }
+    }
+
+    let (targets, textures) = graph.transient_counts();
+    if targets > 0 {
+        let _ = writeln!(
+            report,
+            "Transient targets: {targets} in {textures} textures, {} KiB",
+            graph.transient_memory_usage() / 1024
+        );
+    }
+    let _ = write!(
+        report,
+        "Total draw calls: {}, bind group changes: {}",
+        stats.draw_calls, stats.bind_group_changes
+    );
+    report
+}
@@ -1,33 +1,91 @@
+use std::{cell::Cell, ops::Deref};
+
 use wgpu::RenderPass;
 
 use crate::{
     assets::ShaderLoader,
     core::graph::NodeColorTarget,
     prelude::World,
-    renderer::newtype::{RenderCommandEncoder, RenderDevice, RenderSurfaceTextureView},
+    render_assets::PipelineCache,
+    renderer::newtype::{
+        RenderCommandEncoder, RenderDevice, RenderSurfaceConfiguration, RenderSurfaceTextureView,
+    },
+    system::ThreadPool,
 };
 
 use super::{
-    GraphNode, NodeDepthTarget, RenderGraph,
+    GraphNode, Msaa, NodeDepthTarget, RenderGraph,
     data::{ColorTargetData, DepthTargetData},
+    parallel::default_encoder_threads,
 };
 
+thread_local! {
+    /// Index of the [`RenderContext`] slot of the node recorded on this thread
+    static CONTEXT_SLOT: Cell<usize> = const { Cell::new(0) };
+}
+
 /// Extremely unsafe version of old `RenderGraphContext`, temporary solution for render systems.
 /// This data is stored in resources. TODO: refactor the whole render graph execution system
 ///
+/// Independent nodes are recorded on worker threads, so every thread has its own
+/// [`NodeContext`] slot which this resource dereferences to.
+///
 /// # Safety
 /// NOT SAFE AT ALL, use with extreme caution!
 ///
 /// In custom render systems, valid fields are:
 /// - `node` - pointer to the current node
 /// - `color_target` - pointer to the color target view, if any
+/// - `resolve_target` - pointer to the surface view if `color_target` is multisampled, see
+///   [`Msaa`](super::Msaa)
 /// - `depth_target` - pointer to the depth target view, if any
 ///
 /// In standard render systems, valid fields are:
 /// - `pass` - pointer to the current render pass
 /// - `node` - pointer to the current node
-#[derive(Default, Clone, crate::macros::Resource)]
+///
+/// In both, draw calls should be reported with [`NodeContext::record_draw_calls`], and bind group
+/// changes with [`NodeContext::record_bind_group_changes`].
+#[derive(Clone, crate::macros::Resource)]
 pub struct RenderContext {
+    /// Context of every thread which records nodes, the main thread uses the first one
+    slots: Vec<NodeContext>,
+}
+// # Safety
+// As unsafe as it gets
+unsafe impl Send for RenderContext {}
+unsafe impl Sync for RenderContext {}
+
+impl Default for RenderContext {
+    fn default() -> Self {
+        Self {
+            slots: vec![NodeContext::default()],
+        }
+    }
+}
+
+impl Deref for RenderContext {
+    type Target = NodeContext;
+
+    /// Returns the context of the node recorded on the current thread
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.slots[CONTEXT_SLOT.get()]
+    }
+}
+
+impl RenderContext {
+    /// Makes sure there are at least `count` slots
+    fn reserve_slots(&mut self, count: usize) {
+        if self.slots.len() < count {
+            self.slots.resize_with(count, NodeContext::default);
+        }
+    }
+}
+
+/// Context of a single node being recorded, see [`RenderContext`]
+#[derive(Default, Clone)]
+pub struct NodeContext {
     /// Current render pass, should be used to issue draw calls etc.
     pub pass: *mut RenderPass<'static>,
     /// Lifetime is tied to graph
@@ -35,19 +93,36 @@ pub struct RenderContext {
     /// Lifetime is tied to the node
     pub color_target: Option<*const wgpu::TextureView>,
     /// Lifetime is tied to the node
+    pub resolve_target: Option<*const wgpu::TextureView>,
+    /// Lifetime is tied to the node
     pub depth_target: Option<*const wgpu::TextureView>,
+    /// Draw calls recorded for the current node
+    draw_calls: Cell<u32>,
+    /// Bind group changes recorded for the current node
+    bind_group_changes: Cell<u32>,
 }
-// # Safety
-// As unsafe as it gets
-unsafe impl Send for RenderContext {}
-unsafe impl Sync for RenderContext {}
 
-impl RenderContext {
+impl NodeContext {
+    /// Record `count` draw calls issued by the current render system, see [`RenderStats`]
+    #[inline]
+    pub fn record_draw_calls(&self, count: u32) {
+        self.draw_calls.set(self.draw_calls.get() + count);
+    }
+
+    /// Record `count` bind group changes issued by the current render system, see
+    /// [`RenderStats`]
+    #[inline]
+    pub fn record_bind_group_changes(&self, count: u32) {
+        self.bind_group_changes
+            .set(self.bind_group_changes.get() + count);
+    }
+
     #[inline]
     fn clear(&mut self) {
         self.pass = std::ptr::null_mut();
         self.node = std::ptr::null_mut();
         self.color_target = None;
+        self.resolve_target = None;
         self.depth_target = None;
     }
 
@@ -56,10 +131,12 @@ impl RenderContext {
         &mut self,
         node: *mut GraphNode,
         color_target: Option<*const wgpu::TextureView>,
+        resolve_target: Option<*const wgpu::TextureView>,
         depth_target: Option<*const wgpu::TextureView>,
     ) {
         self.node = node;
         self.color_target = color_target;
+        self.resolve_target = resolve_target;
         self.depth_target = depth_target;
     }
 
@@ -68,14 +145,65 @@ impl RenderContext {
         self.pass = pass;
         self.node = node;
         self.color_target = None;
+        self.resolve_target = None;
         self.depth_target = None;
     }
 }
 
+/// Statistics of the last render graph execution. Used as a resource.
+#[derive(Default, Debug, Clone, crate::macros::Resource)]
+pub struct RenderStats {
+    /// Draw calls recorded by render systems
+    pub draw_calls: u32,
+    /// Bind group changes recorded by render systems, lower means better batched draws
+    pub bind_group_changes: u32,
+    /// Statistics of every executed node, in execution order
+    pub nodes: Vec<NodeRenderStats>,
+}
+
+/// Statistics of a single graph node in the last render graph execution
+#[derive(Debug, Clone)]
+pub struct NodeRenderStats {
+    pub name: String,
+    /// Draw calls recorded by the node's render system
+    pub draw_calls: u32,
+    /// Bind group changes recorded by the node's render system
+    pub bind_group_changes: u32,
+}
+
+/// Node recorded on a worker thread, pointers stay valid until the worker pool is waited on
+struct NodeTask {
+    graph: *const RenderGraph,
+    world: *mut World,
+    node: *mut GraphNode,
+    device: *const RenderDevice,
+    surface_texture_view: *const RenderSurfaceTextureView,
+    context: *mut NodeContext,
+    slot: usize,
+    encoder: *mut Option<RenderCommandEncoder>,
+}
+// # Safety
+// Nodes of a group don't conflict, and each task has its own context slot and encoder
+unsafe impl Send for NodeTask {}
+
+impl NodeTask {
+    fn run(self) {
+        CONTEXT_SLOT.set(self.slot);
+        unsafe {
+            *self.encoder = (*self.graph).record_node(
+                &mut *self.world,
+                &mut *self.node,
+                &*self.device,
+                &*self.surface_texture_view,
+                &mut *self.context,
+            );
+        }
+        CONTEXT_SLOT.set(0);
+    }
+}
+
 impl RenderGraph {
     pub(crate) fn execute(&mut self, world: &mut World) {
-        let sorted = self.sorted.iter().map(|n| unsafe { &mut **n });
-
         let device = world.resources.get::<RenderDevice>();
         let mut shader_loader = world.resources.get_mut::<ShaderLoader>();
         let surface_texture_view = world.resources.get::<RenderSurfaceTextureView>();
@@ -83,61 +211,174 @@ impl RenderGraph {
         if !world.resources.contains::<RenderContext>() {
             world.resources.insert(RenderContext::default())
         }
-        let mut render_context = world.resources.get_mut::<RenderContext>();
+        if !world.resources.contains::<RenderStats>() {
+            world.resources.insert(RenderStats::default())
+        }
+        if !world.resources.contains::<PipelineCache>() {
+            world.resources.insert(PipelineCache::default())
+        }
 
-        for node in sorted {
+        let samples = world
+            .resources
+            .try_get::<Msaa>()
+            .map_or(1, |msaa| msaa.samples());
+        let config = world.resources.get::<RenderSurfaceConfiguration>();
+        self.prepare_msaa(&device, samples, &config);
+        if self.transients.dirty {
+            self.allocate_transients(&device, (config.width, config.height));
+        }
+
+        let mut pipelines = world.resources.get_mut::<PipelineCache>();
+        for &node in &self.sorted {
+            let node = unsafe { &mut *node };
             if node.data.needs_regen {
-                node.generate_data(world, &device, &mut shader_loader);
+                node.generate_data(world, &device, &mut shader_loader, &mut pipelines);
+            } else {
+                node.data.update_pipeline(&mut pipelines);
             }
+        }
+
+        let mut render_context = world.resources.get_mut::<RenderContext>();
+        let mut node_stats = Vec::with_capacity(self.sorted.len());
+        let mut draw_calls = 0;
+        let mut bind_group_changes = 0;
 
-            let node_raw = node as *mut GraphNode;
-            let color_attachment = self.get_color_attachment(node, &surface_texture_view);
-            let depth_attachment = self.get_depth_attachment(node);
+        let groups = self.encoding_groups();
+        let widest = groups.iter().map(Vec::len).max().unwrap_or(0);
+        render_context.reserve_slots(widest + 1);
 
-            if node.custom_system.is_some() {
-                render_context.update_custom(
-                    node_raw,
-                    color_attachment.as_ref().map(|x| x.view as *const _),
-                    depth_attachment.as_ref().map(|x| x.view as *const _),
+        let graph = self as *const RenderGraph;
+        let pool = self
+            .encoders
+            .get_or_insert_with(|| ThreadPool::new(default_encoder_threads()));
+
+        // groups don't follow the sorted order, e.g. nodes of a level are regrouped by conflicts,
+        // so encoders are kept by sorted index until all nodes are recorded
+        let mut sorted_encoders: Vec<Option<RenderCommandEncoder>> =
+            self.sorted.iter().map(|_| None).collect();
+
+        for group in groups {
+            let mut encoders: Vec<Option<RenderCommandEncoder>> =
+                group.iter().map(|_| None).collect();
+
+            if group.len() == 1 {
+                let node = unsafe { &mut *self.sorted[group[0]] };
+                encoders[0] = unsafe { &*graph }.record_node(
+                    world,
+                    node,
+                    &device,
+                    &surface_texture_view,
+                    &mut render_context.slots[0],
                 );
+            } else {
+                for (i, &index) in group.iter().enumerate() {
+                    let task = NodeTask {
+                        graph,
+                        world,
+                        node: self.sorted[index],
+                        device: &*device,
+                        surface_texture_view: &*surface_texture_view,
+                        context: &mut render_context.slots[i + 1],
+                        slot: i + 1,
+                        encoder: &mut encoders[i],
+                    };
+                    pool.submit(Box::new(move || task.run()));
+                }
+                pool.wait_all();
+            }
 
-                let custom_system = node.custom_system.as_mut().unwrap();
-                custom_system.run(world);
-                custom_system.apply(world);
-                continue;
+            // changes are applied in the sorted order of the group
+            for (i, (&index, encoder)) in group.iter().zip(encoders).enumerate() {
+                let node = unsafe { &mut *self.sorted[index] };
+                match node.custom_system.as_mut() {
+                    Some(custom_system) => custom_system.apply(world),
+                    None => node.system.apply(world),
+                }
+                sorted_encoders[index] = encoder;
+
+                let slot = if group.len() == 1 { 0 } else { i + 1 };
+                let context = &mut render_context.slots[slot];
+                draw_calls += context.draw_calls.get();
+                bind_group_changes += context.bind_group_changes.get();
+                node_stats.push(NodeRenderStats {
+                    name: node.name.clone(),
+                    draw_calls: context.draw_calls.get(),
+                    bind_group_changes: context.bind_group_changes.get(),
+                });
+                context.clear();
             }
+        }
+
+        // encoders are submitted in the sorted order, so nodes sharing a target, e.g. the
+        // surface, draw in the order of their dependencies
+        for encoder in sorted_encoders.into_iter().flatten() {
+            world.render_command_queue.push(encoder);
+        }
+
+        let mut stats = world.resources.get_mut::<RenderStats>();
+        stats.draw_calls = draw_calls;
+        stats.bind_group_changes = bind_group_changes;
+        stats.nodes = node_stats;
+    }
+
+    /// Run the system of `node` with `context`, and return the encoder of a standard node. Its
+    /// changes are applied later, on the main thread.
+    fn record_node(
+        &self,
+        world: &mut World,
+        node: &mut GraphNode,
+        device: &RenderDevice,
+        surface_texture_view: &RenderSurfaceTextureView,
+        context: &mut NodeContext,
+    ) -> Option<RenderCommandEncoder> {
+        context.draw_calls.set(0);
+        context.bind_group_changes.set(0);
 
-            let mut encoder = RenderCommandEncoder::new(&device, node.name.as_str());
-            // Safety: casting to static lifetime for the render context, dropped after use
-            let encoder_ptr = unsafe { &mut *(&mut encoder as *mut RenderCommandEncoder) };
-            let mut render_pass = encoder_ptr.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some(&format!("{} render pass", node.name)),
-                color_attachments: &[color_attachment]
-                    .into_iter()
-                    .filter(|x| x.is_some())
-                    .collect::<Vec<_>>(),
-                depth_stencil_attachment: depth_attachment,
-                occlusion_query_set: None,
-                timestamp_writes: None,
-            });
-
-            render_pass.set_pipeline(
-                node.data
-                    .pipeline
+        let node_raw = node as *mut GraphNode;
+        let color_attachment = self.get_color_attachment(node, surface_texture_view);
+        let depth_attachment = self.get_depth_attachment(node);
+
+        if let Some(custom_system) = unsafe { &mut *node_raw }.custom_system.as_mut() {
+            context.update_custom(
+                node_raw,
+                color_attachment.as_ref().map(|x| x.view as *const _),
+                color_attachment
                     .as_ref()
-                    .expect("Pipeline should have been generated by now")
-                    .render_pipeline(),
+                    .and_then(|x| x.resolve_target.map(|view| view as *const _)),
+                depth_attachment.as_ref().map(|x| x.view as *const _),
             );
 
-            render_context.update_standard(&mut render_pass, node_raw);
-            node.system.run(world);
-            node.system.apply(world);
-
-            drop(render_pass);
-            world.render_command_queue.push(encoder);
+            custom_system.run(world);
+            return None;
         }
 
-        render_context.clear();
+        let mut encoder = RenderCommandEncoder::new(device, node.name.as_str());
+        // Safety: casting to static lifetime for the render context, dropped after use
+        let encoder_ptr = unsafe { &mut *(&mut encoder as *mut RenderCommandEncoder) };
+        let mut render_pass = encoder_ptr.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some(&format!("{} render pass", node.name)),
+            color_attachments: &[color_attachment]
+                .into_iter()
+                .filter(|x| x.is_some())
+                .collect::<Vec<_>>(),
+            depth_stencil_attachment: depth_attachment,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+
+        render_pass.set_pipeline(
+            node.data
+                .pipeline
+                .as_ref()
+                .expect("Pipeline should have been generated by now")
+                .render_pipeline(),
+        );
+
+        context.update_standard(&mut render_pass, node_raw);
+        unsafe { &mut *node_raw }.system.run(world);
+
+        drop(render_pass);
+        Some(encoder)
     }
 
     fn get_color_attachment<'a>(
@@ -149,10 +390,25 @@ impl RenderGraph {
             Some(ref target_data) => match target_data {
                 ColorTargetData::Texture(texture) => &texture.view,
                 ColorTargetData::RAE(rae) => &rae.view,
+                ColorTargetData::Transient(texture) => &texture.view,
             },
             None => match &node.color_target {
                 NodeColorTarget::None => return None,
-                NodeColorTarget::Surface => surface_texture_view,
+                NodeColorTarget::Surface => {
+                    let graph = self as *const RenderGraph;
+                    let graph = unsafe { &*graph };
+
+                    // multisampled nodes render into the shared texture, resolved to the surface
+                    if let Some(msaa_view) = graph.msaa_view().filter(|_| node.sample_count > 1) {
+                        return Some(wgpu::RenderPassColorAttachment {
+                            view: msaa_view,
+                            depth_slice: None,
+                            resolve_target: Some(surface_texture_view),
+                            ops: node.color_ops,
+                        });
+                    }
+                    surface_texture_view
+                }
                 NodeColorTarget::Node(name) => {
                     let graph = self as *const RenderGraph;
                     let graph = unsafe { &*graph };
@@ -192,6 +448,7 @@ impl RenderGraph {
             Some(ref target_data) => match target_data {
                 DepthTargetData::Texture(texture) => &texture.view,
                 DepthTargetData::RAE(rae) => &rae.view,
+                DepthTargetData::Transient(texture) => &texture.view,
             },
             None => match &node.depth_target {
                 NodeDepthTarget::None => return None,
@@ -2,7 +2,9 @@ use std::collections::{HashMap, HashSet};
 
 use winit::dpi::PhysicalSize;
 
-use super::GraphNode;
+use crate::{prelude::EntityId, system::ThreadPool};
+
+use super::{GraphNode, msaa::MsaaTarget, transient::TransientPool, window::WindowTargets};
 
 /// Directed acyclic graph of render passes and their dependencies
 #[derive(Default)]
@@ -10,6 +12,18 @@ pub struct RenderGraph {
     pub(crate) nodes: HashMap<String, GraphNode>,
     /// Topological sort of `self.nodes`, updated on each node add/remove
     pub(crate) sorted: Vec<*mut GraphNode>,
+    /// Textures of the transient node targets
+    pub(crate) transients: TransientPool,
+    /// Multisampled color target of the nodes which render to the surface
+    pub(crate) msaa: MsaaTarget,
+    /// Threads which record independent nodes, created on the first execution
+    pub(crate) encoders: Option<ThreadPool>,
+    /// Size of the surface the size dependent targets were created for
+    pub(crate) size: PhysicalSize<u32>,
+    /// Window whose size dependent targets are in the nodes
+    pub(crate) window: Option<EntityId>,
+    /// Size dependent targets of the other windows the graph renders to
+    pub(crate) windows: HashMap<EntityId, WindowTargets>,
 }
 
 impl RenderGraph {
@@ -57,6 +71,9 @@ impl RenderGraph {
         }
 
         self.sorted = sorted;
+        self.transients.dirty = true;
+        self.msaa.dirty = true;
+        self.windows.clear();
     }
 
     /// Populates the `before` dependencies with the respective `after` dependencies from nodes
@@ -120,5 +137,25 @@ impl RenderGraph {
         for node in self.nodes.values_mut() {
             node.resize(&size);
         }
+        self.transients.dirty = true;
+        self.size = size;
+    }
+
+    /// Resize the graph if its targets were created for a surface of a different size, e.g. when
+    /// the rendered window was resized
+    pub(crate) fn fit(&mut self, size: PhysicalSize<u32>) {
+        if self.size != size {
+            self.resize(size);
+        }
+    }
+
+    /// Marks every node for regeneration, their pipelines and targets are recreated on the next
+    /// execution
+    pub(crate) fn invalidate(&mut self) {
+        for node in self.nodes.values_mut() {
+            node.data.needs_regen = true;
+        }
+        self.transients.clear();
+        self.windows.clear();
     }
 }
@@ -2,11 +2,17 @@ mod data;
 pub mod debug;
 mod execute;
 mod graph;
+mod msaa;
 mod node;
+mod parallel;
 mod targets;
+mod transient;
+mod window;
 
 pub use data::NodeData;
-pub use execute::RenderContext;
+pub use execute::{NodeContext, NodeRenderStats, RenderContext, RenderStats};
 pub use graph::RenderGraph;
+pub use msaa::Msaa;
 pub use node::{GraphNode, GraphNodeBuilder};
 pub use targets::{NodeColorTarget, NodeDepthTarget};
+pub use transient::{TransientSize, TransientTexture};
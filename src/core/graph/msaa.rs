@@ -0,0 +1,137 @@
+use crate::{macros::Resource, prelude::Texture, renderer::newtype::RenderDevice};
+
+use super::{NodeColorTarget, RenderGraph};
+
+/// Multisample anti-aliasing of the nodes which render to the surface. Used as a resource, the
+/// [`RenderGraph`](super::RenderGraph) recreates their pipelines and targets when it changes.
+///
+/// Nodes render into a shared multisampled color texture, which is resolved to the surface by
+/// each node. Their depth targets must be multisampled too, owned and transient depth targets are
+/// recreated with the matching sample count, depth targets from the asset manager are not.
+///
+/// # Note
+/// [`Msaa::X2`] and [`Msaa::X8`] are not supported by every adapter and surface format.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Msaa {
+    #[default]
+    Off,
+    X2,
+    X4,
+    X8,
+}
+
+impl Msaa {
+    /// Returns the sample count of targets and pipelines
+    #[inline]
+    pub fn samples(self) -> u32 {
+        match self {
+            Msaa::Off => 1,
+            Msaa::X2 => 2,
+            Msaa::X4 => 4,
+            Msaa::X8 => 8,
+        }
+    }
+}
+
+/// Multisampled color texture of a [`RenderGraph`], and the sample count applied to its nodes
+#[derive(Default)]
+pub(crate) struct MsaaTarget {
+    /// Applied sample count, zero before the first execution
+    samples: u32,
+    /// Whether the sample count has to be applied again, e.g. after nodes were added
+    pub(crate) dirty: bool,
+    /// Texture and its width, height, format and sample count
+    pub(crate) color: Option<((u32, u32, wgpu::TextureFormat, u32), Texture)>,
+}
+
+impl RenderGraph {
+    /// Returns true if the color target of the `name` node is the surface, following node
+    /// references
+    fn renders_to_surface(&self, name: &str) -> bool {
+        self.target_owner(name, false)
+            .and_then(|owner| self.nodes.get(owner))
+            .is_some_and(|node| matches!(node.color_target, NodeColorTarget::Surface))
+    }
+
+    /// Applies `samples` to the nodes which render to the surface, and recreates the shared
+    /// multisampled color texture if the surface or sample count changed
+    pub(crate) fn prepare_msaa(
+        &mut self,
+        device: &RenderDevice,
+        samples: u32,
+        config: &wgpu::SurfaceConfiguration,
+    ) {
+        if self.msaa.samples != samples || self.msaa.dirty {
+            let names: Vec<_> = self.nodes.keys().cloned().collect();
+            for name in names {
+                let node_samples = if self.renders_to_surface(&name) {
+                    samples
+                } else {
+                    1
+                };
+                let node = self.nodes.get_mut(&name).expect("Node should exist");
+                if node.sample_count != node_samples {
+                    node.set_sample_count(node_samples);
+                    self.transients.dirty = true;
+                }
+            }
+
+            self.msaa.samples = samples;
+            self.msaa.dirty = false;
+            // targets of other windows were created with the previous sample count
+            self.windows.clear();
+        }
+
+        if samples == 1 {
+            self.msaa.color = None;
+            return;
+        }
+
+        let key = (
+            config.width.max(1),
+            config.height.max(1),
+            config.format,
+            samples,
+        );
+        if self
+            .msaa
+            .color
+            .as_ref()
+            .is_some_and(|(current, _)| *current == key)
+        {
+            return;
+        }
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("msaa color texture"),
+            size: wgpu::Extent3d {
+                width: key.0,
+                height: key.1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: samples,
+            dimension: wgpu::TextureDimension::D2,
+            format: config.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor::default());
+
+        self.msaa.color = Some((
+            key,
+            Texture {
+                texture,
+                view,
+                sampler,
+            },
+        ));
+    }
+
+    /// Returns the view of the multisampled color texture, if MSAA is enabled
+    #[inline]
+    pub(crate) fn msaa_view(&self) -> Option<&wgpu::TextureView> {
+        self.msaa.color.as_ref().map(|(_, texture)| &texture.view)
+    }
+}
@@ -4,7 +4,7 @@ use crate::{
     assets::ShaderLoader,
     palette,
     prelude::{IntoSystem, World},
-    render_assets::pipeline::PipelineBuilder,
+    render_assets::{PipelineCache, pipeline::PipelineBuilder},
     renderer::newtype::RenderDevice,
     system::{System, SystemParam},
 };
@@ -26,6 +26,11 @@ pub struct GraphNode {
     pub after: Vec<String>,
     /// List of nodes which must render after this node
     pub before: Vec<String>,
+    /// List of nodes whose targets are sampled by this node, keeps their transient targets alive
+    pub reads: Vec<String>,
+    /// Sample count of the pipeline and targets, set by the graph from the [`Msaa`](super::Msaa)
+    /// resource if the node renders to the surface
+    pub sample_count: u32,
     pub data: NodeData,
 }
 
@@ -54,6 +59,8 @@ impl GraphNode {
             }),
             after: Vec::new(),
             before: Vec::new(),
+            reads: Vec::new(),
+            sample_count: 1,
             data: NodeData::new(),
         }
     }
@@ -64,9 +71,10 @@ impl GraphNode {
         world: &mut World,
         device: &RenderDevice,
         shader_loader: &mut ShaderLoader,
+        pipelines: &mut PipelineCache,
     ) {
         self.data
-            .generate_pipeline(device, shader_loader, &self.pipeline_builder);
+            .generate_pipeline(device, shader_loader, &self.pipeline_builder, pipelines);
         self.data.generate_color_target(world, &self.color_target);
         self.data.generate_depth_target(world, &self.depth_target);
 
@@ -92,6 +100,24 @@ impl GraphNode {
             self.data.needs_regen = true;
         }
     }
+
+    /// Set the sample count of the pipeline and the owned depth target, and mark the node for
+    /// regeneration if it changed
+    pub(crate) fn set_sample_count(&mut self, sample_count: u32) {
+        if self.sample_count == sample_count {
+            return;
+        }
+
+        self.sample_count = sample_count;
+        self.pipeline_builder.sample_count = sample_count;
+        if let NodeDepthTarget::Owned(image) = &mut self.depth_target
+            && let Some(texture) = &mut image.texture_descriptor
+        {
+            texture.sample_count = sample_count;
+        }
+
+        self.data.needs_regen = true;
+    }
 }
 
 /// Helper struct to create a [`GraphNode`]
@@ -106,6 +132,7 @@ pub struct GraphNodeBuilder {
     depth_ops: Option<wgpu::Operations<f32>>,
     after: Vec<String>,
     before: Vec<String>,
+    reads: Vec<String>,
 }
 
 impl GraphNodeBuilder {
@@ -127,6 +154,7 @@ impl GraphNodeBuilder {
             }),
             after: Vec::new(),
             before: Vec::new(),
+            reads: Vec::new(),
         }
     }
 
@@ -191,6 +219,15 @@ impl GraphNodeBuilder {
         self
     }
 
+    /// This node samples the targets of the `name` node, so their transient textures are not
+    /// reused by other nodes before this node runs. Also adds `name` as a dependency.
+    pub fn read_target(mut self, name: &str) -> Self {
+        if !self.reads.contains(&name.to_string()) {
+            self.reads.push(name.to_string());
+        }
+        self.run_after(name)
+    }
+
     pub fn build(mut self) -> GraphNode {
         let err = |field: &str| {
             format!(
@@ -230,6 +267,8 @@ impl GraphNodeBuilder {
             depth_ops: self.depth_ops,
             after: self.after,
             before: self.before,
+            reads: self.reads,
+            sample_count: 1,
             data: NodeData::new(),
         }
     }
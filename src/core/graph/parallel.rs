@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+
+use crate::system::ThreadPool;
+
+use super::{NodeColorTarget, NodeDepthTarget, RenderGraph};
+
+/// Returns the amount of threads recording graph nodes, none on the web
+pub(super) fn default_encoder_threads() -> usize {
+    if cfg!(target_arch = "wasm32") {
+        0
+    } else {
+        std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+    }
+}
+
+impl RenderGraph {
+    /// Record all nodes on the calling thread, in their sorted order
+    pub fn set_single_threaded(&mut self) {
+        self.encoders = Some(ThreadPool::new(0));
+    }
+
+    /// Splits the sorted nodes into groups which are recorded in parallel, returned as indices
+    /// into `self.sorted`.
+    ///
+    /// A node is placed one level after the deepest node it depends on, through `after`,
+    /// `before`, node targets or reads. Nodes of a level are split further if their systems
+    /// conflict, the same way the scheduler batches systems.
+    pub(crate) fn encoding_groups(&self) -> Vec<Vec<usize>> {
+        let normalized = self.normalize_dependencies();
+        let nodes = self
+            .sorted
+            .iter()
+            .map(|node| unsafe { &**node })
+            .collect::<Vec<_>>();
+        let indices = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (node.name.as_str(), i))
+            .collect::<HashMap<_, _>>();
+
+        let mut levels = vec![0; nodes.len()];
+        for (i, node) in nodes.iter().enumerate() {
+            let mut dependencies = normalized
+                .get(&node.name)
+                .map(|names| names.iter().map(String::as_str).collect::<Vec<_>>())
+                .unwrap_or_default();
+            if let NodeColorTarget::Node(name) = &node.color_target {
+                dependencies.push(name);
+            }
+            if let NodeDepthTarget::Node(name) = &node.depth_target {
+                dependencies.push(name);
+            }
+            dependencies.extend(node.reads.iter().map(String::as_str));
+
+            // dependencies sorted after the node are recorded after it anyway
+            levels[i] = dependencies
+                .into_iter()
+                .filter_map(|name| indices.get(name))
+                .filter(|&&dependency| dependency < i)
+                .map(|&dependency| levels[dependency] + 1)
+                .max()
+                .unwrap_or(0);
+        }
+
+        let depth = levels.iter().max().map_or(0, |level| level + 1);
+        let mut groups = Vec::new();
+        for level in 0..depth {
+            let mut level_groups: Vec<Vec<usize>> = Vec::new();
+            for i in (0..nodes.len()).filter(|&i| levels[i] == level) {
+                let system = nodes[i].custom_system.as_ref().unwrap_or(&nodes[i].system);
+                let group = level_groups.iter_mut().find(|group| {
+                    group.iter().all(|&other| {
+                        let other = nodes[other]
+                            .custom_system
+                            .as_ref()
+                            .unwrap_or(&nodes[other].system);
+                        !system.conflicts_with(other)
+                    })
+                });
+
+                match group {
+                    Some(group) => group.push(i),
+                    None => level_groups.push(vec![i]),
+                }
+            }
+            groups.extend(level_groups);
+        }
+
+        groups
+    }
+}
@@ -2,6 +2,8 @@ use std::fmt::Display;
 
 use crate::{assets::Handle, prelude::Image};
 
+use super::TransientTexture;
+
 /// Describes the color target view to use in a render pass node
 pub enum NodeColorTarget {
     /// Will use the swapchain
@@ -15,6 +17,9 @@ pub enum NodeColorTarget {
     Image(Handle<Image>),
     /// Use an owned custom image as the color target
     Owned(Image),
+    /// Use a texture allocated by the graph, which shares memory with other transient targets
+    /// not used at the same time, see [`TransientTexture`]
+    Transient(TransientTexture),
     /// Use the output of another node as the target
     ///
     /// # Note
@@ -36,6 +41,9 @@ pub enum NodeDepthTarget {
     Image(Handle<Image>),
     /// Use an owned custom image as the depth target
     Owned(Image),
+    /// Use a texture allocated by the graph, which shares memory with other transient targets
+    /// not used at the same time, see [`TransientTexture`]
+    Transient(TransientTexture),
     /// Use the output of another node as the target
     ///
     /// # Note
@@ -53,6 +61,9 @@ impl Display for NodeColorTarget {
             NodeColorTarget::None => write!(f, "NodeColorTarget::None"),
             NodeColorTarget::Image(_) => write!(f, "NodeColorTarget::Image(..)"),
             NodeColorTarget::Owned(_) => write!(f, "NodeColorTarget::Owned(..)"),
+            NodeColorTarget::Transient(texture) => {
+                write!(f, "NodeColorTarget::Transient({:?})", texture.format)
+            }
             NodeColorTarget::Node(name) => write!(f, "NodeColorTarget::Node({})", name),
         }
     }
@@ -64,6 +75,9 @@ impl Display for NodeDepthTarget {
             NodeDepthTarget::None => write!(f, "NodeDepthTarget::None"),
             NodeDepthTarget::Image(_) => write!(f, "NodeDepthTarget::Image(..)"),
             NodeDepthTarget::Owned(_) => write!(f, "NodeDepthTarget::Owned(..)"),
+            NodeDepthTarget::Transient(texture) => {
+                write!(f, "NodeDepthTarget::Transient({:?})", texture.format)
+            }
             NodeDepthTarget::Node(name) => write!(f, "NodeDepthTarget::Node({})", name),
         }
     }
@@ -0,0 +1,336 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    prelude::{Image, Texture},
+    renderer::newtype::RenderDevice,
+};
+
+use super::{
+    GraphNode, NodeColorTarget, NodeDepthTarget, RenderGraph,
+    data::{ColorTargetData, DepthTargetData},
+};
+
+/// Size of a [`TransientTexture`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransientSize {
+    /// Fraction of the surface size, e.g. `0.5` for half resolution. Resized with the surface.
+    Surface(f32),
+    /// Fixed size in pixels
+    Fixed(u32, u32),
+}
+
+/// Description of a transient render target. Its texture only exists from the first to the last
+/// node which uses it, and it shares memory with other transient targets whose lifetimes don't
+/// overlap, as long as their size, format, usage and sample count match.
+///
+/// A target is used by the node which renders into it, by nodes which use it with
+/// [`NodeColorTarget::Node`] or [`NodeDepthTarget::Node`], and by nodes which sample it, declared
+/// with [`GraphNodeBuilder::read_target`](super::GraphNodeBuilder::read_target). The content is
+/// undefined before the first use, so the first node should clear it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransientTexture {
+    pub size: TransientSize,
+    pub format: wgpu::TextureFormat,
+    pub usage: wgpu::TextureUsages,
+    pub sample_count: u32,
+}
+
+impl TransientTexture {
+    /// Create a new surface sized transient texture, which can be rendered to and sampled
+    pub fn new(format: wgpu::TextureFormat) -> Self {
+        Self {
+            size: TransientSize::Surface(1.0),
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            sample_count: 1,
+        }
+    }
+
+    /// Returns self with `size`
+    #[must_use]
+    pub fn with_size(mut self, size: TransientSize) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Returns self with `usage`, render attachment usage is always added
+    #[must_use]
+    pub fn with_usage(mut self, usage: wgpu::TextureUsages) -> Self {
+        self.usage = usage | wgpu::TextureUsages::RENDER_ATTACHMENT;
+        self
+    }
+
+    /// Returns self with `sample_count` for multisampling
+    #[must_use]
+    pub fn with_sample_count(mut self, sample_count: u32) -> Self {
+        self.sample_count = sample_count;
+        self
+    }
+
+    /// Returns the physical texture properties for a surface of `surface_size`
+    fn key(&self, surface_size: (u32, u32)) -> TextureKey {
+        let (width, height) = match self.size {
+            TransientSize::Surface(scale) => (
+                (surface_size.0 as f32 * scale).round() as u32,
+                (surface_size.1 as f32 * scale).round() as u32,
+            ),
+            TransientSize::Fixed(width, height) => (width, height),
+        };
+
+        TextureKey {
+            width: width.max(1),
+            height: height.max(1),
+            format: self.format,
+            usage: self.usage,
+            sample_count: self.sample_count,
+        }
+    }
+}
+
+/// Properties which have to match for transient targets to share a texture
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct TextureKey {
+    width: u32,
+    height: u32,
+    format: wgpu::TextureFormat,
+    usage: wgpu::TextureUsages,
+    sample_count: u32,
+}
+
+impl TextureKey {
+    fn create(&self, device: &RenderDevice) -> Texture {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("transient texture"),
+            size: wgpu::Extent3d {
+                width: self.width,
+                height: self.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: self.sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.format,
+            usage: self.usage,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&Image::default_view_descriptor());
+        let sampler = device.create_sampler(&Image::default_sampler_descriptor());
+
+        Texture {
+            texture,
+            view,
+            sampler,
+        }
+    }
+
+    /// Returns the size of the texture in bytes
+    fn memory_usage(&self) -> u64 {
+        let block_size = self.format.block_copy_size(None).unwrap_or(4) as u64;
+        self.width as u64 * self.height as u64 * block_size * self.sample_count as u64
+    }
+}
+
+/// Transient target of a node, and the range of sorted node indices it's used in
+struct TransientUse {
+    node: *mut GraphNode,
+    depth: bool,
+    key: TextureKey,
+    first: usize,
+    last: usize,
+}
+
+/// Textures shared by the transient targets of a [`RenderGraph`]
+#[derive(Default)]
+pub(crate) struct TransientPool {
+    /// Allocated textures
+    textures: Vec<(TextureKey, Arc<Texture>)>,
+    /// Amount of transient targets in the last allocation
+    targets: usize,
+    /// Incremented on every allocation
+    pub(crate) generation: u64,
+    /// Whether the targets have to be allocated again, e.g. after the graph or surface changed
+    pub(crate) dirty: bool,
+}
+
+impl TransientPool {
+    /// Returns the amount of transient targets and the amount of textures they share
+    #[inline]
+    pub fn counts(&self) -> (usize, usize) {
+        (self.targets, self.textures.len())
+    }
+
+    /// Returns the memory used by the transient textures in bytes
+    pub fn memory_usage(&self) -> u64 {
+        self.textures
+            .iter()
+            .map(|(key, _)| key.memory_usage())
+            .sum()
+    }
+
+    /// Drops all textures, e.g. after the device was lost, and marks the pool for allocation
+    pub fn clear(&mut self) {
+        self.textures.clear();
+        self.dirty = true;
+    }
+}
+
+impl RenderGraph {
+    /// Returns the name of the node which owns the color or depth target `name` renders to,
+    /// following [`NodeColorTarget::Node`] and [`NodeDepthTarget::Node`] references
+    pub(super) fn target_owner<'a>(&'a self, mut name: &'a str, depth: bool) -> Option<&'a str> {
+        // bounded, so reference cycles don't loop forever
+        for _ in 0..=self.nodes.len() {
+            let node = self.nodes.get(name)?;
+            let next = match (depth, &node.color_target, &node.depth_target) {
+                (false, NodeColorTarget::Node(next), _) => next,
+                (true, _, NodeDepthTarget::Node(next)) => next,
+                _ => return Some(name),
+            };
+            name = next;
+        }
+        None
+    }
+
+    /// Assigns textures to the transient targets of all nodes. Targets whose node index ranges
+    /// don't overlap share a texture if their properties match, textures of the previous
+    /// allocation are reused.
+    pub(crate) fn allocate_transients(&mut self, device: &RenderDevice, surface_size: (u32, u32)) {
+        let mut uses: HashMap<(&str, bool), TransientUse> = HashMap::new();
+        for (i, &node) in self.sorted.iter().enumerate() {
+            let graph_node = unsafe { &*node };
+            let mut targets = Vec::new();
+            if let NodeColorTarget::Transient(texture) = &graph_node.color_target {
+                targets.push((false, texture));
+            }
+            if let NodeDepthTarget::Transient(texture) = &graph_node.depth_target {
+                targets.push((true, texture));
+            }
+
+            for (depth, texture) in targets {
+                let mut key = texture.key(surface_size);
+                if depth {
+                    // depth targets of multisampled nodes have to match their color target
+                    key.sample_count = key.sample_count.max(graph_node.sample_count);
+                }
+                uses.insert(
+                    (graph_node.name.as_str(), depth),
+                    TransientUse {
+                        node,
+                        depth,
+                        key,
+                        first: i,
+                        last: i,
+                    },
+                );
+            }
+        }
+
+        // extend the lifetimes to the nodes which render into or read the targets
+        for (i, node) in self.sorted.iter().enumerate() {
+            let node = unsafe { &**node };
+            let mut used = Vec::new();
+            if let NodeColorTarget::Node(name) = &node.color_target {
+                used.push((name.as_str(), false));
+            }
+            if let NodeDepthTarget::Node(name) = &node.depth_target {
+                used.push((name.as_str(), true));
+            }
+            for name in &node.reads {
+                used.push((name.as_str(), false));
+                used.push((name.as_str(), true));
+            }
+
+            for (name, depth) in used {
+                let Some(owner) = self.target_owner(name, depth) else {
+                    continue;
+                };
+                if let Some(transient) = uses.get_mut(&(owner, depth)) {
+                    // a node which runs before the owner still keeps the texture alive
+                    transient.first = transient.first.min(i);
+                    transient.last = transient.last.max(i);
+                }
+            }
+        }
+
+        let mut uses: Vec<_> = uses.into_values().collect();
+        uses.sort_by_key(|transient| (transient.first, self.sorted.len() - transient.last));
+
+        let mut previous = std::mem::take(&mut self.transients.textures);
+        let mut textures: Vec<(TextureKey, Arc<Texture>, usize)> = Vec::new();
+
+        for transient in &uses {
+            let index = match textures
+                .iter()
+                .position(|(key, _, last)| *key == transient.key && *last < transient.first)
+            {
+                Some(index) => index,
+                None => {
+                    let texture = match previous.iter().position(|(key, _)| *key == transient.key) {
+                        Some(index) => previous.swap_remove(index).1,
+                        None => Arc::new(transient.key.create(device)),
+                    };
+                    textures.push((transient.key, texture, 0));
+                    textures.len() - 1
+                }
+            };
+            textures[index].2 = transient.last;
+
+            let texture = textures[index].1.clone();
+            let data = &mut unsafe { &mut *transient.node }.data;
+            if transient.depth {
+                data.depth_target = Some(DepthTargetData::Transient(texture));
+            } else {
+                data.color_target = Some(ColorTargetData::Transient(texture));
+            }
+        }
+
+        self.transients.targets = uses.len();
+        self.transients.textures = textures
+            .into_iter()
+            .map(|(key, texture, _)| (key, texture))
+            .collect();
+        self.transients.generation += 1;
+        self.transients.dirty = false;
+    }
+
+    /// Returns the texture of the color target of the `name` node, following node references.
+    /// Used to sample the output of a node, e.g. a transient target in a post processing chain.
+    pub fn color_texture(&self, name: &str) -> Option<&Texture> {
+        let node = self.nodes.get(self.target_owner(name, false)?)?;
+        match node.data.color_target.as_ref()? {
+            ColorTargetData::Texture(texture) => Some(texture),
+            ColorTargetData::RAE(rae) => Some(rae),
+            ColorTargetData::Transient(texture) => Some(texture),
+        }
+    }
+
+    /// Returns the texture of the depth target of the `name` node, following node references
+    pub fn depth_texture(&self, name: &str) -> Option<&Texture> {
+        let node = self.nodes.get(self.target_owner(name, true)?)?;
+        match node.data.depth_target.as_ref()? {
+            DepthTargetData::Texture(texture) => Some(texture),
+            DepthTargetData::RAE(rae) => Some(rae),
+            DepthTargetData::Transient(texture) => Some(texture),
+        }
+    }
+
+    /// Returns the amount of transient targets and the amount of textures they share, after the
+    /// last execution
+    #[inline]
+    pub fn transient_counts(&self) -> (usize, usize) {
+        self.transients.counts()
+    }
+
+    /// Returns the memory used by transient textures in bytes, after the last execution
+    #[inline]
+    pub fn transient_memory_usage(&self) -> u64 {
+        self.transients.memory_usage()
+    }
+
+    /// Returns a number which changes whenever transient targets get new textures, e.g. after a
+    /// resize. Bind groups of sampled transient targets have to be recreated when it changes.
+    #[inline]
+    pub fn transient_generation(&self) -> u64 {
+        self.transients.generation
+    }
+}
@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+
+use winit::dpi::PhysicalSize;
+
+use crate::prelude::{EntityId, Texture};
+
+use super::{
+    GraphNode, NodeColorTarget, NodeDepthTarget, RenderGraph,
+    data::{ColorTargetData, DepthTargetData},
+    transient::TransientPool,
+};
+
+/// Size dependent targets of a [`RenderGraph`] for a single window, kept while the graph renders
+/// to another one, so windows of different sizes don't recreate them every frame
+pub(crate) struct WindowTargets {
+    /// Surface size the targets were created for
+    size: PhysicalSize<u32>,
+    /// Color and depth targets of the nodes, by node name
+    nodes: HashMap<String, (Option<ColorTargetData>, Option<DepthTargetData>)>,
+    transients: TransientPool,
+    msaa: Option<((u32, u32, wgpu::TextureFormat, u32), Texture)>,
+}
+
+impl GraphNode {
+    /// Returns whether the color and depth targets of the node depend on the surface size
+    fn sized_targets(&self) -> (bool, bool) {
+        let color = matches!(self.color_target, NodeColorTarget::Transient(_));
+        let depth = match self.depth_target {
+            NodeDepthTarget::Transient(_) => true,
+            NodeDepthTarget::Owned(_) => matches!(self.color_target, NodeColorTarget::Surface),
+            _ => false,
+        };
+        (color, depth)
+    }
+}
+
+impl RenderGraph {
+    /// Swap in the size dependent targets of `window`, the targets of the previous window are kept
+    /// until it's rendered again. Targets of a window rendered for the first time are created on
+    /// the next execution.
+    pub(crate) fn use_window(&mut self, window: EntityId) {
+        let Some(previous) = self.window.replace(window) else {
+            return;
+        };
+        if previous == window {
+            return;
+        }
+
+        let generation = self.transients.generation;
+        let targets = self.take_window_targets();
+        self.windows.insert(previous, targets);
+
+        match self.windows.remove(&window) {
+            Some(targets) => self.restore_window_targets(targets),
+            None => {
+                for node in self.nodes.values_mut() {
+                    if node.sized_targets().1 {
+                        node.data.needs_regen = true;
+                    }
+                }
+                self.transients.dirty = true;
+            }
+        }
+
+        // bind groups of sampled transient targets have to be recreated
+        self.transients.generation = generation + 1;
+    }
+
+    /// Drop the targets of windows which don't exist anymore
+    pub(crate) fn retain_windows(&mut self, exists: impl Fn(EntityId) -> bool) {
+        self.windows.retain(|window, _| exists(*window));
+    }
+
+    /// Move the size dependent targets out of the graph
+    fn take_window_targets(&mut self) -> WindowTargets {
+        let mut nodes = HashMap::new();
+        for node in self.nodes.values_mut() {
+            let (color, depth) = node.sized_targets();
+            if !color && !depth {
+                continue;
+            }
+
+            let color = color.then(|| node.data.color_target.take()).flatten();
+            let depth = depth.then(|| node.data.depth_target.take()).flatten();
+            nodes.insert(node.name.clone(), (color, depth));
+        }
+
+        WindowTargets {
+            size: self.size,
+            nodes,
+            transients: std::mem::take(&mut self.transients),
+            msaa: self.msaa.color.take(),
+        }
+    }
+
+    /// Move the size dependent targets of a window back into the graph
+    fn restore_window_targets(&mut self, targets: WindowTargets) {
+        for (name, (color, depth)) in targets.nodes {
+            let Some(node) = self.nodes.get_mut(&name) else {
+                continue;
+            };
+            if color.is_some() {
+                node.data.color_target = color;
+            }
+            if depth.is_some() {
+                node.data.depth_target = depth;
+            }
+        }
+
+        // the owned depth descriptors have to match the restored targets
+        if self.size != targets.size {
+            for node in self.nodes.values_mut() {
+                let needs_regen = node.data.needs_regen;
+                node.resize(&targets.size);
+                node.data.needs_regen = needs_regen;
+            }
+            self.size = targets.size;
+        }
+
+        self.transients = targets.transients;
+        self.msaa.color = targets.msaa;
+    }
+}
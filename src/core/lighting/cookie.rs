@@ -0,0 +1,148 @@
+use crate::{
+    assets::{Assets, Handle},
+    prelude::Image,
+    renderer::newtype::{RenderDevice, RenderQueue},
+};
+
+/// Texture array with the cookies of every light, each resampled into a layer of the same size
+pub struct CookieArray {
+    texture: wgpu::Texture,
+    sampler: wgpu::Sampler,
+    /// Cookie image of every layer
+    cookies: Vec<Handle<Image>>,
+}
+
+impl CookieArray {
+    pub const SIZE: u32 = 256;
+    pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+    pub fn new(device: &RenderDevice) -> Self {
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("CookieArray Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        Self {
+            texture: Self::create_texture(device, 1),
+            sampler,
+            cookies: Vec::new(),
+        }
+    }
+
+    fn create_texture(device: &RenderDevice, layers: u32) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("CookieArray Texture"),
+            size: wgpu::Extent3d {
+                width: Self::SIZE,
+                height: Self::SIZE,
+                depth_or_array_layers: layers,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: Self::FORMAT,
+            usage: wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[Self::FORMAT],
+        })
+    }
+
+    /// Update the layers to contain `cookies`, images which aren't loaded yet are skipped.
+    /// The texture is only rewritten when the set of cookies changes, so an image edited in place
+    /// is not updated.
+    pub fn update(
+        &mut self,
+        cookies: &[Handle<Image>],
+        images: &Assets<Image>,
+        device: &RenderDevice,
+        queue: &RenderQueue,
+    ) {
+        let mut loaded = Vec::with_capacity(cookies.len());
+        for cookie in cookies {
+            if !loaded.contains(cookie) && images.get(cookie).is_some_and(Self::is_valid) {
+                loaded.push(cookie.clone());
+            }
+        }
+
+        if loaded == self.cookies {
+            return;
+        }
+
+        self.texture = Self::create_texture(device, (loaded.len() as u32).max(1));
+        for (layer, cookie) in loaded.iter().enumerate() {
+            let image = images.get(cookie).expect("cookie image is loaded");
+
+            queue.write_texture(
+                wgpu::TexelCopyTextureInfo {
+                    texture: &self.texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: 0,
+                        y: 0,
+                        z: layer as u32,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                &Self::resample(image),
+                wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(4 * Self::SIZE),
+                    rows_per_image: Some(Self::SIZE),
+                },
+                wgpu::Extent3d {
+                    width: Self::SIZE,
+                    height: Self::SIZE,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+
+        self.cookies = loaded;
+    }
+
+    /// Returns the layer of `cookie`, or None if it isn't in the array
+    pub fn layer(&self, cookie: &Handle<Image>) -> Option<u32> {
+        self.cookies
+            .iter()
+            .position(|c| c == cookie)
+            .map(|layer| layer as u32)
+    }
+
+    /// Create a texture view for the whole cookie array
+    pub fn create_view(&self) -> wgpu::TextureView {
+        self.texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("CookieArray View"),
+            format: Some(Self::FORMAT),
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        })
+    }
+
+    pub fn sampler(&self) -> &wgpu::Sampler {
+        &self.sampler
+    }
+
+    /// Whether the image has rgba8 data for its whole size
+    fn is_valid(image: &Image) -> bool {
+        let (width, height) = (image.size.width, image.size.height);
+        width > 0 && height > 0 && image.data.len() >= (width * height * 4) as usize
+    }
+
+    /// Returns the rgba8 data of `image` resampled to `SIZE` x `SIZE` with the nearest texel
+    fn resample(image: &Image) -> Vec<u8> {
+        let (width, height) = (image.size.width, image.size.height);
+        let mut data = Vec::with_capacity((Self::SIZE * Self::SIZE * 4) as usize);
+
+        for y in 0..Self::SIZE {
+            let source_y = ((y as u64 * height as u64) / Self::SIZE as u64) as u32;
+            for x in 0..Self::SIZE {
+                let source_x = ((x as u64 * width as u64) / Self::SIZE as u64) as u32;
+                let i = ((source_y * width + source_x) * 4) as usize;
+                data.extend_from_slice(&image.data[i..i + 4]);
+            }
+        }
+
+        data
+    }
+}
@@ -1,10 +1,11 @@
 use crate::{
-    prelude::{Light, World},
-    render_assets::{BindGroup, IntoRenderAsset},
+    assets::{Assets, Handle},
+    prelude::{Image, Light, World},
+    render_assets::{BindGroup, IntoRenderAsset, StagingBelt},
     renderer::newtype::{RenderDevice, RenderQueue},
 };
 
-use super::{LightStorage, ShadowMapArray};
+use super::{CookieArray, LightStorage, ShadowMapArray};
 
 /// Manages the light storage and shadow maps for every applicable light type
 #[derive(crate::macros::Resource)]
@@ -14,6 +15,7 @@ pub struct LightAndShadowManager {
     point_shadow_map: ShadowMapArray,
     spot_shadow_map: ShadowMapArray,
     sampler: wgpu::Sampler,
+    cookies: CookieArray,
 }
 
 impl LightAndShadowManager {
@@ -72,18 +74,20 @@ impl LightAndShadowManager {
             point_shadow_map,
             spot_shadow_map,
             sampler,
+            cookies: CookieArray::new(device),
         }
     }
 
     /// Update the light storage and shadow maps to match the lights.
-    /// Sets the shadow map index for each light.
+    /// Sets the shadow map index for each light. Returns true if any shadow map texture was
+    /// recreated, which discards its contents.
     pub fn update(
         &mut self,
         lights: &mut [Light],
         world: &mut World,
         device: &RenderDevice,
-        queue: &RenderQueue,
-    ) {
+        belt: &mut StagingBelt,
+    ) -> bool {
         let mut directional_lights = 0u32;
         let mut point_lights = 0u32;
         let mut spot_lights = 0u32;
@@ -111,12 +115,40 @@ impl LightAndShadowManager {
         point_lights = point_lights.max(6);
         spot_lights = spot_lights.max(1);
 
-        self.directional_shadow_map
-            .resize(world, directional_lights);
-        self.point_shadow_map.resize(world, point_lights);
-        self.spot_shadow_map.resize(world, spot_lights);
+        // no short circuit, every array has to be resized
+        let resized = [
+            self.directional_shadow_map
+                .resize(world, directional_lights),
+            self.point_shadow_map.resize(world, point_lights),
+            self.spot_shadow_map.resize(world, spot_lights),
+        ];
 
-        self.storage.update(lights, lights.len(), &device, &queue);
+        self.storage.update(lights, lights.len(), device, belt);
+        resized.contains(&true)
+    }
+
+    /// Update the cookie texture array to contain `cookies`, and set the cookie index of each light
+    /// whose cookie is loaded. `cookies` holds the light index and the cookie of every light
+    /// with a cookie, it has to be called before [`Self::update`].
+    pub fn update_cookies(
+        &mut self,
+        lights: &mut [Light],
+        cookies: &[(usize, Handle<Image>)],
+        images: &Assets<Image>,
+        device: &RenderDevice,
+        queue: &RenderQueue,
+    ) {
+        let handles = cookies
+            .iter()
+            .map(|(_, cookie)| cookie.clone())
+            .collect::<Vec<_>>();
+        self.cookies.update(&handles, images, device, queue);
+
+        for (index, cookie) in cookies {
+            if let Some(layer) = self.cookies.layer(cookie) {
+                lights[*index].set_cookie_index(layer);
+            }
+        }
     }
 
     /// Create a texture view for the shadow map of a given light.
@@ -229,6 +261,22 @@ impl IntoRenderAsset<BindGroup> for LightAndShadowManager {
                 None,
                 wgpu::BindingResource::Sampler(&self.sampler),
             )
+            .add_custom(
+                visibility,
+                wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2Array,
+                    multisampled: false,
+                },
+                None,
+                wgpu::BindingResource::TextureView(&self.cookies.create_view()),
+            )
+            .add_custom(
+                visibility,
+                wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                None,
+                wgpu::BindingResource::Sampler(self.cookies.sampler()),
+            )
             .finish(&world.resources.get())
     }
 }
@@ -1,7 +1,11 @@
+mod cookie;
 mod shadow_map;
 mod manager;
+mod shadow_cache;
 mod storage;
 
+pub use cookie::CookieArray;
 pub use shadow_map::ShadowMapArray;
 pub use manager::LightAndShadowManager;
+pub use shadow_cache::ShadowMapCache;
 pub use storage::LightStorage;
@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+
+use crate::prelude::Light;
+
+/// Tracks which shadow maps are still up to date, so the shadow pass only re-renders the lights
+/// whose view or casters changed since they were last rendered. Used as a resource.
+///
+/// Shadow casters are tracked as a whole, any moved, added or removed caster invalidates every
+/// cached shadow map.
+#[derive(Default, crate::macros::Resource)]
+pub struct ShadowMapCache {
+    /// View projection of the light last rendered into each shadow map, by light type and shadow
+    /// map index
+    rendered: HashMap<(u32, u32), [[f32; 4]; 4]>,
+    /// Material, mesh and instance count of every shadow casting instance group last frame
+    casters: Vec<(u64, u64, u32)>,
+    /// Lights skipped by the last shadow pass
+    cached: u32,
+}
+
+impl ShadowMapCache {
+    /// Discard every cached shadow map, e.g. after a shadow caster changed
+    pub fn invalidate(&mut self) {
+        self.rendered.clear();
+    }
+
+    /// Returns true if the shadow map of `light` is up to date and doesn't have to be rendered
+    pub fn is_cached(&self, light: &Light) -> bool {
+        Self::key(light).is_some_and(|key| self.rendered.get(&key) == Some(&light.view_proj))
+    }
+
+    /// Mark the shadow map of `light` as rendered with its current view projection
+    pub fn mark_rendered(&mut self, light: &Light) {
+        if let Some(key) = Self::key(light) {
+            self.rendered.insert(key, light.view_proj);
+        }
+    }
+
+    /// Invalidates every shadow map if the shadow casting instance groups differ from the last
+    /// call, which happens when casters are added, removed or hidden
+    pub(crate) fn update_casters(&mut self, casters: Vec<(u64, u64, u32)>) {
+        if self.casters != casters {
+            self.casters = casters;
+            self.invalidate();
+        }
+    }
+
+    /// Set the amount of lights skipped by the last shadow pass
+    pub(crate) fn set_cached_count(&mut self, cached: u32) {
+        self.cached = cached;
+    }
+
+    /// Returns the amount of lights whose shadow map was reused by the last shadow pass
+    pub fn cached_count(&self) -> u32 {
+        self.cached
+    }
+
+    /// Light type and shadow map index of `light`, None if it has no shadow map
+    fn key(light: &Light) -> Option<(u32, u32)> {
+        let light_type = if light.is_directional() {
+            0
+        } else if light.is_point() {
+            1
+        } else if light.is_spot() {
+            2
+        } else {
+            return None;
+        };
+
+        Some((light_type, light.shadow_map_index()))
+    }
+}
@@ -49,18 +49,20 @@ impl ShadowMapArray {
         }
     }
 
-    /// Resize the texture array to n layers. Panics if `n == 0`
-    pub fn resize(&mut self, world: &mut World, layers: u32) {
+    /// Resize the texture array to n layers, returns true if the texture was recreated. Panics if
+    /// `n == 0`
+    pub fn resize(&mut self, world: &mut World, layers: u32) -> bool {
         assert!(layers > 0);
 
         if layers == self.layers() {
-            return;
+            return false;
         }
 
         self.size.depth_or_array_layers = layers;
         let resized = Self::new(world, self.size);
 
         self.texture = resized.texture;
+        true
     }
 
     /// Get the size of the `depth_or_array_layers`
@@ -1,24 +1,34 @@
+use std::collections::HashMap;
+
 use crate::{
-    assets::Handle,
+    assets::{Assets, Handle},
+    ecs::entities::EntityId,
     math::GlobalTransform,
-    prelude::{Material, Mesh, Res, ResMut},
-    query::{Query, RunQuery},
-    render_assets::TransformStorage,
+    prelude::{Hidden, Material, Mesh, NotShadowCaster, NotShadowReceiver, Res, ResMut, ZIndex},
+    query::{Query, RunQuery, filter::Without},
+    render_assets::{StagingBelt, TransformStorage},
     renderer::{
-        culling::Visibility,
-        newtype::{RenderDevice, RenderQueue},
+        culling::{Visibility, VisibilityCameras, VisibilityRange},
+        newtype::RenderDevice,
     },
     system::Commands,
 };
 
-/// One instance group represents a group of instances with the same material and mesh.
-/// Instance count defines how many instances are in the group and instance offset is the offset in
-/// the `TransformStorage` where these instances are stored.
+/// One instance group represents a group of instances with the same material, mesh, shadow flags
+/// and visibility range. Instance count defines how many instances are in the group and instance offset is the
+/// offset in the `TransformStorage` where these instances are stored.
+#[derive(Clone)]
 pub struct InstanceGroup {
     pub material: Handle<Material>,
     pub mesh: Handle<Mesh>,
     pub instance_count: u32,
     pub instance_offset: u32,
+    /// False if the instances are [`NotShadowCaster`]s
+    pub casts_shadows: bool,
+    /// False if the instances are [`NotShadowReceiver`]s
+    pub receives_shadows: bool,
+    /// [`VisibilityRange`] of the instances, faded in and out by the standard shader
+    pub visibility_range: Option<VisibilityRange>,
 }
 
 impl InstanceGroup {
@@ -33,81 +43,300 @@ impl InstanceGroup {
             mesh,
             instance_count,
             instance_offset,
+            casts_shadows: true,
+            receives_shadows: true,
+            visibility_range: None,
         }
     }
+
+    /// Returns true if the group has the same material, mesh and instance flags
+    fn matches(
+        &self,
+        material: &Handle<Material>,
+        mesh: &Handle<Mesh>,
+        flags: InstanceFlags,
+    ) -> bool {
+        self.material == *material
+            && self.mesh == *mesh
+            && self.casts_shadows == flags.casts
+            && self.receives_shadows == flags.receives
+            && self.visibility_range == flags.range
+    }
+}
+
+/// Shadow flags and visibility range of a single instance
+#[derive(Clone, Copy, PartialEq)]
+struct InstanceFlags {
+    casts: bool,
+    receives: bool,
+    range: Option<VisibilityRange>,
+}
+
+impl InstanceFlags {
+    fn new(
+        caster: Option<&NotShadowCaster>,
+        receiver: Option<&NotShadowReceiver>,
+        range: Option<&VisibilityRange>,
+    ) -> Self {
+        Self {
+            casts: caster.is_none(),
+            receives: receiver.is_none(),
+            range: range.copied(),
+        }
+    }
+
+    /// Returns a key which orders equal flags next to each other
+    fn sort_key(&self) -> (bool, bool, Option<[u32; 3]>) {
+        let range = self
+            .range
+            .map(|range| [range.min, range.max, range.fade].map(f32::to_bits));
+        (self.casts, self.receives, range)
+    }
 }
 
 /// Grouped instances first by material and then by mesh.
 #[derive(crate::macros::Resource)]
 pub struct GroupedInstances {
+    /// Groups of all instances, regardless of their visibility
     pub groups: Vec<InstanceGroup>,
+    /// Groups of meshes with a [`ZIndex`], in draw order
+    pub sorted: Vec<InstanceGroup>,
+    /// Groups of the instances visible in each culled camera, see
+    /// [`VisibilityCameras`]
+    pub camera_groups: HashMap<EntityId, Vec<InstanceGroup>>,
+    /// Sorted groups of the meshes visible in each culled camera
+    pub camera_sorted: HashMap<EntityId, Vec<InstanceGroup>>,
+}
+
+impl GroupedInstances {
+    /// Returns the groups to draw for `camera`, its culled groups if they exist, otherwise all
+    /// groups
+    pub fn groups_for(&self, camera: EntityId) -> &[InstanceGroup] {
+        self.camera_groups.get(&camera).unwrap_or(&self.groups)
+    }
+
+    /// Returns the sorted groups to draw for `camera`, its culled groups if they exist, otherwise
+    /// all sorted groups
+    pub fn sorted_for(&self, camera: EntityId) -> &[InstanceGroup] {
+        self.camera_sorted.get(&camera).unwrap_or(&self.sorted)
+    }
+}
+
+/// Instances of a mesh which are not entities, e.g. scattered vegetation. Drawn as one instance
+/// group after the mesh entities.
+pub struct InstanceBatch {
+    pub material: Handle<Material>,
+    pub mesh: Handle<Mesh>,
+    /// World transform of every instance
+    pub transforms: Vec<[[f32; 4]; 4]>,
+    pub casts_shadows: bool,
+    pub receives_shadows: bool,
+}
+
+/// Batches of instances without entities which are added to the [`GroupedInstances`]. Systems
+/// push their batches every frame before [`generate_grouped_instances_system`] runs, which drains
+/// them.
+#[derive(Default, crate::macros::Resource)]
+pub struct InstanceBatches {
+    pub batches: Vec<InstanceBatch>,
+}
+
+impl InstanceBatches {
+    /// Add a batch to be drawn this frame
+    #[inline]
+    pub fn push(&mut self, batch: InstanceBatch) {
+        self.batches.push(batch);
+    }
 }
 
 /// Pre-render system to generate [`grouped instances`](GroupedInstances) resource for rendering.
 pub fn generate_grouped_instances_system(
     mut commands: Commands,
     device: Res<RenderDevice>,
-    queue: Res<RenderQueue>,
+    mut belt: ResMut<StagingBelt>,
     mut transforms_storage: ResMut<TransformStorage>,
-    mut query: Query<(&Handle<Material>, &Handle<Mesh>, &GlobalTransform)>,
+    mut instance_batches: ResMut<InstanceBatches>,
+    meshes: Res<Assets<Mesh>>,
+    visibility_cameras: Option<Res<VisibilityCameras>>,
+    mut query: Query<
+        (
+            &Handle<Material>,
+            &Handle<Mesh>,
+            &GlobalTransform,
+            Option<&NotShadowCaster>,
+            Option<&NotShadowReceiver>,
+            Option<&VisibilityRange>,
+            Option<&Visibility>,
+        ),
+        (Without<Hidden>, Without<ZIndex>),
+    >,
+    mut sorted_query: Query<
+        (
+            &Handle<Material>,
+            &Handle<Mesh>,
+            &GlobalTransform,
+            &ZIndex,
+            Option<&NotShadowReceiver>,
+            Option<&VisibilityRange>,
+            Option<&Visibility>,
+        ),
+        Without<Hidden>,
+    >,
 ) {
-    // Prepare sorted storage
     let mut transforms = Vec::new();
-    let mut sorted = Vec::<(&Handle<Material>, &Handle<Mesh>, &GlobalTransform)>::new();
-    for (mat, mesh, global_transform) in query.iter_mut() {
-        sorted.push((mat, mesh, global_transform));
-    }
 
-    // Sort by material and mesh
-    sorted.sort_by(|a, b| {
-        let material_cmp = a.0.id().cmp(&b.0.id());
-        if material_cmp != std::cmp::Ordering::Equal {
-            return material_cmp;
-        }
-        a.1.id().cmp(&b.1.id()) // mesh comparison
-    });
-
-    // Group by material and mesh
-    let last_index = sorted.len().saturating_sub(1);
-    let mut last_entry = None;
-    let mut instance_count = 0;
-    let mut instance_offset = 0;
-    let mut groups = Vec::<InstanceGroup>::new();
-    for (i, (material, mesh, global_transform)) in sorted.into_iter().enumerate() {
-        if let Some((last_material, last_mesh, last_instance_count)) = last_entry {
-            if last_material == *material && last_mesh == *mesh {
-                instance_count += 1;
-            } else {
-                groups.push(InstanceGroup::new(
-                    last_material,
-                    last_mesh,
-                    last_instance_count,
-                    instance_offset,
-                ));
-
-                instance_offset += last_instance_count;
-                instance_count = 1;
-            }
-        } else {
-            instance_count = 1;
-        }
+    // Culled cameras which get their own groups, empty if frustum culling is disabled
+    let cameras = visibility_cameras
+        .map(|cameras| cameras.iter().collect::<Vec<_>>())
+        .unwrap_or_default();
 
-        if i == last_index {
-            groups.push(InstanceGroup::new(
-                material.clone(),
-                mesh.clone(),
-                instance_count,
-                instance_offset,
-            ));
+    // Sort by material and mesh, then by shadow flags and visibility range
+    let sorted = query
+        .iter_sorted_by_key(|(material, mesh, _, caster, receiver, range, _)| {
+            let flags = InstanceFlags::new(*caster, *receiver, *range);
+            (material.id(), mesh.id(), flags.sort_key())
+        })
+        .into_iter()
+        // meshes loaded with `load_async` are drawn once they are loaded
+        .filter(|(_, mesh, ..)| meshes.get(mesh).is_some())
+        .map(
+            |(material, mesh, global_transform, caster, receiver, range, visibility)| {
+                let flags = InstanceFlags::new(caster, receiver, range);
+                (material, mesh, global_transform, flags, visibility)
+            },
+        )
+        .collect::<Vec<_>>();
+    let mut groups = group_instances(
+        sorted.iter().map(|(a, b, c, d, _)| (*a, *b, *c, *d)),
+        &mut transforms,
+    );
+
+    // Group the instances visible in each camera
+    let mut camera_groups = group_camera_instances(&sorted, &cameras, &mut transforms);
+
+    // Append instance batches as their own groups, they are already culled
+    for batch in instance_batches.batches.drain(..) {
+        if batch.transforms.is_empty() || meshes.get(&batch.mesh).is_none() {
+            continue;
         }
 
-        last_entry = Some((material.clone(), mesh.clone(), instance_count));
-        transforms.push(global_transform.as_matrix().to_cols_array_2d());
+        let group = InstanceGroup {
+            casts_shadows: batch.casts_shadows,
+            receives_shadows: batch.receives_shadows,
+            ..InstanceGroup::new(
+                batch.material,
+                batch.mesh,
+                batch.transforms.len() as u32,
+                transforms.len() as u32,
+            )
+        };
+        for groups in camera_groups.values_mut() {
+            groups.push(group.clone());
+        }
+        groups.push(group);
+        transforms.extend(batch.transforms);
     }
 
+    // Sort by z index, then material and mesh, then by the Z translation
+    let sorted = sorted_query
+        .iter_sorted_by(|a, b| {
+            let (material_a, mesh_a, transform_a, z_index_a, ..) = a;
+            let (material_b, mesh_b, transform_b, z_index_b, ..) = b;
+
+            z_index_a
+                .cmp(z_index_b)
+                .then_with(|| material_a.id().cmp(&material_b.id()))
+                .then_with(|| mesh_a.id().cmp(&mesh_b.id()))
+                .then_with(|| {
+                    let z_a = transform_a.translation().z;
+                    z_a.total_cmp(&transform_b.translation().z)
+                })
+        })
+        .into_iter()
+        .filter(|(_, mesh, ..)| meshes.get(mesh).is_some())
+        .map(
+            |(material, mesh, global_transform, _, receiver, range, visibility)| {
+                // sorted meshes never cast shadows
+                let flags = InstanceFlags::new(Some(&NotShadowCaster), receiver, range);
+                (material, mesh, global_transform, flags, visibility)
+            },
+        )
+        .collect::<Vec<_>>();
+    let camera_sorted = group_camera_instances(&sorted, &cameras, &mut transforms);
+    let sorted = group_instances(
+        sorted.iter().map(|(a, b, c, d, _)| (*a, *b, *c, *d)),
+        &mut transforms,
+    );
+
     // Set transforms storage
-    transforms_storage.update(&transforms, transforms.len(), &device, &queue);
+    transforms_storage.update(&transforms, transforms.len(), &device, &mut belt);
 
-    let grouped_instances = GroupedInstances { groups };
+    let grouped_instances = GroupedInstances {
+        groups,
+        sorted,
+        camera_groups,
+        camera_sorted,
+    };
     commands.insert_resource(grouped_instances);
 }
+
+/// Instance with its instance flags and visibility, before grouping
+type VisibleInstance<'a> = (
+    &'a Handle<Material>,
+    &'a Handle<Mesh>,
+    &'a GlobalTransform,
+    InstanceFlags,
+    Option<&'a Visibility>,
+);
+
+/// Groups the instances visible in each camera of `cameras`, given as their visibility slot index
+/// and id. Entities without [`Visibility`] are never culled.
+fn group_camera_instances(
+    instances: &[VisibleInstance],
+    cameras: &[(u32, EntityId)],
+    transforms: &mut Vec<[[f32; 4]; 4]>,
+) -> HashMap<EntityId, Vec<InstanceGroup>> {
+    cameras
+        .iter()
+        .map(|&(index, camera)| {
+            let visible = instances
+                .iter()
+                .filter(|(.., visibility)| visibility.is_none_or(|v| v.is_visible_at(index)))
+                .map(|(a, b, c, d, _)| (*a, *b, *c, *d));
+            (camera, group_instances(visible, transforms))
+        })
+        .collect()
+}
+
+/// Groups consecutive instances with the same material, mesh and instance flags, and appends
+/// their transforms
+fn group_instances<'a>(
+    instances: impl IntoIterator<
+        Item = (
+            &'a Handle<Material>,
+            &'a Handle<Mesh>,
+            &'a GlobalTransform,
+            InstanceFlags,
+        ),
+    >,
+    transforms: &mut Vec<[[f32; 4]; 4]>,
+) -> Vec<InstanceGroup> {
+    let mut groups = Vec::<InstanceGroup>::new();
+    for (material, mesh, global_transform, flags) in instances {
+        match groups.last_mut() {
+            Some(group) if group.matches(material, mesh, flags) => {
+                group.instance_count += 1;
+            }
+            _ => groups.push(InstanceGroup {
+                casts_shadows: flags.casts,
+                receives_shadows: flags.receives,
+                visibility_range: flags.range,
+                ..InstanceGroup::new(material.clone(), mesh.clone(), 1, transforms.len() as u32)
+            }),
+        }
+
+        transforms.push(global_transform.as_matrix().to_cols_array_2d());
+    }
+
+    groups
+}
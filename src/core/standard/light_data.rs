@@ -1,10 +1,17 @@
-use glam::{Mat4, Vec4Swizzles};
+use glam::{Mat4, Vec3, Vec4Swizzles};
 
 use crate::{
-    core::lighting::LightAndShadowManager,
-    math::CubeFace,
+    core::lighting::{LightAndShadowManager, ShadowMapCache},
+    math::{
+        CubeFace,
+        bounding_volume::{Frustum, Sphere, intersection::frustum_sphere},
+    },
     prelude::*,
-    renderer::newtype::{RenderDevice, RenderQueue},
+    render_assets::StagingBelt,
+    renderer::{
+        culling::FrustumCullingSettings,
+        newtype::{RenderDevice, RenderQueue},
+    },
 };
 
 /// Prepared light data for rendering
@@ -13,28 +20,47 @@ pub struct PreparedLightData {
     pub lights: Vec<Light>,
 }
 
-/// Pre-render system to prepare [`light data`](PreparedLightData) resource for rendering
+/// Returns true if the range of a light at `position` intersects any of the `frustums`, or if
+/// there are no frustums to cull against
+fn in_any_frustum(frustums: &[Frustum], position: Vec3, range: f32) -> bool {
+    let sphere = Sphere::new(position, range);
+    frustums.is_empty()
+        || frustums
+            .iter()
+            .any(|frustum| frustum_sphere(frustum, &sphere))
+}
+
+/// Pre-render system to prepare [`light data`](PreparedLightData) resource for rendering. Point
+/// and spot lights whose range is outside of every camera frustum are marked as not visible, so
+/// they are skipped by the shaders and the shadow pass.
 pub fn prepare_light_data_system(
     world: &mut World,
     mut commands: Commands,
     device: Res<RenderDevice>,
     queue: Res<RenderQueue>,
+    mut belt: ResMut<StagingBelt>,
     ambient_light: Option<Res<AmbientLight>>,
+    images: Res<Assets<Image>>,
     mut light_manager: ResMut<LightAndShadowManager>,
+    mut shadow_cache: ResMut<ShadowMapCache>,
+    culling_settings: Option<Res<FrustumCullingSettings>>,
 
-    mut camera_query: Query<(&GlobalTransform, &Camera), (With<Projection>, With<Camera3D>)>,
-    mut directional_query: Query<(&GlobalTransform, &DirectionalLight)>,
-    mut spot_query: Query<(&GlobalTransform, &SpotLight)>,
-    mut point_query: Query<(&GlobalTransform, &PointLight)>,
+    mut camera_query: Query<
+        (&GlobalTransform, &Camera, Option<&Frustum>),
+        (With<Projection>, With<Camera3D>),
+    >,
+    mut directional_query: Query<(&GlobalTransform, &DirectionalLight), Without<Hidden>>,
+    mut spot_query: Query<(&GlobalTransform, &SpotLight), Without<Hidden>>,
+    mut point_query: Query<(&GlobalTransform, &PointLight), Without<Hidden>>,
 ) {
     // Extract camera position
     let active_camera = camera_query
         .iter_mut()
         .into_iter()
-        .filter(|(_, c)| c.active)
+        .filter(|(_, c, _)| c.active)
         .take(1)
         .next();
-    let camera_position = match active_camera.map(|(t, _)| t.matrix.w_axis.xyz()) {
+    let camera_position = match active_camera.map(|(t, ..)| t.matrix.w_axis.xyz()) {
         Some(p) => p,
         None => {
             commands.insert_resource(PreparedLightData { lights: Vec::new() });
@@ -42,13 +68,29 @@ pub fn prepare_light_data_system(
         }
     };
 
+    // Frustums of the active cameras, empty if culling is disabled
+    let frustums = match culling_settings {
+        Some(settings) if settings.enabled => camera_query
+            .iter_mut()
+            .into_iter()
+            .filter(|(_, camera, _)| camera.active)
+            .filter_map(|(.., frustum)| frustum.cloned())
+            .collect::<Vec<_>>(),
+        _ => Vec::new(),
+    };
+
     let mut lights = Vec::new();
+    // light index and cookie of every light with a cookie
+    let mut cookies = Vec::new();
 
     // directional lights
     for (global_transform, light) in directional_query.iter_mut() {
         let (view_projection_matrix, direction) =
             light.view_projection_matrix(50.0, 0.1, 50.0, camera_position, global_transform.matrix);
 
+        if let Some(cookie) = &light.cookie {
+            cookies.push((lights.len(), cookie.clone()));
+        }
         lights.push(
             light
                 .as_light(view_projection_matrix)
@@ -60,26 +102,31 @@ pub fn prepare_light_data_system(
     for (global_transform, light) in spot_query.iter_mut() {
         let (view_projection_matrix, spot_direction) =
             light.view_projection_matrix(1.0, 0.1, global_transform.matrix);
+        let position = global_transform.matrix.w_axis.xyz();
 
-        lights.push(
-            light
-                .as_light(view_projection_matrix)
-                .with_spot(global_transform.matrix.w_axis.xyz(), spot_direction),
-        )
+        if let Some(cookie) = &light.cookie {
+            cookies.push((lights.len(), cookie.clone()));
+        }
+        let mut light_data = light
+            .as_light(view_projection_matrix)
+            .with_spot(position, spot_direction);
+        light_data.set_visible(in_any_frustum(&frustums, position, light.range));
+        lights.push(light_data)
     }
 
     // point lights
     for (global_transform, light) in point_query.iter_mut() {
+        let position = global_transform.matrix.w_axis.xyz();
+        let visible = in_any_frustum(&frustums, position, light.range);
+
         for i in 0..6 {
             let face = CubeFace::from_index(i);
             let view_projection_matrix =
                 light.view_proj_matrix_for_face(global_transform.matrix, face);
 
-            lights.push(
-                light
-                    .as_light(view_projection_matrix)
-                    .with_point(global_transform.matrix.w_axis.xyz()),
-            )
+            let mut light_data = light.as_light(view_projection_matrix).with_point(position);
+            light_data.set_visible(visible);
+            lights.push(light_data)
         }
     }
 
@@ -88,7 +135,11 @@ pub fn prepare_light_data_system(
         lights.push(light.as_light(Mat4::IDENTITY))
     };
 
-    light_manager.update(&mut lights, world, &device, &queue);
+    light_manager.update_cookies(&mut lights, &cookies, &images, &device, &queue);
+    if light_manager.update(&mut lights, world, &device, &mut belt) {
+        // recreated shadow maps lost their contents
+        shadow_cache.invalidate();
+    }
 
     let prepared_light_data = PreparedLightData { lights };
     commands.insert_resource(prepared_light_data);
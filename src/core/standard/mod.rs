@@ -1,7 +1,6 @@
 pub mod atlas;
 pub mod grouped;
 pub mod light_data;
-pub mod movement;
 pub mod rendering;
 pub mod shadows;
 pub mod skybox;
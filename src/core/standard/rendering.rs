@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use pipeline::PipelineBuilder;
 
 use crate::{
@@ -10,7 +12,7 @@ use crate::{
     },
 };
 
-use super::grouped::GroupedInstances;
+use super::grouped::{GroupedInstances, InstanceGroup};
 
 /// Creates a node for standard main render pass
 pub fn standard_main_node(
@@ -71,19 +73,12 @@ fn main_render_system(
     >,
 
     graph_ctx: Res<RenderContext>,
+    rendered: Res<RenderedWindow>,
 ) {
-    // Camera
-    let (active_camera_id, active_camera) = match camera_query
-        .iter_mut()
-        .into_iter()
-        .filter(|(_, c)| c.active)
-        .take(1)
-        .next()
-    {
-        Some(camera) => camera,
-        None => return,
+    let cameras = Camera::rendering_to(camera_query.iter_mut(), &rendered);
+    let Some((_, first_camera)) = cameras.first() else {
+        return;
     };
-    let camera_bind_group = bind_groups.get_by_entity(active_camera_id, active_camera, world);
 
     // Create render pass
     let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
@@ -91,9 +86,9 @@ fn main_render_system(
         color_attachments: &[Some(wgpu::RenderPassColorAttachment {
             view: unsafe { &*graph_ctx.color_target.expect("main color target is None") },
             depth_slice: None,
-            resolve_target: None,
+            resolve_target: graph_ctx.resolve_target.map(|view| unsafe { &*view }),
             ops: wgpu::Operations {
-                load: wgpu::LoadOp::Clear(active_camera.clear_color.into()),
+                load: wgpu::LoadOp::Clear(first_camera.clear_color.into()),
                 store: wgpu::StoreOp::Store,
             },
         })],
@@ -131,23 +126,288 @@ fn main_render_system(
 
     // Set bind groups
     render_pass.set_bind_group(1, transforms_storage.bind_group(), &[]);
-    render_pass.set_bind_group(2, &*camera_bind_group, &[]);
     render_pass.set_bind_group(3, &*manager_bind_group, &[]);
 
-    // Instanced draw loop
+    let target_size = surface_size(world);
+    // the transforms and manager bind groups are set once
+    let mut counts = DrawCounts {
+        draw_calls: 0,
+        bind_group_changes: 2,
+    };
+    for (camera_id, camera) in cameras {
+        if !camera.set_render_pass_camera(
+            camera_id,
+            &mut render_pass,
+            2,
+            target_size,
+            &mut bind_groups,
+            world,
+        ) {
+            continue;
+        }
+        counts.bind_group_changes += 1;
+
+        // opaque draws can be reordered, so they are batched by their bind groups
+        let groups = batched_draw_order(grouped.groups_for(camera_id), &mut bind_groups, world);
+        counts += draw_instance_groups(
+            &mut render_pass,
+            groups,
+            &mut buffers,
+            &mut bind_groups,
+            world,
+        );
+    }
+    counts.record(&graph_ctx);
+}
+
+/// Returns the size of the surface in pixels, which the viewports of cameras are relative to
+pub(crate) fn surface_size(world: &World) -> Vec2 {
+    let surface_config = world.resources.get::<RenderSurfaceConfiguration>();
+    Vec2::new(surface_config.width as f32, surface_config.height as f32)
+}
+
+/// Creates a node for the sorted 2D render pass, which draws meshes with a [`ZIndex`] on top of
+/// the main pass
+pub fn standard_main_2d_node(
+    device: &RenderDevice,
+    shader_loader: &mut ShaderLoader,
+    surface_config: &RenderSurfaceConfiguration,
+) -> GraphNode {
+    // Sorted meshes are blended in order, so they only test depth against the main pass
+    let pipeline_builder = create_main_pipeline_builder(device, shader_loader, surface_config)
+        .set_label("main_2d_pipeline")
+        .set_primitive_state(wgpu::PrimitiveState {
+            cull_mode: None,
+            ..PipelineBuilder::default_primitive_state()
+        })
+        .set_depth_stencil(Some(wgpu::DepthStencilState {
+            depth_write_enabled: false,
+            depth_compare: wgpu::CompareFunction::LessEqual,
+            ..PipelineBuilder::default_depth_stencil()
+        }));
+
+    GraphNodeBuilder::new("main_2d")
+        .set_pipeline(pipeline_builder)
+        .set_custom_system(main_2d_render_system)
+        .set_color_target(NodeColorTarget::Surface)
+        .set_depth_target(NodeDepthTarget::Node("main".to_string()))
+        .run_after("main")
+        .build()
+}
+
+fn main_2d_render_system(
+    world: &mut World,
+    encoder: &mut RenderCommandEncoder,
+    mut buffers: ResMut<RenderAssets<Buffer>>,
+    mut bind_groups: ResMut<RenderAssets<BindGroup>>,
+    manager: Res<LightAndShadowManager>,
+    grouped: Res<GroupedInstances>,
+    transforms_storage: Res<TransformStorage>,
+
+    mut camera_query: Query<(EntityId, &Camera), With<Camera3D>>,
+
+    graph_ctx: Res<RenderContext>,
+    rendered: Res<RenderedWindow>,
+) {
+    if grouped.sorted.is_empty() {
+        return;
+    }
+
+    let cameras = Camera::rendering_to(camera_query.iter_mut(), &rendered);
+    if cameras.is_empty() {
+        return;
+    }
+
+    // Create render pass on top of the main pass
+    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("main 2d render pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: unsafe {
+                &*graph_ctx
+                    .color_target
+                    .expect("main_2d color target is None")
+            },
+            depth_slice: None,
+            resolve_target: graph_ctx.resolve_target.map(|view| unsafe { &*view }),
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+            view: unsafe {
+                &*graph_ctx
+                    .depth_target
+                    .expect("main_2d depth target is None")
+            },
+            depth_ops: Some(wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: wgpu::StoreOp::Store,
+            }),
+            stencil_ops: None,
+        }),
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    });
+
+    // Setup pipeline
+    render_pass.set_pipeline(
+        unsafe { &*graph_ctx.node }
+            .data
+            .pipeline
+            .as_ref()
+            .expect("Pipeline should have been generated by now")
+            .render_pipeline(),
+    );
+
+    // Set light count push constant
+    render_pass.set_push_constants(
+        wgpu::ShaderStages::FRAGMENT,
+        0,
+        bytemuck::cast_slice(&[manager.storage.count() as u32]),
+    );
+
+    let manager_bind_group = bind_groups.get_by_resource(&manager, world, false);
+
+    // Set bind groups
+    render_pass.set_bind_group(1, transforms_storage.bind_group(), &[]);
+    render_pass.set_bind_group(3, &*manager_bind_group, &[]);
+
+    let target_size = surface_size(world);
+    // the transforms and manager bind groups are set once
+    let mut counts = DrawCounts {
+        draw_calls: 0,
+        bind_group_changes: 2,
+    };
+    for (camera_id, camera) in cameras {
+        if !camera.set_render_pass_camera(
+            camera_id,
+            &mut render_pass,
+            2,
+            target_size,
+            &mut bind_groups,
+            world,
+        ) {
+            continue;
+        }
+        counts.bind_group_changes += 1;
+
+        // sorted draws are blended, so their order is kept
+        counts += draw_instance_groups(
+            &mut render_pass,
+            grouped.sorted_for(camera_id),
+            &mut buffers,
+            &mut bind_groups,
+            world,
+        );
+    }
+    counts.record(&graph_ctx);
+}
+
+/// Instance flag which disables shadow sampling in the main shader
+const NOT_SHADOW_RECEIVER: u32 = 1;
+
+/// Draw calls and bind group changes issued by a render system
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct DrawCounts {
+    pub draw_calls: u32,
+    pub bind_group_changes: u32,
+}
+
+impl DrawCounts {
+    /// Record the counts for the current node, see [`RenderStats`]
+    pub fn record(&self, graph_ctx: &RenderContext) {
+        graph_ctx.record_draw_calls(self.draw_calls);
+        graph_ctx.record_bind_group_changes(self.bind_group_changes);
+    }
+}
+
+impl std::ops::AddAssign for DrawCounts {
+    fn add_assign(&mut self, rhs: Self) {
+        self.draw_calls += rhs.draw_calls;
+        self.bind_group_changes += rhs.bind_group_changes;
+    }
+}
+
+/// Returns `groups` ordered by their material bind group layout, then by material and mesh, so
+/// consecutive draws share as much state as possible. The pass draws everything with a single
+/// pipeline, so the layout is the coarsest state which differs between materials. Groups without
+/// a shared layout are drawn last.
+pub(crate) fn batched_draw_order<'a>(
+    groups: &'a [InstanceGroup],
+    bind_groups: &mut RenderAssets<BindGroup>,
+    world: &mut World,
+) -> Vec<&'a InstanceGroup> {
+    let mut layouts = HashMap::new();
+    let mut ordered = groups
+        .iter()
+        .map(|group| {
+            let layout = *layouts.entry(group.material.id()).or_insert_with(|| {
+                let bind_group = bind_groups.get_by_handle(&group.material, world);
+                bind_group.layout_id().unwrap_or(u32::MAX)
+            });
+            (layout, group)
+        })
+        .collect::<Vec<_>>();
+
+    // stable, so instance groups keep their relative order within a material and mesh
+    ordered.sort_by_key(|(layout, group)| (*layout, group.material.id(), group.mesh.id()));
+    ordered.into_iter().map(|(_, group)| group).collect()
+}
+
+/// Instanced draw loop over `groups`, binds their materials and meshes and returns the issued
+/// draw calls and material bind group changes
+pub(crate) fn draw_instance_groups<'a>(
+    render_pass: &mut wgpu::RenderPass,
+    groups: impl IntoIterator<Item = &'a InstanceGroup>,
+    buffers: &mut RenderAssets<Buffer>,
+    bind_groups: &mut RenderAssets<BindGroup>,
+    world: &mut World,
+) -> DrawCounts {
+    let mut counts = DrawCounts::default();
     let mut last_material = None;
     let mut last_mesh = None;
-    // for (material, mesh, instance_count, instance_offset) in grouped {
-    for group in &grouped.groups {
+    let mut last_flags = None;
+    let mut last_range = None;
+    for group in groups {
         let material = &group.material;
         let mesh = &group.mesh;
         let instance_count = group.instance_count;
         let instance_offset = group.instance_offset;
 
+        // set instance flags after the light count
+        let flags = if group.receives_shadows {
+            0
+        } else {
+            NOT_SHADOW_RECEIVER
+        };
+        if last_flags != Some(flags) {
+            render_pass.set_push_constants(
+                wgpu::ShaderStages::FRAGMENT,
+                4,
+                bytemuck::bytes_of(&flags),
+            );
+            last_flags = Some(flags);
+        }
+
+        // set visibility range after the flags, a max of 0 disables it
+        let range = group
+            .visibility_range
+            .map_or([0.0; 3], |range| [range.min, range.max, range.fade]);
+        if last_range != Some(range) {
+            render_pass.set_push_constants(
+                wgpu::ShaderStages::FRAGMENT,
+                8,
+                bytemuck::bytes_of(&range),
+            );
+            last_range = Some(range);
+        }
+
         // bind material
         if last_material != Some(material) {
             let material_bind_group = bind_groups.get_by_handle(material, world);
             render_pass.set_bind_group(0, &*material_bind_group, &[]);
+            counts.bind_group_changes += 1;
             last_material = Some(material);
         }
 
@@ -170,11 +430,14 @@ fn main_render_system(
         } else {
             render_pass.draw(0..mesh_buffer.num_vertices, instance_range);
         }
+        counts.draw_calls += 1;
     }
+
+    counts
 }
 
 // TODO: add a better way to generate/get bind group layouts
-fn create_main_pipeline_builder(
+pub(crate) fn create_main_pipeline_builder(
     device: &RenderDevice,
     shader_loader: &mut ShaderLoader,
     surface_config: &RenderSurfaceConfiguration,
@@ -182,53 +445,7 @@ fn create_main_pipeline_builder(
     // Material bind group layout for texture and uniform buffer
     let material_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
         label: Some("material_bind_group_layout"),
-        entries: &[
-            // base texture
-            wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::FRAGMENT,
-                ty: wgpu::BindingType::Texture {
-                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                    view_dimension: wgpu::TextureViewDimension::D2,
-                    multisampled: false,
-                },
-                count: None,
-            },
-            wgpu::BindGroupLayoutEntry {
-                binding: 1,
-                visibility: wgpu::ShaderStages::FRAGMENT,
-                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                count: None,
-            },
-            // normal map
-            wgpu::BindGroupLayoutEntry {
-                binding: 2,
-                visibility: wgpu::ShaderStages::FRAGMENT,
-                ty: wgpu::BindingType::Texture {
-                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                    view_dimension: wgpu::TextureViewDimension::D2,
-                    multisampled: false,
-                },
-                count: None,
-            },
-            wgpu::BindGroupLayoutEntry {
-                binding: 3,
-                visibility: wgpu::ShaderStages::FRAGMENT,
-                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                count: None,
-            },
-            // uniform buffer
-            wgpu::BindGroupLayoutEntry {
-                binding: 4,
-                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            },
-        ],
+        entries: &Material::layout_entries(),
     });
 
     // Transform bind group layout for storage buffer
@@ -316,13 +533,29 @@ fn create_main_pipeline_builder(
                 ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
                 count: None,
             },
+            // light cookies
+            wgpu::BindGroupLayoutEntry {
+                binding: 5,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2Array,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            // light cookie sampler
+            wgpu::BindGroupLayoutEntry {
+                binding: 6,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
         ],
     });
 
-    // Load shader modules
-    shader_loader
-        .load("main", include_str!("../../shaders/shader.wgsl"), device)
-        .expect("Shader with label 'main' already exists");
+    // Load shader modules, the main and main_2d pipelines share the shader
+    shader_loader.load("main", include_str!("../../shaders/shader.wgsl"), device);
 
     // Create builder
     Pipeline::build("main_pipeline")
@@ -339,6 +572,6 @@ fn create_main_pipeline_builder(
         .set_depth_format(wgpu::TextureFormat::Depth32Float)
         .set_push_constant_ranges(vec![wgpu::PushConstantRange {
             stages: wgpu::ShaderStages::FRAGMENT,
-            range: 0..4,
+            range: 0..20,
         }])
 }
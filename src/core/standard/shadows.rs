@@ -2,7 +2,10 @@ use pipeline::PipelineBuilder;
 
 use crate::{
     assets::ShaderLoader,
-    core::{graph::*, lighting::LightAndShadowManager},
+    core::{
+        graph::*,
+        lighting::{LightAndShadowManager, ShadowMapCache},
+    },
     prelude::*,
     render_assets::*,
     renderer::newtype::{RenderCommandEncoder, RenderDevice},
@@ -22,6 +25,7 @@ pub fn standard_shadow_node(
     // Create light and shadow manager
     let manager = LightAndShadowManager::new(world, &device);
     world.resources.insert(manager);
+    world.resources.insert(ShadowMapCache::default());
 
     // Create graph node
     GraphNodeBuilder::new("shadow")
@@ -35,6 +39,24 @@ pub fn standard_shadow_node(
         .build()
 }
 
+/// Pre-render system which invalidates the [`ShadowMapCache`] if a shadow caster moved or changed
+/// its mesh
+pub fn shadow_caster_change_system(
+    mut cache: ResMut<ShadowMapCache>,
+    mut query: Query<
+        &Handle<Mesh>,
+        (
+            Or<(Changed<GlobalTransform>, Changed<Handle<Mesh>>)>,
+            Without<NotShadowCaster>,
+            Without<Hidden>,
+        ),
+    >,
+) {
+    if !query.is_empty() {
+        cache.invalidate();
+    }
+}
+
 fn shadow_render_system(
     graph_ctx: Res<RenderContext>,
 
@@ -48,6 +70,7 @@ fn shadow_render_system(
     // Resources
     light_manager: Res<LightAndShadowManager>,
     transforms_storage: Res<TransformStorage>,
+    mut cache: ResMut<ShadowMapCache>,
 
     // Resources from preparation system
     grouped: Res<GroupedInstances>,
@@ -61,7 +84,18 @@ fn shadow_render_system(
         .expect("Pipeline should have been generated by now")
         .render_pipeline();
 
+    // Added, removed or hidden casters change the groups
+    let casters = grouped
+        .groups
+        .iter()
+        .filter(|group| group.casts_shadows)
+        .map(|group| (group.material.id(), group.mesh.id(), group.instance_count))
+        .collect();
+    cache.update_casters(casters);
+
     // Instanced per light
+    let mut draw_calls = 0;
+    let mut cached = 0;
     for i in 0..light_data.lights.len() {
         let light = &light_data.lights[i];
 
@@ -69,7 +103,14 @@ fn shadow_render_system(
             continue;
         }
 
-        per_light_render_pass(
+        // shadow map from a previous frame is still valid
+        if cache.is_cached(light) {
+            cached += 1;
+            continue;
+        }
+        cache.mark_rendered(light);
+
+        draw_calls += per_light_render_pass(
             i as u32,
             light,
             &grouped,
@@ -82,6 +123,9 @@ fn shadow_render_system(
             &materials,
         );
     }
+
+    cache.set_cached_count(cached);
+    graph_ctx.record_draw_calls(draw_calls);
 }
 
 fn per_light_render_pass(
@@ -95,7 +139,7 @@ fn per_light_render_pass(
     encoder: &mut RenderCommandEncoder,
     world: &mut World,
     materials: &Assets<Material>,
-) {
+) -> u32 {
     // Create render pass with the correct layer in the shadow map
     let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
         label: Some("shadow render pass"),
@@ -127,6 +171,7 @@ fn per_light_render_pass(
     );
 
     // Instanced draw loop
+    let mut draw_calls = 0;
     let mut last_mesh = None;
     for group in &grouped.groups {
         let material = &group.material;
@@ -134,6 +179,10 @@ fn per_light_render_pass(
         let instance_count = group.instance_count;
         let instance_offset = group.instance_offset;
 
+        if !group.casts_shadows {
+            continue;
+        }
+
         // check unlit material
         if let Some(material) = materials.get(material)
             && material.unlit
@@ -160,7 +209,10 @@ fn per_light_render_pass(
         } else {
             render_pass.draw(0..mesh_buffer.num_vertices, instance_range);
         }
+        draw_calls += 1;
     }
+
+    draw_calls
 }
 
 fn create_shadow_pipeline_builder(
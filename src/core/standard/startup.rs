@@ -5,7 +5,10 @@ use crate::{
     renderer::newtype::{RenderDevice, RenderSurfaceConfiguration, RenderWindow},
 };
 
-use super::{rendering::standard_main_node, shadows::standard_shadow_node};
+use super::{
+    rendering::{standard_main_2d_node, standard_main_node},
+    shadows::standard_shadow_node,
+};
 
 /// Internal system to add necessary resources for standard rendering
 pub fn add_render_resources(mut commands: Commands, device: Res<RenderDevice>) {
@@ -25,6 +28,9 @@ pub fn register_standard_graph(
     let main_node = standard_main_node(&device, &mut shader_loader, &surface_config, &window);
     graph.add(main_node);
 
+    let main_2d_node = standard_main_2d_node(&device, &mut shader_loader, &surface_config);
+    graph.add(main_2d_node);
+
     let shadow_node = standard_shadow_node(&device, &mut shader_loader, world);
     graph.add(shadow_node);
 }
@@ -1,49 +1,105 @@
-use winit::event::WindowEvent;
+use std::collections::{HashMap, HashSet};
 
 use crate::{
-    event::EventReader, prelude::*, render_assets::*, renderer::newtype::RenderQueue,
+    event::EventReader, prelude::*, query::filter::QueryFilter, render_assets::*,
+    renderer::newtype::RenderDevice,
 };
 
-/// Internal system that updates active camera buffers with changed projection and transform.
+/// Internal system that updates active camera buffers with changed projection, transform and
+/// exposure.
 pub fn update_camera_buffers(
     world: &mut World,
     mut buffers: ResMut<RenderAssets<Buffer>>,
-    window_events: EventReader<WindowEvent>,
-    queue: Res<RenderQueue>,
+    mut resize_events: EventReader<WindowResized>,
+    device: Res<RenderDevice>,
+    mut belt: ResMut<StagingBelt>,
 
+    mut windows: Query<(EntityId, &Window, Option<&PrimaryWindow>)>,
     mut query: Query<
-        (EntityId, &Camera, &Projection, &GlobalTransform),
+        (
+            EntityId,
+            &Camera,
+            &Projection,
+            &GlobalTransform,
+            Option<&Exposure>,
+            Option<&CameraShake>,
+        ),
         (
             With<Camera3D>,
-            Or<(Changed<Projection>, Changed<GlobalTransform>)>,
+            Or<(
+                Changed<Projection>,
+                Changed<GlobalTransform>,
+                Changed<Exposure>,
+                Changed<CameraShake>,
+            )>,
         ),
     >,
 ) {
-    let resize_event = window_events.read()
-        .into_iter()
-        .filter_map(|e| {
-            if let WindowEvent::Resized(size) = e {
-                Some(*size)
-            } else {
-                None
+    let resized = resize_events
+        .read()
+        .map(|event| event.window)
+        .collect::<HashSet<_>>();
+
+    // size of every window, cameras without a window render to the primary one
+    let mut primary = None;
+    let mut sizes = HashMap::new();
+    for (id, window, is_primary) in windows.iter_mut() {
+        if is_primary.is_some() {
+            primary = Some(id);
+        }
+        let size = window.size();
+        sizes.insert(id, Vec2::new(size.width as f32, size.height as f32));
+    }
+    let window_size = |camera: &Camera| {
+        camera
+            .window
+            .or(primary)
+            .and_then(|window| sizes.get(&window).copied())
+    };
+
+    // projections are fitted to the viewport of their camera
+    let resize = |proj: &mut Projection, camera: &Camera, size: Vec2| {
+        let (_, viewport_size) = camera.viewport_pixels(size);
+        if viewport_size.x >= 1.0 && viewport_size.y >= 1.0 {
+            proj.resize(viewport_size.x, viewport_size.y);
+        }
+    };
+
+    if !resized.is_empty() {
+        let mut proj_query = query.cast::<(&mut Projection, &Camera), ()>();
+        for (proj, camera) in proj_query.iter_mut() {
+            let window = camera.window.or(primary);
+            if window.is_some_and(|window| resized.contains(&window))
+                && let Some(size) = window_size(camera)
+            {
+                resize(proj, camera, size);
             }
-        })
-        .next_back();
+        }
+    }
 
-    if let Some(size) = resize_event {
-        let mut proj_query = query.cast::<&mut Projection, With<Camera>>();
-        for proj in proj_query.iter_mut() {
-            proj.resize(size.width as f32, size.height as f32);
+    // refit cameras with a changed viewport or window
+    let mut viewport_query = query.cast::<(&mut Projection, &Camera), Changed<Camera>>();
+    for (proj, camera) in viewport_query.iter_mut() {
+        if (camera.viewport.is_some() || camera.window.is_some())
+            && let Some(size) = window_size(camera)
+        {
+            resize(proj, camera, size);
         }
     }
 
-    for (id, camera, projection, global_transform) in query.iter_mut() {
+    for (id, camera, projection, global_transform, exposure, shake) in query.iter_mut() {
         if !camera.active {
             continue;
         }
 
+        // the shake only offsets the uploaded view, not the camera's transform
+        let global_transform = match shake {
+            Some(shake) => &GlobalTransform::new(global_transform.matrix * shake.offset()),
+            None => global_transform,
+        };
+
         let camera_buffer = buffers.get_by_entity(id, camera, world);
-        let camera_buffer_data = Camera::get_buffer_data(projection, global_transform);
+        let camera_buffer_data = Camera::get_buffer_data(projection, global_transform, exposure);
 
         let camera_buffer = camera_buffer
             .uniform
@@ -51,28 +107,103 @@ pub fn update_camera_buffers(
             .expect("Camera buffer should be an uniform buffer");
         let data = bytemuck::cast_slice(&camera_buffer_data);
 
-        queue.write_buffer(camera_buffer, 0, data);
+        belt.write(camera_buffer, 0, data, &device);
+    }
+}
+
+/// Internal system that adds the trauma of [`CameraShakeEvent`]s and advances every shaking
+/// [`CameraShake`]. Runs before [`update_camera_buffers`].
+pub fn update_camera_shake_system(
+    time: Res<Time>,
+    mut shake_events: EventReader<CameraShakeEvent>,
+    mut query: Query<(EntityId, &CameraShake)>,
+) {
+    let events = shake_events.read().collect::<Vec<_>>();
+
+    // only fetch shakes which change mutably, so idle cameras keep their buffers
+    let updated = query
+        .iter_mut()
+        .into_iter()
+        .filter(|(id, shake)| {
+            shake.is_shaking()
+                || events
+                    .iter()
+                    .any(|e| e.camera.is_none_or(|camera| camera == *id))
+        })
+        .map(|(id, _)| id)
+        .collect::<Vec<_>>();
+
+    let mut shake_query = query.cast::<&mut CameraShake, ()>();
+    for id in updated {
+        let Some(shake) = shake_query.get(id) else {
+            continue;
+        };
+
+        for event in &events {
+            if event.camera.is_none_or(|camera| camera == id) {
+                shake.add_trauma(event.trauma);
+            }
+        }
+        shake.update(time.delta());
     }
 }
 
 /// Internal system that updates global transforms of entities with changed local transforms.
+/// [`Static`] entities are skipped, see [`bake_static_transforms`].
 pub fn update_global_transforms(mut q: Query<()>) {
     // update root entities
-    let mut query =
-        q.cast::<(&mut GlobalTransform, &Transform), (Changed<Transform>, Without<Parent>)>();
+    let mut query = q.cast::<
+        (&mut GlobalTransform, &Transform),
+        (Changed<Transform>, Without<Parent>, Without<Static>),
+    >();
     for (global, local) in query.iter_mut() {
         global.update(local);
     }
 
     // recursively update children of updated entities
-    let mut query =
-        q.cast::<(EntityId, &mut GlobalTransform), (With<Children>, Changed<Transform>)>();
+    let mut query = q.cast::<
+        (EntityId, &mut GlobalTransform),
+        (With<Children>, Changed<Transform>, Without<Static>),
+    >();
     for (id, global) in query.iter_mut() {
-        update_children(id, global, q.cast());
+        update_children::<Without<Static>>(id, global, q.cast());
+    }
+}
+
+/// Internal system that computes the global transforms of entities which became [`Static`], and
+/// of their children. Runs after [`update_global_transforms`], so their parents are up to date.
+pub fn bake_static_transforms(mut q: Query<()>) {
+    // static root entities
+    let mut query =
+        q.cast::<(EntityId, &mut GlobalTransform, &Transform), (Added<Static>, Without<Parent>)>();
+    for (id, global, local) in query.iter_mut() {
+        global.update(local);
+        update_children::<()>(id, global, q.cast());
+    }
+
+    // static children, baked from their parent's current transform
+    let children = q
+        .cast::<(EntityId, &Parent), (Added<Static>, With<GlobalTransform>)>()
+        .iter_mut()
+        .into_iter()
+        .map(|(id, parent)| (id, parent.id))
+        .collect::<Vec<_>>();
+    for (id, parent_id) in children {
+        let Some(parent_global) = q.cast::<&GlobalTransform, ()>().get(parent_id).copied() else {
+            continue;
+        };
+
+        let mut child_query = q.cast::<(&mut GlobalTransform, &Transform), ()>();
+        if let Some((global, local)) = child_query.get(id) {
+            *global = parent_global.combine_child(local);
+            update_children::<()>(id, global, q.cast());
+        }
     }
 }
 
-fn update_children(
+/// Recursively updates the global transforms of the children of `parent_id` which match the
+/// filter `F`
+fn update_children<F: QueryFilter>(
     parent_id: EntityId,
     parent_global: &GlobalTransform,
     mut parent_query: Query<&Children>,
@@ -84,14 +215,15 @@ fn update_children(
     };
 
     // update every child recursively
-    let mut child_query = parent_query.cast::<(&mut GlobalTransform, &Transform), With<Parent>>();
+    let mut child_query =
+        parent_query.cast::<(&mut GlobalTransform, &Transform), (With<Parent>, F)>();
     for child in &children.ids {
         if let Some((global, local)) = child_query.get(*child) {
             // update child of parent
             *global = parent_global.combine_child(local);
 
             // recursively update children of child
-            update_children(*child, global, child_query.cast());
+            update_children::<F>(*child, global, child_query.cast());
         }
     }
 }
@@ -0,0 +1,175 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    prelude::*,
+    renderer::newtype::{RenderCommandEncoder, RenderDevice, RenderQueue},
+};
+
+use super::Diagnostics;
+
+/// Features needed to write timestamps between passes
+const TIMESTAMP_FEATURES: wgpu::Features =
+    wgpu::Features::TIMESTAMP_QUERY.union(wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS);
+/// Size of the two resolved timestamps
+const TIMESTAMPS_SIZE: u64 = 2 * std::mem::size_of::<u64>() as u64;
+
+/// Query set and buffers of the GPU timer
+struct TimestampQueries {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    readback_buffer: wgpu::Buffer,
+}
+
+impl TimestampQueries {
+    fn new(device: &RenderDevice) -> Self {
+        Self {
+            query_set: device.create_query_set(&wgpu::QuerySetDescriptor {
+                label: Some("diagnostics_timestamp_query_set"),
+                ty: wgpu::QueryType::Timestamp,
+                count: 2,
+            }),
+            resolve_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("diagnostics_timestamp_resolve_buffer"),
+                size: TIMESTAMPS_SIZE,
+                usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            }),
+            readback_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("diagnostics_timestamp_readback_buffer"),
+                size: TIMESTAMPS_SIZE,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            }),
+        }
+    }
+}
+
+/// State of the asynchronous readback
+#[derive(Default)]
+enum Readback {
+    /// Nothing in flight, a new frame can be measured
+    #[default]
+    Idle,
+    /// Start timestamp was written in the current frame
+    Started,
+    /// Timestamps were resolved, the buffer can be mapped once they are submitted
+    Copied,
+    /// Waiting for the buffer to be mapped, the flag is set by the map callback
+    Mapping(Arc<Mutex<Option<bool>>>),
+}
+
+/// GPU timer queries and the readback in flight. Used as a resource.
+#[derive(Default, crate::macros::Resource)]
+struct GpuTimer {
+    queries: Option<TimestampQueries>,
+    readback: Readback,
+}
+
+/// Adds the GPU timer resource and systems
+pub(super) fn build(app: &mut App) {
+    let supported = app
+        .world
+        .resources
+        .get::<RenderDevice>()
+        .features()
+        .contains(TIMESTAMP_FEATURES);
+    if !supported {
+        tracing::info!("GPU timing is disabled, the device doesn't support timestamp queries");
+        return;
+    }
+
+    app.init_resource::<GpuTimer>()
+        .register_system(gpu_timer_readback_system, phase::First)
+        .register_system(gpu_timer_start_system, phase::Render)
+        .register_system(gpu_timer_end_system, phase::PostRender);
+}
+
+/// System which writes the start timestamp, if no readback is in flight
+fn gpu_timer_start_system(
+    device: Res<RenderDevice>,
+    mut timer: ResMut<GpuTimer>,
+    encoder: &mut RenderCommandEncoder,
+) {
+    // a recreated device might not support them
+    if !device.features().contains(TIMESTAMP_FEATURES) || !matches!(timer.readback, Readback::Idle)
+    {
+        return;
+    }
+
+    let timer = &mut *timer;
+    let queries = timer
+        .queries
+        .get_or_insert_with(|| TimestampQueries::new(&device));
+
+    encoder.write_timestamp(&queries.query_set, 0);
+    timer.readback = Readback::Started;
+}
+
+/// System which writes the end timestamp and copies both into the readback buffer
+fn gpu_timer_end_system(mut timer: ResMut<GpuTimer>, encoder: &mut RenderCommandEncoder) {
+    let timer = &mut *timer;
+    let (Readback::Started, Some(queries)) = (&timer.readback, &timer.queries) else {
+        return;
+    };
+
+    encoder.write_timestamp(&queries.query_set, 1);
+    encoder.resolve_query_set(&queries.query_set, 0..2, &queries.resolve_buffer, 0);
+    encoder.copy_buffer_to_buffer(
+        &queries.resolve_buffer,
+        0,
+        &queries.readback_buffer,
+        0,
+        TIMESTAMPS_SIZE,
+    );
+
+    timer.readback = Readback::Copied;
+}
+
+/// System which reads back the timestamps of a previous frame into the [`Diagnostics`]
+fn gpu_timer_readback_system(
+    device: Res<RenderDevice>,
+    queue: Res<RenderQueue>,
+    mut timer: ResMut<GpuTimer>,
+    mut diagnostics: ResMut<Diagnostics>,
+) {
+    let timer = &mut *timer;
+    let Some(queries) = &timer.queries else {
+        return;
+    };
+
+    // the timestamps were submitted at the end of the last frame
+    if let Readback::Copied = timer.readback {
+        let mapped = Arc::new(Mutex::new(None));
+        let callback_mapped = mapped.clone();
+
+        queries
+            .readback_buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                *callback_mapped.lock().unwrap() = Some(result.is_ok());
+            });
+
+        timer.readback = Readback::Mapping(mapped);
+    }
+
+    let Readback::Mapping(mapped) = &timer.readback else {
+        return;
+    };
+
+    let _ = device.poll(wgpu::PollType::Poll);
+    let Some(success) = *mapped.lock().unwrap() else {
+        return;
+    };
+    timer.readback = Readback::Idle;
+
+    if success {
+        let data = queries.readback_buffer.slice(..).get_mapped_range();
+        let start = u64::from_ne_bytes(data[0..8].try_into().unwrap());
+        let end = u64::from_ne_bytes(data[8..16].try_into().unwrap());
+        drop(data);
+        queries.readback_buffer.unmap();
+
+        let nanos = end.saturating_sub(start) as f64 * queue.get_timestamp_period() as f64;
+        diagnostics.gpu_time = Some((nanos / 1e9) as f32);
+    }
+}
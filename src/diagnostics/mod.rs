@@ -0,0 +1,198 @@
+//! # Diagnostics plugin
+//! Collects per-frame statistics into the [`Diagnostics`] resource and shows them in a toggleable
+//! overlay, rendered through the UI pipeline.
+//!
+//! ## Usage
+//!
+//! ```ignore
+//! app.add_plugin(DiagnosticsPlugin::default());
+//!
+//! fn my_system(diagnostics: Res<Diagnostics>) {
+//!     info!("{:.1} fps, {} draw calls", diagnostics.fps(), diagnostics.draw_calls);
+//! }
+//! ```
+//!
+//! ## Collected statistics
+//!
+//! - Frame times of the last [`DiagnosticsPlugin::history`] frames
+//! - Entity, archetype and memory statistics, see [`Entities::stats`]
+//! - Draw calls and bind group changes recorded by the render graph, see [`RenderStats`]
+//! - Amount of unique bind group layouts, see [`BindGroupLayouts`]
+//! - GPU memory held by render assets, see [`RenderMemoryStats`]
+//! - GPU time between the start of the [`Render`](phase::Render) phase and the end of the
+//!   [`PostRender`](phase::PostRender) phase. Only available if the adapter supports timestamp
+//!   queries inside encoders.
+
+mod gpu;
+mod overlay;
+
+pub mod prelude {
+    pub use super::{Diagnostics, DiagnosticsPlugin};
+}
+
+use std::collections::VecDeque;
+
+use crate::{
+    core::graph::RenderStats,
+    input::InputPlugin,
+    plugins::{RenderPlugin, TimePlugin},
+    prelude::*,
+    render_assets::{BindGroupLayouts, RenderMemoryStats},
+    ui::plugin::UiPlugin,
+};
+
+/// Plugin which collects the [`Diagnostics`] and adds the overlay. For more information, see the
+/// [diagnostics module](crate::diagnostics).
+pub struct DiagnosticsPlugin {
+    /// Amount of frame times kept in the [`Diagnostics`]
+    pub history: usize,
+    /// Key which toggles the diagnostics overlay, or None to disable it
+    pub overlay_toggle: Option<KeyCode>,
+    /// Whether to measure the GPU time with timestamp queries, if supported
+    pub gpu_timing: bool,
+}
+
+impl Default for DiagnosticsPlugin {
+    fn default() -> Self {
+        Self {
+            history: 120,
+            overlay_toggle: Some(KeyCode::F3),
+            gpu_timing: true,
+        }
+    }
+}
+
+impl Plugin for DiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.world.resources.insert(Diagnostics::new(self.history));
+        app.register_system(update_diagnostics_system, phase::FrameEnd);
+
+        if let Some(key) = self.overlay_toggle {
+            overlay::build(app, key);
+        }
+    }
+
+    fn finish(&self, app: &mut App) {
+        // device features are known only once the renderer is initialized
+        if self.gpu_timing {
+            gpu::build(app);
+        }
+    }
+
+    fn dependencies(&self) -> Vec<PluginId> {
+        let mut dependencies = vec![PluginId::of::<TimePlugin>()];
+        if self.gpu_timing {
+            dependencies.push(PluginId::of::<RenderPlugin>());
+        }
+        if self.overlay_toggle.is_some() {
+            dependencies.push(PluginId::of::<UiPlugin>());
+            dependencies.push(PluginId::of::<InputPlugin>());
+        }
+        dependencies
+    }
+}
+
+/// Statistics of the latest frames. Used as a resource.
+#[derive(Resource, Debug, Clone)]
+pub struct Diagnostics {
+    /// Frame times in seconds, oldest first
+    frame_times: VecDeque<f32>,
+    capacity: usize,
+    /// Entity storage statistics of the last frame
+    pub entities: EntitiesStats,
+    /// Draw calls of the last frame
+    pub draw_calls: u32,
+    /// Bind group changes of the last frame
+    pub bind_group_changes: u32,
+    /// Amount of unique bind group layouts
+    pub bind_group_layouts: usize,
+    /// GPU memory held by render assets at the end of the previous frame
+    pub render_memory: RenderMemoryStats,
+    /// GPU time in seconds of the latest measured frame, None if it's not measured
+    pub gpu_time: Option<f32>,
+}
+
+impl Diagnostics {
+    fn new(capacity: usize) -> Self {
+        Self {
+            frame_times: VecDeque::with_capacity(capacity),
+            capacity,
+            entities: EntitiesStats::default(),
+            draw_calls: 0,
+            bind_group_changes: 0,
+            bind_group_layouts: 0,
+            render_memory: RenderMemoryStats::default(),
+            gpu_time: None,
+        }
+    }
+
+    /// Returns the frame times in seconds, oldest first
+    #[inline]
+    pub fn frame_times(&self) -> &VecDeque<f32> {
+        &self.frame_times
+    }
+
+    /// Returns the last frame time in seconds
+    #[inline]
+    pub fn frame_time(&self) -> f32 {
+        self.frame_times.back().copied().unwrap_or_default()
+    }
+
+    /// Returns the average frame time in seconds
+    pub fn average_frame_time(&self) -> f32 {
+        if self.frame_times.is_empty() {
+            return 0.0;
+        }
+
+        self.frame_times.iter().sum::<f32>() / self.frame_times.len() as f32
+    }
+
+    /// Returns the longest frame time in seconds
+    pub fn max_frame_time(&self) -> f32 {
+        self.frame_times.iter().copied().fold(0.0, f32::max)
+    }
+
+    /// Returns the average FPS over the frame time history
+    pub fn fps(&self) -> f32 {
+        let average = self.average_frame_time();
+        if average > 0.0 { 1.0 / average } else { 0.0 }
+    }
+
+    fn push_frame_time(&mut self, frame_time: f32) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.frame_times.len() == self.capacity {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(frame_time);
+    }
+}
+
+/// System which updates the [`Diagnostics`] at the end of the frame
+fn update_diagnostics_system(world: &mut World) {
+    let frame_time = world.resources.get::<Time>().delta();
+    let (draw_calls, bind_group_changes) = world
+        .resources
+        .try_get::<RenderStats>()
+        .map_or((0, 0), |stats| (stats.draw_calls, stats.bind_group_changes));
+    let bind_group_layouts = world
+        .resources
+        .try_get::<BindGroupLayouts>()
+        .map_or(0, |layouts| layouts.len());
+    let render_memory = world
+        .resources
+        .try_get::<RenderMemoryStats>()
+        .map(|stats| *stats)
+        .unwrap_or_default();
+    let entities = world.entities.stats();
+
+    let mut diagnostics = world.resources.get_mut::<Diagnostics>();
+    diagnostics.push_frame_time(frame_time);
+    diagnostics.entities = entities;
+    diagnostics.draw_calls = draw_calls;
+    diagnostics.bind_group_changes = bind_group_changes;
+    diagnostics.bind_group_layouts = bind_group_layouts;
+    diagnostics.render_memory = render_memory;
+}
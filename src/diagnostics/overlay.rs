@@ -0,0 +1,173 @@
+use std::time::Duration;
+
+use crate::{prelude::*, ui::prelude::*};
+
+use super::Diagnostics;
+
+/// Amount of bars in the frame time graph
+const GRAPH_BARS: usize = 60;
+/// Height of the frame time graph in pixels
+const GRAPH_HEIGHT: f32 = 48.0;
+/// Frame time in seconds which fills the whole graph height
+const GRAPH_MAX_FRAME_TIME: f32 = 1.0 / 20.0;
+/// Interval at which the overlay is refreshed, every refresh rebuilds the ui
+const REFRESH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// State of the diagnostics overlay. Used as a resource.
+#[derive(Resource)]
+struct DiagnosticsOverlay {
+    toggle: KeyCode,
+    /// Root ui node, if the overlay is open
+    root: Option<EntityId>,
+    /// Text node with the statistics
+    text: Option<EntityId>,
+    /// Bar nodes of the frame time graph, oldest first
+    bars: Vec<EntityId>,
+}
+
+/// Adds the overlay resource and systems
+pub(super) fn build(app: &mut App, toggle: KeyCode) {
+    app.world.resources.insert(DiagnosticsOverlay {
+        toggle,
+        root: None,
+        text: None,
+        bars: Vec::new(),
+    });
+
+    app.add_system(toggle_diagnostics_overlay_system)
+        .add_system(update_diagnostics_overlay_system.run_if(on_timer(REFRESH_INTERVAL)));
+}
+
+/// System which opens and closes the overlay
+fn toggle_diagnostics_overlay_system(
+    mut commands: Commands,
+    input: Res<Input<KeyCode>>,
+    mut overlay: ResMut<DiagnosticsOverlay>,
+) {
+    if !input.just_pressed(overlay.toggle) {
+        return;
+    }
+
+    if let Some(root) = overlay.root.take() {
+        commands.entity(root).despawn_recursive();
+        overlay.text = None;
+        overlay.bars.clear();
+        return;
+    }
+
+    let root = commands
+        .spawn_empty()
+        .insert(Node {
+            position: Position::Absolute,
+            z_index: i32::MAX,
+            width: Val::Vw(25.0),
+            margin: UiRect::left(Val::Vw(74.0)),
+            padding: UiRect::all(Val::Px(8.0)),
+            background_color: Color::new(0.0, 0.0, 0.0, 0.75),
+            ..Default::default()
+        })
+        .entity_id();
+
+    let mut text = None;
+    let mut bars = Vec::with_capacity(GRAPH_BARS);
+    commands.entity(root).with_children(|p| {
+        let id = p
+            .spawn_empty()
+            .insert(Node {
+                color: Some(color::WHITE),
+                background_color: color::TRANSPARENT,
+                ..Default::default()
+            })
+            .insert(Text::new(""))
+            .entity_id();
+        text = Some(id);
+
+        // bars are aligned to the bottom of the graph
+        p.spawn_empty()
+            .insert(Node {
+                display: Display::Flex,
+                align_items: AlignItems::FlexEnd,
+                column_gap: Val::Px(1.0),
+                height: Val::Px(GRAPH_HEIGHT),
+                margin: UiRect::top(Val::Px(4.0)),
+                background_color: color::TRANSPARENT,
+                ..Default::default()
+            })
+            .with_children(|p| {
+                for _ in 0..GRAPH_BARS {
+                    let id = p
+                        .spawn_empty()
+                        .insert(Node {
+                            width: Val::Px(3.0),
+                            height: Val::Px(0.0),
+                            background_color: color::GREEN,
+                            ..Default::default()
+                        })
+                        .entity_id();
+                    bars.push(id);
+                }
+            });
+    });
+
+    overlay.root = Some(root);
+    overlay.text = text;
+    overlay.bars = bars;
+}
+
+/// System which writes the latest statistics into the overlay
+fn update_diagnostics_overlay_system(
+    diagnostics: Res<Diagnostics>,
+    overlay: Res<DiagnosticsOverlay>,
+    mut query: Query<&mut Text>,
+) {
+    let Some(text_id) = overlay.text else {
+        return;
+    };
+
+    // text node is spawned with commands, so it might not exist yet
+    if let Some(text) = query.get(text_id) {
+        let entities = &diagnostics.entities;
+        let gpu_time = diagnostics
+            .gpu_time
+            .map_or("n/a".to_string(), |time| format!("{:.2} ms", time * 1000.0));
+
+        text.content = format!(
+            "FPS: {:.1}\nFrame: {:.2} ms (max {:.2} ms)\nGPU: {}\nDraw calls: {} ({} bind groups)\nEntities: {}\nArchetypes: {} ({} empty)\nComponent memory: {:.1} KiB\nGPU memory: {:.1} MiB",
+            diagnostics.fps(),
+            diagnostics.frame_time() * 1000.0,
+            diagnostics.max_frame_time() * 1000.0,
+            gpu_time,
+            diagnostics.draw_calls,
+            diagnostics.bind_group_changes,
+            entities.entity_count,
+            entities.archetype_count,
+            entities.empty_archetype_count,
+            entities.memory_usage as f32 / 1024.0,
+            diagnostics.render_memory.total_bytes() as f32 / (1024.0 * 1024.0),
+        );
+    }
+
+    // latest frame times are drawn on the right
+    let frame_times = diagnostics.frame_times();
+    let skipped = GRAPH_BARS.saturating_sub(frame_times.len());
+    let samples = frame_times
+        .iter()
+        .skip(frame_times.len().saturating_sub(GRAPH_BARS));
+
+    let mut bar_query = query.cast::<&mut Node, ()>();
+    for (&bar_id, frame_time) in overlay.bars.iter().skip(skipped).zip(samples) {
+        let Some(bar) = bar_query.get(bar_id) else {
+            continue;
+        };
+
+        let ratio = (frame_time / GRAPH_MAX_FRAME_TIME).min(1.0);
+        bar.height = Val::Px((ratio * GRAPH_HEIGHT).max(1.0));
+        bar.background_color = if *frame_time <= 1.0 / 60.0 {
+            color::GREEN
+        } else if *frame_time <= 1.0 / 30.0 {
+            color::YELLOW
+        } else {
+            color::RED
+        };
+    }
+}
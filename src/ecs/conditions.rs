@@ -0,0 +1,125 @@
+//! Common run [conditions](IntoSystemCondition), for state conditions see
+//! [`state::conditions`](super::state::conditions).
+//!
+//! ```ignore
+//! app.register_system(
+//!     spawn_wave_system
+//!         .run_if(resource_equals(Difficulty::Hard))
+//!         .run_if(not(any_with_component::<Enemy>)),
+//!     phase::Update,
+//! );
+//! ```
+//!
+//! Conditions can be combined with [`and`](IntoSystemCondition::and),
+//! [`or`](IntoSystemCondition::or) and [`not`](IntoSystemCondition::not):
+//! ```ignore
+//! app.register_system(
+//!     autosave_system.run_if(
+//!         in_state(GameState::Playing).and(on_timer(Duration::from_secs(60)).or(on_event::<Quit>)),
+//!     ),
+//!     phase::Update,
+//! );
+//! ```
+
+use std::time::Duration;
+
+use crate::{
+    event::EventReader,
+    prelude::*,
+    system::{IntoSystemCondition, SystemParam},
+};
+
+/// Creates a [Condition](IntoSystemCondition) which negates the result of the provided
+/// condition, same as [`IntoSystemCondition::not`]
+pub fn not<Params: SystemParam>(
+    condition: impl IntoSystemCondition<Params>,
+) -> impl IntoSystemCondition<Params> {
+    condition.not()
+}
+
+/// [Condition](IntoSystemCondition) which evaluates to true if any events of type `E` have been sent
+pub fn on_event<E: Event>(mut event_reader: EventReader<E>) -> bool {
+    event_reader.read().next().is_some()
+}
+
+/// [Condition](IntoSystemCondition) which evaluates to true if resource `R` has changed, or false
+/// if it doesn't exist
+pub fn resource_changed<R: Resource>(resource: Option<Res<R>>) -> bool {
+    resource.is_some_and(|r| r.has_changed())
+}
+
+/// [Condition](IntoSystemCondition) which evaluates to true if a resource `R` has been inserted,
+/// or false if it doesn't exist
+pub fn resource_added<R: Resource>(resource: Option<Res<R>>) -> bool {
+    resource.is_some_and(|r| r.was_added())
+}
+
+/// [Condition](IntoSystemCondition) which evaluates to true if resource `R` exists
+pub fn resource_exists<R: Resource>(resource: Option<Res<R>>) -> bool {
+    resource.is_some()
+}
+
+/// Creates a [Condition](IntoSystemCondition) which evaluates to true if resource `R` is equal to
+/// `value`, or false if it doesn't exist
+pub fn resource_equals<R: Resource + PartialEq>(
+    value: R,
+) -> impl IntoSystemCondition<Option<Res<R>>> {
+    let closure = move |resource: Option<Res<R>>| resource.is_some_and(|r| *r == value);
+    closure.build()
+}
+
+/// [Condition](IntoSystemCondition) which evaluates to true if any entity has component `C`
+pub fn any_with_component<C: Component>(mut query: Query<&C>) -> bool {
+    !query.is_empty()
+}
+
+/// Creates a [Condition](IntoSystemCondition) which evaluates to true every time `duration`
+/// elapses, at most once per run. Time advances by [`Time::step_delta`], so in `FixedUpdate` it
+/// follows the fixed timestep instead of the frame time.
+pub fn on_timer(
+    duration: Duration,
+) -> impl IntoSystemCondition<(Res<Time>, Local<'static, Option<Timer>>)> {
+    let closure = move |time: Res<Time>, mut timer: Local<Option<Timer>>| {
+        let timer = timer.get_or_insert_with(|| Timer::repeating(duration));
+        timer.update(time.step_delta());
+        timer.just_finished()
+    };
+    closure.build()
+}
+
+/// Alias of [`on_timer`]
+#[inline]
+pub fn on_interval(
+    duration: Duration,
+) -> impl IntoSystemCondition<(Res<Time>, Local<'static, Option<Timer>>)> {
+    on_timer(duration)
+}
+
+/// Misspelled alias of [`on_timer`]
+#[deprecated(note = "use `on_timer` instead")]
+#[inline]
+pub fn on_internval(
+    duration: Duration,
+) -> impl IntoSystemCondition<(Res<Time>, Local<'static, Option<Timer>>)> {
+    on_timer(duration)
+}
+
+/// Creates a [Condition](IntoSystemCondition) which evaluates to true on every `n`th run,
+/// starting with the `n`th. In `FixedUpdate` it counts fixed steps instead of frames.
+///
+/// # Panics
+/// Panics if `n` is 0
+pub fn every_n_frames(n: u32) -> impl IntoSystemCondition<Local<'static, u32>> {
+    assert!(n > 0, "every_n_frames requires n > 0");
+
+    let closure = move |mut count: Local<u32>| {
+        *count += 1;
+        if *count == n {
+            *count = 0;
+            true
+        } else {
+            false
+        }
+    };
+    closure.build()
+}
@@ -263,6 +263,22 @@ impl Archetype {
         self.id
     }
 
+    /// Amount of component types in this archetype
+    #[inline]
+    pub fn component_count(&self) -> usize {
+        self.types.len()
+    }
+
+    /// Returns the allocated memory of the components table and entity ids in bytes
+    pub fn memory_usage(&self) -> usize {
+        let entity_ids = self.entity_ids.capacity() * std::mem::size_of::<EntityId>();
+        self.components
+            .iter()
+            .map(ComponentsData::memory_usage)
+            .sum::<usize>()
+            + entity_ids
+    }
+
     /// Returns a pointer to the [`ComponentsData`] at `index`
     #[inline]
     pub(crate) fn get_components_data_mut(&mut self, index: usize) -> *mut ComponentsData {
@@ -378,6 +394,12 @@ pub(crate) struct TickFilterIndices {
 }
 
 impl TickFilterIndices {
+    /// Returns true if there are no tick filters, so every entity of the archetype matches
+    #[inline]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.changed.iter().all(Vec::is_empty) && self.added.iter().all(Vec::is_empty)
+    }
+
     #[inline]
     fn changed_empty(&self) -> bool {
         self.changed.len() == 1 && self.changed[0].is_empty()
@@ -516,6 +538,31 @@ impl Archetype {
         TickFilterIndices { changed, added }
     }
 
+    /// Returns the number of entities passing the tick filters, without checking each entity if
+    /// there are none
+    pub(crate) fn count_matching(
+        &self,
+        indices: &TickFilterIndices,
+        system_last_run: Tick,
+    ) -> usize {
+        if indices.is_empty() {
+            return self.len();
+        }
+
+        (0..self.len())
+            .filter(|&at| self.check_changed_fields(at, indices, system_last_run))
+            .count()
+    }
+
+    /// Returns true if any entity passes the tick filters
+    pub(crate) fn any_matching(&self, indices: &TickFilterIndices, system_last_run: Tick) -> bool {
+        if indices.is_empty() {
+            return !self.is_empty();
+        }
+
+        (0..self.len()).any(|at| self.check_changed_fields(at, indices, system_last_run))
+    }
+
     /// Checks if requested fields (indices) are marked as changed in entities[at]
     ///
     /// # Note
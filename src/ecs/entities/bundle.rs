@@ -0,0 +1,199 @@
+use std::{
+    any::{Any, TypeId},
+    mem::ManuallyDrop,
+    ptr::NonNull,
+};
+
+use crate::{
+    ecs::{ptr::OwnedPtr, world::World},
+    math::{GlobalTransform, Transform},
+};
+
+use super::{
+    EntityId,
+    components::{Component, ComponentInfoPtr, ComponentsRegistry},
+};
+
+/// A group of components which are inserted together, moving the entity to its new archetype only
+/// once. Every [`Component`] is a bundle, and so are tuples of bundles.
+///
+/// Structs can derive it, every field has to be a bundle itself:
+/// ```ignore
+/// #[derive(Bundle)]
+/// struct PlayerBundle {
+///     transform: Transform,
+///     mesh: Handle<Mesh>,
+///     player: Player,
+/// }
+///
+/// commands.spawn(PlayerBundle { .. });
+/// ```
+///
+/// If a bundle contains the same component type more than once, the last one is inserted.
+pub trait Bundle: Send + Sync + 'static {
+    /// Moves every component of the bundle into `components`
+    fn push_components(self, components: &mut BundleComponents);
+}
+
+impl<C: Component> Bundle for C {
+    #[inline]
+    fn push_components(self, components: &mut BundleComponents) {
+        components.push(self);
+    }
+}
+
+/// Type erased components of a [`Bundle`], collected before they are inserted into an entity
+#[derive(Default)]
+pub struct BundleComponents {
+    components: Vec<BundleComponent>,
+}
+
+/// Single boxed component, the box is freed without dropping the component once it's inserted
+struct BundleComponent {
+    type_id: TypeId,
+    data: NonNull<u8>,
+    /// Frees the box without dropping the component
+    free: unsafe fn(NonNull<u8>),
+    /// Drops the component and frees the box
+    drop: unsafe fn(NonNull<u8>),
+    info: fn(&mut ComponentsRegistry) -> ComponentInfoPtr,
+    insert_required: fn(&mut World, EntityId),
+}
+
+/// Frees a box created by [`BundleComponents::push`]
+///
+/// # Safety
+/// `data` must come from `Box<ManuallyDrop<C>>`
+unsafe fn free_box<C>(data: NonNull<u8>) {
+    drop(unsafe { Box::from_raw(data.cast::<ManuallyDrop<C>>().as_ptr()) });
+}
+
+/// Drops the component in a box created by [`BundleComponents::push`] and frees it
+///
+/// # Safety
+/// `data` must come from `Box<ManuallyDrop<C>>`
+unsafe fn drop_box<C>(data: NonNull<u8>) {
+    let mut component = unsafe { Box::from_raw(data.cast::<ManuallyDrop<C>>().as_ptr()) };
+    unsafe { ManuallyDrop::drop(&mut component) };
+}
+
+impl BundleComponents {
+    /// Add `component`, a [`Transform`] also adds its [`GlobalTransform`] like
+    /// [`Commands`](crate::system::Commands) do.
+    ///
+    /// # Panics
+    /// Panics if the component is an [`EntityId`] or a [`GlobalTransform`]
+    pub fn push<C: Component>(&mut self, component: C) {
+        let type_id = TypeId::of::<C>();
+        assert!(
+            type_id != TypeId::of::<EntityId>() && type_id != TypeId::of::<GlobalTransform>(),
+            "Cannot insert EntityId or GlobalTransform in a bundle"
+        );
+
+        if let Some(transform) = (&component as &dyn Any).downcast_ref::<Transform>() {
+            let global_transform = GlobalTransform::from_transform(transform);
+            self.push_unchecked(global_transform);
+        }
+
+        self.push_unchecked(component);
+    }
+
+    fn push_unchecked<C: Component>(&mut self, component: C) {
+        let type_id = TypeId::of::<C>();
+        let data = NonNull::from(Box::leak(Box::new(ManuallyDrop::new(component)))).cast();
+
+        // a component of the same type is replaced by the later one
+        if let Some(index) = self.components.iter().position(|c| c.type_id == type_id) {
+            let old = self.components.swap_remove(index);
+            // Safety: data is an exclusively owned box of the component's type
+            unsafe { (old.drop)(old.data) };
+        }
+
+        self.components.push(BundleComponent {
+            type_id,
+            data,
+            free: free_box::<C>,
+            drop: drop_box::<C>,
+            info: ComponentsRegistry::get_or_register::<C>,
+            insert_required: C::insert_required,
+        });
+    }
+
+    /// Moves the components out as owned pointers and passes them to `insert`, which has to take
+    /// ownership of all of them. Then inserts the required components of the components whose
+    /// type ids `insert` returned.
+    pub(crate) fn insert(
+        mut self,
+        world: &mut World,
+        entity_id: EntityId,
+        insert: impl FnOnce(&mut World, Vec<(ComponentInfoPtr, OwnedPtr)>) -> Vec<TypeId>,
+    ) {
+        let bundle = std::mem::take(&mut self.components);
+        let components = bundle
+            .iter()
+            // Safety: data is exclusively owned, and ownership is moved to `insert`
+            .map(|c| {
+                ((c.info)(&mut world.registry), unsafe {
+                    OwnedPtr::from_raw(c.data)
+                })
+            })
+            .collect();
+        let added = insert(world, components);
+
+        let mut required = Vec::new();
+        for component in bundle {
+            // Safety: component was moved out, only the box is freed
+            unsafe { (component.free)(component.data) };
+
+            if added.contains(&component.type_id) {
+                required.push(component.insert_required);
+            }
+        }
+
+        for insert_required in required {
+            insert_required(world, entity_id);
+        }
+    }
+}
+
+impl Drop for BundleComponents {
+    fn drop(&mut self) {
+        // components which were never inserted
+        for component in self.components.drain(..) {
+            // Safety: data is an exclusively owned box of the component's type
+            unsafe { (component.drop)(component.data) };
+        }
+    }
+}
+
+macro_rules! impl_bundle_tuple {
+    ($($type:ident),*) => {
+        impl<$($type: Bundle),*> Bundle for ($($type,)*) {
+            #[allow(non_snake_case, unused_variables)]
+            #[inline]
+            fn push_components(self, components: &mut BundleComponents) {
+                let ($($type,)*) = self;
+                $(
+                    $type.push_components(components);
+                )*
+            }
+        }
+    };
+}
+
+impl_bundle_tuple!();
+impl_bundle_tuple!(A);
+impl_bundle_tuple!(A, B);
+impl_bundle_tuple!(A, B, C);
+impl_bundle_tuple!(A, B, C, D);
+impl_bundle_tuple!(A, B, C, D, E);
+impl_bundle_tuple!(A, B, C, D, E, F);
+impl_bundle_tuple!(A, B, C, D, E, F, G);
+impl_bundle_tuple!(A, B, C, D, E, F, G, H);
+impl_bundle_tuple!(A, B, C, D, E, F, G, H, I);
+impl_bundle_tuple!(A, B, C, D, E, F, G, H, I, J);
+impl_bundle_tuple!(A, B, C, D, E, F, G, H, I, J, K);
+impl_bundle_tuple!(A, B, C, D, E, F, G, H, I, J, K, L);
+impl_bundle_tuple!(A, B, C, D, E, F, G, H, I, J, K, L, M);
+impl_bundle_tuple!(A, B, C, D, E, F, G, H, I, J, K, L, M, N);
+impl_bundle_tuple!(A, B, C, D, E, F, G, H, I, J, K, L, M, N, O);
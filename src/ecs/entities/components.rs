@@ -11,8 +11,9 @@ use crate::{
         ptr::{DataPtr, DataPtrMut, OwnedPtr, UntypedPtrLt},
         store::blob::{BlobVec, DropFn, new_option_drop_fn},
         tick::{TickStamp, TickStampMut},
+        world::World,
     },
-    prelude::Tick,
+    prelude::{EntityId, Tick},
 };
 
 /// A type which can be used as an entity component in the ECS.
@@ -22,6 +23,20 @@ pub trait Component: Send + Sync + 'static {
     fn get_type_id() -> TypeId {
         TypeId::of::<Self>()
     }
+
+    /// Inserts the components required by this component into `entity_id`, unless they already
+    /// exist. Called after this component is newly added to an entity.
+    ///
+    /// Implemented by the derive macro for types listed in `#[require(...)]`, which must
+    /// implement [`Default`]. Required components can have requirements of their own.
+    ///
+    /// ```ignore
+    /// #[derive(Component)]
+    /// #[require(WorldBoundingVolume, Visibility)]
+    /// pub enum LocalBoundingVolume { ... }
+    /// ```
+    #[inline]
+    fn insert_required(_world: &mut World, _entity_id: EntityId) {}
 }
 
 #[repr(transparent)]
@@ -263,6 +278,14 @@ impl ComponentsData {
         self.data.is_empty()
     }
 
+    /// Returns the allocated memory in bytes, including change detection ticks
+    pub fn memory_usage(&self) -> usize {
+        let components = self.data.capacity() * self.data.layout().pad_to_align().size();
+        let ticks =
+            (self.changed_at.capacity() + self.added_at.capacity()) * std::mem::size_of::<Tick>();
+        components + ticks
+    }
+
     /// Returns immutable [`TickStamp`] for component at `index`.
     #[inline]
     pub fn get_ticks(&self, i: usize, current_tick: Tick, last_run: Tick) -> TickStamp {
@@ -0,0 +1,25 @@
+use crate::macros::{Component, Reflect};
+
+/// Marker which turns an entity off without despawning it, e.g. for pooling.
+///
+/// Queries skip disabled entities, unless they mention `Disabled` themselves, like
+/// `Query<&Transform, With<Disabled>>` or `Query<(&Transform, Option<&Disabled>)>`. So disabled
+/// entities aren't updated, rendered or heard, their spatial audio is paused until the marker is
+/// removed.
+#[derive(Component, Reflect, Default, Debug, Clone, Copy)]
+pub struct Disabled;
+
+/// Marker which hides an entity from rendering, while its systems keep running. It applies to
+/// meshes, outlines, tilemaps and lights, children are not hidden with it.
+#[derive(Component, Reflect, Default, Debug, Clone, Copy)]
+pub struct Hidden;
+
+/// Marker for entities which never move, like level geometry. Their `GlobalTransform` is computed
+/// once when the marker is added, after that they are skipped by the transform propagation, so
+/// changes to their `Transform` or their parent's are ignored. Remove the marker to move them
+/// again.
+///
+/// Static meshes sharing a material can also be merged into one mesh, see
+/// [`StaticMeshPlugin`](crate::renderer::static_mesh::StaticMeshPlugin).
+#[derive(Component, Reflect, Default, Debug, Clone, Copy)]
+pub struct Static;
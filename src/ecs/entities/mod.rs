@@ -1,26 +1,40 @@
 pub mod archetype;
+pub mod bundle;
 pub mod components;
+pub mod markers;
 pub mod relation;
+pub mod stats;
 pub mod tracking;
 
+pub use bundle::{Bundle, BundleComponents};
 pub use components::Component;
 use components::ComponentInfoPtr;
 
-use std::{any::TypeId, collections::HashMap, hash::Hash, mem::ManuallyDrop};
+use std::{
+    any::TypeId,
+    collections::HashMap,
+    hash::{BuildHasherDefault, DefaultHasher, Hash},
+    mem::ManuallyDrop,
+};
 
 use crate::ecs::entities::archetype::TickFilterIndices;
 use crate::ecs::entities::{archetype::TypedComponentData, tracking::EntityTracking};
+use crate::ecs::hierarchy::OrphanPolicy;
 use crate::macros::{Component, Reflect};
 use crate::query::{QueryComponentType, filter::Filters};
 
 use archetype::{Archetype, ArchetypeId};
 use relation::{Children, Parent};
 
-use super::{ptr::OwnedPtr, tick::Tick};
+use super::{
+    ptr::{OwnedPtr, UntypedPtrLt},
+    tick::Tick,
+};
 
 /// Unique identifier for an [entity](Entities) in a [`World`](crate::ecs::world::World).
 /// Consists of an `index` and a `generation` to avoid reusing IDs of despawned entities.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Component, Reflect)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct EntityId {
     /// Index of the entity, serves as the main identifier and is reused after despawning an
     /// entity. It's used as an index in the entities storage.
@@ -71,8 +85,9 @@ pub struct Entities {
     /// Not public since entity creation and despawning is done via commands, which if not applied
     /// by the user would lead to tracked entities which do not exist
     pub(crate) tracking: EntityTracking,
-    /// Holds all archetypes in the world by their unique id
-    pub(crate) archetypes: HashMap<ArchetypeId, Archetype>,
+    /// Holds all archetypes in the world by their unique id. Uses an unseeded hasher, so the
+    /// iteration order is the same across runs of the same binary.
+    pub(crate) archetypes: HashMap<ArchetypeId, Archetype, BuildHasherDefault<DefaultHasher>>,
     /// Pointer to current tick in the world, used for component change tracking
     current_tick: *const Tick,
     /// Info pointer for EntityId component insertion
@@ -83,7 +98,7 @@ impl Default for Entities {
     fn default() -> Self {
         Self {
             tracking: EntityTracking::new(),
-            archetypes: HashMap::new(),
+            archetypes: HashMap::default(),
             current_tick: std::ptr::null(),
             entity_info: ComponentInfoPtr::null(),
         }
@@ -124,6 +139,11 @@ impl Entities {
         self.archetypes.values()
     }
 
+    /// Returns entity, archetype and memory statistics, useful to detect archetype fragmentation
+    pub fn stats(&self) -> stats::EntitiesStats {
+        stats::EntitiesStats::new(self.archetypes())
+    }
+
     // / Initialize tick pointer and entity info, necessary for entity creation. Done in
     /// [`World`](crate::prelude::World) initialization.
     #[inline]
@@ -237,6 +257,46 @@ impl Entities {
         }
     }
 
+    /// Despawn entity and handle its children according to `policy`
+    pub(crate) fn despawn_entity_with(&mut self, entity_id: EntityId, policy: OrphanPolicy) {
+        let children = self
+            .get_component::<Children>(entity_id)
+            .map(|children| children.ids.clone())
+            .unwrap_or_default();
+
+        match policy {
+            OrphanPolicy::Orphan => {}
+            OrphanPolicy::Reparent => {
+                if let Some(grandparent_id) = self.get_component::<Parent>(entity_id).map(|p| p.id)
+                {
+                    // Relink children directly, both components already exist
+                    for &child_id in &children {
+                        if let Some(parent) = self.get_component_mut::<Parent>(child_id) {
+                            parent.set(grandparent_id);
+                        }
+                        if let Some(grandparent_children) =
+                            self.get_component_mut::<Children>(grandparent_id)
+                        {
+                            grandparent_children.add(child_id);
+                        }
+                    }
+
+                    // Children were moved, so despawning mustn't unlink them
+                    if let Some(own_children) = self.get_component_mut::<Children>(entity_id) {
+                        own_children.ids.clear();
+                    }
+                }
+            }
+            OrphanPolicy::Despawn => {
+                for child_id in children {
+                    self.despawn_entity_recursive(child_id);
+                }
+            }
+        }
+
+        self.despawn_entity(entity_id);
+    }
+
     /// Despawn entity and all its children recursively
     pub(crate) fn despawn_entity_recursive(&mut self, entity_id: EntityId) {
         if let Some(children) = self.get_component::<Children>(entity_id) {
@@ -248,7 +308,8 @@ impl Entities {
         self.despawn_entity(entity_id);
     }
 
-    /// Insert new component, or replace existing one
+    /// Insert new component, or replace existing one. Returns true if the component was newly
+    /// added to the entity
     ///
     /// # Panics
     /// Panics if components type_id is EntityId
@@ -257,11 +318,10 @@ impl Entities {
         entity_id: EntityId,
         component: OwnedPtr,
         info: ComponentInfoPtr,
-        replace: bool,
-    ) {
+    ) -> bool {
         let tick = self.tick();
         let type_id = info.as_ref().type_id;
-        let archetypes_ptr = &mut self.archetypes as *mut HashMap<_, _>;
+        let archetypes_ptr = &mut self.archetypes as *mut HashMap<_, _, _>;
         assert_ne!(
             type_id,
             TypeId::of::<EntityId>(),
@@ -271,7 +331,7 @@ impl Entities {
         // Get entity location
         let Some(location) = self.tracking.get_location(entity_id) else {
             info.drop(component);
-            return;
+            return false;
         };
 
         // Get current archetype
@@ -281,15 +341,11 @@ impl Entities {
             .get_mut(&id)
             .expect("archetype should exist");
 
-        // If component type already exists, replace it or drop the new one
+        // If component type already exists, replace it
         if archetype.has_type(&type_id) {
-            if replace {
-                let component = TypedComponentData::from_parts(info, component, tick, tick);
-                archetype.set_component(entity_id, location, component);
-            } else {
-                info.drop(component);
-            }
-            return;
+            let component = TypedComponentData::from_parts(info, component, tick, tick);
+            archetype.set_component(entity_id, location, component);
+            return false;
         }
 
         // Remove entity from archetype and add new component
@@ -332,6 +388,110 @@ impl Entities {
 
         // Update entity location
         self.tracking.set_location(entity_id, new_location);
+        true
+    }
+
+    /// Insert new components, or replace existing ones, moving the entity to its new archetype at
+    /// most once. Returns the type ids of the newly added components
+    ///
+    /// # Panics
+    /// Panics if components contain EntityId or duplicate types
+    pub(crate) fn insert_components(
+        &mut self,
+        entity_id: EntityId,
+        components: Vec<(ComponentInfoPtr, OwnedPtr)>,
+    ) -> Vec<TypeId> {
+        let tick = self.tick();
+        let archetypes_ptr = &mut self.archetypes as *mut HashMap<_, _, _>;
+        assert!(
+            !components
+                .iter()
+                .any(|(info, _)| info.as_ref().type_id == TypeId::of::<EntityId>()),
+            "Cannot insert EntityId as a component"
+        );
+
+        // Get entity location
+        let Some(location) = self.tracking.get_location(entity_id) else {
+            for (info, component) in components {
+                info.drop(component);
+            }
+            return Vec::new();
+        };
+
+        // Get current archetype
+        let id = location.archetype_id();
+        let archetype = self
+            .archetypes
+            .get_mut(&id)
+            .expect("archetype should exist");
+
+        // Replace existing components in place, collect the new ones
+        let mut new_components = Vec::new();
+        for (info, component) in components {
+            let component = TypedComponentData::from_parts(info, component, tick, tick);
+            if archetype.has_type(&info.as_ref().type_id) {
+                archetype.set_component(entity_id, location, component);
+            } else {
+                new_components.push(component);
+            }
+        }
+
+        if new_components.is_empty() {
+            return Vec::new();
+        }
+
+        let added = new_components
+            .iter()
+            .map(|component| component.info.as_ref().type_id)
+            .collect();
+
+        // Remove entity from archetype and add new components
+        let mut removed = archetype.remove_entity(entity_id, location);
+        removed.components.append(&mut new_components);
+
+        // Sort components by type id
+        removed
+            .components
+            .sort_by_key(|component| component.info.as_ref().type_id);
+        assert!(
+            removed
+                .components
+                .windows(2)
+                .all(|w| w[0].info.as_ref().type_id != w[1].info.as_ref().type_id),
+            "Cannot insert duplicate components"
+        );
+
+        // Remove entity from tracking
+        self.tracking.remove_location(entity_id);
+
+        // Update swapped entity location
+        if let Some(swapped) = removed.swapped {
+            self.tracking.set_location(swapped, location);
+        }
+
+        // Safety: components are correct and sorted
+        let new_id = unsafe { Archetype::hash_sorted_components(&mut removed.components) };
+
+        // Safety: since `removed` references archetype, we need to do another mut borrow
+        // which is safe here because we are accessing a different archetype
+        let archetypes = unsafe { &mut *archetypes_ptr };
+
+        // Insert entity into new archetype
+        let new_archetype = archetypes.entry(new_id).or_insert_with(|| {
+            let infos = removed
+                .components
+                .iter()
+                .map(|component| component.info)
+                .collect();
+            Archetype::new(new_id, infos)
+        });
+
+        // Safety: components are correct and sorted
+        let new_location = unsafe { new_archetype.insert_entity(entity_id, removed.components) };
+
+        // Update entity location
+        self.tracking.set_location(entity_id, new_location);
+        added
     }
 
     /// Remove component
@@ -339,7 +499,7 @@ impl Entities {
     /// # Panics
     /// Panics if type_id is EntityId
     pub(crate) fn remove_component(&mut self, entity_id: EntityId, type_id: TypeId) {
-        let archetypes_ptr = &mut self.archetypes as *mut HashMap<_, _>;
+        let archetypes_ptr = &mut self.archetypes as *mut HashMap<_, _, _>;
         assert_ne!(
             type_id,
             TypeId::of::<EntityId>(),
@@ -458,6 +618,60 @@ impl Entities {
         Some(component)
     }
 
+    /// Get untyped component `type_id` if it exists, marking it as changed if `mark_changed` is
+    /// true
+    pub fn get_component_untyped(
+        &mut self,
+        entity_id: EntityId,
+        type_id: TypeId,
+        mark_changed: bool,
+    ) -> Option<UntypedPtrLt<'_>> {
+        let current_tick = self.tick();
+
+        let location = self.tracking.get_location(entity_id)?;
+        let entity_index = location.index();
+        let id = location.archetype_id();
+        let archetype = self
+            .archetypes
+            .get_mut(&id)
+            .expect("archetype should exist");
+
+        let component_index = archetype.try_component_index(&type_id)?;
+        let components = &mut archetype.components[component_index];
+
+        if mark_changed {
+            components.set_changed_at(entity_index, current_tick);
+        }
+
+        Some(components.get_untyped_lt(entity_index))
+    }
+
+    /// Returns true if the entity exists and has component `type_id`
+    pub(crate) fn has_component(&self, entity_id: EntityId, type_id: TypeId) -> bool {
+        let Some(location) = self.tracking.get_location(entity_id) else {
+            return false;
+        };
+
+        let id = location.archetype_id();
+        let archetype = self.archetypes.get(&id).expect("archetype should exist");
+        archetype.has_type(&type_id)
+    }
+
+    /// Returns the type ids of all components of the entity, empty if it doesn't exist
+    pub(crate) fn component_types(&self, entity_id: EntityId) -> Vec<TypeId> {
+        let Some(location) = self.tracking.get_location(entity_id) else {
+            return Vec::new();
+        };
+
+        let id = location.archetype_id();
+        let archetype = self.archetypes.get(&id).expect("archetype should exist");
+        archetype
+            .components
+            .iter()
+            .map(|components| components.get_type_id())
+            .collect()
+    }
+
     /// Add child to parent's Children component, and add Parent component to child
     ///
     /// # Panics
@@ -490,13 +704,13 @@ impl Entities {
             let children = Children::new(vec![child_id]);
             let mut children = ManuallyDrop::new(children);
             let ptr = unsafe { OwnedPtr::new_ref(&mut children) }; // safety: children not used after this
-            self.insert_component(parent_id, ptr, children_info, true);
+            self.insert_component(parent_id, ptr, children_info);
         }
 
         let parent = Parent::new(parent_id);
         let mut parent = ManuallyDrop::new(parent);
         let ptr = unsafe { OwnedPtr::new_ref(&mut parent) }; // safety: parent not used after this
-        self.insert_component(child_id, ptr, parent_info, true);
+        self.insert_component(child_id, ptr, parent_info);
     }
 
     /// Breaks the relation link between parent and child.
@@ -0,0 +1,74 @@
+use super::archetype::{Archetype, ArchetypeId};
+
+/// Snapshot of the entity storage, returned by [`Entities::stats`](super::Entities::stats).
+///
+/// Many small archetypes usually mean the archetypes are fragmented, e.g. by often inserting and
+/// removing marker components, which slows down queries.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EntitiesStats {
+    /// Amount of entities in all archetypes
+    pub entity_count: usize,
+    /// Amount of archetypes, including empty ones
+    pub archetype_count: usize,
+    /// Amount of archetypes without any entities
+    pub empty_archetype_count: usize,
+    /// Allocated memory of all component tables in bytes
+    pub memory_usage: usize,
+    /// Per archetype statistics, sorted by entity count, largest first
+    pub archetypes: Vec<ArchetypeStats>,
+}
+
+/// Statistics of a single [`Archetype`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ArchetypeStats {
+    pub id: ArchetypeId,
+    /// Amount of entities in the archetype
+    pub entity_count: usize,
+    /// Amount of component types in the archetype
+    pub component_count: usize,
+    /// Allocated memory of the component table in bytes
+    pub memory_usage: usize,
+}
+
+impl ArchetypeStats {
+    fn new(archetype: &Archetype) -> Self {
+        Self {
+            id: archetype.id(),
+            entity_count: archetype.len(),
+            component_count: archetype.component_count(),
+            memory_usage: archetype.memory_usage(),
+        }
+    }
+}
+
+impl EntitiesStats {
+    pub(super) fn new<'a>(archetypes: impl Iterator<Item = &'a Archetype>) -> Self {
+        let mut archetypes = archetypes.map(ArchetypeStats::new).collect::<Vec<_>>();
+        archetypes.sort_by_key(|stats| std::cmp::Reverse(stats.entity_count));
+
+        Self {
+            entity_count: archetypes.iter().map(|a| a.entity_count).sum(),
+            archetype_count: archetypes.len(),
+            empty_archetype_count: archetypes.iter().filter(|a| a.entity_count == 0).count(),
+            memory_usage: archetypes.iter().map(|a| a.memory_usage).sum(),
+            archetypes,
+        }
+    }
+
+    /// Returns the average amount of entities in non-empty archetypes
+    pub fn average_archetype_size(&self) -> f32 {
+        let used = self.archetype_count - self.empty_archetype_count;
+        if used == 0 {
+            return 0.0;
+        }
+
+        self.entity_count as f32 / used as f32
+    }
+
+    /// Returns archetypes with at least one but at most `max_entities` entities
+    pub fn small_archetypes(&self, max_entities: usize) -> impl Iterator<Item = &ArchetypeStats> {
+        self.archetypes
+            .iter()
+            .filter(move |a| a.entity_count > 0 && a.entity_count <= max_entities)
+    }
+}
@@ -0,0 +1,171 @@
+//! Policies for keeping the [`Parent`] / [`Children`] hierarchy consistent.
+//!
+//! When an entity is despawned non-recursively, its children are handled according to the
+//! [`OrphanPolicy`] in [`HierarchySettings`]. The [`HierarchyPlugin`] also validates the hierarchy
+//! every frame, detecting links to despawned entities and one-sided relations, which can be left
+//! behind by code working with [`Entities`](super::entities::Entities) directly.
+
+use std::any::TypeId;
+
+use crate::{
+    app::{App, Plugin},
+    macros::Resource,
+    prelude::{Children, EntityId, Parent, World},
+    query::{Query, RunQuery},
+    system::phase,
+};
+
+/// What happens to the children of an entity despawned with
+/// [`EntityCommands::despawn`](crate::system::commands::EntityCommands::despawn)
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrphanPolicy {
+    /// Children lose their [`Parent`] and become root entities
+    #[default]
+    Orphan,
+    /// Children are moved to the parent of the despawned entity, or orphaned if it has none
+    Reparent,
+    /// Children are despawned recursively
+    Despawn,
+}
+
+/// What the hierarchy validation does with broken relations
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HierarchyValidation {
+    /// Don't validate the hierarchy
+    Disabled,
+    /// Log broken relations
+    Report,
+    /// Log and repair broken relations
+    #[default]
+    Repair,
+}
+
+/// Settings for the entity hierarchy. Used as a resource.
+#[derive(Resource, Default, Debug, Clone, Copy)]
+pub struct HierarchySettings {
+    /// Policy for children of non-recursively despawned entities
+    pub orphan_policy: OrphanPolicy,
+    /// Behaviour of the validation system
+    pub validation: HierarchyValidation,
+}
+
+/// Adds [`HierarchySettings`] and the hierarchy validation system. For more information, see the
+/// [hierarchy module](crate::ecs::hierarchy).
+#[derive(Default)]
+pub struct HierarchyPlugin {
+    pub settings: HierarchySettings,
+}
+
+impl Plugin for HierarchyPlugin {
+    fn build(&self, app: &mut App) {
+        app.world.resources.insert(self.settings);
+        app.register_system(validate_hierarchy_system, phase::PostUpdate);
+    }
+}
+
+/// Returns true if `entity_id` exists, including its generation
+fn is_alive(world: &World, entity_id: EntityId) -> bool {
+    world.entities.get_component::<EntityId>(entity_id) == Some(&entity_id)
+}
+
+/// Detects [`Parent`] and [`Children`] links to despawned entities, and relations which are only
+/// stored on one side. Depending on [`HierarchySettings::validation`], they are reported or
+/// repaired, where the child's [`Parent`] is the source of truth.
+pub fn validate_hierarchy_system(world: &mut World) {
+    let validation = world
+        .resources
+        .try_get::<HierarchySettings>()
+        .map(|settings| settings.validation)
+        .unwrap_or_default();
+    if validation == HierarchyValidation::Disabled {
+        return;
+    }
+    let repair = validation == HierarchyValidation::Repair;
+
+    let tick = *world.tick;
+    let parents: Vec<(EntityId, EntityId)> =
+        Query::<(EntityId, &Parent)>::new(&mut world.entities, tick)
+            .iter_mut()
+            .into_iter()
+            .map(|(child, parent)| (child, parent.id))
+            .collect();
+    let children: Vec<(EntityId, Vec<EntityId>)> =
+        Query::<(EntityId, &Children)>::new(&mut world.entities, tick)
+            .iter_mut()
+            .into_iter()
+            .map(|(parent, children)| (parent, children.ids.clone()))
+            .collect();
+
+    for (child, parent) in parents {
+        if !is_alive(world, parent) {
+            tracing::warn!("Entity {child:?} has a despawned parent {parent:?}");
+            if repair {
+                world
+                    .entities
+                    .remove_component(child, TypeId::of::<Parent>());
+            }
+            continue;
+        }
+
+        let is_listed = world
+            .entities
+            .get_component::<Children>(parent)
+            .is_some_and(|children| children.ids.contains(&child));
+        if !is_listed {
+            tracing::warn!(
+                "Entity {child:?} is missing from the children of its parent {parent:?}"
+            );
+            if repair {
+                world.add_child(parent, child);
+            }
+        }
+    }
+
+    for (parent, ids) in children {
+        for child in ids {
+            let child_parent = is_alive(world, child)
+                .then(|| world.entities.get_component::<Parent>(child))
+                .flatten()
+                .map(|parent| parent.id);
+
+            if child_parent == Some(parent) {
+                continue;
+            }
+
+            match child_parent {
+                None if is_alive(world, child) => {
+                    tracing::warn!("Child {child:?} of entity {parent:?} has no parent link");
+                    if repair {
+                        world.add_child(parent, child);
+                    }
+                }
+                None => {
+                    tracing::warn!("Entity {parent:?} has a despawned child {child:?}");
+                    if repair {
+                        remove_listed_child(world, parent, child);
+                    }
+                }
+                Some(other) => {
+                    tracing::warn!("Child {child:?} of entity {parent:?} has parent {other:?}");
+                    if repair {
+                        remove_listed_child(world, parent, child);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Removes `child` from the [`Children`] of `parent` without touching the child
+fn remove_listed_child(world: &mut World, parent: EntityId, child: EntityId) {
+    let Some(children) = world.entities.get_component_mut::<Children>(parent) else {
+        return;
+    };
+
+    children.remove(child);
+    if children.ids.is_empty() {
+        world
+            .entities
+            .remove_component(parent, TypeId::of::<Children>());
+    }
+}
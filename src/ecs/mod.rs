@@ -1,5 +1,7 @@
 pub mod change_detection;
+pub mod conditions;
 pub mod entities;
+pub mod hierarchy;
 pub mod resources;
 pub mod state;
 pub mod tick;
@@ -10,13 +12,18 @@ pub mod store;
 
 pub mod prelude {
     pub use super::change_detection::ChangeDetection;
+    pub use super::conditions::*;
     pub use super::entities::{
-        Entities, EntityId,
+        Bundle, Entities, EntityId,
         components::{Component, Mut, Ref},
+        markers::{Disabled, Hidden, Static},
         relation::{Children, Parent},
+        stats::{ArchetypeStats, EntitiesStats},
     };
+    pub use super::hierarchy::{HierarchySettings, OrphanPolicy};
     pub use super::resources::{
-        FixedTime, FpsCounter, Res, ResMut, Resource, Resources, Time, Timer, TimerVariant,
+        FixedAlpha, FixedTime, FpsCounter, Res, ResMut, Resource, Resources, Rng, Time, Timer,
+        TimerVariant,
     };
     pub use super::state::{NextState, State, StateTransitionEvent, States, conditions::*};
     pub use super::tick::Tick;
@@ -1,7 +1,9 @@
 pub mod resources;
+pub mod rng;
 pub mod time;
 
 pub use resources::*;
+pub use rng::*;
 pub use time::*;
 
 /// A type which can be stored as a world resource. Accessed with [`Res`] and [`ResMut`]
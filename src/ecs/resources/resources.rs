@@ -14,7 +14,7 @@ use crate::{
         store::blob::BlobVec,
         tick::{Tick, TickStamp, TickStampMut},
     },
-    render_assets::{BindGroup, Buffer, Pipeline, RenderAssets},
+    render_assets::{BindGroup, BindGroupLayouts, Buffer, Pipeline, RenderAssets},
     renderer::{Image, Material, Mesh, Texture},
 };
 
@@ -268,15 +268,21 @@ impl Resources {
         self.insert(RenderAssets::<BindGroup>::new());
         self.insert(RenderAssets::<Pipeline>::new());
         self.insert(RenderAssets::<Texture>::new());
+        self.insert(BindGroupLayouts::default());
 
         // resources
         self.insert(AssetLoader::new());
         self.insert(ShaderLoader::new());
     }
 
-    /// Update some builtin resources
+    /// Update some builtin resources, if they exist. They are missing in apps without the
+    /// `TimePlugin`, e.g. in tests.
     pub(crate) fn update(&mut self) {
-        self.get_mut::<Time>().update();
-        self.get_mut::<FixedTime>().update();
+        if let Some(mut time) = self.try_get_mut::<Time>() {
+            time.update();
+        }
+        if let Some(mut fixed_time) = self.try_get_mut::<FixedTime>() {
+            fixed_time.update();
+        }
     }
 }
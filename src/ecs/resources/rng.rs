@@ -0,0 +1,165 @@
+use std::ops::Range;
+
+use crate::macros::Resource;
+
+/// Seeded pseudo random number generator (xoshiro256**). The same seed always produces the same
+/// sequence, so simulations using it can be replayed. Used as a resource, added by the
+/// [`RngPlugin`](crate::plugins::RngPlugin) or seeded by the
+/// [`DeterministicPlugin`](crate::plugins::DeterministicPlugin).
+///
+/// Not suitable for cryptography.
+#[derive(Resource, Debug, Clone)]
+pub struct Rng {
+    seed: u64,
+    state: [u64; 4],
+}
+
+impl Default for Rng {
+    /// Rng seeded from the current time
+    fn default() -> Self {
+        let seed = web_time::SystemTime::now()
+            .duration_since(web_time::UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos() as u64);
+
+        Self::new(seed)
+    }
+}
+
+impl Rng {
+    /// Create a new Rng from `seed`
+    pub fn new(seed: u64) -> Self {
+        // expand the seed with splitmix64, the state must not be all zeros
+        let mut x = seed;
+        let mut next = || {
+            x = x.wrapping_add(0x9e3779b97f4a7c15);
+            let mut z = x;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+            z ^ (z >> 31)
+        };
+
+        Self {
+            seed,
+            state: [next(), next(), next(), next()],
+        }
+    }
+
+    /// Returns the seed this Rng was created with
+    #[inline]
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns a new Rng seeded from this one, e.g. to give each system its own sequence which
+    /// doesn't depend on the order other systems draw numbers in
+    pub fn fork(&mut self) -> Self {
+        Self::new(self.u64())
+    }
+
+    /// Returns a new Rng seeded from this one's seed and `stream`, without advancing this one. The
+    /// same seed and stream always give the same sequence, no matter when or in which order
+    /// streams are created.
+    pub fn fork_stream(&self, stream: &str) -> Self {
+        // FNV-1a, stable between platforms and compiler versions unlike the std hasher
+        let hash = stream.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+        Self::new(self.seed ^ hash)
+    }
+
+    /// Returns the Rng stored in `local`, forked with [`Self::fork_stream`] on first use. Gives
+    /// each system its own reproducible sequence, which doesn't change when other systems draw
+    /// more or fewer numbers.
+    ///
+    /// ```ignore
+    /// fn spawn_enemies(rng: Res<Rng>, mut local: Local<Option<Rng>>) {
+    ///     let rng = rng.fork_local(&mut local, "spawn_enemies");
+    ///     let x = rng.range_f32(-10.0..10.0);
+    /// }
+    /// ```
+    pub fn fork_local<'a>(&self, local: &'a mut Option<Rng>, stream: &str) -> &'a mut Rng {
+        local.get_or_insert_with(|| self.fork_stream(stream))
+    }
+
+    /// Returns a random u64
+    pub fn u64(&mut self) -> u64 {
+        let [s0, s1, s2, s3] = &mut self.state;
+        let result = s1.wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = *s1 << 17;
+
+        *s2 ^= *s0;
+        *s3 ^= *s1;
+        *s1 ^= *s2;
+        *s0 ^= *s3;
+        *s2 ^= t;
+        *s3 = s3.rotate_left(45);
+
+        result
+    }
+
+    /// Returns a random u32
+    #[inline]
+    pub fn u32(&mut self) -> u32 {
+        (self.u64() >> 32) as u32
+    }
+
+    /// Returns a random f32 in `0.0..1.0`
+    #[inline]
+    pub fn f32(&mut self) -> f32 {
+        (self.u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Returns a random bool, true with `probability` in `0.0..=1.0`
+    #[inline]
+    pub fn bool(&mut self, probability: f32) -> bool {
+        self.f32() < probability
+    }
+
+    /// Returns a random f32 in `range`
+    #[inline]
+    pub fn range_f32(&mut self, range: Range<f32>) -> f32 {
+        range.start + self.f32() * (range.end - range.start)
+    }
+
+    /// Returns a random usize in `range`
+    ///
+    /// # Panics
+    /// If `range` is empty
+    pub fn range_usize(&mut self, range: Range<usize>) -> usize {
+        assert!(!range.is_empty(), "Cannot sample from an empty range");
+
+        // multiply-shift instead of modulo, the bias is negligible for game use
+        let len = (range.end - range.start) as u128;
+        range.start + ((self.u64() as u128 * len) >> 64) as usize
+    }
+
+    /// Returns a random i32 in `range`
+    ///
+    /// # Panics
+    /// If `range` is empty
+    pub fn range_i32(&mut self, range: Range<i32>) -> i32 {
+        assert!(!range.is_empty(), "Cannot sample from an empty range");
+
+        let len = range.end.abs_diff(range.start) as u64;
+        range
+            .start
+            .wrapping_add(((self.u64() as u128 * len as u128) >> 64) as i32)
+    }
+
+    /// Returns a random element of `slice`, or None if it's empty
+    pub fn choose<'a, T>(&mut self, slice: &'a [T]) -> Option<&'a T> {
+        if slice.is_empty() {
+            return None;
+        }
+
+        slice.get(self.range_usize(0..slice.len()))
+    }
+
+    /// Shuffles `slice` in place
+    pub fn shuffle<T>(&mut self, slice: &mut [T]) {
+        for i in (1..slice.len()).rev() {
+            let j = self.range_usize(0..i + 1);
+            slice.swap(i, j);
+        }
+    }
+}
@@ -1,7 +1,15 @@
 use crate::macros::Resource;
 use web_time::{Duration, Instant};
 
-/// Resource used for tracking time in the application
+/// Default [`Time::max_delta`]
+pub const DEFAULT_MAX_DELTA: f32 = 0.25;
+
+/// Default [`Time::smoothing`]
+pub const DEFAULT_SMOOTHING: f32 = 0.1;
+
+/// Resource used for tracking the frame time of the application, updated once per frame. Systems
+/// in phases with a fixed timestep should use [`Time::step_delta`], the accumulator driving those
+/// phases is the separate [`FixedTime`] resource.
 #[derive(Resource, Debug, Clone)]
 pub struct Time {
     /// Current world tick / frame count
@@ -10,8 +18,22 @@ pub struct Time {
     start: Instant,
     /// The exact time the last frame was rendered
     last_frame: Instant,
-    /// Duration since the last frame
+    /// Duration since the last frame, clamped to `max_delta`
     delta: f32,
+    /// Measured duration since the last frame
+    raw_delta: f32,
+    /// Exponential moving average of the delta
+    smoothed_delta: f32,
+    /// Sum of all deltas
+    elapsed: f64,
+    /// Upper limit of the delta, None disables the clamp
+    max_delta: Option<f32>,
+    /// Weight of the newest delta in the smoothed delta, from 0 to 1
+    smoothing: f32,
+    /// Step used as the delta instead of the measured frame time
+    fixed_step: Option<f32>,
+    /// Delta of the fixed timestep phase iteration being run, see [`Self::step_delta`]
+    phase_step: Option<f32>,
 }
 
 impl Default for Time {
@@ -24,6 +46,13 @@ impl Default for Time {
             start,
             last_frame,
             delta: 0.0,
+            raw_delta: 0.0,
+            smoothed_delta: 0.0,
+            elapsed: 0.0,
+            max_delta: Some(DEFAULT_MAX_DELTA),
+            smoothing: DEFAULT_SMOOTHING,
+            fixed_step: None,
+            phase_step: None,
         }
     }
 }
@@ -39,11 +68,61 @@ impl Time {
     #[inline]
     pub(crate) fn update(&mut self) {
         let now = Instant::now();
-        self.delta = now.duration_since(self.last_frame).as_secs_f32();
+        self.raw_delta = now.duration_since(self.last_frame).as_secs_f32();
+        self.delta = match (self.fixed_step, self.max_delta) {
+            (Some(step), _) => step,
+            (None, Some(max)) => self.raw_delta.min(max),
+            (None, None) => self.raw_delta,
+        };
+        self.smoothed_delta = match self.tick {
+            0 => self.delta,
+            _ => self.smoothed_delta + (self.delta - self.smoothed_delta) * self.smoothing,
+        };
+        self.elapsed += self.delta as f64;
         self.last_frame = now;
         self.tick += 1;
     }
 
+    /// Limit the delta to `max` seconds, so a long frame, e.g. after a hitch or a breakpoint,
+    /// doesn't advance the simulation in one huge step. None disables the limit, it's
+    /// [`DEFAULT_MAX_DELTA`] by default. It doesn't apply to the fixed step.
+    #[inline]
+    pub fn set_max_delta(&mut self, max: Option<f32>) {
+        self.max_delta = max;
+    }
+
+    /// Returns the upper limit of the delta, if set
+    #[inline]
+    pub fn max_delta(&self) -> Option<f32> {
+        self.max_delta
+    }
+
+    /// Set the weight of the newest delta in the [`smoothed_delta`](Self::smoothed_delta), from 0
+    /// to 1. Lower values are smoother but react slower, it's [`DEFAULT_SMOOTHING`] by default.
+    #[inline]
+    pub fn set_smoothing(&mut self, smoothing: f32) {
+        self.smoothing = smoothing.clamp(0.0, 1.0);
+    }
+
+    /// Returns the weight of the newest delta in the smoothed delta
+    #[inline]
+    pub fn smoothing(&self) -> f32 {
+        self.smoothing
+    }
+
+    /// Advance time by `step` seconds every frame instead of the measured frame time, so the
+    /// simulation doesn't depend on the frame rate. None restores the measured time.
+    #[inline]
+    pub fn set_fixed_step(&mut self, step: Option<f32>) {
+        self.fixed_step = step;
+    }
+
+    /// Returns the fixed step, if set
+    #[inline]
+    pub fn fixed_step(&self) -> Option<f32> {
+        self.fixed_step
+    }
+
     /// Returns the start time of the application
     #[inline]
     pub fn start(&self) -> Instant {
@@ -62,33 +141,109 @@ impl Time {
         self.tick
     }
 
-    /// Returns the duration of the last frame in seconds
+    /// Returns the number of frames since the application started
+    #[inline]
+    pub fn frame_count(&self) -> u64 {
+        self.tick
+    }
+
+    /// Returns the duration of the last frame in seconds, clamped to the
+    /// [`max_delta`](Self::max_delta). It's the fixed step if one is set.
     #[inline]
     pub fn delta(&self) -> f32 {
         self.delta
     }
 
-    /// Returns the elapsed time since the application started in seconds
+    /// Returns the [`delta`](Self::delta) as a [`Duration`]
+    #[inline]
+    pub fn delta_duration(&self) -> Duration {
+        Duration::from_secs_f32(self.delta)
+    }
+
+    /// Returns the measured duration of the last frame in seconds, without the clamp or the fixed
+    /// step
+    #[inline]
+    pub fn raw_delta(&self) -> f32 {
+        self.raw_delta
+    }
+
+    /// Returns the delta averaged over the last frames, useful for displays which shouldn't
+    /// flicker or for effects which shouldn't react to a single slow frame
+    #[inline]
+    pub fn smoothed_delta(&self) -> f32 {
+        self.smoothed_delta
+    }
+
+    /// Returns the time in seconds a single run of the current system covers. It's the fixed delta
+    /// inside a phase with the [`FixedTimestep`] policy, such as `FixedUpdate`, which may run
+    /// multiple times per frame, otherwise it's the [`delta`](Self::delta).
+    ///
+    /// [`FixedTimestep`]: crate::system::PhaseExecutionPolicy::FixedTimestep
+    #[inline]
+    pub fn step_delta(&self) -> f32 {
+        self.phase_step.unwrap_or(self.delta)
+    }
+
+    /// Returns true while a phase with a fixed timestep runs
+    #[inline]
+    pub fn in_fixed_step(&self) -> bool {
+        self.phase_step.is_some()
+    }
+
+    /// Set the delta of the fixed timestep phase being run, None when it finished
+    #[inline]
+    pub(crate) fn set_phase_step(&mut self, step: Option<f32>) {
+        self.phase_step = step;
+    }
+
+    /// Returns the elapsed time since the application started in seconds, the sum of every
+    /// [`delta`](Self::delta). Time lost to the [`max_delta`](Self::max_delta) clamp isn't
+    /// included, with a fixed step it's the simulated time.
     #[inline]
     pub fn elapsed(&self) -> f32 {
-        self.start.elapsed().as_secs_f32()
+        self.elapsed as f32
+    }
+
+    /// Returns the [`elapsed`](Self::elapsed) time as a [`Duration`], which keeps its precision
+    /// in long running applications
+    #[inline]
+    pub fn elapsed_duration(&self) -> Duration {
+        Duration::from_secs_f64(self.elapsed)
     }
 
-    /// Returns the frames per second (FPS) of the last frame
+    /// Returns the real time since the application started, including time lost to the clamp
+    #[inline]
+    pub fn since_startup(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// Returns the frames per second (FPS) of the last frame, based on the measured frame time
     #[inline]
     pub fn fps(&self) -> f32 {
-        1.0 / self.delta
+        1.0 / self.raw_delta
+    }
+
+    /// Returns the frames per second (FPS) based on the [`smoothed_delta`](Self::smoothed_delta)
+    #[inline]
+    pub fn smoothed_fps(&self) -> f32 {
+        1.0 / self.smoothed_delta
     }
 
     /// Sleep the thread to achieve a target frame rate. If `fps <= fps_target` it will do nothing.
     /// This should realy only be used once, at the end of the frame, since it will block the
     /// thread and not call [`Self::update`], so each call to this function will sleep the same.
     /// It's not very accurate since it's based on the delta time of the last frame.
+    ///
+    /// Does nothing on the web, where the browser limits the frame rate.
     #[inline]
     pub fn sleep(&mut self, fps_target: f32) {
+        if cfg!(target_arch = "wasm32") {
+            return;
+        }
+
         let fps = self.fps();
         if fps > fps_target {
-            let secs = 1.0 / fps_target - self.delta;
+            let secs = 1.0 / fps_target - self.raw_delta;
             std::thread::sleep(std::time::Duration::from_secs_f32(secs));
         }
     }
@@ -96,6 +251,10 @@ impl Time {
 
 /// Resource used for fixed time step updates. It will try to run the systems on average at a fixed
 /// rate, therefore it may run multiple times or zero times during udpate loop depending on the frame rate.
+///
+/// It measures the frame time with its own [`Time`], independent of the [`Time`] resource. Frames
+/// longer than [`DEFAULT_MAX_DELTA`] only add that much to the accumulator, so a hitch doesn't run
+/// the fixed phases many times to catch up.
 #[derive(Resource, Debug, Clone)]
 pub struct FixedTime {
     time: Time,
@@ -136,6 +295,18 @@ impl FixedTime {
     #[inline]
     pub fn set_fixed_delta(&mut self, fixed_delta: f32) {
         self.fixed_delta = fixed_delta;
+        if self.time.fixed_step().is_some() {
+            self.time.set_fixed_step(Some(fixed_delta));
+        }
+    }
+
+    /// Advance the internal time by exactly one fixed delta every frame, so [`Self::iter`] always
+    /// returns 1
+    #[inline]
+    pub fn set_lockstep(&mut self, lockstep: bool) {
+        self.accumulator = 0.0;
+        self.time
+            .set_fixed_step(lockstep.then_some(self.fixed_delta));
     }
 
     /// Returns the internal fixed delta time step
@@ -157,6 +328,44 @@ impl FixedTime {
 
         iter
     }
+
+    /// Returns the fraction of a fixed delta left in the accumulator after [`Self::iter`], from 0
+    /// to 1. Used to interpolate between the last two fixed steps when rendering.
+    #[inline]
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / self.fixed_delta).clamp(0.0, 1.0)
+    }
+}
+
+/// Resource with the interpolation alpha of the last phase with the
+/// [`FixedTimestep`](crate::system::PhaseExecutionPolicy::FixedTimestep) policy, such as
+/// `FixedUpdate`, updated every frame after it runs. Rendering can blend between the previous and
+/// the current fixed step state with it, so movement stays smooth when the frame rate differs
+/// from the fixed rate.
+///
+/// ```ignore
+/// fn interpolate(alpha: Res<FixedAlpha>, mut query: Query<(&mut Transform, &Physics)>) {
+///     for (transform, physics) in query.iter_mut() {
+///         transform.translation = physics.previous.lerp(physics.current, alpha.alpha());
+///     }
+/// }
+/// ```
+#[derive(Resource, Debug, Clone, Copy, Default)]
+pub struct FixedAlpha {
+    alpha: f32,
+}
+
+impl FixedAlpha {
+    /// Returns the fraction of a fixed step elapsed since the last one ran, from 0 to 1
+    #[inline]
+    pub fn alpha(&self) -> f32 {
+        self.alpha
+    }
+
+    #[inline]
+    pub(crate) fn set(&mut self, alpha: f32) {
+        self.alpha = alpha;
+    }
 }
 
 /// Resoruce used for tracking the FPS over time
@@ -1,18 +1,12 @@
-use std::time::Duration;
-
-use crate::{
-    event::EventReader,
-    prelude::*,
-    system::{IntoSystemCondition, SystemParam},
-};
+use crate::{event::EventReader, prelude::*, system::IntoSystemCondition};
 
 /// Creates a [Condition](IntoSystemCondition) which evaluates to true if the current state is
 /// exiting the provided `state`
 pub fn on_exit<S: States + 'static>(
     state: S,
 ) -> impl IntoSystemCondition<EventReader<StateTransitionEvent<S>>> {
-    let closure = move |transition_events: EventReader<StateTransitionEvent<S>>| {
-        transition_events.read().iter().any(|e| e.exiting(state))
+    let closure = move |mut transition_events: EventReader<StateTransitionEvent<S>>| {
+        transition_events.read().any(|e| e.exiting(state))
     };
     closure.build()
 }
@@ -22,17 +16,17 @@ pub fn on_exit<S: States + 'static>(
 pub fn on_enter<S: States + 'static>(
     state: S,
 ) -> impl IntoSystemCondition<EventReader<StateTransitionEvent<S>>> {
-    let closure = move |trasition_events: EventReader<StateTransitionEvent<S>>| {
-        trasition_events.read().iter().any(|e| e.entering(state))
+    let closure = move |mut trasition_events: EventReader<StateTransitionEvent<S>>| {
+        trasition_events.read().any(|e| e.entering(state))
     };
     closure.build()
 }
 
 /// [Condition](IntoSystemCondition) which evaluates to true if any state transition event has occured
 pub fn on_transition<S: States + 'static>(
-    event_reader: EventReader<StateTransitionEvent<S>>,
+    mut event_reader: EventReader<StateTransitionEvent<S>>,
 ) -> bool {
-    event_reader.has_any()
+    event_reader.read().next().is_some()
 }
 
 /// Creates a [Condition](IntoSystemCondition) which evaluates to true if the current state is `state`
@@ -48,48 +42,3 @@ pub fn not_in_state<S: States + 'static>(
     let closure = move |res: Option<Res<State<S>>>| res.is_none_or(|s| s.get() != state);
     closure.build()
 }
-
-/// Creates a [Condition](IntoSystemCondition) which negates the result of the provided condition
-/// TODO: Because of the current implementation, it requires 'world' param, thus making the system
-/// where this is used not parallel. This should be changed in the future.
-pub fn not<Params: SystemParam>(
-    condition: impl IntoSystemCondition<Params>,
-) -> impl IntoSystemCondition<&'static mut World> {
-    let mut condition = condition.build();
-    let closure = move |world: &mut World| !condition.run(world);
-    closure.build()
-}
-
-/// [Condition](IntoSystemCondition) which evaluates to true if any events of type `E` have been sent
-pub fn on_event<E: Event>(event_reader: EventReader<E>) -> bool {
-    event_reader.has_any()
-}
-
-/// [Condition](IntoSystemCondition) which evaluates to true if resource `R` has changed, or false
-/// if it doesn't exist
-pub fn resource_changed<R: Resource>(resource: Option<Res<R>>) -> bool {
-    resource.is_some_and(|r| r.has_changed())
-}
-
-/// [Condition](IntoSystemCondition) which evaluates to true if a resource `R` has been inserted,
-/// or false if it doesn't exist
-pub fn resource_added<R: Resource>(resource: Option<Res<R>>) -> bool {
-    resource.is_some_and(|r| r.was_added())
-}
-
-/// [Condition](IntoSystemCondition) which evaluates to true if resource `R` exists
-pub fn resource_exists<R: Resource>(resource: Option<Res<R>>) -> bool {
-    resource.is_some()
-}
-
-/// Creates a [Condition](IntoSystemCondition) which evaluates to true in intervals of `duration`,
-/// but at most once per frame. If you want a smaller duration you might want to use the
-/// [FixedUpdate](phase::FixedUpdate) system phase instead.
-pub fn on_internval(duration: Duration) -> impl IntoSystemCondition<Res<Time>> {
-    let mut timer = Timer::repeating(duration);
-    let closure = move |time: Res<Time>| {
-        timer.update(time.delta());
-        timer.just_finished()
-    };
-    closure.build()
-}
@@ -1,11 +1,13 @@
 use crate::app::App;
 use crate::prelude::{Component, EntityId};
 use crate::query::Query;
+use crate::render_assets::StagingBelt;
 use crate::renderer::newtype::{RenderCommandQueue, RenderQueue};
 use crate::system::commands::CommandQueue;
 
-use super::entities::Entities;
+use super::entities::{Bundle, BundleComponents, Entities};
 use super::entities::components::ComponentsRegistry;
+use super::hierarchy::HierarchySettings;
 use super::resources::Resources;
 use super::tick::Tick;
 
@@ -109,11 +111,16 @@ impl World {
         self.command_queue.apply(world)
     }
 
-    /// Flushes all queued render commands to the world
+    /// Flushes all queued render commands to the world, after the uploads of the staging belt
     #[inline]
     pub(crate) fn flush_render_commands(&mut self) {
-        let queue = self.resources.get_mut::<RenderQueue>();
-        queue.submit(self.render_command_queue.drain());
+        let queue = self.resources.get::<RenderQueue>();
+        match self.resources.try_get_mut::<StagingBelt>() {
+            Some(mut belt) => belt.submit(&queue, self.render_command_queue.drain()),
+            None => {
+                queue.submit(self.render_command_queue.drain());
+            }
+        }
     }
 
     /// Reborrows the world as a mutable reference with a different lifetime.
@@ -136,8 +143,21 @@ impl World {
         entity_id
     }
 
-    /// Inserts (or replaces) a component into an entity
-    #[inline]
+    /// Despawns an entity, its children are handled according to the
+    /// [`OrphanPolicy`](crate::ecs::hierarchy::OrphanPolicy) in
+    /// [`HierarchySettings`](crate::ecs::hierarchy::HierarchySettings)
+    pub fn despawn(&mut self, entity_id: EntityId) {
+        let policy = self
+            .resources
+            .try_get::<HierarchySettings>()
+            .map(|settings| settings.orphan_policy)
+            .unwrap_or_default();
+
+        self.entities.despawn_entity_with(entity_id, policy);
+    }
+
+    /// Inserts (or replaces) a component into an entity. If the component is newly added, its
+    /// [required components](Component::insert_required) are inserted too.
     pub fn insert_component<C: Component>(
         &mut self,
         entity_id: EntityId,
@@ -145,15 +165,55 @@ impl World {
         replace: bool,
     ) {
         use crate::ecs::ptr::OwnedPtr;
-        use std::mem::ManuallyDrop;
+        use std::{any::TypeId, mem::ManuallyDrop};
+
+        if !replace && self.entities.has_component(entity_id, TypeId::of::<C>()) {
+            return;
+        }
 
         let info = self.registry.get_or_register::<C>();
         let mut component = ManuallyDrop::new(component);
         // Safety: component is inserted and not used anymore
         let ptr = unsafe { OwnedPtr::new_ref(&mut component) };
 
-        self.entities
-            .insert_component(entity_id, ptr, info, replace);
+        if self.entities.insert_component(entity_id, ptr, info) {
+            C::insert_required(self, entity_id);
+        }
+    }
+
+    /// Spawns a new entity with every component of `bundle` and returns its id. The entity is
+    /// created directly in its final archetype.
+    pub fn spawn_bundle<B: Bundle>(&mut self, bundle: B) -> EntityId {
+        let entity_id = self.entities.tracking.new_id();
+        self.spawn_bundle_at(entity_id, bundle);
+        entity_id
+    }
+
+    /// Spawns an entity with an already allocated `entity_id`, used by deferred commands
+    pub(crate) fn spawn_bundle_at<B: Bundle>(&mut self, entity_id: EntityId, bundle: B) {
+        let mut components = BundleComponents::default();
+        bundle.push_components(&mut components);
+
+        components.insert(self, entity_id, |world, components| {
+            let added = components
+                .iter()
+                .map(|(info, _)| info.as_ref().type_id)
+                .collect();
+            world.entities.spawn_entity(entity_id, components);
+            added
+        });
+    }
+
+    /// Inserts (or replaces) every component of `bundle` into an entity, moving it to its new
+    /// archetype at most once. Newly added components insert their
+    /// [required components](Component::insert_required) too.
+    pub fn insert_bundle<B: Bundle>(&mut self, entity_id: EntityId, bundle: B) {
+        let mut components = BundleComponents::default();
+        bundle.push_components(&mut components);
+
+        components.insert(self, entity_id, |world, components| {
+            world.entities.insert_components(entity_id, components)
+        });
     }
 
     /// Adds a child entity to a parent entity
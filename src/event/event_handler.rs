@@ -1,3 +1,5 @@
+use std::{iter::Chain, slice::Iter};
+
 use crate::{
     event::Event,
     prelude::{Res, ResMut},
@@ -10,9 +12,18 @@ pub struct EventWriter<E: Event> {
     events: ResMut<Events<E>>,
 }
 
-/// Event handler for reading events
+/// Iterator over unread events, oldest first
+pub type EventIter<'a, E> = Chain<Iter<'a, E>, Iter<'a, E>>;
+
+/// Event handler for reading events. Each system has its own cursor, so it reads every event once,
+/// even if it doesn't run every frame.
 pub struct EventReader<E: Event> {
     events: Res<Events<E>>,
+    /// Id of the next unread event, stored in the system's state
+    ///
+    /// # Safety
+    /// Always valid, the state outlives the system run
+    cursor: *mut usize,
 }
 
 impl<E: Event> EventWriter<E> {
@@ -30,27 +41,46 @@ impl<E: Event> EventWriter<E> {
 }
 
 impl<E: Event> EventReader<E> {
-    /// Get a reader for reading events
+    /// Get a reader for reading events after `cursor`
+    #[inline]
+    pub(crate) fn new(events: Res<Events<E>>, cursor: &mut usize) -> EventReader<E> {
+        EventReader { events, cursor }
+    }
+
+    #[inline]
+    fn cursor(&self) -> usize {
+        unsafe { *self.cursor }
+    }
+
+    /// Read all unread events of type E and mark them as read
+    pub fn read(&mut self) -> EventIter<'_, E> {
+        let (older, newer) = self.events.read_from(self.cursor());
+        unsafe { *self.cursor = self.events.event_count() };
+        older.iter().chain(newer.iter())
+    }
+
+    /// Mark all events of type E as read
     #[inline]
-    pub(crate) fn new(events: Res<Events<E>>) -> EventReader<E> {
-        EventReader { events }
+    pub fn clear(&mut self) {
+        unsafe { *self.cursor = self.events.event_count() };
     }
 
-    /// Read all events of type E
+    /// Returns the amount of unread events of type E
     #[inline]
-    pub fn read(&self) -> &[E] {
-        self.events.read()
+    pub fn len(&self) -> usize {
+        let (older, newer) = self.events.read_from(self.cursor());
+        older.len() + newer.len()
     }
 
-    /// Check if any events of type E exist
+    /// Check if any unread events of type E exist
     #[inline]
     pub fn has_any(&self) -> bool {
-        !self.events.is_empty()
+        !self.is_empty()
     }
 
-    /// Check if no events of type E exist
+    /// Check if no unread events of type E exist
     #[inline]
     pub fn is_empty(&self) -> bool {
-        self.events.is_empty()
+        self.len() == 0
     }
 }
@@ -10,7 +10,7 @@ pub use winit::event::{ElementState, MouseScrollDelta};
 
 use glam::Vec2;
 
-/// System to apply all staged events
+/// System which drops the events written before the previous update
 pub fn apply_events<E: Event>(mut events: ResMut<Events<E>>) {
     events.apply();
 }
@@ -49,6 +49,19 @@ pub struct MouseMotion {
     pub delta: Vec2,
 }
 
+/// Event sent after the GPU device was lost and the renderer tried to recreate it.
+///
+/// On recovery, render assets and graph pipelines are recreated lazily. GPU resources created
+/// directly with the lost device, e.g. in startup systems, are not recreated and have to be
+/// handled by the user.
+#[derive(Event, Debug, Clone)]
+pub struct RenderDeviceLost {
+    pub reason: wgpu::DeviceLostReason,
+    pub message: String,
+    /// Whether a new device was created
+    pub recovered: bool,
+}
+
 /// Event for cursor movement. Stores the absolute position of the cursor.
 ///
 /// For relative movement, use [`MouseMotion`](MouseMotion).
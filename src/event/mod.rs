@@ -5,23 +5,25 @@ pub mod plugin;
 pub use event_handler::*;
 pub use events::*;
 
-/// Manager for events. Created events are stored in a staging area until the end of the frame.
+/// Manager for events. Written events can be read right away and are kept for two
+/// [`apply`](apply_events) updates, so systems which don't run every frame still see them. Every
+/// [`EventReader`] has its own cursor and reads each event once.
 #[derive(Debug, crate::macros::Resource)]
 pub struct Events<E: Event> {
-    /// Double buffer for events
-    /// storage: Current frame events
-    /// staging: Unapplied events, to be used in the next frame
-    buffers: [Vec<E>; 2],
-    /// Indicates if the storage and stagging buffers have been swapped. If false, storage will be
-    /// at index 0, otherwise at index 1
-    swapped: bool,
+    /// Events written before the last update, dropped in the next one
+    older: Vec<E>,
+    /// Events written since the last update
+    newer: Vec<E>,
+    /// Id of the first event in `older`, ids increase with every written event
+    older_start: usize,
 }
 
 impl<E: Event> Default for Events<E> {
     fn default() -> Self {
         Self {
-            buffers: [Vec::new(), Vec::new()],
-            swapped: false,
+            older: Vec::new(),
+            newer: Vec::new(),
+            older_start: 0,
         }
     }
 }
@@ -33,45 +35,31 @@ impl<E: Event> Events<E> {
         Self::default()
     }
 
-    /// Get the storage buffer index
-    #[inline]
-    fn storage(&self) -> usize {
-        if self.swapped { 1 } else { 0 }
-    }
-
-    /// Get the staging buffer index
-    #[inline]
-    fn staging(&self) -> usize {
-        if self.swapped { 0 } else { 1 }
-    }
-
-    /// Apply staged events to be used in the next frame
+    /// Drop the older events, newer events become older
     #[inline]
     pub(super) fn apply(&mut self) {
-        let storage = self.storage();
-
-        self.buffers[storage].clear();
-
-        self.swapped = !self.swapped;
+        self.older_start += self.older.len();
+        std::mem::swap(&mut self.older, &mut self.newer);
+        self.newer.clear();
     }
 
-    /// Write event `E` to the staging area
+    /// Write event `E`
     pub(super) fn write(&mut self, event: E) {
-        let staging = self.staging();
-        let staging = &mut self.buffers[staging];
-        staging.push(event);
+        self.newer.push(event);
     }
 
-    /// Read all events of type `E` from the storage
-    pub(super) fn read(&self) -> &[E] {
-        let storage = self.storage();
-        let storage = &self.buffers[storage];
-        storage.as_slice()
+    /// Returns the id of the next written event
+    #[inline]
+    pub(super) fn event_count(&self) -> usize {
+        self.older_start + self.older.len() + self.newer.len()
     }
 
-    /// Check if events of type `E` are empty
-    #[inline]
-    pub(super) fn is_empty(&self) -> bool {
-        self.buffers[self.storage()].is_empty()
+    /// Returns the stored events with an id of at least `cursor`, oldest first
+    pub(super) fn read_from(&self, cursor: usize) -> (&[E], &[E]) {
+        let skip = cursor.saturating_sub(self.older_start);
+        let older = &self.older[skip.min(self.older.len())..];
+        let skip = skip.saturating_sub(self.older.len());
+        let newer = &self.newer[skip.min(self.newer.len())..];
+        (older, newer)
     }
 }
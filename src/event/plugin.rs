@@ -1,5 +1,6 @@
 use crate::app::Plugin;
 use crate::event::*;
+use crate::window::WindowResized;
 
 /// Plugin for registering built-in event types from [`events`](crate::event::events)
 pub struct EventPlugin;
@@ -12,6 +13,8 @@ impl Plugin for EventPlugin {
             .register_event::<MouseInput>()
             .register_event::<MouseWheel>()
             .register_event::<MouseMotion>()
-            .register_event::<CursorMoved>();
+            .register_event::<CursorMoved>()
+            .register_event::<RenderDeviceLost>()
+            .register_event::<WindowResized>();
     }
 }
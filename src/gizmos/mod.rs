@@ -0,0 +1,276 @@
+//! # Gizmos
+//! Immediate mode debug drawing. Lines are requested through the [`Gizmos`] system parameter
+//! every frame they should be visible, and rendered by the `gizmos` graph node on top of the
+//! scene.
+//!
+//! ## Usage
+//!
+//! - Add the [`GizmoPlugin`] to the app, it is not part of the [`DefaultPlugins`].
+//! - Draw with the [`Gizmos`] parameter from any system, nothing is kept between frames.
+//! ```ignore
+//! app.add_plugin(GizmoPlugin);
+//!
+//! fn debug_system(mut gizmos: Gizmos, query: Query<(&GlobalTransform, &Agent)>) {
+//!     for (transform, agent) in query.iter_mut() {
+//!         gizmos.ray(transform.translation(), agent.velocity(), color::LIME);
+//!         gizmos.sphere(transform.translation(), 0.5, color::RED);
+//!     }
+//! }
+//! ```
+//!
+//! ## Bounding volumes
+//!
+//! Set [`GizmoConfig::bounding_volumes`] to draw the [`WorldBoundingVolume`] of every entity,
+//! which shows what the frustum culling tests against.
+
+mod render;
+
+pub mod prelude {
+    pub use super::{GizmoConfig, GizmoPlugin, Gizmos};
+}
+
+use glam::{Mat4, Vec3};
+
+use crate::{
+    plugins::RenderPlugin,
+    prelude::*,
+    renderer::palette,
+    system::{IntoParamInfo, ParamInfo, SystemContext, SystemParam},
+};
+
+/// Amount of line segments in circles and spheres
+const CIRCLE_SEGMENTS: usize = 32;
+
+/// Plugin which adds the [`GizmoConfig`] and the `gizmos` graph node drawing the [`Gizmos`]
+/// lines. For more information, see the [gizmos module](crate::gizmos).
+pub struct GizmoPlugin;
+
+impl Plugin for GizmoPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GizmoConfig>()
+            .init_resource::<GizmoBuffer>()
+            .add_startup_system(render::register_gizmo_graph)
+            .register_system(draw_bounding_volumes_system, phase::PreRender)
+            // lines of all systems are applied by now
+            .register_system(
+                render::prepare_gizmos_system,
+                phase::PreRender.layer(layer::Post),
+            );
+    }
+
+    fn dependencies(&self) -> Vec<PluginId> {
+        vec![PluginId::of::<RenderPlugin>()]
+    }
+}
+
+/// Settings of the gizmo drawing. Used as a resource.
+#[derive(Resource)]
+pub struct GizmoConfig {
+    /// Whether the lines are drawn, they are discarded otherwise
+    pub enabled: bool,
+    /// Draw the [`WorldBoundingVolume`] of every entity
+    pub bounding_volumes: bool,
+    pub bounding_volume_color: Color,
+}
+
+impl Default for GizmoConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            bounding_volumes: false,
+            bounding_volume_color: palette::YELLOW,
+        }
+    }
+}
+
+/// Vertex of a gizmo line
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct GizmoVertex {
+    position: [f32; 3],
+    color: [f32; 4],
+}
+
+/// Lines requested by [`Gizmos`] during the frame, and the lines drawn in the `gizmos` graph node
+#[derive(Default, Resource)]
+struct GizmoBuffer {
+    /// Lines applied by systems since the last `prepare_gizmos_system`
+    pending: Vec<GizmoVertex>,
+    /// Lines drawn this frame
+    lines: Vec<GizmoVertex>,
+}
+
+/// System parameter to draw debug lines for the current frame. Every system has its own buffer,
+/// which is applied after it runs, so drawing doesn't conflict with other systems. Lines drawn
+/// after the [`PreRender`](phase::PreRender) phase are shown next frame.
+///
+/// Shapes are drawn in world space, with [`GizmoPlugin`] added to the app.
+pub struct Gizmos<'s> {
+    lines: &'s mut Vec<GizmoVertex>,
+}
+
+impl Gizmos<'_> {
+    /// Draw a line from `start` to `end`
+    pub fn line(&mut self, start: Vec3, end: Vec3, color: Color) {
+        let color = [color.r, color.g, color.b, color.a];
+        self.lines.push(GizmoVertex {
+            position: start.to_array(),
+            color,
+        });
+        self.lines.push(GizmoVertex {
+            position: end.to_array(),
+            color,
+        });
+    }
+
+    /// Draw connected lines through `points`
+    pub fn line_strip(&mut self, points: impl IntoIterator<Item = Vec3>, color: Color) {
+        let mut points = points.into_iter();
+        let Some(mut previous) = points.next() else {
+            return;
+        };
+
+        for point in points {
+            self.line(previous, point, color);
+            previous = point;
+        }
+    }
+
+    /// Draw a line from `origin` to `origin + direction`
+    #[inline]
+    pub fn ray(&mut self, origin: Vec3, direction: Vec3, color: Color) {
+        self.line(origin, origin + direction, color);
+    }
+
+    /// Draw a circle around `center`, facing `normal`
+    pub fn circle(&mut self, center: Vec3, normal: Vec3, radius: f32, color: Color) {
+        let (u, v) = normal.normalize_or(Vec3::Y).any_orthonormal_pair();
+        let points = (0..=CIRCLE_SEGMENTS).map(|i| {
+            let angle = i as f32 / CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+            center + (u * angle.cos() + v * angle.sin()) * radius
+        });
+        self.line_strip(points, color);
+    }
+
+    /// Draw a sphere as three circles around its axes
+    pub fn sphere(&mut self, center: Vec3, radius: f32, color: Color) {
+        self.circle(center, Vec3::X, radius, color);
+        self.circle(center, Vec3::Y, radius, color);
+        self.circle(center, Vec3::Z, radius, color);
+    }
+
+    /// Draw the edges of an axis aligned box
+    pub fn aabb(&mut self, aabb: &AABB, color: Color) {
+        let (min, max) = (aabb.min, aabb.max);
+        let corners = [
+            Vec3::new(min.x, min.y, min.z),
+            Vec3::new(max.x, min.y, min.z),
+            Vec3::new(max.x, min.y, max.z),
+            Vec3::new(min.x, min.y, max.z),
+            Vec3::new(min.x, max.y, min.z),
+            Vec3::new(max.x, max.y, min.z),
+            Vec3::new(max.x, max.y, max.z),
+            Vec3::new(min.x, max.y, max.z),
+        ];
+        self.box_edges(&corners, color);
+    }
+
+    /// Draw the edges of an oriented box
+    pub fn obb(&mut self, obb: &OBB, color: Color) {
+        let he = obb.half_extents;
+        let corners = [
+            Vec3::new(-he.x, -he.y, -he.z),
+            Vec3::new(he.x, -he.y, -he.z),
+            Vec3::new(he.x, -he.y, he.z),
+            Vec3::new(-he.x, -he.y, he.z),
+            Vec3::new(-he.x, he.y, -he.z),
+            Vec3::new(he.x, he.y, -he.z),
+            Vec3::new(he.x, he.y, he.z),
+            Vec3::new(-he.x, he.y, he.z),
+        ]
+        .map(|corner| obb.center + obb.rotation.transform_vector3(corner));
+        self.box_edges(&corners, color);
+    }
+
+    /// Draw a bounding volume, nothing for [`WorldBoundingVolume::None`]
+    pub fn bounding_volume(&mut self, volume: &WorldBoundingVolume, color: Color) {
+        match volume {
+            WorldBoundingVolume::Sphere(sphere) => self.sphere(sphere.center, sphere.radius, color),
+            WorldBoundingVolume::AABB(aabb) => self.aabb(aabb, color),
+            WorldBoundingVolume::OBB(obb) => self.obb(obb, color),
+            WorldBoundingVolume::None => {}
+        }
+    }
+
+    /// Draw the three axes of `transform`, `X` in red, `Y` in green and `Z` in blue
+    pub fn axes(&mut self, transform: Mat4, length: f32) {
+        let origin = transform.transform_point3(Vec3::ZERO);
+        let axes = [
+            (Vec3::X, palette::RED),
+            (Vec3::Y, palette::LIME),
+            (Vec3::Z, palette::BLUE),
+        ];
+        for (axis, color) in axes {
+            let direction = transform.transform_vector3(axis).normalize_or_zero() * length;
+            self.ray(origin, direction, color);
+        }
+    }
+
+    /// Draw the 12 edges of a box, `corners` are the bottom face followed by the top face
+    fn box_edges(&mut self, corners: &[Vec3; 8], color: Color) {
+        for i in 0..4 {
+            let next = (i + 1) % 4;
+            self.line(corners[i], corners[next], color);
+            self.line(corners[i + 4], corners[next + 4], color);
+            self.line(corners[i], corners[i + 4], color);
+        }
+    }
+}
+
+impl IntoParamInfo for Gizmos<'_> {
+    /// Lines are buffered per system, so it doesn't access the world
+    fn params_info() -> Vec<ParamInfo> {
+        Vec::new()
+    }
+}
+
+impl SystemParam for Gizmos<'_> {
+    type State = Vec<GizmoVertex>;
+
+    #[inline]
+    fn extract(_world: &mut World, state: &mut Self::State, _context: &SystemContext) -> Self {
+        // Reborrow to satisfy lifetime requirements, the state outlives the system run
+        Gizmos {
+            lines: unsafe { &mut *(state as *mut Self::State) },
+        }
+    }
+
+    #[inline]
+    fn apply(world: &mut World, state: &mut Self::State, _context: &SystemContext) {
+        // discard the lines if the plugin isn't added
+        match world.resources.try_get_mut::<GizmoBuffer>() {
+            Some(mut buffer) => buffer.pending.append(state),
+            None => state.clear(),
+        }
+    }
+
+    #[inline]
+    fn init_state() -> Self::State {
+        Vec::new()
+    }
+}
+
+/// Draws the [`WorldBoundingVolume`] of every entity if [`GizmoConfig::bounding_volumes`] is set
+fn draw_bounding_volumes_system(
+    config: Res<GizmoConfig>,
+    mut gizmos: Gizmos,
+    mut query: Query<&WorldBoundingVolume>,
+) {
+    if !config.enabled || !config.bounding_volumes {
+        return;
+    }
+
+    for volume in query.iter_mut() {
+        gizmos.bounding_volume(volume, config.bounding_volume_color);
+    }
+}
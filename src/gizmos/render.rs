@@ -0,0 +1,183 @@
+use wgpu::{VertexAttribute, VertexFormat};
+
+use crate::{
+    assets::ShaderLoader,
+    core::{graph::*, standard::rendering::surface_size},
+    prelude::*,
+    render_assets::{BindGroup, Buffer, Pipeline, RenderAssets, pipeline::PipelineBuilder},
+    renderer::newtype::{RenderCommandEncoder, RenderDevice, RenderSurfaceConfiguration},
+};
+
+use super::{GizmoBuffer, GizmoConfig, GizmoVertex};
+
+impl GizmoVertex {
+    fn vertex_descriptor() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<GizmoVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                // Position
+                VertexAttribute {
+                    format: VertexFormat::Float32x3,
+                    offset: 0,
+                    shader_location: 0,
+                },
+                // Color
+                VertexAttribute {
+                    format: VertexFormat::Float32x4,
+                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                },
+            ],
+        }
+    }
+}
+
+/// Moves the lines applied during the frame into the ones drawn by the `gizmos` graph node
+pub(super) fn prepare_gizmos_system(config: Res<GizmoConfig>, mut buffer: ResMut<GizmoBuffer>) {
+    let buffer = &mut *buffer;
+    buffer.lines.clear();
+    if config.enabled {
+        std::mem::swap(&mut buffer.lines, &mut buffer.pending);
+    } else {
+        buffer.pending.clear();
+    }
+}
+
+/// Startup system to register the gizmo graph node
+pub(super) fn register_gizmo_graph(
+    graph: &mut RenderGraph,
+    device: Res<RenderDevice>,
+    surface_config: Res<RenderSurfaceConfiguration>,
+    mut shader_loader: ResMut<ShaderLoader>,
+) {
+    let pipeline_builder =
+        create_gizmo_pipeline_builder(&device, &surface_config, &mut shader_loader);
+
+    let node = GraphNodeBuilder::new("gizmos")
+        .set_pipeline(pipeline_builder)
+        .set_custom_system(gizmo_render_system)
+        .set_color_target(NodeColorTarget::Surface)
+        .run_after("main")
+        .run_before("ui_image")
+        .build();
+
+    graph.add(node);
+}
+
+/// Gizmo graph node rendering system, draws the lines prepared by [`prepare_gizmos_system`] into
+/// the viewport of every active camera
+fn gizmo_render_system(
+    graph_ctx: Res<RenderContext>,
+
+    world: &mut World,
+    encoder: &mut RenderCommandEncoder,
+    device: Res<RenderDevice>,
+    buffer: Res<GizmoBuffer>,
+    mut bind_groups: ResMut<RenderAssets<BindGroup>>,
+
+    mut camera_query: Query<(EntityId, &Camera), With<Camera3D>>,
+    rendered: Res<RenderedWindow>,
+) {
+    if buffer.lines.is_empty() {
+        return;
+    }
+
+    let cameras = Camera::rendering_to(camera_query.iter_mut(), &rendered);
+    if cameras.is_empty() {
+        return;
+    }
+
+    let lines = Buffer::new("gizmos").create_vertex_buffer(
+        &buffer.lines,
+        buffer.lines.len(),
+        None,
+        &device,
+    );
+    let vertex_buffer = lines
+        .vertex
+        .as_ref()
+        .expect("Gizmo lines should not be empty");
+
+    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("gizmos render pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: unsafe { &*graph_ctx.color_target.expect("gizmos color target is None") },
+            depth_slice: None,
+            resolve_target: graph_ctx.resolve_target.map(|view| unsafe { &*view }),
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    });
+
+    render_pass.set_pipeline(
+        unsafe { &*graph_ctx.node }
+            .data
+            .pipeline
+            .as_ref()
+            .expect("Pipeline should have been generated by now")
+            .render_pipeline(),
+    );
+    render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+
+    let target_size = surface_size(world);
+    let mut draw_calls = 0;
+    for (camera_id, camera) in cameras {
+        if !camera.set_render_pass_camera(
+            camera_id,
+            &mut render_pass,
+            0,
+            target_size,
+            &mut bind_groups,
+            world,
+        ) {
+            continue;
+        }
+        render_pass.draw(0..lines.num_vertices, 0..1);
+        draw_calls += 1;
+    }
+
+    graph_ctx.record_draw_calls(draw_calls);
+    graph_ctx.record_bind_group_changes(draw_calls);
+}
+
+fn create_gizmo_pipeline_builder(
+    device: &RenderDevice,
+    surface_config: &RenderSurfaceConfiguration,
+    shader_loader: &mut ShaderLoader,
+) -> PipelineBuilder {
+    // Camera bind group layout for uniform buffer
+    let camera_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("camera_bind_group_layout"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+    });
+
+    // the line shader is shared with the inspector gizmo
+    shader_loader.load("gizmo", include_str!("../shaders/gizmo.wgsl"), device);
+
+    let mut primitive_state = PipelineBuilder::default_primitive_state();
+    primitive_state.topology = wgpu::PrimitiveTopology::LineList;
+    primitive_state.cull_mode = None;
+
+    Pipeline::build("gizmos_pipeline")
+        .set_bind_group_layouts(vec![camera_layout])
+        .set_vertex_buffer_layouts(vec![GizmoVertex::vertex_descriptor()])
+        .set_vertex_shader("gizmo", "vs_main")
+        .set_fragment_shader("gizmo", "fs_main")
+        .add_color_format(surface_config.format)
+        .set_primitive_state(primitive_state)
+}
@@ -13,6 +13,24 @@ pub mod ecs;
 pub mod event;
 pub mod audio;
 pub mod reflect;
+pub mod picking;
+pub mod tilemap;
+pub mod sprite;
+pub mod nav;
+pub mod scatter;
+pub mod terrain;
+pub mod water;
+pub mod log;
+pub mod diagnostics;
+pub mod gizmos;
+pub mod network;
+pub mod camera_controller;
+pub mod localization;
+pub mod console;
+#[cfg(feature = "scripting")]
+pub mod scripting;
+#[cfg(feature = "persistence")]
+pub mod persistence;
 
 pub use renderer::palette;
 pub use app::input;
@@ -25,3 +43,7 @@ pub use winit;
 pub use image;
 pub use wgpu;
 pub use glam;
+#[cfg(feature = "serialize")]
+pub use serde;
+#[cfg(feature = "serialize")]
+pub use ron;
@@ -0,0 +1,281 @@
+//! # Localization plugin
+//! Translates [`LocalizedText`] components into UI [`Text`] for the active locale of the
+//! [`Localization`] resource. Messages are stored in [`Translations`] assets, loaded from Fluent
+//! (`.ftl`) or simple key-value files.
+//!
+//! ## Usage
+//!
+//! ```ignore
+//! app.add_plugin(LocalizationPlugin::new("en-US").with_fallback("en-US"));
+//!
+//! fn setup(world: &mut World, mut commands: Commands) {
+//!     let mut loader = world.resources.get_mut::<AssetLoader>();
+//!     let en = loader.load::<Translations>("assets/locales/en-US.ftl", &mut world.resources);
+//!     world.resources.get_mut::<Localization>().add(en);
+//!
+//!     commands
+//!         .spawn_empty()
+//!         .insert(Node::default())
+//!         .insert(LocalizedText::new("hello").with_arg("name", "Vavo"));
+//! }
+//!
+//! fn switch_language(mut localization: ResMut<Localization>) {
+//!     localization.set_locale("de-DE");
+//! }
+//! ```
+//!
+//! Changing the locale re-resolves every [`LocalizedText`] in the next
+//! [`PreUpdate`](phase::PreUpdate) phase. Entities without a [`Text`] get one inserted, existing
+//! texts keep their font, size and color and only their content is replaced.
+
+mod translations;
+
+pub mod prelude {
+    pub use super::{Localization, LocalizationPlugin, LocalizedText, Translations};
+}
+
+pub use translations::Translations;
+
+use crate::{prelude::*, ui::prelude::Text};
+
+/// Plugin which adds the [`Localization`] resource and resolves [`LocalizedText`]. For more
+/// information, see the [localization module](crate::localization).
+pub struct LocalizationPlugin {
+    /// Initially active locale
+    pub locale: String,
+    /// Locale used for messages missing in the active locale
+    pub fallback: Option<String>,
+}
+
+impl Default for LocalizationPlugin {
+    fn default() -> Self {
+        Self::new("en-US")
+    }
+}
+
+impl LocalizationPlugin {
+    /// Create a new plugin with the initially active `locale`
+    pub fn new(locale: impl Into<String>) -> Self {
+        Self {
+            locale: locale.into(),
+            fallback: None,
+        }
+    }
+
+    /// Returns self with new `fallback` locale
+    #[must_use]
+    pub fn with_fallback(mut self, fallback: impl Into<String>) -> Self {
+        self.fallback = Some(fallback.into());
+        self
+    }
+}
+
+impl Plugin for LocalizationPlugin {
+    fn build(&self, app: &mut App) {
+        let mut localization = Localization::new(self.locale.clone());
+        localization.fallback = self.fallback.clone();
+
+        app.init_resource::<Assets<Translations>>()
+            .set_resource(localization)
+            .register_system(resolve_localized_text, phase::PreUpdate);
+    }
+}
+
+/// Active locale and the translations of every locale. Used as a resource.
+#[derive(Resource, Debug)]
+pub struct Localization {
+    locale: String,
+    /// Locale used for messages missing in the active locale
+    pub fallback: Option<String>,
+    translations: Vec<Handle<Translations>>,
+    /// Incremented when the locale or translations change
+    generation: u64,
+}
+
+impl Localization {
+    /// Create a new localization with the active `locale` and no translations
+    pub fn new(locale: impl Into<String>) -> Self {
+        Self {
+            locale: locale.into(),
+            fallback: None,
+            translations: Vec::new(),
+            generation: 0,
+        }
+    }
+
+    /// Returns the active locale
+    #[inline]
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// Set the active locale, every [`LocalizedText`] is resolved again
+    pub fn set_locale(&mut self, locale: impl Into<String>) {
+        let locale = locale.into();
+        if locale != self.locale {
+            self.locale = locale;
+            self.generation += 1;
+        }
+    }
+
+    /// Add `translations`, their locale is read from the asset. Translations added later take
+    /// precedence over earlier ones with the same locale.
+    pub fn add(&mut self, translations: Handle<Translations>) {
+        if !self.translations.contains(&translations) {
+            self.translations.push(translations);
+            self.generation += 1;
+        }
+    }
+
+    /// Remove `translations`
+    pub fn remove(&mut self, translations: &Handle<Translations>) {
+        let len = self.translations.len();
+        self.translations.retain(|handle| handle != translations);
+        if self.translations.len() != len {
+            self.generation += 1;
+        }
+    }
+
+    /// Mark every [`LocalizedText`] to be resolved again, e.g. after editing [`Translations`]
+    pub fn refresh(&mut self) {
+        self.generation += 1;
+    }
+
+    /// Returns the locales which have translations
+    pub fn locales<'a>(&self, assets: &'a Assets<Translations>) -> Vec<&'a str> {
+        let mut locales = Vec::new();
+        for translations in self.translations.iter().filter_map(|h| assets.get(h)) {
+            if !locales.contains(&translations.locale.as_str()) {
+                locales.push(translations.locale.as_str());
+            }
+        }
+        locales
+    }
+
+    /// Format message `id` with `args` in the active locale, then the fallback locale. Returns
+    /// None if neither has the message.
+    pub fn try_format(
+        &self,
+        id: &str,
+        args: &[(String, String)],
+        assets: &Assets<Translations>,
+    ) -> Option<String> {
+        let fallback = self.fallback.as_deref().filter(|f| *f != self.locale);
+
+        std::iter::once(self.locale.as_str())
+            .chain(fallback)
+            .find_map(|locale| {
+                self.translations
+                    .iter()
+                    .rev()
+                    .filter_map(|handle| assets.get(handle))
+                    .filter(|translations| translations.locale == locale)
+                    .find_map(|translations| translations.format(id, args))
+            })
+    }
+
+    /// Format message `id` with `args`, or returns the `id` itself if the message doesn't exist in
+    /// the active or fallback locale
+    pub fn format(
+        &self,
+        id: &str,
+        args: &[(String, String)],
+        assets: &Assets<Translations>,
+    ) -> String {
+        self.try_format(id, args, assets).unwrap_or_else(|| {
+            tracing::warn!(
+                "Missing translation for '{}' in locale '{}'",
+                id,
+                self.locale
+            );
+            id.to_string()
+        })
+    }
+}
+
+/// Text resolved from a message of the active locale into the entity's [`Text`] content. Args
+/// fill `{ $name }` variables of the message.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct LocalizedText {
+    /// Message id
+    pub key: String,
+    /// Variables by name
+    pub args: Vec<(String, String)>,
+}
+
+impl LocalizedText {
+    /// Create a new localized text for message `key`, without args
+    pub fn new(key: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            args: Vec::new(),
+        }
+    }
+
+    /// Returns self with variable `name` set to `value`
+    #[must_use]
+    pub fn with_arg(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+        self.set_arg(name, value);
+        self
+    }
+
+    /// Set variable `name` to `value`, replacing the old value
+    pub fn set_arg(&mut self, name: impl Into<String>, value: impl ToString) {
+        let name = name.into();
+        let value = value.to_string();
+
+        match self.args.iter_mut().find(|(arg, _)| *arg == name) {
+            Some((_, old)) => *old = value,
+            None => self.args.push((name, value)),
+        }
+    }
+}
+
+/// System which writes resolved [`LocalizedText`] into [`Text`]. Runs for changed localized texts,
+/// or for all of them when the [`Localization`] changes.
+pub fn resolve_localized_text(
+    mut commands: Commands,
+    localization: Res<Localization>,
+    translations: Res<Assets<Translations>>,
+    mut resolved_generation: Local<Option<u64>>,
+    mut q: Query<()>,
+) {
+    let all = *resolved_generation != Some(localization.generation);
+    *resolved_generation = Some(localization.generation);
+
+    let mut updates = Vec::new();
+    let mut resolve = |id: EntityId, localized: &LocalizedText, text: Option<&Text>| {
+        let content = localization.format(&localized.key, &localized.args, &translations);
+        match text {
+            // texts are fetched mutably only when the content differs, so the layout isn't rebuilt
+            Some(text) if text.content == content => {}
+            Some(_) => updates.push((id, content)),
+            None => {
+                commands.entity(id).insert(Text::new(content));
+            }
+        }
+    };
+
+    if all {
+        for (id, localized, text) in q
+            .cast::<(EntityId, &LocalizedText, Option<&Text>), ()>()
+            .iter_mut()
+        {
+            resolve(id, localized, text);
+        }
+    } else {
+        for (id, localized, text) in q
+            .cast::<(EntityId, &LocalizedText, Option<&Text>), Changed<LocalizedText>>()
+            .iter_mut()
+        {
+            resolve(id, localized, text);
+        }
+    }
+
+    let mut texts = q.cast::<&mut Text, ()>();
+    for (id, content) in updates {
+        if let Some(text) = texts.get(id) {
+            text.content = content;
+        }
+    }
+}
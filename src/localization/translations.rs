@@ -0,0 +1,206 @@
+use std::{collections::HashMap, fmt::Debug, path::Path};
+
+use crate::{
+    assets::{AssetLoader, AsyncLoadableAsset, LoadableAsset, io},
+    prelude::Resources,
+};
+
+/// Maximum depth of message and term references, stops reference cycles
+const MAX_REFERENCE_DEPTH: usize = 8;
+
+/// Messages of a single locale, parsed from a Fluent (`.ftl`) or a simple key-value file.
+///
+/// Supported syntax:
+/// ```text
+/// # comments start with a hash
+/// hello = Hello, { $name }!
+/// -brand = Vavo
+/// about = About { -brand }
+/// multiline =
+///     Indented lines continue
+///     the previous message
+/// ```
+///
+/// Placeables can reference variables (`{ $name }`), terms (`{ -term }`), other messages
+/// (`{ message }`) and string literals (`{ "{" }`). Selectors, functions and attributes are not
+/// supported, attributes are skipped.
+#[derive(Debug, Clone, Default, crate::macros::Asset)]
+pub struct Translations {
+    /// Locale of the messages, e.g. `en-US`
+    pub locale: String,
+    /// Message and term patterns by their id, terms keep the leading `-`
+    messages: HashMap<String, String>,
+}
+
+impl Translations {
+    /// Create new empty translations for `locale`
+    pub fn new(locale: impl Into<String>) -> Self {
+        Self {
+            locale: locale.into(),
+            messages: HashMap::new(),
+        }
+    }
+
+    /// Parse translations for `locale` from the contents of a Fluent or key-value file
+    pub fn parse(locale: impl Into<String>, source: &str) -> Self {
+        let mut translations = Self::new(locale);
+        let mut current: Option<String> = None;
+
+        for line in source.lines() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            // indented lines continue the current message, except for attributes
+            if line.starts_with(char::is_whitespace) {
+                if trimmed.starts_with('.') {
+                    continue;
+                }
+
+                if let Some(pattern) = current
+                    .as_ref()
+                    .and_then(|id| translations.messages.get_mut(id))
+                {
+                    if !pattern.is_empty() {
+                        pattern.push('\n');
+                    }
+                    pattern.push_str(trimmed);
+                }
+                continue;
+            }
+
+            current = None;
+            if let Some((id, pattern)) = line.split_once('=') {
+                let id = id.trim();
+                if !id.is_empty() {
+                    translations.insert(id, pattern.trim());
+                    current = Some(id.to_string());
+                }
+            }
+        }
+
+        translations
+    }
+
+    /// Insert or replace a message, `id` starting with `-` is a term
+    pub fn insert(&mut self, id: impl Into<String>, pattern: impl Into<String>) {
+        self.messages.insert(id.into(), pattern.into());
+    }
+
+    /// Whether a message with `id` exists
+    pub fn contains(&self, id: &str) -> bool {
+        self.messages.contains_key(id)
+    }
+
+    /// Returns the unformatted pattern of a message
+    pub fn pattern(&self, id: &str) -> Option<&str> {
+        self.messages.get(id).map(String::as_str)
+    }
+
+    /// Format a message with `args`, or None if the message doesn't exist. Unknown variables and
+    /// references are left in the output in braces.
+    pub fn format(&self, id: &str, args: &[(String, String)]) -> Option<String> {
+        let pattern = self.messages.get(id)?;
+        let mut output = String::with_capacity(pattern.len());
+        self.format_pattern(pattern, args, 0, &mut output);
+        Some(output)
+    }
+
+    fn format_pattern(
+        &self,
+        pattern: &str,
+        args: &[(String, String)],
+        depth: usize,
+        output: &mut String,
+    ) {
+        let mut rest = pattern;
+
+        while let Some(start) = rest.find('{') {
+            output.push_str(&rest[..start]);
+
+            let Some(end) = Self::placeable_end(&rest[start..]) else {
+                output.push_str(&rest[start..]);
+                return;
+            };
+
+            let placeable = rest[start + 1..start + end].trim();
+            self.format_placeable(placeable, args, depth, output);
+            rest = &rest[start + end + 1..];
+        }
+
+        output.push_str(rest);
+    }
+
+    fn format_placeable(
+        &self,
+        placeable: &str,
+        args: &[(String, String)],
+        depth: usize,
+        output: &mut String,
+    ) {
+        if let Some(literal) = placeable
+            .strip_prefix('"')
+            .and_then(|literal| literal.strip_suffix('"'))
+        {
+            output.push_str(literal);
+            return;
+        }
+
+        if let Some(name) = placeable.strip_prefix('$') {
+            match args.iter().find(|(arg, _)| arg == name) {
+                Some((_, value)) => output.push_str(value),
+                None => output.push_str(&format!("{{${name}}}")),
+            }
+            return;
+        }
+
+        // term or message reference
+        match self.messages.get(placeable) {
+            Some(pattern) if depth < MAX_REFERENCE_DEPTH => {
+                self.format_pattern(pattern, args, depth + 1, output)
+            }
+            _ => output.push_str(&format!("{{{placeable}}}")),
+        }
+    }
+
+    /// Returns the index of the `}` closing the placeable at the start of `source`, skipping braces
+    /// inside string literals
+    fn placeable_end(source: &str) -> Option<usize> {
+        let mut in_literal = false;
+
+        for (i, c) in source.char_indices().skip(1) {
+            match c {
+                '"' => in_literal = !in_literal,
+                '}' if !in_literal => return Some(i),
+                _ => {}
+            }
+        }
+
+        None
+    }
+}
+
+impl LoadableAsset for Translations {
+    /// Loads translations, the locale is the file name without the extension, e.g. `en-US.ftl`
+    fn load<P: AsRef<Path> + Debug>(_: &mut AssetLoader, _: &mut Resources, path: P) -> Self {
+        let bytes = io::read(path.as_ref())
+            .unwrap_or_else(|err| panic!("Failed to read translations from '{:?}': {}", path, err));
+        Self::decode(bytes, path.as_ref()).unwrap_or_else(|err| panic!("{}", err))
+    }
+}
+
+impl AsyncLoadableAsset for Translations {
+    /// Parses translations, the locale is the file name without the extension
+    fn decode(bytes: Vec<u8>, path: &Path) -> Result<Self, String> {
+        let source = String::from_utf8(bytes)
+            .map_err(|err| format!("Translations at '{:?}' are not utf-8: {}", path, err))?;
+
+        let locale = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        Ok(Self::parse(locale, &source))
+    }
+}
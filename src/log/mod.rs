@@ -0,0 +1,205 @@
+//! # Log plugin
+//! Structured logging built on [`tracing`], with per-module level filters, a console writer and
+//! an in-game log overlay.
+//!
+//! ## Usage
+//!
+//! - The [`LogPlugin`] is part of the [`DefaultPlugins`], to change its settings replace it in the
+//!   plugin group.
+//! - Log with the re-exported [`tracing`] macros.
+//! ```ignore
+//! app.add_plugins(
+//!     DefaultPlugins
+//!         .build()
+//!         .set(LogPlugin::default().with_filter("my_game::ai", Level::DEBUG)),
+//! );
+//!
+//! info!("spawned {} enemies", count);
+//! warn!(target: "my_game::ai", "no path found");
+//! ```
+//!
+//! ## History and overlay
+//!
+//! Every log which passes the filter is stored in the [`LogHistory`] resource and sent as a
+//! [`LogEvent`]. The overlay shows the latest entries of the history, it's toggled with
+//! [`LogPlugin::overlay_toggle`].
+
+mod overlay;
+mod subscriber;
+
+pub mod prelude {
+    pub use super::{Level, LogEntry, LogEvent, LogHistory, LogPlugin};
+    pub use tracing::{debug, error, info, trace, warn};
+}
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use crate::{event::EventWriter, prelude::*};
+
+pub use tracing::{self, Level};
+
+use subscriber::{LogFilter, LogSubscriber};
+
+/// Plugin which installs the global log subscriber. For more information, see the
+/// [log module](crate::log).
+///
+/// # Note
+/// Only one subscriber can be installed per process. If one already exists, logs are handled by
+/// it and the [`LogHistory`] stays empty.
+pub struct LogPlugin {
+    /// Default level for every module
+    pub level: Level,
+    /// Per-module levels, `(target, level)`. The longest matching target wins, `"vavo"` matches
+    /// `"vavo::window"` too.
+    pub filters: Vec<(String, Level)>,
+    /// Whether to write logs to the console
+    pub console: bool,
+    /// Amount of entries kept in the [`LogHistory`]
+    pub capacity: usize,
+    /// Key which toggles the log overlay, or None to disable it
+    pub overlay_toggle: Option<KeyCode>,
+}
+
+impl Default for LogPlugin {
+    fn default() -> Self {
+        Self {
+            level: Level::INFO,
+            filters: Vec::new(),
+            console: true,
+            capacity: 256,
+            overlay_toggle: Some(KeyCode::F1),
+        }
+    }
+}
+
+impl LogPlugin {
+    /// Returns self with a new level filter for `target` module
+    #[must_use]
+    pub fn with_filter(mut self, target: &str, level: Level) -> Self {
+        self.filters.push((target.to_string(), level));
+        self
+    }
+}
+
+impl Plugin for LogPlugin {
+    fn build(&self, app: &mut App) {
+        // plugin could be added twice, e.g. on its own and with the default plugins
+        if app.world.resources.contains::<LogHistory>() {
+            return;
+        }
+
+        let history = LogHistory::new(self.capacity);
+        let subscriber = LogSubscriber::new(
+            LogFilter::new(self.level, self.filters.clone()),
+            self.console,
+            self.capacity,
+            history.pending.clone(),
+        );
+
+        if tracing::subscriber::set_global_default(subscriber).is_err() {
+            tracing::warn!("Global log subscriber is already set, LogPlugin will not install one");
+        }
+
+        app.world.resources.insert(history);
+        app.register_event::<LogEvent>()
+            .register_system(collect_logs_system, phase::First);
+
+        if let Some(key) = self.overlay_toggle {
+            overlay::build(app, key);
+        }
+    }
+}
+
+/// Single log record
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogEntry {
+    pub level: Level,
+    /// Module path or custom target of the log
+    pub target: String,
+    /// Formatted message, followed by the other fields as `key=value`
+    pub message: String,
+}
+
+/// Event sent for every new [`LogEntry`]
+#[derive(Event, Debug, Clone)]
+pub struct LogEvent {
+    pub entry: LogEntry,
+}
+
+/// Latest log entries, up to its capacity. Used as a resource.
+#[derive(Resource)]
+pub struct LogHistory {
+    entries: VecDeque<LogEntry>,
+    capacity: usize,
+    /// Incremented every time new entries are added
+    version: u64,
+    /// Entries written by the subscriber, which were not collected yet
+    pending: Arc<Mutex<VecDeque<LogEntry>>>,
+}
+
+impl LogHistory {
+    fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+            version: 0,
+            pending: Arc::default(),
+        }
+    }
+
+    /// Returns the entries, oldest first
+    #[inline]
+    pub fn entries(&self) -> &VecDeque<LogEntry> {
+        &self.entries
+    }
+
+    /// Returns the last `n` entries, oldest first
+    pub fn last(&self, n: usize) -> impl Iterator<Item = &LogEntry> {
+        self.entries
+            .iter()
+            .skip(self.entries.len().saturating_sub(n))
+    }
+
+    /// Removes all entries
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.version += 1;
+    }
+
+    /// Returns the version of the history, which changes when entries change
+    #[inline]
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    fn push(&mut self, entry: LogEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+}
+
+/// System which moves logs from the subscriber into the [`LogHistory`] and sends them as
+/// [`LogEvent`]s, runs in the First phase.
+fn collect_logs_system(mut history: ResMut<LogHistory>, mut events: EventWriter<LogEvent>) {
+    let pending = std::mem::take(&mut *history.pending.lock().unwrap());
+    if pending.is_empty() {
+        return;
+    }
+
+    for entry in pending {
+        events.write(LogEvent {
+            entry: entry.clone(),
+        });
+        history.push(entry);
+    }
+    history.version += 1;
+}
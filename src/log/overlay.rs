@@ -0,0 +1,101 @@
+use crate::{prelude::*, ui::prelude::*};
+
+use super::LogHistory;
+
+/// Amount of entries shown in the overlay
+const OVERLAY_LINES: usize = 20;
+
+/// State of the log overlay. Used as a resource.
+#[derive(Resource)]
+struct LogOverlay {
+    toggle: KeyCode,
+    /// Root ui node and its text node, if the overlay is open
+    nodes: Option<(EntityId, EntityId)>,
+    /// History version shown in the text node
+    version: Option<u64>,
+}
+
+/// Adds the overlay resource and systems
+pub(super) fn build(app: &mut App, toggle: KeyCode) {
+    app.world.resources.insert(LogOverlay {
+        toggle,
+        nodes: None,
+        version: None,
+    });
+
+    app.add_system(toggle_log_overlay_system)
+        .add_system(update_log_overlay_system);
+}
+
+/// System which opens and closes the overlay
+fn toggle_log_overlay_system(
+    mut commands: Commands,
+    input: Res<Input<KeyCode>>,
+    mut overlay: ResMut<LogOverlay>,
+) {
+    if !input.just_pressed(overlay.toggle) {
+        return;
+    }
+
+    if let Some((root, _)) = overlay.nodes.take() {
+        commands.entity(root).despawn_recursive();
+        return;
+    }
+
+    let root = commands
+        .spawn_empty()
+        .insert(Node {
+            position: Position::Absolute,
+            z_index: i32::MAX,
+            width: Val::Vw(60.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            background_color: Color::new(0.0, 0.0, 0.0, 0.75),
+            ..Default::default()
+        })
+        .entity_id();
+
+    let mut text = None;
+    commands.entity(root).with_children(|p| {
+        let id = p
+            .spawn_empty()
+            .insert(Node {
+                color: Some(color::WHITE),
+                background_color: color::TRANSPARENT,
+                ..Default::default()
+            })
+            .insert(Text::new(""))
+            .entity_id();
+        text = Some(id);
+    });
+
+    overlay.nodes = Some((root, text.expect("Overlay text node should be spawned")));
+    overlay.version = None;
+}
+
+/// System which writes the latest history entries into the overlay text
+fn update_log_overlay_system(
+    history: Res<LogHistory>,
+    mut overlay: ResMut<LogOverlay>,
+    mut query: Query<&mut Text>,
+) {
+    let Some((_, text_id)) = overlay.nodes else {
+        return;
+    };
+
+    if overlay.version == Some(history.version()) {
+        return;
+    }
+
+    // text node is spawned with commands, so it might not exist yet
+    let Some(text) = query.get(text_id) else {
+        return;
+    };
+
+    text.content = history
+        .last(OVERLAY_LINES)
+        .map(|entry| format!("{:>5} {}: {}", entry.level, entry.target, entry.message))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    overlay.version = Some(history.version());
+}
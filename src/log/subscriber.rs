@@ -0,0 +1,191 @@
+use std::{
+    collections::VecDeque,
+    fmt::Write,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use tracing::{
+    Event, Level, Metadata, Subscriber,
+    field::{Field, Visit},
+    level_filters::LevelFilter,
+    span,
+};
+
+use super::LogEntry;
+
+/// Level filter with per-module overrides
+pub(super) struct LogFilter {
+    level: Level,
+    /// Sorted by target length, longest first
+    filters: Vec<(String, Level)>,
+}
+
+impl LogFilter {
+    pub fn new(level: Level, mut filters: Vec<(String, Level)>) -> Self {
+        filters.sort_by_key(|(target, _)| std::cmp::Reverse(target.len()));
+        Self { level, filters }
+    }
+
+    /// Returns the level for `target`, based on the longest matching module filter
+    fn level(&self, target: &str) -> Level {
+        self.filters
+            .iter()
+            .find(|(module, _)| {
+                target
+                    .strip_prefix(module.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .map_or(self.level, |(_, level)| *level)
+    }
+
+    /// Returns true if `metadata` passes the filter
+    #[inline]
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        // more verbose levels are greater
+        *metadata.level() <= self.level(metadata.target())
+    }
+
+    /// Returns the most verbose level any module can log at
+    fn max_level(&self) -> Level {
+        self.filters
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.level, Level::max)
+    }
+}
+
+/// Global subscriber installed by the [`LogPlugin`](super::LogPlugin). Spans are accepted but
+/// not tracked, only events are recorded.
+pub(super) struct LogSubscriber {
+    filter: LogFilter,
+    console: bool,
+    capacity: usize,
+    pending: Arc<Mutex<VecDeque<LogEntry>>>,
+    next_span: AtomicU64,
+}
+
+impl LogSubscriber {
+    pub fn new(
+        filter: LogFilter,
+        console: bool,
+        capacity: usize,
+        pending: Arc<Mutex<VecDeque<LogEntry>>>,
+    ) -> Self {
+        Self {
+            filter,
+            console,
+            capacity,
+            pending,
+            next_span: AtomicU64::new(1),
+        }
+    }
+}
+
+impl Subscriber for LogSubscriber {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        self.filter.enabled(metadata)
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        Some(LevelFilter::from_level(self.filter.max_level()))
+    }
+
+    fn new_span(&self, _: &span::Attributes<'_>) -> span::Id {
+        span::Id::from_u64(self.next_span.fetch_add(1, Ordering::Relaxed))
+    }
+
+    fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+    fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let metadata = event.metadata();
+
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+
+        let entry = LogEntry {
+            level: *metadata.level(),
+            target: metadata.target().to_string(),
+            message: visitor.finish(),
+        };
+
+        if self.console {
+            write_console(&entry);
+        }
+
+        // drop the oldest entries if nothing collects them
+        let mut pending = self.pending.lock().unwrap();
+        if pending.len() >= self.capacity.max(1) {
+            pending.pop_front();
+        }
+        pending.push_back(entry);
+    }
+
+    fn enter(&self, _: &span::Id) {}
+
+    fn exit(&self, _: &span::Id) {}
+}
+
+/// Formats the `message` field followed by the other fields as `key=value`
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+    fields: String,
+}
+
+impl MessageVisitor {
+    fn finish(self) -> String {
+        if self.fields.is_empty() {
+            self.message
+        } else if self.message.is_empty() {
+            self.fields
+        } else {
+            format!("{} {}", self.message, self.fields)
+        }
+    }
+}
+
+impl Visit for MessageVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            self.record_debug(field, &value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+            return;
+        }
+
+        if !self.fields.is_empty() {
+            self.fields.push(' ');
+        }
+        let _ = write!(self.fields, "{}={:?}", field.name(), value);
+    }
+}
+
+/// Writes `entry` to stderr
+#[cfg(not(target_arch = "wasm32"))]
+fn write_console(entry: &LogEntry) {
+    eprintln!("{:>5} {}: {}", entry.level, entry.target, entry.message);
+}
+
+/// Writes `entry` to the browser console, stderr is not visible on the web
+#[cfg(target_arch = "wasm32")]
+fn write_console(entry: &LogEntry) {
+    let line = format!("{} {}: {}", entry.level, entry.target, entry.message).into();
+
+    match entry.level {
+        Level::ERROR => web_sys::console::error_1(&line),
+        Level::WARN => web_sys::console::warn_1(&line),
+        Level::INFO => web_sys::console::info_1(&line),
+        _ => web_sys::console::debug_1(&line),
+    }
+}
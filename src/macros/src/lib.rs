@@ -2,7 +2,7 @@ use proc_macro::TokenStream;
 use proc_macro_crate::{FoundCrate, crate_name};
 use proc_macro2::Span;
 use quote::quote;
-use syn::{DeriveInput, Ident, parse_macro_input};
+use syn::{Data, DeriveInput, Ident, Index, Path, Token, parse_macro_input, punctuated::Punctuated};
 
 mod reflect;
 
@@ -81,7 +81,7 @@ pub fn derive_states(item: proc_macro::TokenStream) -> TokenStream {
     TokenStream::from(expanded)
 }
 
-#[proc_macro_derive(Component)]
+#[proc_macro_derive(Component, attributes(require))]
 pub fn derive_component(item: proc_macro::TokenStream) -> TokenStream {
     let path = resolve_path_name();
     let input = parse_macro_input!(item as DeriveInput);
@@ -89,8 +89,67 @@ pub fn derive_component(item: proc_macro::TokenStream) -> TokenStream {
     let generics = &input.generics;
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
+    // Collect types from all `#[require(A, B, ...)]` attributes
+    let mut required = Vec::new();
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("require")) {
+        match attr.parse_args_with(Punctuated::<Path, Token![,]>::parse_terminated) {
+            Ok(paths) => required.extend(paths),
+            Err(err) => return err.to_compile_error().into(),
+        }
+    }
+
+    let insert_required = if required.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            fn insert_required(world: &mut #path::ecs::world::World, entity_id: #path::ecs::entities::EntityId) {
+                #(
+                    world.insert_component(entity_id, <#required as ::core::default::Default>::default(), false);
+                )*
+            }
+        }
+    };
+
+    let expanded = quote! {
+        impl #impl_generics #path::ecs::entities::components::Component for #name #ty_generics #where_clause {
+            #insert_required
+        }
+    };
+
+    TokenStream::from(expanded)
+}
+
+#[proc_macro_derive(Bundle)]
+pub fn derive_bundle(item: proc_macro::TokenStream) -> TokenStream {
+    let path = resolve_path_name();
+    let input = parse_macro_input!(item as DeriveInput);
+    let name = &input.ident;
+    let generics = &input.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let Data::Struct(data) = &input.data else {
+        return syn::Error::new_spanned(name, "Bundle can only be derived for structs")
+            .to_compile_error()
+            .into();
+    };
+
+    // Named fields are accessed by name, tuple fields by index
+    let fields = data.fields.iter().enumerate().map(|(i, field)| match &field.ident {
+        Some(ident) => quote!(#ident),
+        None => {
+            let index = Index::from(i);
+            quote!(#index)
+        }
+    });
+
     let expanded = quote! {
-        impl #impl_generics #path::ecs::entities::components::Component for #name #ty_generics #where_clause {}
+        impl #impl_generics #path::ecs::entities::Bundle for #name #ty_generics #where_clause {
+            fn push_components(self, components: &mut #path::ecs::entities::BundleComponents) {
+                #(
+                    #path::ecs::entities::Bundle::push_components(self.#fields, components);
+                )*
+            }
+        }
     };
 
     TokenStream::from(expanded)
@@ -45,6 +45,13 @@ pub fn derive_reflect_implementation(item: TokenStream) -> TokenStream {
                     }
                 }
 
+                fn field_mut_by_index(&mut self, index: usize) -> Option<&mut dyn #path::reflect::Reflect> {
+                    match index {
+                        #(#field_indices => Some(&mut self.#fields),)*
+                        _ => None,
+                    }
+                }
+
                 fn set_field_by_index(&mut self, index: usize, value: Box<dyn std::any::Any>) -> Result<(), Box<dyn std::any::Any>> {
                     match index {
                         #(#field_indices => value.downcast::<_>().map(|value| self.#fields = *value),)*
@@ -108,6 +115,9 @@ pub fn derive_reflect_implementation(item: TokenStream) -> TokenStream {
                 }
             });
 
+            // the same arms bind mutable references when matching on `&mut self`
+            let variant_matches_mut = variant_matches.clone();
+
             let set_variant_matches = data_enum.variants.iter().map(|v| {
                 let variant_name = &v.ident;
                 match &v.fields {
@@ -147,6 +157,13 @@ pub fn derive_reflect_implementation(item: TokenStream) -> TokenStream {
                     }
                 }
 
+                fn field_mut_by_index(&mut self, index: usize) -> Option<&mut dyn #path::reflect::Reflect> {
+                    match self {
+                        #(#variant_matches_mut,)*
+                        _ => None,
+                    }
+                }
+
                 fn set_field_by_index(&mut self, index: usize, value: Box<dyn std::any::Any>) -> Result<(), Box<dyn std::any::Any>> {
                     match self {
                         #(#set_variant_matches,)*
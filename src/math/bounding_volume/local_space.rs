@@ -1,12 +1,17 @@
 use glam::{Mat4, Vec3};
 use vavo_macros::{Component, Reflect};
 
-use super::{AABB, OBB, Sphere};
+use super::{AABB, OBB, Sphere, WorldBoundingVolume};
+use crate::renderer::culling::Visibility;
 
 #[derive(Default, Reflect, Component, Clone, Debug)]
+#[require(WorldBoundingVolume, Visibility)]
 /// A bounding volume that represents a local space bounding volume. Changes only when the object's
 /// model changes. For world space bounding volumes, see
 /// [`WorldBoundingVolume`](super::WorldBoundingVolume).
+///
+/// Inserting it also inserts default [`WorldBoundingVolume`](super::WorldBoundingVolume) and
+/// [`Visibility`] components if they are missing.
 pub enum LocalBoundingVolume {
     Sphere(Sphere),
     AABB(AABB),
@@ -1,29 +1,189 @@
-use glam::{Mat4, Vec3};
+use glam::{Mat4, Vec2, Vec3, Vec4Swizzles};
 
 use crate::{
     assets::Handle,
     ecs::entities::EntityId,
-    macros::{Component, Reflect},
+    macros::{Bundle, Component, Reflect},
     prelude::World,
     render_assets::{BindGroup, Buffer, IntoRenderAsset, RenderAssets},
     renderer::{Color, Image, palette},
+    window::{CursorWindow, RenderedWindow},
 };
 
-use super::{GlobalTransform, Rect, bounding_volume::Plane};
+use super::{GlobalTransform, Ray, Rect, Transform, bounding_volume::Plane};
 
 /// Main camera component
-/// Requires Projection, Transform, and Camera2D/3D components
+/// Requires Projection, Transform, and Camera3D components, see [`Camera2D`] for 2D scenes
 #[derive(Component)]
 pub struct Camera {
     pub active: bool,
     pub target: Option<Handle<Image>>,
     pub clear_color: Color,
+    /// Part of the render target the camera draws into, the whole target if None. Multiple active
+    /// cameras with viewports render split-screen.
+    pub viewport: Option<Viewport>,
+    /// [`Window`](crate::window::Window) entity the camera renders to, the primary window if None
+    pub window: Option<EntityId>,
+}
+
+/// Area of the render target a camera draws into, in normalized coordinates where (0, 0) is the
+/// top left and (1, 1) the bottom right corner of the target.
+///
+/// Every active 3D camera is drawn into its viewport by every pass, with its own frustum culling
+/// results, and picking casts its ray from the camera under the cursor. Planar water reflections
+/// are rendered for the first active camera and shared by the others. The whole target is cleared
+/// with the clear color of the first active camera.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    /// Top left corner
+    pub position: Vec2,
+    pub size: Vec2,
+}
+
+impl Viewport {
+    /// Viewport covering the whole render target
+    pub const FULL: Self = Self {
+        position: Vec2::ZERO,
+        size: Vec2::ONE,
+    };
+
+    pub fn new(position: Vec2, size: Vec2) -> Self {
+        Self { position, size }
+    }
+
+    /// Returns the cell at `index` of a grid with `columns` and `rows`, counted row by row. E.g.
+    /// `Viewport::grid(1, 2, 1)` is the right half of a two player split-screen.
+    pub fn grid(index: u32, columns: u32, rows: u32) -> Self {
+        let (columns, rows) = (columns.max(1), rows.max(1));
+        let size = Vec2::new(1.0 / columns as f32, 1.0 / rows as f32);
+        let cell = Vec2::new((index % columns) as f32, (index / columns % rows) as f32);
+
+        Self {
+            position: cell * size,
+            size,
+        }
+    }
+
+    /// Returns the position and size in pixels, for a render target of `target_size` pixels
+    pub fn to_pixels(&self, target_size: Vec2) -> (Vec2, Vec2) {
+        let min = (self.position * target_size)
+            .round()
+            .clamp(Vec2::ZERO, target_size);
+        let max = ((self.position + self.size) * target_size)
+            .round()
+            .clamp(min, target_size);
+
+        (min, max - min)
+    }
+}
+
+/// Exposure of a camera, which scales the intensity of all lights before they are shaded. Cameras
+/// without it use an exposure of 1.0, which suits unitless light intensities.
+///
+/// Lights in physical units (lux, lumens, candela) need an exposure matching the scene, the same
+/// way a real camera does. The value is stored as the exposure value at ISO 100, the shader
+/// multiplies light intensities by `1 / (1.2 * 2^ev100)`.
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq)]
+pub struct Exposure {
+    pub ev100: f32,
+}
+
+impl Exposure {
+    /// Direct sunlight
+    pub const SUNLIGHT: Self = Self { ev100: 15.0 };
+    /// Overcast sky
+    pub const OVERCAST: Self = Self { ev100: 12.0 };
+    /// Brightly lit interior
+    pub const INDOOR: Self = Self { ev100: 7.0 };
+    /// Dim interior or night street lighting
+    pub const NIGHT: Self = Self { ev100: 3.0 };
+
+    pub fn new(ev100: f32) -> Self {
+        Self { ev100 }
+    }
+
+    /// Create exposure from physical camera settings, with `aperture` in f-stops, `shutter_speed`
+    /// in seconds and `sensitivity` in ISO
+    pub fn from_physical_camera(aperture: f32, shutter_speed: f32, sensitivity: f32) -> Self {
+        let ev100 = (aperture * aperture / shutter_speed * 100.0 / sensitivity).log2();
+        Self { ev100 }
+    }
+
+    /// Returns the multiplier applied to light intensities
+    pub fn exposure(&self) -> f32 {
+        1.0 / (1.2 * 2.0_f32.powf(self.ev100))
+    }
 }
 
 /// Defines a 3D camera, required for 3D rendering
 #[derive(Component, Reflect)]
 pub struct Camera3D {}
 
+/// Orthographic camera for 2D scenes, spawned as a bundle. It looks down the `-Z` axis from
+/// `Z` = [`Camera2D::DEPTH`], one world unit is one pixel and the origin is in the center of the
+/// viewport.
+///
+/// Content is ordered by its `Z` translation, meshes with a higher `Z` are drawn on top of lower
+/// ones, in the range `-DEPTH..DEPTH`. Transparent meshes should have a [`ZIndex`] to be blended
+/// in order, see [`ZIndex`] for details.
+///
+/// It renders with the standard 3D passes, so it's also a [`Camera3D`].
+///
+/// ```ignore
+/// commands.spawn(Camera2D::new());
+/// ```
+///
+/// [`ZIndex`]: crate::renderer::ZIndex
+#[derive(Bundle)]
+pub struct Camera2D {
+    pub camera: Camera,
+    pub camera_3d: Camera3D,
+    pub projection: Projection,
+    pub transform: Transform,
+}
+
+impl Camera2D {
+    /// Distance of the camera from the `XY` plane, content is visible up to this distance in
+    /// front of and behind the plane
+    pub const DEPTH: f32 = 1000.0;
+
+    /// Create a new 2D camera
+    pub fn new() -> Self {
+        Self {
+            camera: Camera::default(),
+            camera_3d: Camera3D::default(),
+            projection: Projection::Orthographic(OrthographicProjection {
+                near: 0.0,
+                far: Self::DEPTH * 2.0,
+                ..Default::default()
+            }),
+            transform: Transform::new().with_translation(Vec3::new(0.0, 0.0, Self::DEPTH)),
+        }
+    }
+
+    /// Returns self with the camera moved to `position` in the `XY` plane
+    #[must_use]
+    pub fn with_position(mut self, position: Vec2) -> Self {
+        self.transform.translation = position.extend(Self::DEPTH);
+        self
+    }
+
+    /// Returns self with the `scale` of the orthographic projection, values below 1 zoom in
+    #[must_use]
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        if let Projection::Orthographic(projection) = &mut self.projection {
+            projection.scale = scale;
+        }
+        self
+    }
+}
+
+impl Default for Camera2D {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Projection type component, required for camera
 #[derive(Component, Reflect)]
 pub enum Projection {
@@ -44,6 +204,7 @@ pub struct PerspectiveProjection {
 #[derive(Component, Reflect)]
 pub struct OrthographicProjection {
     pub area: Rect,
+    /// Multiplier of the visible area, values below 1 zoom in
     pub scale: f32,
     pub near: f32,
     pub far: f32,
@@ -55,6 +216,8 @@ impl Default for Camera {
             active: true,
             target: None,
             clear_color: palette::BLACK,
+            viewport: None,
+            window: None,
         }
     }
 }
@@ -112,14 +275,10 @@ impl Projection {
                 view_projection.to_cols_array_2d()
             }
             Projection::Orthographic(o) => {
-                let projection = glam::Mat4::orthographic_rh(
-                    o.area.min.x,
-                    o.area.max.x,
-                    o.area.min.y,
-                    o.area.max.y,
-                    o.near,
-                    o.far,
-                );
+                let min = o.area.min * o.scale;
+                let max = o.area.max * o.scale;
+                let projection =
+                    glam::Mat4::orthographic_rh(min.x, max.x, min.y, max.y, o.near, o.far);
                 let view_projection = projection * view;
 
                 view_projection.to_cols_array_2d()
@@ -127,6 +286,78 @@ impl Projection {
         }
     }
 
+    /// Returns a world space ray starting on the near plane and going through
+    /// `viewport_position`, which is in physical pixels with the origin in the top left corner.
+    /// `matrix` is the camera's global transform.
+    ///
+    /// Returns `None` if the viewport is empty or the projection can't be inverted.
+    pub fn viewport_to_ray(
+        &self,
+        viewport_position: Vec2,
+        viewport_size: Vec2,
+        matrix: &Mat4,
+    ) -> Option<Ray> {
+        if viewport_size.x <= 0.0 || viewport_size.y <= 0.0 {
+            return None;
+        }
+
+        let ndc = Vec2::new(
+            viewport_position.x / viewport_size.x * 2.0 - 1.0,
+            1.0 - viewport_position.y / viewport_size.y * 2.0,
+        );
+
+        let view_projection = Mat4::from_cols_array_2d(&self.get_view_projection_matrix(matrix));
+        let inverse = view_projection.inverse();
+
+        let near = inverse * ndc.extend(0.0).extend(1.0);
+        let far = inverse * ndc.extend(1.0).extend(1.0);
+        if near.w == 0.0 || far.w == 0.0 {
+            return None;
+        }
+
+        let near = near.xyz() / near.w;
+        let far = far.xyz() / far.w;
+        let direction = far - near;
+        if !direction.is_finite() || direction.length_squared() == 0.0 {
+            return None;
+        }
+
+        Some(Ray::new(near, direction))
+    }
+
+    /// Returns the position of `world_position` in the viewport, in physical pixels with the origin
+    /// in the top left corner. `matrix` is the camera's global transform.
+    ///
+    /// Positions outside of the viewport's bounds are still returned. Returns `None` if the
+    /// position is behind the camera, outside of the near and far planes, or the viewport is
+    /// empty.
+    pub fn world_to_viewport(
+        &self,
+        world_position: Vec3,
+        viewport_size: Vec2,
+        matrix: &Mat4,
+    ) -> Option<Vec2> {
+        if viewport_size.x <= 0.0 || viewport_size.y <= 0.0 {
+            return None;
+        }
+
+        let view_projection = Mat4::from_cols_array_2d(&self.get_view_projection_matrix(matrix));
+        let clip = view_projection * world_position.extend(1.0);
+        if clip.w <= 0.0 {
+            return None;
+        }
+
+        let ndc = clip.xyz() / clip.w;
+        if !(0.0..=1.0).contains(&ndc.z) {
+            return None;
+        }
+
+        Some(Vec2::new(
+            (ndc.x + 1.0) / 2.0 * viewport_size.x,
+            (1.0 - ndc.y) / 2.0 * viewport_size.y,
+        ))
+    }
+
     /// Resize the projection `aspect ratio` / `area` based on new width and height
     pub fn resize(&mut self, width: f32, height: f32) {
         match self {
@@ -142,6 +373,9 @@ impl Projection {
     /// Get the frustum planes for the camera in world space.
     /// The planes are in the order: left, right, bottom, top, near, far
     ///
+    /// Works for both projections, the near plane is extracted for the `0..1` depth range of
+    /// wgpu clip space.
+    ///
     /// Use the [transform matrix](GlobalTransform) of a [`Camera3D`] to get the frustum planes.
     pub fn get_frustum_planes(&self, global_transform: &Mat4) -> [Plane; 6] {
         let view_proj_matrix = self.get_view_projection_matrix(global_transform);
@@ -179,13 +413,13 @@ impl Projection {
         );
         planes[3].d = view_proj_matrix[3][3] - view_proj_matrix[3][1];
 
-        // Near plane (Z-axis)
+        // Near plane (Z-axis), clip space depth starts at 0 instead of -w
         planes[4].normal = Vec3::new(
-            view_proj_matrix[0][3] + view_proj_matrix[0][2], // x
-            view_proj_matrix[1][3] + view_proj_matrix[1][2], // y
-            view_proj_matrix[2][3] + view_proj_matrix[2][2], // z
+            view_proj_matrix[0][2], // x
+            view_proj_matrix[1][2], // y
+            view_proj_matrix[2][2], // z
         );
-        planes[4].d = view_proj_matrix[3][3] + view_proj_matrix[3][2];
+        planes[4].d = view_proj_matrix[3][2];
 
         // Far plane (Z-axis)
         planes[5].normal = Vec3::new(
@@ -207,9 +441,150 @@ impl Projection {
 }
 
 impl Camera {
+    /// Returns self with a new `viewport`
+    #[inline]
+    #[must_use]
+    pub fn with_viewport(mut self, viewport: Viewport) -> Self {
+        self.viewport = Some(viewport);
+        self
+    }
+
+    /// Returns self rendering to the `window` entity
+    #[inline]
+    #[must_use]
+    pub fn with_window(mut self, window: EntityId) -> Self {
+        self.window = Some(window);
+        self
+    }
+
+    /// Returns true if the camera is active and renders to the `rendered` window
+    #[inline]
+    pub fn renders_to(&self, rendered: &RenderedWindow) -> bool {
+        self.active
+            && match self.window {
+                Some(window) => window == rendered.entity,
+                None => rendered.primary,
+            }
+    }
+
+    /// Returns the position and size in pixels of the area the camera draws into, for a render
+    /// target of `target_size` pixels
+    pub fn viewport_pixels(&self, target_size: Vec2) -> (Vec2, Vec2) {
+        self.viewport
+            .unwrap_or(Viewport::FULL)
+            .to_pixels(target_size)
+    }
+
+    /// Sets the viewport of `render_pass` to the camera's viewport, or the whole target without
+    /// one, for a render target of `target_size` pixels. Returns false if the viewport is empty,
+    /// in which case nothing should be drawn.
+    pub fn set_render_pass_viewport(
+        &self,
+        render_pass: &mut wgpu::RenderPass,
+        target_size: Vec2,
+    ) -> bool {
+        let (position, size) = self.viewport_pixels(target_size);
+        if size.x < 1.0 || size.y < 1.0 {
+            return false;
+        }
+
+        render_pass.set_viewport(position.x, position.y, size.x, size.y, 0.0, 1.0);
+        true
+    }
+
+    /// Returns the cursor position relative to the camera's viewport and the viewport size in
+    /// pixels, if the camera renders to the window under the cursor and its viewport contains it
+    pub fn cursor_in_viewport(&self, cursor: &CursorWindow) -> Option<(Vec2, Vec2)> {
+        if !self.renders_to(&cursor.window) {
+            return None;
+        }
+
+        let (position, size) = self.viewport_pixels(cursor.size);
+        let relative = cursor.position - position;
+        (relative.cmpge(Vec2::ZERO).all() && relative.cmplt(size).all()).then_some((relative, size))
+    }
+
+    /// Returns the cameras which render to `rendered`, which graph nodes draw one after another
+    /// into their viewports with [`Camera::set_render_pass_camera`]
+    pub fn rendering_to<'a>(
+        cameras: impl IntoIterator<Item = (EntityId, &'a Camera)>,
+        rendered: &RenderedWindow,
+    ) -> Vec<(EntityId, &'a Camera)> {
+        cameras
+            .into_iter()
+            .filter(|(_, camera)| camera.renders_to(rendered))
+            .collect()
+    }
+
+    /// Prepares `render_pass` to draw what the camera sees, by setting its viewport and its bind
+    /// group at `index`. Returns false if the viewport is empty, in which case nothing should be
+    /// drawn.
+    pub fn set_render_pass_camera(
+        &self,
+        camera_id: EntityId,
+        render_pass: &mut wgpu::RenderPass,
+        index: u32,
+        target_size: Vec2,
+        bind_groups: &mut RenderAssets<BindGroup>,
+        world: &mut World,
+    ) -> bool {
+        if !self.set_render_pass_viewport(render_pass, target_size) {
+            return false;
+        }
+
+        let camera_bind_group = bind_groups.get_by_entity(camera_id, self, world);
+        render_pass.set_bind_group(index, &*camera_bind_group, &[]);
+        true
+    }
+
+    /// Returns the viewport position of `world_position` as seen by a camera with `projection`
+    /// and `global_transform`. See [`Projection::world_to_viewport`].
+    #[inline]
+    pub fn world_to_viewport(
+        projection: &Projection,
+        global_transform: &GlobalTransform,
+        viewport_size: Vec2,
+        world_position: Vec3,
+    ) -> Option<Vec2> {
+        projection.world_to_viewport(world_position, viewport_size, &global_transform.matrix)
+    }
+
+    /// Returns a world space ray through `viewport_position`, e.g. the cursor, for a camera with
+    /// `projection` and `global_transform`. See [`Projection::viewport_to_ray`].
+    #[inline]
+    pub fn viewport_to_world(
+        projection: &Projection,
+        global_transform: &GlobalTransform,
+        viewport_size: Vec2,
+        viewport_position: Vec2,
+    ) -> Option<Ray> {
+        projection.viewport_to_ray(viewport_position, viewport_size, &global_transform.matrix)
+    }
+
+    /// Returns the world position on `plane` under `viewport_position`, e.g. the ground under the
+    /// cursor. Returns `None` if the ray through the position misses the plane.
+    pub fn viewport_to_world_plane(
+        projection: &Projection,
+        global_transform: &GlobalTransform,
+        viewport_size: Vec2,
+        viewport_position: Vec2,
+        plane: &Plane,
+    ) -> Option<Vec3> {
+        let ray = Self::viewport_to_world(
+            projection,
+            global_transform,
+            viewport_size,
+            viewport_position,
+        )?;
+        let distance = ray.intersect_plane(plane)?;
+        Some(ray.at(distance))
+    }
+
+    /// Returns the camera uniform data, the view projection matrix, position and `exposure`
     pub fn get_buffer_data(
         projection: &Projection,
         global_transform: &GlobalTransform,
+        exposure: Option<&Exposure>,
     ) -> Vec<f32> {
         let mut data = projection
             .get_view_projection_matrix(&global_transform.matrix)
@@ -221,7 +596,7 @@ impl Camera {
             translation.x,
             translation.y,
             translation.z,
-            0.0, // padding
+            exposure.map_or(1.0, Exposure::exposure),
         ]);
         data
     }
@@ -240,7 +615,8 @@ impl IntoRenderAsset<Buffer> for Camera {
             .get_component(id)
             .expect("Camera should have a GlobalTransform component");
 
-        let data = Camera::get_buffer_data(projection, global_transform);
+        let exposure = world.entities.get_component::<Exposure>(id);
+        let data = Camera::get_buffer_data(projection, global_transform, exposure);
 
         Buffer::new("camera").create_uniform_buffer(
             &data,
@@ -0,0 +1,185 @@
+use glam::{EulerRot, Mat4, Quat, Vec3};
+
+use crate::{
+    ecs::entities::EntityId,
+    macros::{Component, Event},
+};
+
+/// Trauma based camera shake, requires a [`Camera`](super::Camera). The shake offsets the view
+/// used for rendering, the camera's transform is left untouched.
+///
+/// Gameplay adds trauma, e.g. on hits or explosions, with [`CameraShake::add_trauma`] or a
+/// [`CameraShakeEvent`]. Trauma decays linearly over time, and the shake strength is the trauma
+/// raised to the [`exponent`](Self::exponent), so small amounts of trauma barely move the camera.
+#[derive(Component, Debug, Clone)]
+pub struct CameraShake {
+    /// Current trauma, between 0.0 and 1.0
+    pub trauma: f32,
+    /// Amount of trauma removed per second
+    pub decay: f32,
+    /// Speed of the shake in noise samples per second
+    pub frequency: f32,
+    /// Strength applied to the shake amount, usually 2.0 or 3.0
+    pub exponent: f32,
+    /// Maximum translation along the local camera axes at full trauma
+    pub max_translation: Vec3,
+    /// Maximum pitch, yaw and roll in radians at full trauma
+    pub max_rotation: Vec3,
+    seed: u32,
+    time: f32,
+}
+
+impl Default for CameraShake {
+    fn default() -> Self {
+        Self {
+            trauma: 0.0,
+            decay: 1.0,
+            frequency: 15.0,
+            exponent: 2.0,
+            max_translation: Vec3::new(0.1, 0.1, 0.0),
+            max_rotation: Vec3::new(0.05, 0.05, 0.1),
+            seed: 0,
+            time: 0.0,
+        }
+    }
+}
+
+impl CameraShake {
+    /// Returns self with new `decay`
+    #[inline]
+    #[must_use]
+    pub fn with_decay(mut self, decay: f32) -> Self {
+        self.decay = decay;
+        self
+    }
+
+    /// Returns self with new `frequency`
+    #[inline]
+    #[must_use]
+    pub fn with_frequency(mut self, frequency: f32) -> Self {
+        self.frequency = frequency;
+        self
+    }
+
+    /// Returns self with new `exponent`
+    #[inline]
+    #[must_use]
+    pub fn with_exponent(mut self, exponent: f32) -> Self {
+        self.exponent = exponent;
+        self
+    }
+
+    /// Returns self with new `max_translation`
+    #[inline]
+    #[must_use]
+    pub fn with_max_translation(mut self, max_translation: Vec3) -> Self {
+        self.max_translation = max_translation;
+        self
+    }
+
+    /// Returns self with new `max_rotation` in radians
+    #[inline]
+    #[must_use]
+    pub fn with_max_rotation(mut self, max_rotation: Vec3) -> Self {
+        self.max_rotation = max_rotation;
+        self
+    }
+
+    /// Returns self with a new noise `seed`, cameras with different seeds shake differently
+    #[inline]
+    #[must_use]
+    pub fn with_seed(mut self, seed: u32) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Add `amount` of trauma, the result is clamped to 1.0
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).clamp(0.0, 1.0);
+    }
+
+    /// Returns the current shake strength, between 0.0 and 1.0
+    pub fn shake(&self) -> f32 {
+        self.trauma.clamp(0.0, 1.0).powf(self.exponent)
+    }
+
+    /// Whether the shake currently offsets the view
+    pub fn is_shaking(&self) -> bool {
+        self.trauma > 0.0
+    }
+
+    /// Advance the noise by `delta` seconds and decay the trauma
+    pub fn update(&mut self, delta: f32) {
+        self.time += delta;
+        self.trauma = (self.trauma - self.decay * delta).max(0.0);
+    }
+
+    /// Returns the local offset which is applied to the camera's global transform
+    pub fn offset(&self) -> Mat4 {
+        let shake = self.shake();
+        if shake <= 0.0 {
+            return Mat4::IDENTITY;
+        }
+
+        let t = self.time * self.frequency;
+        let sample = |axis: u32| noise(t, self.seed.wrapping_mul(6).wrapping_add(axis));
+
+        let translation = self.max_translation * Vec3::new(sample(0), sample(1), sample(2)) * shake;
+        let rotation = self.max_rotation * Vec3::new(sample(3), sample(4), sample(5)) * shake;
+
+        Mat4::from_rotation_translation(
+            Quat::from_euler(EulerRot::YXZ, rotation.y, rotation.x, rotation.z),
+            translation,
+        )
+    }
+}
+
+/// Event which adds trauma to camera shakes
+#[derive(Event, Debug, Clone, Copy)]
+pub struct CameraShakeEvent {
+    /// Camera entity to shake, or every [`CameraShake`] if None
+    pub camera: Option<EntityId>,
+    pub trauma: f32,
+}
+
+impl CameraShakeEvent {
+    /// Shake every camera with a [`CameraShake`]
+    pub fn all(trauma: f32) -> Self {
+        Self {
+            camera: None,
+            trauma,
+        }
+    }
+
+    /// Shake a single `camera`
+    pub fn camera(camera: EntityId, trauma: f32) -> Self {
+        Self {
+            camera: Some(camera),
+            trauma,
+        }
+    }
+}
+
+/// Returns a pseudo random gradient between -1.0 and 1.0 for the integer point `x`
+fn gradient(x: i32, seed: u32) -> f32 {
+    let mut h = (x as u32).wrapping_mul(0x27d4_eb2d) ^ seed.wrapping_mul(0x1656_67b1);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x85eb_ca6b);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0xc2b2_ae35);
+    h ^= h >> 16;
+
+    h as f32 / u32::MAX as f32 * 2.0 - 1.0
+}
+
+/// Smooth 1D gradient noise, roughly between -1.0 and 1.0
+fn noise(t: f32, seed: u32) -> f32 {
+    let i = t.floor();
+    let f = t - i;
+
+    let a = gradient(i as i32, seed) * f;
+    let b = gradient(i as i32 + 1, seed) * (f - 1.0);
+    let s = f * f * (3.0 - 2.0 * f);
+
+    (a + (b - a) * s) * 2.0
+}
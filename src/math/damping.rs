@@ -0,0 +1,97 @@
+use std::ops::{Add, Mul, Sub};
+
+use glam::{Vec2, Vec3, Vec4};
+
+/// Value which can be smoothed with [`exp_decay`] and [`smooth_damp`]
+pub trait Damp: Copy + Add<Output = Self> + Sub<Output = Self> + Mul<f32, Output = Self> {
+    /// Returns the dot product, used to detect overshooting the target
+    fn dot(self, other: Self) -> f32;
+}
+
+impl Damp for f32 {
+    #[inline]
+    fn dot(self, other: Self) -> f32 {
+        self * other
+    }
+}
+
+macro_rules! impl_damp {
+    ($($type:ty),*) => {$(
+        impl Damp for $type {
+            #[inline]
+            fn dot(self, other: Self) -> f32 {
+                <$type>::dot(self, other)
+            }
+        }
+    )*};
+}
+
+impl_damp!(Vec2, Vec3, Vec4);
+
+/// Returns the interpolation factor of [`exp_decay`], the fraction of the remaining distance
+/// covered in `delta` seconds at `rate`
+#[inline]
+pub fn exp_decay_factor(rate: f32, delta: f32) -> f32 {
+    1.0 - (-rate * delta).exp()
+}
+
+/// Moves `current` towards `target`, covering the same fraction of the remaining distance every
+/// second regardless of the frame rate. Replaces `current.lerp(target, rate * delta)`, which
+/// depends on the frame rate. Higher `rate` is faster, after `1 / rate` seconds ~63% of the
+/// distance is covered.
+///
+/// ```ignore
+/// transform.translation = exp_decay(transform.translation, target, 10.0, time.delta());
+/// ```
+#[inline]
+pub fn exp_decay<T: Damp>(current: T, target: T, rate: f32, delta: f32) -> T {
+    current + (target - current) * exp_decay_factor(rate, delta)
+}
+
+/// Moves `current` towards `target` like a critically damped spring, starting and stopping
+/// smoothly without overshooting. `velocity` keeps the speed between calls and should be stored
+/// with the value, starting at zero. `smooth_time` is roughly the time in seconds to reach the
+/// target.
+///
+/// ```ignore
+/// fn follow(time: Res<Time>, mut cameras: Query<(&mut Transform, &mut Follow)>) {
+///     for (transform, follow) in cameras.iter_mut() {
+///         transform.translation = smooth_damp(
+///             transform.translation,
+///             follow.target,
+///             &mut follow.velocity,
+///             0.3,
+///             time.delta(),
+///         );
+///     }
+/// }
+/// ```
+pub fn smooth_damp<T: Damp>(
+    current: T,
+    target: T,
+    velocity: &mut T,
+    smooth_time: f32,
+    delta: f32,
+) -> T {
+    if delta <= 0.0 {
+        return current;
+    }
+
+    // approximation of the exact spring solution from Game Programming Gems 4, chapter 1.10
+    let smooth_time = smooth_time.max(1e-4);
+    let omega = 2.0 / smooth_time;
+    let x = omega * delta;
+    let decay = 1.0 / (1.0 + x + 0.48 * x * x + 0.235 * x * x * x);
+
+    let change = current - target;
+    let temp = (*velocity + change * omega) * delta;
+    *velocity = (*velocity - temp * omega) * decay;
+    let result = target + (change + temp) * decay;
+
+    // the approximation can overshoot when the delta is large
+    if (target - current).dot(result - target) > 0.0 {
+        *velocity = *velocity * 0.0;
+        return target;
+    }
+    result
+}
@@ -1,8 +1,11 @@
-use std::ops::{BitOr, BitOrAssign};
+use std::{
+    f32::consts::PI,
+    ops::{BitOr, BitOrAssign},
+};
 
 use glam::{Mat4, Quat, Vec3};
 
-use crate::{palette, prelude::Color, macros::{Resource, Component}};
+use crate::{palette, prelude::{Color, Handle, Image}, macros::{Resource, Component}};
 
 use super::CubeFace;
 
@@ -13,6 +16,7 @@ enum LightFlags {
     Spot = 3,
     Visible = 4,
     CastShadow = 5,
+    Cookie = 6,
 }
 
 impl BitOr for LightFlags {
@@ -42,7 +46,9 @@ pub struct Light {
 
     pub flags: u32,
     shadow_map_index: u32,
-    padding_u32: [u32; 2],
+    /// Layer of the light's cookie in the cookie texture array, set if the light has a cookie
+    cookie_index: u32,
+    padding_u32: u32,
 
     /// Optional position for point light or spot light.
     /// Defined by `with_point` or `with_spot` methods
@@ -53,9 +59,51 @@ pub struct Light {
     /// Defined by `with_spot` or `with_directional` methods
     direction: [f32; 3],
     padding_dir: f32,
+
+    /// Constant, linear and quadratic attenuation factors, and 1.0 if the light fades out at its
+    /// range. See [`Attenuation`]
+    pub attenuation: [f32; 4],
 } 
 
-/// Ambient light source affecting all objects in the scene equally, set as a resource
+/// Falloff of point and spot light intensity with the distance from the light
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Attenuation {
+    /// `1 / (constant + linear * d + quadratic * d^2)`, not limited by the light's range. Suited
+    /// for unitless intensities around 1.0
+    Polynomial {
+        constant: f32,
+        linear: f32,
+        quadratic: f32,
+    },
+    /// Physically based `1 / d^2` falloff, smoothly faded out to zero at the light's range.
+    /// Suited for intensities in candela, see [`PointLight::with_lumens`]
+    InverseSquare,
+}
+
+impl Default for Attenuation {
+    fn default() -> Self {
+        Self::Polynomial {
+            constant: 1.0,
+            linear: 0.09,
+            quadratic: 0.032,
+        }
+    }
+}
+
+impl Attenuation {
+    /// Returns the factors in the layout of [`Light::attenuation`]
+    pub fn as_factors(&self) -> [f32; 4] {
+        match *self {
+            Self::Polynomial { constant, linear, quadratic } => [constant, linear, quadratic, 0.0],
+            Self::InverseSquare => [0.0, 0.0, 1.0, 1.0],
+        }
+    }
+}
+
+/// Ambient light source affecting all objects in the scene equally, set as a resource.
+///
+/// Like other lights, the intensity is scaled by the camera's [`Exposure`](super::Exposure), so
+/// scenes using physical units need an ambient intensity in lux.
 #[derive(Resource)]
 pub struct AmbientLight {
     pub color: Color,
@@ -68,8 +116,12 @@ pub struct AmbientLight {
 #[derive(Component)]
 pub struct DirectionalLight {
     pub color: Color,
+    /// Unitless multiplier, or illuminance in lux when used with an [`Exposure`](super::Exposure)
     pub intensity: f32,
     pub shadow: bool,
+    /// Optional texture modulating the light color, projected over the area covered by the
+    /// light's shadow map
+    pub cookie: Option<Handle<Image>>,
 }
 
 /// Light source emitting light in all directions from a point in space (light bulb)
@@ -77,9 +129,13 @@ pub struct DirectionalLight {
 #[derive(Component)]
 pub struct PointLight {
     pub color: Color,
+    /// Unitless multiplier, or luminous intensity in candela when used with an
+    /// [`Exposure`](super::Exposure)
     pub intensity: f32,
     pub shadow: bool,
+    /// Distance where the light stops affecting objects, also the far plane of its shadow map
     pub range: f32,
+    pub attenuation: Attenuation,
 }
 
 /// Light source emitting cone light in a specific direction with a perspective projection
@@ -88,11 +144,19 @@ pub struct PointLight {
 #[derive(Component)]
 pub struct SpotLight {
     pub color: Color,
+    /// Unitless multiplier, or luminous intensity in candela when used with an
+    /// [`Exposure`](super::Exposure)
     pub intensity: f32,
     pub shadow: bool,
+    /// Distance where the light stops affecting objects, also the far plane of its shadow map
     pub range: f32,
+    /// Angle from the spot direction in degrees, where the intensity starts to fall off
     pub inner_angle: f32,
+    /// Angle from the spot direction in degrees, where the intensity reaches zero
     pub outer_angle: f32,
+    pub attenuation: Attenuation,
+    /// Optional texture modulating the light color, projected through the light's cone (gobo)
+    pub cookie: Option<Handle<Image>>,
 }
 
 impl Light {
@@ -104,6 +168,16 @@ impl Light {
         self.shadow_map_index
     }
 
+    /// Set the cookie texture array layer, and mark the light as having a cookie
+    pub fn set_cookie_index(&mut self, index: u32) {
+        self.cookie_index = index;
+        self.flags |= LightFlags::Cookie;
+    }
+
+    pub fn cookie_index(&self) -> u32 {
+        self.cookie_index
+    }
+
     pub fn with_point(mut self, position: Vec3) -> Self {
         self.position = position.into();
         self
@@ -124,6 +198,16 @@ impl Light {
         self.flags & (1 << LightFlags::Visible as u32) != 0
     }
 
+    /// Set whether the light affects the scene, culled lights are skipped by the shaders and the
+    /// shadow pass
+    pub fn set_visible(&mut self, visible: bool) {
+        if visible {
+            self.flags |= LightFlags::Visible;
+        } else {
+            self.flags &= !(1 << LightFlags::Visible as u32);
+        }
+    }
+
     pub fn is_shadowed(&self) -> bool {
         self.flags & (1 << LightFlags::CastShadow as u32) != 0
     }
@@ -143,6 +227,10 @@ impl Light {
     pub fn is_point(&self) -> bool {
         self.flags & (1 << LightFlags::Point as u32) != 0
     }
+
+    pub fn has_cookie(&self) -> bool {
+        self.flags & (1 << LightFlags::Cookie as u32) != 0
+    }
 }
 
 impl Default for Light {
@@ -158,13 +246,16 @@ impl Default for Light {
 
             flags: LightFlags::Visible | LightFlags::Ambient,  
             shadow_map_index: 0,
-            padding_u32: [0; 2],
+            cookie_index: 0,
+            padding_u32: 0,
 
             position: [0.0; 3],
             padding_pos: 0.0,
 
             direction: [0.0; 3],
             padding_dir: 0.0,
+
+            attenuation: Attenuation::default().as_factors(),
         }
     }
 }
@@ -206,6 +297,19 @@ impl DirectionalLight {
         }
     }
 
+    /// Set the intensity to an illuminance in lux, e.g. 100 000 for direct sunlight or 1 000 for
+    /// an overcast day
+    pub fn with_lux(mut self, lux: f32) -> Self {
+        self.intensity = lux;
+        self
+    }
+
+    /// Set the cookie texture
+    pub fn with_cookie(mut self, cookie: Handle<Image>) -> Self {
+        self.cookie = Some(cookie);
+        self
+    }
+
     pub fn view_matrix(&self, camera_position: Vec3, rotation: Quat) -> (Mat4, Vec3) {
         // Local space light direction (-Y) and up vector (-Z)
         let local_direction = Vec3::new(0.0, -1.0, 0.0);
@@ -247,6 +351,7 @@ impl Default for DirectionalLight {
             color: palette::WHITE,
             intensity: 1.0,
             shadow: true,
+            cookie: None,
         }
     }
 }
@@ -264,10 +369,28 @@ impl PointLight {
             flags,
             range: self.range,
             view_proj: view_projection_matrix.to_cols_array_2d(),
+            attenuation: self.attenuation.as_factors(),
             ..Default::default()
         }
     }
 
+    /// Set the intensity from a luminous power in lumens emitted in all directions, and switch
+    /// to the physically based [`Attenuation::InverseSquare`]. A 60 W incandescent bulb emits
+    /// around 800 lumens.
+    pub fn with_lumens(mut self, lumens: f32) -> Self {
+        self.intensity = lumens / (4.0 * PI);
+        self.attenuation = Attenuation::InverseSquare;
+        self
+    }
+
+    /// Set the intensity to a luminous intensity in candela, and switch to the physically based
+    /// [`Attenuation::InverseSquare`]
+    pub fn with_candela(mut self, candela: f32) -> Self {
+        self.intensity = candela;
+        self.attenuation = Attenuation::InverseSquare;
+        self
+    }
+
     pub fn view_matrix_for_face(&self, position: Vec3, face: CubeFace) -> Mat4 {
         // Look direction for each cube map face.
         let (eye, direction, up) = match face {
@@ -304,6 +427,7 @@ impl Default for PointLight {
             intensity: 1.0,
             shadow: true,
             range: 10.0,
+            attenuation: Attenuation::default(),
         }
     }
 }
@@ -323,10 +447,37 @@ impl SpotLight {
             inner_angle: self.inner_angle,
             outer_angle: self.outer_angle,
             view_proj: view_projection_matrix.to_cols_array_2d(),
+            attenuation: self.attenuation.as_factors(),
             ..Default::default()
         }
     }
 
+    /// Set the intensity from a luminous power in lumens emitted into the cone of the
+    /// [`outer_angle`](Self::outer_angle), and switch to the physically based
+    /// [`Attenuation::InverseSquare`]. The angle has to be set before calling this, since
+    /// narrower cones concentrate the same power into a brighter beam.
+    pub fn with_lumens(mut self, lumens: f32) -> Self {
+        // solid angle of the cone in steradians
+        let solid_angle = 2.0 * PI * (1.0 - self.outer_angle.to_radians().cos());
+        self.intensity = lumens / solid_angle.max(f32::EPSILON);
+        self.attenuation = Attenuation::InverseSquare;
+        self
+    }
+
+    /// Set the intensity to a luminous intensity in candela, and switch to the physically based
+    /// [`Attenuation::InverseSquare`]
+    pub fn with_candela(mut self, candela: f32) -> Self {
+        self.intensity = candela;
+        self.attenuation = Attenuation::InverseSquare;
+        self
+    }
+
+    /// Set the cookie texture
+    pub fn with_cookie(mut self, cookie: Handle<Image>) -> Self {
+        self.cookie = Some(cookie);
+        self
+    }
+
     pub fn view_matrix(&self, position: Vec3, rotation: Quat) -> (Mat4, Vec3) {
         // Local space light direction (-Y) and up vector (-Z)
         let local_direction = Vec3::new(0.0, -1.0, 0.0);
@@ -373,6 +524,8 @@ impl Default for SpotLight {
             range: 50.0,
             inner_angle: 37.5,
             outer_angle: 45.0,
+            attenuation: Attenuation::default(),
+            cookie: None,
         }
     }
 }
@@ -1,7 +1,10 @@
 mod transform;
 mod camera;
+mod camera_shake;
 mod light;
 mod face;
+mod ray;
+mod damping;
 pub mod shapes;
 pub mod bounding_volume;
 
@@ -9,9 +12,12 @@ use glam::Vec2;
 pub use transform::*;
 pub use face::*;
 pub use camera::*;
+pub use camera_shake::*;
 pub use light::*;
+pub use ray::*;
+pub use damping::*;
 
-#[derive(crate::macros::Reflect)]
+#[derive(Clone, Copy, Debug, PartialEq, crate::macros::Reflect)]
 pub struct Rect {
     pub min: Vec2,
     pub max: Vec2,
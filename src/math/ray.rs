@@ -0,0 +1,148 @@
+use glam::{Mat4, Vec3};
+
+use super::bounding_volume::{AABB, OBB, Plane, Sphere, WorldBoundingVolume};
+
+/// Half-line in 3D space, defined by an origin and a normalized direction
+#[derive(crate::macros::Reflect, Clone, Copy, Debug, PartialEq)]
+pub struct Ray {
+    pub origin: Vec3,
+    pub direction: Vec3,
+}
+
+impl Ray {
+    /// Create a new ray, `direction` gets normalized
+    #[inline]
+    pub fn new(origin: Vec3, direction: Vec3) -> Self {
+        Self {
+            origin,
+            direction: direction.normalize_or(Vec3::NEG_Z),
+        }
+    }
+
+    /// Returns the point at `distance` along the ray
+    #[inline]
+    pub fn at(&self, distance: f32) -> Vec3 {
+        self.origin + self.direction * distance
+    }
+
+    /// Returns the ray transformed by `matrix`, the direction is renormalized
+    #[inline]
+    pub fn transform(&self, matrix: &Mat4) -> Self {
+        Self::new(
+            matrix.transform_point3(self.origin),
+            matrix.transform_vector3(self.direction),
+        )
+    }
+
+    /// Returns the distance to the closest intersection with a sphere
+    pub fn intersect_sphere(&self, sphere: &Sphere) -> Option<f32> {
+        let offset = self.origin - sphere.center;
+        let b = offset.dot(self.direction);
+        let c = offset.length_squared() - sphere.radius * sphere.radius;
+
+        // origin is outside and pointing away
+        if c > 0.0 && b > 0.0 {
+            return None;
+        }
+
+        let discriminant = b * b - c;
+        if discriminant < 0.0 {
+            return None;
+        }
+
+        Some((-b - discriminant.sqrt()).max(0.0))
+    }
+
+    /// Returns the distance to the closest intersection with an axis aligned box
+    pub fn intersect_aabb(&self, aabb: &AABB) -> Option<f32> {
+        Self::slab_test(self.origin, self.direction, aabb.min, aabb.max)
+    }
+
+    /// Returns the distance to the closest intersection with an oriented box
+    pub fn intersect_obb(&self, obb: &OBB) -> Option<f32> {
+        let axes = obb.get_obb_axes();
+        let offset = self.origin - obb.center;
+
+        // move the ray into the box space, the distance is preserved since the axes are normalized
+        let origin = Vec3::new(
+            axes[0].dot(offset),
+            axes[1].dot(offset),
+            axes[2].dot(offset),
+        );
+        let direction = Vec3::new(
+            axes[0].dot(self.direction),
+            axes[1].dot(self.direction),
+            axes[2].dot(self.direction),
+        );
+
+        Self::slab_test(origin, direction, -obb.half_extents, obb.half_extents)
+    }
+
+    /// Returns the distance to the closest intersection with a world bounding volume
+    pub fn intersect_bounding_volume(&self, volume: &WorldBoundingVolume) -> Option<f32> {
+        match volume {
+            WorldBoundingVolume::Sphere(sphere) => self.intersect_sphere(sphere),
+            WorldBoundingVolume::AABB(aabb) => self.intersect_aabb(aabb),
+            WorldBoundingVolume::OBB(obb) => self.intersect_obb(obb),
+            WorldBoundingVolume::None => None,
+        }
+    }
+
+    /// Returns the distance to the intersection with a plane, both sides are hit
+    pub fn intersect_plane(&self, plane: &Plane) -> Option<f32> {
+        let denominator = plane.normal.dot(self.direction);
+        if denominator.abs() < f32::EPSILON {
+            // parallel to the plane
+            return None;
+        }
+
+        let distance = -(plane.normal.dot(self.origin) + plane.d) / denominator;
+        (distance >= 0.0).then_some(distance)
+    }
+
+    /// Returns the distance to the intersection with a triangle, both sides are hit.
+    /// Uses the Möller–Trumbore algorithm.
+    pub fn intersect_triangle(&self, a: Vec3, b: Vec3, c: Vec3) -> Option<f32> {
+        let edge1 = b - a;
+        let edge2 = c - a;
+
+        let p = self.direction.cross(edge2);
+        let determinant = edge1.dot(p);
+        if determinant.abs() < f32::EPSILON {
+            // parallel to the triangle
+            return None;
+        }
+
+        let inverse = 1.0 / determinant;
+        let t = self.origin - a;
+        let u = t.dot(p) * inverse;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let q = t.cross(edge1);
+        let v = self.direction.dot(q) * inverse;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let distance = edge2.dot(q) * inverse;
+        (distance >= 0.0).then_some(distance)
+    }
+
+    /// Slab test against a box given by `min` and `max` corners
+    fn slab_test(origin: Vec3, direction: Vec3, min: Vec3, max: Vec3) -> Option<f32> {
+        let inverse = direction.recip();
+        let t1 = (min - origin) * inverse;
+        let t2 = (max - origin) * inverse;
+
+        let near = t1.min(t2).max_element();
+        let far = t1.max(t2).min_element();
+
+        if far < 0.0 || near > far {
+            return None;
+        }
+
+        Some(near.max(0.0))
+    }
+}
@@ -7,9 +7,12 @@ use crate::{
     render_assets::{BindGroup, Buffer, IntoRenderAsset, RenderAssets},
 };
 
+use super::exp_decay_factor;
+
 /// Represents the local transform of an entity, relative to its parent or the world space if it
 /// has no parent.
 #[derive(Component, Reflect, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct Transform {
     pub scale: Vec3,
     pub rotation: Quat,
@@ -238,6 +241,16 @@ impl Transform {
         self.look_to(direction, up);
     }
 
+    /// Rotates this transform towards looking at `target`, smoothed with [`exp_decay`] so the
+    /// turn speed doesn't depend on the frame rate. Higher `rate` turns faster.
+    pub fn smooth_look_at(&mut self, target: Vec3, up: Vec3, rate: f32, delta: f32) {
+        let goal = self.looking_at(target, up).rotation;
+        self.rotation = self
+            .rotation
+            .slerp(goal, exp_decay_factor(rate, delta))
+            .normalize();
+    }
+
     /// Rotates this transform to look in the `direction` with the `up` vector
     #[inline]
     pub fn look_to(&mut self, direction: Vec3, up: Vec3) {
@@ -0,0 +1,238 @@
+use std::collections::VecDeque;
+
+use glam::Vec3;
+
+use crate::prelude::*;
+
+use super::{NavPath, SpatialHash};
+
+/// Distance from the final waypoint at which an agent has arrived
+const ARRIVE_TOLERANCE: f32 = 0.05;
+
+/// Plugin which moves [`Agent`] entities along their waypoints in [`phase::FixedUpdate`], and
+/// keeps the [`SpatialHash`] resource filled with their positions
+pub struct AgentPlugin;
+
+impl Plugin for AgentPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpatialHash>()
+            .register_system(update_spatial_hash.before(steer_agents), phase::FixedUpdate)
+            .register_system(steer_agents, phase::FixedUpdate);
+    }
+}
+
+/// Component which steers an entity along waypoints on the world `XZ` plane, its height is kept.
+/// Requires the [`AgentPlugin`].
+///
+/// Agents seek intermediate waypoints at full speed, slow down to arrive at the final one, and
+/// steer away from other agents within their avoidance radius. Changes in velocity are limited by
+/// the acceleration, so turns are smooth.
+///
+/// ```ignore
+/// let mut agent = Agent::new(4.0).with_avoidance(1.5, 1.0);
+/// if let Some(path) = grid.find_path(start, goal) {
+///     agent.follow(&path);
+/// }
+/// world.spawn_bundle((agent, Transform::new().with_translation(start)));
+/// ```
+#[derive(Component, Debug, Clone)]
+pub struct Agent {
+    /// Maximum speed in world units per second
+    pub max_speed: f32,
+    /// Maximum change of velocity in world units per second squared
+    pub max_acceleration: f32,
+    /// Distance from the final waypoint at which the agent starts slowing down
+    pub arrive_radius: f32,
+    /// Distance at which intermediate waypoints count as reached
+    pub waypoint_radius: f32,
+    /// Distance to other agents at which the agent steers away from them, zero disables avoidance
+    pub avoid_radius: f32,
+    /// Strength of the avoidance relative to following the waypoints
+    pub avoid_weight: f32,
+    waypoints: VecDeque<Vec3>,
+    velocity: Vec3,
+}
+
+impl Agent {
+    /// Create a new agent with `max_speed` and no waypoints
+    pub fn new(max_speed: f32) -> Self {
+        Self {
+            max_speed,
+            max_acceleration: max_speed * 4.0,
+            arrive_radius: 1.0,
+            waypoint_radius: 0.5,
+            avoid_radius: 1.0,
+            avoid_weight: 1.0,
+            waypoints: VecDeque::new(),
+            velocity: Vec3::ZERO,
+        }
+    }
+
+    /// Returns self with the maximum acceleration
+    #[must_use]
+    pub fn with_acceleration(mut self, max_acceleration: f32) -> Self {
+        self.max_acceleration = max_acceleration;
+        self
+    }
+
+    /// Returns self with the radius at which it slows down before the final waypoint
+    #[must_use]
+    pub fn with_arrive_radius(mut self, arrive_radius: f32) -> Self {
+        self.arrive_radius = arrive_radius;
+        self
+    }
+
+    /// Returns self with the radius at which intermediate waypoints count as reached
+    #[must_use]
+    pub fn with_waypoint_radius(mut self, waypoint_radius: f32) -> Self {
+        self.waypoint_radius = waypoint_radius;
+        self
+    }
+
+    /// Returns self with the radius and strength of avoiding other agents
+    #[must_use]
+    pub fn with_avoidance(mut self, avoid_radius: f32, avoid_weight: f32) -> Self {
+        self.avoid_radius = avoid_radius;
+        self.avoid_weight = avoid_weight;
+        self
+    }
+
+    /// Follow the waypoints of `path`, replacing the current ones. The first waypoint is skipped,
+    /// it's the cell the agent starts in.
+    pub fn follow(&mut self, path: &NavPath) {
+        self.waypoints = path.waypoints().iter().skip(1).copied().collect();
+    }
+
+    /// Move straight to `target`, replacing the current waypoints
+    pub fn seek(&mut self, target: Vec3) {
+        self.waypoints.clear();
+        self.waypoints.push_back(target);
+    }
+
+    /// Add `waypoint` after the current ones
+    pub fn push_waypoint(&mut self, waypoint: Vec3) {
+        self.waypoints.push_back(waypoint);
+    }
+
+    /// Remove all waypoints, the agent slows down and stops
+    pub fn stop(&mut self) {
+        self.waypoints.clear();
+    }
+
+    /// Returns the remaining waypoints, the first one is the current target
+    pub fn waypoints(&self) -> impl Iterator<Item = &Vec3> {
+        self.waypoints.iter()
+    }
+
+    /// Returns the current target
+    #[inline]
+    pub fn target(&self) -> Option<Vec3> {
+        self.waypoints.front().copied()
+    }
+
+    /// Returns true if the agent has no waypoints left
+    #[inline]
+    pub fn is_idle(&self) -> bool {
+        self.waypoints.is_empty()
+    }
+
+    /// Returns the current velocity
+    #[inline]
+    pub fn velocity(&self) -> Vec3 {
+        self.velocity
+    }
+
+    /// Returns the velocity the agent wants to move with to reach its waypoints
+    fn desired_velocity(&mut self, position: Vec3) -> Vec3 {
+        // skip reached intermediate waypoints
+        while self.waypoints.len() > 1
+            && planar(self.waypoints[0] - position).length() <= self.waypoint_radius
+        {
+            self.waypoints.pop_front();
+        }
+
+        let Some(target) = self.target() else {
+            return Vec3::ZERO;
+        };
+        let offset = planar(target - position);
+        let distance = offset.length();
+
+        if self.waypoints.len() > 1 {
+            // seek
+            return offset / distance.max(f32::EPSILON) * self.max_speed;
+        }
+
+        // arrive
+        if distance <= ARRIVE_TOLERANCE {
+            self.waypoints.clear();
+            return Vec3::ZERO;
+        }
+        let speed = self.max_speed * (distance / self.arrive_radius.max(f32::EPSILON)).min(1.0);
+        offset / distance * speed
+    }
+
+    /// Returns the velocity which steers away from agents closer than the avoidance radius
+    fn avoidance(&self, entity: EntityId, position: Vec3, grid: &SpatialHash) -> Vec3 {
+        if self.avoid_radius <= 0.0 {
+            return Vec3::ZERO;
+        }
+
+        let mut push = Vec3::ZERO;
+        for (other, other_position) in grid.neighbours(position, self.avoid_radius) {
+            if other == entity {
+                continue;
+            }
+
+            let away = planar(position - other_position);
+            let distance = away.length();
+            // agents at the same position are pushed apart in opposite directions
+            let direction = away
+                .try_normalize()
+                .unwrap_or(if entity.index() > other.index() {
+                    Vec3::X
+                } else {
+                    Vec3::NEG_X
+                });
+            push += direction * (1.0 - distance / self.avoid_radius);
+        }
+
+        push * self.max_speed * self.avoid_weight
+    }
+}
+
+/// Returns `vector` on the `XZ` plane
+#[inline]
+fn planar(vector: Vec3) -> Vec3 {
+    Vec3::new(vector.x, 0.0, vector.z)
+}
+
+/// System which inserts the positions of all agents into the [`SpatialHash`]
+fn update_spatial_hash(
+    mut grid: ResMut<SpatialHash>,
+    mut query: Query<(EntityId, &Transform), With<Agent>>,
+) {
+    grid.clear();
+    for (id, transform) in query.iter_mut() {
+        grid.insert(id, transform.translation);
+    }
+}
+
+/// System which applies the seek, arrive and avoid behaviors of agents and moves them
+fn steer_agents(
+    time: Res<Time>,
+    grid: Res<SpatialHash>,
+    mut query: Query<(EntityId, &mut Agent, &mut Transform)>,
+) {
+    let delta = time.step_delta();
+
+    for (id, agent, transform) in query.iter_mut() {
+        let position = transform.translation;
+        let desired = agent.desired_velocity(position) + agent.avoidance(id, position, &grid);
+        let desired = desired.clamp_length_max(agent.max_speed);
+
+        let steering = (desired - agent.velocity).clamp_length_max(agent.max_acceleration * delta);
+        agent.velocity = (agent.velocity + steering).clamp_length_max(agent.max_speed);
+
+        transform.translation += agent.velocity * delta;
+    }
+}
@@ -0,0 +1,320 @@
+use glam::{UVec2, Vec3};
+use wgpu::{VertexAttribute, VertexFormat};
+
+use crate::{
+    assets::ShaderLoader,
+    core::{graph::*, standard::rendering::surface_size},
+    plugins::RenderPlugin,
+    prelude::*,
+    render_assets::{BindGroup, Buffer, Pipeline, RenderAssets, pipeline::PipelineBuilder},
+    renderer::{
+        newtype::{RenderCommandEncoder, RenderDevice, RenderSurfaceConfiguration},
+        palette,
+    },
+};
+
+use super::{NavGrid, NavPath};
+
+/// Height above the grid at which lines are drawn, so they aren't hidden inside the ground
+const LINE_OFFSET: f32 = 0.02;
+
+/// Plugin which draws the [`NavGrid`] resource and the paths passed to [`NavDebug::draw_path`]
+pub struct NavDebugPlugin;
+
+impl Plugin for NavDebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NavDebug>()
+            .add_startup_system(register_nav_debug_graph)
+            .register_system(prepare_nav_debug_system, phase::PreRender);
+    }
+
+    fn dependencies(&self) -> Vec<PluginId> {
+        vec![PluginId::of::<RenderPlugin>()]
+    }
+}
+
+/// Settings of the navigation debug drawing. Used as a resource.
+#[derive(Resource)]
+pub struct NavDebug {
+    pub enabled: bool,
+    /// Draw the grid bounds and blocked cells
+    pub draw_grid: bool,
+    pub blocked_color: Color,
+    pub bounds_color: Color,
+    pub path_color: Color,
+    /// Paths drawn until [`Self::clear_paths`] is called
+    paths: Vec<Vec<Vec3>>,
+    /// Lines drawn in the `nav_debug` graph node
+    lines: Vec<NavDebugVertex>,
+}
+
+impl Default for NavDebug {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            draw_grid: true,
+            blocked_color: palette::RED,
+            bounds_color: palette::GRAY,
+            path_color: palette::LIME,
+            paths: Vec::new(),
+            lines: Vec::new(),
+        }
+    }
+}
+
+impl NavDebug {
+    /// Draw `path` every frame until [`Self::clear_paths`] is called
+    pub fn draw_path(&mut self, path: &NavPath) {
+        self.paths.push(path.waypoints().to_vec());
+    }
+
+    /// Stop drawing all paths
+    pub fn clear_paths(&mut self) {
+        self.paths.clear();
+    }
+}
+
+/// Vertex of a navigation debug line
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct NavDebugVertex {
+    position: [f32; 3],
+    color: [f32; 4],
+}
+
+impl NavDebugVertex {
+    fn vertex_descriptor() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<NavDebugVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                // Position
+                VertexAttribute {
+                    format: VertexFormat::Float32x3,
+                    offset: 0,
+                    shader_location: 0,
+                },
+                // Color
+                VertexAttribute {
+                    format: VertexFormat::Float32x4,
+                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                },
+            ],
+        }
+    }
+}
+
+/// Prepares the lines drawn by the `nav_debug` graph node
+fn prepare_nav_debug_system(mut debug: ResMut<NavDebug>, grid: Option<Res<NavGrid>>) {
+    debug.lines.clear();
+    if !debug.enabled {
+        return;
+    }
+
+    let mut lines = Vec::new();
+    let mut line = |from: Vec3, to: Vec3, color: Color| {
+        let color = [color.r, color.g, color.b, color.a];
+        lines.push(NavDebugVertex {
+            position: from.to_array(),
+            color,
+        });
+        lines.push(NavDebugVertex {
+            position: to.to_array(),
+            color,
+        });
+    };
+    let up = Vec3::Y * LINE_OFFSET;
+
+    if debug.draw_grid
+        && let Some(grid) = grid
+    {
+        let size = grid.size().as_vec2() * grid.cell_size();
+        let origin = grid.origin() + up;
+        let corners = [
+            origin,
+            origin + Vec3::X * size.x,
+            origin + Vec3::new(size.x, 0.0, size.y),
+            origin + Vec3::Z * size.y,
+        ];
+        for i in 0..4 {
+            line(corners[i], corners[(i + 1) % 4], debug.bounds_color);
+        }
+
+        // cross over blocked cells
+        let half = grid.cell_size() * 0.4;
+        for y in 0..grid.size().y {
+            for x in 0..grid.size().x {
+                let cell = UVec2::new(x, y);
+                if grid.is_walkable(cell) {
+                    continue;
+                }
+                let center = grid.cell_center(cell) + up;
+                line(
+                    center + Vec3::new(-half, 0.0, -half),
+                    center + Vec3::new(half, 0.0, half),
+                    debug.blocked_color,
+                );
+                line(
+                    center + Vec3::new(-half, 0.0, half),
+                    center + Vec3::new(half, 0.0, -half),
+                    debug.blocked_color,
+                );
+            }
+        }
+    }
+
+    // paths are raised a bit more, so they're drawn over the grid
+    for path in &debug.paths {
+        for segment in path.windows(2) {
+            line(
+                segment[0] + up * 2.0,
+                segment[1] + up * 2.0,
+                debug.path_color,
+            );
+        }
+    }
+
+    debug.lines = lines;
+}
+
+/// Startup system to register the navigation debug graph node
+fn register_nav_debug_graph(
+    graph: &mut RenderGraph,
+    device: Res<RenderDevice>,
+    surface_config: Res<RenderSurfaceConfiguration>,
+    mut shader_loader: ResMut<ShaderLoader>,
+) {
+    let pipeline_builder =
+        create_nav_debug_pipeline_builder(&device, &surface_config, &mut shader_loader);
+
+    let node = GraphNodeBuilder::new("nav_debug")
+        .set_pipeline(pipeline_builder)
+        .set_custom_system(nav_debug_render_system)
+        .set_color_target(NodeColorTarget::Surface)
+        .run_after("main")
+        .run_before("ui_image")
+        .build();
+
+    graph.add(node);
+}
+
+/// Navigation debug graph node rendering system, draws the lines prepared by
+/// [`prepare_nav_debug_system`]
+fn nav_debug_render_system(
+    graph_ctx: Res<RenderContext>,
+
+    world: &mut World,
+    encoder: &mut RenderCommandEncoder,
+    device: Res<RenderDevice>,
+    debug: Res<NavDebug>,
+    mut bind_groups: ResMut<RenderAssets<BindGroup>>,
+
+    mut camera_query: Query<(EntityId, &Camera), With<Camera3D>>,
+    rendered: Res<RenderedWindow>,
+) {
+    if debug.lines.is_empty() {
+        return;
+    }
+
+    let cameras = Camera::rendering_to(camera_query.iter_mut(), &rendered);
+    if cameras.is_empty() {
+        return;
+    }
+
+    let buffer = Buffer::new("nav_debug").create_vertex_buffer(
+        &debug.lines,
+        debug.lines.len(),
+        None,
+        &device,
+    );
+    let vertex_buffer = buffer
+        .vertex
+        .as_ref()
+        .expect("Nav debug lines should not be empty");
+
+    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("nav debug render pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: unsafe {
+                &*graph_ctx
+                    .color_target
+                    .expect("nav debug color target is None")
+            },
+            depth_slice: None,
+            resolve_target: graph_ctx.resolve_target.map(|view| unsafe { &*view }),
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    });
+
+    render_pass.set_pipeline(
+        unsafe { &*graph_ctx.node }
+            .data
+            .pipeline
+            .as_ref()
+            .expect("Pipeline should have been generated by now")
+            .render_pipeline(),
+    );
+    render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+
+    let target_size = surface_size(world);
+    let mut draw_calls = 0;
+    for (camera_id, camera) in cameras {
+        if !camera.set_render_pass_camera(
+            camera_id,
+            &mut render_pass,
+            0,
+            target_size,
+            &mut bind_groups,
+            world,
+        ) {
+            continue;
+        }
+        render_pass.draw(0..buffer.num_vertices, 0..1);
+        draw_calls += 1;
+    }
+
+    graph_ctx.record_draw_calls(draw_calls);
+}
+
+fn create_nav_debug_pipeline_builder(
+    device: &RenderDevice,
+    surface_config: &RenderSurfaceConfiguration,
+    shader_loader: &mut ShaderLoader,
+) -> PipelineBuilder {
+    // Camera bind group layout for uniform buffer
+    let camera_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("camera_bind_group_layout"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+    });
+
+    // the line shader is shared with the inspector gizmo
+    shader_loader.load("gizmo", include_str!("../shaders/gizmo.wgsl"), device);
+
+    let mut primitive_state = PipelineBuilder::default_primitive_state();
+    primitive_state.topology = wgpu::PrimitiveTopology::LineList;
+    primitive_state.cull_mode = None;
+
+    Pipeline::build("nav_debug_pipeline")
+        .set_bind_group_layouts(vec![camera_layout])
+        .set_vertex_buffer_layouts(vec![NavDebugVertex::vertex_descriptor()])
+        .set_vertex_shader("gizmo", "vs_main")
+        .set_fragment_shader("gizmo", "fs_main")
+        .add_color_format(surface_config.format)
+        .set_primitive_state(primitive_state)
+}
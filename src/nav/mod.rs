@@ -0,0 +1,399 @@
+//! # Navigation
+//! Grid based pathfinding with A*, for top-down and strategy games.
+//!
+//! ## Usage
+//!
+//! - Insert a [`NavGrid`] resource, it covers the world `XZ` plane starting at its origin. Cells
+//!   are walkable by default, mark obstacles with [`NavGrid::set_walkable`] and slow terrain with
+//!   [`NavGrid::set_cost`].
+//! - Find paths with [`NavGrid::find_path`], or with [`NavGrid::find_path_async`] to search on
+//!   another thread for large grids.
+//! ```ignore
+//! let mut grid = NavGrid::new(UVec2::new(64, 64), 1.0);
+//! grid.set_walkable(UVec2::new(10, 12), false);
+//! app.set_resource(grid);
+//!
+//! fn move_to_target(grid: Res<NavGrid>, mut task: Local<Option<Task<Option<NavPath>>>>) {
+//!     let task = task.get_or_insert_with(|| grid.find_path_async(start, goal));
+//!     if let Some(Ok(Some(path))) = task.retrieve() {
+//!         for waypoint in path.waypoints() {
+//!             // ...
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! ## Agents
+//!
+//! Add the [`AgentPlugin`] and give entities an [`Agent`] component, they follow paths with
+//! [`Agent::follow`] and steer around each other in [`phase::FixedUpdate`]. Neighbours are found
+//! with the [`SpatialHash`] resource, which can also be used by other systems.
+//! ```ignore
+//! app.add_plugin(AgentPlugin);
+//!
+//! fn send_units(grid: Res<NavGrid>, mut query: Query<(&mut Agent, &Transform), With<Selected>>) {
+//!     for (agent, transform) in query.iter_mut() {
+//!         if let Some(path) = grid.find_path(transform.translation, target) {
+//!             agent.follow(&path);
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! ## Debugging
+//!
+//! Add the [`NavDebugPlugin`] to draw the grid bounds, blocked cells and the paths passed to
+//! [`NavDebug::draw_path`].
+
+mod agent;
+mod debug;
+mod spatial;
+
+pub mod prelude {
+    pub use super::{Agent, AgentPlugin, NavDebug, NavDebugPlugin, NavGrid, NavPath, SpatialHash};
+}
+
+use std::{cmp::Ordering, collections::BinaryHeap, sync::Arc};
+
+use glam::{IVec2, UVec2, Vec3};
+
+use crate::prelude::*;
+
+pub use agent::{Agent, AgentPlugin};
+pub use debug::{NavDebug, NavDebugPlugin};
+pub use spatial::SpatialHash;
+
+/// Cost of moving diagonally relative to moving straight
+const DIAGONAL_COST: f32 = std::f32::consts::SQRT_2;
+
+/// Navigation cell of a [`NavGrid`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct NavCell {
+    walkable: bool,
+    /// Cost multiplier of moving into the cell, at least 1
+    cost: f32,
+}
+
+/// Grid of walkable cells on the world `XZ` plane, used as a resource for pathfinding. Cell
+/// `(0, 0)` starts at the origin, `x` goes along the `X` axis and `y` along the `Z` axis.
+///
+/// Cloning is cheap, the cells are shared until one of the copies is changed.
+#[derive(Resource, Debug, Clone)]
+pub struct NavGrid {
+    size: UVec2,
+    cell_size: f32,
+    origin: Vec3,
+    /// Allow moving diagonally, never past the corner of a blocked cell
+    pub diagonal: bool,
+    cells: Arc<Vec<NavCell>>,
+}
+
+impl NavGrid {
+    /// Create a new grid of `size` walkable cells, each `cell_size` world units wide
+    pub fn new(size: UVec2, cell_size: f32) -> Self {
+        let cell = NavCell {
+            walkable: true,
+            cost: 1.0,
+        };
+
+        Self {
+            size,
+            cell_size,
+            origin: Vec3::ZERO,
+            diagonal: true,
+            cells: Arc::new(vec![cell; (size.x * size.y) as usize]),
+        }
+    }
+
+    /// Returns self with the world position of the corner of cell `(0, 0)`
+    #[must_use]
+    pub fn with_origin(mut self, origin: Vec3) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    /// Returns self with diagonal movement enabled or disabled
+    #[must_use]
+    pub fn with_diagonal(mut self, diagonal: bool) -> Self {
+        self.diagonal = diagonal;
+        self
+    }
+
+    /// Returns the amount of cells along each axis
+    #[inline]
+    pub fn size(&self) -> UVec2 {
+        self.size
+    }
+
+    /// Returns the width of a cell in world units
+    #[inline]
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    /// Returns the world position of the corner of cell `(0, 0)`
+    #[inline]
+    pub fn origin(&self) -> Vec3 {
+        self.origin
+    }
+
+    /// Returns the index of `cell`, or None if it's outside of the grid
+    #[inline]
+    fn index(&self, cell: UVec2) -> Option<usize> {
+        (cell.x < self.size.x && cell.y < self.size.y)
+            .then(|| (cell.y * self.size.x + cell.x) as usize)
+    }
+
+    /// Returns the cell at `index`
+    #[inline]
+    fn cell_of(&self, index: usize) -> UVec2 {
+        UVec2::new(index as u32 % self.size.x, index as u32 / self.size.x)
+    }
+
+    /// Returns true if `cell` is inside of the grid and walkable
+    pub fn is_walkable(&self, cell: UVec2) -> bool {
+        self.index(cell)
+            .is_some_and(|index| self.cells[index].walkable)
+    }
+
+    /// Set whether `cell` can be walked through, cells outside of the grid are ignored
+    pub fn set_walkable(&mut self, cell: UVec2, walkable: bool) {
+        if let Some(index) = self.index(cell) {
+            Arc::make_mut(&mut self.cells)[index].walkable = walkable;
+        }
+    }
+
+    /// Returns the cost multiplier of moving into `cell`, or None if it's outside of the grid
+    pub fn cost(&self, cell: UVec2) -> Option<f32> {
+        self.index(cell).map(|index| self.cells[index].cost)
+    }
+
+    /// Set the cost multiplier of moving into `cell`, e.g. 3 for a swamp which takes three times
+    /// as long to cross. Costs below 1 are clamped to 1, so the A* heuristic stays exact.
+    pub fn set_cost(&mut self, cell: UVec2, cost: f32) {
+        if let Some(index) = self.index(cell) {
+            Arc::make_mut(&mut self.cells)[index].cost = cost.max(1.0);
+        }
+    }
+
+    /// Set every cell in the rectangle from `min` to `max` inclusive as walkable or blocked
+    pub fn set_walkable_rect(&mut self, min: UVec2, max: UVec2, walkable: bool) {
+        let max = max.min(self.size.saturating_sub(UVec2::ONE));
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                self.set_walkable(UVec2::new(x, y), walkable);
+            }
+        }
+    }
+
+    /// Returns the cell containing world position `position`, or None if it's outside of the
+    /// grid. The height is ignored.
+    pub fn cell_at(&self, position: Vec3) -> Option<UVec2> {
+        let local = (position - self.origin) / self.cell_size;
+        let cell = IVec2::new(local.x.floor() as i32, local.z.floor() as i32);
+
+        (cell.cmpge(IVec2::ZERO).all() && cell.cmplt(self.size.as_ivec2()).all())
+            .then(|| cell.as_uvec2())
+    }
+
+    /// Returns the world position of the center of `cell`, at the height of the origin
+    pub fn cell_center(&self, cell: UVec2) -> Vec3 {
+        let center = (cell.as_vec2() + 0.5) * self.cell_size;
+        self.origin + Vec3::new(center.x, 0.0, center.y)
+    }
+
+    /// Returns the walkable neighbours of the cell at `index` and the cost of moving to them
+    fn neighbours(&self, index: usize) -> impl Iterator<Item = (usize, f32)> + '_ {
+        let cell = self.cell_of(index).as_ivec2();
+        let size = self.size.as_ivec2();
+        let walkable = move |offset: IVec2| {
+            let neighbour = cell + offset;
+            let inside = neighbour.cmpge(IVec2::ZERO).all() && neighbour.cmplt(size).all();
+            inside && self.is_walkable(neighbour.as_uvec2())
+        };
+
+        let directions = [
+            IVec2::X,
+            IVec2::NEG_X,
+            IVec2::Y,
+            IVec2::NEG_Y,
+            IVec2::new(1, 1),
+            IVec2::new(1, -1),
+            IVec2::new(-1, 1),
+            IVec2::new(-1, -1),
+        ];
+        let count = if self.diagonal { 8 } else { 4 };
+
+        directions
+            .into_iter()
+            .take(count)
+            .filter_map(move |offset| {
+                let diagonal = offset.x != 0 && offset.y != 0;
+                // diagonal moves can't cut the corner of a blocked cell
+                if !walkable(offset)
+                    || diagonal
+                        && (!walkable(IVec2::new(offset.x, 0))
+                            || !walkable(IVec2::new(0, offset.y)))
+                {
+                    return None;
+                }
+
+                let index = self.index((cell + offset).as_uvec2())?;
+                let step = if diagonal { DIAGONAL_COST } else { 1.0 };
+                Some((index, step * self.cells[index].cost))
+            })
+    }
+
+    /// Returns the estimated cost from `from` to `to`, never more than the real cost
+    fn heuristic(&self, from: UVec2, to: UVec2) -> f32 {
+        let delta = from.as_ivec2() - to.as_ivec2();
+        let (dx, dy) = (delta.x.unsigned_abs() as f32, delta.y.unsigned_abs() as f32);
+
+        if self.diagonal {
+            // octile distance
+            dx.max(dy) + (DIAGONAL_COST - 1.0) * dx.min(dy)
+        } else {
+            dx + dy
+        }
+    }
+
+    /// Returns the cheapest path between the cells at world positions `start` and `goal`, or None
+    /// if either is outside of the grid or blocked, or the goal can't be reached
+    pub fn find_path(&self, start: Vec3, goal: Vec3) -> Option<NavPath> {
+        let start = self.cell_at(start)?;
+        let goal = self.cell_at(goal)?;
+        self.find_cell_path(start, goal)
+    }
+
+    /// Same as [`Self::find_path`] with cells instead of world positions
+    pub fn find_cell_path(&self, start: UVec2, goal: UVec2) -> Option<NavPath> {
+        if !self.is_walkable(start) || !self.is_walkable(goal) {
+            return None;
+        }
+
+        let start_index = self.index(start)?;
+        let goal_index = self.index(goal)?;
+
+        let mut costs = vec![f32::INFINITY; self.cells.len()];
+        let mut previous = vec![usize::MAX; self.cells.len()];
+        let mut open = BinaryHeap::new();
+
+        costs[start_index] = 0.0;
+        open.push(OpenNode {
+            estimate: self.heuristic(start, goal),
+            index: start_index,
+        });
+
+        while let Some(OpenNode { estimate, index }) = open.pop() {
+            if index == goal_index {
+                return Some(self.build_path(&previous, goal_index, costs[goal_index]));
+            }
+
+            // skip outdated entries, the node was reached more cheaply since it was pushed
+            let cost = costs[index];
+            if estimate > cost + self.heuristic(self.cell_of(index), goal) + f32::EPSILON {
+                continue;
+            }
+
+            for (neighbour, step) in self.neighbours(index) {
+                let neighbour_cost = cost + step;
+                if neighbour_cost < costs[neighbour] {
+                    costs[neighbour] = neighbour_cost;
+                    previous[neighbour] = index;
+                    open.push(OpenNode {
+                        estimate: neighbour_cost + self.heuristic(self.cell_of(neighbour), goal),
+                        index: neighbour,
+                    });
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Follow the `previous` links back from the goal
+    fn build_path(&self, previous: &[usize], goal_index: usize, cost: f32) -> NavPath {
+        let mut cells = vec![self.cell_of(goal_index)];
+        let mut index = goal_index;
+        while previous[index] != usize::MAX {
+            index = previous[index];
+            cells.push(self.cell_of(index));
+        }
+        cells.reverse();
+
+        NavPath {
+            waypoints: cells.iter().map(|&cell| self.cell_center(cell)).collect(),
+            cells,
+            cost: cost * self.cell_size,
+        }
+    }
+
+    /// Same as [`Self::find_path`], but searches on another thread. The grid is cloned, so later
+    /// changes don't affect the search.
+    pub fn find_path_async(&self, start: Vec3, goal: Vec3) -> Task<Option<NavPath>> {
+        let grid = self.clone();
+        Task::execute(move || grid.find_path(start, goal))
+    }
+}
+
+/// Entry of the A* open set, ordered so the binary heap pops the lowest estimate first
+#[derive(Debug, Clone, Copy)]
+struct OpenNode {
+    /// Cost so far plus the heuristic
+    estimate: f32,
+    index: usize,
+}
+
+impl PartialEq for OpenNode {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for OpenNode {}
+
+impl PartialOrd for OpenNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OpenNode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .estimate
+            .total_cmp(&self.estimate)
+            .then_with(|| other.index.cmp(&self.index))
+    }
+}
+
+/// Path found by a [`NavGrid`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct NavPath {
+    /// Cells from the start to the goal, both included
+    cells: Vec<UVec2>,
+    /// World positions of the cell centers
+    waypoints: Vec<Vec3>,
+    /// Total cost in world units, scaled by the cell costs
+    cost: f32,
+}
+
+impl NavPath {
+    /// Returns the world positions of the cell centers along the path, from the start to the goal
+    #[inline]
+    pub fn waypoints(&self) -> &[Vec3] {
+        &self.waypoints
+    }
+
+    /// Returns the cells along the path, from the start to the goal
+    #[inline]
+    pub fn cells(&self) -> &[UVec2] {
+        &self.cells
+    }
+
+    /// Returns the total cost of the path, the length in world units scaled by the cell costs
+    #[inline]
+    pub fn cost(&self) -> f32 {
+        self.cost
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+use glam::{IVec2, Vec3};
+
+use crate::prelude::*;
+
+/// Spatial hash of entity positions on the world `XZ` plane, for finding nearby entities without
+/// checking all of them. Used as a resource.
+///
+/// The [`AgentPlugin`](super::AgentPlugin) fills it with the positions of all agents at the start
+/// of every fixed step, other entities can be inserted by systems which run after it.
+#[derive(Resource, Debug, Clone)]
+pub struct SpatialHash {
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<(EntityId, Vec3)>>,
+    len: usize,
+}
+
+impl Default for SpatialHash {
+    fn default() -> Self {
+        Self::new(4.0)
+    }
+}
+
+impl SpatialHash {
+    /// Create a new empty spatial hash, queries are fastest when `cell_size` is about the
+    /// largest query radius
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size: cell_size.max(f32::EPSILON),
+            cells: HashMap::new(),
+            len: 0,
+        }
+    }
+
+    /// Returns the width of a cell in world units
+    #[inline]
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    /// Returns the amount of inserted entities
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if no entities are inserted
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[inline]
+    fn cell(&self, position: Vec3) -> IVec2 {
+        IVec2::new(
+            (position.x / self.cell_size).floor() as i32,
+            (position.z / self.cell_size).floor() as i32,
+        )
+    }
+
+    /// Remove all entities, the allocated cells are kept
+    pub fn clear(&mut self) {
+        for entities in self.cells.values_mut() {
+            entities.clear();
+        }
+        self.len = 0;
+    }
+
+    /// Insert `entity` at `position`
+    pub fn insert(&mut self, entity: EntityId, position: Vec3) {
+        let cell = self.cell(position);
+        self.cells.entry(cell).or_default().push((entity, position));
+        self.len += 1;
+    }
+
+    /// Returns the entities and their positions within `radius` of `position` on the `XZ` plane
+    pub fn neighbours(
+        &self,
+        position: Vec3,
+        radius: f32,
+    ) -> impl Iterator<Item = (EntityId, Vec3)> + '_ {
+        let min = self.cell(position - Vec3::splat(radius));
+        let max = self.cell(position + Vec3::splat(radius));
+        let radius_squared = radius * radius;
+
+        (min.y..=max.y)
+            .flat_map(move |y| (min.x..=max.x).map(move |x| IVec2::new(x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .filter(move |(_, other)| {
+                let offset = *other - position;
+                offset.x * offset.x + offset.z * offset.z <= radius_squared
+            })
+    }
+}
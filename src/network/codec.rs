@@ -0,0 +1,205 @@
+use std::fmt;
+
+use crate::reflect::Reflect;
+
+/// Error returned when a received packet can't be decoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The packet ended in the middle of a value
+    UnexpectedEnd,
+    /// The packet doesn't start with the protocol id, or contains an unknown message
+    InvalidPacket,
+    /// The encoded value has a field which the local value doesn't, e.g. an `Option` which is
+    /// `None` locally, or a shorter `Vec`
+    MissingField(&'static str),
+    /// A string is not valid UTF-8
+    InvalidString,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEnd => write!(f, "Unexpected end of packet"),
+            Self::InvalidPacket => write!(f, "Invalid packet"),
+            Self::MissingField(type_name) => write!(f, "Missing field in '{}'", type_name),
+            Self::InvalidString => write!(f, "Invalid UTF-8 string"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Appends little endian encoded values to a byte buffer
+#[derive(Default)]
+pub(crate) struct Writer {
+    pub bytes: Vec<u8>,
+}
+
+impl Writer {
+    #[inline]
+    pub fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    #[inline]
+    pub fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    #[inline]
+    pub fn u64(&mut self, value: u64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// Length prefixed bytes
+    #[inline]
+    pub fn bytes(&mut self, bytes: &[u8]) {
+        self.u32(bytes.len() as u32);
+        self.bytes.extend_from_slice(bytes);
+    }
+}
+
+/// Reads values written by [`Writer`]
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    #[inline]
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        if self.bytes.len() < len {
+            return Err(DecodeError::UnexpectedEnd);
+        }
+
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+        Ok(self.take(N)?.try_into().expect("Length checked in take"))
+    }
+
+    #[inline]
+    pub fn u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    #[inline]
+    pub fn u32(&mut self) -> Result<u32, DecodeError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    #[inline]
+    pub fn u64(&mut self) -> Result<u64, DecodeError> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    /// Length prefixed bytes
+    #[inline]
+    pub fn bytes(&mut self) -> Result<&'a [u8], DecodeError> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+}
+
+/// Encode and decode leaf values, returns false if `value` is not a leaf type
+macro_rules! leaf_codec {
+    ($($type:ty),+) => {
+        fn encode_leaf(value: &dyn Reflect, writer: &mut Writer) -> bool {
+            $(if let Some(value) = value.downcast_ref::<$type>() {
+                writer.bytes.extend_from_slice(&value.to_le_bytes());
+                return true;
+            })+
+
+            // sizes are always sent as 64 bit to work between 32 and 64 bit platforms
+            if let Some(value) = value.downcast_ref::<usize>() {
+                writer.u64(*value as u64);
+            } else if let Some(value) = value.downcast_ref::<isize>() {
+                writer.u64(*value as i64 as u64);
+            } else if let Some(value) = value.downcast_ref::<bool>() {
+                writer.u8(*value as u8);
+            } else if let Some(value) = value.downcast_ref::<char>() {
+                writer.u32(*value as u32);
+            } else if let Some(value) = value.downcast_ref::<String>() {
+                writer.bytes(value.as_bytes());
+            } else {
+                return false;
+            }
+            true
+        }
+
+        fn decode_leaf(value: &mut dyn Reflect, reader: &mut Reader) -> Result<bool, DecodeError> {
+            $(if let Some(value) = value.downcast_mut::<$type>() {
+                *value = <$type>::from_le_bytes(reader.array()?);
+                return Ok(true);
+            })+
+
+            if let Some(value) = value.downcast_mut::<usize>() {
+                *value = reader.u64()? as usize;
+            } else if let Some(value) = value.downcast_mut::<isize>() {
+                *value = reader.u64()? as i64 as isize;
+            } else if let Some(value) = value.downcast_mut::<bool>() {
+                *value = reader.u8()? != 0;
+            } else if let Some(value) = value.downcast_mut::<char>() {
+                *value = char::from_u32(reader.u32()?).ok_or(DecodeError::InvalidString)?;
+            } else if let Some(value) = value.downcast_mut::<String>() {
+                let bytes = reader.bytes()?;
+                *value = String::from_utf8(bytes.to_vec()).map_err(|_| DecodeError::InvalidString)?;
+            } else {
+                return Ok(false);
+            }
+            Ok(true)
+        }
+    };
+}
+
+leaf_codec!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
+
+/// Encode `value` by walking its reflected fields. Primitives and strings are written directly,
+/// everything else as a field count followed by the fields.
+///
+/// Types without reflected fields, like maps and sets, are encoded as empty.
+pub(crate) fn encode(value: &dyn Reflect, writer: &mut Writer) {
+    if encode_leaf(value, writer) {
+        return;
+    }
+
+    let count = (0..)
+        .take_while(|&index| value.field_by_index(index).is_some())
+        .count();
+
+    writer.u32(count as u32);
+    for index in 0..count {
+        let field = value.field_by_index(index).expect("Field counted above");
+        encode(field, writer);
+    }
+}
+
+/// Decode a value written by [`encode`] into the existing `value`. Since fields are only
+/// overwritten, collections must already have the same length as the encoded ones.
+pub(crate) fn decode(value: &mut dyn Reflect, reader: &mut Reader) -> Result<(), DecodeError> {
+    if decode_leaf(value, reader)? {
+        return Ok(());
+    }
+
+    let count = reader.u32()? as usize;
+    for index in 0..count {
+        let type_name = value.type_name();
+        let field = value
+            .field_mut_by_index(index)
+            .ok_or(DecodeError::MissingField(type_name))?;
+        decode(field, reader)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,180 @@
+//! # Network plugin
+//! Server authoritative snapshot replication over a pluggable packet [`Transport`].
+//!
+//! ## Usage
+//!
+//! The server and the clients add the [`NetworkPlugin`] with their role and register the same
+//! replicated components. Entities marked with [`Replicated`] on the server are spawned on every
+//! connected client, with a [`NetworkEntity`] holding their id on the server.
+//! ```ignore
+//! // server
+//! app.add_plugin(NetworkPlugin::server(UdpTransport::server("0.0.0.0:5000")?))
+//!     .replicate::<Transform>();
+//!
+//! commands.spawn_empty().insert(Replicated).insert(Transform::default());
+//!
+//! // client
+//! app.add_plugin(NetworkPlugin::client(UdpTransport::client("127.0.0.1:5000")?))
+//!     .replicate::<Transform>();
+//! ```
+//!
+//! ## Replication
+//!
+//! Components registered with [`App::replicate`] are encoded through their [`Reflect`]
+//! implementation, and sent whenever their change tick moves, see [`Changed`]. A new client gets
+//! a full snapshot, which is also resent every [`NetworkSettings::snapshot_interval`] frames to
+//! recover from lost packets.
+//!
+//! Not replicated: component removals, [`EntityId`]s inside components (they are only valid on
+//! the server), and collections whose length differs from the client's value.
+//!
+//! [`Reflect`]: crate::reflect::Reflect
+//! [`Changed`]: crate::query::filter::Changed
+//! [`EntityId`]: crate::ecs::entities::EntityId
+
+mod codec;
+mod protocol;
+mod replication;
+mod transport;
+
+pub mod prelude {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub use super::UdpTransport;
+    pub use super::{
+        NetworkClient, NetworkEntity, NetworkEvent, NetworkPlugin, NetworkServer, NetworkSettings,
+        PeerId, Replicated, Transport,
+    };
+}
+
+use std::{sync::Mutex, time::Duration};
+
+use crate::{macros::Event, prelude::*};
+
+pub use codec::DecodeError;
+pub(crate) use replication::register_replicated;
+pub use replication::{NetworkClient, NetworkEntity, NetworkServer, Replicated};
+#[cfg(not(target_arch = "wasm32"))]
+pub use transport::UdpTransport;
+pub use transport::{MAX_PACKET_SIZE, PeerId, Transport};
+
+use replication::{
+    ReplicationRegistry, client_receive_system, server_collect_entities_system,
+    server_receive_system, server_send_system,
+};
+
+/// Side of the connection the app runs on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetworkRole {
+    /// Owns the replicated state and sends it to clients
+    Server,
+    /// Receives the replicated state from a server
+    Client,
+}
+
+/// Timing settings of the replication protocol
+#[derive(Debug, Clone)]
+pub struct NetworkSettings {
+    /// Frames between full snapshots sent to every client, 0 to only send snapshots to new clients
+    pub snapshot_interval: u32,
+    /// How often the client tells the server it's still connected
+    pub heartbeat_interval: Duration,
+    /// Time without packets after which the other side is considered disconnected
+    pub timeout: Duration,
+}
+
+impl Default for NetworkSettings {
+    fn default() -> Self {
+        Self {
+            snapshot_interval: 60,
+            heartbeat_interval: Duration::from_secs(1),
+            timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Connection changes, on the client the peer is always [`PeerId::SERVER`]
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetworkEvent {
+    Connected(PeerId),
+    Disconnected(PeerId),
+}
+
+/// Plugin which adds the [`NetworkServer`] or [`NetworkClient`] resource and the replication
+/// systems. For more information, see the [network module](crate::network).
+///
+/// Not part of the [`DefaultPlugins`].
+pub struct NetworkPlugin {
+    role: NetworkRole,
+    /// Protocol timing, must be the same on the server and the clients
+    pub settings: NetworkSettings,
+    /// Taken when the plugin is built
+    transport: Mutex<Option<Box<dyn Transport>>>,
+}
+
+impl NetworkPlugin {
+    fn new(role: NetworkRole, transport: impl Transport) -> Self {
+        Self {
+            role,
+            settings: NetworkSettings::default(),
+            transport: Mutex::new(Some(Box::new(transport))),
+        }
+    }
+
+    /// Run as the server, accepting clients on `transport`
+    pub fn server(transport: impl Transport) -> Self {
+        Self::new(NetworkRole::Server, transport)
+    }
+
+    /// Run as a client, connecting to the server through `transport`
+    pub fn client(transport: impl Transport) -> Self {
+        Self::new(NetworkRole::Client, transport)
+    }
+
+    /// Set the protocol timing
+    pub fn with_settings(mut self, settings: NetworkSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Returns the role of the app
+    #[inline]
+    pub fn role(&self) -> NetworkRole {
+        self.role
+    }
+}
+
+impl Plugin for NetworkPlugin {
+    fn build(&self, app: &mut App) {
+        let transport = self
+            .transport
+            .lock()
+            .unwrap()
+            .take()
+            .expect("NetworkPlugin can only be built once");
+
+        app.register_event::<NetworkEvent>()
+            .init_resource::<ReplicationRegistry>()
+            .register_type::<NetworkEntity>();
+
+        match self.role {
+            NetworkRole::Server => {
+                let server = NetworkServer::new(transport, self.settings.clone());
+                app.set_resource(server)
+                    .register_system(server_receive_system, phase::First)
+                    .register_system(server_collect_entities_system, phase::PostUpdate)
+                    .register_system(server_send_system, phase::FrameEnd);
+            }
+            NetworkRole::Client => {
+                let client = NetworkClient::new(transport, self.settings.clone());
+                app.set_resource(client)
+                    .register_system(client_receive_system, phase::First);
+            }
+        }
+    }
+
+    fn cleanup(&self, app: &mut App) {
+        if let Some(mut client) = app.world.resources.try_get_mut::<NetworkClient>() {
+            client.disconnect();
+        }
+    }
+}
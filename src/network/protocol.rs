@@ -0,0 +1,130 @@
+use super::{
+    codec::{DecodeError, Reader, Writer},
+    transport::MAX_PACKET_SIZE,
+};
+
+/// First bytes of every packet, packets from other applications or incompatible versions are
+/// ignored
+const PROTOCOL_ID: u32 = u32::from_le_bytes(*b"vav0");
+
+/// Message sent between the server and clients, a packet contains one or more messages
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Message {
+    /// Client wants to join, or is still waiting for the first snapshot
+    Connect,
+    /// Client is still connected
+    Heartbeat,
+    /// Client is leaving
+    Disconnect,
+    /// Replicated entity was spawned on the server
+    Spawn { entity: u64 },
+    /// Replicated entity was despawned on the server, or lost its `Replicated` marker
+    Despawn { entity: u64 },
+    /// Value of a replicated component
+    Component {
+        entity: u64,
+        component: u64,
+        data: Vec<u8>,
+    },
+}
+
+impl Message {
+    fn encode(&self, writer: &mut Writer) {
+        match self {
+            Self::Connect => writer.u8(0),
+            Self::Heartbeat => writer.u8(1),
+            Self::Disconnect => writer.u8(2),
+            Self::Spawn { entity } => {
+                writer.u8(3);
+                writer.u64(*entity);
+            }
+            Self::Despawn { entity } => {
+                writer.u8(4);
+                writer.u64(*entity);
+            }
+            Self::Component {
+                entity,
+                component,
+                data,
+            } => {
+                writer.u8(5);
+                writer.u64(*entity);
+                writer.u64(*component);
+                writer.bytes(data);
+            }
+        }
+    }
+
+    fn decode(reader: &mut Reader) -> Result<Self, DecodeError> {
+        Ok(match reader.u8()? {
+            0 => Self::Connect,
+            1 => Self::Heartbeat,
+            2 => Self::Disconnect,
+            3 => Self::Spawn {
+                entity: reader.u64()?,
+            },
+            4 => Self::Despawn {
+                entity: reader.u64()?,
+            },
+            5 => Self::Component {
+                entity: reader.u64()?,
+                component: reader.u64()?,
+                data: reader.bytes()?.to_vec(),
+            },
+            _ => return Err(DecodeError::InvalidPacket),
+        })
+    }
+}
+
+/// Splits messages into packets of at most [`MAX_PACKET_SIZE`] bytes. A single message larger
+/// than that gets its own packet.
+#[derive(Default)]
+pub(crate) struct PacketWriter {
+    packets: Vec<Vec<u8>>,
+    current: Writer,
+}
+
+impl PacketWriter {
+    pub fn push(&mut self, message: &Message) {
+        let mut writer = Writer::default();
+        message.encode(&mut writer);
+
+        if !self.current.bytes.is_empty()
+            && self.current.bytes.len() + writer.bytes.len() > MAX_PACKET_SIZE
+        {
+            self.flush();
+        }
+
+        if self.current.bytes.is_empty() {
+            self.current.u32(PROTOCOL_ID);
+        }
+        self.current.bytes.extend_from_slice(&writer.bytes);
+    }
+
+    fn flush(&mut self) {
+        let packet = std::mem::take(&mut self.current.bytes);
+        if !packet.is_empty() {
+            self.packets.push(packet);
+        }
+    }
+
+    /// Returns all written packets
+    pub fn finish(mut self) -> Vec<Vec<u8>> {
+        self.flush();
+        self.packets
+    }
+}
+
+/// Decode all messages in `packet`
+pub(crate) fn read_packet(packet: &[u8]) -> Result<Vec<Message>, DecodeError> {
+    let mut reader = Reader::new(packet);
+    if reader.u32()? != PROTOCOL_ID {
+        return Err(DecodeError::InvalidPacket);
+    }
+
+    let mut messages = Vec::new();
+    while !reader.is_empty() {
+        messages.push(Message::decode(&mut reader)?);
+    }
+    Ok(messages)
+}
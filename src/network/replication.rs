@@ -0,0 +1,530 @@
+use std::{
+    any::type_name,
+    collections::{HashMap, HashSet},
+};
+
+use web_time::Instant;
+
+use crate::{
+    event::EventWriter,
+    macros::{Component, Reflect, Resource},
+    prelude::*,
+};
+
+use super::{
+    NetworkEvent, NetworkSettings,
+    codec::{self, Reader, Writer},
+    protocol::{Message, PacketWriter, read_packet},
+    transport::{PeerId, Transport},
+};
+
+/// Marks an entity on the server to be replicated to clients, together with its replicated
+/// components. Removing the marker despawns the entity on the clients.
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct Replicated;
+
+/// Added to entities spawned by replication on the client, holds the entity's id on the server
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NetworkEntity(pub u64);
+
+/// Network ids of the replicated component types
+#[derive(Resource, Default)]
+pub(crate) struct ReplicationRegistry {
+    components: HashMap<u64, &'static str>,
+}
+
+/// Returns the network id of component `C`, a hash of its type path which is the same for every
+/// build of the app
+fn component_id<C: 'static>() -> u64 {
+    // FNV-1a
+    type_name::<C>()
+        .bytes()
+        .fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        })
+}
+
+/// Register component `C` for replication, see [`App::replicate`]
+pub(crate) fn register_replicated<C: Component + Reflect + Default>(app: &mut App) {
+    let id = component_id::<C>();
+    let name = type_name::<C>();
+
+    app.init_resource::<ReplicationRegistry>();
+    let existing = app
+        .world
+        .resources
+        .get_mut::<ReplicationRegistry>()
+        .components
+        .insert(id, name);
+    if let Some(existing) = existing {
+        assert_eq!(existing, name, "Replicated component id collision");
+        return;
+    }
+
+    app.register_type::<C>()
+        .register_system(
+            server_collect_component_system::<C>.run_if(resource_exists::<NetworkServer>),
+            phase::Last,
+        )
+        .register_system(
+            client_apply_component_system::<C>.run_if(resource_exists::<NetworkClient>),
+            phase::PreUpdate,
+        );
+}
+
+/// Client connected to the server
+struct Peer {
+    last_seen: Instant,
+    /// Whether the next packets should be a full snapshot instead of changes
+    needs_snapshot: bool,
+}
+
+/// Messages collected during the frame, sent in [`server_send_system`]
+#[derive(Default)]
+struct Outbox {
+    spawned: HashSet<u64>,
+    despawned: Vec<u64>,
+    changed: Vec<Message>,
+    snapshot: Vec<Message>,
+}
+
+/// Server side of the replication, added by the [`NetworkPlugin`](super::NetworkPlugin)
+#[derive(Resource)]
+pub struct NetworkServer {
+    transport: Box<dyn Transport>,
+    settings: NetworkSettings,
+    peers: HashMap<PeerId, Peer>,
+    /// Replicated entities as of the last [`server_collect_entities_system`]
+    entities: HashSet<u64>,
+    outbox: Outbox,
+    /// Whether a snapshot has to be collected this frame
+    snapshot_due: bool,
+    frame: u32,
+}
+
+impl NetworkServer {
+    pub(crate) fn new(transport: Box<dyn Transport>, settings: NetworkSettings) -> Self {
+        Self {
+            transport,
+            settings,
+            peers: HashMap::new(),
+            entities: HashSet::new(),
+            outbox: Outbox::default(),
+            snapshot_due: false,
+            frame: 0,
+        }
+    }
+
+    /// Returns the connected clients
+    pub fn peers(&self) -> impl Iterator<Item = PeerId> + '_ {
+        self.peers.keys().copied()
+    }
+
+    /// Returns the number of connected clients
+    #[inline]
+    pub fn peer_count(&self) -> usize {
+        self.peers.len()
+    }
+
+    /// Send a full snapshot to every client next frame
+    #[inline]
+    pub fn request_snapshot(&mut self) {
+        self.peers
+            .values_mut()
+            .for_each(|peer| peer.needs_snapshot = true);
+    }
+
+    fn send(&mut self, peer: PeerId, packets: &[Vec<u8>]) {
+        for packet in packets {
+            if let Err(error) = self.transport.send(peer, packet) {
+                tracing::warn!("Failed to send packet to {:?}: {}", peer, error);
+                return;
+            }
+        }
+    }
+}
+
+/// Client side of the replication, added by the [`NetworkPlugin`](super::NetworkPlugin)
+#[derive(Resource)]
+pub struct NetworkClient {
+    transport: Box<dyn Transport>,
+    settings: NetworkSettings,
+    connected: bool,
+    last_sent: Option<Instant>,
+    last_received: Option<Instant>,
+    /// Local entity of every replicated server entity
+    entities: HashMap<u64, EntityId>,
+    /// Received component values by component id, applied in [`client_apply_component_system`]
+    inbox: HashMap<u64, Vec<(EntityId, Vec<u8>)>>,
+}
+
+impl NetworkClient {
+    pub(crate) fn new(transport: Box<dyn Transport>, settings: NetworkSettings) -> Self {
+        Self {
+            transport,
+            settings,
+            connected: false,
+            last_sent: None,
+            last_received: None,
+            entities: HashMap::new(),
+            inbox: HashMap::new(),
+        }
+    }
+
+    /// Returns true once the first packet from the server arrived, until it times out
+    #[inline]
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    /// Returns the local entity replicating the server's `entity`
+    #[inline]
+    pub fn local_entity(&self, entity: NetworkEntity) -> Option<EntityId> {
+        self.entities.get(&entity.0).copied()
+    }
+
+    fn send(&mut self, message: Message) {
+        let mut writer = PacketWriter::default();
+        writer.push(&message);
+
+        for packet in writer.finish() {
+            if let Err(error) = self.transport.send(PeerId::SERVER, &packet) {
+                tracing::warn!("Failed to send packet to the server: {}", error);
+            }
+        }
+        self.last_sent = Some(Instant::now());
+    }
+
+    /// Tell the server the client is leaving
+    pub(crate) fn disconnect(&mut self) {
+        if self.connected {
+            self.send(Message::Disconnect);
+            self.connected = false;
+        }
+    }
+
+    fn local_entity_or_spawn(&mut self, entity: u64, commands: &mut Commands) -> EntityId {
+        *self.entities.entry(entity).or_insert_with(|| {
+            commands
+                .spawn_empty()
+                .insert(NetworkEntity(entity))
+                .entity_id()
+        })
+    }
+}
+
+/// Receive client messages, track connections and decide if a snapshot is due
+pub(crate) fn server_receive_system(
+    mut server: ResMut<NetworkServer>,
+    mut events: EventWriter<NetworkEvent>,
+) {
+    let server = &mut *server;
+    let now = Instant::now();
+
+    loop {
+        let (peer, packet) = match server.transport.receive() {
+            Ok(Some(received)) => received,
+            Ok(None) => break,
+            Err(error) => {
+                tracing::warn!("Failed to receive packets: {}", error);
+                break;
+            }
+        };
+
+        let messages = match read_packet(&packet) {
+            Ok(messages) => messages,
+            Err(error) => {
+                tracing::debug!("Ignoring packet from {:?}: {}", peer, error);
+                continue;
+            }
+        };
+
+        for message in messages {
+            match message {
+                Message::Connect => {
+                    let state = server.peers.entry(peer).or_insert_with(|| {
+                        tracing::info!("Client {:?} connected", peer);
+                        events.write(NetworkEvent::Connected(peer));
+                        Peer {
+                            last_seen: now,
+                            needs_snapshot: true,
+                        }
+                    });
+
+                    // the client didn't receive a snapshot yet
+                    state.needs_snapshot = true;
+                }
+                Message::Disconnect if server.peers.remove(&peer).is_some() => {
+                    tracing::info!("Client {:?} disconnected", peer);
+                    events.write(NetworkEvent::Disconnected(peer));
+                }
+                _ => {}
+            }
+
+            if let Some(state) = server.peers.get_mut(&peer) {
+                state.last_seen = now;
+            }
+        }
+    }
+
+    let timeout = server.settings.timeout;
+    server.peers.retain(|peer, state| {
+        let alive = now.duration_since(state.last_seen) < timeout;
+        if !alive {
+            tracing::info!("Client {:?} timed out", peer);
+            events.write(NetworkEvent::Disconnected(*peer));
+        }
+        alive
+    });
+
+    server.frame = server.frame.wrapping_add(1);
+    let interval = server.settings.snapshot_interval;
+    if interval > 0 && server.frame.is_multiple_of(interval) {
+        server.request_snapshot();
+    }
+    server.snapshot_due = server.peers.values().any(|peer| peer.needs_snapshot);
+}
+
+/// Find spawned and despawned replicated entities
+pub(crate) fn server_collect_entities_system(
+    mut server: ResMut<NetworkServer>,
+    mut query: Query<EntityId, With<Replicated>>,
+) {
+    let server = &mut *server;
+    let entities: HashSet<u64> = query
+        .iter_mut()
+        .into_iter()
+        .map(EntityId::to_bits)
+        .collect();
+
+    server.outbox.spawned = entities.difference(&server.entities).copied().collect();
+    server.outbox.despawned = server.entities.difference(&entities).copied().collect();
+    server.entities = entities;
+}
+
+/// Encode changed `C` components, and all of them if a snapshot is due
+fn server_collect_component_system<C: Component + Reflect>(
+    mut server: ResMut<NetworkServer>,
+    mut changed: Query<(EntityId, &C), Changed<C>>,
+) {
+    let server = &mut *server;
+    let component = component_id::<C>();
+    let message = |entity: EntityId, value: &C| {
+        let mut writer = Writer::default();
+        codec::encode(value, &mut writer);
+        Message::Component {
+            entity: entity.to_bits(),
+            component,
+            data: writer.bytes,
+        }
+    };
+
+    // `entities` holds the replicated entities, so the queries don't need a `With` filter
+    let spawned = &server.outbox.spawned;
+    for (entity, value) in changed.iter_mut() {
+        let bits = entity.to_bits();
+        // spawned entities are sent below
+        if server.entities.contains(&bits) && !spawned.contains(&bits) {
+            server.outbox.changed.push(message(entity, value));
+        }
+    }
+
+    if !server.snapshot_due && spawned.is_empty() {
+        return;
+    }
+
+    let mut all = changed.cast::<(EntityId, &C), ()>();
+    for (entity, value) in all.iter_mut() {
+        if !server.entities.contains(&entity.to_bits()) {
+            continue;
+        }
+        if spawned.contains(&entity.to_bits()) {
+            server.outbox.changed.push(message(entity, value));
+        }
+        if server.snapshot_due {
+            server.outbox.snapshot.push(message(entity, value));
+        }
+    }
+}
+
+/// Send the collected changes, or a snapshot to clients which need one
+pub(crate) fn server_send_system(mut server: ResMut<NetworkServer>) {
+    let server = &mut *server;
+    let outbox = std::mem::take(&mut server.outbox);
+    server.snapshot_due = false;
+
+    if server.peers.is_empty() {
+        return;
+    }
+
+    // spawns first and despawns last, so components are never applied to missing entities
+    let mut changes = PacketWriter::default();
+    for &entity in &outbox.spawned {
+        changes.push(&Message::Spawn { entity });
+    }
+    for message in &outbox.changed {
+        changes.push(message);
+    }
+    for &entity in &outbox.despawned {
+        changes.push(&Message::Despawn { entity });
+    }
+    let changes = changes.finish();
+
+    let snapshot = if server.peers.values().any(|peer| peer.needs_snapshot) {
+        let mut snapshot = PacketWriter::default();
+        for &entity in &server.entities {
+            snapshot.push(&Message::Spawn { entity });
+        }
+        for message in &outbox.snapshot {
+            snapshot.push(message);
+        }
+        for &entity in &outbox.despawned {
+            snapshot.push(&Message::Despawn { entity });
+        }
+        snapshot.finish()
+    } else {
+        Vec::new()
+    };
+
+    let peers: Vec<_> = server
+        .peers
+        .iter_mut()
+        .map(|(&id, peer)| (id, std::mem::take(&mut peer.needs_snapshot)))
+        .collect();
+
+    for (peer, needs_snapshot) in peers {
+        let packets = if needs_snapshot { &snapshot } else { &changes };
+        server.send(peer, packets);
+    }
+}
+
+/// Keep the connection alive, receive server messages and spawn or despawn replicated entities
+pub(crate) fn client_receive_system(
+    mut client: ResMut<NetworkClient>,
+    mut commands: Commands,
+    mut events: EventWriter<NetworkEvent>,
+    registry: Res<ReplicationRegistry>,
+) {
+    let client = &mut *client;
+    let now = Instant::now();
+
+    let heartbeat_due = client
+        .last_sent
+        .is_none_or(|sent| now.duration_since(sent) >= client.settings.heartbeat_interval);
+    if heartbeat_due {
+        // keep asking for a snapshot until the server answers
+        let message = match client.connected {
+            true => Message::Heartbeat,
+            false => Message::Connect,
+        };
+        client.send(message);
+    }
+
+    loop {
+        let (peer, packet) = match client.transport.receive() {
+            Ok(Some(received)) => received,
+            Ok(None) => break,
+            Err(error) => {
+                tracing::warn!("Failed to receive packets: {}", error);
+                break;
+            }
+        };
+
+        if peer != PeerId::SERVER {
+            continue;
+        }
+
+        let messages = match read_packet(&packet) {
+            Ok(messages) => messages,
+            Err(error) => {
+                tracing::debug!("Ignoring packet from the server: {}", error);
+                continue;
+            }
+        };
+
+        client.last_received = Some(now);
+        if !client.connected {
+            client.connected = true;
+            tracing::info!("Connected to the server");
+            events.write(NetworkEvent::Connected(PeerId::SERVER));
+        }
+
+        for message in messages {
+            match message {
+                Message::Spawn { entity } => {
+                    client.local_entity_or_spawn(entity, &mut commands);
+                }
+                Message::Despawn { entity } => {
+                    if let Some(local) = client.entities.remove(&entity) {
+                        for updates in client.inbox.values_mut() {
+                            updates.retain(|(id, _)| *id != local);
+                        }
+                        commands.entity(local).despawn();
+                    }
+                }
+                Message::Component {
+                    entity,
+                    component,
+                    data,
+                } => {
+                    if !registry.components.contains_key(&component) {
+                        tracing::debug!("Ignoring unregistered component {}", component);
+                        continue;
+                    }
+
+                    let local = client.local_entity_or_spawn(entity, &mut commands);
+                    client
+                        .inbox
+                        .entry(component)
+                        .or_default()
+                        .push((local, data));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let timed_out = client
+        .last_received
+        .is_some_and(|received| now.duration_since(received) >= client.settings.timeout);
+    if client.connected && timed_out {
+        client.connected = false;
+        tracing::info!("Connection to the server timed out");
+        events.write(NetworkEvent::Disconnected(PeerId::SERVER));
+    }
+}
+
+/// Apply received `C` values, inserting the component if the entity doesn't have it yet
+fn client_apply_component_system<C: Component + Reflect + Default>(
+    mut client: ResMut<NetworkClient>,
+    mut query: Query<&mut C>,
+    mut commands: Commands,
+) {
+    let Some(updates) = client.inbox.remove(&component_id::<C>()) else {
+        return;
+    };
+
+    for (entity, data) in updates {
+        let mut reader = Reader::new(&data);
+
+        let result = match query.get(entity) {
+            Some(value) => codec::decode(value, &mut reader),
+            None => {
+                let mut value = C::default();
+                let result = codec::decode(&mut value, &mut reader);
+                if result.is_ok() {
+                    commands.entity(entity).insert(value);
+                }
+                result
+            }
+        };
+
+        if let Err(error) = result {
+            tracing::warn!(
+                "Failed to apply replicated '{}': {}",
+                type_name::<C>(),
+                error
+            );
+        }
+    }
+}
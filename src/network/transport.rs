@@ -0,0 +1,127 @@
+use std::io;
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    collections::HashMap,
+    net::{SocketAddr, ToSocketAddrs, UdpSocket},
+};
+
+/// Identifies the other side of a connection. On the client, the server is always
+/// [`PeerId::SERVER`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PeerId(pub u64);
+
+impl PeerId {
+    /// Id of the server, as seen by the client
+    pub const SERVER: PeerId = PeerId(0);
+}
+
+/// Unreliable, unordered packet transport used by the
+/// [`NetworkPlugin`](super::NetworkPlugin). Packets may be dropped, duplicated or reordered, the
+/// replication protocol recovers through periodic snapshots.
+///
+/// Implement this trait to use a different protocol, e.g. a WebSocket or a relay service.
+pub trait Transport: Send + Sync + 'static {
+    /// Send a single packet to `peer`. Packets are at most [`MAX_PACKET_SIZE`] bytes long.
+    fn send(&mut self, peer: PeerId, packet: &[u8]) -> io::Result<()>;
+
+    /// Returns the next received packet and its sender, or None if there are no more packets
+    /// this frame. Must not block.
+    fn receive(&mut self) -> io::Result<Option<(PeerId, Vec<u8>)>>;
+}
+
+/// Maximum size of a single packet in bytes, chosen to fit into a typical MTU
+pub const MAX_PACKET_SIZE: usize = 1200;
+
+/// [`Transport`] over a non-blocking UDP socket.
+///
+/// # Usage
+/// ```ignore
+/// let server = UdpTransport::server("0.0.0.0:5000")?;
+/// let client = UdpTransport::client("127.0.0.1:5000")?;
+/// ```
+#[cfg(not(target_arch = "wasm32"))]
+pub struct UdpTransport {
+    socket: UdpSocket,
+    /// Peer ids of known addresses, unused on the client
+    peers: HashMap<SocketAddr, PeerId>,
+    /// Addresses of known peers, indexed by `PeerId - 1`
+    addresses: Vec<SocketAddr>,
+    is_client: bool,
+    buffer: Vec<u8>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl UdpTransport {
+    fn new(socket: UdpSocket, is_client: bool) -> io::Result<Self> {
+        socket.set_nonblocking(true)?;
+
+        Ok(Self {
+            socket,
+            peers: HashMap::new(),
+            addresses: Vec::new(),
+            is_client,
+            buffer: vec![0; u16::MAX as usize],
+        })
+    }
+
+    /// Bind a server socket to `address`, every address sending packets to it becomes a peer
+    pub fn server(address: impl ToSocketAddrs) -> io::Result<Self> {
+        Self::new(UdpSocket::bind(address)?, false)
+    }
+
+    /// Bind a client socket to a random local port and connect it to the server at `address`
+    pub fn client(address: impl ToSocketAddrs) -> io::Result<Self> {
+        let socket = UdpSocket::bind(("0.0.0.0", 0))?;
+        socket.connect(address)?;
+        Self::new(socket, true)
+    }
+
+    /// Returns the local address of the socket
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    fn peer_id(&mut self, address: SocketAddr) -> PeerId {
+        *self.peers.entry(address).or_insert_with(|| {
+            self.addresses.push(address);
+            PeerId(self.addresses.len() as u64)
+        })
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Transport for UdpTransport {
+    fn send(&mut self, peer: PeerId, packet: &[u8]) -> io::Result<()> {
+        if self.is_client {
+            self.socket.send(packet)?;
+            return Ok(());
+        }
+
+        let address = peer
+            .0
+            .checked_sub(1)
+            .and_then(|index| self.addresses.get(index as usize))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Unknown peer"))?;
+        self.socket.send_to(packet, address)?;
+        Ok(())
+    }
+
+    fn receive(&mut self) -> io::Result<Option<(PeerId, Vec<u8>)>> {
+        loop {
+            let (len, peer) = match self.socket.recv_from(&mut self.buffer) {
+                Ok((len, _)) if self.is_client => (len, PeerId::SERVER),
+                Ok((len, address)) => (len, self.peer_id(address)),
+                Err(error) => match error.kind() {
+                    io::ErrorKind::WouldBlock => return Ok(None),
+                    // ICMP port unreachable from a previous send, e.g. the server isn't running
+                    // yet or a client left, the socket itself is fine
+                    io::ErrorKind::ConnectionRefused | io::ErrorKind::ConnectionReset => continue,
+                    _ => return Err(error),
+                },
+            };
+
+            return Ok(Some((peer, self.buffer[..len].to_vec())));
+        }
+    }
+}
@@ -0,0 +1,184 @@
+//! # Persistence
+//! Values which are kept between runs of the app, enabled with the `persistence` feature.
+//!
+//! ## Usage
+//!
+//! Add a [`PersistentPlugin`] for each value, it's loaded when the plugin is built and inserted as
+//! a [`Persistent<T>`] resource. If nothing was saved yet or the saved value can't be read, the
+//! default value is used. The value derives the traits of [`serde`], which is re-exported as
+//! `vavo::serde`.
+//! ```ignore
+//! #[derive(Reflect, Serialize, Deserialize, Default)]
+//! struct Settings {
+//!     fullscreen: bool,
+//!     volume: f32,
+//!     jump_key: String,
+//! }
+//!
+//! app.add_plugin(PersistentPlugin::<Settings>::new("my_game", "settings"))
+//!     .register_system(toggle_fullscreen, phase::Update);
+//!
+//! fn toggle_fullscreen(input: Res<Input<KeyCode>>, mut settings: ResMut<Persistent<Settings>>) {
+//!     if input.just_pressed(KeyCode::F11) {
+//!         settings.fullscreen = !settings.fullscreen;
+//!     }
+//! }
+//! ```
+//!
+//! Changed values are saved at the end of the frame, at most once every [`SAVE_INTERVAL`], and
+//! when the app exits. Values are stored as [RON](https://docs.rs/ron) in the user config
+//! directory, e.g. `~/.config/my_game/settings.ron` on Linux. On the web they are stored in the
+//! browser's local storage.
+
+mod storage;
+
+pub mod prelude {
+    pub use super::{Persistent, PersistentPlugin};
+}
+
+use std::{
+    io,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+};
+
+use serde::{Serialize, de::DeserializeOwned};
+use web_time::{Duration, Instant};
+
+use crate::{prelude::*, system::phase};
+
+/// Minimum time between two saves of a changed value
+pub const SAVE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Resource holding a value which is loaded at startup and saved when it changes, added by the
+/// [`PersistentPlugin`]. Derefs to the value.
+#[derive(Resource)]
+pub struct Persistent<T: Reflect + Serialize + DeserializeOwned + Default> {
+    value: T,
+    app: String,
+    name: String,
+    /// Changed since the last save
+    dirty: bool,
+    last_save: Instant,
+}
+
+impl<T: Reflect + Serialize + DeserializeOwned + Default> Persistent<T> {
+    /// Load value `name` of app `app`, or use the default value if it wasn't saved yet or can't
+    /// be read
+    pub fn load(app: impl Into<String>, name: impl Into<String>) -> Self {
+        let app = app.into();
+        let name = name.into();
+
+        let value = match storage::read(&app, &name) {
+            Ok(Some(contents)) => ron::from_str(&contents).unwrap_or_else(|err| {
+                tracing::error!("Failed to parse persistent value '{name}', using default: {err}");
+                T::default()
+            }),
+            Ok(None) => T::default(),
+            Err(err) => {
+                tracing::error!("Failed to read persistent value '{name}', using default: {err}");
+                T::default()
+            }
+        };
+
+        Self {
+            value,
+            app,
+            name,
+            dirty: false,
+            last_save: Instant::now(),
+        }
+    }
+
+    /// Save the value now, instead of waiting for the next automatic save
+    pub fn save(&mut self) -> io::Result<()> {
+        let contents = ron::ser::to_string_pretty(&self.value, ron::ser::PrettyConfig::default())
+            .map_err(io::Error::other)?;
+        storage::write(&self.app, &self.name, &contents)?;
+
+        self.dirty = false;
+        self.last_save = Instant::now();
+        Ok(())
+    }
+
+    /// Returns the name the value is saved under
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Save the value and log errors
+    fn try_save(&mut self) {
+        if let Err(err) = self.save() {
+            tracing::error!("Failed to save persistent value '{}': {err}", self.name);
+            // don't retry every frame
+            self.last_save = Instant::now();
+        }
+    }
+}
+
+impl<T: Reflect + Serialize + DeserializeOwned + Default> Deref for Persistent<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<T: Reflect + Serialize + DeserializeOwned + Default> DerefMut for Persistent<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
+
+/// System which saves [`Persistent<T>`] if it has changed
+fn save_persistent_system<T: Reflect + Serialize + DeserializeOwned + Default>(
+    mut persistent: ResMut<Persistent<T>>,
+) {
+    let changed = persistent.has_changed();
+    // saving must not count as a change, or the value would be saved every interval
+    let persistent = persistent.deref_mut_no_change();
+    persistent.dirty |= changed;
+
+    if persistent.dirty && persistent.last_save.elapsed() >= SAVE_INTERVAL {
+        persistent.try_save();
+    }
+}
+
+/// Adds the [`Persistent<T>`] resource, loaded from value `name` of app `app`, and saves it when
+/// it changes or the app exits
+pub struct PersistentPlugin<T> {
+    app: String,
+    name: String,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> PersistentPlugin<T> {
+    /// Create the plugin for value `name`. `app` is the directory in the user config directory,
+    /// it should be the same for every value of the app.
+    pub fn new(app: impl Into<String>, name: impl Into<String>) -> Self {
+        Self {
+            app: app.into(),
+            name: name.into(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Reflect + Serialize + DeserializeOwned + Default> Plugin for PersistentPlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.world
+            .resources
+            .insert(Persistent::<T>::load(&self.app, &self.name));
+        app.register_system(save_persistent_system::<T>, phase::Last);
+    }
+
+    fn cleanup(&self, app: &mut App) {
+        if let Some(mut persistent) = app.world.resources.try_get_mut::<Persistent<T>>() {
+            let persistent = persistent.deref_mut_no_change();
+            if persistent.dirty {
+                persistent.try_save();
+            }
+        }
+    }
+}
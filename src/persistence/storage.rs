@@ -0,0 +1,82 @@
+//! Storage of persistent values, files in the user config directory on native targets and local
+//! storage on the web.
+
+use std::io;
+
+/// Returns the saved contents of value `name`, or `None` if it wasn't saved yet
+#[cfg(not(target_arch = "wasm32"))]
+pub(super) fn read(app: &str, name: &str) -> io::Result<Option<String>> {
+    match std::fs::read_to_string(native::path(app, name)?) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Save `contents` of value `name`
+#[cfg(not(target_arch = "wasm32"))]
+pub(super) fn write(app: &str, name: &str, contents: &str) -> io::Result<()> {
+    let path = native::path(app, name)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    // write to a temporary file first, so an interrupted save doesn't corrupt the old value
+    let temp = path.with_extension("ron.tmp");
+    std::fs::write(&temp, contents)?;
+    std::fs::rename(temp, path)
+}
+
+/// Returns the saved contents of value `name`, or `None` if it wasn't saved yet
+#[cfg(target_arch = "wasm32")]
+pub(super) fn read(app: &str, name: &str) -> io::Result<Option<String>> {
+    web::storage()?
+        .get_item(&web::key(app, name))
+        .map_err(web::error)
+}
+
+/// Save `contents` of value `name`
+#[cfg(target_arch = "wasm32")]
+pub(super) fn write(app: &str, name: &str, contents: &str) -> io::Result<()> {
+    web::storage()?
+        .set_item(&web::key(app, name), contents)
+        .map_err(web::error)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use std::{io, path::PathBuf};
+
+    /// Returns the file of value `name`
+    pub(super) fn path(app: &str, name: &str) -> io::Result<PathBuf> {
+        let config = dirs::config_dir().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "No user config directory found")
+        })?;
+        Ok(config.join(app).join(format!("{name}.ron")))
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod web {
+    use std::io;
+
+    use wasm_bindgen::JsValue;
+
+    /// Returns the key of value `name` in local storage
+    pub(super) fn key(app: &str, name: &str) -> String {
+        format!("{app}/{name}")
+    }
+
+    /// Returns the local storage of the page
+    pub(super) fn storage() -> io::Result<web_sys::Storage> {
+        web_sys::window()
+            .ok_or_else(|| io::Error::other("No window found"))?
+            .local_storage()
+            .map_err(error)?
+            .ok_or_else(|| io::Error::other("Local storage is not available"))
+    }
+
+    pub(super) fn error(err: JsValue) -> io::Error {
+        io::Error::other(format!("{err:?}"))
+    }
+}
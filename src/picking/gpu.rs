@@ -0,0 +1,433 @@
+use std::sync::{Arc, Mutex};
+
+use glam::Vec2;
+use winit::dpi::PhysicalSize;
+
+use crate::{
+    assets::ShaderLoader,
+    core::graph::*,
+    prelude::*,
+    render_assets::{BindGroup, Buffer, Pipeline, RenderAssets, pipeline::PipelineBuilder},
+    renderer::{
+        culling::{Visibility, VisibilityCameras},
+        newtype::{RenderCommandEncoder, RenderDevice},
+    },
+};
+
+use super::{PickHit, PickingMode, PickingSettings, PickingState};
+
+const ID_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R32Uint;
+const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+/// Push constant of a single drawn entity
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct PickingPushConstant {
+    model: [[f32; 4]; 4],
+    /// Index into the drawn entities, offset by one since 0 is the cleared background
+    id: u32,
+    _padding: [u32; 3],
+}
+
+/// Offscreen targets of the picking node, sized to the window
+struct PickingTargets {
+    size: PhysicalSize<u32>,
+    id_texture: wgpu::Texture,
+    id_view: wgpu::TextureView,
+    depth_texture: wgpu::Texture,
+    depth_view: wgpu::TextureView,
+    /// Readback buffer, holds the id at offset 0 and the depth at offset 4
+    buffer: wgpu::Buffer,
+}
+
+impl PickingTargets {
+    fn new(size: PhysicalSize<u32>, device: &RenderDevice) -> Self {
+        let create_texture = |label: &str, format: wgpu::TextureFormat| {
+            device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(label),
+                size: wgpu::Extent3d {
+                    width: size.width,
+                    height: size.height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+                view_formats: &[],
+            })
+        };
+
+        let id_texture = create_texture("picking_id_texture", ID_FORMAT);
+        let depth_texture = create_texture("picking_depth_texture", DEPTH_FORMAT);
+
+        Self {
+            size,
+            id_view: id_texture.create_view(&Default::default()),
+            id_texture,
+            depth_view: depth_texture.create_view(&Default::default()),
+            depth_texture,
+            buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("picking_readback_buffer"),
+                size: 8,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            }),
+        }
+    }
+}
+
+/// Render time data needed to resolve the read back pixel
+struct PendingPick {
+    /// Entities in draw order, the rendered id is the index offset by one
+    entities: Vec<EntityId>,
+    inverse_view_projection: Mat4,
+    /// Cursor position in normalized device coordinates
+    ndc: Vec2,
+}
+
+impl PendingPick {
+    /// Returns the hit for the read back `id` and `depth`, `None` if the background was hit
+    fn resolve(&self, id: u32, depth: f32) -> Option<PickHit> {
+        let entity = *self.entities.get(id.checked_sub(1)? as usize)?;
+
+        let unproject = |z: f32| {
+            self.inverse_view_projection
+                .project_point3(self.ndc.extend(z))
+        };
+        let origin = unproject(0.0);
+        let position = unproject(depth);
+
+        Some(PickHit {
+            entity,
+            distance: origin.distance(position),
+            position,
+        })
+    }
+}
+
+/// State of the asynchronous readback
+#[derive(Default)]
+enum Readback {
+    /// Nothing in flight, a new pick can be rendered
+    #[default]
+    Idle,
+    /// Pixel copy was recorded, the buffer can be mapped once it's submitted
+    Copied(PendingPick),
+    /// Waiting for the buffer to be mapped, the flag is set by the map callback
+    Mapping(PendingPick, Arc<Mutex<Option<bool>>>),
+}
+
+/// GPU picking targets and the readback in flight. Used as a resource.
+#[derive(Default, crate::macros::Resource)]
+pub(crate) struct GpuPicking {
+    targets: Option<PickingTargets>,
+    readback: Readback,
+}
+
+/// Startup system to register the picking graph node
+pub(crate) fn register_gpu_picking_graph(
+    graph: &mut RenderGraph,
+    device: Res<RenderDevice>,
+    mut shader_loader: ResMut<ShaderLoader>,
+) {
+    let pipeline_builder = create_picking_pipeline_builder(&device, &mut shader_loader);
+
+    let node = GraphNodeBuilder::new("picking")
+        .set_pipeline(pipeline_builder)
+        .set_custom_system(gpu_picking_render_system)
+        .run_after("main")
+        .build();
+
+    graph.add(node);
+}
+
+/// Picking graph node rendering system, draws entity ids of the pixel under the cursor and
+/// records its copy into the readback buffer. Only runs with [`PickingMode::Gpu`].
+fn gpu_picking_render_system(
+    graph_ctx: Res<RenderContext>,
+
+    world: &mut World,
+    encoder: &mut RenderCommandEncoder,
+    device: Res<RenderDevice>,
+    settings: Res<PickingSettings>,
+    mut gpu: ResMut<GpuPicking>,
+    mut buffers: ResMut<RenderAssets<Buffer>>,
+    mut bind_groups: ResMut<RenderAssets<BindGroup>>,
+    meshes: Res<Assets<Mesh>>,
+
+    mut query: Query<
+        (
+            EntityId,
+            &Handle<Mesh>,
+            &GlobalTransform,
+            Option<&Visibility>,
+        ),
+        With<Handle<Material>>,
+    >,
+    rendered: Res<RenderedWindow>,
+) {
+    if !settings.enabled || settings.mode != PickingMode::Gpu {
+        return;
+    }
+
+    // wait for the previous readback
+    if !matches!(gpu.readback, Readback::Idle) {
+        return;
+    }
+
+    // the cursor is picked in the window it's over, by the camera under it
+    let Some(cursor) = CursorWindow::find(&mut query.cast()) else {
+        return;
+    };
+    if cursor.window != *rendered {
+        return;
+    }
+    let Some((camera_id, camera, projection, global_transform, viewport)) = query
+        .cast::<(EntityId, &Camera, &Projection, &GlobalTransform), With<Camera3D>>()
+        .iter_mut()
+        .into_iter()
+        .rev()
+        .find_map(|(camera_id, camera, projection, global_transform)| {
+            let viewport = camera.cursor_in_viewport(&cursor)?;
+            Some((camera_id, camera, projection, global_transform, viewport))
+        })
+    else {
+        return;
+    };
+    let view_projection =
+        Mat4::from_cols_array_2d(&projection.get_view_projection_matrix(&global_transform.matrix));
+
+    // recreate targets on resize
+    let size = PhysicalSize::new(cursor.size.x as u32, cursor.size.y as u32);
+    if gpu
+        .targets
+        .as_ref()
+        .is_none_or(|targets| targets.size != size)
+    {
+        gpu.targets = Some(PickingTargets::new(size, &device));
+    }
+    let targets = gpu.targets.as_ref().expect("Picking targets should exist");
+
+    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("picking render pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: &targets.id_view,
+            depth_slice: None,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+            view: &targets.depth_view,
+            depth_ops: Some(wgpu::Operations {
+                load: wgpu::LoadOp::Clear(1.0),
+                store: wgpu::StoreOp::Store,
+            }),
+            stencil_ops: None,
+        }),
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    });
+
+    render_pass.set_pipeline(
+        unsafe { &*graph_ctx.node }
+            .data
+            .pipeline
+            .as_ref()
+            .expect("Pipeline should have been generated by now")
+            .render_pipeline(),
+    );
+    if !camera.set_render_pass_camera(
+        camera_id,
+        &mut render_pass,
+        0,
+        cursor.size,
+        &mut bind_groups,
+        world,
+    ) {
+        return;
+    }
+
+    // only the pixel under the cursor is needed
+    let (x, y) = (cursor.position.x as u32, cursor.position.y as u32);
+    render_pass.set_scissor_rect(x, y, 1, 1);
+
+    // skip entities outside of the picking camera's frustum
+    let camera_index = world
+        .resources
+        .try_get::<VisibilityCameras>()
+        .and_then(|cameras| cameras.index_of(camera_id));
+    let is_visible = |visibility: &Visibility| match camera_index {
+        Some(index) => visibility.is_visible_at(index),
+        None => visibility.is_visible(),
+    };
+
+    let mut entities = Vec::new();
+    for (id, mesh, global_transform, visibility) in query.iter_mut() {
+        if visibility.is_some_and(|v| !is_visible(v)) || meshes.get(mesh).is_none() {
+            continue;
+        }
+
+        let mesh_buffer = buffers.get_by_handle(mesh, world);
+        let Some(vertex_buffer) = mesh_buffer.vertex.as_ref() else {
+            continue;
+        };
+
+        entities.push(id);
+        let push_constant = PickingPushConstant {
+            model: global_transform.matrix.to_cols_array_2d(),
+            id: entities.len() as u32,
+            _padding: [0; 3],
+        };
+
+        render_pass.set_push_constants(
+            wgpu::ShaderStages::VERTEX_FRAGMENT,
+            0,
+            bytemuck::bytes_of(&push_constant),
+        );
+        render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+
+        if let Some(index_buffer) = &mesh_buffer.index {
+            render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+            render_pass.draw_indexed(0..mesh_buffer.num_indices, 0, 0..1);
+        } else {
+            render_pass.draw(0..mesh_buffer.num_vertices, 0..1);
+        }
+    }
+
+    drop(render_pass);
+    graph_ctx.record_draw_calls(entities.len() as u32);
+
+    // copy the pixel under the cursor
+    let mut copy_pixel = |texture: &wgpu::Texture, aspect: wgpu::TextureAspect, offset: u64| {
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x, y, z: 0 },
+                aspect,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &targets.buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset,
+                    bytes_per_row: None,
+                    rows_per_image: None,
+                },
+            },
+            wgpu::Extent3d {
+                width: 1,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+        );
+    };
+    copy_pixel(&targets.id_texture, wgpu::TextureAspect::All, 0);
+    copy_pixel(&targets.depth_texture, wgpu::TextureAspect::DepthOnly, 4);
+
+    // the camera's projection covers its viewport
+    let (viewport_cursor, viewport_size) = viewport;
+    let pixel = (viewport_cursor.floor() + 0.5) / viewport_size;
+    let ndc = Vec2::new(pixel.x * 2.0 - 1.0, 1.0 - pixel.y * 2.0);
+
+    gpu.readback = Readback::Copied(PendingPick {
+        entities,
+        inverse_view_projection: view_projection.inverse(),
+        ndc,
+    });
+}
+
+/// System which maps the readback buffer once the pixel copy was submitted, and resolves the
+/// picked entity when the mapping finishes. Runs in the First phase.
+pub(crate) fn gpu_picking_readback_system(
+    device: Res<RenderDevice>,
+    mut gpu: ResMut<GpuPicking>,
+    mut state: ResMut<PickingState>,
+) {
+    let gpu = &mut *gpu;
+    let Some(targets) = &gpu.targets else {
+        return;
+    };
+
+    // the copy was submitted at the end of the last frame
+    if let Readback::Copied(pending) = std::mem::take(&mut gpu.readback) {
+        let mapped = Arc::new(Mutex::new(None));
+        let callback_mapped = mapped.clone();
+
+        targets
+            .buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                *callback_mapped.lock().unwrap() = Some(result.is_ok());
+            });
+
+        gpu.readback = Readback::Mapping(pending, mapped);
+    }
+
+    let Readback::Mapping(_, mapped) = &gpu.readback else {
+        return;
+    };
+
+    let _ = device.poll(wgpu::PollType::Poll);
+    let Some(success) = *mapped.lock().unwrap() else {
+        return;
+    };
+
+    let Readback::Mapping(pending, _) = std::mem::take(&mut gpu.readback) else {
+        unreachable!("Readback should be mapping");
+    };
+
+    if success {
+        let data = targets.buffer.slice(..).get_mapped_range();
+        let id = u32::from_ne_bytes(data[0..4].try_into().unwrap());
+        let depth = f32::from_ne_bytes(data[4..8].try_into().unwrap());
+        drop(data);
+        targets.buffer.unmap();
+
+        state.gpu_hit = pending.resolve(id, depth);
+    }
+}
+
+fn create_picking_pipeline_builder(
+    device: &RenderDevice,
+    shader_loader: &mut ShaderLoader,
+) -> PipelineBuilder {
+    // Camera bind group layout for uniform buffer
+    let camera_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("camera_bind_group_layout"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+    });
+
+    // Load shader modules
+    shader_loader.load("picking", include_str!("../shaders/picking.wgsl"), device);
+
+    Pipeline::build("picking_pipeline")
+        .set_bind_group_layouts(vec![camera_layout])
+        .set_vertex_buffer_layouts(vec![Mesh::vertex_descriptor()])
+        .set_vertex_shader("picking", "vs_main")
+        .set_fragment_shader("picking", "fs_main")
+        // integer targets can't be blended
+        .add_color_target(Some(wgpu::ColorTargetState {
+            format: ID_FORMAT,
+            blend: None,
+            write_mask: wgpu::ColorWrites::ALL,
+        }))
+        .set_depth_format(DEPTH_FORMAT)
+        .set_push_constant_ranges(vec![wgpu::PushConstantRange {
+            stages: wgpu::ShaderStages::VERTEX_FRAGMENT,
+            range: 0..std::mem::size_of::<PickingPushConstant>() as u32,
+        }])
+}
@@ -0,0 +1,271 @@
+//! # Picking plugin
+//! CPU mouse picking, a ray is cast from the active camera under the cursor every frame and
+//! tested against the [`WorldBoundingVolume`] of every entity.
+//!
+//! Bounding volumes are managed by the [`FrustumCullingPlugin`](crate::renderer::culling::FrustumCullingPlugin),
+//! so it has to be enabled for entities to be pickable. With [`PickingMode::Mesh`], hits are
+//! refined by testing the triangles of the entity's [`Mesh`].
+//!
+//! With [`PickingMode::Gpu`], entity ids of the pixel under the cursor are rendered into an
+//! offscreen target in the `picking` graph node instead, and read back asynchronously. This is
+//! pixel accurate for any mesh, but hits arrive a frame or two late.
+//!
+//! ## Usage
+//!
+//! - Add the [`PickingPlugin`] to the app, it is not part of the [`DefaultPlugins`].
+//! - Read [`PickHover`], [`PickHoverEnd`] and [`PickClick`] events, or check the currently
+//!   hovered entity in the [`PickingState`] resource. [`PointerOver`] and [`PointerClick`] are
+//!   aliases of the hover and click events.
+//! ```ignore
+//! fn select_system(mut clicks: EventReader<PickClick>) {
+//!     for click in clicks.read() {
+//!         println!("clicked {:?} at {}", click.hit.entity, click.hit.position);
+//!     }
+//! }
+//! ```
+
+mod gpu;
+
+pub mod prelude {
+    pub use super::{
+        PickClick, PickHit, PickHover, PickHoverEnd, PickingMode, PickingPlugin, PickingSettings,
+        PickingState, PointerClick, PointerOver,
+    };
+}
+
+use wgpu::PrimitiveTopology;
+
+use crate::{
+    event::EventWriter, input::InputPlugin, math::bounding_volume::WorldBoundingVolume,
+    plugins::RenderPlugin, prelude::*, renderer::culling::Visibility,
+};
+
+/// Plugin which adds CPU mouse picking. For more information, see the
+/// [picking module](crate::picking).
+pub struct PickingPlugin;
+
+impl Plugin for PickingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PickingSettings>()
+            .init_resource::<PickingState>()
+            .init_resource::<gpu::GpuPicking>()
+            .register_event::<PickHover>()
+            .register_event::<PickHoverEnd>()
+            .register_event::<PickClick>()
+            .add_startup_system(gpu::register_gpu_picking_graph)
+            .register_system(gpu::gpu_picking_readback_system, phase::First)
+            .register_system(picking_system, phase::PreUpdate);
+    }
+
+    fn dependencies(&self) -> Vec<PluginId> {
+        vec![
+            PluginId::of::<RenderPlugin>(),
+            PluginId::of::<InputPlugin>(),
+        ]
+    }
+}
+
+/// Settings used for picking. Used as a resource.
+#[derive(Resource)]
+pub struct PickingSettings {
+    /// Whether to cast picking rays
+    pub enabled: bool,
+    pub mode: PickingMode,
+}
+
+impl Default for PickingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            mode: PickingMode::default(),
+        }
+    }
+}
+
+/// Precision of the picking tests
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PickingMode {
+    /// Only test the world bounding volumes, fast but imprecise
+    #[default]
+    BoundingVolume,
+    /// Test mesh triangles of entities whose bounding volume was hit
+    Mesh,
+    /// Render entity ids on the GPU and read back the pixel under the cursor, pixel accurate
+    /// but delayed. Only entities with a [`Material`] are drawn.
+    Gpu,
+}
+
+/// Result of a successful picking test
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PickHit {
+    pub entity: EntityId,
+    /// Distance from the ray origin on the camera's near plane
+    pub distance: f32,
+    /// World space position of the hit
+    pub position: Vec3,
+}
+
+/// Picking state of the current frame. Used as a resource.
+#[derive(Resource, Default, Debug)]
+pub struct PickingState {
+    ray: Option<Ray>,
+    hovered: Option<PickHit>,
+    /// Latest resolved GPU readback
+    gpu_hit: Option<PickHit>,
+}
+
+impl PickingState {
+    /// Returns the ray cast this frame, `None` if the cursor is outside of the window
+    #[inline]
+    pub fn ray(&self) -> Option<Ray> {
+        self.ray
+    }
+
+    /// Returns the closest hit under the cursor
+    #[inline]
+    pub fn hovered(&self) -> Option<PickHit> {
+        self.hovered
+    }
+}
+
+/// Event sent when the cursor starts hovering over an entity
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PickHover {
+    pub hit: PickHit,
+}
+
+/// Event sent when the cursor stops hovering over an entity
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PickHoverEnd {
+    pub entity: EntityId,
+}
+
+/// Event sent when a mouse button is pressed while hovering over an entity
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PickClick {
+    pub hit: PickHit,
+    pub button: MouseButton,
+}
+
+/// Alias of [`PickHover`], for pointer style event names
+pub type PointerOver = PickHover;
+
+/// Alias of [`PickClick`], for pointer style event names
+pub type PointerClick = PickClick;
+
+/// System which casts the picking ray and sends picking events, runs in the PreUpdate phase.
+pub fn picking_system(
+    settings: Res<PickingSettings>,
+    mut state: ResMut<PickingState>,
+    mouse_input: Res<Input<MouseButton>>,
+    meshes: Res<Assets<Mesh>>,
+    mut hover_events: EventWriter<PickHover>,
+    mut hover_end_events: EventWriter<PickHoverEnd>,
+    mut click_events: EventWriter<PickClick>,
+    mut query: Query<(
+        EntityId,
+        &WorldBoundingVolume,
+        &GlobalTransform,
+        Option<&Visibility>,
+        Option<&Handle<Mesh>>,
+    )>,
+) {
+    // early exit based on settings
+    let ray = if settings.enabled {
+        cursor_ray(query.cast(), query.cast())
+    } else {
+        None
+    };
+
+    let hit = ray.and_then(|ray| {
+        if settings.mode == PickingMode::Gpu {
+            return state.gpu_hit;
+        }
+
+        query
+            .iter_mut()
+            .into_iter()
+            .filter(|(.., visibility, _)| visibility.is_none_or(|v| v.is_visible()))
+            .filter_map(|(id, volume, global_transform, _, mesh)| {
+                let mut distance = ray.intersect_bounding_volume(volume)?;
+
+                // refine with mesh triangles
+                if settings.mode == PickingMode::Mesh
+                    && let Some(mesh) = mesh.and_then(|handle| meshes.get(handle))
+                    && mesh.topology == PrimitiveTopology::TriangleList
+                {
+                    distance = intersect_mesh(&ray, mesh, &global_transform.matrix)?;
+                }
+
+                Some(PickHit {
+                    entity: id,
+                    distance,
+                    position: ray.at(distance),
+                })
+            })
+            .min_by(|a, b| a.distance.total_cmp(&b.distance))
+    });
+
+    // hover events
+    let previous = state.hovered.map(|hit| hit.entity);
+    let current = hit.map(|hit| hit.entity);
+    if previous != current {
+        if let Some(entity) = previous {
+            hover_end_events.write(PickHoverEnd { entity });
+        }
+        if let Some(hit) = hit {
+            hover_events.write(PickHover { hit });
+        }
+    }
+
+    // click events
+    if let Some(hit) = hit {
+        for button in [MouseButton::Left, MouseButton::Right, MouseButton::Middle] {
+            if mouse_input.just_pressed(button) {
+                click_events.write(PickClick { hit, button });
+            }
+        }
+    }
+
+    state.ray = ray;
+    state.hovered = hit;
+}
+
+/// Returns a ray through the cursor from the active camera under it. Cameras drawn later are on
+/// top, so the last one whose viewport contains the cursor is used.
+fn cursor_ray(
+    mut windows: Query<(EntityId, &Window, Option<&PrimaryWindow>)>,
+    mut camera_query: Query<(&Camera, &Projection, &GlobalTransform), With<Camera3D>>,
+) -> Option<Ray> {
+    let cursor = CursorWindow::find(&mut windows)?;
+
+    camera_query
+        .iter_mut()
+        .into_iter()
+        .rev()
+        .find_map(|(camera, projection, global_transform)| {
+            let (position, size) = camera.cursor_in_viewport(&cursor)?;
+            Camera::viewport_to_world(projection, global_transform, size, position)
+        })
+}
+
+/// Returns the world space distance to the closest triangle of a triangle list mesh
+fn intersect_mesh(ray: &Ray, mesh: &Mesh, matrix: &Mat4) -> Option<f32> {
+    let local_ray = ray.transform(&matrix.inverse());
+    let vertex = |i: u32| Vec3::from(mesh.positions[i as usize]);
+
+    let triangles: Box<dyn Iterator<Item = [u32; 3]>> = match &mesh.indices {
+        Some(indices) => Box::new(indices.chunks_exact(3).map(|t| [t[0], t[1], t[2]])),
+        None => {
+            Box::new((0..mesh.positions.len() as u32 / 3).map(|i| [i * 3, i * 3 + 1, i * 3 + 2]))
+        }
+    };
+
+    let local_distance = triangles
+        .filter_map(|[a, b, c]| local_ray.intersect_triangle(vertex(a), vertex(b), vertex(c)))
+        .min_by(|a, b| a.total_cmp(b))?;
+
+    // distances are not preserved by scaling, so measure the hit in world space
+    let position = matrix.transform_point3(local_ray.at(local_distance));
+    Some(ray.origin.distance(position))
+}
@@ -1,45 +1,76 @@
 use std::time::Duration;
 
 use crate::{
-    app::{App, Plugin},
+    app::{App, Plugin, PluginGroup, PluginGroupBuilder, PluginId},
+    assets::{AssetEvent, AssetGroupPlugin, AssetPlugin, scene::PrefabPlugin},
     audio::AudioPlugin,
+    core::graph::Msaa,
     core::standard::{
-        grouped::generate_grouped_instances_system,
+        grouped::{InstanceBatches, generate_grouped_instances_system},
         light_data::prepare_light_data_system,
-        movement::movement_system,
+        shadows::shadow_caster_change_system,
         startup::{add_render_resources, register_standard_graph},
-        update::{update_camera_buffers, update_global_transforms},
+        update::{
+            bake_static_transforms, update_camera_buffers, update_camera_shake_system,
+            update_global_transforms,
+        },
     },
+    ecs::hierarchy::HierarchyPlugin,
     event::plugin::EventPlugin,
     input::InputPlugin,
-    prelude::{FixedTime, FpsCounter, ResMut, Time, on_internval},
+    log::LogPlugin,
+    prelude::{
+        CameraShakeEvent, FixedAlpha, FixedTime, FpsCounter, ResMut, Rng, Time, layer, on_timer,
+    },
     reflect::ReflectionPlugin,
-    renderer::culling::FrustumCullingPlugin,
-    system::{IntoSystem, phase},
+    render_assets::{RenderMemoryStats, StagingBelt, evict_render_assets_system},
+    renderer::{
+        Image, ImageUploadQueue, culling::FrustumCullingPlugin, upload_loaded_images_system,
+    },
+    system::{IntoSystem, PhaseExecutionPolicy, PhaseLabel, phase},
     ui::plugin::UiPlugin,
+    window::apply_window_config_system,
 };
 
-/// Default plugins which are necessary for the app to run, includes:
+/// Group of the default plugins which are necessary for the app to run, includes:
+/// - [`LogPlugin`]
 /// - [`EventPlugin`]
 /// - [`RenderPlugin`]
 /// - [`TimePlugin`]
+/// - [`RngPlugin`]
 /// - [`InputPlugin`]
 /// - [`UiPlugin`]
 /// - [`AudioPlugin`]
 /// - [`ReflectionPlugin`]
 /// - [`FrustumCullingPlugin`]
-pub struct DefaultPlugin;
+/// - [`AssetPlugin`]
+/// - [`PrefabPlugin`]
+/// - [`AssetGroupPlugin`]
+/// - [`HierarchyPlugin`]
+///
+/// Plugins can be disabled or reconfigured through [`PluginGroup::build`]:
+/// ```ignore
+/// app.add_plugins(DefaultPlugins.build().disable::<AudioPlugin>());
+/// ```
+pub struct DefaultPlugins;
 
-impl Plugin for DefaultPlugin {
-    fn build(&self, app: &mut App) {
-        app.add_plugin(EventPlugin)
-            .add_plugin(RenderPlugin)
-            .add_plugin(TimePlugin)
-            .add_plugin(InputPlugin)
-            .add_plugin(UiPlugin)
-            .add_plugin(AudioPlugin)
-            .add_plugin(ReflectionPlugin)
-            .add_plugin(FrustumCullingPlugin);
+impl PluginGroup for DefaultPlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::new()
+            .add(LogPlugin::default())
+            .add(EventPlugin)
+            .add(RenderPlugin)
+            .add(TimePlugin)
+            .add(RngPlugin::default())
+            .add(InputPlugin)
+            .add(UiPlugin)
+            .add(AudioPlugin)
+            .add(ReflectionPlugin)
+            .add(FrustumCullingPlugin)
+            .add(AssetPlugin)
+            .add(PrefabPlugin)
+            .add(AssetGroupPlugin)
+            .add(HierarchyPlugin::default())
     }
 }
 
@@ -51,31 +82,124 @@ pub struct RenderPlugin;
 
 impl Plugin for RenderPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(add_render_resources)
+        app.init_resource::<RenderMemoryStats>()
+            .init_resource::<InstanceBatches>()
+            .init_resource::<Msaa>()
+            .init_resource::<StagingBelt>()
+            .init_resource::<ImageUploadQueue>()
+            .register_event::<CameraShakeEvent>()
+            .register_event::<AssetEvent<Image>>()
+            .add_startup_system(add_render_resources)
             .add_startup_system(register_standard_graph)
             .register_system(update_global_transforms, phase::Last)
+            .register_system(bake_static_transforms, phase::Last.layer(layer::End))
+            .register_system(
+                update_camera_shake_system,
+                phase::PreRender.layer(layer::Pre),
+            )
+            .register_system(
+                upload_loaded_images_system,
+                phase::PreRender.layer(layer::Pre),
+            )
+            .register_system(apply_window_config_system, phase::PostUpdate)
             .register_system(update_camera_buffers, phase::PreRender)
             .register_system(prepare_light_data_system, phase::PreRender)
-            .register_system(generate_grouped_instances_system, phase::PreRender);
+            .register_system(shadow_caster_change_system, phase::PreRender)
+            .register_system(generate_grouped_instances_system, phase::PreRender)
+            .register_system(evict_render_assets_system, phase::FrameEnd);
     }
 }
 
-/// Provides default camera movement functionality, good when no proper movement system is implemented yet.
-pub struct NoclipMovementPlugin;
+/// Adds time functionality to the app via the `Time` and `FixedAlpha` resources.
+pub struct TimePlugin;
 
-impl Plugin for NoclipMovementPlugin {
+impl Plugin for TimePlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(movement_system);
+        app.world.resources.insert(Time::new());
+        app.world.resources.insert(FixedTime::from_hz(60.0));
+        app.world.resources.insert(FixedAlpha::default());
     }
 }
 
-/// Adds time functionality to the app via the `Time` resource.
-pub struct TimePlugin;
+/// Adds the [`Rng`] resource, shared by systems and engine features which need randomness
+#[derive(Default)]
+pub struct RngPlugin {
+    /// Seed of the [`Rng`], or None to seed it from the current time
+    pub seed: Option<u64>,
+}
 
-impl Plugin for TimePlugin {
+impl Plugin for RngPlugin {
     fn build(&self, app: &mut App) {
-        app.world.resources.insert(Time::new());
-        app.world.resources.insert(FixedTime::from_hz(60.0));
+        // keep the Rng of the DeterministicPlugin if it was built first
+        if app.world.resources.contains::<Rng>() {
+            return;
+        }
+
+        let rng = self.seed.map_or_else(Rng::default, Rng::new);
+        app.world.resources.insert(rng);
+    }
+}
+
+/// Makes the simulation reproducible, so replays and lockstep networking give identical results
+/// across runs. It:
+/// - inserts an [`Rng`] resource seeded with `seed`
+/// - runs all systems and render graph nodes on the main thread, in their scheduled order
+/// - advances [`Time`] and [`FixedTime`] by exactly `timestep` every frame, independent of the
+///   real frame time, and runs `FixedUpdate` once per frame
+///
+/// Archetypes are always iterated in the same order, regardless of this plugin.
+///
+/// Only code using the provided resources is deterministic, e.g. reading the system clock or
+/// float differences between platforms are not covered.
+pub struct DeterministicPlugin {
+    /// Seed of the [`Rng`] resource
+    pub seed: u64,
+    /// Time step of a single frame in seconds
+    pub timestep: f32,
+}
+
+impl Default for DeterministicPlugin {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            timestep: 1.0 / 60.0,
+        }
+    }
+}
+
+impl DeterministicPlugin {
+    fn fixed_time(&self) -> FixedTime {
+        let mut fixed_time = FixedTime::new(self.timestep);
+        fixed_time.set_lockstep(true);
+        fixed_time
+    }
+}
+
+impl Plugin for DeterministicPlugin {
+    fn build(&self, app: &mut App) {
+        app.world.resources.insert(Rng::new(self.seed));
+
+        // Safety: the graph isn't executing while plugins are built
+        unsafe { app.render_graph() }.set_single_threaded();
+
+        let scheduler = app.scheduler_mut();
+        scheduler.set_single_threaded();
+        scheduler.pending_changes.policy(
+            phase::FixedUpdate,
+            PhaseExecutionPolicy::FixedTimestep(self.fixed_time()),
+        );
+    }
+
+    fn finish(&self, app: &mut App) {
+        // TimePlugin may be built after this plugin, so configure its resources here
+        if let Some(mut time) = app.world.resources.try_get_mut::<Time>() {
+            time.set_fixed_step(Some(self.timestep));
+        }
+        app.world.resources.insert(self.fixed_time());
+    }
+
+    fn dependencies(&self) -> Vec<PluginId> {
+        vec![PluginId::of::<TimePlugin>()]
     }
 }
 
@@ -83,8 +207,7 @@ impl Plugin for TimePlugin {
 pub struct FpsCounterPlugin {
     /// The capacity of the FPS counter (number of samples to keep)
     pub capacity: usize,
-    /// The interval (in seconds) at which to print the FPS to the console, or None to disable
-    /// printing
+    /// The interval (in seconds) at which to log the FPS, or None to disable logging
     pub interval: Option<f32>,
 }
 
@@ -95,7 +218,7 @@ impl Plugin for FpsCounterPlugin {
 
         if let Some(interval) = self.interval {
             let duration = Duration::from_secs_f32(interval);
-            app.add_system(print_fps_system.run_if(on_internval(duration)));
+            app.add_system(log_fps_system.run_if(on_timer(duration)));
         }
     }
 }
@@ -105,7 +228,7 @@ fn update_fps_counter_system(mut fps_counter: ResMut<FpsCounter>) {
     fps_counter.update();
 }
 
-/// System to print the current FPS to the console
-fn print_fps_system(fps_counter: ResMut<FpsCounter>) {
-    println!("FPS: {:.2}", fps_counter.average_fps());
+/// System to log the current FPS
+fn log_fps_system(fps_counter: ResMut<FpsCounter>) {
+    tracing::info!("FPS: {:.2}", fps_counter.average_fps());
 }
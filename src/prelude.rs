@@ -1,29 +1,71 @@
 pub use super::{
-    app::{App, Plugin},
-    assets::{Asset, AssetLoader, Assets, Handle, Name, Scene, SceneProto, ShaderLoader},
+    app::{App, Plugin, PluginGroup, PluginGroupBuilder, PluginId},
+    assets::{
+        Asset, AssetEvent, AssetGroup, AssetGroupPlugin, AssetGroups, AssetLoader, AssetPlugin,
+        AssetWatcher, Assets, Handle, HotReloadPlugin, LoadState, Name, Prefab, PrefabInstance,
+        PrefabRef, Scene, SceneProto, ShaderLoader, asset_group_loaded,
+        scene::streaming::{
+            ChunkEvent, ChunkStreamer, ChunkStreamingPlugin, StreamedChunk, StreamingFocus,
+            chunks_streamed,
+        },
+    },
     audio::prelude::*,
+    camera_controller::prelude::*,
+    console::prelude::*,
+    core::graph::Msaa,
+    diagnostics::prelude::*,
     ecs::prelude::*,
     event::*,
+    gizmos::prelude::*,
     glam::{self, Mat4, Vec2, Vec3, Vec4},
     image::{self},
-    input::{Input, KeyCode, MouseButton},
+    input::{
+        ActionMap, ActionMapPlugin, ActionRebound, GamepadAxes, GamepadAxis, GamepadButton, Input,
+        InputBinding, KeyCode, MouseButton,
+    },
+    localization::prelude::*,
+    log::prelude::*,
     math::*,
-    plugins::DefaultPlugin,
+    nav::prelude::*,
+    network::prelude::*,
+    picking::prelude::*,
+    plugins::{DefaultPlugins, DeterministicPlugin, RngPlugin},
     query::{
         Query, RunQuery,
         filter::{Added, Changed, Or, With, Without},
     },
     reflect::Reflect,
-    renderer::{Color, Face, Image, Material, Mesh, Meshable, Texture},
+    renderer::{
+        Color, Face, Image, Material, Mesh, Meshable, NotShadowCaster, NotShadowReceiver,
+        RenderSettings, Texture, ZIndex,
+        culling::VisibilityRange,
+        custom_material::{CustomMaterial, MaterialPlugin},
+        outline::{OutlinePlugin, Outlined},
+        static_mesh::StaticMeshPlugin,
+    },
+    scatter::prelude::*,
+    sprite::prelude::*,
     system::{
-        AsyncTask, Commands, IntoSchedulerLocation, IntoSystem, IntoSystemCondition, Task, layer,
-        phase,
+        AsyncTask, Commands, IntoSchedulerLocation, IntoSystem, IntoSystemCondition,
+        IntoSystemLabel, Local, Task, layer, phase,
     },
+    terrain::prelude::*,
+    tilemap::prelude::*,
+    water::prelude::*,
     wgpu::{self},
     window::prelude::*,
     winit::{self},
 };
 
+#[cfg(feature = "scripting")]
+pub use super::scripting::prelude::*;
+
+#[cfg(feature = "serialize")]
+pub use super::assets::scene::DynamicScene;
+
+#[cfg(feature = "persistence")]
+pub use super::persistence::prelude::*;
+
 pub use vavo_macros::*;
 
 /// Re-exported scene macros
@@ -1,6 +1,8 @@
 use std::{any::TypeId, marker::PhantomData};
 
-use crate::prelude::Component;
+use crate::prelude::{Component, Disabled};
+
+use super::QueryComponentType;
 
 /// A filter that checks if a component is marked as changed in the current frame. That is, if the
 /// component was requested as a mutable reference in a query.
@@ -133,9 +135,34 @@ impl Filters {
         filters
     }
 
+    /// Create filters for a query of `requested` types with filters `F`. Entities with the
+    /// [`Disabled`] marker are excluded, unless the query mentions it.
+    pub fn for_query<F: QueryFilter>(requested: &[QueryComponentType]) -> Filters {
+        let mut filters = Filters::from::<F>();
+
+        let disabled = TypeId::of::<Disabled>();
+        let is_requested = requested
+            .iter()
+            .any(|query_type| *query_type.get_inner_type() == disabled);
+        if !is_requested && !filters.mentions(&disabled) {
+            filters.without.push(disabled);
+        }
+
+        filters
+    }
+
     /// Appends filters from 'F'
     pub fn add<F: QueryFilter>(&mut self) {
         F::into_filters(self);
         self.empty = false;
     }
+
+    /// Returns true if any filter, including nested ones, uses `type_id`
+    fn mentions(&self, type_id: &TypeId) -> bool {
+        self.changed.contains(type_id)
+            || self.added.contains(type_id)
+            || self.with.contains(type_id)
+            || self.without.contains(type_id)
+            || self.or.iter().any(|filters| filters.mentions(type_id))
+    }
 }
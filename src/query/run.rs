@@ -1,3 +1,5 @@
+use std::cmp::Ordering;
+
 use crate::{
     ecs::entities::{Component, EntityId, components::ComponentsData},
     prelude::{Mut, Ref, Tick},
@@ -12,7 +14,63 @@ pub trait RunQuery {
     type Output;
 
     fn iter_mut(&mut self) -> Vec<Self::Output>;
+
+    /// Returns the components of `entity_id` without iterating the query. Returns `None` if the
+    /// entity doesn't exist, was despawned (its generation doesn't match), or doesn't match the
+    /// query's components and filters.
     fn get(&mut self, entity_id: EntityId) -> Option<Self::Output>;
+
+    /// Same as [`get`](RunQuery::get), mutable access is decided by the query's types, e.g.
+    /// `Query<&mut Transform>` returns `Option<&mut Transform>`.
+    #[inline]
+    fn get_mut(&mut self, entity_id: EntityId) -> Option<Self::Output> {
+        self.get(entity_id)
+    }
+
+    /// Returns the number of matching entities without fetching their components. Archetypes are
+    /// counted by their length, only `Changed` and `Added` filters are checked per entity.
+    fn count(&mut self) -> usize;
+
+    /// Returns true if no entity matches the query, see [`count`](RunQuery::count)
+    fn is_empty(&mut self) -> bool;
+
+    /// Returns all results sorted with the `compare` function. The sort is stable, see
+    /// [`slice::sort_by`].
+    ///
+    /// ```ignore
+    /// // back to front, e.g. for transparent objects
+    /// let sorted = query.iter_sorted_by(|(_, a), (_, b)| {
+    ///     let a = a.translation().distance_squared(camera_position);
+    ///     let b = b.translation().distance_squared(camera_position);
+    ///     b.total_cmp(&a)
+    /// });
+    /// ```
+    fn iter_sorted_by<F>(&mut self, compare: F) -> Vec<Self::Output>
+    where
+        F: FnMut(&Self::Output, &Self::Output) -> Ordering,
+    {
+        let mut results = self.iter_mut();
+        results.sort_by(compare);
+        results
+    }
+
+    /// Returns all results sorted by the key extracted with `f`. The sort is stable, see
+    /// [`slice::sort_by_key`].
+    ///
+    /// ```ignore
+    /// for (entity, node) in query.iter_sorted_by_key(|(_, node)| node.z_index) {
+    ///     ...
+    /// }
+    /// ```
+    fn iter_sorted_by_key<K, F>(&mut self, f: F) -> Vec<Self::Output>
+    where
+        K: Ord,
+        F: FnMut(&Self::Output) -> K,
+    {
+        let mut results = self.iter_mut();
+        results.sort_by_key(f);
+        results
+    }
 }
 
 /// Retrieve information about the requested component type in the query
@@ -170,9 +228,8 @@ macro_rules! impl_run_query {
             type Output = ($($types),+);
 
             fn iter_mut(&mut self) -> Vec<($($types),+)> {
-                let mut filters = Filters::from::<QF>();
-
                 let requested_types = [$($types::get_type_id()),+];
+                let mut filters = Filters::for_query::<QF>(&requested_types);
                 let mut result = Vec::new();
                 let entities = unsafe { &mut *self.entities };
                 let current_tick = entities.tick();
@@ -225,10 +282,30 @@ macro_rules! impl_run_query {
                 result
             }
 
-            fn get(&mut self, entity_id: EntityId) -> Option<($($types),+)> {
-                let mut filters = Filters::from::<QF>();
+            fn count(&mut self) -> usize {
+                let requested_types = [$($types::get_type_id()),+];
+                let mut filters = Filters::for_query::<QF>(&requested_types);
+                let entities = unsafe { &mut *self.entities };
 
+                entities
+                    .archetypes_filtered(&requested_types, &mut filters)
+                    .map(|(archetype, indices)| archetype.count_matching(&indices, self.system_last_run))
+                    .sum()
+            }
+
+            fn is_empty(&mut self) -> bool {
                 let requested_types = [$($types::get_type_id()),+];
+                let mut filters = Filters::for_query::<QF>(&requested_types);
+                let entities = unsafe { &mut *self.entities };
+
+                !entities
+                    .archetypes_filtered(&requested_types, &mut filters)
+                    .any(|(archetype, indices)| archetype.any_matching(&indices, self.system_last_run))
+            }
+
+            fn get(&mut self, entity_id: EntityId) -> Option<($($types),+)> {
+                let requested_types = [$($types::get_type_id()),+];
+                let mut filters = Filters::for_query::<QF>(&requested_types);
                 let entities = unsafe { &mut *self.entities };
                 let current_tick = entities.tick();
 
@@ -239,6 +316,11 @@ macro_rules! impl_run_query {
                 let id = location.archetype_id();
                 let archetype = entities.archetypes.get_mut(&id).expect("archetype should exist");
 
+                // Locations are tracked by index, a stale id can point to a reused slot
+                if !archetype.has_entity(&entity_id, &location) {
+                    return None;
+                }
+
                 // Check if the archetype matches the query
                 if let Some(changed_filter_indices) = archetype.filtered(&requested_types, &mut filters) {
                     let mut type_index = 0;
@@ -0,0 +1,638 @@
+use glam::{Mat4, Quat, Vec2, Vec3};
+use wgpu::{VertexAttribute, VertexFormat};
+
+use crate::{
+    assets::ShaderLoader,
+    core::{graph::*, standard::rendering::surface_size},
+    event::EventReader,
+    math::bounding_volume::Plane,
+    picking::{PickClick, PickingPlugin, PickingState},
+    plugins::RenderPlugin,
+    prelude::*,
+    render_assets::{BindGroup, Buffer, Pipeline, RenderAssets, pipeline::PipelineBuilder},
+    renderer::{
+        newtype::{RenderCommandEncoder, RenderDevice, RenderSurfaceConfiguration},
+        palette,
+    },
+};
+
+use super::{InspectorPanel, InspectorPlugin, InspectorSelection, InspectorState};
+
+/// Distance in pixels from a handle at which it can still be grabbed
+const GRAB_DISTANCE: f32 = 8.0;
+/// Amount of line segments in a rotation circle
+const CIRCLE_SEGMENTS: usize = 48;
+
+/// Plugin which draws translate, rotate and scale handles on the entity selected in the
+/// inspector, dragging a handle changes the entity's [`Transform`].
+///
+/// Entities are selected by clicking on them, which requires the [`PickingPlugin`]. Clicking on
+/// empty space clears the selection. The mode is switched with the keys in [`TransformGizmo`].
+pub struct TransformGizmoPlugin;
+
+impl Plugin for TransformGizmoPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TransformGizmo>()
+            .add_startup_system(register_gizmo_graph)
+            .add_system(transform_gizmo_system);
+    }
+
+    fn dependencies(&self) -> Vec<PluginId> {
+        vec![
+            PluginId::of::<InspectorPlugin>(),
+            PluginId::of::<PickingPlugin>(),
+            PluginId::of::<RenderPlugin>(),
+        ]
+    }
+}
+
+/// Transform property changed by the gizmo handles
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GizmoMode {
+    /// Move along the global axes
+    #[default]
+    Translate,
+    /// Rotate around the global axes
+    Rotate,
+    /// Scale along the entity's local axes
+    Scale,
+}
+
+/// Settings and state of the transform gizmo. Used as a resource.
+#[derive(Resource)]
+pub struct TransformGizmo {
+    pub mode: GizmoMode,
+    /// Length of the handles in pixels
+    pub size: f32,
+    pub translate_key: KeyCode,
+    pub rotate_key: KeyCode,
+    pub scale_key: KeyCode,
+    /// Camera the handles are sized for and drawn by
+    camera: Option<EntityId>,
+    /// Axis under the cursor
+    hovered: Option<usize>,
+    drag: Option<GizmoDrag>,
+    /// Lines drawn in the `inspector_gizmo` graph node
+    lines: Vec<GizmoVertex>,
+}
+
+impl Default for TransformGizmo {
+    fn default() -> Self {
+        Self {
+            mode: GizmoMode::default(),
+            size: 100.0,
+            translate_key: KeyCode::Digit1,
+            rotate_key: KeyCode::Digit2,
+            scale_key: KeyCode::Digit3,
+            camera: None,
+            hovered: None,
+            drag: None,
+            lines: Vec::new(),
+        }
+    }
+}
+
+impl TransformGizmo {
+    /// Returns true while a handle is being dragged
+    #[inline]
+    pub fn is_dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+}
+
+/// Handles of the selected entity in the current frame
+struct GizmoHandles {
+    entity: EntityId,
+    transform: Transform,
+    /// Converts world space vectors to the entity's parent space
+    parent_inverse: Mat4,
+    origin: Vec3,
+    axes: [Vec3; 3],
+    length: f32,
+    polylines: [Vec<Vec3>; 3],
+    /// Axis under the cursor
+    hovered: Option<usize>,
+}
+
+/// Handle drag in progress
+struct GizmoDrag {
+    entity: EntityId,
+    mode: GizmoMode,
+    axis: usize,
+    direction: Vec3,
+    origin: Vec3,
+    /// Axis parameter or direction from the origin at the start of the drag
+    start: (f32, Vec3),
+    start_transform: Transform,
+    /// Converts world space vectors to the entity's parent space
+    parent_inverse: Mat4,
+}
+
+/// Vertex of a gizmo line
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct GizmoVertex {
+    position: [f32; 3],
+    color: [f32; 4],
+}
+
+impl GizmoVertex {
+    fn vertex_descriptor() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<GizmoVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                // Position
+                VertexAttribute {
+                    format: VertexFormat::Float32x3,
+                    offset: 0,
+                    shader_location: 0,
+                },
+                // Color
+                VertexAttribute {
+                    format: VertexFormat::Float32x4,
+                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                },
+            ],
+        }
+    }
+}
+
+/// Returns the parameter of the point on the line through `origin` along `direction`, which is
+/// closest to `ray`. None if the ray is parallel to the line.
+fn closest_on_axis(ray: &Ray, origin: Vec3, direction: Vec3) -> Option<f32> {
+    let b = direction.dot(ray.direction);
+    let denominator = 1.0 - b * b;
+    if denominator.abs() < 1e-4 {
+        return None;
+    }
+
+    let w = origin - ray.origin;
+    Some((b * ray.direction.dot(w) - direction.dot(w)) / denominator)
+}
+
+/// Returns the direction from `origin` to where `ray` hits the plane perpendicular to `axis`
+fn direction_on_plane(ray: &Ray, origin: Vec3, axis: Vec3) -> Option<Vec3> {
+    let plane = Plane::new(axis, -axis.dot(origin));
+    let distance = ray.intersect_plane(&plane)?;
+    (ray.at(distance) - origin).try_normalize()
+}
+
+/// Returns the distance from `point` to the segment from `a` to `b`
+fn distance_to_segment(point: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let segment = b - a;
+    let t = ((point - a).dot(segment) / segment.length_squared().max(f32::EPSILON)).clamp(0.0, 1.0);
+    point.distance(a + segment * t)
+}
+
+/// Returns the polylines of the three handles in world space
+fn handle_polylines(mode: GizmoMode, origin: Vec3, axes: [Vec3; 3], length: f32) -> [Vec<Vec3>; 3] {
+    axes.map(|axis| match mode {
+        GizmoMode::Translate | GizmoMode::Scale => vec![origin, origin + axis * length],
+        GizmoMode::Rotate => {
+            let tangent = axis.any_orthonormal_vector();
+            let bitangent = axis.cross(tangent);
+            (0..=CIRCLE_SEGMENTS)
+                .map(|i| {
+                    let angle = i as f32 / CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+                    origin + (tangent * angle.cos() + bitangent * angle.sin()) * length
+                })
+                .collect()
+        }
+    })
+}
+
+/// Handles selection, hovering and dragging of the gizmo handles, and prepares the lines drawn
+/// by the `inspector_gizmo` graph node
+fn transform_gizmo_system(
+    state: Res<State<InspectorState>>,
+    mut selection: ResMut<InspectorSelection>,
+    mut gizmo: ResMut<TransformGizmo>,
+    panel: Res<InspectorPanel>,
+    picking: Res<PickingState>,
+    mut windows: Query<(EntityId, &Window, Option<&PrimaryWindow>)>,
+    key_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    mut clicks: EventReader<PickClick>,
+    mut camera_query: Query<(EntityId, &Camera, &Projection, &GlobalTransform), With<Camera3D>>,
+    mut query: Query<(&mut Transform, &GlobalTransform)>,
+) {
+    gizmo.lines.clear();
+    gizmo.hovered = None;
+
+    // read every run, so clicks from runs which return early are not used later
+    let clicked = clicks
+        .read()
+        .find(|click| click.button == MouseButton::Left)
+        .map(|click| click.hit.entity);
+
+    if state.get() != InspectorState::On {
+        gizmo.drag = None;
+        return;
+    }
+
+    // mode
+    if key_input.just_pressed(gizmo.translate_key) {
+        gizmo.mode = GizmoMode::Translate;
+    } else if key_input.just_pressed(gizmo.rotate_key) {
+        gizmo.mode = GizmoMode::Rotate;
+    } else if key_input.just_pressed(gizmo.scale_key) {
+        gizmo.mode = GizmoMode::Scale;
+    }
+
+    // handles are sized for the camera under the cursor, the previous one is kept while dragging
+    // or when the cursor is outside of every viewport
+    let cursor_window = CursorWindow::find(&mut windows);
+    let cameras = camera_query.iter_mut();
+    let under_cursor = cursor_window
+        .filter(|_| gizmo.drag.is_none())
+        .and_then(|cursor| {
+            cameras
+                .iter()
+                .rev()
+                .find(|(_, camera, ..)| camera.cursor_in_viewport(&cursor).is_some())
+        })
+        .map(|(id, ..)| *id);
+    let Some(&(camera_id, camera, projection, camera_transform)) = under_cursor
+        .or(gizmo.camera)
+        .and_then(|id| {
+            cameras
+                .iter()
+                .find(|(other, camera, ..)| *other == id && camera.active)
+        })
+        .or_else(|| cameras.iter().find(|(_, camera, ..)| camera.active))
+    else {
+        gizmo.camera = None;
+        return;
+    };
+    gizmo.camera = Some(camera_id);
+
+    // size of the camera's viewport, and the cursor relative to it
+    let Some(window_size) = windows
+        .iter_mut()
+        .into_iter()
+        .find(|(entity, _, primary)| match camera.window {
+            Some(window) => window == *entity,
+            None => primary.is_some(),
+        })
+        .map(|(_, window, _)| Vec2::new(window.size().width as f32, window.size().height as f32))
+    else {
+        return;
+    };
+    let (viewport_position, viewport_size) = camera.viewport_pixels(window_size);
+    let cursor = cursor_window
+        .filter(|cursor| camera.renders_to(&cursor.window))
+        .map(|cursor| cursor.position - viewport_position);
+    let ray = picking.ray();
+
+    // update the drag in progress
+    if !mouse_input.pressed(MouseButton::Left) {
+        gizmo.drag = None;
+    }
+    if let (Some(drag), Some(ray)) = (&gizmo.drag, ray)
+        && let Some((transform, _)) = query.get(drag.entity)
+    {
+        apply_drag(drag, &ray, transform);
+    }
+
+    let selected = selection.entity.and_then(|entity| {
+        query
+            .get(entity)
+            .map(|(transform, global)| (entity, *transform, global))
+    });
+    if selected.is_none() {
+        selection.entity = None;
+        gizmo.drag = None;
+    }
+
+    let handles = selected.and_then(|(entity, transform, global_transform)| {
+        // scale the handles to a constant size in pixels
+        let origin = global_transform.translation();
+        let camera_right = camera_transform.rotation() * Vec3::X;
+        let to_viewport = |point: Vec3| {
+            Camera::world_to_viewport(projection, camera_transform, viewport_size, point)
+        };
+        let pixels_per_unit = to_viewport(origin)?.distance(to_viewport(origin + camera_right)?);
+        if pixels_per_unit <= f32::EPSILON {
+            return None;
+        }
+        let length = gizmo.size / pixels_per_unit;
+
+        let axes = match gizmo.mode {
+            GizmoMode::Translate | GizmoMode::Rotate => [Vec3::X, Vec3::Y, Vec3::Z],
+            GizmoMode::Scale => {
+                let rotation = global_transform.rotation();
+                [rotation * Vec3::X, rotation * Vec3::Y, rotation * Vec3::Z]
+            }
+        };
+        let polylines = handle_polylines(gizmo.mode, origin, axes, length);
+
+        // axis under the cursor
+        let hovered = cursor.and_then(|cursor| {
+            polylines
+                .iter()
+                .enumerate()
+                .filter_map(|(axis, points)| {
+                    let points = points
+                        .iter()
+                        .map(|point| to_viewport(*point))
+                        .collect::<Option<Vec<_>>>()?;
+                    let distance = points
+                        .windows(2)
+                        .map(|segment| distance_to_segment(cursor, segment[0], segment[1]))
+                        .fold(f32::INFINITY, f32::min);
+                    Some((axis, distance))
+                })
+                .filter(|(_, distance)| *distance <= GRAB_DISTANCE)
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(axis, _)| axis)
+        });
+
+        // converts world space vectors to the entity's parent space
+        let parent_inverse = transform.as_matrix() * global_transform.matrix.inverse();
+
+        Some(GizmoHandles {
+            entity,
+            transform,
+            parent_inverse,
+            origin,
+            axes,
+            length,
+            polylines,
+            hovered,
+        })
+    });
+
+    // clicks on the inspector panel don't grab handles or change the selection
+    if mouse_input.just_pressed(MouseButton::Left) && gizmo.drag.is_none() && !panel.is_hovered() {
+        match (&handles, ray) {
+            // start dragging a handle
+            (
+                Some(
+                    handles @ GizmoHandles {
+                        hovered: Some(axis),
+                        ..
+                    },
+                ),
+                Some(ray),
+            ) => {
+                let direction = handles.axes[*axis];
+                let origin = handles.origin;
+                let start = match gizmo.mode {
+                    GizmoMode::Rotate => {
+                        direction_on_plane(&ray, origin, direction).map(|v| (0.0, v))
+                    }
+                    _ => closest_on_axis(&ray, origin, direction).map(|t| (t, Vec3::ZERO)),
+                };
+
+                gizmo.drag = start.map(|start| GizmoDrag {
+                    entity: handles.entity,
+                    mode: gizmo.mode,
+                    axis: *axis,
+                    direction,
+                    origin,
+                    start,
+                    start_transform: handles.transform,
+                    parent_inverse: handles.parent_inverse,
+                });
+            }
+            // select the clicked entity, or clear the selection
+            _ => {
+                selection.entity = clicked;
+            }
+        }
+    }
+
+    let Some(GizmoHandles {
+        origin,
+        axes,
+        length,
+        polylines,
+        hovered,
+        ..
+    }) = handles
+    else {
+        return;
+    };
+    gizmo.hovered = hovered;
+
+    // prepare lines
+    let active = gizmo.drag.as_ref().map(|drag| drag.axis).or(gizmo.hovered);
+    let colors = [palette::RED, palette::LIME, palette::BLUE];
+    let mut lines = Vec::new();
+    for (axis, points) in polylines.iter().enumerate() {
+        let color = if active == Some(axis) {
+            palette::YELLOW
+        } else {
+            colors[axis]
+        };
+        let color = [color.r, color.g, color.b, color.a];
+        let mut line = |from: Vec3, to: Vec3| {
+            lines.push(GizmoVertex {
+                position: from.to_array(),
+                color,
+            });
+            lines.push(GizmoVertex {
+                position: to.to_array(),
+                color,
+            });
+        };
+
+        for segment in points.windows(2) {
+            line(segment[0], segment[1]);
+        }
+
+        // cross at the tip of scale handles
+        if gizmo.mode == GizmoMode::Scale {
+            let tip = origin + axes[axis] * length;
+            for other in (0..3).filter(|other| *other != axis) {
+                let offset = axes[other] * length * 0.08;
+                line(tip - offset, tip + offset);
+            }
+        }
+    }
+    gizmo.lines = lines;
+}
+
+/// Applies the drag to the `transform` of the dragged entity
+fn apply_drag(drag: &GizmoDrag, ray: &Ray, transform: &mut Transform) {
+    let start = &drag.start_transform;
+
+    match drag.mode {
+        GizmoMode::Translate => {
+            let Some(t) = closest_on_axis(ray, drag.origin, drag.direction) else {
+                return;
+            };
+            let delta = drag.direction * (t - drag.start.0);
+            transform.translation =
+                start.translation + drag.parent_inverse.transform_vector3(delta);
+        }
+        GizmoMode::Rotate => {
+            let Some(current) = direction_on_plane(ray, drag.origin, drag.direction) else {
+                return;
+            };
+            let from = drag.start.1;
+            let angle = drag
+                .direction
+                .dot(from.cross(current))
+                .atan2(from.dot(current));
+            let axis = drag
+                .parent_inverse
+                .transform_vector3(drag.direction)
+                .normalize_or(drag.direction);
+            transform.rotation = (Quat::from_axis_angle(axis, angle) * start.rotation).normalize();
+        }
+        GizmoMode::Scale => {
+            let Some(t) = closest_on_axis(ray, drag.origin, drag.direction) else {
+                return;
+            };
+            if drag.start.0.abs() < f32::EPSILON {
+                return;
+            }
+            let factor = (t / drag.start.0).max(0.01);
+            transform.scale[drag.axis] = start.scale[drag.axis] * factor;
+        }
+    }
+}
+
+/// Startup system to register the inspector gizmo graph node
+fn register_gizmo_graph(
+    graph: &mut RenderGraph,
+    device: Res<RenderDevice>,
+    surface_config: Res<RenderSurfaceConfiguration>,
+    mut shader_loader: ResMut<ShaderLoader>,
+) {
+    let pipeline_builder =
+        create_gizmo_pipeline_builder(&device, &surface_config, &mut shader_loader);
+
+    // drawn without depth, so the handles are never hidden by other meshes
+    let node = GraphNodeBuilder::new("inspector_gizmo")
+        .set_pipeline(pipeline_builder)
+        .set_custom_system(gizmo_render_system)
+        .set_color_target(NodeColorTarget::Surface)
+        .run_after("main")
+        .run_before("ui_image")
+        .build();
+
+    graph.add(node);
+}
+
+/// Inspector gizmo graph node rendering system, draws the lines prepared by
+/// [`transform_gizmo_system`]
+fn gizmo_render_system(
+    graph_ctx: Res<RenderContext>,
+
+    world: &mut World,
+    encoder: &mut RenderCommandEncoder,
+    device: Res<RenderDevice>,
+    gizmo: Res<TransformGizmo>,
+    mut bind_groups: ResMut<RenderAssets<BindGroup>>,
+
+    mut camera_query: Query<&Camera, With<Camera3D>>,
+    rendered: Res<RenderedWindow>,
+) {
+    if gizmo.lines.is_empty() {
+        return;
+    }
+
+    // the handles are drawn by the camera they are sized for
+    let Some((camera_id, camera)) = gizmo
+        .camera
+        .and_then(|id| Some((id, camera_query.get(id)?)))
+        .filter(|(_, camera)| camera.renders_to(&rendered))
+    else {
+        return;
+    };
+
+    let buffer = Buffer::new("inspector_gizmo").create_vertex_buffer(
+        &gizmo.lines,
+        gizmo.lines.len(),
+        None,
+        &device,
+    );
+    let vertex_buffer = buffer
+        .vertex
+        .as_ref()
+        .expect("Gizmo lines should not be empty");
+
+    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("inspector gizmo render pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: unsafe {
+                &*graph_ctx
+                    .color_target
+                    .expect("inspector gizmo color target is None")
+            },
+            depth_slice: None,
+            resolve_target: graph_ctx.resolve_target.map(|view| unsafe { &*view }),
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    });
+
+    render_pass.set_pipeline(
+        unsafe { &*graph_ctx.node }
+            .data
+            .pipeline
+            .as_ref()
+            .expect("Pipeline should have been generated by now")
+            .render_pipeline(),
+    );
+    let target_size = surface_size(world);
+    if !camera.set_render_pass_camera(
+        camera_id,
+        &mut render_pass,
+        0,
+        target_size,
+        &mut bind_groups,
+        world,
+    ) {
+        return;
+    }
+    render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+    render_pass.draw(0..buffer.num_vertices, 0..1);
+
+    graph_ctx.record_draw_calls(1);
+}
+
+fn create_gizmo_pipeline_builder(
+    device: &RenderDevice,
+    surface_config: &RenderSurfaceConfiguration,
+    shader_loader: &mut ShaderLoader,
+) -> PipelineBuilder {
+    // Camera bind group layout for uniform buffer
+    let camera_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("camera_bind_group_layout"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+    });
+
+    // Load shader modules
+    shader_loader.load("gizmo", include_str!("../../shaders/gizmo.wgsl"), device);
+
+    let mut primitive_state = PipelineBuilder::default_primitive_state();
+    primitive_state.topology = wgpu::PrimitiveTopology::LineList;
+    primitive_state.cull_mode = None;
+
+    Pipeline::build("inspector_gizmo_pipeline")
+        .set_bind_group_layouts(vec![camera_layout])
+        .set_vertex_buffer_layouts(vec![GizmoVertex::vertex_descriptor()])
+        .set_vertex_shader("gizmo", "vs_main")
+        .set_fragment_shader("gizmo", "fs_main")
+        .add_color_format(surface_config.format)
+        .set_primitive_state(primitive_state)
+}
@@ -1,16 +1,33 @@
-use std::any::TypeId;
-
-use crate::{prelude::*, ui::prelude::*};
-
-/// Provides a Inspector Tool for dynamic reflection of types.
+mod gizmo;
+mod panel;
+
+use crate::prelude::*;
+
+pub use gizmo::{GizmoMode, TransformGizmo, TransformGizmoPlugin};
+use panel::InspectorPanel;
+
+/// Provides a Inspector Tool for dynamic reflection of types, toggled with the backquote key.
+///
+/// The inspector panel lists entities by their hierarchy, clicking one selects it and shows its
+/// children. Components of the selected entity which are registered with
+/// [`App::register_type`] can be expanded into their fields. Primitive and vector fields are
+/// edited by clicking them, typing the new value and pressing enter, e.g. `1 2 3` for a `Vec3`.
+/// Bool fields are flipped by clicking them.
+///
+/// Add the [`TransformGizmoPlugin`] to edit the transform of the selected entity.
 pub struct InspectorPlugin;
 
 impl Plugin for InspectorPlugin {
     fn build(&self, app: &mut App) {
         app.register_state::<InspectorState>()
+            .init_resource::<InspectorSelection>()
+            .init_resource::<InspectorPanel>()
             .add_startup_system(setup_inspector)
-            .add_system(handle_inspector)
-            .add_system(create_inspector.run_if(on_enter(InspectorState::On)))
+            // before the keyboard system, so escape cancelling an edit doesn't close the inspector
+            .add_system(handle_inspector.before(panel::inspector_keyboard_system))
+            .add_system(panel::inspector_click_system)
+            .add_system(panel::inspector_keyboard_system)
+            .add_system(panel::update_inspector_panel)
             .add_system(cleanup_inspector.run_if(on_exit(InspectorState::On)));
     }
 }
@@ -18,6 +35,12 @@ impl Plugin for InspectorPlugin {
 #[derive(Component)]
 struct InspectorMenu;
 
+/// Entity selected in the inspector. Used as a resource.
+#[derive(Resource, Default, Debug)]
+pub struct InspectorSelection {
+    pub entity: Option<EntityId>,
+}
+
 #[derive(States, Default, Debug, PartialEq, Eq, Clone, Copy)]
 enum InspectorState {
     On,
@@ -33,7 +56,13 @@ fn handle_inspector(
     input: Res<Input<KeyCode>>,
     state: Res<State<InspectorState>>,
     mut next_state: ResMut<NextState<InspectorState>>,
+    panel: Res<InspectorPanel>,
 ) {
+    // keys are typed into the edited field
+    if panel.is_editing() {
+        return;
+    }
+
     if input.just_pressed(KeyCode::Backquote) {
         match state.get() {
             InspectorState::On => next_state.set(InspectorState::Off),
@@ -46,83 +75,14 @@ fn handle_inspector(
     }
 }
 
-/// Creates the Inspector UI menu
-fn create_inspector(
+/// Despawns Inspector UI menu
+fn cleanup_inspector(
     mut commands: Commands,
-    mut query: Query<(EntityId, &Transform, &GlobalTransform)>,
-    app: &mut App,
+    mut panel: ResMut<InspectorPanel>,
+    mut query: Query<EntityId, With<InspectorMenu>>,
 ) {
-    let query_result = query.iter_mut();
-    let count = query_result.len();
-
-    let menu = commands
-        .spawn_empty()
-        .insert(InspectorMenu)
-        .insert(Node {
-            border: UiRect::all(Val::Px(2.0)),
-            border_color: color::RED,
-            background_color: Color::new(0.0, 0.0, 0.0, 0.8),
-            ..Default::default()
-        })
-        .entity_id();
-
-    commands.entity(menu).with_children(|p| {
-        p.spawn_empty()
-            .insert(Node {
-                color: Some(color::WHITE),
-                background_color: color::TRANSPARENT,
-                ..Default::default()
-            })
-            .insert(Text::new(format!("total: {:?}", count)));
-    });
-
-    for (id, transform, global) in query_result {
-        commands.entity(menu).with_children(|p| {
-            p.spawn_empty()
-                .insert(Node {
-                    color: Some(color::WHITE),
-                    background_color: color::TRANSPARENT,
-                    ..Default::default()
-                })
-                .insert(Text::new(format!("{:?}:", id.index())));
-        });
-    }
-
-    let registry = &app.type_registry;
-    println!("PRINTING");
-    for archetype in app.world.entities.archetypes() {
-        let id_idx = archetype.component_index(&TypeId::of::<EntityId>());
-
-        for entity in 0..archetype.len() {
-            let mut entity_id = None;
-            let components: Vec<_> = archetype
-                .components
-                .iter()
-                .enumerate()
-                .filter_map(|(i, c)| {
-                    if i == id_idx {
-                        entity_id = registry
-                            .reflect(c.get_untyped_lt(entity), c.get_type_id())
-                            .unwrap()
-                            .downcast_ref::<EntityId>();
-                        return None;
-                    }
-                    registry.reflect(c.get_untyped_lt(entity), c.get_type_id())
-                })
-                .collect();
-
-            print!("{:?}: ", entity_id.unwrap().to_bits());
-            for c in components {
-                print!("{:?} ", c);
-            }
-            println!();
-        }
-    }
-}
-
-/// Despawns Inspector UI menu
-fn cleanup_inspector(mut commands: Commands, mut query: Query<EntityId, With<InspectorMenu>>) {
     if let Some(id) = query.iter_mut().first() {
         commands.entity(*id).despawn_recursive();
     }
+    panel.close();
 }
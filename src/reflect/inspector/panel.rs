@@ -0,0 +1,634 @@
+use std::{
+    any::{Any, TypeId},
+    collections::{HashMap, HashSet},
+};
+
+use glam::{Vec2, Vec3, Vec4};
+use winit::keyboard::PhysicalKey;
+
+use crate::{prelude::*, reflect::Reflect, ui::prelude::*};
+
+use super::{InspectorMenu, InspectorSelection, InspectorState};
+
+/// Seconds between refreshes of the shown values
+const REFRESH_INTERVAL: f32 = 0.25;
+/// Maximum amount of entity rows in the hierarchy
+const MAX_ENTITY_ROWS: usize = 200;
+/// Maximum depth of expanded fields
+const MAX_FIELD_DEPTH: usize = 8;
+/// Maximum amount of fields shown per value, e.g. for long lists
+const MAX_FIELDS: usize = 64;
+/// Indentation in pixels per hierarchy or field level
+const INDENT: f32 = 12.0;
+
+const FONT_SIZE: f32 = 14.0;
+const TEXT_COLOR: Color = color::WHITE;
+const MUTED_COLOR: Color = color::GRAY;
+const SELECTED_COLOR: Color = color::YELLOW;
+const EDIT_COLOR: Color = color::LIME;
+const ERROR_COLOR: Color = color::RED;
+
+/// Marker for every node of the inspector panel, they are hidden from the hierarchy
+#[derive(Component)]
+pub(super) struct InspectorUi;
+
+/// Field of a component, by the field indices from the component to the field
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(super) struct FieldKey {
+    entity: EntityId,
+    component: TypeId,
+    path: Vec<usize>,
+}
+
+impl FieldKey {
+    fn child(&self, index: usize) -> Self {
+        let mut path = self.path.clone();
+        path.push(index);
+        Self {
+            entity: self.entity,
+            component: self.component,
+            path,
+        }
+    }
+}
+
+/// Action of a clicked inspector row
+#[derive(Component, Debug, Clone)]
+pub(super) enum InspectorRow {
+    /// Select the entity and show its children
+    Entity(EntityId),
+    /// Show or hide the fields of a component or field
+    Expand(FieldKey),
+    /// Start editing a field with its current value as text
+    Edit {
+        key: FieldKey,
+        label: String,
+        text: String,
+    },
+    /// Flip a bool field to the given value
+    Toggle(FieldKey, bool),
+}
+
+/// Field edited in the panel
+#[derive(Debug)]
+struct FieldEdit {
+    key: FieldKey,
+    label: String,
+    input: String,
+}
+
+/// State of the inspector panel. Used as a resource.
+#[derive(Resource, Default)]
+pub(super) struct InspectorPanel {
+    /// Root ui node, if the panel is shown
+    root: Option<EntityId>,
+    expanded_entities: HashSet<EntityId>,
+    expanded_fields: HashSet<FieldKey>,
+    /// Entity shown in the last build
+    shown_selection: Option<EntityId>,
+    editing: Option<FieldEdit>,
+    /// Text node of the edited field
+    edit_node: Option<EntityId>,
+    /// Edits submitted since the last build
+    pending: Vec<(FieldKey, String)>,
+    /// Error of the last applied edit
+    error: Option<String>,
+    /// Whether the panel has to be built again
+    dirty: bool,
+    /// Elapsed seconds at the last build
+    last_build: f32,
+    hovered: bool,
+}
+
+impl InspectorPanel {
+    /// Returns true if the cursor is over the panel
+    #[inline]
+    pub(super) fn is_hovered(&self) -> bool {
+        self.hovered
+    }
+
+    /// Returns true while a field is being edited, so keys are not handled elsewhere
+    #[inline]
+    pub(super) fn is_editing(&self) -> bool {
+        self.editing.is_some()
+    }
+
+    /// Forget the despawned panel nodes and cancel the edit
+    pub(super) fn close(&mut self) {
+        self.root = None;
+        self.edit_node = None;
+        self.editing = None;
+        self.hovered = false;
+    }
+
+    fn edit_content(edit: &FieldEdit) -> String {
+        format!("{}: {}_", edit.label, edit.input)
+    }
+}
+
+/// Row of the panel, built from the world and spawned as a text node
+struct Row {
+    text: String,
+    depth: usize,
+    color: Color,
+    action: Option<InspectorRow>,
+    /// Row of the edited field
+    editing: bool,
+}
+
+impl Row {
+    fn label(text: impl Into<String>, depth: usize, color: Color) -> Self {
+        Self {
+            text: text.into(),
+            depth,
+            color,
+            action: None,
+            editing: false,
+        }
+    }
+
+    fn with_action(mut self, action: InspectorRow) -> Self {
+        self.action = Some(action);
+        self
+    }
+}
+
+/// Editable leaf types, edited as text
+macro_rules! editable_values {
+    ($($type:ty),+) => {
+        /// Returns the text of an editable value, or None if it can't be edited
+        fn edit_text(value: &dyn Reflect) -> Option<String> {
+            $(if let Some(value) = value.downcast_ref::<$type>() {
+                return Some(value.to_string());
+            })+
+
+            let floats = if let Some(value) = value.downcast_ref::<Vec2>() {
+                value.to_array().to_vec()
+            } else if let Some(value) = value.downcast_ref::<Vec3>() {
+                value.to_array().to_vec()
+            } else if let Some(value) = value.downcast_ref::<Vec4>() {
+                value.to_array().to_vec()
+            } else {
+                return None;
+            };
+
+            let floats: Vec<_> = floats.iter().map(f32::to_string).collect();
+            Some(floats.join(" "))
+        }
+
+        /// Parses `text` into a value of the same type as `value`
+        fn parse_value(value: &dyn Reflect, text: &str) -> Result<Box<dyn Any>, String> {
+            let text = text.trim();
+            $(if value.is::<$type>() {
+                return text
+                    .parse::<$type>()
+                    .map(|value| Box::new(value) as Box<dyn Any>)
+                    .map_err(|_| format!("Expected {}, got '{}'", stringify!($type), text));
+            })+
+
+            let floats = text
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|value| !value.is_empty())
+                .map(|value| value.parse::<f32>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| format!("Expected numbers, got '{}'", text));
+
+            let expected = |len: usize| {
+                let floats = floats.clone()?;
+                if floats.len() == len {
+                    Ok(floats)
+                } else {
+                    Err(format!("Expected {} numbers, got {}", len, floats.len()))
+                }
+            };
+
+            if value.is::<Vec2>() {
+                Ok(Box::new(Vec2::from_slice(&expected(2)?)))
+            } else if value.is::<Vec3>() {
+                Ok(Box::new(Vec3::from_slice(&expected(3)?)))
+            } else if value.is::<Vec4>() {
+                Ok(Box::new(Vec4::from_slice(&expected(4)?)))
+            } else {
+                Err(format!("Cannot edit '{}'", value.type_name()))
+            }
+        }
+    };
+}
+
+editable_values!(
+    u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64, bool, char
+);
+
+/// Applies an edit to a field of a component with [`Reflect::set_field_by_index`]
+fn apply_edit(app: &mut App, key: &FieldKey, text: &str) -> Result<(), String> {
+    let (last, parents) = key
+        .path
+        .split_last()
+        .ok_or_else(|| "Components can't be replaced".to_string())?;
+
+    let ptr = app
+        .world
+        .entities
+        .get_component_untyped(key.entity, key.component, true)
+        .ok_or_else(|| "Entity no longer has the component".to_string())?;
+    // Safety: the pointer comes from the component storage of `key.component` and the world is
+    // exclusively borrowed
+    let mut target = unsafe { app.type_registry.reflect_mut(ptr, key.component) }
+        .ok_or_else(|| "Component is not registered".to_string())?;
+
+    for index in parents {
+        let type_name = target.type_name();
+        target = target
+            .field_mut_by_index(*index)
+            .ok_or_else(|| format!("'{}' has no field {}", type_name, index))?;
+    }
+
+    let field = target
+        .field_by_index(*last)
+        .ok_or_else(|| format!("'{}' has no field {}", target.type_name(), last))?;
+    let value = parse_value(field, text)?;
+
+    let type_name = target.type_name();
+    target
+        .set_field_by_index(*last, value)
+        .map_err(|_| format!("Cannot set field {} of '{}'", last, type_name))
+}
+
+/// Handles clicks on panel rows, and tracks whether the cursor is over the panel
+pub(super) fn inspector_click_system(
+    mut panel: ResMut<InspectorPanel>,
+    mut selection: ResMut<InspectorSelection>,
+    mut root_query: Query<&Interaction, With<InspectorMenu>>,
+    mut row_query: Query<(&Interaction, &InspectorRow), Changed<Interaction>>,
+) {
+    panel.hovered = root_query
+        .iter_mut()
+        .first()
+        .is_some_and(|interaction| **interaction != Interaction::None);
+
+    for (interaction, row) in row_query.iter_mut() {
+        if *interaction != Interaction::Press {
+            continue;
+        }
+
+        match row {
+            InspectorRow::Entity(entity) => {
+                selection.entity = Some(*entity);
+                if !panel.expanded_entities.remove(entity) {
+                    panel.expanded_entities.insert(*entity);
+                }
+            }
+            InspectorRow::Expand(key) => {
+                if !panel.expanded_fields.remove(key) {
+                    panel.expanded_fields.insert(key.clone());
+                }
+            }
+            InspectorRow::Edit { key, label, text } => {
+                panel.editing = Some(FieldEdit {
+                    key: key.clone(),
+                    label: label.clone(),
+                    input: text.clone(),
+                });
+            }
+            InspectorRow::Toggle(key, value) => {
+                panel.pending.push((key.clone(), value.to_string()));
+            }
+        }
+        panel.dirty = true;
+    }
+}
+
+/// Edits the text of the edited field from keyboard events, enter applies the edit and escape
+/// cancels it
+pub(super) fn inspector_keyboard_system(
+    mut panel: ResMut<InspectorPanel>,
+    mut window_events: EventReader<WindowEvent>,
+    mut texts: Query<&mut Text>,
+) {
+    for event in window_events.read() {
+        let WindowEvent::KeyboardInput { event, .. } = event else {
+            continue;
+        };
+        if event.state != ElementState::Pressed {
+            continue;
+        }
+        let Some(edit) = &mut panel.editing else {
+            return;
+        };
+
+        match event.physical_key {
+            PhysicalKey::Code(KeyCode::Enter | KeyCode::NumpadEnter) => {
+                let edit = panel.editing.take().expect("edit was checked above");
+                panel.pending.push((edit.key, edit.input));
+                panel.dirty = true;
+                return;
+            }
+            PhysicalKey::Code(KeyCode::Escape) => {
+                panel.editing = None;
+                panel.dirty = true;
+                return;
+            }
+            PhysicalKey::Code(KeyCode::Backspace) => {
+                edit.input.pop();
+            }
+            _ => {
+                let text = event.text.as_deref().unwrap_or_default();
+                edit.input.extend(text.chars().filter(|c| !c.is_control()));
+            }
+        }
+
+        // the text node is spawned with commands, so it might not exist yet
+        let content = InspectorPanel::edit_content(edit);
+        match panel.edit_node.and_then(|id| texts.get(id)) {
+            Some(text) => text.content = content,
+            None => panel.dirty = true,
+        }
+    }
+}
+
+/// Applies submitted edits, and builds the panel again when it changed or its values have to be
+/// refreshed
+pub(super) fn update_inspector_panel(mut commands: Commands, app: &mut App) {
+    let world = &mut app.world;
+    if world.resources.get::<State<InspectorState>>().get() != InspectorState::On {
+        return;
+    }
+    let elapsed = world.resources.get::<Time>().elapsed();
+    let selected = world.resources.get::<InspectorSelection>().entity;
+
+    let pending = {
+        let mut panel = world.resources.get_mut::<InspectorPanel>();
+        let refresh = elapsed - panel.last_build >= REFRESH_INTERVAL;
+        if !panel.dirty && !refresh && panel.shown_selection == selected {
+            return;
+        }
+        std::mem::take(&mut panel.pending)
+    };
+
+    let mut error = None;
+    for (key, text) in pending {
+        if let Err(err) = apply_edit(app, &key, &text) {
+            tracing::warn!("Inspector edit failed: {}", err);
+            error = Some(err);
+        }
+    }
+
+    // the panel is taken out, so the world can be reflected while building the rows
+    let mut panel = std::mem::take(&mut *app.world.resources.get_mut::<InspectorPanel>());
+    if error.is_some() {
+        panel.error = error;
+    }
+
+    let mut rows = hierarchy_rows(&mut app.world, &panel, selected);
+    if let Some(entity) = selected {
+        component_rows(app, &panel, entity, &mut rows);
+    }
+    if let Some(error) = &panel.error {
+        rows.push(Row::label(format!("error: {}", error), 0, ERROR_COLOR));
+    }
+
+    if let Some(root) = panel.root.take() {
+        commands.entity(root).despawn_recursive();
+    }
+    let (root, edit_node) = spawn_panel(&mut commands, rows);
+
+    panel.root = Some(root);
+    panel.edit_node = edit_node;
+    panel.shown_selection = selected;
+    panel.last_build = elapsed;
+    panel.dirty = false;
+    *app.world.resources.get_mut::<InspectorPanel>() = panel;
+}
+
+/// Returns the rows of the entity hierarchy, children are shown under expanded entities
+fn hierarchy_rows(
+    world: &mut World,
+    panel: &InspectorPanel,
+    selected: Option<EntityId>,
+) -> Vec<Row> {
+    let mut query = world.query_filtered::<(EntityId, Option<&Parent>, Option<&Children>, Option<&Name>), Without<InspectorUi>>();
+    let entities: HashMap<EntityId, (bool, Vec<EntityId>, Option<String>)> = query
+        .iter_mut()
+        .into_iter()
+        .map(|(id, parent, children, name)| {
+            let children = children.map(|c| c.ids.clone()).unwrap_or_default();
+            let name = name.map(|name| name.name().to_string());
+            (id, (parent.is_some(), children, name))
+        })
+        .collect();
+
+    let mut roots: Vec<_> = entities
+        .iter()
+        .filter(|(_, (has_parent, ..))| !has_parent)
+        .map(|(id, _)| *id)
+        .collect();
+    roots.sort_by_key(|id| id.index());
+
+    let mut rows = vec![Row::label(
+        format!("Entities ({})", entities.len()),
+        0,
+        TEXT_COLOR,
+    )];
+
+    // depth first, children of expanded entities right below them
+    let mut stack: Vec<_> = roots.into_iter().rev().map(|id| (id, 1)).collect();
+    while let Some((id, depth)) = stack.pop() {
+        if rows.len() > MAX_ENTITY_ROWS {
+            rows.push(Row::label("...", depth, MUTED_COLOR));
+            break;
+        }
+        let Some((_, children, name)) = entities.get(&id) else {
+            continue;
+        };
+
+        let expanded = panel.expanded_entities.contains(&id);
+        let marker = match (children.is_empty(), expanded) {
+            (true, _) => " ",
+            (false, true) => "-",
+            (false, false) => "+",
+        };
+        let mut text = format!("{} {}v{}", marker, id.index(), id.generation());
+        if let Some(name) = name {
+            text.push(' ');
+            text.push_str(name);
+        }
+        let color = if selected == Some(id) {
+            SELECTED_COLOR
+        } else {
+            TEXT_COLOR
+        };
+        rows.push(Row::label(text, depth, color).with_action(InspectorRow::Entity(id)));
+
+        if expanded {
+            stack.extend(children.iter().rev().map(|child| (*child, depth + 1)));
+        }
+    }
+
+    rows
+}
+
+/// Pushes the rows of the registered components of `entity` and their expanded fields
+fn component_rows(app: &mut App, panel: &InspectorPanel, entity: EntityId, rows: &mut Vec<Row>) {
+    rows.push(Row::label(
+        format!("Entity {}v{}", entity.index(), entity.generation()),
+        0,
+        TEXT_COLOR,
+    ));
+
+    // component storage is borrowed mutably, so the components are reflected one at a time
+    let mut components: Vec<_> = app
+        .world
+        .entities
+        .component_types(entity)
+        .into_iter()
+        .filter(|type_id| *type_id != TypeId::of::<EntityId>())
+        .filter_map(|type_id| {
+            let ptr = app
+                .world
+                .entities
+                .get_component_untyped(entity, type_id, false)?;
+            let name = app.type_registry.reflect(ptr, type_id)?.type_name();
+            Some((type_id, name))
+        })
+        .collect();
+    components.sort_by_key(|(_, name)| *name);
+
+    for (type_id, name) in components {
+        let key = FieldKey {
+            entity,
+            component: type_id,
+            path: Vec::new(),
+        };
+        let expanded = panel.expanded_fields.contains(&key);
+        let marker = if expanded { "-" } else { "+" };
+
+        rows.push(
+            Row::label(format!("{} {}", marker, name), 1, TEXT_COLOR)
+                .with_action(InspectorRow::Expand(key.clone())),
+        );
+        if !expanded {
+            continue;
+        }
+
+        let component = app
+            .world
+            .entities
+            .get_component_untyped(entity, type_id, false)
+            .and_then(|ptr| app.type_registry.reflect(ptr, type_id));
+        if let Some(component) = component {
+            field_rows(panel, component, &key, 2, rows);
+        }
+    }
+}
+
+/// Pushes the rows of the fields of `value`, editable fields can be clicked to edit them
+fn field_rows(
+    panel: &InspectorPanel,
+    value: &dyn Reflect,
+    key: &FieldKey,
+    depth: usize,
+    rows: &mut Vec<Row>,
+) {
+    if depth > MAX_FIELD_DEPTH {
+        rows.push(Row::label("...", depth, MUTED_COLOR));
+        return;
+    }
+
+    let names = value.field_names();
+    for index in 0..MAX_FIELDS {
+        let Some(field) = value.field_by_index(index) else {
+            return;
+        };
+        let key = key.child(index);
+        let label = names
+            .get(index)
+            .map(|name| name.to_string())
+            .unwrap_or_else(|| index.to_string());
+
+        if let Some(text) = edit_text(field) {
+            let row = match &panel.editing {
+                Some(edit) if edit.key == key => Row {
+                    editing: true,
+                    ..Row::label(InspectorPanel::edit_content(edit), depth, EDIT_COLOR)
+                },
+                _ => match field.downcast_ref::<bool>() {
+                    Some(value) => Row::label(format!("{}: {}", label, text), depth, TEXT_COLOR)
+                        .with_action(InspectorRow::Toggle(key, !value)),
+                    None => Row::label(format!("{}: {}", label, text), depth, TEXT_COLOR)
+                        .with_action(InspectorRow::Edit { key, label, text }),
+                },
+            };
+            rows.push(row);
+            continue;
+        }
+
+        // leaf values which can't be edited, e.g. strings, return themselves as their only field
+        if std::ptr::addr_eq(field, value) {
+            rows.push(Row::label(format!("{:?}", field), depth, MUTED_COLOR));
+            return;
+        }
+
+        let expanded = panel.expanded_fields.contains(&key);
+        let marker = if expanded { "-" } else { "+" };
+        rows.push(
+            Row::label(format!("{} {}", marker, label), depth, TEXT_COLOR)
+                .with_action(InspectorRow::Expand(key.clone())),
+        );
+        if expanded {
+            field_rows(panel, field, &key, depth + 1, rows);
+        }
+    }
+
+    rows.push(Row::label("...", depth, MUTED_COLOR));
+}
+
+/// Spawns the panel with `rows`, returns the root node and the text node of the edited field
+fn spawn_panel(commands: &mut Commands, rows: Vec<Row>) -> (EntityId, Option<EntityId>) {
+    let root = commands
+        .spawn_empty()
+        .insert(InspectorMenu)
+        .insert(InspectorUi)
+        .insert(Button)
+        .insert(Node {
+            position: Position::Absolute,
+            z_index: i32::MAX - 1,
+            flex_direction: FlexDirection::Column,
+            width: Val::Px(360.0),
+            height: Val::Vh(100.0),
+            padding: UiRect::all(Val::Px(6.0)),
+            border: UiRect::left(Val::Px(2.0)),
+            border_color: color::RED,
+            background_color: Color::new(0.0, 0.0, 0.0, 0.8),
+            ..Default::default()
+        })
+        .entity_id();
+
+    let mut edit_node = None;
+    commands.entity(root).with_children(|p| {
+        for row in rows {
+            let mut text = Text::new(row.text);
+            text.font_size(FONT_SIZE).line_height(FONT_SIZE * 1.2);
+
+            let mut entity = p
+                .spawn_empty()
+                .insert(InspectorUi)
+                .insert(Node {
+                    color: Some(row.color),
+                    background_color: color::TRANSPARENT,
+                    margin: UiRect::left(Val::Px(row.depth as f32 * INDENT)),
+                    ..Default::default()
+                })
+                .insert(text);
+            if let Some(action) = row.action {
+                entity = entity.insert(Button).insert(action);
+            }
+
+            let id = entity.entity_id();
+            if row.editing {
+                edit_node = Some(id);
+            }
+        }
+    });
+
+    (root, edit_node)
+}
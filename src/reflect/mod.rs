@@ -22,6 +22,10 @@ impl Plugin for ReflectionPlugin {
             .register_type::<Transform>()
             .register_type::<GlobalTransform>()
             .register_type::<Projection>();
+
+        #[cfg(feature = "serialize")]
+        app.register_serializable::<Transform>()
+            .register_serializable::<Name>();
     }
 }
 
@@ -37,6 +41,15 @@ pub trait Reflect: GetTypeInfo + Any + Send + Sync + 'static {
     }
     fn field_by_index(&self, index: usize) -> Option<&dyn Reflect>;
 
+    fn field_mut(&mut self, name: &str) -> Option<&mut dyn Reflect> {
+        let index = self.field_names().iter().position(|n| n == &name)?;
+        self.field_mut_by_index(index)
+    }
+    /// Mutable access to a nested field, None for leaf types like primitives and strings.
+    fn field_mut_by_index(&mut self, _index: usize) -> Option<&mut dyn Reflect> {
+        None
+    }
+
     fn set_field(&mut self, name: &str, value: Box<dyn Any>) -> Result<(), Box<dyn Any>> {
         let index = match self.field_names().iter().position(|n| n == &name) {
             Some(index) => index,
@@ -145,6 +158,13 @@ impl<T: Reflect> Reflect for Option<T> {
         }
     }
 
+    fn field_mut_by_index(&mut self, index: usize) -> Option<&mut dyn Reflect> {
+        match self {
+            Some(value) if index == 0 => Some(value),
+            _ => None,
+        }
+    }
+
     fn set_field_by_index(&mut self, index: usize, value: Box<dyn Any>) -> Result<(), Box<dyn Any>> {
         match self {
             Some(v) if index == 0 => value.downcast::<T>().map(|value| *v = *value),
@@ -162,6 +182,14 @@ impl<T: Reflect, E: Reflect> Reflect for Result<T, E> {
         }
     }
 
+    fn field_mut_by_index(&mut self, index: usize) -> Option<&mut dyn Reflect> {
+        match self {
+            Ok(value) if index == 0 => Some(value),
+            Err(value) if index == 0 => Some(value),
+            _ => None,
+        }
+    }
+
     fn set_field_by_index(&mut self, index: usize, value: Box<dyn Any>) -> Result<(), Box<dyn Any>> {
         match self {
             Ok(v) if index == 0 => value.downcast::<T>().map(|value| *v = *value),
@@ -179,6 +207,10 @@ macro_rules! impl_list {
                 self.get(index).map(|value| value as &dyn Reflect)
             }
 
+            fn field_mut_by_index(&mut self, index: usize) -> Option<&mut dyn Reflect> {
+                self.get_mut(index).map(|value| value as &mut dyn Reflect)
+            }
+
             fn set_field_by_index(&mut self, index: usize, value: Box<dyn Any>) -> Result<(), Box<dyn Any>> {
                 if self.len() <= index {
                     return Err(value);
@@ -197,6 +229,10 @@ impl<T: Reflect, const N: usize> Reflect for [T; N] {
         self.get(index).map(|value| value as &dyn Reflect)
     }
 
+    fn field_mut_by_index(&mut self, index: usize) -> Option<&mut dyn Reflect> {
+        self.get_mut(index).map(|value| value as &mut dyn Reflect)
+    }
+
     fn set_field_by_index(&mut self, index: usize, value: Box<dyn Any>) -> Result<(), Box<dyn Any>> {
         if self.len() <= index {
             return Err(value);
@@ -217,6 +253,13 @@ macro_rules! impl_tuple {
                 }
             }
 
+            fn field_mut_by_index(&mut self, index: usize) -> Option<&mut dyn Reflect> {
+                match index {
+                    $($index => Some(&mut self.$index as &mut dyn Reflect),)+
+                    _ => None
+                }
+            }
+
             fn set_field_by_index(&mut self, index: usize, value: Box<dyn Any>) -> Result<(), Box<dyn Any>> {
                 match index {
                     $($index => value.downcast::<_>().map(|v| self.$index = *v),)+
@@ -269,6 +312,13 @@ macro_rules! impl_struct {
                 }
             }
 
+            fn field_mut_by_index(&mut self, index: usize) -> Option<&mut dyn Reflect> {
+                match index {
+                    $($field_index => Some(&mut self.$field),)*
+                    _ => None
+                }
+            }
+
             fn set_field_by_index(&mut self, index: usize, value: Box<dyn Any>) -> Result<(), Box<dyn Any>> {
                 match index {
                     $($field_index => value.downcast::<_>().map(|v| self.$field = *v),)*
@@ -1,18 +1,52 @@
-use std::{any::TypeId, collections::HashMap};
+use std::{
+    any::{TypeId, type_name},
+    collections::HashMap,
+};
 
 use crate::ecs::ptr::UntypedPtrLt;
+#[cfg(feature = "serialize")]
+use crate::prelude::{Component, EntityId, World};
 
 use super::Reflect;
 
 /// Function which transforms a value into a [`Reflect`] trait object.
 pub type ReflectTransformer = for<'a> fn(UntypedPtrLt<'a>) -> &'a dyn Reflect;
 
+/// Function which transforms a value into a mutable [`Reflect`] trait object.
+pub type ReflectMutTransformer = for<'a> fn(UntypedPtrLt<'a>) -> &'a mut dyn Reflect;
+
+/// Registered reflectable type
+#[derive(Clone, Copy)]
+struct Registration {
+    reflect: ReflectTransformer,
+    reflect_mut: ReflectMutTransformer,
+}
+
+/// Functions which (de)serialize a registered component to and from RON, used by
+/// [`DynamicScene`](crate::assets::scene::DynamicScene)
+#[cfg(feature = "serialize")]
+#[derive(Clone, Copy)]
+pub struct ReflectSerialize {
+    /// Short type name the component is saved under
+    pub name: &'static str,
+    /// Serializes the reflected component
+    pub serialize: fn(&dyn Reflect) -> ron::Result<Box<ron::value::RawValue>>,
+    /// Deserializes the component into a reflected value
+    pub deserialize: fn(&ron::value::RawValue) -> ron::error::SpannedResult<Box<dyn Reflect>>,
+    /// Inserts a value returned by `deserialize` into an entity
+    pub insert: fn(&mut World, EntityId, Box<dyn Reflect>),
+}
+
 /// Type Registry for reflectable types. It is used to transform unknown components into
 /// [`Reflect`] trait objects.
 ///
 /// Use [`App::register_type`](crate::app::App) to register new types.
 pub struct ReflectTypeRegistry {
-    type_ids: HashMap<TypeId, ReflectTransformer>,
+    type_ids: HashMap<TypeId, Registration>,
+    /// Type ids by short type name, e.g. `Transform`
+    names: HashMap<&'static str, TypeId>,
+    #[cfg(feature = "serialize")]
+    serializable: HashMap<TypeId, ReflectSerialize>,
 }
 
 impl ReflectTypeRegistry {
@@ -20,25 +54,100 @@ impl ReflectTypeRegistry {
     pub fn new() -> Self {
         Self {
             type_ids: HashMap::new(),
+            names: HashMap::new(),
+            #[cfg(feature = "serialize")]
+            serializable: HashMap::new(),
         }
     }
 
     /// Register new reflectable type.
     pub fn register<T: Reflect>(&mut self) {
-        self.type_ids.insert(TypeId::of::<T>(), |value| unsafe {
-            value.as_ptr().cast::<T>().as_ref()
-        });
+        let registration = Registration {
+            reflect: |value| unsafe { value.as_ptr().cast::<T>().as_ref() },
+            reflect_mut: |value| unsafe { value.as_ptr().cast::<T>().as_mut() },
+        };
+
+        self.type_ids.insert(TypeId::of::<T>(), registration);
+        self.names.insert(short_type_name::<T>(), TypeId::of::<T>());
+    }
+
+    /// Register component `C` as reflectable and serializable, so it's saved and loaded with
+    /// [`DynamicScene`](crate::assets::scene::DynamicScene)s
+    #[cfg(feature = "serialize")]
+    pub fn register_serializable<C>(&mut self)
+    where
+        C: Component + Reflect + serde::Serialize + serde::de::DeserializeOwned,
+    {
+        self.register::<C>();
+        self.serializable.insert(
+            TypeId::of::<C>(),
+            ReflectSerialize {
+                name: short_type_name::<C>(),
+                serialize: |value| {
+                    let value = value
+                        .downcast_ref::<C>()
+                        .expect("Serialized value should be of the registered type");
+                    ron::value::RawValue::from_rust(value)
+                },
+                deserialize: |raw| Ok(Box::new(raw.into_rust::<C>()?)),
+                insert: |world, entity, value| {
+                    assert!(
+                        value.is::<C>(),
+                        "Inserted value should be of the registered type"
+                    );
+                    // Safety: the type was checked above
+                    let value = unsafe { Box::from_raw(Box::into_raw(value).cast::<C>()) };
+                    world.insert_component(entity, *value, true);
+                },
+            },
+        );
+    }
+
+    /// Returns the serialization functions of a type registered with
+    /// [`register_serializable`](Self::register_serializable)
+    #[cfg(feature = "serialize")]
+    pub fn get_serializable(&self, type_id: TypeId) -> Option<&ReflectSerialize> {
+        self.serializable.get(&type_id)
+    }
+
+    /// Returns the serialization functions of a serializable type by its short type name
+    #[cfg(feature = "serialize")]
+    pub fn get_serializable_by_name(&self, name: &str) -> Option<&ReflectSerialize> {
+        self.get_by_name(name)
+            .and_then(|type_id| self.serializable.get(&type_id))
     }
 
     /// Returns the [`ReflectTransformer`] for the given type id.
     pub fn get(&self, type_id: TypeId) -> Option<ReflectTransformer> {
-        self.type_ids.get(&type_id).copied()
+        self.type_ids
+            .get(&type_id)
+            .map(|registration| registration.reflect)
+    }
+
+    /// Returns the type id of a registered type by its name without the module path and generics,
+    /// e.g. `Transform`. If multiple types share the name, the last registered one is returned.
+    pub fn get_by_name(&self, name: &str) -> Option<TypeId> {
+        self.names.get(name).copied()
     }
 
     /// Reflects the given value if it is registered.
     pub fn reflect<'a>(&self, value: UntypedPtrLt<'a>, type_id: TypeId) -> Option<&'a dyn Reflect> {
         self.get(type_id).map(|transformer| transformer(value))
     }
+
+    /// Reflects the given value mutably if it is registered.
+    ///
+    /// # Safety
+    /// `value` must point to a value of type `type_id` which is not borrowed elsewhere.
+    pub unsafe fn reflect_mut<'a>(
+        &self,
+        value: UntypedPtrLt<'a>,
+        type_id: TypeId,
+    ) -> Option<&'a mut dyn Reflect> {
+        self.type_ids
+            .get(&type_id)
+            .map(|registration| (registration.reflect_mut)(value))
+    }
 }
 
 impl Default for ReflectTypeRegistry {
@@ -46,3 +155,10 @@ impl Default for ReflectTypeRegistry {
         Self::new()
     }
 }
+
+/// Returns the type name of `T` without the module path and generics
+pub(crate) fn short_type_name<T>() -> &'static str {
+    let name = type_name::<T>();
+    let name = name.split('<').next().unwrap_or(name);
+    name.rsplit("::").next().unwrap_or(name)
+}
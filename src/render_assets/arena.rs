@@ -0,0 +1,200 @@
+use std::num::NonZero;
+
+use bytemuck::NoUninit;
+
+use crate::renderer::newtype::{RenderDevice, RenderQueue};
+
+/// Per-frame uniform data of many objects in a single buffer, bound once with a dynamic offset
+/// for each object. Data is pushed every frame after a [`clear`](Self::clear), and uploaded with a
+/// single write by [`flush`](Self::flush), which grows the buffer if needed.
+///
+/// Elements are aligned to the device's `min_uniform_buffer_offset_alignment`, the bind group
+/// has one uniform binding of a single element. Pipelines should use
+/// [`create_layout`](Self::create_layout) for the matching bind group layout.
+///
+/// ```ignore
+/// arena.clear();
+/// let offsets = objects.iter().map(|object| arena.push(&object.uniform())).collect::<Vec<_>>();
+/// arena.flush(&device, &queue);
+///
+/// for offset in offsets {
+///     render_pass.set_bind_group(1, arena.bind_group(), &[offset]);
+///     render_pass.draw(..);
+/// }
+/// ```
+pub struct UniformArena {
+    name: String,
+    /// Size of a single element in bytes
+    element_size: usize,
+    /// Aligned distance between two elements in bytes
+    stride: usize,
+    /// Amount of elements which fit into the buffer
+    capacity: usize,
+    /// Elements pushed since the last clear
+    data: Vec<u8>,
+    visibility: wgpu::ShaderStages,
+    buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+impl UniformArena {
+    /// Create a new arena for `capacity` elements of `element_size` bytes
+    pub fn new(
+        name: &str,
+        element_size: usize,
+        capacity: usize,
+        device: &RenderDevice,
+        visibility: wgpu::ShaderStages,
+    ) -> Self {
+        if element_size == 0 {
+            panic!("Uniform arena '{}' cannot have empty elements", name);
+        }
+
+        let alignment = device.limits().min_uniform_buffer_offset_alignment as usize;
+        let stride = element_size.div_ceil(alignment) * alignment;
+        let capacity = capacity.max(1);
+        let (buffer, bind_group) =
+            Self::create_buffer(name, element_size, stride * capacity, device, visibility);
+
+        Self {
+            name: name.to_string(),
+            element_size,
+            stride,
+            capacity,
+            data: Vec::new(),
+            visibility,
+            buffer,
+            bind_group,
+        }
+    }
+
+    /// Create the bind group layout of an arena with `element_size` elements
+    pub fn create_layout(
+        device: &RenderDevice,
+        element_size: usize,
+        visibility: wgpu::ShaderStages,
+    ) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("uniform_arena_bind_group_layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: true,
+                    min_binding_size: NonZero::new(element_size as u64),
+                },
+                count: None,
+            }],
+        })
+    }
+
+    fn create_buffer(
+        name: &str,
+        element_size: usize,
+        size: usize,
+        device: &RenderDevice,
+        visibility: wgpu::ShaderStages,
+    ) -> (wgpu::Buffer, wgpu::BindGroup) {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!("{}_arena", name)),
+            size: size as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let layout = Self::create_layout(device, element_size, visibility);
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(&format!("{}_arena", name)),
+            layout: &layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &buffer,
+                    offset: 0,
+                    size: NonZero::new(element_size as u64),
+                }),
+            }],
+        });
+
+        (buffer, bind_group)
+    }
+
+    /// Remove all elements, usually at the start of a frame
+    #[inline]
+    pub fn clear(&mut self) {
+        self.data.clear();
+    }
+
+    /// Add an element and return its dynamic offset. It's uploaded on the next
+    /// [`flush`](Self::flush).
+    ///
+    /// # Panics
+    /// Panics if the size of `value` doesn't match the element size of the arena
+    pub fn push<A: NoUninit>(&mut self, value: &A) -> u32 {
+        let bytes = bytemuck::bytes_of(value);
+        assert_eq!(
+            bytes.len(),
+            self.element_size,
+            "Element size of uniform arena '{}' doesn't match",
+            self.name
+        );
+
+        let offset = self.data.len();
+        self.data.extend_from_slice(bytes);
+        self.data.resize(offset + self.stride, 0);
+        offset as u32
+    }
+
+    /// Upload the elements with a single write, the buffer and bind group are recreated if they
+    /// don't fit
+    pub fn flush(&mut self, device: &RenderDevice, queue: &RenderQueue) {
+        if self.data.is_empty() {
+            return;
+        }
+
+        let count = self.len();
+        if count > self.capacity {
+            self.capacity = count.next_power_of_two();
+            (self.buffer, self.bind_group) = Self::create_buffer(
+                &self.name,
+                self.element_size,
+                self.stride * self.capacity,
+                device,
+                self.visibility,
+            );
+        }
+
+        queue.write_buffer(&self.buffer, 0, &self.data);
+    }
+
+    /// Return the bind group, bound with the offsets returned by [`push`](Self::push)
+    #[inline]
+    pub fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+
+    /// Return the amount of elements pushed since the last clear
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.data.len() / self.stride
+    }
+
+    /// Return true if no elements were pushed since the last clear
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Return the aligned distance between two elements in bytes
+    #[inline]
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    /// Return the size of the buffer in bytes
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.stride * self.capacity
+    }
+}
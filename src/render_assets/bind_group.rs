@@ -1,16 +1,19 @@
-use std::num::NonZero;
+use std::{collections::HashMap, num::NonZero};
 
 use crate::assets::Handle;
+use crate::ecs::entities::EntityId;
 use crate::prelude::{Color, World};
 use crate::renderer::newtype::RenderDevice;
-use crate::renderer::{Image, SingleColorTexture, Texture};
+use crate::renderer::{Image, SingleColorTexture, Texture, is_texture_ready};
 
-use super::RenderAssets;
 use super::render_assets::RenderAssetEntry;
+use super::{IntoRenderAsset, RenderAssets};
 
 #[derive(crate::macros::RenderAsset)]
 pub struct BindGroup {
     pub(crate) inner: wgpu::BindGroup,
+    /// Id of the shared layout in [`BindGroupLayouts`], None if the layout isn't shared
+    pub(crate) layout_id: Option<u32>,
 }
 
 impl<'a> From<&'a BindGroup> for Option<&'a wgpu::BindGroup> {
@@ -23,6 +26,93 @@ impl BindGroup {
     pub fn build<'a>(label: &'a str) -> BindGroupBuilder<'a> {
         BindGroupBuilder::new(label)
     }
+
+    /// Returns the id of the shared layout, see [`BindGroupBuilder::finish_shared`]
+    #[inline]
+    pub fn layout_id(&self) -> Option<u32> {
+        self.layout_id
+    }
+}
+
+/// Types which are bound as a single bind group, e.g. materials. Their layout is known without an
+/// instance, so pipelines using them can be created up front.
+///
+/// Every type implementing it is an [`IntoRenderAsset<BindGroup>`], so its bind group is cached in
+/// the [`RenderAssets<BindGroup>`].
+pub trait AsBindGroup {
+    /// Layout entries of the bind group, they have to match the bind group created by
+    /// [`Self::as_bind_group`]
+    fn layout_entries() -> Vec<wgpu::BindGroupLayoutEntry>;
+
+    /// Create the bind group, it should be finished with [`BindGroupBuilder::finish_shared`] so
+    /// it shares its layout with the pipeline
+    fn as_bind_group(&self, world: &mut World) -> BindGroup;
+
+    /// Returns the shared layout of the bind group, created if it doesn't exist yet
+    fn bind_group_layout(
+        label: &str,
+        device: &RenderDevice,
+        layouts: &mut BindGroupLayouts,
+    ) -> wgpu::BindGroupLayout {
+        let (_, layout) = layouts.get_or_create(label, Self::layout_entries(), device);
+        layout.clone()
+    }
+}
+
+impl<T: AsBindGroup> IntoRenderAsset<BindGroup> for T {
+    fn create_render_asset(&self, world: &mut World, _: Option<EntityId>) -> BindGroup {
+        self.as_bind_group(world)
+    }
+}
+
+/// Deduplicated bind group layouts, bind groups with identical layout entries share one layout.
+/// Used as a resource.
+#[derive(Default, crate::macros::Resource)]
+pub struct BindGroupLayouts {
+    layouts: HashMap<Vec<wgpu::BindGroupLayoutEntry>, (u32, wgpu::BindGroupLayout)>,
+    requests: u64,
+}
+
+impl BindGroupLayouts {
+    /// Returns the id and layout for `entries`, the layout is created only if no identical one
+    /// exists
+    pub fn get_or_create(
+        &mut self,
+        label: &str,
+        mut entries: Vec<wgpu::BindGroupLayoutEntry>,
+        device: &RenderDevice,
+    ) -> (u32, &wgpu::BindGroupLayout) {
+        self.requests += 1;
+        entries.sort_by_key(|entry| entry.binding);
+
+        let next_id = self.layouts.len() as u32;
+        let (id, layout) = self.layouts.entry(entries).or_insert_with_key(|entries| {
+            let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries,
+                label: Some(&format!("{label}_bind_group_layout")),
+            });
+            (next_id, layout)
+        });
+
+        (*id, layout)
+    }
+
+    /// Returns the amount of unique layouts
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.layouts.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.layouts.is_empty()
+    }
+
+    /// Returns the amount of requested layouts which reused an existing one
+    #[inline]
+    pub fn deduplicated(&self) -> u64 {
+        self.requests - self.layouts.len() as u64
+    }
 }
 
 pub struct BindGroupBuilder<'a> {
@@ -84,6 +174,11 @@ impl<'a> BindGroupBuilder<'a> {
         sample_type: Option<wgpu::TextureSampleType>,
         sampler_bind: Option<wgpu::SamplerBindingType>,
     ) -> Self {
+        // images which are still loading or uploading are replaced by the default color
+        let texture = texture
+            .as_ref()
+            .filter(|texture| is_texture_ready(texture, world));
+
         if let Some(texture) = texture {
             let mut render_images = world.resources.get_mut::<RenderAssets<Texture>>();
             let texture = render_images.get_by_handle(texture, world);
@@ -203,10 +298,7 @@ impl<'a> BindGroupBuilder<'a> {
     }
 
     pub fn finish(self, device: &RenderDevice) -> BindGroup {
-        let (mut entries, mut layouts) = self.texture_layout_entries();
-
-        layouts.extend(self.layout_entries.clone());
-        entries.extend(self.entries.clone());
+        let (entries, layouts) = self.all_entries();
 
         let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             entries: &layouts,
@@ -219,6 +311,41 @@ impl<'a> BindGroupBuilder<'a> {
             label: Some(&format!("{}_bind_group", self.label)),
         });
 
-        BindGroup { inner: bind_group }
+        BindGroup {
+            inner: bind_group,
+            layout_id: None,
+        }
+    }
+
+    /// Same as [`Self::finish`], but the layout is shared with other bind groups with identical
+    /// layout entries
+    pub fn finish_shared(self, device: &RenderDevice, layouts: &mut BindGroupLayouts) -> BindGroup {
+        let (entries, layout_entries) = self.all_entries();
+        let (layout_id, layout) = layouts.get_or_create(self.label, layout_entries, device);
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &entries,
+            label: Some(&format!("{}_bind_group", self.label)),
+        });
+
+        BindGroup {
+            inner: bind_group,
+            layout_id: Some(layout_id),
+        }
+    }
+
+    fn all_entries(
+        &self,
+    ) -> (
+        Vec<wgpu::BindGroupEntry<'_>>,
+        Vec<wgpu::BindGroupLayoutEntry>,
+    ) {
+        let (mut entries, mut layouts) = self.texture_layout_entries();
+
+        layouts.extend(self.layout_entries.clone());
+        entries.extend(self.entries.clone());
+
+        (entries, layouts)
     }
 }
@@ -3,7 +3,8 @@ use wgpu::util::DeviceExt;
 
 use crate::renderer::newtype::RenderDevice;
 
-#[derive(crate::macros::RenderAsset)]
+use super::RenderAsset;
+
 pub struct Buffer {
     pub label: String,
     pub vertex: Option<wgpu::Buffer>,
@@ -14,6 +15,16 @@ pub struct Buffer {
     pub num_vertices: u32,
 }
 
+impl RenderAsset for Buffer {
+    fn gpu_size(&self) -> u64 {
+        [&self.vertex, &self.index, &self.uniform, &self.storage]
+            .into_iter()
+            .flatten()
+            .map(|buffer| buffer.size())
+            .sum()
+    }
+}
+
 impl Buffer {
     pub fn new(label: &str) -> Self {
         Self {
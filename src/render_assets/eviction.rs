@@ -0,0 +1,82 @@
+use crate::{macros::Resource, prelude::ResMut, renderer::Texture};
+
+use super::{BindGroup, Buffer, Pipeline, RenderAssets};
+
+/// Eviction policy of [`RenderAssets`]. The default policy never evicts anything.
+///
+/// Only assets created through the `get_by_*` methods are evicted. They are recreated on their
+/// next access, so eviction trades GPU memory for recreation cost.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EvictionPolicy {
+    /// Evict assets which were not used for more than this many frames
+    pub max_unused_frames: Option<u64>,
+    /// Evict the least recently used assets while the held GPU memory exceeds this many bytes
+    pub byte_budget: Option<u64>,
+}
+
+impl EvictionPolicy {
+    /// Policy which evicts assets unused for more than `frames` frames
+    pub fn unused_for(frames: u64) -> Self {
+        Self {
+            max_unused_frames: Some(frames),
+            byte_budget: None,
+        }
+    }
+
+    /// Policy which keeps the GPU memory of evictable assets under `bytes`
+    pub fn budget(bytes: u64) -> Self {
+        Self {
+            max_unused_frames: None,
+            byte_budget: Some(bytes),
+        }
+    }
+}
+
+/// Statistics of a single [`RenderAssets`] storage
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RenderAssetStats {
+    /// Amount of stored assets
+    pub count: usize,
+    /// Approximate GPU memory held by the stored assets in bytes
+    pub bytes: u64,
+    /// Total amount of evicted assets
+    pub evicted: u64,
+}
+
+/// GPU memory held per render asset type, updated at the end of every frame. Used as a resource.
+#[derive(Resource, Debug, Clone, Copy, Default)]
+pub struct RenderMemoryStats {
+    pub buffers: RenderAssetStats,
+    pub textures: RenderAssetStats,
+    pub bind_groups: RenderAssetStats,
+    pub pipelines: RenderAssetStats,
+}
+
+impl RenderMemoryStats {
+    /// Returns the approximate GPU memory held by all render assets in bytes
+    pub fn total_bytes(&self) -> u64 {
+        self.buffers.bytes + self.textures.bytes + self.bind_groups.bytes + self.pipelines.bytes
+    }
+}
+
+/// System which evicts unused render assets according to their [`EvictionPolicy`] and updates
+/// the [`RenderMemoryStats`]
+pub(crate) fn evict_render_assets_system(
+    mut buffers: ResMut<RenderAssets<Buffer>>,
+    mut textures: ResMut<RenderAssets<Texture>>,
+    mut bind_groups: ResMut<RenderAssets<BindGroup>>,
+    mut pipelines: ResMut<RenderAssets<Pipeline>>,
+    mut stats: ResMut<RenderMemoryStats>,
+) {
+    buffers.evict();
+    textures.evict();
+    bind_groups.evict();
+    pipelines.evict();
+
+    *stats = RenderMemoryStats {
+        buffers: buffers.stats(),
+        textures: textures.stats(),
+        bind_groups: bind_groups.stats(),
+        pipelines: pipelines.stats(),
+    };
+}
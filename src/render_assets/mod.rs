@@ -1,15 +1,28 @@
 mod render_assets;
+mod arena;
 mod buffer;
 mod bind_group;
 pub mod pipeline;
 mod render_handle;
 mod storage;
+mod staging;
+mod eviction;
 
 pub use render_assets::{RenderAssets, IntoRenderAsset, RenderAssetEntry};
 pub use buffer::Buffer;
-pub use bind_group::BindGroup;
-pub use pipeline::{StandardPipeline, Pipeline};
+pub use bind_group::{AsBindGroup, BindGroup, BindGroupBuilder, BindGroupLayouts};
+pub use pipeline::{StandardPipeline, Pipeline, PipelineCache, PipelineKey};
 pub use render_handle::RenderHandle;
 pub use storage::{Storage, TransformStorage};
+pub use arena::UniformArena;
+pub use staging::StagingBelt;
+pub use eviction::{EvictionPolicy, RenderAssetStats, RenderMemoryStats};
+pub(crate) use eviction::evict_render_assets_system;
 
-pub trait RenderAsset: Send + Sync + 'static {}
+pub trait RenderAsset: Send + Sync + 'static {
+    /// Approximate GPU memory held by the asset in bytes, used by the [`EvictionPolicy`] budget
+    /// and [`RenderMemoryStats`]
+    fn gpu_size(&self) -> u64 {
+        0
+    }
+}
@@ -1,8 +1,14 @@
-use crate::{assets::ShaderLoader, renderer::newtype::RenderDevice};
+use std::{
+    collections::HashMap,
+    panic::AssertUnwindSafe,
+    sync::{Arc, Mutex},
+};
+
+use crate::{assets::ShaderLoader, renderer::newtype::RenderDevice, system::ThreadPool};
 
 use super::RenderHandle;
 
-#[derive(crate::macros::RenderAsset)]
+#[derive(Clone, crate::macros::RenderAsset)]
 pub struct Pipeline {
     inner: wgpu::RenderPipeline,
 }
@@ -43,6 +49,9 @@ pub struct PipelineBuilder {
     pub primitive_state: wgpu::PrimitiveState,
     /// Depth stencil state for the pipeline.
     pub depth_stencil: Option<wgpu::DepthStencilState>,
+    /// Multisample count, has to match the targets of the render pass. Set by the render graph
+    /// for nodes affected by [`Msaa`](crate::core::graph::Msaa).
+    pub sample_count: u32,
 }
 
 impl PipelineBuilder {
@@ -58,6 +67,7 @@ impl PipelineBuilder {
             color_targets: Vec::new(),
             primitive_state: Self::default_primitive_state(),
             depth_stencil: None,
+            sample_count: 1,
         }
     }
 
@@ -196,6 +206,12 @@ impl PipelineBuilder {
         self
     }
 
+    /// Set the multisample count, default is 1
+    pub fn set_sample_count(mut self, sample_count: u32) -> Self {
+        self.sample_count = sample_count;
+        self
+    }
+
     /// Set push constant ranges for pipeline layout
     pub fn set_push_constant_ranges(mut self, ranges: Vec<wgpu::PushConstantRange>) -> Self {
         self.push_constant_ranges = ranges;
@@ -230,32 +246,89 @@ impl PipelineBuilder {
         }
     }
 
+    /// Resolve the shaders and return the key identifying the compiled pipeline in a
+    /// [`PipelineCache`]
+    pub fn key(&self, device: &RenderDevice, shader_loader: &ShaderLoader) -> PipelineKey {
+        let (vertex_module, vertex_entry) = self.load_shader(&self.vertex_shader, shader_loader);
+        let fragment = self
+            .load_shader_maybe(&self.fragment_shader, shader_loader)
+            .map(|(module, entry)| (module.clone(), entry));
+
+        PipelineKey {
+            device: wgpu::Device::clone(device),
+            bind_group_layouts: self.bind_group_layouts.clone(),
+            vertex_buffer_layouts: self.vertex_buffer_layouts.clone(),
+            vertex: (vertex_module.clone(), vertex_entry),
+            fragment,
+            push_constant_ranges: self.push_constant_ranges.clone(),
+            color_targets: self.color_targets.clone(),
+            primitive_state: self.primitive_state,
+            depth_stencil: self.depth_stencil.clone(),
+            sample_count: self.sample_count,
+        }
+    }
+
     /// Finish building the pipeline
     pub fn finish(&self, device: &RenderDevice, shader_loader: &ShaderLoader) -> Pipeline {
-        // shader modules
-        let (vertex_module, vertex_entry) = self.load_shader(&self.vertex_shader, &shader_loader);
-        let fragment_maybe = self.load_shader_maybe(&self.fragment_shader, &shader_loader);
+        self.key(device, shader_loader).create(&self.label)
+    }
+}
+
+/// Everything a compiled pipeline depends on, with the shaders resolved to their modules. Builders
+/// which only differ in their label have the same key.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct PipelineKey {
+    device: wgpu::Device,
+    bind_group_layouts: Option<Vec<wgpu::BindGroupLayout>>,
+    vertex_buffer_layouts: Vec<wgpu::VertexBufferLayout<'static>>,
+    vertex: (wgpu::ShaderModule, String),
+    fragment: Option<(wgpu::ShaderModule, String)>,
+    push_constant_ranges: Vec<wgpu::PushConstantRange>,
+    color_targets: Vec<Option<wgpu::ColorTargetState>>,
+    primitive_state: wgpu::PrimitiveState,
+    depth_stencil: Option<wgpu::DepthStencilState>,
+    sample_count: u32,
+}
+
+impl PipelineKey {
+    /// Returns true if pipelines of both keys can be used in place of each other, they may only
+    /// differ in their shader modules, e.g. after a shader reload
+    pub fn is_compatible(&self, other: &Self) -> bool {
+        self.device == other.device
+            && self.bind_group_layouts == other.bind_group_layouts
+            && self.vertex_buffer_layouts == other.vertex_buffer_layouts
+            && self.vertex.1 == other.vertex.1
+            && self.fragment.as_ref().map(|(_, entry)| entry)
+                == other.fragment.as_ref().map(|(_, entry)| entry)
+            && self.push_constant_ranges == other.push_constant_ranges
+            && self.color_targets == other.color_targets
+            && self.primitive_state == other.primitive_state
+            && self.depth_stencil == other.depth_stencil
+            && self.sample_count == other.sample_count
+    }
 
-        // pipeline layout
+    /// Compile the pipeline
+    fn create(&self, label: &str) -> Pipeline {
         let layout = self.bind_group_layouts.as_ref().map(|layouts| {
-            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: Some(&format!("{}_layout", self.label)),
-                bind_group_layouts: &layouts.iter().collect::<Vec<_>>(),
-                push_constant_ranges: &self.push_constant_ranges,
-            })
+            self.device
+                .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                    label: Some(&format!("{}_layout", label)),
+                    bind_group_layouts: &layouts.iter().collect::<Vec<_>>(),
+                    push_constant_ranges: &self.push_constant_ranges,
+                })
         });
 
-        // descriptor
         let pipeline_desc = wgpu::RenderPipelineDescriptor {
-            label: Some(&self.label),
+            label: Some(label),
             layout: layout.as_ref(),
             vertex: wgpu::VertexState {
-                module: vertex_module,
-                entry_point: Some(&vertex_entry),
+                module: &self.vertex.0,
+                entry_point: Some(&self.vertex.1),
                 buffers: self.vertex_buffer_layouts.as_ref(),
                 compilation_options: Default::default(),
             },
-            fragment: fragment_maybe
+            fragment: self
+                .fragment
                 .as_ref()
                 .map(|(module, entry)| wgpu::FragmentState {
                     module,
@@ -265,13 +338,147 @@ impl PipelineBuilder {
                 }),
             primitive: self.primitive_state,
             depth_stencil: self.depth_stencil.clone(),
-            multisample: wgpu::MultisampleState::default(),
+            multisample: wgpu::MultisampleState {
+                count: self.sample_count,
+                ..Default::default()
+            },
             multiview: None,
             cache: None,
         };
 
         Pipeline {
-            inner: device.create_render_pipeline(&pipeline_desc),
+            inner: self.device.create_render_pipeline(&pipeline_desc),
+        }
+    }
+}
+
+enum CachedPipeline {
+    Compiling(String),
+    Ready(Pipeline),
+}
+
+/// Pipelines finished by the compiler threads, taken by [`PipelineCache::poll`]
+type Compiled = Arc<Mutex<Vec<(PipelineKey, Option<Pipeline>)>>>;
+
+/// Cache of compiled pipelines by their [`PipelineKey`]. New pipelines are compiled on a
+/// background thread, on the web they are compiled on the calling thread.
+///
+/// The render graph takes the pipelines of its nodes from here, a node whose pipeline is still
+/// compiling keeps rendering with its previous [compatible](PipelineKey::is_compatible) one.
+#[derive(crate::macros::Resource)]
+pub struct PipelineCache {
+    pipelines: HashMap<PipelineKey, CachedPipeline>,
+    compiled: Compiled,
+    compiler: ThreadPool,
+}
+
+impl PipelineCache {
+    /// Create an empty cache
+    pub fn new() -> Self {
+        let threads = if cfg!(target_arch = "wasm32") { 0 } else { 1 };
+
+        Self {
+            pipelines: HashMap::new(),
+            compiled: Arc::default(),
+            compiler: ThreadPool::new(threads),
         }
     }
+
+    /// Start compiling the pipeline of `builder` if it's not cached yet, and return its key
+    pub fn queue(
+        &mut self,
+        builder: &PipelineBuilder,
+        device: &RenderDevice,
+        shader_loader: &ShaderLoader,
+    ) -> PipelineKey {
+        let key = builder.key(device, shader_loader);
+        self.queue_key(key.clone(), &builder.label);
+        key
+    }
+
+    /// Start compiling the pipeline of `key` if it's not cached yet
+    pub fn queue_key(&mut self, key: PipelineKey, label: &str) {
+        if self.pipelines.contains_key(&key) {
+            return;
+        }
+
+        self.pipelines
+            .insert(key.clone(), CachedPipeline::Compiling(label.to_string()));
+
+        let label = label.to_string();
+        let compiled = self.compiled.clone();
+        self.compiler.submit(Box::new(move || {
+            // a panic is reported on the thread which requests the pipeline
+            let pipeline = std::panic::catch_unwind(AssertUnwindSafe(|| key.create(&label)));
+            compiled.lock().unwrap().push((key, pipeline.ok()));
+        }));
+    }
+
+    /// Move finished pipelines into the cache
+    ///
+    /// # Panics
+    /// Panics if a pipeline failed to compile
+    pub fn poll(&mut self) {
+        let compiled = std::mem::take(&mut *self.compiled.lock().unwrap());
+        for (key, pipeline) in compiled {
+            let Some(pipeline) = pipeline else {
+                let label = match self.pipelines.remove(&key) {
+                    Some(CachedPipeline::Compiling(label)) => label,
+                    _ => String::new(),
+                };
+                panic!("Pipeline '{}' failed to compile", label);
+            };
+
+            self.pipelines.insert(key, CachedPipeline::Ready(pipeline));
+        }
+    }
+
+    /// Return the pipeline of `key` if it's compiled
+    pub fn get(&mut self, key: &PipelineKey) -> Option<&Pipeline> {
+        self.poll();
+        match self.pipelines.get(key)? {
+            CachedPipeline::Ready(pipeline) => Some(pipeline),
+            CachedPipeline::Compiling(_) => None,
+        }
+    }
+
+    /// Return the pipeline of `key`, compiling it first or waiting for its compilation to
+    /// finish
+    pub fn get_blocking(&mut self, key: PipelineKey, label: &str) -> &Pipeline {
+        self.queue_key(key.clone(), label);
+        while matches!(self.pipelines.get(&key), Some(CachedPipeline::Compiling(_))) {
+            std::thread::yield_now();
+            self.poll();
+        }
+
+        match &self.pipelines[&key] {
+            CachedPipeline::Ready(pipeline) => pipeline,
+            CachedPipeline::Compiling(_) => unreachable!(),
+        }
+    }
+
+    /// Return the amount of cached and compiling pipelines
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.pipelines.len()
+    }
+
+    /// Return true if there are no cached or compiling pipelines
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.pipelines.is_empty()
+    }
+
+    /// Drop all cached pipelines, after waiting for the ones which are still compiling
+    pub fn clear(&mut self) {
+        self.compiler.wait_all();
+        self.compiled.lock().unwrap().clear();
+        self.pipelines.clear();
+    }
+}
+
+impl Default for PipelineCache {
+    fn default() -> Self {
+        Self::new()
+    }
 }
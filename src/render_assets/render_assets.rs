@@ -6,7 +6,7 @@ use crate::{
     prelude::{Component, Res, ResMut, World},
 };
 
-use super::{RenderAsset, RenderHandle};
+use super::{EvictionPolicy, RenderAsset, RenderAssetStats, RenderHandle};
 
 pub trait IntoRenderAsset<R: RenderAsset> {
     fn create_render_asset(&self, world: &mut World, entity_id: Option<EntityId>) -> R;
@@ -21,6 +21,13 @@ impl<RA: RenderAsset> Clone for RenderAssetEntry<RA> {
     }
 }
 
+impl<RA: RenderAsset> RenderAssetEntry<RA> {
+    /// Wrap an asset which is not stored in [`RenderAssets`]
+    pub(crate) fn new(asset: RA) -> Self {
+        RenderAssetEntry(Arc::new(asset))
+    }
+}
+
 impl<RA: RenderAsset> Deref for RenderAssetEntry<RA> {
     type Target = RA;
 
@@ -64,6 +71,18 @@ pub struct RenderAssets<RA: RenderAsset> {
     entity_component_map: HashMap<EntityComponentId, RenderHandle<RA>>,
     resource_map: HashMap<ResourceId, RenderHandle<RA>>,
     next_id: u64,
+    /// Usage of assets created through the `get_by_*` methods, only these can be evicted
+    usage: HashMap<RenderHandle<RA>, AssetUsage>,
+    policy: EvictionPolicy,
+    frame: u64,
+    evicted: u64,
+}
+
+/// Last frame an asset was accessed in and the GPU memory it held at that time
+#[derive(Debug, Clone, Copy)]
+struct AssetUsage {
+    last_used: u64,
+    size: u64,
 }
 
 impl<RA: RenderAsset> Default for RenderAssets<RA> {
@@ -74,6 +93,10 @@ impl<RA: RenderAsset> Default for RenderAssets<RA> {
             entity_component_map: HashMap::new(),
             resource_map: HashMap::new(),
             next_id: 0,
+            usage: HashMap::new(),
+            policy: EvictionPolicy::default(),
+            frame: 0,
+            evicted: 0,
         }
     }
 }
@@ -83,6 +106,25 @@ impl<RA: RenderAsset> RenderAssets<RA> {
         Self::default()
     }
 
+    /// Create new render assets with an eviction `policy`
+    pub fn with_policy(policy: EvictionPolicy) -> Self {
+        Self {
+            policy,
+            ..Self::default()
+        }
+    }
+
+    #[inline]
+    pub fn policy(&self) -> &EvictionPolicy {
+        &self.policy
+    }
+
+    /// Set the eviction policy, applied on the next [`Self::evict`]
+    #[inline]
+    pub fn set_policy(&mut self, policy: EvictionPolicy) {
+        self.policy = policy;
+    }
+
     fn step_id(&mut self) -> RenderHandle<RA> {
         let id = self.next_id;
         self.next_id += 1;
@@ -109,21 +151,24 @@ impl<RA: RenderAsset> RenderAssets<RA> {
         C: Component + IntoRenderAsset<RA>,
     {
         let entity_component_id = (entity_id, component).into();
-
-        let rae = match self.entity_component_map.get(&entity_component_id) {
-            Some(key) => self
-                .storage
-                .entry(key.clone())
-                .or_insert_with(|| Arc::new(component.create_render_asset(world, Some(entity_id)))),
+        let key = match self.entity_component_map.get(&entity_component_id) {
+            Some(key) => key.clone(),
             None => {
-                let key = self.insert(component.create_render_asset(world, Some(entity_id)));
+                let key = self.step_id();
                 self.entity_component_map
                     .insert(entity_component_id, key.clone());
-                self.storage.get(&key).unwrap()
+                key
             }
         };
 
-        RenderAssetEntry(rae.clone())
+        let rae = self
+            .storage
+            .entry(key.clone())
+            .or_insert_with(|| Arc::new(component.create_render_asset(world, Some(entity_id))))
+            .clone();
+
+        self.mark_used(key, &rae);
+        RenderAssetEntry(rae)
     }
 
     pub fn get_by_handle<A>(
@@ -135,20 +180,23 @@ impl<RA: RenderAsset> RenderAssets<RA> {
         A: Asset + IntoRenderAsset<RA>,
     {
         let asset_handle_id = handle.into();
-
-        let rae = match self.handle_map.get(&asset_handle_id) {
-            Some(key) => self
-                .storage
-                .entry(key.clone())
-                .or_insert_with(|| Arc::new(Self::create_asset(handle, world))),
+        let key = match self.handle_map.get(&asset_handle_id) {
+            Some(key) => key.clone(),
             None => {
-                let key = self.insert(Self::create_asset(handle, world));
+                let key = self.step_id();
                 self.handle_map.insert(asset_handle_id, key.clone());
-                self.storage.get(&key).unwrap()
+                key
             }
         };
 
-        RenderAssetEntry(rae.clone())
+        let rae = self
+            .storage
+            .entry(key.clone())
+            .or_insert_with(|| Arc::new(Self::create_asset(handle, world)))
+            .clone();
+
+        self.mark_used(key, &rae);
+        RenderAssetEntry(rae)
     }
 
     pub fn get_by_resource<R>(
@@ -185,25 +233,95 @@ impl<RA: RenderAsset> RenderAssets<RA> {
         R: Resource + IntoRenderAsset<RA>,
     {
         let resource_id = resource.into();
-
-        let rae = match self.resource_map.get(&resource_id) {
+        let key = match self.resource_map.get(&resource_id) {
             Some(key) => {
                 if replace {
                     self.storage.remove(key);
                 }
-
-                self.storage
-                    .entry(key.clone())
-                    .or_insert_with(|| Arc::new(resource.create_render_asset(world, None)))
+                key.clone()
             }
             None => {
-                let key = self.insert(resource.create_render_asset(world, None));
+                let key = self.step_id();
                 self.resource_map.insert(resource_id, key.clone());
-                self.storage.get(&key).unwrap()
+                key
             }
         };
 
-        RenderAssetEntry(rae.clone())
+        let rae = self
+            .storage
+            .entry(key.clone())
+            .or_insert_with(|| Arc::new(resource.create_render_asset(world, None)))
+            .clone();
+
+        self.mark_used(key, &rae);
+        RenderAssetEntry(rae)
+    }
+
+    fn mark_used(&mut self, key: RenderHandle<RA>, asset: &RA) {
+        let usage = AssetUsage {
+            last_used: self.frame,
+            size: asset.gpu_size(),
+        };
+        self.usage.insert(key, usage);
+    }
+
+    /// Apply the eviction [policy](EvictionPolicy) and advance to the next frame, called once per
+    /// frame by the [`RenderPlugin`](crate::plugins::RenderPlugin).
+    ///
+    /// Evicted assets are only dropped from the storage, their keys are kept so they are recreated
+    /// transparently on their next access. Assets used in the current frame, assets still
+    /// referenced elsewhere, and assets added with [`Self::insert`] are never evicted.
+    pub fn evict(&mut self) {
+        let frame = self.frame;
+        self.frame += 1;
+
+        if self.policy.max_unused_frames.is_none() && self.policy.byte_budget.is_none() {
+            return;
+        }
+
+        let mut candidates = self
+            .usage
+            .iter()
+            .filter(|(key, usage)| {
+                usage.last_used < frame
+                    && self
+                        .storage
+                        .get(*key)
+                        .is_some_and(|asset| Arc::strong_count(asset) == 1)
+            })
+            .map(|(key, usage)| (key.clone(), *usage))
+            .collect::<Vec<_>>();
+
+        // Least recently used first
+        candidates.sort_by_key(|(_, usage)| usage.last_used);
+
+        let mut bytes = self.usage.values().map(|usage| usage.size).sum::<u64>();
+        for (key, usage) in candidates {
+            let expired = self
+                .policy
+                .max_unused_frames
+                .is_some_and(|max| frame - usage.last_used > max);
+            let over_budget = self.policy.byte_budget.is_some_and(|budget| bytes > budget);
+
+            if !expired && !over_budget {
+                // Remaining candidates are used more recently
+                break;
+            }
+
+            self.storage.remove(&key);
+            self.usage.remove(&key);
+            bytes -= usage.size;
+            self.evicted += 1;
+        }
+    }
+
+    /// Returns the amount of stored assets and the GPU memory held by them
+    pub fn stats(&self) -> RenderAssetStats {
+        RenderAssetStats {
+            count: self.storage.len(),
+            bytes: self.storage.values().map(|asset| asset.gpu_size()).sum(),
+            evicted: self.evicted,
+        }
     }
 
     fn create_asset<A>(handle: &Handle<A>, world: &mut World) -> RA
@@ -215,9 +333,20 @@ impl<RA: RenderAsset> RenderAssets<RA> {
         asset.create_render_asset(world, None)
     }
 
+    /// Removes all render assets, they are recreated on their next access. Handles returned by
+    /// [`Self::insert`] become invalid.
+    pub fn clear(&mut self) {
+        self.storage.clear();
+        self.handle_map.clear();
+        self.entity_component_map.clear();
+        self.resource_map.clear();
+        self.usage.clear();
+    }
+
     pub fn remove<A: Asset>(&mut self, handle: &Handle<A>) -> Option<Arc<RA>> {
         // TODO: should we remove both the handle and the asset?
         let key = self.handle_map.remove(&handle.into())?;
+        self.usage.remove(&key);
         self.storage.remove(&key)
     }
 
@@ -229,6 +358,7 @@ impl<RA: RenderAsset> RenderAssets<RA> {
     ) -> Option<Arc<RA>> {
         let entity_component_id = (entity_id, component).into();
         let key = self.entity_component_map.remove(&entity_component_id)?;
+        self.usage.remove(&key);
         self.storage.remove(&key)
     }
 }
@@ -0,0 +1,81 @@
+use std::num::NonZero;
+
+use crate::{
+    macros::Resource,
+    renderer::newtype::{RenderCommandEncoder, RenderDevice, RenderQueue},
+};
+
+/// Per-frame buffer uploads through persistently reused, mapped staging buffers. Writes are
+/// copied into the staging memory and recorded as buffer copies into a single upload encoder,
+/// which is submitted before the rendering commands of the frame. The staging buffers are
+/// recalled and remapped once the GPU is done with them, so steady per-frame uploads don't
+/// allocate.
+///
+/// Use it instead of [`wgpu::Queue::write_buffer`] for data that is uploaded every frame, the
+/// target buffer needs [`wgpu::BufferUsages::COPY_DST`].
+#[derive(Resource)]
+pub struct StagingBelt {
+    belt: wgpu::util::StagingBelt,
+    /// Encoder with the copies recorded since the last submit
+    encoder: Option<RenderCommandEncoder>,
+}
+
+impl StagingBelt {
+    /// Size of a single staging buffer in bytes, larger writes get their own buffer
+    pub const CHUNK_SIZE: u64 = 1 << 20;
+
+    /// Create a new staging belt with [`CHUNK_SIZE`](Self::CHUNK_SIZE) chunks
+    pub fn new() -> Self {
+        Self {
+            belt: wgpu::util::StagingBelt::new(Self::CHUNK_SIZE),
+            encoder: None,
+        }
+    }
+
+    /// Schedule a write of `data` into `target` at `offset`, it's applied at the start of the next
+    /// submit. Empty writes are ignored.
+    ///
+    /// # Panics
+    /// Panics if the `data` length or `offset` isn't a multiple of
+    /// [`wgpu::COPY_BUFFER_ALIGNMENT`]
+    pub fn write(
+        &mut self,
+        target: &wgpu::Buffer,
+        offset: wgpu::BufferAddress,
+        data: &[u8],
+        device: &RenderDevice,
+    ) {
+        let Some(size) = NonZero::new(data.len() as u64) else {
+            return;
+        };
+
+        let encoder = self
+            .encoder
+            .get_or_insert_with(|| RenderCommandEncoder::new(device, "Staging Belt Encoder"));
+        self.belt
+            .write_buffer(encoder, target, offset, size, device)
+            .copy_from_slice(data);
+    }
+
+    /// Submit the recorded copies, followed by `commands`, and recall the staging buffers
+    pub(crate) fn submit(
+        &mut self,
+        queue: &RenderQueue,
+        commands: impl Iterator<Item = wgpu::CommandBuffer>,
+    ) {
+        let Some(encoder) = self.encoder.take() else {
+            queue.submit(commands);
+            return;
+        };
+
+        self.belt.finish();
+        queue.submit(std::iter::once(encoder.unwrap().finish()).chain(commands));
+        self.belt.recall();
+    }
+}
+
+impl Default for StagingBelt {
+    fn default() -> Self {
+        Self::new()
+    }
+}
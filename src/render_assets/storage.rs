@@ -2,10 +2,9 @@ use std::ops::{Deref, DerefMut};
 
 use bytemuck::{AnyBitPattern, NoUninit};
 
-use crate::renderer::newtype::RenderQueue;
 use crate::{macros::Resource, renderer::newtype::RenderDevice};
 
-use super::{BindGroup, Buffer};
+use super::{BindGroup, Buffer, StagingBelt};
 
 /// Special kind of `RenderAsset` which is stored as a global Resource
 /// Contains a buffer with instanced transform (or any kind of) data, and the bind group for it
@@ -80,7 +79,7 @@ impl Storage {
         self.count = new.count;
     }
 
-    /// Update the buffer with new data
+    /// Update the buffer with new data, uploaded through the staging belt
     /// Resizes the buffer if the data is larger than the current buffer size
     ///
     /// # Note
@@ -95,7 +94,7 @@ impl Storage {
         data: &[A],
         count: usize,
         device: &RenderDevice,
-        queue: &RenderQueue,
+        belt: &mut StagingBelt,
     ) where
         A: NoUninit + AnyBitPattern,
     {
@@ -117,7 +116,7 @@ impl Storage {
         }
 
         let buffer = self.buffer();
-        queue.write_buffer(buffer, 0, data);
+        belt.write(buffer, 0, data, device);
     }
 
     /// Return the storage buffer
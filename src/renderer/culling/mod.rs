@@ -3,22 +3,34 @@
 //!
 //! For settings, see [`FrustumCullingSettings`].
 //!
-//! By default, each entity with a mesh component will have [`LocalBoundingVolume::Sphere`], and
-//! default `WorldBoundingVolume` and `Visibility` components added to it. If any of these get
-//! removed, they will be readded. Currently, changes on mesh or LBV won't trigger a recalculation.
-//! Only a direct change in response to `Query<&mut Handle<Mesh>>` will trigger it.
+//! By default, each entity with a mesh component and no [`LocalBoundingVolume`] will have
+//! [`LocalBoundingVolume::Sphere`] added to it, which requires default `WorldBoundingVolume` and
+//! `Visibility` components. A user provided LBV is never overwritten, so changes on the mesh won't
+//! trigger a recalculation, remove the LBV to have it recalculated.
 //!
 //! Every entity with [`LocalBoundingVolume`], [`Visibility`] and [`WorldBoundingVolume`]
 //! components will have their WBV and Visibility recalculated on `GlobalTransform` or
 //! `LocalBoundingVolume` change.
 //!
 //! All active cameras in the scene will have a [`Frustum`] component added to it, it will be
-//! recalculated on `GlobalTransform` change. If the camera's `Frustum` changes, all entities will
-//! have their `Visibility` recalculated.
+//! recalculated on `GlobalTransform` change. If a camera's `Frustum` changes, or a camera is
+//! (de)activated, all entities will have their `Visibility` recalculated.
+//!
+//! Visibility is tracked per camera as a bitmask, every active camera gets a slot in the
+//! [`VisibilityCameras`] resource. Every camera, e.g. in split-screen or rendering to a texture,
+//! draws only the entities inside of its own frustum, and a moving camera only updates its own
+//! bit. Shadow passes are never culled by camera frustums.
+//!
+//! Entities with a [`VisibilityRange`] are also culled by their distance to the camera, so distant
+//! clutter is skipped without a full LOD setup. The standard shader fades them in and out over
+//! [`VisibilityRange::fade`] with a dither pattern, which works even if culling is disabled.
 //!
 //! For more information, see [`FrustumCullingPlugin`].
 
+use glam::Vec3;
+
 use crate::{
+    macros::Reflect,
     math::bounding_volume::{
         Frustum, LocalBoundingVolume, Sphere, ToWorldSpace, WorldBoundingVolume,
     },
@@ -32,6 +44,7 @@ pub struct FrustumCullingPlugin;
 impl Plugin for FrustumCullingPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<FrustumCullingSettings>()
+            .init_resource::<VisibilityCameras>()
             // These two use `commands.insert`, so we need them in separate phases to apply
             .register_system(add_local_bounding_volume_system, phase::PostUpdate)
             .register_system(update_camera_frustum_system, phase::Last)
@@ -55,51 +68,237 @@ impl Default for FrustumCullingSettings {
     }
 }
 
-#[derive(Component)]
-/// This component indicates whether an entity is visible in the frustum.
+#[derive(Resource, Default)]
+/// Active cameras which entities are culled against, each camera has a stable slot which is its
+/// bit in the [`Visibility`] mask. Empty if frustum culling is disabled. Used as a resource.
+pub struct VisibilityCameras {
+    slots: Vec<Option<EntityId>>,
+}
+
+impl VisibilityCameras {
+    /// Maximum amount of cameras with their own visibility, the size of the [`Visibility`] mask
+    pub const MAX_CAMERAS: usize = 64;
+
+    /// Returns the slot index of `camera`, or None if it doesn't have one
+    pub fn index_of(&self, camera: EntityId) -> Option<u32> {
+        self.slots
+            .iter()
+            .position(|slot| *slot == Some(camera))
+            .map(|index| index as u32)
+    }
+
+    /// Returns the slot index and id of every camera
+    pub fn iter(&self) -> impl Iterator<Item = (u32, EntityId)> + '_ {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(index, slot)| slot.map(|camera| (index as u32, camera)))
+    }
+
+    /// Frees the slots of cameras which are not in `cameras` and assigns slots to the new ones.
+    /// Returns the masks of the added and removed slots.
+    fn sync(&mut self, cameras: &[EntityId]) -> (u64, u64) {
+        let mut removed = 0;
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            if slot.is_some_and(|camera| !cameras.contains(&camera)) {
+                *slot = None;
+                removed |= 1 << index;
+            }
+        }
+
+        let mut added = 0;
+        for &camera in cameras {
+            if self.index_of(camera).is_some() {
+                continue;
+            }
+
+            let index = match self.slots.iter().position(Option::is_none) {
+                Some(index) => index,
+                None if self.slots.len() < Self::MAX_CAMERAS => {
+                    self.slots.push(None);
+                    self.slots.len() - 1
+                }
+                None => {
+                    tracing::warn!(
+                        "More than {} active cameras, camera {:?} is not culled",
+                        Self::MAX_CAMERAS,
+                        camera
+                    );
+                    continue;
+                }
+            };
+
+            self.slots[index] = Some(camera);
+            added |= 1 << index;
+        }
+
+        // trailing free slots are dropped
+        while self.slots.last().is_some_and(Option::is_none) {
+            self.slots.pop();
+        }
+
+        (added, removed)
+    }
+}
+
+#[derive(Component, Default)]
+/// This component indicates in which cameras' frustums an entity is visible, as a bitmask of
+/// their [`VisibilityCameras`] slots.
 /// Shouldn't be used directly, it's used as an internal cache for the culling system.
 pub struct Visibility {
-    pub visible: bool,
+    mask: u64,
 }
 
 impl Visibility {
-    pub fn new(visible: bool) -> Self {
-        Self { visible }
+    /// Returns true if the entity is visible in any camera
+    pub fn is_visible(&self) -> bool {
+        self.mask != 0
     }
 
-    pub fn is_visible(&self) -> bool {
-        self.visible
+    /// Returns true if the entity is visible in the camera with slot `index`, see
+    /// [`VisibilityCameras::index_of`]
+    pub fn is_visible_at(&self, index: u32) -> bool {
+        index < 64 && self.mask & (1 << index) != 0
+    }
+
+    /// Returns the visibility mask, with a bit for every camera slot
+    pub fn mask(&self) -> u64 {
+        self.mask
+    }
+
+    /// Recalculates the bits of `frustums`, other bits in `keep` are left untouched. If the entity
+    /// has a `range` and a `position`, it's also culled by its distance to each camera.
+    fn update(
+        &mut self,
+        world_bv: &WorldBoundingVolume,
+        range: Option<(&VisibilityRange, Vec3)>,
+        frustums: &[(u32, CameraFrustum)],
+        keep: u64,
+    ) {
+        self.mask &= keep;
+        for (index, camera) in frustums {
+            let in_range = range
+                .is_none_or(|(range, position)| range.contains(camera.position.distance(position)));
+
+            if in_range && camera.frustum.intersects(world_bv) {
+                self.mask |= 1 << index;
+            }
+        }
     }
 }
 
-/// This system updates the `Visibility` component of all entities in the scene if the camera has
-/// its `Frustum` changed.
+/// This component limits the distance from the camera at which an entity is drawn, it's culled
+/// outside of `min..=max`. Meant for distant clutter which doesn't need a full LOD setup.
+///
+/// The distance is measured from the entity's `GlobalTransform` translation.
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq)]
+pub struct VisibilityRange {
+    pub min: f32,
+    pub max: f32,
+    /// Distance over which the standard shader fades the entity out before `max`, and in after
+    /// `min` if it's above 0, with a dither pattern. 0 for a hard cut
+    pub fade: f32,
+}
+
+impl VisibilityRange {
+    /// Creates a new range visible up to `max` without fading
+    pub fn new(max: f32) -> Self {
+        Self {
+            min: 0.0,
+            max,
+            fade: 0.0,
+        }
+    }
+
+    /// Returns self with new `min`
+    pub fn with_min(mut self, min: f32) -> Self {
+        self.min = min;
+        self
+    }
+
+    /// Returns self with new `fade`
+    pub fn with_fade(mut self, fade: f32) -> Self {
+        self.fade = fade;
+        self
+    }
+
+    /// Returns true if `distance` is inside of the range
+    pub fn contains(&self, distance: f32) -> bool {
+        self.min <= distance && distance <= self.max
+    }
+}
+
+/// Frustum and position of an active camera
+struct CameraFrustum {
+    frustum: Frustum,
+    position: Vec3,
+}
+
+/// Returns the frustums of all active cameras
+fn active_frustums(
+    mut cameras: Query<(EntityId, &Camera, &Frustum, &GlobalTransform)>,
+) -> Vec<(EntityId, CameraFrustum)> {
+    cameras
+        .iter_mut()
+        .into_iter()
+        .filter(|(_, camera, ..)| camera.active)
+        .map(|(id, _, frustum, global_transform)| {
+            let camera = CameraFrustum {
+                frustum: frustum.clone(),
+                position: global_transform.translation(),
+            };
+            (id, camera)
+        })
+        .collect()
+}
+
+/// This system updates the `Visibility` component of all entities in the scene if a camera has
+/// its `Frustum` changed, or the set of active cameras changed. Only the bits of those cameras
+/// are recalculated.
 pub fn frustum_visibility_update_system(
     settings: Res<FrustumCullingSettings>,
-    mut query: Query<(&WorldBoundingVolume, &mut Visibility)>,
+    mut cameras: ResMut<VisibilityCameras>,
+    mut query: Query<(
+        &WorldBoundingVolume,
+        &mut Visibility,
+        Option<&VisibilityRange>,
+        Option<&GlobalTransform>,
+    )>,
 ) {
     // early exit based on settings
     if !settings.enabled {
+        if !cameras.slots.is_empty() {
+            cameras.slots.clear();
+        }
         return;
     }
 
-    // extract the active camera
-    let active_camera = query
-        .cast::<(&Camera, &Frustum), Changed<Frustum>>()
+    let frustums = active_frustums(query.cast());
+    let ids = frustums.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+    let (added, removed) = cameras.sync(&ids);
+
+    // slots of the cameras with a changed frustum
+    let changed = query
+        .cast::<(EntityId, &Camera), Changed<Frustum>>()
         .iter_mut()
         .into_iter()
-        .find(|(camera, _)| camera.active);
+        .filter(|(_, camera)| camera.active)
+        .filter_map(|(id, _)| cameras.index_of(id))
+        .fold(added, |mask, index| mask | 1 << index);
 
-    let Some((_, frustum)) = active_camera else {
+    if changed == 0 && removed == 0 {
         return;
-    };
+    }
 
-    for (world_bv, visibility) in query.iter_mut() {
-        // check for intersections
-        let visible = frustum.intersects(world_bv);
+    let frustums = frustums
+        .into_iter()
+        .filter_map(|(id, frustum)| Some((cameras.index_of(id)?, frustum)))
+        .filter(|(index, _)| changed & (1 << index) != 0)
+        .collect::<Vec<_>>();
 
-        // update visibility
-        visibility.visible = visible;
+    for (world_bv, visibility, range, global_transform) in query.iter_mut() {
+        let range = range.zip(global_transform.map(GlobalTransform::translation));
+        visibility.update(world_bv, range, &frustums, !(changed | removed));
     }
 }
 
@@ -146,22 +345,14 @@ pub fn update_camera_frustum_system(
     }
 }
 
-/// This system (re)adds a `LocalBoundingVolume::Sphere` to all entities with a `Mesh` component.
-/// It also adds default `WorldBoundingVolume::None` and `Visibility::new(false)`. All of these
-/// components are added only if they don't exist (even if they got removed).
+/// This system adds a `LocalBoundingVolume::Sphere` to all entities with a `Mesh` component which
+/// don't have one yet. The required `WorldBoundingVolume::None` and `Visibility::default()` are
+/// added with it.
 pub fn add_local_bounding_volume_system(
     settings: Res<FrustumCullingSettings>,
     mesh_assets: Res<Assets<Mesh>>,
     mut commands: Commands,
-    mut query: Query<
-        (EntityId, &Handle<Mesh>),
-        Or<(
-            Without<LocalBoundingVolume>,
-            Without<WorldBoundingVolume>,
-            Without<Visibility>,
-            Changed<Handle<Mesh>>,
-        )>,
-    >,
+    mut query: Query<(EntityId, &Handle<Mesh>), Without<LocalBoundingVolume>>,
 ) {
     // early exit based on settings
     if !settings.enabled {
@@ -169,31 +360,39 @@ pub fn add_local_bounding_volume_system(
     }
 
     for (id, mesh_handle) in query.iter_mut() {
-        // get the mesh
-        let mesh = mesh_assets.get(mesh_handle).unwrap();
+        // meshes loaded with `load_async` get their volume once they are loaded
+        let Some(mesh) = mesh_assets.get(mesh_handle) else {
+            continue;
+        };
 
         // add the local bounding volume
         let sphere = Sphere::from_mesh(mesh);
         commands
             .entity(id)
-            .insert_if_new(LocalBoundingVolume::Sphere(sphere))
-            .insert_if_new(WorldBoundingVolume::None)
-            .insert_if_new(Visibility::new(false));
+            .entry::<LocalBoundingVolume>()
+            .or_insert(LocalBoundingVolume::Sphere(sphere));
     }
 }
 
-/// This system gets entities with `local bounding volume` where either `GlobalTransform` or
-/// `LocalBoundingVolume` has changed, and updates the `WorldBoundingVolume` and `Visibility`.
+/// This system gets entities with `local bounding volume` where either `GlobalTransform`,
+/// `LocalBoundingVolume` or `VisibilityRange` has changed, and updates the `WorldBoundingVolume`
+/// and `Visibility`.
 pub fn visibility_update_system(
     settings: Res<FrustumCullingSettings>,
+    cameras: Res<VisibilityCameras>,
     mut query: Query<
         (
             &LocalBoundingVolume,
             &mut WorldBoundingVolume,
             &GlobalTransform,
             &mut Visibility,
+            Option<&VisibilityRange>,
         ),
-        Or<(Changed<GlobalTransform>, Changed<LocalBoundingVolume>)>,
+        Or<(
+            Changed<GlobalTransform>,
+            Changed<LocalBoundingVolume>,
+            Changed<VisibilityRange>,
+        )>,
     >,
 ) {
     // early exit based on settings
@@ -201,25 +400,17 @@ pub fn visibility_update_system(
         return;
     }
 
-    // extract the active camera
-    let active_camera = query
-        .cast::<(&Camera, &Frustum), ()>()
-        .iter_mut()
+    let frustums = active_frustums(query.cast())
         .into_iter()
-        .find(|(camera, _)| camera.active);
+        .filter_map(|(id, frustum)| Some((cameras.index_of(id)?, frustum)))
+        .collect::<Vec<_>>();
 
-    let Some((_, frustum)) = active_camera else {
-        return;
-    };
-
-    for (local_bv, world_bv, global_transform, visibility) in query.iter_mut() {
+    for (local_bv, world_bv, global_transform, visibility, range) in query.iter_mut() {
         // update world bounding volume
         *world_bv = local_bv.to_world_space(&global_transform.matrix);
 
-        // check for intersections
-        let visible = frustum.intersects(world_bv);
-
-        // update visibility
-        visibility.visible = visible;
+        // check for intersections with every culled camera
+        let range = range.map(|range| (range, global_transform.translation()));
+        visibility.update(world_bv, range, &frustums, 0);
     }
 }
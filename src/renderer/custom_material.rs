@@ -0,0 +1,317 @@
+//! This module lets users define their own material types with a custom WGSL shader, instead of
+//! the fixed [`Material`].
+//!
+//! A custom material is an [`Asset`] implementing [`AsBindGroup`] and [`CustomMaterial`]. Every
+//! material type gets its own `material_<type>` graph node with a pipeline specialized for it,
+//! added by the [`MaterialPlugin`] of that type. Entities with a [`Handle`] to the material, a
+//! [`Handle<Mesh>`] and a [`GlobalTransform`] are drawn by it, after the main pass.
+//!
+//! ## Shader interface
+//!
+//! - `@group(0)` is the material bind group, as described by [`AsBindGroup::layout_entries`]
+//! - `@group(1) @binding(0)` is the camera uniform, see `shaders/shader.wgsl`
+//! - the model matrix is a `mat4x4<f32>` vertex push constant at offset 0
+//! - vertices have the [`Mesh::vertex_descriptor`] layout
+//!
+//! ```ignore
+//! #[derive(Asset)]
+//! struct GlowMaterial { color: Color }
+//!
+//! impl AsBindGroup for GlowMaterial {
+//!     fn layout_entries() -> Vec<wgpu::BindGroupLayoutEntry> {
+//!         vec![/* uniform buffer at binding 0 */]
+//!     }
+//!
+//!     fn as_bind_group(&self, world: &mut World) -> BindGroup {
+//!         let buffer = Buffer::new("glow").create_uniform_buffer(/* .. */);
+//!         BindGroup::build("glow")
+//!             .add_uniform_buffer(buffer.uniform.as_ref().unwrap(), wgpu::ShaderStages::FRAGMENT)
+//!             .finish_shared(&world.resources.get(), &mut world.resources.get_mut())
+//!     }
+//! }
+//!
+//! impl CustomMaterial for GlowMaterial {
+//!     fn shader() -> &'static str {
+//!         include_str!("glow.wgsl")
+//!     }
+//! }
+//!
+//! app.add_plugin(MaterialPlugin::<GlowMaterial>::default());
+//! ```
+
+use std::{any::type_name, marker::PhantomData};
+
+use crate::{
+    assets::{Asset, ShaderLoader},
+    core::{graph::*, standard::rendering::surface_size},
+    plugins::RenderPlugin,
+    prelude::*,
+    render_assets::{
+        AsBindGroup, BindGroup, BindGroupLayouts, Buffer, Pipeline, RenderAssets,
+        pipeline::PipelineBuilder,
+    },
+    renderer::{
+        culling::Visibility,
+        newtype::{RenderCommandEncoder, RenderDevice, RenderSurfaceConfiguration},
+    },
+};
+
+/// Material type with its own shader, drawn by its [`MaterialPlugin`]. For more information, see
+/// the [custom material module](crate::renderer::custom_material).
+pub trait CustomMaterial: Asset + AsBindGroup {
+    /// WGSL source of the material shader, loaded through the [`ShaderLoader`]
+    fn shader() -> &'static str;
+
+    /// Entry point of the vertex shader
+    fn vertex_entry() -> &'static str {
+        "vs_main"
+    }
+
+    /// Entry point of the fragment shader
+    fn fragment_entry() -> &'static str {
+        "fs_main"
+    }
+
+    /// Customize the pipeline of the material type, e.g. its blending or culling. The bind group
+    /// layouts, vertex layout and push constants should be kept.
+    fn specialize(builder: PipelineBuilder) -> PipelineBuilder {
+        builder
+    }
+}
+
+/// Plugin which adds the [`Assets`] and the graph node drawing the material type `M`. For more
+/// information, see the [custom material module](crate::renderer::custom_material).
+pub struct MaterialPlugin<M: CustomMaterial>(PhantomData<M>);
+
+impl<M: CustomMaterial> Default for MaterialPlugin<M> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<M: CustomMaterial> Plugin for MaterialPlugin<M> {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Assets<M>>()
+            .add_startup_system(register_material_graph::<M>);
+    }
+
+    fn dependencies(&self) -> Vec<PluginId> {
+        vec![PluginId::of::<RenderPlugin>()]
+    }
+}
+
+/// Returns the label of the shader, pipeline and graph node of the material type `M`
+fn material_label<M: CustomMaterial>() -> String {
+    let name = type_name::<M>();
+    let name = name.rsplit("::").next().unwrap_or(name);
+    format!("material_{name}")
+}
+
+/// Startup system to register the graph node of the material type `M`
+fn register_material_graph<M: CustomMaterial>(
+    graph: &mut RenderGraph,
+    device: Res<RenderDevice>,
+    surface_config: Res<RenderSurfaceConfiguration>,
+    mut shader_loader: ResMut<ShaderLoader>,
+    mut layouts: ResMut<BindGroupLayouts>,
+) {
+    let label = material_label::<M>();
+    let pipeline_builder = create_material_pipeline_builder::<M>(
+        &label,
+        &device,
+        &surface_config,
+        &mut shader_loader,
+        &mut layouts,
+    );
+
+    let node = GraphNodeBuilder::new(&label)
+        .set_pipeline(pipeline_builder)
+        .set_custom_system(material_render_system::<M>)
+        .set_color_target(NodeColorTarget::Surface)
+        .set_depth_target(NodeDepthTarget::Node("main".to_string()))
+        .run_after("main")
+        .run_before("main_2d")
+        .build();
+
+    graph.add(node);
+}
+
+/// Material graph node rendering system, draws every visible entity with a material of type `M`
+fn material_render_system<M: CustomMaterial>(
+    graph_ctx: Res<RenderContext>,
+
+    world: &mut World,
+    encoder: &mut RenderCommandEncoder,
+    mut buffers: ResMut<RenderAssets<Buffer>>,
+    mut bind_groups: ResMut<RenderAssets<BindGroup>>,
+    meshes: Res<Assets<Mesh>>,
+
+    mut query: Query<
+        (
+            &Handle<M>,
+            &Handle<Mesh>,
+            &GlobalTransform,
+            Option<&Visibility>,
+        ),
+        Without<Hidden>,
+    >,
+    rendered: Res<RenderedWindow>,
+) {
+    let mut entities = query
+        .iter_mut()
+        .into_iter()
+        .filter(|(_, mesh, _, visibility)| {
+            visibility.is_none_or(|v| v.is_visible()) && meshes.get(mesh).is_some()
+        })
+        .collect::<Vec<_>>();
+    if entities.is_empty() {
+        return;
+    }
+
+    // batch by material, then mesh
+    entities.sort_by_key(|(material, mesh, ..)| (material.id(), mesh.id()));
+
+    let cameras = Camera::rendering_to(
+        query
+            .cast::<(EntityId, &Camera), (With<Projection>, With<Camera3D>)>()
+            .iter_mut(),
+        &rendered,
+    );
+    if cameras.is_empty() {
+        return;
+    }
+
+    let label = material_label::<M>();
+    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some(&format!("{label} render pass")),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: unsafe {
+                &*graph_ctx
+                    .color_target
+                    .expect("material color target is None")
+            },
+            depth_slice: None,
+            resolve_target: graph_ctx.resolve_target.map(|view| unsafe { &*view }),
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+            view: unsafe {
+                &*graph_ctx
+                    .depth_target
+                    .expect("material depth target is None")
+            },
+            depth_ops: Some(wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: wgpu::StoreOp::Store,
+            }),
+            stencil_ops: None,
+        }),
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    });
+
+    render_pass.set_pipeline(
+        unsafe { &*graph_ctx.node }
+            .data
+            .pipeline
+            .as_ref()
+            .expect("Pipeline should have been generated by now")
+            .render_pipeline(),
+    );
+
+    let target_size = surface_size(world);
+    let mut draw_calls = 0;
+    let mut bind_group_changes = 0;
+    for (camera_id, camera) in cameras {
+        if !camera.set_render_pass_camera(
+            camera_id,
+            &mut render_pass,
+            1,
+            target_size,
+            &mut bind_groups,
+            world,
+        ) {
+            continue;
+        }
+        bind_group_changes += 1;
+
+        let mut last_material = None;
+        for &(material, mesh, global_transform, _) in &entities {
+            let mesh_buffer = buffers.get_by_handle(mesh, world);
+            let Some(vertex_buffer) = mesh_buffer.vertex.as_ref() else {
+                continue;
+            };
+
+            if last_material != Some(material.id()) {
+                let material_bind_group = bind_groups.get_by_handle(material, world);
+                render_pass.set_bind_group(0, &*material_bind_group, &[]);
+                bind_group_changes += 1;
+                last_material = Some(material.id());
+            }
+
+            render_pass.set_push_constants(
+                wgpu::ShaderStages::VERTEX,
+                0,
+                bytemuck::bytes_of(&global_transform.matrix.to_cols_array_2d()),
+            );
+            render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+
+            if let Some(index_buffer) = &mesh_buffer.index {
+                render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                render_pass.draw_indexed(0..mesh_buffer.num_indices, 0, 0..1);
+            } else {
+                render_pass.draw(0..mesh_buffer.num_vertices, 0..1);
+            }
+            draw_calls += 1;
+        }
+    }
+
+    graph_ctx.record_draw_calls(draw_calls);
+    graph_ctx.record_bind_group_changes(bind_group_changes);
+}
+
+fn create_material_pipeline_builder<M: CustomMaterial>(
+    label: &str,
+    device: &RenderDevice,
+    surface_config: &RenderSurfaceConfiguration,
+    shader_loader: &mut ShaderLoader,
+    layouts: &mut BindGroupLayouts,
+) -> PipelineBuilder {
+    // Material bind group layout, shared with the bind groups of the material instances
+    let material_layout = M::bind_group_layout(label, device, layouts);
+
+    // Camera bind group layout for uniform buffer
+    let camera_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("camera_bind_group_layout"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+    });
+
+    // Load shader modules
+    shader_loader.load(label, M::shader(), device);
+
+    let builder = Pipeline::build(&format!("{label}_pipeline"))
+        .set_bind_group_layouts(vec![material_layout, camera_layout])
+        .set_vertex_buffer_layouts(vec![Mesh::vertex_descriptor()])
+        .set_vertex_shader(label, M::vertex_entry())
+        .set_fragment_shader(label, M::fragment_entry())
+        .add_color_format(surface_config.format)
+        .set_depth_format(wgpu::TextureFormat::Depth32Float)
+        .set_push_constant_ranges(vec![wgpu::PushConstantRange {
+            stages: wgpu::ShaderStages::VERTEX,
+            range: 0..std::mem::size_of::<[[f32; 4]; 4]>() as u32,
+        }]);
+
+    M::specialize(builder)
+}
@@ -1,20 +1,44 @@
+use std::collections::VecDeque;
+
 use crate::{
-    assets::Assets,
-    macros::{Asset, RenderAsset},
+    assets::{AssetEvent, Assets, Handle},
+    event::EventReader,
+    macros::{Asset, Resource},
     prelude::World,
-    render_assets::{IntoRenderAsset, RenderAssetEntry, RenderAssets},
+    render_assets::{BindGroup, IntoRenderAsset, RenderAsset, RenderAssetEntry, RenderAssets},
     renderer::newtype::{RenderDevice, RenderQueue},
 };
 
 use super::Color;
 
-#[derive(RenderAsset)]
 pub struct Texture {
     pub texture: wgpu::Texture,
     pub view: wgpu::TextureView,
     pub sampler: wgpu::Sampler,
 }
 
+impl RenderAsset for Texture {
+    fn gpu_size(&self) -> u64 {
+        let format = self.texture.format();
+        let (block_width, block_height) = format.block_dimensions();
+        let block_size = format.block_copy_size(None).unwrap_or(4) as u64;
+
+        // Sum of all mip levels, each level is at least one block
+        (0..self.texture.mip_level_count())
+            .map(|level| {
+                let size = self
+                    .texture
+                    .size()
+                    .mip_level_size(level, self.texture.dimension());
+                let blocks_x = size.width.div_ceil(block_width) as u64;
+                let blocks_y = size.height.div_ceil(block_height) as u64;
+                blocks_x * blocks_y * size.depth_or_array_layers as u64 * block_size
+            })
+            .sum::<u64>()
+            * self.texture.sample_count() as u64
+    }
+}
+
 #[derive(Clone)]
 /// Texture render asset which represents a 1x1 texture with a single rgba color.
 /// Created with default image descriptors.
@@ -52,7 +76,8 @@ impl SingleColorTexture {
 
 #[derive(Clone, Debug, Asset)]
 pub struct Image {
-    /// Image data, if set, will be used to write to the texture during creation
+    /// Image data, if set, will be used to write to the texture during creation. Mip levels
+    /// after the first one follow the base level, see [`Image::generate_mipmaps`].
     pub data: Vec<u8>,
     pub size: wgpu::Extent3d,
     pub texture_descriptor: Option<wgpu::TextureDescriptor<'static>>,
@@ -71,6 +96,49 @@ impl Image {
         }
     }
 
+    /// Generate the mip chain of an rgba8 image on the CPU and append it to [`Self::data`],
+    /// every level is filtered from the previous one. Does nothing if the image already has mip
+    /// levels or its data doesn't match its size.
+    ///
+    /// Images decoded in loading tasks get their mip levels there, so only the upload is left to
+    /// the render side.
+    pub fn generate_mipmaps(&mut self) {
+        let dimension = wgpu::TextureDimension::D2;
+        let levels = self.size.max_mips(dimension);
+        if levels <= 1 || self.mip_level_count() > 1 || self.size.depth_or_array_layers != 1 {
+            return;
+        }
+
+        let Some(mut level) =
+            image::RgbaImage::from_raw(self.size.width, self.size.height, self.data.clone())
+        else {
+            return;
+        };
+
+        for mip in 1..levels {
+            let size = self.size.mip_level_size(mip, dimension);
+            level = image::imageops::resize(
+                &level,
+                size.width,
+                size.height,
+                image::imageops::FilterType::Triangle,
+            );
+            self.data.extend_from_slice(level.as_raw());
+        }
+
+        self.texture_descriptor
+            .get_or_insert_with(|| Self::default_texture_descriptor(self.size))
+            .mip_level_count = levels;
+    }
+
+    /// Returns the amount of mip levels of the texture, 1 without a texture descriptor
+    #[inline]
+    pub fn mip_level_count(&self) -> u32 {
+        self.texture_descriptor
+            .as_ref()
+            .map_or(1, |descriptor| descriptor.mip_level_count)
+    }
+
     pub fn default_texture_descriptor(size: wgpu::Extent3d) -> wgpu::TextureDescriptor<'static> {
         wgpu::TextureDescriptor {
             label: Some("Image Texture"),
@@ -136,22 +204,33 @@ impl IntoRenderAsset<Texture> for Image {
                 .unwrap_or(&Self::default_sampler_descriptor()),
         );
 
-        if !self.data.is_empty() {
+        // upload every mip level stored in the data, one after another
+        let mut offset = 0;
+        for mip_level in 0..texture_descriptor.mip_level_count {
+            let size = self
+                .size
+                .mip_level_size(mip_level, texture_descriptor.dimension);
+            let len = (4 * size.width * size.height * size.depth_or_array_layers) as usize;
+            let Some(data) = self.data.get(offset..offset + len) else {
+                break;
+            };
+
             queue.write_texture(
                 wgpu::TexelCopyTextureInfo {
                     texture: &texture,
-                    mip_level: 0,
+                    mip_level,
                     origin: wgpu::Origin3d::ZERO,
                     aspect: wgpu::TextureAspect::All,
                 },
-                &self.data,
+                data,
                 wgpu::TexelCopyBufferLayout {
                     offset: 0,
-                    bytes_per_row: Some(4 * self.size.width),
-                    rows_per_image: Some(self.size.height),
+                    bytes_per_row: Some(4 * size.width),
+                    rows_per_image: Some(size.height),
                 },
-                self.size,
+                size,
             );
+            offset += len;
         }
 
         Texture {
@@ -161,3 +240,98 @@ impl IntoRenderAsset<Texture> for Image {
         }
     }
 }
+
+/// Images loaded in a task which are waiting for their texture upload. Uploads are spread over
+/// frames by [`upload_loaded_images_system`], until then materials use their fallback color.
+/// Used as a resource.
+#[derive(Resource)]
+pub struct ImageUploadQueue {
+    pending: VecDeque<Handle<Image>>,
+    /// Maximum bytes of image data uploaded per frame, at least one image is uploaded every frame
+    pub bytes_per_frame: u64,
+}
+
+impl Default for ImageUploadQueue {
+    fn default() -> Self {
+        Self {
+            pending: VecDeque::new(),
+            bytes_per_frame: 32 * 1024 * 1024,
+        }
+    }
+}
+
+impl ImageUploadQueue {
+    /// Returns true if the texture of the image wasn't uploaded yet
+    #[inline]
+    pub fn is_pending(&self, handle: &Handle<Image>) -> bool {
+        self.pending.contains(handle)
+    }
+
+    /// Returns the amount of images waiting for their upload
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+/// Returns true if the texture of the image can be used, i.e. the image is loaded and not
+/// waiting in the [`ImageUploadQueue`]
+pub(crate) fn is_texture_ready(handle: &Handle<Image>, world: &World) -> bool {
+    let loaded = world
+        .resources
+        .try_get::<Assets<Image>>()
+        .is_some_and(|images| images.get(handle).is_some());
+    let pending = world
+        .resources
+        .try_get::<ImageUploadQueue>()
+        .is_some_and(|queue| queue.is_pending(handle));
+
+    loaded && !pending
+}
+
+/// Uploads the textures of images finished by loading tasks, at most
+/// [`ImageUploadQueue::bytes_per_frame`] per frame, so streaming large images doesn't stall a
+/// single frame. Bind groups are dropped after uploads, so materials pick up the new textures.
+pub fn upload_loaded_images_system(world: &mut World, mut events: EventReader<AssetEvent<Image>>) {
+    let mut queue = world.resources.get_mut::<ImageUploadQueue>();
+    for event in events.read() {
+        if let AssetEvent::Loaded(handle) = event {
+            queue.pending.push_back(handle.clone());
+        }
+    }
+
+    if queue.is_empty() {
+        return;
+    }
+
+    let images = world.resources.get::<Assets<Image>>();
+    let mut textures = world.resources.get_mut::<RenderAssets<Texture>>();
+    let pending = queue.len();
+    let mut uploaded = 0;
+    while let Some(handle) = queue.pending.front().cloned() {
+        // removed before it was uploaded
+        let Some(image) = images.get(&handle) else {
+            queue.pending.pop_front();
+            continue;
+        };
+
+        let size = image.data.len() as u64;
+        if uploaded > 0 && uploaded + size > queue.bytes_per_frame {
+            break;
+        }
+
+        textures.get_by_handle(&handle, world);
+        queue.pending.pop_front();
+        uploaded += size;
+    }
+
+    // any bind group, e.g. of a material, can use the fallback of an uploaded image
+    if queue.len() < pending {
+        world.resources.get_mut::<RenderAssets<BindGroup>>().clear();
+    }
+}
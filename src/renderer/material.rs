@@ -2,7 +2,7 @@ use crate::{
     assets::Handle,
     ecs::entities::EntityId,
     prelude::World,
-    render_assets::{BindGroup, Buffer, IntoRenderAsset},
+    render_assets::{AsBindGroup, BindGroup, BindGroupLayouts, Buffer, IntoRenderAsset},
 };
 
 use super::{Color, Face, Image, palette};
@@ -75,8 +75,47 @@ impl IntoRenderAsset<Buffer> for Material {
     }
 }
 
-impl IntoRenderAsset<BindGroup> for Material {
-    fn create_render_asset(&self, world: &mut World, _: Option<EntityId>) -> BindGroup {
+impl AsBindGroup for Material {
+    fn layout_entries() -> Vec<wgpu::BindGroupLayoutEntry> {
+        let texture = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let sampler = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+            count: None,
+        };
+
+        vec![
+            // base texture
+            texture(0),
+            sampler(1),
+            // normal map
+            texture(2),
+            sampler(3),
+            // uniform buffer
+            wgpu::BindGroupLayoutEntry {
+                binding: 4,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+        ]
+    }
+
+    fn as_bind_group(&self, world: &mut World) -> BindGroup {
         let buffer: Buffer = self.create_render_asset(world, None);
         let uniform = buffer
             .uniform
@@ -92,6 +131,10 @@ impl IntoRenderAsset<BindGroup> for Material {
                 None,
             )
             .add_uniform_buffer(&uniform, wgpu::ShaderStages::VERTEX_FRAGMENT)
-            .finish(&world.resources.get())
+            // materials share their layout, so draws can be batched by it
+            .finish_shared(
+                &world.resources.get(),
+                &mut world.resources.get_mut::<BindGroupLayouts>(),
+            )
     }
 }
@@ -2,7 +2,7 @@ mod meshable;
 
 use std::mem;
 
-use glam::Vec3;
+use glam::{Mat3, Mat4, Vec3};
 pub use wgpu::PrimitiveTopology;
 use wgpu::{VertexAttribute, VertexFormat};
 
@@ -87,6 +87,77 @@ impl Mesh {
         meshable.mesh()
     }
 
+    /// Merges `meshes` into one mesh, with each mesh transformed by its matrix. Attributes missing
+    /// from some of the meshes get their default values, and non indexed meshes are indexed if any
+    /// mesh is. Returns None if `meshes` is empty or the meshes have different topologies.
+    pub fn merge<'a>(meshes: impl IntoIterator<Item = (&'a Mesh, Mat4)>) -> Option<Self> {
+        let meshes = meshes.into_iter().collect::<Vec<_>>();
+        let (first, _) = meshes.first()?;
+        if meshes
+            .iter()
+            .any(|(mesh, _)| mesh.topology != first.topology)
+        {
+            return None;
+        }
+
+        let has_colors = meshes.iter().any(|(mesh, _)| mesh.colors.is_some());
+        let has_normals = meshes.iter().any(|(mesh, _)| mesh.normals.is_some());
+        let has_uvs = meshes.iter().any(|(mesh, _)| mesh.uvs.is_some());
+        let has_indices = meshes.iter().any(|(mesh, _)| mesh.indices.is_some());
+
+        let mut merged = Mesh {
+            topology: first.topology,
+            colors: has_colors.then(Vec::new),
+            normals: has_normals.then(Vec::new),
+            uvs: has_uvs.then(Vec::new),
+            indices: has_indices.then(Vec::new),
+            ..Default::default()
+        };
+
+        for (mesh, transform) in meshes {
+            let offset = merged.positions.len() as u32;
+            let count = mesh.positions.len();
+            let normal_matrix = Mat3::from_mat4(transform).inverse().transpose();
+
+            merged.positions.extend(
+                mesh.positions
+                    .iter()
+                    .map(|p| transform.transform_point3(Vec3::from(*p)).to_array()),
+            );
+
+            if let Some(colors) = &mut merged.colors {
+                match &mesh.colors {
+                    Some(mesh_colors) => colors.extend_from_slice(mesh_colors),
+                    None => colors.extend(std::iter::repeat_n(palette::TRANSPARENT, count)),
+                }
+            }
+            if let Some(normals) = &mut merged.normals {
+                match &mesh.normals {
+                    Some(mesh_normals) => normals.extend(mesh_normals.iter().map(|n| {
+                        (normal_matrix * Vec3::from(*n))
+                            .normalize_or_zero()
+                            .to_array()
+                    })),
+                    None => normals.extend(std::iter::repeat_n([0.0; 3], count)),
+                }
+            }
+            if let Some(uvs) = &mut merged.uvs {
+                match &mesh.uvs {
+                    Some(mesh_uvs) => uvs.extend_from_slice(mesh_uvs),
+                    None => uvs.extend(std::iter::repeat_n([0.0; 2], count)),
+                }
+            }
+            if let Some(indices) = &mut merged.indices {
+                match &mesh.indices {
+                    Some(mesh_indices) => indices.extend(mesh_indices.iter().map(|i| i + offset)),
+                    None => indices.extend(offset..offset + count as u32),
+                }
+            }
+        }
+
+        Some(merged)
+    }
+
     pub(crate) const VERTEX_SIZE_IN_F32: usize = 12;
     pub(crate) const VERTEX_SIZE_IN_U8: usize = 12 * std::mem::size_of::<f32>();
 
@@ -1,15 +1,48 @@
 mod color;
 pub mod culling;
+pub mod custom_material;
 mod image;
 mod material;
 mod mesh;
 pub mod newtype;
+pub mod outline;
 pub mod palette;
+pub mod settings;
+pub mod static_mesh;
+
+use crate::macros::{Component, Reflect};
 
 pub use color::Color;
-pub use image::{Image, SingleColorTexture, Texture};
+pub(crate) use image::is_texture_ready;
+pub use image::{
+    Image, ImageUploadQueue, SingleColorTexture, Texture, upload_loaded_images_system,
+};
 pub use material::Material;
 pub use mesh::{Mesh, Meshable};
+pub use settings::{RenderInitError, RenderSettings};
+
+/// Puts an entity's mesh on the sorted 2D path, where it's drawn after all other meshes in the
+/// `main_2d` graph node, ordered by this index. Lower indices are drawn first, so higher ones end
+/// up on top.
+///
+/// Sorted meshes are alpha blended and don't write depth, so they are still hidden behind other
+/// meshes but never hide each other, regardless of their distance to the camera. Meshes with the
+/// same index are batched by material (its texture) first, and then ordered by their `Z`
+/// translation, from the lowest to the highest. Ties keep a stable order between frames.
+///
+/// Sorted meshes don't cast shadows.
+#[derive(Component, Reflect, Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ZIndex(pub i32);
+
+/// Marker which excludes an entity's mesh from the shadow maps, it's still lit and can receive
+/// shadows from other meshes
+#[derive(Component, Reflect, Default, Debug, Clone, Copy)]
+pub struct NotShadowCaster;
+
+/// Marker which makes an entity's mesh ignore shadows, it's lit as if no other mesh was blocking
+/// the lights. It still casts shadows, unless it's also a [`NotShadowCaster`].
+#[derive(Component, Reflect, Default, Debug, Clone, Copy)]
+pub struct NotShadowReceiver;
 
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Face {
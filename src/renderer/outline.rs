@@ -0,0 +1,310 @@
+//! This module draws colored outlines around meshes, useful for editor selection and gameplay
+//! highlighting.
+//!
+//! Every entity with an [`Outlined`] component and a mesh is drawn again in the `outline` graph
+//! node, after the main pass. The mesh is extruded along its normals by [`Outlined::width`]
+//! pixels and only its back faces are drawn, so the main pass depth hides everything but the
+//! silhouette. Meshes with smooth normals produce a continuous outline, hard edges may leave
+//! small gaps at the corners.
+//!
+//! For more information, see [`OutlinePlugin`].
+
+use crate::{
+    assets::ShaderLoader,
+    core::graph::*,
+    plugins::RenderPlugin,
+    prelude::*,
+    render_assets::{
+        BindGroup, Buffer, Pipeline, RenderAssets, UniformArena, pipeline::PipelineBuilder,
+    },
+    renderer::{
+        culling::Visibility,
+        newtype::{RenderCommandEncoder, RenderDevice, RenderQueue, RenderSurfaceConfiguration},
+        palette,
+    },
+};
+
+/// This plugin adds the outline graph node. For more information, see the
+/// [outline module](crate::renderer::outline).
+pub struct OutlinePlugin;
+
+impl Plugin for OutlinePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(register_outline_graph);
+    }
+
+    fn dependencies(&self) -> Vec<PluginId> {
+        vec![PluginId::of::<RenderPlugin>()]
+    }
+}
+
+/// Draws an outline around the entity's mesh
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct Outlined {
+    pub color: Color,
+    /// Width of the outline in physical pixels
+    pub width: f32,
+}
+
+impl Outlined {
+    /// Create a new outline with `color` and `width` in pixels
+    #[inline]
+    pub fn new(color: Color, width: f32) -> Self {
+        Self { color, width }
+    }
+}
+
+impl Default for Outlined {
+    fn default() -> Self {
+        Self::new(palette::ORANGE, 3.0)
+    }
+}
+
+/// Uniform of a single outlined entity, stored in the pass's [`UniformArena`]
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct OutlineUniform {
+    model: [[f32; 4]; 4],
+    color: [f32; 4],
+    viewport: [f32; 2],
+    width: f32,
+    _padding: f32,
+}
+
+/// Startup system to register the outline graph node
+fn register_outline_graph(
+    graph: &mut RenderGraph,
+    device: Res<RenderDevice>,
+    surface_config: Res<RenderSurfaceConfiguration>,
+    mut shader_loader: ResMut<ShaderLoader>,
+) {
+    let pipeline_builder =
+        create_outline_pipeline_builder(&device, &surface_config, &mut shader_loader);
+
+    let node = GraphNodeBuilder::new("outline")
+        .set_pipeline(pipeline_builder)
+        .set_custom_system(outline_render_system)
+        .set_color_target(NodeColorTarget::Surface)
+        .set_depth_target(NodeDepthTarget::Node("main".to_string()))
+        .run_after("main")
+        .run_before("ui_image")
+        .build();
+
+    graph.add(node);
+}
+
+/// Outline graph node rendering system, draws the extruded back faces of outlined meshes
+fn outline_render_system(
+    graph_ctx: Res<RenderContext>,
+
+    world: &mut World,
+    encoder: &mut RenderCommandEncoder,
+    surface_config: Res<RenderSurfaceConfiguration>,
+    device: Res<RenderDevice>,
+    queue: Res<RenderQueue>,
+    mut buffers: ResMut<RenderAssets<Buffer>>,
+    mut bind_groups: ResMut<RenderAssets<BindGroup>>,
+    mut arena: Local<Option<UniformArena>>,
+    meshes: Res<Assets<Mesh>>,
+
+    mut query: Query<
+        (
+            &Outlined,
+            &Handle<Mesh>,
+            &GlobalTransform,
+            Option<&Visibility>,
+        ),
+        Without<Hidden>,
+    >,
+    rendered: Res<RenderedWindow>,
+) {
+    let outlined = query
+        .iter_mut()
+        .into_iter()
+        .filter(|(outlined, mesh, _, visibility)| {
+            outlined.width > 0.0
+                && visibility.is_none_or(|v| v.is_visible())
+                && meshes.get(mesh).is_some()
+        })
+        .collect::<Vec<_>>();
+
+    if outlined.is_empty() {
+        return;
+    }
+
+    let cameras = Camera::rendering_to(
+        query
+            .cast::<(EntityId, &Camera), (With<Projection>, With<Camera3D>)>()
+            .iter_mut(),
+        &rendered,
+    );
+    if cameras.is_empty() {
+        return;
+    }
+
+    let meshes = outlined
+        .into_iter()
+        .filter_map(|(outlined, mesh, global_transform, _)| {
+            let mesh_buffer = buffers.get_by_handle(mesh, world);
+            mesh_buffer
+                .vertex
+                .is_some()
+                .then_some((outlined, mesh_buffer, global_transform))
+        })
+        .collect::<Vec<_>>();
+
+    // upload the uniforms of all outlines with a single write, the width is relative to the
+    // viewport of every camera
+    let target_size = Vec2::new(surface_config.width as f32, surface_config.height as f32);
+    let arena = arena.get_or_insert_with(|| {
+        UniformArena::new(
+            "outline",
+            std::mem::size_of::<OutlineUniform>(),
+            meshes.len() * cameras.len(),
+            &device,
+            wgpu::ShaderStages::VERTEX_FRAGMENT,
+        )
+    });
+    arena.clear();
+
+    let mut offsets = Vec::with_capacity(cameras.len());
+    for (_, camera) in &cameras {
+        let (_, viewport) = camera.viewport_pixels(target_size);
+        let camera_offsets = meshes
+            .iter()
+            .map(|(outlined, _, global_transform)| {
+                let color = outlined.color;
+                arena.push(&OutlineUniform {
+                    model: global_transform.matrix.to_cols_array_2d(),
+                    color: [color.r, color.g, color.b, color.a],
+                    viewport: viewport.to_array(),
+                    width: outlined.width,
+                    _padding: 0.0,
+                })
+            })
+            .collect::<Vec<_>>();
+        offsets.push(camera_offsets);
+    }
+    arena.flush(&device, &queue);
+
+    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("outline render pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: unsafe {
+                &*graph_ctx
+                    .color_target
+                    .expect("outline color target is None")
+            },
+            depth_slice: None,
+            resolve_target: graph_ctx.resolve_target.map(|view| unsafe { &*view }),
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+            view: unsafe {
+                &*graph_ctx
+                    .depth_target
+                    .expect("outline depth target is None")
+            },
+            depth_ops: Some(wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: wgpu::StoreOp::Store,
+            }),
+            stencil_ops: None,
+        }),
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    });
+
+    render_pass.set_pipeline(
+        unsafe { &*graph_ctx.node }
+            .data
+            .pipeline
+            .as_ref()
+            .expect("Pipeline should have been generated by now")
+            .render_pipeline(),
+    );
+
+    let mut draw_calls = 0;
+    for ((camera_id, camera), offsets) in cameras.into_iter().zip(offsets) {
+        if !camera.set_render_pass_camera(
+            camera_id,
+            &mut render_pass,
+            0,
+            target_size,
+            &mut bind_groups,
+            world,
+        ) {
+            continue;
+        }
+
+        for ((_, mesh_buffer, _), offset) in meshes.iter().zip(offsets) {
+            let Some(vertex_buffer) = mesh_buffer.vertex.as_ref() else {
+                continue;
+            };
+
+            render_pass.set_bind_group(1, arena.bind_group(), &[offset]);
+            render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+
+            if let Some(index_buffer) = &mesh_buffer.index {
+                render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                render_pass.draw_indexed(0..mesh_buffer.num_indices, 0, 0..1);
+            } else {
+                render_pass.draw(0..mesh_buffer.num_vertices, 0..1);
+            }
+            draw_calls += 1;
+        }
+    }
+
+    graph_ctx.record_draw_calls(draw_calls);
+}
+
+fn create_outline_pipeline_builder(
+    device: &RenderDevice,
+    surface_config: &RenderSurfaceConfiguration,
+    shader_loader: &mut ShaderLoader,
+) -> PipelineBuilder {
+    // Camera bind group layout for uniform buffer
+    let camera_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("camera_bind_group_layout"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+    });
+
+    // Load shader modules
+    shader_loader.load("outline", include_str!("../shaders/outline.wgsl"), device);
+
+    // Only back faces of the extruded hull are drawn
+    let mut primitive_state = PipelineBuilder::default_primitive_state();
+    primitive_state.cull_mode = Some(wgpu::Face::Front);
+
+    // Test against the main pass depth, but don't write to it
+    let mut depth_stencil = PipelineBuilder::default_depth_stencil();
+    depth_stencil.depth_write_enabled = false;
+
+    Pipeline::build("outline_pipeline")
+        .set_bind_group_layouts(vec![
+            camera_layout,
+            UniformArena::create_layout(
+                device,
+                std::mem::size_of::<OutlineUniform>(),
+                wgpu::ShaderStages::VERTEX_FRAGMENT,
+            ),
+        ])
+        .set_vertex_buffer_layouts(vec![Mesh::vertex_descriptor()])
+        .set_vertex_shader("outline", "vs_main")
+        .set_fragment_shader("outline", "fs_main")
+        .add_color_format(surface_config.format)
+        .set_depth_stencil(Some(depth_stencil))
+        .set_primitive_state(primitive_state)
+}
@@ -0,0 +1,162 @@
+use std::fmt;
+
+/// Features the engine can't render without
+const ENGINE_FEATURES: wgpu::Features = wgpu::Features::PUSH_CONSTANTS;
+/// Push constant size used by the engine pipelines
+const ENGINE_PUSH_CONSTANT_SIZE: u32 = 128;
+
+/// Settings used when creating the GPU instance, adapter and device. Insert it as a resource
+/// before the app runs, changes after startup have no effect.
+///
+/// On the web the default backends are WebGPU and WebGL2. Note that the engine pipelines need
+/// push constants, which WebGPU doesn't expose, and storage buffers, which WebGL2 doesn't support,
+/// so initialization fails on browsers without them.
+///
+/// # Usage
+/// ```ignore
+/// app.set_resource(RenderSettings {
+///     backends: wgpu::Backends::VULKAN,
+///     adapter_name: Some("NVIDIA".to_string()),
+///     ..Default::default()
+/// });
+/// ```
+#[derive(crate::macros::Resource, Debug, Clone)]
+pub struct RenderSettings {
+    /// Backends the instance can use
+    pub backends: wgpu::Backends,
+    pub power_preference: wgpu::PowerPreference,
+    /// Force a specific adapter, matched as a case insensitive substring of its name
+    pub adapter_name: Option<String>,
+    /// Features the device must support, in addition to the ones required by the engine
+    pub required_features: wgpu::Features,
+    /// Features enabled only if the adapter supports them
+    pub optional_features: wgpu::Features,
+    /// Limits the device must support, the push constant size is raised to the engine's minimum
+    pub required_limits: wgpu::Limits,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            backends: if cfg!(target_arch = "wasm32") {
+                wgpu::Backends::BROWSER_WEBGPU | wgpu::Backends::GL
+            } else {
+                wgpu::Backends::PRIMARY
+            },
+            power_preference: wgpu::PowerPreference::default(),
+            adapter_name: None,
+            required_features: wgpu::Features::empty(),
+            // timestamps are used for gpu frame time diagnostics
+            optional_features: wgpu::Features::TIMESTAMP_QUERY
+                | wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS,
+            required_limits: wgpu::Limits::default(),
+        }
+    }
+}
+
+impl RenderSettings {
+    /// Returns the features to request from `adapter`, or the required ones it doesn't support
+    pub(crate) fn device_features(
+        &self,
+        adapter: &wgpu::Adapter,
+    ) -> Result<wgpu::Features, RenderInitError> {
+        let supported = adapter.features();
+        let required = self.required_features | ENGINE_FEATURES;
+
+        let missing = required - supported;
+        if !missing.is_empty() {
+            return Err(RenderInitError::MissingFeatures {
+                adapter: adapter.get_info().name,
+                missing,
+            });
+        }
+
+        Ok(required | (self.optional_features & supported))
+    }
+
+    /// Returns the limits to request, checked against the `adapter` limits
+    pub(crate) fn device_limits(
+        &self,
+        adapter: &wgpu::Adapter,
+    ) -> Result<wgpu::Limits, RenderInitError> {
+        let mut limits = self.required_limits.clone();
+        limits.max_push_constant_size =
+            limits.max_push_constant_size.max(ENGINE_PUSH_CONSTANT_SIZE);
+
+        let mut unsupported = Vec::new();
+        limits.check_limits_with_fail_fn(&adapter.limits(), false, |name, requested, allowed| {
+            unsupported.push(format!("{name} (requested {requested}, allowed {allowed})"));
+        });
+
+        if !unsupported.is_empty() {
+            return Err(RenderInitError::UnsupportedLimits {
+                adapter: adapter.get_info().name,
+                limits: unsupported,
+            });
+        }
+
+        Ok(limits)
+    }
+}
+
+/// Error returned when the renderer can't be initialized with the [`RenderSettings`]
+#[derive(Debug)]
+pub enum RenderInitError {
+    /// No adapter is compatible with the surface and backends
+    NoAdapter {
+        backends: wgpu::Backends,
+        source: wgpu::RequestAdapterError,
+    },
+    /// No adapter matches [`RenderSettings::adapter_name`]
+    AdapterNotFound {
+        name: String,
+        available: Vec<String>,
+    },
+    /// The adapter doesn't support the required features
+    MissingFeatures {
+        adapter: String,
+        missing: wgpu::Features,
+    },
+    /// The adapter doesn't support the required limits
+    UnsupportedLimits {
+        adapter: String,
+        limits: Vec<String>,
+    },
+    /// The device request failed
+    Device(wgpu::RequestDeviceError),
+    /// The new adapter can't present the surface format used so far
+    UnsupportedSurfaceFormat(wgpu::TextureFormat),
+}
+
+impl fmt::Display for RenderInitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoAdapter { backends, source } => write!(
+                f,
+                "No compatible adapter found for backends {backends:?}: {source}"
+            ),
+            Self::AdapterNotFound { name, available } => write!(
+                f,
+                "No compatible adapter matches '{name}', available adapters: [{}]",
+                available.join(", ")
+            ),
+            Self::MissingFeatures { adapter, missing } => {
+                write!(
+                    f,
+                    "Adapter '{adapter}' doesn't support features {missing:?}"
+                )
+            }
+            Self::UnsupportedLimits { adapter, limits } => write!(
+                f,
+                "Adapter '{adapter}' doesn't support limits: {}",
+                limits.join(", ")
+            ),
+            Self::Device(err) => write!(f, "Failed to create device: {err}"),
+            Self::UnsupportedSurfaceFormat(format) => {
+                write!(f, "Adapter doesn't support the surface format {format:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RenderInitError {}
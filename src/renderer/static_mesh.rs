@@ -0,0 +1,110 @@
+//! This module merges the meshes of [`Static`] entities which share a material into combined
+//! meshes, so a level made of many small pieces is drawn with a few large buffers.
+//!
+//! When entities become static, the ones with the same material and shadow flags are merged into
+//! a single mesh in world space, which is spawned as a new static entity. The original entities
+//! are [`Hidden`], so they keep their components and systems but are no longer drawn. Meshes of
+//! entities merged in the same frame are combined, entities which become static later are merged
+//! into new meshes.
+//!
+//! Merged meshes are culled as a whole, so entities with a [`VisibilityRange`], a [`ZIndex`] or a
+//! mesh which isn't loaded yet are left as they are.
+//!
+//! For more information, see [`StaticMeshPlugin`].
+
+use std::collections::HashMap;
+
+use crate::{
+    macros::Reflect, plugins::RenderPlugin, prelude::*, renderer::culling::VisibilityRange,
+};
+
+/// This plugin merges the meshes of static entities. For more information, see the
+/// [static mesh module](crate::renderer::static_mesh).
+pub struct StaticMeshPlugin;
+
+impl Plugin for StaticMeshPlugin {
+    fn build(&self, app: &mut App) {
+        // after the static transforms are baked
+        app.register_system(merge_static_meshes_system, phase::Last.layer(layer::Post));
+    }
+
+    fn dependencies(&self) -> Vec<PluginId> {
+        vec![PluginId::of::<RenderPlugin>()]
+    }
+}
+
+/// Marker for the entities spawned with a merged mesh by the [`StaticMeshPlugin`]
+#[derive(Component, Reflect, Default, Debug, Clone, Copy)]
+pub struct MergedStaticMesh;
+
+/// Merges the meshes of entities which became [`Static`], grouped by material and shadow flags
+fn merge_static_meshes_system(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut query: Query<
+        (
+            EntityId,
+            &Handle<Material>,
+            &Handle<Mesh>,
+            &GlobalTransform,
+            Option<&NotShadowCaster>,
+            Option<&NotShadowReceiver>,
+        ),
+        (
+            Added<Static>,
+            Without<Hidden>,
+            Without<ZIndex>,
+            Without<VisibilityRange>,
+            Without<MergedStaticMesh>,
+        ),
+    >,
+) {
+    let mut groups = HashMap::<_, Vec<_>>::new();
+    for (id, material, mesh, global_transform, caster, receiver) in query.iter_mut() {
+        // only loaded triangle meshes can be merged
+        if meshes
+            .get(mesh)
+            .is_none_or(|mesh| mesh.topology != wgpu::PrimitiveTopology::TriangleList)
+        {
+            continue;
+        }
+
+        let key = (material.id(), caster.is_none(), receiver.is_none());
+        groups
+            .entry(key)
+            .or_default()
+            .push((id, material, mesh, global_transform.matrix));
+    }
+
+    for ((_, casts_shadows, receives_shadows), entities) in groups {
+        // a single mesh doesn't need merging
+        if entities.len() < 2 {
+            continue;
+        }
+
+        let merged = Mesh::merge(
+            entities
+                .iter()
+                .filter_map(|(_, _, mesh, matrix)| meshes.get(mesh).map(|mesh| (mesh, *matrix))),
+        );
+        let Some(merged) = merged else {
+            continue;
+        };
+
+        let material = entities[0].1.clone();
+        let mesh = meshes.add(merged);
+        commands
+            .spawn_empty()
+            .insert(material)
+            .insert(mesh)
+            .insert(Transform::default())
+            .insert(Static)
+            .insert(MergedStaticMesh)
+            .insert_if(NotShadowCaster, || !casts_shadows)
+            .insert_if(NotShadowReceiver, || !receives_shadows);
+
+        for (id, ..) in entities {
+            commands.entity(id).insert(Hidden);
+        }
+    }
+}
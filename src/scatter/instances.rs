@@ -0,0 +1,232 @@
+use std::{collections::HashMap, ops::Range};
+
+use glam::{Mat4, Vec2, Vec3};
+
+use crate::{
+    core::standard::grouped::{InstanceBatch, InstanceBatches},
+    math::bounding_volume::{Frustum, Sphere, WorldBoundingVolume},
+    prelude::*,
+    renderer::culling::FrustumCullingSettings,
+    terrain::{Heightmap, Terrain},
+};
+
+use super::{Scatter, ScatterSurface, placement};
+
+/// Fields of a [`Scatter`] and its surface which affect the placements
+#[derive(Clone, PartialEq)]
+struct PlacementKey {
+    surface: ScatterSurface,
+    density: f32,
+    density_map: Option<Handle<Image>>,
+    seed: u64,
+    scale: Range<f32>,
+    random_rotation: bool,
+    align_to_normal: f32,
+    max_instances: usize,
+    version: u64,
+    /// Heightmap, size, height scale and version of a terrain surface
+    terrain: Option<(Handle<Heightmap>, Vec2, f32, u64)>,
+}
+
+impl PlacementKey {
+    fn new(scatter: &Scatter, terrain: Option<&Terrain>) -> Self {
+        Self {
+            surface: scatter.surface.clone(),
+            density: scatter.density,
+            density_map: scatter.density_map.clone(),
+            seed: scatter.seed,
+            scale: scatter.scale.clone(),
+            random_rotation: scatter.random_rotation,
+            align_to_normal: scatter.align_to_normal,
+            max_instances: scatter.max_instances,
+            version: scatter.version(),
+            terrain: terrain.map(|terrain| {
+                (
+                    terrain.heightmap.clone(),
+                    terrain.size,
+                    terrain.height_scale,
+                    terrain.version(),
+                )
+            }),
+        }
+    }
+}
+
+/// Generated placements of a single scatter
+struct PreparedScatter {
+    key: PlacementKey,
+    /// Local transforms of the instances
+    placements: Vec<Mat4>,
+    /// Instance mesh and its bounding radius around the origin
+    bounds: Option<(Handle<Mesh>, f32)>,
+}
+
+/// Cache of the generated placements of every [`Scatter`]. Updated in
+/// [`scatter_instances_system`].
+#[derive(Default, crate::macros::Resource)]
+pub struct ScatterCache {
+    scatters: HashMap<EntityId, PreparedScatter>,
+    visible: usize,
+}
+
+impl ScatterCache {
+    /// Returns the amount of generated instances of all scatters
+    pub fn instance_count(&self) -> usize {
+        self.scatters.values().map(|s| s.placements.len()).sum()
+    }
+
+    /// Returns the amount of instances which will be drawn this frame
+    pub fn visible_instances(&self) -> usize {
+        self.visible
+    }
+}
+
+/// Generates the placements of `scatter`, returns None if an asset isn't loaded yet
+fn generate_placements(
+    scatter: &Scatter,
+    terrain: Option<&Terrain>,
+    meshes: &Assets<Mesh>,
+    images: &Assets<Image>,
+    heightmaps: Option<&Assets<Heightmap>>,
+) -> Option<Vec<Mat4>> {
+    let density_map = match &scatter.density_map {
+        Some(handle) => Some(images.get(handle)?),
+        None => None,
+    };
+
+    match &scatter.surface {
+        ScatterSurface::Mesh(surface) => Some(placement::scatter_on_mesh(
+            scatter,
+            meshes.get(surface)?,
+            density_map,
+        )),
+        ScatterSurface::Terrain => {
+            let terrain = terrain?;
+            let heightmap = heightmaps?.get(&terrain.heightmap)?;
+            Some(placement::scatter_on_terrain(
+                scatter,
+                terrain,
+                heightmap,
+                density_map,
+            ))
+        }
+    }
+}
+
+/// Pre-render system to generate missing placements of every [`Scatter`], and push the instances
+/// which pass culling and fade as [`InstanceBatch`]es.
+pub(crate) fn scatter_instances_system(
+    meshes: Res<Assets<Mesh>>,
+    images: Res<Assets<Image>>,
+    heightmaps: Option<Res<Assets<Heightmap>>>,
+    culling: Option<Res<FrustumCullingSettings>>,
+    mut cache: ResMut<ScatterCache>,
+    mut batches: ResMut<InstanceBatches>,
+    mut query: Query<(EntityId, &Scatter, &GlobalTransform, Option<&Terrain>), Without<Hidden>>,
+) {
+    cache.visible = 0;
+
+    // extract the active camera position and frustum
+    let culling = culling.is_some_and(|settings| settings.enabled);
+    let camera = query
+        .cast::<(&Camera, &GlobalTransform, Option<&Frustum>), With<Camera3D>>()
+        .iter_mut()
+        .into_iter()
+        .find(|(camera, _, _)| camera.active)
+        .map(|(_, transform, frustum)| (transform.translation(), frustum.cloned()));
+
+    let mut alive = Vec::new();
+    for (id, scatter, global_transform, terrain) in query.iter_mut() {
+        alive.push(id);
+
+        // regenerate the placements if the settings or the surface changed
+        let key = PlacementKey::new(scatter, terrain);
+        if cache
+            .scatters
+            .get(&id)
+            .is_none_or(|prepared| prepared.key != key)
+        {
+            let Some(placements) =
+                generate_placements(scatter, terrain, &meshes, &images, heightmaps.as_deref())
+            else {
+                continue;
+            };
+
+            cache.scatters.insert(
+                id,
+                PreparedScatter {
+                    key,
+                    placements,
+                    bounds: None,
+                },
+            );
+        }
+
+        let Some(prepared) = cache.scatters.get_mut(&id) else {
+            continue;
+        };
+
+        // bounding radius of the instance mesh, it might not be loaded yet
+        if prepared
+            .bounds
+            .as_ref()
+            .is_none_or(|(mesh, _)| *mesh != scatter.mesh)
+        {
+            prepared.bounds = meshes.get(&scatter.mesh).map(|mesh| {
+                let radius = mesh
+                    .positions
+                    .iter()
+                    .map(|p| Vec3::from(*p).length())
+                    .fold(0.0, f32::max);
+                (scatter.mesh.clone(), radius)
+            });
+        }
+        let Some((_, radius)) = prepared.bounds else {
+            continue;
+        };
+
+        let Some((camera_position, frustum)) = &camera else {
+            continue;
+        };
+
+        let mut transforms = Vec::new();
+        for placement in &prepared.placements {
+            let mut matrix = global_transform.matrix * *placement;
+            let position = matrix.w_axis.truncate();
+
+            if let Some(fade) = &scatter.fade {
+                let Some(factor) = fade.factor(position.distance(*camera_position)) else {
+                    continue;
+                };
+                matrix *= Mat4::from_scale(Vec3::splat(factor));
+            }
+
+            if culling && let Some(frustum) = frustum {
+                let scale = matrix
+                    .x_axis
+                    .length()
+                    .max(matrix.y_axis.length())
+                    .max(matrix.z_axis.length());
+                let sphere = Sphere::new(position, radius * scale);
+                if !frustum.intersects(&WorldBoundingVolume::Sphere(sphere)) {
+                    continue;
+                }
+            }
+
+            transforms.push(matrix.to_cols_array_2d());
+        }
+
+        let visible = transforms.len();
+        batches.push(InstanceBatch {
+            material: scatter.material.clone(),
+            mesh: scatter.mesh.clone(),
+            transforms,
+            casts_shadows: scatter.casts_shadows,
+            receives_shadows: true,
+        });
+        cache.visible += visible;
+    }
+
+    // drop removed scatters
+    cache.scatters.retain(|id, _| alive.contains(id));
+}
@@ -0,0 +1,223 @@
+//! # Scatter plugin
+//! Distributes instances of a mesh over the surface of another mesh or a terrain, e.g. grass,
+//! rocks or trees. Scattered instances are not entities, they are drawn by the standard render
+//! path as [`InstanceBatch`]es, so they are lit and cast shadows like mesh entities.
+//!
+//! ## Usage
+//!
+//! - Add the [`ScatterPlugin`] to the app, it is not part of the [`DefaultPlugins`].
+//! - Insert a [`Scatter`] to an entity with a [`Transform`]. The instances are placed in the
+//!   entity's local space, so they move with it.
+//! ```ignore
+//! let grass = Scatter::new(ScatterSurface::Terrain, grass_mesh, grass_material, 2.0)
+//!     .with_density_map(grass_mask)
+//!     .with_scale(0.8..1.4)
+//!     .with_fade(ScatterFade::new(40.0, 60.0));
+//! commands.entity(terrain_entity).insert(grass);
+//! ```
+//!
+//! ## Placement
+//!
+//! Placements are generated once from the [`Scatter::seed`], so the same settings always produce
+//! the same instances. They are regenerated when the placement settings or the surface change,
+//! call [`Scatter::mark_changed`] after editing a surface asset in place.
+//!
+//! The amount of instances is the [`Scatter::density`] times the surface area. An optional density
+//! map, stretched over the surface by its uv coordinates, scales the density by its red channel.
+//!
+//! ## Culling and fade
+//!
+//! Every frame, instances outside of the active camera's
+//! [`Frustum`](crate::math::bounding_volume::Frustum) are skipped if frustum culling is enabled.
+//! With a [`ScatterFade`], instances shrink to nothing between the fade start and end distance
+//! from the camera, and are not drawn past it.
+//!
+//! [`InstanceBatch`]: crate::core::standard::grouped::InstanceBatch
+
+mod instances;
+mod placement;
+
+pub mod prelude {
+    pub use super::{Scatter, ScatterFade, ScatterPlugin, ScatterSurface};
+}
+
+use std::ops::Range;
+
+use crate::{plugins::RenderPlugin, prelude::*, system::PhaseLabel};
+
+pub use instances::ScatterCache;
+
+/// Plugin which adds the scatter instance system
+pub struct ScatterPlugin;
+
+impl Plugin for ScatterPlugin {
+    fn build(&self, app: &mut App) {
+        // instance batches have to be pushed before the grouping system in the main layer
+        app.init_resource::<ScatterCache>().register_system(
+            instances::scatter_instances_system,
+            phase::PreRender.layer(layer::Pre),
+        );
+    }
+
+    fn dependencies(&self) -> Vec<PluginId> {
+        vec![PluginId::of::<RenderPlugin>()]
+    }
+}
+
+/// Surface which instances of a [`Scatter`] are placed on
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScatterSurface {
+    /// Triangles of a mesh with a triangle list topology
+    Mesh(Handle<Mesh>),
+    /// The [`Terrain`] on the same entity
+    Terrain,
+}
+
+/// Distance from the camera at which scattered instances shrink and disappear
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScatterFade {
+    /// Distance at which instances start to shrink
+    pub start: f32,
+    /// Distance at which instances are no longer drawn
+    pub end: f32,
+}
+
+impl ScatterFade {
+    /// Create a new fade between `start` and `end` distance
+    pub fn new(start: f32, end: f32) -> Self {
+        Self { start, end }
+    }
+
+    /// Returns the scale factor of an instance at `distance` from the camera, or None if it's
+    /// faded out
+    pub fn factor(&self, distance: f32) -> Option<f32> {
+        if distance >= self.end {
+            None
+        } else if distance <= self.start {
+            Some(1.0)
+        } else {
+            Some(1.0 - (distance - self.start) / (self.end - self.start))
+        }
+    }
+}
+
+/// Scatters instances of `mesh` with `material` over a [`ScatterSurface`]. Requires a
+/// [`Transform`].
+#[derive(Component, Clone, Debug)]
+pub struct Scatter {
+    pub surface: ScatterSurface,
+    pub mesh: Handle<Mesh>,
+    pub material: Handle<Material>,
+    /// Amount of instances per square unit of the surface, in its local units
+    pub density: f32,
+    /// Image scaling the density by its red channel, mapped by the surface uv coordinates
+    pub density_map: Option<Handle<Image>>,
+    /// Seed of the placement, the same seed places instances at the same positions
+    pub seed: u64,
+    /// Range of the random uniform scale of instances
+    pub scale: Range<f32>,
+    /// Whether instances are randomly rotated around their up axis
+    pub random_rotation: bool,
+    /// How much instances lean towards the surface normal, 0.0 keeps them upright along the local
+    /// `Y` axis and 1.0 aligns them with the normal
+    pub align_to_normal: f32,
+    pub fade: Option<ScatterFade>,
+    /// Upper limit of the amount of generated instances
+    pub max_instances: usize,
+    pub casts_shadows: bool,
+    version: u64,
+}
+
+impl Scatter {
+    /// Create a new scatter with `density` instances per square unit
+    pub fn new(
+        surface: ScatterSurface,
+        mesh: Handle<Mesh>,
+        material: Handle<Material>,
+        density: f32,
+    ) -> Self {
+        Self {
+            surface,
+            mesh,
+            material,
+            density,
+            density_map: None,
+            seed: 0,
+            scale: 1.0..1.0,
+            random_rotation: true,
+            align_to_normal: 0.0,
+            fade: None,
+            max_instances: 100_000,
+            casts_shadows: true,
+            version: 0,
+        }
+    }
+
+    /// Returns self with a new density map
+    #[inline]
+    #[must_use]
+    pub fn with_density_map(mut self, density_map: Handle<Image>) -> Self {
+        self.density_map = Some(density_map);
+        self
+    }
+
+    /// Returns self with new `seed`
+    #[inline]
+    #[must_use]
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Returns self with new `scale` range
+    #[inline]
+    #[must_use]
+    pub fn with_scale(mut self, scale: Range<f32>) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Returns self with new `align_to_normal`
+    #[inline]
+    #[must_use]
+    pub fn with_align_to_normal(mut self, align_to_normal: f32) -> Self {
+        self.align_to_normal = align_to_normal;
+        self
+    }
+
+    /// Returns self with a new distance fade
+    #[inline]
+    #[must_use]
+    pub fn with_fade(mut self, fade: ScatterFade) -> Self {
+        self.fade = Some(fade);
+        self
+    }
+
+    /// Returns self with new `max_instances`
+    #[inline]
+    #[must_use]
+    pub fn with_max_instances(mut self, max_instances: usize) -> Self {
+        self.max_instances = max_instances;
+        self
+    }
+
+    /// Returns self with new `casts_shadows`
+    #[inline]
+    #[must_use]
+    pub fn with_shadows(mut self, casts_shadows: bool) -> Self {
+        self.casts_shadows = casts_shadows;
+        self
+    }
+
+    /// Regenerate the placements, e.g. after editing the surface or density map asset
+    #[inline]
+    pub fn mark_changed(&mut self) {
+        self.version += 1;
+    }
+
+    /// Returns the version which is incremented by [`Self::mark_changed`]
+    #[inline]
+    pub(crate) fn version(&self) -> u64 {
+        self.version
+    }
+}
@@ -0,0 +1,160 @@
+use glam::{Mat4, Quat, Vec2, Vec3};
+
+use crate::{
+    prelude::{Image, Mesh, Rng},
+    terrain::{Heightmap, Terrain},
+};
+
+use super::Scatter;
+
+/// Returns the density map value at normalized `uv`, read from the red channel of the nearest
+/// texel. Images without data don't limit the density.
+fn sample_density(image: &Image, uv: Vec2) -> f32 {
+    let (width, height) = (image.size.width, image.size.height);
+    if image.data.len() < (width * height * 4) as usize || width == 0 || height == 0 {
+        return 1.0;
+    }
+
+    let uv = uv.clamp(Vec2::ZERO, Vec2::ONE);
+    let x = ((uv.x * width as f32) as u32).min(width - 1);
+    let y = ((uv.y * height as f32) as u32).min(height - 1);
+
+    image.data[((y * width + x) * 4) as usize] as f32 / u8::MAX as f32
+}
+
+/// Returns the amount of candidate instances for a surface with `area`
+fn candidate_count(scatter: &Scatter, area: f32) -> usize {
+    ((scatter.density.max(0.0) * area).round() as usize).min(scatter.max_instances)
+}
+
+/// Returns the local transform of an instance at `position` on a surface with `normal`
+fn instance_matrix(scatter: &Scatter, rng: &mut Rng, position: Vec3, normal: Vec3) -> Mat4 {
+    let up = Vec3::Y
+        .lerp(normal, scatter.align_to_normal.clamp(0.0, 1.0))
+        .try_normalize()
+        .unwrap_or(Vec3::Y);
+
+    let yaw = if scatter.random_rotation {
+        rng.range_f32(0.0..std::f32::consts::TAU)
+    } else {
+        0.0
+    };
+    let rotation = Quat::from_rotation_arc(Vec3::Y, up) * Quat::from_rotation_y(yaw);
+    let scale = rng.range_f32(scatter.scale.clone());
+
+    Mat4::from_scale_rotation_translation(Vec3::splat(scale), rotation, position)
+}
+
+/// Generates local instance transforms on the triangles of `surface`, with a probability
+/// proportional to the triangle area. Only triangle lists are supported.
+pub(crate) fn scatter_on_mesh(
+    scatter: &Scatter,
+    surface: &Mesh,
+    density_map: Option<&Image>,
+) -> Vec<Mat4> {
+    if surface.topology != wgpu::PrimitiveTopology::TriangleList {
+        tracing::warn!("Scatter surface mesh must have a triangle list topology");
+        return Vec::new();
+    }
+
+    let triangles = match &surface.indices {
+        Some(indices) => indices
+            .chunks_exact(3)
+            .map(|t| [t[0] as usize, t[1] as usize, t[2] as usize])
+            .collect::<Vec<_>>(),
+        None => (0..surface.positions.len() / 3)
+            .map(|i| [i * 3, i * 3 + 1, i * 3 + 2])
+            .collect(),
+    };
+    let position = |i: usize| Vec3::from(surface.positions[i]);
+
+    // cumulative areas to pick triangles weighted by their area
+    let mut cumulative = Vec::with_capacity(triangles.len());
+    let mut area = 0.0;
+    for &[a, b, c] in &triangles {
+        area += (position(b) - position(a))
+            .cross(position(c) - position(a))
+            .length()
+            * 0.5;
+        cumulative.push(area);
+    }
+
+    if area <= 0.0 {
+        return Vec::new();
+    }
+
+    let mut rng = Rng::new(scatter.seed);
+    let count = candidate_count(scatter, area);
+    let mut instances = Vec::with_capacity(count);
+
+    for _ in 0..count {
+        let target = rng.f32() * area;
+        let index = cumulative
+            .partition_point(|&a| a < target)
+            .min(triangles.len() - 1);
+        let [a, b, c] = triangles[index];
+
+        // uniform barycentric coordinates
+        let (mut u, mut v) = (rng.f32(), rng.f32());
+        if u + v > 1.0 {
+            (u, v) = (1.0 - u, 1.0 - v);
+        }
+        let w = 1.0 - u - v;
+
+        if let (Some(density_map), Some(uvs)) = (density_map, &surface.uvs) {
+            let uv = Vec2::from(uvs[a]) * w + Vec2::from(uvs[b]) * u + Vec2::from(uvs[c]) * v;
+            if rng.f32() >= sample_density(density_map, uv) {
+                continue;
+            }
+        }
+
+        let point = position(a) * w + position(b) * u + position(c) * v;
+        let normal = match &surface.normals {
+            Some(normals) => {
+                Vec3::from(normals[a]) * w + Vec3::from(normals[b]) * u + Vec3::from(normals[c]) * v
+            }
+            None => (position(b) - position(a)).cross(position(c) - position(a)),
+        }
+        .try_normalize()
+        .unwrap_or(Vec3::Y);
+
+        instances.push(instance_matrix(scatter, &mut rng, point, normal));
+    }
+
+    instances
+}
+
+/// Generates local instance transforms on the surface of `terrain`, the density map is stretched
+/// over the whole terrain like its splat map
+pub(crate) fn scatter_on_terrain(
+    scatter: &Scatter,
+    terrain: &Terrain,
+    heightmap: &Heightmap,
+    density_map: Option<&Image>,
+) -> Vec<Mat4> {
+    let mut rng = Rng::new(scatter.seed);
+    let count = candidate_count(scatter, terrain.size.x * terrain.size.y);
+    let mut instances = Vec::with_capacity(count);
+
+    for _ in 0..count {
+        let uv = Vec2::new(rng.f32(), rng.f32());
+        if let Some(density_map) = density_map
+            && rng.f32() >= sample_density(density_map, uv)
+        {
+            continue;
+        }
+
+        let local = uv * terrain.size;
+        let (Some(height), Some(normal)) = (
+            terrain.height_at(heightmap, local),
+            terrain.normal_at(heightmap, local),
+        ) else {
+            continue;
+        };
+
+        let point = Vec3::new(local.x, height, local.y);
+        instances.push(instance_matrix(scatter, &mut rng, point, normal));
+    }
+
+    instances
+}
@@ -0,0 +1,177 @@
+use std::{cell::Cell, collections::HashMap, ptr, sync::Arc};
+
+use rhai::{Dynamic, Engine, EvalAltResult, INT};
+
+use crate::{prelude::*, reflect::Reflect};
+
+use super::{
+    ScriptEvent,
+    value::{apply_dynamic, to_dynamic},
+};
+
+/// Inserts a component built from a script value, see
+/// [`ScriptPlugin::with_component`](super::ScriptPlugin::with_component)
+pub(super) type Inserter = fn(&mut Commands, EntityId, &Dynamic) -> Result<(), String>;
+
+thread_local! {
+    /// App the currently running scripts have access to
+    static APP: Cell<*mut App> = const { Cell::new(ptr::null_mut()) };
+}
+
+/// Give the script functions access to `app` while `f` runs
+pub(super) fn with_app_context<R>(app: &mut App, f: impl FnOnce() -> R) -> R {
+    let previous = APP.replace(app);
+    let result = f();
+    APP.set(previous);
+    result
+}
+
+/// Run `f` with the app of the current [`with_app_context`] call
+fn with_app<R>(f: impl FnOnce(&mut App) -> R) -> Result<R, Box<EvalAltResult>> {
+    let app = APP.get();
+    if app.is_null() {
+        return Err("Script functions can only be called while scripts run".into());
+    }
+
+    // Safety: the pointer is only set while `with_app_context` holds the mutable borrow, and the
+    // script runner doesn't access the app while scripts run
+    Ok(f(unsafe { &mut *app }))
+}
+
+/// Scripts see entities as integers
+#[inline]
+pub(super) fn entity_to_int(entity: EntityId) -> INT {
+    entity.to_bits() as INT
+}
+
+#[inline]
+fn int_to_entity(entity: INT) -> EntityId {
+    EntityId::from_bits(entity as u64)
+}
+
+fn commands(app: &mut App) -> Commands<'_, '_> {
+    Commands::new(
+        &mut app.world.entities.tracking,
+        &mut app.world.command_queue,
+    )
+}
+
+fn component_type(app: &App, name: &str) -> Result<std::any::TypeId, Box<EvalAltResult>> {
+    app.type_registry
+        .get_by_name(name)
+        .ok_or_else(|| format!("Component '{}' is not registered", name).into())
+}
+
+/// Returns true if `entity` has the component `name`
+fn has(entity: INT, name: &str) -> Result<bool, Box<EvalAltResult>> {
+    with_app(|app| {
+        let type_id = component_type(app, name)?;
+        Ok(app
+            .world
+            .entities
+            .get_component_untyped(int_to_entity(entity), type_id, false)
+            .is_some())
+    })?
+}
+
+/// Returns the component `name` of `entity` as a script value, or unit if it doesn't have it
+fn get(entity: INT, name: &str) -> Result<Dynamic, Box<EvalAltResult>> {
+    with_app(|app| {
+        let type_id = component_type(app, name)?;
+        let value = app
+            .world
+            .entities
+            .get_component_untyped(int_to_entity(entity), type_id, false)
+            .and_then(|ptr| app.type_registry.reflect(ptr, type_id))
+            .map(to_dynamic)
+            .unwrap_or(Dynamic::UNIT);
+        Ok(value)
+    })?
+}
+
+/// Writes `value` into the component `name` of `entity` and marks it changed
+fn set(entity: INT, name: &str, value: Dynamic) -> Result<(), Box<EvalAltResult>> {
+    with_app(|app| {
+        let type_id = component_type(app, name)?;
+        let ptr = app
+            .world
+            .entities
+            .get_component_untyped(int_to_entity(entity), type_id, true)
+            .ok_or_else(|| format!("Entity {} has no component '{}'", entity, name))?;
+
+        // Safety: the pointer comes from the component storage of `type_id` and the world is
+        // exclusively borrowed
+        let component = unsafe { app.type_registry.reflect_mut(ptr, type_id) }
+            .expect("type was found in the registry");
+        apply_dynamic(component, &value).map_err(Into::into)
+    })?
+}
+
+/// Register the entity, component and event functions available to scripts
+pub(super) fn register_api(engine: &mut Engine, inserters: HashMap<&'static str, Inserter>) {
+    engine
+        .on_print(|text| tracing::info!(target: "script", "{}", text))
+        .on_debug(|text, source, position| {
+            tracing::debug!(target: "script", "{} @ {:?} {}", text, source, position)
+        });
+
+    engine
+        .register_fn("spawn_entity", || {
+            with_app(|app| entity_to_int(commands(app).spawn_empty().entity_id()))
+        })
+        .register_fn("despawn", |entity: INT| {
+            with_app(|app| commands(app).entity(int_to_entity(entity)).despawn())
+        })
+        .register_fn("despawn_recursive", |entity: INT| {
+            with_app(|app| {
+                commands(app)
+                    .entity(int_to_entity(entity))
+                    .despawn_recursive()
+            })
+        })
+        .register_fn("exists", |entity: INT| {
+            with_app(|app| {
+                app.world
+                    .entities
+                    .tracking
+                    .get_location(int_to_entity(entity))
+                    .is_some()
+            })
+        });
+
+    let inserters = Arc::new(inserters);
+    engine
+        .register_fn("has", has)
+        .register_fn("get", get)
+        .register_fn("set", set)
+        .register_fn(
+            "insert",
+            move |entity: INT, name: &str, value: Dynamic| -> Result<(), Box<EvalAltResult>> {
+                let inserter = inserters.get(name).ok_or_else(|| {
+                    format!("Component '{}' can't be inserted from scripts", name)
+                })?;
+                with_app(|app| inserter(&mut commands(app), int_to_entity(entity), &value))?
+                    .map_err(Into::into)
+            },
+        );
+
+    engine
+        .register_fn("send_event", |name: &str, data: Dynamic| {
+            with_app(|app| app.create_event(ScriptEvent::new(name, data)))
+        })
+        .register_fn("send_event", |name: &str| {
+            with_app(|app| app.create_event(ScriptEvent::new(name, Dynamic::UNIT)))
+        });
+}
+
+/// [`Inserter`] for component `C`, the script value is applied to `C::default()`
+pub(super) fn insert_component<C: Component + Reflect + Default>(
+    commands: &mut Commands,
+    entity: EntityId,
+    value: &Dynamic,
+) -> Result<(), String> {
+    let mut component = C::default();
+    apply_dynamic(&mut component, value)?;
+    commands.entity(entity).insert(component);
+    Ok(())
+}
@@ -0,0 +1,112 @@
+use std::{fmt::Debug, path::Path};
+
+use rhai::AST;
+
+use crate::{
+    assets::{Asset, AssetLoader, Assets, LoadableAsset, io},
+    prelude::Resources,
+};
+
+/// Rhai script asset, loaded from `.rhai` files with the [`AssetLoader`] or created from source.
+/// Add its handle to an entity to run it, see the [scripting module](super).
+pub struct Script {
+    name: String,
+    source: String,
+    /// None if the source failed to compile
+    ast: Option<AST>,
+    /// Incremented on every reload, script instances restart when it changes
+    version: u32,
+}
+
+impl Asset for Script {}
+
+impl Script {
+    /// Create a script from `source`, `name` is used in error messages
+    pub fn new(name: impl Into<String>, source: impl Into<String>) -> Self {
+        let mut script = Self {
+            name: name.into(),
+            source: source.into(),
+            ast: None,
+            version: 0,
+        };
+        script.compile();
+        script
+    }
+
+    /// Returns the name of the script, its path if it was loaded from a file
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the source code
+    #[inline]
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns the compiled script, or None if it has syntax errors
+    #[inline]
+    pub fn ast(&self) -> Option<&AST> {
+        self.ast.as_ref()
+    }
+
+    /// Returns the number of times the script was reloaded
+    #[inline]
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Replace the source code and recompile, running instances restart
+    pub fn set_source(&mut self, source: impl Into<String>) {
+        self.source = source.into();
+        self.version = self.version.wrapping_add(1);
+        self.compile();
+    }
+
+    fn compile(&mut self) {
+        // only parses the source, functions are resolved by the engine running the script
+        self.ast = match rhai::Engine::new_raw().compile(&self.source) {
+            Ok(ast) => Some(ast),
+            Err(error) => {
+                tracing::error!("Failed to compile script '{}': {}", self.name, error);
+                None
+            }
+        };
+    }
+}
+
+/// Reads the source of the script file at `path`
+fn read_source(path: &Path) -> Result<String, String> {
+    let bytes = io::read(path).map_err(|_| format!("Could not read script at '{:?}'", path))?;
+    String::from_utf8(bytes).map_err(|_| format!("Script at '{:?}' is not valid UTF-8", path))
+}
+
+impl LoadableAsset for Script {
+    fn load<P: AsRef<Path> + Debug>(_: &mut AssetLoader, _: &mut Resources, path: P) -> Self {
+        let source = read_source(path.as_ref()).unwrap_or_else(|err| panic!("{}", err));
+        Self::new(path.as_ref().to_string_lossy(), source)
+    }
+
+    /// Reads the file again, the version follows the replaced script so its instances restart
+    fn reload(
+        _: &mut AssetLoader,
+        resources: &mut Resources,
+        path: &Path,
+    ) -> Option<Result<Self, String>> {
+        let mut script = match read_source(path) {
+            Ok(source) => Self::new(path.to_string_lossy(), source),
+            Err(err) => return Some(Err(err)),
+        };
+
+        let previous = resources.try_get::<Assets<Script>>().and_then(|scripts| {
+            scripts
+                .iter()
+                .find(|(_, previous)| previous.name == script.name)
+                .map(|(_, previous)| previous.version)
+        });
+        script.version = previous.map_or(0, |version| version.wrapping_add(1));
+
+        Some(Ok(script))
+    }
+}
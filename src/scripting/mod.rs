@@ -0,0 +1,272 @@
+//! # Scripting plugin
+//! Runs [Rhai](https://rhai.rs) scripts attached to entities, enabled with the `scripting` feature.
+//!
+//! ## Usage
+//!
+//! Add the [`ScriptPlugin`] and insert a [`Handle<Script>`] on an entity. Every frame in
+//! [`phase::Update`], the script functions below are called if the script defines them. `this` is
+//! an object map kept between calls, use it to store the script's state.
+//! ```ignore
+//! app.add_plugin(ScriptPlugin::default());
+//!
+//! let script = asset_loader.load::<Script>("assets/scripts/spin.rhai", resources);
+//! commands.spawn_empty().insert(script).insert(Transform::default());
+//! ```
+//! ```rhai
+//! fn init(entity) { this.speed = 2.0; }
+//!
+//! fn update(entity, dt) {
+//!     let transform = get(entity, "Transform");
+//!     transform.translation.x += this.speed * dt;
+//!     set(entity, "Transform", #{ translation: transform.translation });
+//! }
+//!
+//! fn on_event(entity, name, data) {
+//!     if name == "reverse" { this.speed = -this.speed; }
+//! }
+//! ```
+//!
+//! `init` is called again when the script is reloaded. Scripts loaded from files are reloaded
+//! when the file changes if the [`HotReloadPlugin`](crate::assets::HotReloadPlugin) is added.
+//!
+//! ## Script functions
+//!
+//! Entities are integers, components are referenced by their type name and must be registered
+//! with [`App::register_type`]. Their values are converted through [`Reflect`], structs become
+//! object maps and other types with fields become arrays.
+//!
+//! - `spawn_entity()`, `despawn(entity)`, `despawn_recursive(entity)` - deferred like [`Commands`]
+//! - `exists(entity)`, `has(entity, name)`
+//! - `get(entity, name)` - returns `()` if the entity doesn't have the component
+//! - `set(entity, name, value)` - value may be a map with only some of the fields
+//! - `insert(entity, name, value)` - only for components added with
+//!   [`ScriptPlugin::with_component`]
+//! - `send_event(name)`, `send_event(name, data)` - sends a [`ScriptEvent`], which every script
+//!   receives in `on_event` on the next frame, and systems can read with an [`EventReader`]
+//!
+//! [`Reflect`]: crate::reflect::Reflect
+//! [`EventReader`]: crate::event::EventReader
+
+mod api;
+mod asset;
+mod value;
+
+pub mod prelude {
+    pub use super::{Script, ScriptEvent, ScriptPlugin};
+}
+
+use std::collections::HashMap;
+
+use rhai::{AST, CallFnOptions, Dynamic, Engine, Scope};
+
+use crate::{
+    event::{EventReader, Events},
+    macros::{Event, Resource},
+    prelude::*,
+    reflect::{Reflect, registry::short_type_name},
+};
+
+pub use asset::Script;
+
+use api::{Inserter, entity_to_int, insert_component, register_api, with_app_context};
+
+/// Event sent by scripts with `send_event`, and delivered to every script's `on_event`.
+/// Systems can send it too.
+#[derive(Event, Debug, Clone)]
+pub struct ScriptEvent {
+    pub name: String,
+    /// Script value, `()` if no data was sent
+    pub data: Dynamic,
+}
+
+impl ScriptEvent {
+    /// Create a new script event
+    pub fn new(name: impl Into<String>, data: Dynamic) -> Self {
+        Self {
+            name: name.into(),
+            data,
+        }
+    }
+}
+
+/// Component which scripts can insert
+struct ScriptComponent {
+    name: &'static str,
+    register: fn(&mut App),
+    inserter: Inserter,
+}
+
+/// Plugin which runs entity scripts, see the [scripting module](crate::scripting).
+///
+/// Not part of the [`DefaultPlugins`].
+pub struct ScriptPlugin {
+    components: Vec<ScriptComponent>,
+}
+
+impl Default for ScriptPlugin {
+    /// Scripts can insert [`Transform`]
+    fn default() -> Self {
+        Self::empty().with_component::<Transform>()
+    }
+}
+
+impl ScriptPlugin {
+    /// Plugin without any insertable components
+    pub fn empty() -> Self {
+        Self {
+            components: Vec::new(),
+        }
+    }
+
+    /// Allow scripts to insert component `C`, it is registered to the type registry
+    pub fn with_component<C: Component + Reflect + Default>(mut self) -> Self {
+        self.components.push(ScriptComponent {
+            name: short_type_name::<C>(),
+            register: |app| {
+                app.register_type::<C>();
+            },
+            inserter: insert_component::<C>,
+        });
+        self
+    }
+}
+
+impl Plugin for ScriptPlugin {
+    fn build(&self, app: &mut App) {
+        let mut inserters = HashMap::new();
+        for component in &self.components {
+            (component.register)(app);
+            inserters.insert(component.name, component.inserter);
+        }
+
+        let mut engine = Engine::new();
+        register_api(&mut engine, inserters);
+
+        app.init_resource::<Assets<Script>>()
+            .register_event::<ScriptEvent>()
+            .set_resource(ScriptRunner {
+                engine,
+                instances: HashMap::new(),
+                event_cursor: 0,
+            })
+            .register_system(run_scripts_system, phase::Update);
+    }
+}
+
+/// Script state of an entity
+struct ScriptInstance {
+    handle: Handle<Script>,
+    /// Script version `init` was called with
+    version: u32,
+    /// Object map bound to `this`
+    state: Dynamic,
+}
+
+/// Scripting engine and the script instances of entities
+#[derive(Resource)]
+struct ScriptRunner {
+    engine: Engine,
+    instances: HashMap<EntityId, ScriptInstance>,
+    /// Cursor of the [`ScriptEvent`] reader
+    event_cursor: usize,
+}
+
+impl ScriptRunner {
+    /// Call script function `name` if the script defines it, errors are logged
+    fn call(
+        &self,
+        ast: &AST,
+        state: &mut Dynamic,
+        name: &str,
+        args: impl rhai::FuncArgs,
+        arg_count: usize,
+    ) {
+        let defined = ast
+            .iter_functions()
+            .any(|function| function.name == name && function.params.len() == arg_count);
+        if !defined {
+            return;
+        }
+
+        let options = CallFnOptions::new().eval_ast(false).bind_this_ptr(state);
+        if let Err(error) =
+            self.engine
+                .call_fn_with_options::<Dynamic>(options, &mut Scope::new(), ast, name, args)
+        {
+            tracing::warn!("Script function '{}' failed: {}", name, error);
+        }
+    }
+}
+
+/// Runs `init`, `on_event` and `update` of entity scripts
+fn run_scripts_system(app: &mut App) {
+    // taken out so script functions can access the whole app
+    let Some(mut runner) = app.world.resources.remove::<ScriptRunner>() else {
+        return;
+    };
+
+    let entities: Vec<_> = app
+        .world
+        .query::<(EntityId, &Handle<Script>)>()
+        .iter_mut()
+        .into_iter()
+        .map(|(entity, handle)| (entity, handle.clone()))
+        .collect();
+
+    let scripts: Vec<_> = {
+        let assets = app.world.resources.get::<Assets<Script>>();
+        entities
+            .into_iter()
+            .filter_map(|(entity, handle)| {
+                let script = assets.get(&handle)?;
+                Some((entity, handle, script.ast()?.clone(), script.version()))
+            })
+            .collect()
+    };
+    let events = EventReader::new(
+        app.world.resources.get::<Events<ScriptEvent>>(),
+        &mut runner.event_cursor,
+    )
+    .read()
+    .cloned()
+    .collect::<Vec<_>>();
+    let dt = app.world.resources.get::<Time>().delta() as rhai::FLOAT;
+
+    // instances of entities which no longer have a script are dropped
+    let mut previous = std::mem::take(&mut runner.instances);
+    with_app_context(app, || {
+        for (entity, handle, ast, version) in scripts {
+            let mut instance = match previous.remove(&entity) {
+                Some(instance) if instance.handle == handle && instance.version == version => {
+                    instance
+                }
+                _ => {
+                    let mut instance = ScriptInstance {
+                        handle,
+                        version,
+                        state: rhai::Map::new().into(),
+                    };
+                    runner.call(
+                        &ast,
+                        &mut instance.state,
+                        "init",
+                        (entity_to_int(entity),),
+                        1,
+                    );
+                    instance
+                }
+            };
+
+            let id = entity_to_int(entity);
+            for event in &events {
+                let args = (id, event.name.clone(), event.data.clone());
+                runner.call(&ast, &mut instance.state, "on_event", args, 3);
+            }
+            runner.call(&ast, &mut instance.state, "update", (id, dt), 2);
+
+            runner.instances.insert(entity, instance);
+        }
+    });
+
+    app.world.resources.insert(runner);
+}
@@ -0,0 +1,130 @@
+use rhai::{Array, Dynamic, FLOAT, INT, Map};
+
+use crate::reflect::Reflect;
+
+/// Convert numeric leaf values between Rust and script values
+macro_rules! numeric_conversions {
+    (int: $($int:ty),+; float: $($float:ty),+) => {
+        fn leaf_to_dynamic(value: &dyn Reflect) -> Option<Dynamic> {
+            $(if let Some(value) = value.downcast_ref::<$int>() {
+                return Some(Dynamic::from_int(*value as INT));
+            })+
+            $(if let Some(value) = value.downcast_ref::<$float>() {
+                return Some(Dynamic::from_float(*value as FLOAT));
+            })+
+
+            if let Some(value) = value.downcast_ref::<bool>() {
+                Some(Dynamic::from_bool(*value))
+            } else if let Some(value) = value.downcast_ref::<char>() {
+                Some(Dynamic::from_char(*value))
+            } else if let Some(value) = value.downcast_ref::<String>() {
+                Some(Dynamic::from(value.clone()))
+            } else {
+                value
+                    .downcast_ref::<&'static str>()
+                    .map(|value| Dynamic::from(value.to_string()))
+            }
+        }
+
+        /// Returns false if `target` is not a leaf type
+        fn apply_leaf(target: &mut dyn Reflect, value: &Dynamic) -> Result<bool, String> {
+            let number = || {
+                value
+                    .as_int()
+                    .map(|int| int as FLOAT)
+                    .or_else(|_| value.as_float())
+                    .map_err(|actual| format!("Expected a number, got '{}'", actual))
+            };
+
+            $(if let Some(target) = target.downcast_mut::<$int>() {
+                *target = number()? as $int;
+                return Ok(true);
+            })+
+            $(if let Some(target) = target.downcast_mut::<$float>() {
+                *target = number()? as $float;
+                return Ok(true);
+            })+
+
+            if let Some(target) = target.downcast_mut::<bool>() {
+                *target = value
+                    .as_bool()
+                    .map_err(|actual| format!("Expected a bool, got '{}'", actual))?;
+            } else if let Some(target) = target.downcast_mut::<char>() {
+                *target = value
+                    .as_char()
+                    .map_err(|actual| format!("Expected a char, got '{}'", actual))?;
+            } else if let Some(target) = target.downcast_mut::<String>() {
+                *target = value
+                    .clone()
+                    .into_string()
+                    .map_err(|actual| format!("Expected a string, got '{}'", actual))?;
+            } else {
+                return Ok(false);
+            }
+            Ok(true)
+        }
+    };
+}
+
+numeric_conversions!(
+    int: u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize;
+    float: f32, f64
+);
+
+/// Convert a reflected value to a script value. Structs become object maps of their fields,
+/// other types with fields (tuples, arrays, lists) become arrays.
+pub(super) fn to_dynamic(value: &dyn Reflect) -> Dynamic {
+    if let Some(leaf) = leaf_to_dynamic(value) {
+        return leaf;
+    }
+
+    let names = value.field_names();
+    if !names.is_empty() {
+        let map: Map = names
+            .iter()
+            .filter_map(|name| Some(((*name).into(), to_dynamic(value.field(name)?))))
+            .collect();
+        return map.into();
+    }
+
+    let array: Array = (0..)
+        .map_while(|index| value.field_by_index(index))
+        .map(to_dynamic)
+        .collect();
+    array.into()
+}
+
+/// Write a script value into a reflected value. Object maps may contain only some of the fields.
+pub(super) fn apply_dynamic(target: &mut dyn Reflect, value: &Dynamic) -> Result<(), String> {
+    if apply_leaf(target, value)? {
+        return Ok(());
+    }
+
+    if let Some(map) = value.clone().try_cast::<Map>() {
+        for (name, field_value) in map {
+            let type_name = target.type_name();
+            let field = target
+                .field_mut(&name)
+                .ok_or_else(|| format!("'{}' has no field '{}'", type_name, name))?;
+            apply_dynamic(field, &field_value)?;
+        }
+        return Ok(());
+    }
+
+    if let Some(array) = value.clone().try_cast::<Array>() {
+        for (index, field_value) in array.iter().enumerate() {
+            let type_name = target.type_name();
+            let field = target
+                .field_mut_by_index(index)
+                .ok_or_else(|| format!("'{}' has no field {}", type_name, index))?;
+            apply_dynamic(field, field_value)?;
+        }
+        return Ok(());
+    }
+
+    Err(format!(
+        "Cannot assign '{}' to '{}'",
+        value.type_name(),
+        target.type_name()
+    ))
+}
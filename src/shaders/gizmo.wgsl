@@ -0,0 +1,30 @@
+struct Camera {
+  view_proj: mat4x4<f32>,
+  view_pos: vec3<f32>,
+}
+
+@group(0) @binding(0) var<uniform> camera: Camera;
+
+struct Input {
+  @location(0) pos: vec3<f32>,
+  @location(1) color: vec4<f32>,
+}
+
+struct Output {
+  @builtin(position) clip: vec4<f32>,
+  @location(0) color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(input: Input) -> Output {
+  var out: Output;
+  out.clip = camera.view_proj * vec4<f32>(input.pos, 1.0);
+  out.color = input.color;
+
+  return out;
+}
+
+@fragment
+fn fs_main(input: Output) -> @location(0) vec4<f32> {
+  return input.color;
+}
@@ -0,0 +1,39 @@
+struct Camera {
+  view_proj: mat4x4<f32>,
+  view_pos: vec3<f32>,
+}
+
+@group(0) @binding(0) var<uniform> camera: Camera;
+
+struct Outline {
+  model: mat4x4<f32>,
+  color: vec4<f32>,
+  viewport: vec2<f32>,
+  width: f32,
+}
+@group(1) @binding(0) var<uniform> outline: Outline;
+
+struct Input {
+  @location(0) pos: vec3<f32>,
+  @location(2) normal: vec3<f32>,
+}
+
+@vertex
+fn vs_main(input: Input) -> @builtin(position) vec4<f32> {
+  var clip = camera.view_proj * outline.model * vec4<f32>(input.pos, 1.0);
+  let clip_normal = camera.view_proj * outline.model * vec4<f32>(input.normal, 0.0);
+
+  // extrude in screen space, so the outline width is constant in pixels
+  let direction = clip_normal.xy;
+  if (dot(direction, direction) > 0.0) {
+    let offset = normalize(direction) / outline.viewport * outline.width * 2.0;
+    clip = vec4<f32>(clip.xy + offset * clip.w, clip.zw);
+  }
+
+  return clip;
+}
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+  return outline.color;
+}
@@ -0,0 +1,26 @@
+struct Camera {
+  view_proj: mat4x4<f32>,
+  view_pos: vec3<f32>,
+}
+
+@group(0) @binding(0) var<uniform> camera: Camera;
+
+struct PushConstant {
+  model: mat4x4<f32>,
+  id: u32,
+}
+var<push_constant> pc: PushConstant;
+
+struct Input {
+  @location(0) pos: vec3<f32>,
+}
+
+@vertex
+fn vs_main(input: Input) -> @builtin(position) vec4<f32> {
+  return camera.view_proj * pc.model * vec4<f32>(input.pos, 1.0);
+}
+
+@fragment
+fn fs_main() -> @location(0) u32 {
+  return pc.id;
+}
@@ -1,6 +1,7 @@
 struct Camera {
   view_proj: mat4x4<f32>,
   view_pos: vec3<f32>,
+  exposure: f32,
 }
 
 @group(2) @binding(0) var<uniform> camera: Camera; 
@@ -18,6 +19,7 @@ struct Output {
   @location(1) uv: vec2<f32>,
   @location(2) world: vec3<f32>,
   @location(3) world_normal: vec3<f32>,
+  @location(4) @interpolate(flat) origin: vec3<f32>,
 };
 
 struct Transform {
@@ -39,6 +41,7 @@ fn vs_main(
   out.world_normal = normalize((transform[instance_index].srt * vec4<f32>(input.normal, 0.0)).xyz);
   out.clip = camera.view_proj * world_pos; 
   out.uv = input.uv;
+  out.origin = transform[instance_index].srt[3].xyz;
 
   return out;
 }
@@ -70,13 +73,19 @@ struct LightData {
 
   flags: u32,
   shadow_map_index: u32,
+  cookie_index: u32,
 
   pos: vec3<f32>,
   direction: vec3<f32>,
+  attenuation: vec4<f32>, // constant, linear, quadratic, range window
 }
 
 struct PushConstant {
   light_count: u32,
+  instance_flags: u32, // not shadow receiver
+  range_min: f32,
+  range_max: f32, // 0 without a visibility range
+  range_fade: f32,
 }
 var<push_constant> pc: PushConstant;
 
@@ -85,9 +94,15 @@ var<push_constant> pc: PushConstant;
 @group(3) @binding(2) var point_shadow_map: texture_depth_cube_array;
 @group(3) @binding(3) var spot_shadow_map: texture_depth_2d_array;
 @group(3) @binding(4) var shadow_map_sampler: sampler_comparison;
+@group(3) @binding(5) var cookies: texture_2d_array<f32>;
+@group(3) @binding(6) var cookie_sampler: sampler;
 
 @fragment 
 fn fs_main(in: Output) -> @location(0) vec4<f32> {
+  if (visibility_range_fade(in.origin) <= dither_threshold(in.clip.xy)) {
+    discard;
+  }
+
   // let flip_normal_map_y = (material.booleans & 1) != 0;
   // let cull_back_faces = (material.booleans & 2) != 0;
   let unlit = (material.booleans & 4) != 0;
@@ -108,16 +123,76 @@ const POINT: u32 = 4;
 const SPOT: u32 = 8;
 const VISIBLE: u32 = 16;
 const SHADOW: u32 = 32;
+const COOKIE: u32 = 64;
+
+const NOT_SHADOW_RECEIVER: u32 = 1;
 
-fn calculate_attenuation(light_distance: f32, range: f32, flags: u32) -> f32 {
-  if ((flags & POINT) != 0) || ((flags & SPOT) != 0) {
-    let constant = 1.0;
-    let linear = 0.09;
-    let quadratic = 0.032;
-    return 1.0 / (constant + linear * light_distance + quadratic * light_distance * light_distance);
+// Returns how visible the instance at `origin` is in its visibility range, 0 outside of it and 1
+// outside of the fade bands
+fn visibility_range_fade(origin: vec3<f32>) -> f32 {
+  if (pc.range_max <= 0.0) {
+    return 1.0;
   }
 
-  return 1.0;
+  let distance = distance(camera.view_pos, origin);
+  if (distance < pc.range_min || distance > pc.range_max) {
+    return 0.0;
+  }
+  if (pc.range_fade <= 0.0) {
+    return 1.0;
+  }
+
+  // no fade in when visible from the camera position
+  let fade_in = select(1.0, (distance - pc.range_min) / pc.range_fade, pc.range_min > 0.0);
+  let fade_out = (pc.range_max - distance) / pc.range_fade;
+  return saturate(min(fade_in, fade_out));
+}
+
+// 4x4 ordered dither threshold of the `pixel`, in (0, 1)
+fn dither_threshold(pixel: vec2<f32>) -> f32 {
+  var bayer = array<f32, 16>(
+    0.0, 8.0, 2.0, 10.0,
+    12.0, 4.0, 14.0, 6.0,
+    3.0, 11.0, 1.0, 9.0,
+    15.0, 7.0, 13.0, 5.0,
+  );
+  let p = vec2<u32>(pixel) % 4u;
+  return (bayer[p.y * 4u + p.x] + 0.5) / 16.0;
+}
+
+// Light units
+//
+// Light intensities are multiplied by the camera exposure, which is 1.0 for cameras without an
+// `Exposure` component, so unitless intensities around 1.0 work as they are. With physical
+// units, directional and ambient intensities are illuminance in lux, and point and spot
+// intensities are luminous intensity in candela (lumens / 4pi for point lights, lumens / cone
+// solid angle for spot lights). The exposure converts them to display values:
+//
+//   exposure = 1 / (1.2 * 2^ev100)
+//
+// so a 100 000 lux sun seen with an ev100 of 15 ends up at an intensity of about 2.5.
+
+fn calculate_attenuation(light_distance: f32, light: LightData) -> f32 {
+  if ((light.flags & POINT) == 0) && ((light.flags & SPOT) == 0) {
+    return 1.0;
+  }
+
+  let constant = light.attenuation.x;
+  let linear = light.attenuation.y;
+  let quadratic = light.attenuation.z;
+
+  // avoid infinite intensity at the light's position
+  let denominator = max(constant + linear * light_distance + quadratic * light_distance * light_distance, 0.0001);
+  var attenuation = 1.0 / denominator;
+
+  // smoothly fade out to zero at the light's range
+  if (light.attenuation.w != 0.0 && light.range > 0.0) {
+    let ratio = light_distance / light.range;
+    let window = clamp(1.0 - ratio * ratio * ratio * ratio, 0.0, 1.0);
+    attenuation *= window * window;
+  }
+
+  return attenuation;
 }
 
 fn calculate_spotlight_intensity(in: Output, light: LightData) -> f32 {
@@ -172,14 +247,14 @@ fn calculate_light_contribution(material_color: vec3<f32>, in: Output, light_i:
 
   // Ambient contribution
   if ((light.flags & AMBIENT) != 0) {
-    return light.color.rgb * light.intensity * material_color.rgb + emissive.rgb;
+    return light.color.rgb * light.intensity * camera.exposure * material_color.rgb + emissive.rgb;
   }
 
   // Light direction and attenuation
   let light_dir = calc_light_dir(light, in.world, light.flags);
   // let distance = length(light.pos - in.world);
   let light_distance = distance(light.pos, in.world);
-  let attenuation = calculate_attenuation(light_distance, light.range, light.flags);
+  let attenuation = calculate_attenuation(light_distance, light);
 
   // Diffuse contribution
   let diffuse_strength = max(dot(in.world_normal, light_dir), 0.0);
@@ -196,7 +271,7 @@ fn calculate_light_contribution(material_color: vec3<f32>, in: Output, light_i:
 
   // Intensity
   let spotlight_intensity = calculate_spotlight_intensity(in, light);
-  let intensity = light.intensity * spotlight_intensity;
+  let intensity = light.intensity * camera.exposure * spotlight_intensity;
 
   if intensity <= 0.0 {
     // spotlight is outside the cone or intensity is 0, no need for shadow calculations
@@ -206,8 +281,11 @@ fn calculate_light_contribution(material_color: vec3<f32>, in: Output, light_i:
   // Shadow
   let shadow = calculate_shadow(light, in, light_i, light_dir);
 
+  // Cookie
+  let cookie = calculate_cookie(light, in.world);
+
   // Calculate final color
-  return shadow * light.color.rgb * attenuation * intensity * (
+  return shadow * cookie * light.color.rgb * attenuation * intensity * (
     diffuse_color * diffuse_strength + specular_color * specular_strength
   ) + emissive.rgb;
 }
@@ -224,7 +302,7 @@ fn calculate_final_color(in: Output, material_color: vec4<f32>) -> vec4<f32> {
 }
 
 fn calculate_shadow(light: LightData, in: Output, light_i: u32, light_dir: vec3<f32>) -> f32 {
-  if ((light.flags & SHADOW) == 0) {
+  if ((light.flags & SHADOW) == 0 || (pc.instance_flags & NOT_SHADOW_RECEIVER) != 0) {
     return 1.0;
   }
 
@@ -272,6 +350,27 @@ fn calculate_shadow(light: LightData, in: Output, light_i: u32, light_dir: vec3<
   return 1.0;
 }
 
+// Projects the world position with the light's view projection matrix, and samples its cookie.
+// Surfaces outside of the light frustum receive no light
+fn calculate_cookie(light: LightData, world_pos: vec3<f32>) -> vec3<f32> {
+  if ((light.flags & COOKIE) == 0) {
+    return vec3<f32>(1.0);
+  }
+
+  let homogeneous_coords = light.view_proj * vec4<f32>(world_pos, 1.0);
+  if (homogeneous_coords.w <= 0.0) {
+    return vec3<f32>(0.0);
+  }
+
+  let uv = homogeneous_coords.xy / homogeneous_coords.w * vec2<f32>(0.5, -0.5) + vec2<f32>(0.5, 0.5);
+  if (uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0) {
+    return vec3<f32>(0.0);
+  }
+
+  // sampled in non-uniform control flow, so the level has to be explicit
+  return textureSampleLevel(cookies, cookie_sampler, uv, light.cookie_index, 0.0).rgb;
+}
+
 fn get_cube_map_coords(mapped_uv: vec2<f32>, face_index: u32) -> vec3<f32> {
     if (face_index == 0u) { return vec3(1.0, -mapped_uv.y, -mapped_uv.x); }  // +X face
     if (face_index == 1u) { return vec3(-1.0, -mapped_uv.y, mapped_uv.x); }  // -X face
@@ -12,6 +12,7 @@ struct LightData {
   fields_u: vec4<u32>,
   fields_p: vec4<f32>,
   fields_d: vec4<f32>,
+  fields_a: vec4<f32>,
 }
 
 @group(0) @binding(0) var<storage, read> transform: array<mat4x4<f32>>; 
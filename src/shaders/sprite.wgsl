@@ -0,0 +1,44 @@
+struct Camera {
+  view_proj: mat4x4<f32>,
+  view_pos: vec3<f32>,
+}
+
+@group(1) @binding(0) var<uniform> camera: Camera;
+
+@group(0) @binding(0) var sprite_texture: texture_2d<f32>;
+@group(0) @binding(1) var sprite_sampler: sampler;
+
+struct Input {
+  @location(0) pos: vec3<f32>,
+  @location(1) uv: vec2<f32>,
+  @location(2) color: vec4<f32>,
+}
+
+struct Output {
+  @builtin(position) clip: vec4<f32>,
+  @location(0) uv: vec2<f32>,
+  @location(1) color: vec4<f32>,
+};
+
+// sprite vertices are already in world space
+@vertex
+fn vs_main(input: Input) -> Output {
+  var out: Output;
+  out.clip = camera.view_proj * vec4<f32>(input.pos, 1.0);
+  out.uv = input.uv;
+  out.color = input.color;
+
+  return out;
+}
+
+@fragment
+fn fs_main(in: Output) -> @location(0) vec4<f32> {
+  let color = textureSample(sprite_texture, sprite_sampler, in.uv) * in.color;
+
+  // fully transparent texels should not write depth
+  if (color.a <= 0.0) {
+    discard;
+  }
+
+  return color;
+}
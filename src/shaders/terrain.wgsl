@@ -0,0 +1,144 @@
+struct Camera {
+  view_proj: mat4x4<f32>,
+  view_pos: vec3<f32>,
+  exposure: f32,
+}
+
+@group(1) @binding(0) var<uniform> camera: Camera;
+
+@group(0) @binding(0) var splat_map: texture_2d<f32>;
+@group(0) @binding(1) var splat_sampler: sampler;
+@group(0) @binding(2) var layer_0: texture_2d<f32>;
+@group(0) @binding(3) var layer_0_sampler: sampler;
+@group(0) @binding(4) var layer_1: texture_2d<f32>;
+@group(0) @binding(5) var layer_1_sampler: sampler;
+@group(0) @binding(6) var layer_2: texture_2d<f32>;
+@group(0) @binding(7) var layer_2_sampler: sampler;
+@group(0) @binding(8) var layer_3: texture_2d<f32>;
+@group(0) @binding(9) var layer_3_sampler: sampler;
+
+struct LightData {
+  view_proj: mat4x4<f32>,
+  color: vec4<f32>,
+
+  intensity: f32,
+  range: f32,
+  inner_angle: f32,
+  outer_angle: f32,
+
+  flags: u32,
+  shadow_map_index: u32,
+  cookie_index: u32,
+
+  pos: vec3<f32>,
+  direction: vec3<f32>,
+  attenuation: vec4<f32>, // constant, linear, quadratic, range window
+}
+
+@group(2) @binding(0) var<storage, read> lights: array<LightData>;
+
+struct PushConstant {
+  model: mat4x4<f32>,
+  layer_tiling: f32,
+  light_count: u32,
+}
+var<push_constant> pc: PushConstant;
+
+const AMBIENT: u32 = 1;
+const DIRECTIONAL: u32 = 2;
+const SPOT: u32 = 8;
+const VISIBLE: u32 = 16;
+
+struct Input {
+  @location(0) pos: vec3<f32>,
+  @location(1) normal: vec3<f32>,
+  @location(2) uv: vec2<f32>,
+}
+
+struct Output {
+  @builtin(position) clip: vec4<f32>,
+  @location(0) world: vec3<f32>,
+  @location(1) world_normal: vec3<f32>,
+  @location(2) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(input: Input) -> Output {
+  var out: Output;
+
+  let world_pos = pc.model * vec4<f32>(input.pos, 1.0);
+  out.world = world_pos.xyz;
+  out.world_normal = normalize((pc.model * vec4<f32>(input.normal, 0.0)).xyz);
+  out.clip = camera.view_proj * world_pos;
+  out.uv = input.uv;
+
+  return out;
+}
+
+// Layers tile across the terrain, the samplers clamp so the uv wraps manually. Gradients come
+// from the continuous uv, otherwise the wrap would cause a seam of the smallest mip level.
+fn sample_layer(texture: texture_2d<f32>, layer_sampler: sampler, uv: vec2<f32>) -> vec4<f32> {
+  return textureSampleGrad(texture, layer_sampler, fract(uv), dpdx(uv), dpdy(uv));
+}
+
+@fragment
+fn fs_main(in: Output) -> @location(0) vec4<f32> {
+  var weights = textureSample(splat_map, splat_sampler, in.uv);
+  let total = weights.r + weights.g + weights.b + weights.a;
+  if (total > 0.0) {
+    weights = weights / total;
+  } else {
+    weights = vec4<f32>(1.0, 0.0, 0.0, 0.0);
+  }
+
+  let layer_uv = in.uv * pc.layer_tiling;
+  let color = sample_layer(layer_0, layer_0_sampler, layer_uv) * weights.r
+    + sample_layer(layer_1, layer_1_sampler, layer_uv) * weights.g
+    + sample_layer(layer_2, layer_2_sampler, layer_uv) * weights.b
+    + sample_layer(layer_3, layer_3_sampler, layer_uv) * weights.a;
+
+  var light_color = vec3<f32>(0.0);
+  for (var i = 0u; i < pc.light_count; i = i + 1u) {
+    light_color += light_contribution(lights[i], in);
+  }
+
+  return vec4<f32>(color.rgb * light_color, 1.0);
+}
+
+// Diffuse only lighting, with the same units and attenuation as the standard shader
+fn light_contribution(light: LightData, in: Output) -> vec3<f32> {
+  if ((light.flags & VISIBLE) == 0) {
+    return vec3<f32>(0.0);
+  }
+
+  let intensity = light.color.rgb * light.intensity * camera.exposure;
+  if ((light.flags & AMBIENT) != 0) {
+    return intensity;
+  }
+
+  if ((light.flags & DIRECTIONAL) != 0) {
+    return intensity * max(dot(in.world_normal, normalize(-light.direction)), 0.0);
+  }
+
+  let to_light = light.pos - in.world;
+  let light_distance = length(to_light);
+  let light_dir = to_light / max(light_distance, 0.0001);
+
+  // attenuation
+  let a = light.attenuation;
+  var attenuation = 1.0 / max(a.x + a.y * light_distance + a.z * light_distance * light_distance, 0.0001);
+  if (a.w != 0.0 && light.range > 0.0) {
+    let ratio = light_distance / light.range;
+    let window = clamp(1.0 - ratio * ratio * ratio * ratio, 0.0, 1.0);
+    attenuation *= window * window;
+  }
+
+  // spot cone
+  var cone = 1.0;
+  if ((light.flags & SPOT) != 0) {
+    let cos_angle = dot(light.direction, -light_dir);
+    cone = smoothstep(cos(radians(light.outer_angle)), cos(radians(light.inner_angle)), cos_angle);
+  }
+
+  return intensity * attenuation * cone * max(dot(in.world_normal, light_dir), 0.0);
+}
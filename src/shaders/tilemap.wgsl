@@ -0,0 +1,48 @@
+struct Camera {
+  view_proj: mat4x4<f32>,
+  view_pos: vec3<f32>,
+}
+
+@group(1) @binding(0) var<uniform> camera: Camera;
+
+@group(0) @binding(0) var atlas_texture: texture_2d<f32>;
+@group(0) @binding(1) var atlas_sampler: sampler;
+
+struct PushConstant {
+  model: mat4x4<f32>,
+}
+var<push_constant> pc: PushConstant;
+
+struct Input {
+  @location(0) pos: vec3<f32>,
+  @location(1) uv: vec2<f32>,
+  @location(2) color: vec4<f32>,
+}
+
+struct Output {
+  @builtin(position) clip: vec4<f32>,
+  @location(0) uv: vec2<f32>,
+  @location(1) color: vec4<f32>,
+};
+
+@vertex
+fn vs_main(input: Input) -> Output {
+  var out: Output;
+  out.clip = camera.view_proj * pc.model * vec4<f32>(input.pos, 1.0);
+  out.uv = input.uv;
+  out.color = input.color;
+
+  return out;
+}
+
+@fragment
+fn fs_main(in: Output) -> @location(0) vec4<f32> {
+  let color = textureSample(atlas_texture, atlas_sampler, in.uv) * in.color;
+
+  // fully transparent texels should not write depth
+  if (color.a <= 0.0) {
+    discard;
+  }
+
+  return color;
+}
@@ -23,14 +23,14 @@ struct WindowSize {
 }
 var<push_constant> window_size: WindowSize;
 
-fn calc_clip_pos(input: Input) -> vec4<f32> {
+fn calc_clip_pos(input: Input, window: WindowSize) -> vec4<f32> {
   var world_pos = transforms[input.transform_index] * vec4<f32>(input.pos, 1.0);
   // out.clip = camera.view_proj * world_pos;
 
   let mil = 1000000.0;
   let screen_pos = vec4<f32>(
-    world_pos.x / window_size.width * 2.0 - 1.0,
-    (window_size.height - world_pos.y) / window_size.height * 2.0 - 1.0,
+    world_pos.x / window.width * 2.0 - 1.0,
+    (window.height - world_pos.y) / window.height * 2.0 - 1.0,
     // we have to flip the z axis for correct z ordering based on z_index
     // z_index may start at 0, so we add 1 to avoid clipping
     // then we convert to NDC with a fake hardcoded far plane at 1mil
@@ -46,7 +46,7 @@ fn vs_main(input: Input) -> Output {
   var out: Output;
   out.color = input.color;
 
-  out.clip = calc_clip_pos(input);   
+  out.clip = calc_clip_pos(input, window_size);   
 
   return out;
 }
@@ -58,16 +58,20 @@ fn fs_main(input: Output) -> @location(0) vec4<f32> {
 
 @group(2) @binding(0) var texture: texture_2d<f32>;
 @group(2) @binding(1) var texture_sampler: sampler;
-@group(2) @binding(2) var<uniform> image_data: ImageData; 
 
-struct ImageData {
-  tint: vec4<f32>,
+struct ImageConstants {
+  window_size: WindowSize,
   flags: u32,
+  // min and max uv coordinates of the drawn region
+  uv_rect: vec4<f32>,
+  tint: vec4<f32>,
 }
+var<push_constant> image_data: ImageConstants;
 
 struct ImageOutput {
   @builtin(position) clip: vec4<f32>,
   @location(0) uv: vec2<f32>,
+  @location(1) tint: vec4<f32>,
 }
 
 @vertex
@@ -95,15 +99,16 @@ fn vs_image(
   }
 
   var out: ImageOutput;
-  out.clip = calc_clip_pos(input);
-  out.uv = uv;
+  out.clip = calc_clip_pos(input, image_data.window_size);
+  out.uv = mix(image_data.uv_rect.xy, image_data.uv_rect.zw, uv);
+  out.tint = image_data.tint;
 
   return out;
 }
 
 @fragment
 fn fs_image(input: ImageOutput) -> @location(0) vec4<f32> {
-  let color = textureSample(texture, texture_sampler, input.uv) * image_data.tint;
+  let color = textureSample(texture, texture_sampler, input.uv) * input.tint;
 
   // hack since images get rendered before ui
   if color.a == 0.0 {
@@ -0,0 +1,81 @@
+struct Camera {
+  view_proj: mat4x4<f32>,
+  view_pos: vec3<f32>,
+  exposure: f32,
+}
+
+@group(1) @binding(0) var<uniform> camera: Camera;
+
+@group(0) @binding(0) var reflection: texture_2d<f32>;
+@group(0) @binding(1) var reflection_sampler: sampler;
+
+struct PushConstant {
+  model: mat4x4<f32>,
+  color: vec4<f32>,
+  size: vec2<f32>,
+  time: f32,
+  reflectivity: f32,
+  distortion: f32,
+  wave_scale: f32,
+  wave_speed: f32,
+}
+var<push_constant> pc: PushConstant;
+
+// Two triangles of the surface quad, counter clockwise when seen from above
+const CORNERS = array<vec2<f32>, 6>(
+  vec2<f32>(0.0, 0.0),
+  vec2<f32>(0.0, 1.0),
+  vec2<f32>(1.0, 0.0),
+  vec2<f32>(1.0, 0.0),
+  vec2<f32>(0.0, 1.0),
+  vec2<f32>(1.0, 1.0),
+);
+
+struct Output {
+  @builtin(position) position: vec4<f32>,
+  @location(0) world: vec3<f32>,
+  @location(1) world_normal: vec3<f32>,
+  @location(2) clip: vec4<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> Output {
+  var out: Output;
+
+  let corner = (CORNERS[index] - 0.5) * pc.size;
+  let world_pos = pc.model * vec4<f32>(corner.x, 0.0, corner.y, 1.0);
+  out.world = world_pos.xyz;
+  out.world_normal = normalize((pc.model * vec4<f32>(0.0, 1.0, 0.0, 0.0)).xyz);
+  out.clip = camera.view_proj * world_pos;
+  out.position = out.clip;
+
+  return out;
+}
+
+@fragment
+fn fs_main(in: Output) -> @location(0) vec4<f32> {
+  // the reflection of a point on the plane is at the same screen position in the target
+  let ndc = in.clip.xy / in.clip.w;
+  var uv = vec2<f32>(ndc.x * 0.5 + 0.5, 0.5 - ndc.y * 0.5);
+
+  // offset the lookup by a few overlapping waves
+  let t = pc.time * pc.wave_speed;
+  let p = in.world.xz * pc.wave_scale;
+  let waves = vec2<f32>(
+    sin(p.x + t) + sin(p.y * 1.3 + t * 0.7),
+    cos(p.y + t * 1.1) + cos(p.x * 0.8 - t * 0.9),
+  ) * 0.5;
+  uv = clamp(uv + waves * pc.distortion, vec2<f32>(0.001), vec2<f32>(0.999));
+
+  let reflected = textureSample(reflection, reflection_sampler, uv).rgb;
+
+  // schlick fresnel with the reflectance of water
+  let view_dir = normalize(camera.view_pos - in.world);
+  let cos_theta = abs(dot(view_dir, normalize(in.world_normal)));
+  let fresnel = 0.02 + 0.98 * pow(1.0 - cos_theta, 5.0);
+  let amount = mix(fresnel, 1.0, clamp(pc.reflectivity, 0.0, 1.0));
+
+  let color = mix(pc.color.rgb, reflected, amount);
+  let alpha = mix(pc.color.a, 1.0, amount);
+  return vec4<f32>(color, alpha);
+}
@@ -0,0 +1,181 @@
+//! # Sprite plugin
+//! Renders textured 2D quads in batches, instead of spawning one entity with a [`Mesh`] and a
+//! [`Material`] per sprite.
+//!
+//! ## Usage
+//!
+//! - Add the [`SpritePlugin`] to the app, it is not part of the [`DefaultPlugins`].
+//! - Spawn a [`Camera2D`] and entities with a [`Sprite`] and a [`Transform`]. Sprites lie in the
+//!   local `XY` plane, with `Y` pointing up.
+//! ```ignore
+//! commands.spawn_empty()
+//!     .insert(Sprite::new(image_handle).with_color(color::RED))
+//!     .insert(Transform::new().with_translation(Vec3::new(0.0, 0.0, 1.0)));
+//!
+//! let atlas = atlases.add(TextureAtlas::from_grid(image_handle, 8, 8));
+//! commands.spawn_empty()
+//!     .insert(Sprite::from_atlas(atlas, 12).flip_x())
+//!     .insert(Transform::new());
+//! ```
+//!
+//! ## Batching
+//!
+//! Sprites are sorted by their `Z` translation, lower values are drawn first. Consecutive sprites
+//! with the same image, including every sprite of one [`TextureAtlas`], are drawn with a single
+//! draw call, so sprites on the same `Z` should share images where possible.
+
+mod render;
+
+pub mod prelude {
+    pub use super::{Sprite, SpritePlugin, SpriteSource};
+}
+
+use glam::Vec2;
+
+use crate::{plugins::RenderPlugin, prelude::*, ui::prelude::TextureAtlas};
+
+pub use render::SpriteRenderCache;
+
+/// Plugin which adds the sprite render node and its preparation system.
+pub struct SpritePlugin;
+
+impl Plugin for SpritePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpriteRenderCache>()
+            .init_resource::<Assets<TextureAtlas>>()
+            .add_startup_system(render::register_sprite_graph)
+            .register_system(render::prepare_sprites_system, phase::PreRender);
+    }
+
+    fn dependencies(&self) -> Vec<PluginId> {
+        vec![PluginId::of::<RenderPlugin>()]
+    }
+}
+
+/// Texture drawn by a [`Sprite`]
+#[derive(Clone, Debug)]
+pub enum SpriteSource {
+    /// The whole image, or its [`Sprite::rect`]
+    Image(Handle<Image>),
+    /// Region at `index` of a [`TextureAtlas`]
+    Atlas {
+        atlas: Handle<TextureAtlas>,
+        index: usize,
+    },
+}
+
+/// Textured quad rendered by the [`SpritePlugin`]. Requires a [`Transform`].
+#[derive(Component, Clone, Debug)]
+pub struct Sprite {
+    pub source: SpriteSource,
+    /// Region of the image in pixels, with the origin in the top left corner. Only used by
+    /// [`SpriteSource::Image`], the whole image is drawn if it's `None`.
+    pub rect: Option<Rect>,
+    /// Size in local units, defaults to the size of the drawn region in pixels
+    pub custom_size: Option<Vec2>,
+    /// Point of the sprite placed at its transform, `(0, 0)` is the bottom left corner and
+    /// `(1, 1)` the top right one. Defaults to the center.
+    pub anchor: Vec2,
+    /// Color multiplied with the texture, defaults to white
+    pub color: Color,
+    pub flip_x: bool,
+    pub flip_y: bool,
+}
+
+impl Sprite {
+    /// Create a new sprite drawing the whole `image`
+    pub fn new(image: Handle<Image>) -> Self {
+        Self::from_source(SpriteSource::Image(image))
+    }
+
+    /// Create a new sprite drawing the region at `index` of `atlas`
+    pub fn from_atlas(atlas: Handle<TextureAtlas>, index: usize) -> Self {
+        Self::from_source(SpriteSource::Atlas { atlas, index })
+    }
+
+    fn from_source(source: SpriteSource) -> Self {
+        Self {
+            source,
+            rect: None,
+            custom_size: None,
+            anchor: Vec2::splat(0.5),
+            color: color::WHITE,
+            flip_x: false,
+            flip_y: false,
+        }
+    }
+
+    /// Returns self drawing only `rect` of the image, in pixels
+    #[inline]
+    #[must_use]
+    pub fn with_rect(mut self, rect: Rect) -> Self {
+        self.rect = Some(rect);
+        self
+    }
+
+    /// Returns self with a custom size in local units
+    #[inline]
+    #[must_use]
+    pub fn with_size(mut self, size: Vec2) -> Self {
+        self.custom_size = Some(size);
+        self
+    }
+
+    /// Returns self with a new `anchor`
+    #[inline]
+    #[must_use]
+    pub fn with_anchor(mut self, anchor: Vec2) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    /// Returns self with a new `color`
+    #[inline]
+    #[must_use]
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Set `flip_x` to true
+    #[inline]
+    #[must_use]
+    pub fn flip_x(mut self) -> Self {
+        self.flip_x = true;
+        self
+    }
+
+    /// Set `flip_y` to true
+    #[inline]
+    #[must_use]
+    pub fn flip_y(mut self) -> Self {
+        self.flip_y = true;
+        self
+    }
+
+    /// Returns the image, the min and max uv coordinates and the size in pixels of the region
+    /// drawn by this sprite, `None` if its image, atlas or atlas region doesn't exist
+    pub(crate) fn region(
+        &self,
+        images: &Assets<Image>,
+        atlases: &Assets<TextureAtlas>,
+    ) -> Option<(Handle<Image>, Vec2, Vec2, Vec2)> {
+        let (image, uv_min, uv_max) = match &self.source {
+            SpriteSource::Image(image) => (image.clone(), Vec2::ZERO, Vec2::ONE),
+            SpriteSource::Atlas { atlas, index } => {
+                let atlas = atlases.get(atlas)?;
+                let (min, max) = atlas.uv_rect(*index)?;
+                (atlas.image.clone(), min, max)
+            }
+        };
+
+        let size = images.get(&image)?.size;
+        let image_size = Vec2::new(size.width as f32, size.height as f32);
+        let (uv_min, uv_max) = match (&self.source, &self.rect) {
+            (SpriteSource::Image(_), Some(rect)) => (rect.min / image_size, rect.max / image_size),
+            _ => (uv_min, uv_max),
+        };
+
+        Some((image, uv_min, uv_max, (uv_max - uv_min) * image_size))
+    }
+}
@@ -0,0 +1,437 @@
+use std::{collections::HashMap, ops::Range};
+
+use glam::{Vec2, Vec3};
+use wgpu::{VertexAttribute, VertexFormat};
+
+use crate::{
+    assets::ShaderLoader,
+    core::{graph::*, standard::rendering::surface_size},
+    math::bounding_volume::{AABB, Frustum, WorldBoundingVolume},
+    prelude::*,
+    render_assets::{
+        BindGroup, Buffer, Pipeline, RenderAssetEntry, RenderAssets, StagingBelt,
+        pipeline::PipelineBuilder,
+    },
+    renderer::{
+        culling::FrustumCullingSettings,
+        newtype::{RenderCommandEncoder, RenderDevice, RenderSurfaceConfiguration},
+    },
+    ui::prelude::TextureAtlas,
+};
+
+use super::Sprite;
+
+/// Vertex of a single sprite corner, in world space
+#[repr(C)]
+#[derive(Default, Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct SpriteVertex {
+    pub position: [f32; 3],
+    pub uv: [f32; 2],
+    pub color: [f32; 4],
+}
+
+impl SpriteVertex {
+    /// Returns the vertex buffer layout for SpriteVertex
+    pub fn vertex_descriptor() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<SpriteVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                // Position
+                VertexAttribute {
+                    format: VertexFormat::Float32x3,
+                    offset: 0,
+                    shader_location: 0,
+                },
+                // UV
+                VertexAttribute {
+                    format: VertexFormat::Float32x2,
+                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                },
+                // Color
+                VertexAttribute {
+                    format: VertexFormat::Float32x4,
+                    offset: std::mem::size_of::<[f32; 5]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                },
+            ],
+        }
+    }
+}
+
+/// Consecutive sprites drawn with the same image
+struct SpriteBatch {
+    image: Handle<Image>,
+    indices: Range<u32>,
+}
+
+/// Bind group of an image used by sprites, with the texture it was created from
+struct SpriteTexture {
+    texture: RenderAssetEntry<Texture>,
+    bind_group: BindGroup,
+}
+
+/// Render cache of every [`Sprite`], holds the vertices of the visible sprites and their batches
+/// of the current frame. Updated in [`prepare_sprites_system`].
+#[derive(Default, crate::macros::Resource)]
+pub struct SpriteRenderCache {
+    buffer: Option<Buffer>,
+    /// Amount of sprites which fit into the buffer
+    capacity: usize,
+    batches: Vec<SpriteBatch>,
+    textures: HashMap<Handle<Image>, SpriteTexture>,
+    visible: usize,
+}
+
+impl SpriteRenderCache {
+    /// Returns the amount of sprites which will be drawn this frame
+    #[inline]
+    pub fn visible_sprites(&self) -> usize {
+        self.visible
+    }
+
+    /// Returns the amount of draw calls needed for the sprites this frame
+    #[inline]
+    pub fn batches(&self) -> usize {
+        self.batches.len()
+    }
+}
+
+/// Creates the sprite buffer for `capacity` sprites, the vertex buffer is writable so it can be
+/// reused every frame
+fn create_sprite_buffer(capacity: usize, device: &RenderDevice) -> Buffer {
+    let vertices = vec![SpriteVertex::default(); capacity * 4];
+    let indices = (0..capacity as u32)
+        .flat_map(|i| {
+            let i = i * 4;
+            [i, i + 1, i + 2, i + 2, i + 3, i]
+        })
+        .collect::<Vec<_>>();
+
+    Buffer::new("sprites")
+        .create_vertex_buffer(
+            &vertices,
+            vertices.len(),
+            Some(wgpu::BufferUsages::COPY_DST),
+            device,
+        )
+        .create_index_buffer(&indices, None, device)
+}
+
+/// Pre-render system to cull the sprites against the active cameras, sort them back to front and
+/// upload their vertices, batched by image. Sprites seen by any camera are kept, the batches are
+/// shared by all cameras.
+pub(crate) fn prepare_sprites_system(
+    world: &mut World,
+    device: Res<RenderDevice>,
+    mut belt: ResMut<StagingBelt>,
+    culling: Option<Res<FrustumCullingSettings>>,
+    mut cache: ResMut<SpriteRenderCache>,
+    images: Res<Assets<Image>>,
+    atlases: Res<Assets<TextureAtlas>>,
+    mut query: Query<(&Sprite, &GlobalTransform), Without<Hidden>>,
+) {
+    // extract the active camera frustums, a camera without one sees everything
+    let culling = culling.is_some_and(|settings| settings.enabled);
+    let frustums = query
+        .cast::<(&Camera, Option<&Frustum>), ()>()
+        .iter_mut()
+        .into_iter()
+        .filter(|(camera, _)| camera.active)
+        .map(|(_, frustum)| frustum.cloned())
+        .collect::<Option<Vec<_>>>();
+
+    let mut sprites = Vec::new();
+    for (sprite, global_transform) in query.iter_mut() {
+        let Some((image, mut uv_min, mut uv_max, pixel_size)) = sprite.region(&images, &atlases)
+        else {
+            continue;
+        };
+
+        let size = sprite.custom_size.unwrap_or(pixel_size);
+        let min = -sprite.anchor * size;
+        let max = min + size;
+        let corners = [
+            Vec2::new(min.x, min.y),
+            Vec2::new(max.x, min.y),
+            Vec2::new(max.x, max.y),
+            Vec2::new(min.x, max.y),
+        ]
+        .map(|corner| global_transform.transform_point(corner.extend(0.0)));
+
+        // cull the sprite
+        if culling && let Some(frustums) = &frustums {
+            let bounds = WorldBoundingVolume::AABB(AABB::new(
+                corners.into_iter().fold(Vec3::MAX, Vec3::min),
+                corners.into_iter().fold(Vec3::MIN, Vec3::max),
+            ));
+
+            if !frustums.iter().any(|frustum| frustum.intersects(&bounds)) {
+                continue;
+            }
+        }
+
+        if sprite.flip_x {
+            std::mem::swap(&mut uv_min.x, &mut uv_max.x);
+        }
+        if sprite.flip_y {
+            std::mem::swap(&mut uv_min.y, &mut uv_max.y);
+        }
+
+        // uv y axis points down, sprite y axis points up
+        let color = [
+            sprite.color.r,
+            sprite.color.g,
+            sprite.color.b,
+            sprite.color.a,
+        ];
+        let uvs = [
+            [uv_min.x, uv_max.y],
+            [uv_max.x, uv_max.y],
+            [uv_max.x, uv_min.y],
+            [uv_min.x, uv_min.y],
+        ];
+        let vertices = [0, 1, 2, 3].map(|i| SpriteVertex {
+            position: corners[i].to_array(),
+            uv: uvs[i],
+            color,
+        });
+
+        sprites.push((global_transform.translation().z, image, vertices));
+    }
+
+    // back to front, sprites on the same depth are grouped by image
+    sprites.sort_by(|(z_a, image_a, _), (z_b, image_b, _)| {
+        z_a.total_cmp(z_b)
+            .then_with(|| image_a.id().cmp(&image_b.id()))
+    });
+
+    cache.batches.clear();
+    cache.visible = sprites.len();
+    let mut vertices = Vec::with_capacity(sprites.len() * 4);
+    for (i, (_, image, sprite_vertices)) in sprites.into_iter().enumerate() {
+        let indices = i as u32 * 6..(i as u32 + 1) * 6;
+        match cache.batches.last_mut() {
+            Some(batch) if batch.image == image => batch.indices.end = indices.end,
+            _ => cache.batches.push(SpriteBatch { image, indices }),
+        }
+        vertices.extend(sprite_vertices);
+    }
+
+    if cache.visible > cache.capacity {
+        cache.capacity = cache.visible.next_power_of_two();
+        cache.buffer = Some(create_sprite_buffer(cache.capacity, &device));
+    }
+    if let Some(buffer) = &cache.buffer
+        && let Some(vertex_buffer) = &buffer.vertex
+    {
+        belt.write(vertex_buffer, 0, bytemuck::cast_slice(&vertices), &device);
+    }
+
+    // recreate the bind groups of images whose texture was recreated, e.g. after a reload
+    let cache = &mut *cache;
+    let mut used = Vec::with_capacity(cache.batches.len());
+    for batch in &cache.batches {
+        let texture = world
+            .resources
+            .get_mut::<RenderAssets<Texture>>()
+            .get_by_handle(&batch.image, world);
+
+        let stale = cache
+            .textures
+            .get(&batch.image)
+            .is_none_or(|cached| !std::ptr::eq(&*cached.texture, &*texture));
+        if stale {
+            let bind_group = BindGroup::build("sprite")
+                .add_texture(&Some(batch.image.clone()), world, color::WHITE, None, None)
+                .finish(&device);
+            cache.textures.insert(
+                batch.image.clone(),
+                SpriteTexture {
+                    texture,
+                    bind_group,
+                },
+            );
+        }
+
+        used.push(batch.image.clone());
+    }
+    cache.textures.retain(|image, _| used.contains(image));
+}
+
+/// Startup system to register the sprite graph node
+pub(crate) fn register_sprite_graph(
+    graph: &mut RenderGraph,
+    device: Res<RenderDevice>,
+    surface_config: Res<RenderSurfaceConfiguration>,
+    mut shader_loader: ResMut<ShaderLoader>,
+) {
+    let pipeline_builder =
+        create_sprite_pipeline_builder(&device, &surface_config, &mut shader_loader);
+
+    let node = GraphNodeBuilder::new("sprite")
+        .set_pipeline(pipeline_builder)
+        .set_custom_system(sprite_render_system)
+        .set_color_target(NodeColorTarget::Surface)
+        .set_depth_target(NodeDepthTarget::Node("main".to_string()))
+        .run_after("main")
+        .run_after("tilemap")
+        .run_before("ui_image")
+        .build();
+
+    graph.add(node);
+}
+
+/// Sprite graph node rendering system, draws every batch of visible sprites
+fn sprite_render_system(
+    graph_ctx: Res<RenderContext>,
+
+    world: &mut World,
+    encoder: &mut RenderCommandEncoder,
+    mut bind_groups: ResMut<RenderAssets<BindGroup>>,
+    cache: Res<SpriteRenderCache>,
+
+    mut camera_query: Query<
+        (EntityId, &Camera),
+        (With<Transform>, With<Projection>, With<Camera3D>),
+    >,
+    rendered: Res<RenderedWindow>,
+) {
+    let Some(buffer) = &cache.buffer else {
+        return;
+    };
+    let (Some(vertex_buffer), Some(index_buffer)) = (&buffer.vertex, &buffer.index) else {
+        return;
+    };
+    if cache.batches.is_empty() {
+        return;
+    }
+
+    let cameras = Camera::rendering_to(camera_query.iter_mut(), &rendered);
+    if cameras.is_empty() {
+        return;
+    }
+
+    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("sprite render pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: unsafe { &*graph_ctx.color_target.expect("sprite color target is None") },
+            depth_slice: None,
+            resolve_target: graph_ctx.resolve_target.map(|view| unsafe { &*view }),
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+            view: unsafe { &*graph_ctx.depth_target.expect("sprite depth target is None") },
+            depth_ops: Some(wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: wgpu::StoreOp::Store,
+            }),
+            stencil_ops: None,
+        }),
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    });
+
+    render_pass.set_pipeline(
+        unsafe { &*graph_ctx.node }
+            .data
+            .pipeline
+            .as_ref()
+            .expect("Pipeline should have been generated by now")
+            .render_pipeline(),
+    );
+
+    render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+    render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+
+    let target_size = surface_size(world);
+    let mut draw_calls = 0;
+    for (camera_id, camera) in cameras {
+        if !camera.set_render_pass_camera(
+            camera_id,
+            &mut render_pass,
+            1,
+            target_size,
+            &mut bind_groups,
+            world,
+        ) {
+            continue;
+        }
+
+        for batch in &cache.batches {
+            let Some(texture) = cache.textures.get(&batch.image) else {
+                continue;
+            };
+
+            render_pass.set_bind_group(0, &texture.bind_group, &[]);
+            render_pass.draw_indexed(batch.indices.clone(), 0, 0..1);
+            draw_calls += 1;
+        }
+    }
+
+    graph_ctx.record_draw_calls(draw_calls);
+}
+
+fn create_sprite_pipeline_builder(
+    device: &RenderDevice,
+    surface_config: &RenderSurfaceConfiguration,
+    shader_loader: &mut ShaderLoader,
+) -> PipelineBuilder {
+    // Texture bind group layout for texture and sampler
+    let texture_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("sprite_texture_bind_group_layout"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ],
+    });
+
+    // Camera bind group layout for uniform buffer
+    let camera_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("camera_bind_group_layout"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+    });
+
+    // Load shader modules
+    shader_loader.load("sprite", include_str!("../shaders/sprite.wgsl"), device);
+
+    // Flipped sprites and negative scales reverse the winding
+    let mut primitive_state = PipelineBuilder::default_primitive_state();
+    primitive_state.cull_mode = None;
+
+    Pipeline::build("sprite_pipeline")
+        .set_bind_group_layouts(vec![texture_layout, camera_layout])
+        .set_vertex_buffer_layouts(vec![SpriteVertex::vertex_descriptor()])
+        .set_vertex_shader("sprite", "vs_main")
+        .set_fragment_shader("sprite", "fs_main")
+        .add_color_format(surface_config.format)
+        .set_depth_format(wgpu::TextureFormat::Depth32Float)
+        .set_primitive_state(primitive_state)
+}
@@ -1,9 +1,14 @@
-use std::any::TypeId;
+use std::{
+    any::{Any, TypeId},
+    marker::PhantomData,
+    sync::Arc,
+};
 
 use crate::{
-    assets::Scene,
+    assets::{Handle, Prefab, Scene, scene::build_instance},
     ecs::{
-        entities::{Component, EntityId, tracking::EntityTracking},
+        entities::{Bundle, Component, EntityId, tracking::EntityTracking},
+        hierarchy::OrphanPolicy,
         resources::Resource,
         world::World,
     },
@@ -17,6 +22,7 @@ enum Command {
     RemoveResource(TypeId),
     SpawnEntity(EntityId),
     DespawnEntity(EntityId),
+    DespawnEntityWith(EntityId, OrphanPolicy),
     DespawnEntityRecursive(EntityId),
     InsertComponent(Box<dyn FnOnce(&mut World) + Send + Sync + 'static>),
     RemoveComponent(EntityId, TypeId),
@@ -31,6 +37,7 @@ impl std::fmt::Debug for Command {
             Self::RemoveResource(..) => write!(f, "Command::RemoveResource"),
             Self::SpawnEntity(..) => write!(f, "Command::SpawnEntity"),
             Self::DespawnEntity(..) => write!(f, "Command::DespawnEntity"),
+            Self::DespawnEntityWith(..) => write!(f, "Command::DespawnEntityWith"),
             Self::DespawnEntityRecursive(..) => write!(f, "Command::DespawnEntityRecursive"),
             Self::InsertComponent(..) => write!(f, "Command::InsertComponent"),
             Self::RemoveComponent(..) => write!(f, "Command::RemoveComponent"),
@@ -62,6 +69,13 @@ pub struct EntityCommands<'a, 't, 'q> {
     commands: &'a mut Commands<'t, 'q>,
 }
 
+/// Deferred access to component `C` of an entity, returned by [`EntityCommands::entry`].
+pub struct EntityEntryCommands<'a, 't, 'q, C: Component> {
+    /// Commands of the entity.
+    entity_commands: EntityCommands<'a, 't, 'q>,
+    marker: PhantomData<C>,
+}
+
 /// Commands for creating child entities under a parent.
 pub struct ParentCommands<'a, 't, 'q> {
     /// Id of the parent entity.
@@ -88,6 +102,17 @@ impl<'a, 't, 'q> ParentCommands<'a, 't, 'q> {
 
         EntityCommands::new(self.commands, child_id)
     }
+
+    /// Spawns a new child entity with every component of `bundle` under the parent and returns
+    /// its [`EntityCommands`].
+    pub fn spawn<B: Bundle>(&mut self, bundle: B) -> EntityCommands<'_, 't, 'q> {
+        let child_id = { self.commands.spawn(bundle).entity_id };
+
+        self.commands
+            .queue(Command::AddChild(self.parent_id, child_id));
+
+        EntityCommands::new(self.commands, child_id)
+    }
 }
 
 impl<'a, 't, 'q> EntityCommands<'a, 't, 'q> {
@@ -106,11 +131,18 @@ impl<'a, 't, 'q> EntityCommands<'a, 't, 'q> {
         self.entity_id
     }
 
-    /// Despawn the entity and break its parent-child relationship.
+    /// Despawn the entity and break its parent-child relationship. Its children are handled
+    /// according to the [`OrphanPolicy`] in [`HierarchySettings`](crate::ecs::hierarchy::HierarchySettings).
     pub fn despawn(self) {
         self.commands.queue(Command::DespawnEntity(self.entity_id));
     }
 
+    /// Despawn the entity, handling its children according to `policy`.
+    pub fn despawn_with(self, policy: OrphanPolicy) {
+        self.commands
+            .queue(Command::DespawnEntityWith(self.entity_id, policy));
+    }
+
     /// Despawns the entity and all its children recursively.
     pub fn despawn_recursive(self) {
         self.commands
@@ -119,11 +151,25 @@ impl<'a, 't, 'q> EntityCommands<'a, 't, 'q> {
 
     /// Inserts new component to the entity.
     pub fn insert<C: Component>(mut self, component: C) -> Self {
-        self.handle_insert_types(&component, true);
+        check_insert_type::<C>();
         self.insert_internal(component, true);
         self
     }
 
+    /// Inserts every component of `bundle` to the entity, moving it to its new archetype only
+    /// once.
+    pub fn insert_bundle<B: Bundle>(self, bundle: B) -> Self {
+        let entity_id = self.entity_id;
+
+        let insert_closure = move |world: &mut World| {
+            world.insert_bundle(entity_id, bundle);
+        };
+
+        self.commands
+            .queue(Command::InsertComponent(Box::new(insert_closure)));
+        self
+    }
+
     /// Inserts new component to the entity if the condition returns true.
     pub fn insert_if<C: Component, F: FnOnce() -> bool>(self, component: C, condition: F) -> Self {
         if condition() {
@@ -135,7 +181,7 @@ impl<'a, 't, 'q> EntityCommands<'a, 't, 'q> {
 
     /// Inserts new component to the entity if it doesn't exist.
     pub fn insert_if_new<C: Component>(mut self, component: C) -> Self {
-        self.handle_insert_types(&component, false);
+        check_insert_type::<C>();
         self.insert_internal(component, false);
         self
     }
@@ -153,6 +199,35 @@ impl<'a, 't, 'q> EntityCommands<'a, 't, 'q> {
         }
     }
 
+    /// Returns an [`EntityEntryCommands`] to insert or modify component `C` depending on whether
+    /// the entity has it when the commands are applied.
+    ///
+    /// ```ignore
+    /// commands
+    ///     .entity(id)
+    ///     .entry::<Health>()
+    ///     .and_modify(|health| health.0 += 10)
+    ///     .or_insert(Health(100));
+    /// ```
+    #[inline]
+    pub fn entry<C: Component>(self) -> EntityEntryCommands<'a, 't, 'q, C> {
+        EntityEntryCommands {
+            entity_commands: self,
+            marker: PhantomData,
+        }
+    }
+
+    /// Inserts the component returned by `f` if the entity doesn't have it, `f` is only called
+    /// if the component is missing when the commands are applied. Shorthand for
+    /// `entry::<C>().or_insert_with(f)`.
+    #[inline]
+    pub fn get_or_insert_with<C: Component, F: FnOnce() -> C + Send + Sync + 'static>(
+        self,
+        f: F,
+    ) -> Self {
+        self.entry::<C>().or_insert_with(f)
+    }
+
     /// Inserts a scene to the entity.
     pub fn insert_scene<S: Scene>(self, scene: S) -> Self {
         self.commands.queue(Command::InsertComponent(Box::new(
@@ -163,6 +238,28 @@ impl<'a, 't, 'q> EntityCommands<'a, 't, 'q> {
         self
     }
 
+    /// Spawns an instance of `prefab` into the entity, see [`PrefabInstance`].
+    pub fn insert_prefab(self, prefab: Handle<Prefab>) -> Self {
+        self.commands.queue(Command::InsertComponent(Box::new(
+            move |world: &mut World| {
+                build_instance(world, self.entity_id, prefab, None);
+            },
+        )));
+        self
+    }
+
+    /// Spawns an instance of `prefab` into the entity, components in `overrides` take precedence
+    /// over the prefab's. The overrides are kept when the instance is rebuilt.
+    pub fn insert_prefab_with<S: Scene>(self, prefab: Handle<Prefab>, overrides: S) -> Self {
+        let overrides: Arc<dyn Scene> = Arc::new(overrides);
+        self.commands.queue(Command::InsertComponent(Box::new(
+            move |world: &mut World| {
+                build_instance(world, self.entity_id, prefab, Some(overrides));
+            },
+        )));
+        self
+    }
+
     /// Removes a component from the entity.
     pub fn remove<C: Component>(self) -> Self {
         self.commands
@@ -208,31 +305,92 @@ impl<'a, 't, 'q> EntityCommands<'a, 't, 'q> {
         let entity_id = self.entity_id;
 
         let insert_closure = move |world: &mut World| {
-            world.insert_component(entity_id, component, replace);
+            insert_into_world(world, entity_id, component, replace);
         };
 
         self.commands
             .queue(Command::InsertComponent(Box::new(insert_closure)))
     }
+}
 
-    /// Checks and handles special cases of the component being inserted
-    fn handle_insert_types<C: Component>(&mut self, component: &C, replace: bool) {
-        let type_id = TypeId::of::<C>();
+impl<'a, 't, 'q, C: Component> EntityEntryCommands<'a, 't, 'q, C> {
+    /// Inserts `component` if the entity doesn't have `C`.
+    #[inline]
+    pub fn or_insert(self, component: C) -> EntityCommands<'a, 't, 'q> {
+        self.entity_commands.insert_if_new(component)
+    }
 
-        if type_id == TypeId::of::<EntityId>() {
-            panic!("Cannot insert EntityId component");
-        } else if type_id == TypeId::of::<GlobalTransform>() {
-            panic!("Cannot insert GlobalTransform component");
-        }
+    /// Inserts the component returned by `f` if the entity doesn't have `C`, `f` is only called
+    /// if the component is missing when the commands are applied.
+    pub fn or_insert_with<F: FnOnce() -> C + Send + Sync + 'static>(
+        self,
+        f: F,
+    ) -> EntityCommands<'a, 't, 'q> {
+        check_insert_type::<C>();
+        let entity_id = self.entity_commands.entity_id;
 
-        if type_id == TypeId::of::<Transform>() {
-            let transform = component as *const C as *const Transform;
-            self.insert_internal(
-                GlobalTransform::from_transform(unsafe { &*transform }),
-                replace,
-            );
-        }
+        let insert_closure = move |world: &mut World| {
+            if !world.entities.has_component(entity_id, TypeId::of::<C>()) {
+                insert_into_world(world, entity_id, f(), false);
+            }
+        };
+
+        self.entity_commands
+            .commands
+            .queue(Command::InsertComponent(Box::new(insert_closure)));
+        self.entity_commands
+    }
+
+    /// Inserts `C::default()` if the entity doesn't have `C`.
+    #[inline]
+    pub fn or_default(self) -> EntityCommands<'a, 't, 'q>
+    where
+        C: Default,
+    {
+        self.or_insert_with(C::default)
     }
+
+    /// Modifies the component with `f` if the entity has it, marking it as changed.
+    pub fn and_modify<F: FnOnce(&mut C) + Send + Sync + 'static>(self, f: F) -> Self {
+        let entity_id = self.entity_commands.entity_id;
+
+        let modify_closure = move |world: &mut World| {
+            if let Some(component) = world.entities.get_component_mut::<C>(entity_id) {
+                f(component);
+            }
+        };
+
+        self.entity_commands
+            .commands
+            .queue(Command::InsertComponent(Box::new(modify_closure)));
+        self
+    }
+}
+
+/// Panics if `C` can't be inserted with commands
+fn check_insert_type<C: Component>() {
+    let type_id = TypeId::of::<C>();
+
+    if type_id == TypeId::of::<EntityId>() {
+        panic!("Cannot insert EntityId component");
+    } else if type_id == TypeId::of::<GlobalTransform>() {
+        panic!("Cannot insert GlobalTransform component");
+    }
+}
+
+/// Inserts `component` into the entity, handling special cases of the component type
+fn insert_into_world<C: Component>(
+    world: &mut World,
+    entity_id: EntityId,
+    component: C,
+    replace: bool,
+) {
+    if let Some(transform) = (&component as &dyn Any).downcast_ref::<Transform>() {
+        let global_transform = GlobalTransform::from_transform(transform);
+        world.insert_component(entity_id, global_transform, replace);
+    }
+
+    world.insert_component(entity_id, component, replace);
 }
 
 impl<'t, 'q> Commands<'t, 'q> {
@@ -266,6 +424,20 @@ impl<'t, 'q> Commands<'t, 'q> {
         EntityCommands::new(self, new_id)
     }
 
+    /// Spawns a new entity with every component of `bundle` and returns its [`EntityCommands`].
+    /// The entity is created directly in its final archetype, instead of moving it once for
+    /// every inserted component.
+    pub fn spawn<'a, B: Bundle>(&'a mut self, bundle: B) -> EntityCommands<'a, 't, 'q> {
+        let new_id = self.tracking.new_id();
+
+        let spawn_closure = move |world: &mut World| {
+            world.spawn_bundle_at(new_id, bundle);
+        };
+        self.queue(Command::InsertComponent(Box::new(spawn_closure)));
+
+        EntityCommands::new(self, new_id)
+    }
+
     /// Selects an entity and returns its [`EntityCommands`] to modify it.
     #[inline]
     pub fn entity<'a>(&'a mut self, entity_id: EntityId) -> EntityCommands<'a, 't, 'q> {
@@ -324,7 +496,10 @@ impl CommandQueue {
                     world.entities.spawn_entity(entity_id, Vec::new());
                 }
                 Command::DespawnEntity(entity_id) => {
-                    world.entities.despawn_entity(entity_id);
+                    world.despawn(entity_id);
+                }
+                Command::DespawnEntityWith(entity_id, policy) => {
+                    world.entities.despawn_entity_with(entity_id, policy);
                 }
                 Command::DespawnEntityRecursive(entity_id) => {
                     world.entities.despawn_entity_recursive(entity_id);
@@ -8,6 +8,11 @@ pub(crate) trait ConflictChecker {
 
 impl ConflictChecker for ParamInfo {
     fn is_conflicting_with(&self, other: &Self) -> bool {
+        // Exclusive params can access anything
+        if self.is_exclusive() || other.is_exclusive() {
+            return true;
+        }
+
         if self.type_info().type_id() != other.type_info().type_id() {
             return false;
         }
@@ -48,10 +53,13 @@ impl ConflictChecker for System {
 
         // self.Main vs Condition
         for other_condition in &other.conditions {
-            other_condition
+            if other_condition
                 .exec
                 .params_info
-                .is_conflicting_with(&self.exec.params_info);
+                .is_conflicting_with(&self.exec.params_info)
+            {
+                return true;
+            }
         }
 
         for condition in &self.conditions {
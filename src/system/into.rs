@@ -1,6 +1,12 @@
-use std::{any::TypeId, collections::HashMap};
+use std::{
+    any::{TypeId, type_name},
+    collections::HashMap,
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+};
 
-use super::{ParamInfo, System, SystemCondition, SystemParam, TypeInfo};
+use super::{ParamInfo, System, SystemCondition, SystemContext, SystemExec, SystemParam, TypeInfo};
+use crate::prelude::{Tick, World};
 
 /// Convert a closure or function into a [`System`]
 pub trait IntoSystem<P: SystemParam> {
@@ -10,6 +16,75 @@ pub trait IntoSystem<P: SystemParam> {
     /// Add new run condition to the system
     fn run_if<CP: SystemParam>(self, condition: impl IntoSystemCondition<CP>)
     -> impl IntoSystem<P>;
+
+    /// Add a label to the system, which other systems can be ordered against. The system is
+    /// always labeled with its function too.
+    fn label(self, label: &'static str) -> impl IntoSystem<P>
+    where
+        Self: Sized,
+    {
+        let mut system = self.build();
+        system.labels.push(label);
+        system
+    }
+
+    /// Run the system before `other`, a label or a system function. Only applies if both are in
+    /// the same layer of the same phase.
+    ///
+    /// ```ignore
+    /// app.register_system(apply_input.before(move_player), phase::Update)
+    ///     .register_system(move_player, phase::Update)
+    ///     .register_system(follow_player.after(move_player), phase::Update);
+    /// ```
+    fn before<M>(self, other: impl IntoSystemLabel<M>) -> impl IntoSystem<P>
+    where
+        Self: Sized,
+    {
+        let mut system = self.build();
+        system.before.push(other.system_label());
+        system
+    }
+
+    /// Run the system after `other`, a label or a system function. Only applies if both are in
+    /// the same layer of the same phase.
+    fn after<M>(self, other: impl IntoSystemLabel<M>) -> impl IntoSystem<P>
+    where
+        Self: Sized,
+    {
+        let mut system = self.build();
+        system.after.push(other.system_label());
+        system
+    }
+}
+
+/// Identifies a [`System`] in ordering constraints, implemented for labels added with
+/// [`IntoSystem::label`] and for system functions
+pub trait IntoSystemLabel<M> {
+    /// Returns the label of the system
+    fn system_label(self) -> &'static str;
+}
+
+/// Marker for [`IntoSystemLabel`] of a label
+#[doc(hidden)]
+pub struct StrLabelMarker;
+
+/// Marker for [`IntoSystemLabel`] of a system function
+#[doc(hidden)]
+pub struct FunctionLabelMarker<P>(PhantomData<P>);
+
+impl IntoSystemLabel<StrLabelMarker> for &'static str {
+    #[inline]
+    fn system_label(self) -> &'static str {
+        self
+    }
+}
+
+impl<P: SystemParam, F: IntoSystem<P>> IntoSystemLabel<FunctionLabelMarker<P>> for F {
+    /// Same as the label every system gets from its function
+    #[inline]
+    fn system_label(self) -> &'static str {
+        type_name::<F>()
+    }
 }
 
 impl<P: SystemParam> IntoSystem<P> for System {
@@ -31,6 +106,88 @@ impl<P: SystemParam> IntoSystem<P> for System {
 pub trait IntoSystemCondition<P: SystemParam> {
     /// Convert the function into a [`SystemCondition`]
     fn build(self) -> SystemCondition;
+
+    /// Returns a condition which evaluates to true if both conditions do. `other` doesn't run if
+    /// this condition is false.
+    ///
+    /// ```ignore
+    /// system.run_if(in_state(GameState::Playing).and(on_timer(Duration::from_secs(1))))
+    /// ```
+    fn and<CP: SystemParam>(
+        self,
+        other: impl IntoSystemCondition<CP>,
+    ) -> impl IntoSystemCondition<P>
+    where
+        Self: Sized,
+    {
+        combine_conditions::<And>(vec![self.build(), other.build()], |conditions, world| {
+            conditions.iter_mut().all(|condition| condition.run(world))
+        })
+    }
+
+    /// Returns a condition which evaluates to true if either condition does. `other` doesn't run
+    /// if this condition is true.
+    fn or<CP: SystemParam>(self, other: impl IntoSystemCondition<CP>) -> impl IntoSystemCondition<P>
+    where
+        Self: Sized,
+    {
+        combine_conditions::<Or>(vec![self.build(), other.build()], |conditions, world| {
+            conditions.iter_mut().any(|condition| condition.run(world))
+        })
+    }
+
+    /// Returns a condition which negates this condition
+    fn not(self) -> impl IntoSystemCondition<P>
+    where
+        Self: Sized,
+    {
+        combine_conditions::<Not>(vec![self.build()], |conditions, world| {
+            !conditions[0].run(world)
+        })
+    }
+}
+
+/// Markers naming combined conditions
+struct And;
+struct Or;
+struct Not;
+
+/// Combine `conditions` into one, which evaluates to `eval` of them. It accesses the parameters
+/// of all conditions, so it doesn't prevent systems from running in parallel.
+fn combine_conditions<Name: 'static>(
+    conditions: Vec<SystemCondition>,
+    eval: fn(&mut [SystemCondition], &mut World) -> bool,
+) -> SystemCondition {
+    let params_info = conditions
+        .iter()
+        .flat_map(|condition| condition.exec.params_info.iter().copied())
+        .collect();
+    let exec_info = TypeInfo::new(type_name::<Name>(), TypeId::of::<Name>());
+
+    // the conditions are shared by the exec, init and apply functions, which never run at the
+    // same time
+    let conditions = Arc::new(Mutex::new(conditions));
+    let init_conditions = conditions.clone();
+    let apply_conditions = conditions.clone();
+
+    let exec = Box::new(move |world: &mut World, _: SystemContext| {
+        eval(&mut conditions.lock().unwrap(), world)
+    });
+    let init = Box::new(move |world: &mut World, _: SystemContext| {
+        for condition in init_conditions.lock().unwrap().iter_mut() {
+            condition.init(world);
+        }
+    });
+    let apply = Box::new(move |world: &mut World, _: SystemContext| {
+        for condition in apply_conditions.lock().unwrap().iter_mut() {
+            condition.apply(world);
+        }
+    });
+
+    SystemCondition {
+        last_run: Tick::default(),
+        exec: SystemExec::new(params_info, exec_info, exec, init, apply),
+    }
 }
 
 impl<P: SystemParam> IntoSystemCondition<P> for SystemCondition {
@@ -108,11 +265,7 @@ pub(super) mod macros {
 
                         let exec = into_systems_impl_body!(self, ($($param),*) );
 
-                        System {
-                            last_run: Tick::default(),
-                            exec,
-                            conditions: Vec::new(),
-                        }
+                        System::new(exec)
                     }
 
                     #[inline]
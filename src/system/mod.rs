@@ -8,8 +8,8 @@ mod tasks;
 
 pub use commands::Commands;
 use conflict::ConflictChecker;
-pub use into::{IntoSystem, IntoSystemCondition};
-pub use params::{ParamInfo, SystemParam, TypeInfo};
+pub use into::{IntoSystem, IntoSystemCondition, IntoSystemLabel};
+pub use params::{IntoParamInfo, Local, ParamInfo, SystemParam, TypeInfo};
 pub use scheduler::{
     label::{layer, phase},
     *,
@@ -104,9 +104,40 @@ pub struct System {
     pub(super) exec: SystemExec,
     /// Run conditions
     pub(super) conditions: Vec<SystemCondition>,
+    /// Labels other systems are ordered against, the first one is the function type name
+    pub(super) labels: Vec<&'static str>,
+    /// This system will run before the systems with these labels
+    pub(super) before: Vec<&'static str>,
+    /// This system will run after the systems with these labels
+    pub(super) after: Vec<&'static str>,
 }
 
 impl System {
+    /// Create a new system labeled with its function
+    #[inline]
+    fn new(exec: SystemExec) -> Self {
+        Self {
+            last_run: Tick::default(),
+            labels: vec![exec.exec_info.type_name()],
+            exec,
+            conditions: Vec::new(),
+            before: Vec::new(),
+            after: Vec::new(),
+        }
+    }
+
+    /// Returns true if the system can't run in parallel with `other`
+    #[inline]
+    pub(crate) fn conflicts_with(&self, other: &System) -> bool {
+        self.is_conflicting_with(other)
+    }
+
+    /// Returns true if the system has to run before `other`
+    pub(super) fn runs_before(&self, other: &System) -> bool {
+        self.before.iter().any(|label| other.labels.contains(label))
+            || other.after.iter().any(|label| self.labels.contains(label))
+    }
+
     /// Same as [`IntoSystem::run_if`] but internal to avoid the need for generic parameters
     #[inline]
     fn internal_run_if(mut self, condition: SystemCondition) -> System {
@@ -114,6 +145,19 @@ impl System {
         self
     }
 
+    /// Returns true if the system or any of its conditions has an
+    /// [exclusive](ParamInfo::is_exclusive) parameter, like `&mut World`
+    pub fn is_exclusive(&self) -> bool {
+        let is_exclusive =
+            |params_info: &[ParamInfo]| params_info.iter().any(ParamInfo::is_exclusive);
+
+        is_exclusive(&self.exec.params_info)
+            || self
+                .conditions
+                .iter()
+                .any(|condition| is_exclusive(&condition.exec.params_info))
+    }
+
     /// Execute system if all conditions are met
     pub fn run(&mut self, world: &mut World) {
         // TODO: handle world tick overflow
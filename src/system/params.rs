@@ -7,7 +7,10 @@ use crate::{
     renderer::newtype::{RenderCommandEncoder, RenderDevice},
     system::{Commands, SystemContext, commands::CommandQueue},
 };
-use std::any::{TypeId, type_name};
+use std::{
+    any::{TypeId, type_name},
+    ops::{Deref, DerefMut},
+};
 
 /// Type information for system functions and parameters
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -40,6 +43,7 @@ impl TypeInfo {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ParamInfo {
     is_mutable: bool,
+    is_exclusive: bool,
     type_info: TypeInfo,
 }
 
@@ -49,6 +53,18 @@ impl ParamInfo {
     pub fn new(is_mutable: bool, type_info: TypeInfo) -> Self {
         Self {
             is_mutable,
+            is_exclusive: false,
+            type_info,
+        }
+    }
+
+    /// Create new parameter information for a parameter with access to the whole world, like
+    /// `&mut World`. Systems with exclusive parameters run alone on the main thread.
+    #[inline]
+    pub fn new_exclusive(type_info: TypeInfo) -> Self {
+        Self {
+            is_mutable: true,
+            is_exclusive: true,
             type_info,
         }
     }
@@ -59,6 +75,12 @@ impl ParamInfo {
         self.is_mutable
     }
 
+    /// Returns `true` if the parameter has access to the whole world
+    #[inline]
+    pub fn is_exclusive(&self) -> bool {
+        self.is_exclusive
+    }
+
     /// Returns the parameter's type information
     #[inline]
     pub fn type_info(&self) -> TypeInfo {
@@ -90,9 +112,23 @@ macro_rules! impl_into_param_info {
     };
 }
 
+/// Macro to implement [`IntoParamInfo`] for params with access to the whole world
+macro_rules! impl_exclusive_param_info {
+    ($type:ident, $for:ty) => {
+        impl IntoParamInfo for $for {
+            fn params_info() -> Vec<ParamInfo> {
+                vec![ParamInfo::new_exclusive(TypeInfo::new(
+                    type_name::<$type>(),
+                    TypeId::of::<$type>(),
+                ))]
+            }
+        }
+    };
+}
+
 // Special app params
-impl_into_param_info!(App, &mut App, true);
-impl_into_param_info!(World, &mut World, true);
+impl_exclusive_param_info!(App, &mut App);
+impl_exclusive_param_info!(World, &mut World);
 impl_into_param_info!(RenderGraph, &mut RenderGraph, true);
 
 // Special params
@@ -186,10 +222,6 @@ impl_stateless_system_param!(&mut RenderGraph, world, unsafe {
 });
 
 // Special params
-impl_stateless_system_param!(E: Event, EventReader<E>, world, _c, {
-    let events = world.resources.get::<Events<E>>();
-    EventReader::new(events)
-});
 impl_stateless_system_param!(E: Event, EventWriter<E>, world, _c, {
     let events = world.resources.get_mut::<Events<E>>();
     EventWriter::new(events)
@@ -254,6 +286,22 @@ impl SystemParam for &mut RenderCommandEncoder {
     }
 }
 
+impl<E: Event> SystemParam for EventReader<E> {
+    /// Id of the next unread event
+    type State = usize;
+
+    #[inline]
+    fn extract(world: &mut World, state: &mut Self::State, _context: &SystemContext) -> Self {
+        let events = world.resources.get::<Events<E>>();
+        EventReader::new(events, state)
+    }
+
+    #[inline]
+    fn init_state() -> Self::State {
+        0
+    }
+}
+
 /// State for [`Commands`] system parameter, implemented as a wrapper with Send + Sync
 #[derive(Default)]
 pub struct CommandsState(CommandQueue);
@@ -282,6 +330,54 @@ impl SystemParam for Commands<'_, '_> {
     }
 }
 
+/// State owned by a single system or [condition](crate::system::IntoSystemCondition), created
+/// with `T::default()` and kept between its runs.
+///
+/// ```ignore
+/// fn count_frames_system(mut frames: Local<u32>) {
+///     *frames += 1;
+/// }
+/// ```
+pub struct Local<'s, T: Default + Send + Sync + 'static>(&'s mut T);
+
+impl<T: Default + Send + Sync + 'static> Deref for Local<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
+
+impl<T: Default + Send + Sync + 'static> DerefMut for Local<'_, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0
+    }
+}
+
+impl<T: Default + Send + Sync + 'static> IntoParamInfo for Local<'_, T> {
+    /// Doesn't access the world
+    fn params_info() -> Vec<ParamInfo> {
+        Vec::new()
+    }
+}
+
+impl<T: Default + Send + Sync + 'static> SystemParam for Local<'_, T> {
+    type State = T;
+
+    #[inline]
+    fn extract(_world: &mut World, state: &mut Self::State, _context: &SystemContext) -> Self {
+        // Reborrow to satisfy lifetime requirements, the state outlives the system run
+        Local(unsafe { &mut *(state as *mut Self::State) })
+    }
+
+    #[inline]
+    fn init_state() -> Self::State {
+        T::default()
+    }
+}
+
 pub struct QueryCache; // Placeholder for query state
 
 impl<T, F> SystemParam for Query<T, F>
@@ -145,6 +145,37 @@ impl SchedulerChanges {
         self
     }
 
+    /// Add a sync point after the layer at `location`. Changes deferred by the systems which ran
+    /// so far, like [commands](crate::system::Commands), are applied there, so later layers of the
+    /// same phase can see them.
+    pub fn sync_point<L: IntoSchedulerLocation>(&mut self, location: L) -> &mut Self {
+        self.changes
+            .push(Box::new(move |scheduler: &mut Scheduler| {
+                let phase = scheduler
+                    .get_phase_mut(location.phase_label())
+                    .expect("Phase not found");
+                let layer = phase
+                    .get_layer_mut(location.layer_label())
+                    .expect("Layer not found");
+                layer.sync_point = true;
+            }));
+        self
+    }
+
+    /// Set whether a phase has a [sync point](Self::sync_point) after every layer
+    pub fn sync_layers<P: PhaseLabel>(&mut self, phase: P, enabled: bool) -> &mut Self {
+        self.changes
+            .push(Box::new(move |scheduler: &mut Scheduler| {
+                let phase_label = phase.phase_label();
+
+                let phase = scheduler
+                    .get_phase_mut(phase_label)
+                    .expect("Phase not found");
+                phase.sync_layers = enabled;
+            }));
+        self
+    }
+
     /// Move a layer to a different phase
     pub fn move_layer<L: IntoSchedulerLocation, PT: PhaseLabel>(
         &mut self,
@@ -8,7 +8,7 @@ pub use changes::SchedulerChanges;
 pub use label::{LayerLabel, PhaseLabel};
 pub use location::{IntoSchedulerLocation, SchedulerLocation};
 pub use phase::{Phase, PhaseExecutionPolicy, PhaseExecutionType};
-pub(super) use threads::ThreadPool;
+pub(crate) use threads::ThreadPool;
 
 use crate::{
     prelude::{FixedTime, World},
@@ -21,6 +21,9 @@ use crate::{
 /// parameter access patterns. These systems can be `executed concurrently` for improved performance.
 ///
 /// Batches are created automatically when inserting systems into [layers](Layer).
+///
+/// [Exclusive](System::is_exclusive) systems always get a batch of their own, which runs on the
+/// main thread with queued commands flushed before and after it.
 pub struct Batch {
     /// Systems in this batch
     systems: Vec<System>,
@@ -41,6 +44,12 @@ impl Batch {
         self.systems.push(system);
     }
 
+    /// Returns true if this batch holds an exclusive system
+    #[inline]
+    fn is_exclusive(&self) -> bool {
+        self.systems.first().is_some_and(System::is_exclusive)
+    }
+
     /// Check if this batch can accept the given system without conflicts
     #[inline]
     fn can_accept(&self, system: &System) -> bool {
@@ -61,11 +70,18 @@ impl Batch {
 /// phase.
 ///
 /// Layers always run sequentially, but systems within them can be [parallelized](Batch).
+///
+/// Commands queued by systems are applied at the end of the phase, unless the layer has a sync
+/// point, see [`SchedulerChanges::sync_point`].
 pub struct Layer {
     /// Layer label
     label: &'static str,
     /// Batches in this layer
     batches: Vec<Batch>,
+    /// Systems added since the batches were built, batched before the layer runs
+    pending: Vec<System>,
+    /// Apply deferred changes after this layer runs
+    sync_point: bool,
 
     /// This layer will run before these layers
     before: Vec<&'static str>,
@@ -80,15 +96,78 @@ impl Layer {
         Self {
             label,
             batches: Vec::new(),
+            pending: Vec::new(),
+            sync_point: false,
             before: Vec::new(),
             after: Vec::new(),
         }
     }
 
-    /// Add a system to this layer
+    /// Add a system to this layer. It's batched with [`Self::build_batches`] before the layer
+    /// runs, so adding many systems sorts them only once.
     #[inline]
     fn add_system(&mut self, system: System) {
-        for batch in &mut self.batches {
+        self.pending.push(system);
+    }
+
+    /// Rebuild the batches with the added systems, so every system runs after the systems it has
+    /// to run after, see [`IntoSystem::before`](crate::system::IntoSystem::before)
+    fn build_batches(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+
+        let mut systems = self
+            .batches
+            .drain(..)
+            .flat_map(|batch| batch.systems)
+            .collect::<Vec<_>>();
+        systems.append(&mut self.pending);
+
+        for system in self.sort_systems(systems) {
+            self.push_system(system);
+        }
+    }
+
+    /// Sort systems so they come after the systems they have to run after, otherwise they keep
+    /// their order
+    fn sort_systems(&self, mut systems: Vec<System>) -> Vec<System> {
+        let mut sorted = Vec::with_capacity(systems.len());
+
+        while !systems.is_empty() {
+            let Some(index) = systems
+                .iter()
+                .position(|system| !systems.iter().any(|other| other.runs_before(system)))
+            else {
+                panic!(
+                    "Conflicting system ordering in layer {:?}, systems {:?} have a cycle in their before and after constraints",
+                    self.label,
+                    systems
+                        .iter()
+                        .map(|system| system.exec.exec_info.type_name())
+                        .collect::<Vec<_>>()
+                );
+            };
+
+            sorted.push(systems.remove(index));
+        }
+
+        sorted
+    }
+
+    /// Put a system into the first batch it can run in without conflicts. Systems are never moved
+    /// before an exclusive system or a system they have to run after, so they keep their order
+    /// relative to them.
+    fn push_system(&mut self, system: System) {
+        let first_available = self
+            .batches
+            .iter()
+            .rposition(|batch| {
+                batch.is_exclusive() || batch.systems.iter().any(|other| other.runs_before(&system))
+            })
+            .map_or(0, |index| index + 1);
+
+        for batch in &mut self.batches[first_available..] {
             if batch.can_accept(&system) {
                 batch.add_system(system);
                 return;
@@ -123,9 +202,14 @@ impl Default for Scheduler {
             .map(Phase::new)
             .collect();
 
-        let size = std::thread::available_parallelism()
-            .map(|n| n.get())
-            .unwrap_or(1);
+        // threads can't be spawned on the web, systems run sequentially there
+        let size = if cfg!(target_arch = "wasm32") {
+            0
+        } else {
+            std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1)
+        };
 
         let mut scheduler = Self {
             phases,
@@ -166,7 +250,11 @@ impl Scheduler {
         for phase in &self.phases {
             println!("  Phase: {:?}", phase.label);
             for layer in &phase.layers {
-                println!("    Layer: {:?}", layer.label);
+                println!(
+                    "    Layer: {:?}, {} pending systems",
+                    layer.label,
+                    layer.pending.len()
+                );
                 for (batch_index, batch) in layer.batches.iter().enumerate() {
                     println!(
                         "      Batch {}: {} systems",
@@ -268,6 +356,12 @@ impl Scheduler {
 }
 
 impl Scheduler {
+    /// Run all systems on the calling thread, in their scheduled order. Parallel phases behave
+    /// like sequential ones.
+    pub(crate) fn set_single_threaded(&mut self) {
+        self.thread_pool = ThreadPool::new(0);
+    }
+
     /// Execute the full scheduler pipeline
     #[inline]
     pub fn execute_pipeline(&mut self, world: &mut World) {
@@ -1,7 +1,7 @@
 use std::fmt::Debug;
 
 use crate::{
-    prelude::{FixedTime, World},
+    prelude::{FixedAlpha, FixedTime, Time, World},
     system::{Layer, SchedulerChanges, System, SystemCondition, ThreadPool, layer},
 };
 
@@ -23,7 +23,9 @@ pub enum PhaseExecutionPolicy {
     Normal,
     /// Run systems for a finite number of iterations, then they are removed from the scheduler
     Finite(usize),
-    /// Run systems at a fixed timestap
+    /// Run systems at a fixed timestep, zero or multiple times per frame depending on the time
+    /// accumulated since the last step. The leftover fraction of a step is written to the
+    /// [`FixedAlpha`] resource.
     FixedTimestep(FixedTime),
     /// Run systems based on a custom condition
     Custom(SystemCondition),
@@ -104,6 +106,8 @@ pub struct Phase {
     pub(super) layers: Vec<Layer>,
     pub(super) execution_type: PhaseExecutionType,
     pub(super) execution_policy: PhaseExecutionPolicy,
+    /// Apply deferred changes after every layer, not only after those with a sync point
+    pub(super) sync_layers: bool,
 
     /// This phase will run before these phases
     pub(super) before: Vec<&'static str>,
@@ -125,6 +129,7 @@ impl Phase {
             layers,
             execution_type: PhaseExecutionType::default(),
             execution_policy: PhaseExecutionPolicy::default(),
+            sync_layers: false,
             before: Vec::new(),
             after: Vec::new(),
         }
@@ -210,6 +215,8 @@ impl Phase {
         thread_pool: &ThreadPool,
     ) {
         let mut iterations = 1;
+        let mut fixed_delta = None;
+        let mut fixed_alpha = None;
 
         if self.execution_policy.is_normal() {
             // Normal execution, run every frame
@@ -220,6 +227,8 @@ impl Phase {
         } else if let Some(timestep) = self.execution_policy.get_fixed_timestep() {
             timestep.update();
             iterations = timestep.iter();
+            fixed_delta = Some(timestep.fixed_delta());
+            fixed_alpha = Some(timestep.alpha());
         } else if let Some(condition) = self.execution_policy.get_custom() {
             if !condition.run(world) {
                 return;
@@ -228,6 +237,18 @@ impl Phase {
             panic!("Unknown phase execution policy");
         }
 
+        // Systems and conditions read the step from Time
+        if fixed_delta.is_some()
+            && let Some(mut time) = world.resources.try_get_mut::<Time>()
+        {
+            time.set_phase_step(fixed_delta);
+        }
+
+        // Systems added since the last execution are batched once
+        for layer in &mut self.layers {
+            layer.build_batches();
+        }
+
         // Execute systems for the determined number of iterations
         for _ in 0..iterations {
             match self.execution_type {
@@ -236,6 +257,18 @@ impl Phase {
             }
         }
 
+        if fixed_delta.is_some()
+            && let Some(mut time) = world.resources.try_get_mut::<Time>()
+        {
+            time.set_phase_step(None);
+        }
+
+        if let Some(alpha) = fixed_alpha
+            && let Some(mut fixed_alpha) = world.resources.try_get_mut::<FixedAlpha>()
+        {
+            fixed_alpha.set(alpha);
+        }
+
         // Apply system changes after execution on main thread
         self.apply_systems(world);
 
@@ -246,20 +279,34 @@ impl Phase {
     /// Execute systems in this phase sequentially
     #[inline]
     fn execute_sequential(&mut self, world: &mut World) {
-        for layer in &mut self.layers {
-            for batch in &mut layer.batches {
-                for system in &mut batch.systems {
+        for layer_index in 0..self.layers.len() {
+            for batch_index in 0..self.layers[layer_index].batches.len() {
+                if self.layers[layer_index].batches[batch_index].is_exclusive() {
+                    self.execute_exclusive(world, layer_index, batch_index);
+                    continue;
+                }
+
+                for system in &mut self.layers[layer_index].batches[batch_index].systems {
                     system.run(world);
                 }
             }
+
+            self.sync_after_layer(world, layer_index);
         }
     }
 
     /// Execute systems in parallel where possible
     #[inline]
     fn execute_parallel(&mut self, world: &mut World, thread_pool: &ThreadPool) {
-        for layer in &mut self.layers {
-            for batch in &mut layer.batches {
+        for layer_index in 0..self.layers.len() {
+            for batch_index in 0..self.layers[layer_index].batches.len() {
+                if self.layers[layer_index].batches[batch_index].is_exclusive() {
+                    self.execute_exclusive(world, layer_index, batch_index);
+                    continue;
+                }
+
+                let batch = &mut self.layers[layer_index].batches[batch_index];
+
                 // TODO: Better heuristic for parallelization, maybe batch systems inside a batch
                 // and send those sub-batches to threads instead of individual systems
                 let parallelize = batch.systems.len() > 5;
@@ -281,9 +328,39 @@ impl Phase {
                     thread_pool.wait_all();
                 }
             }
+
+            self.sync_after_layer(world, layer_index);
         }
     }
 
+    /// Execute the exclusive system of a batch on the main thread. Changes of the systems which
+    /// ran before it are applied first, and its own changes right after it.
+    fn execute_exclusive(&mut self, world: &mut World, layer_index: usize, batch_index: usize) {
+        self.apply_deferred(world);
+
+        for system in &mut self.layers[layer_index].batches[batch_index].systems {
+            system.run(world);
+            system.apply(world);
+        }
+        world.flush_commands();
+    }
+
+    /// Apply deferred changes if the layer has a sync point
+    #[inline]
+    fn sync_after_layer(&mut self, world: &mut World, layer_index: usize) {
+        if self.sync_layers || self.layers[layer_index].sync_point {
+            self.apply_deferred(world);
+        }
+    }
+
+    /// Apply the systems which ran so far and flush their commands to the world
+    #[inline]
+    fn apply_deferred(&mut self, world: &mut World) {
+        // Applying systems which didn't run yet is a no-op
+        self.apply_systems(world);
+        world.flush_commands();
+    }
+
     /// Apply all systems
     #[inline]
     fn apply_systems(&mut self, world: &mut World) {
@@ -57,12 +57,17 @@ impl ThreadPool {
         }
     }
 
-    /// Execute a task in the thread pool
+    /// Execute a task in the thread pool, without any threads it runs on the calling thread
     #[inline]
     pub fn submit<F>(&self, task: Box<F>)
     where
         F: FnOnce() + Send + 'static,
     {
+        if self.threads.is_empty() {
+            task();
+            return;
+        }
+
         let message = Message::Task(task);
         self.active_tasks.fetch_add(1, Ordering::SeqCst);
         self.manager.send(message).unwrap();
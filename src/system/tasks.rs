@@ -1,23 +1,28 @@
 use std::{
     any::Any,
     sync::{Arc, Mutex, PoisonError, TryLockError},
-    thread,
 };
 
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+
 type WorkerResult<T> = Arc<Mutex<Option<T>>>;
 
 type Panic = Box<dyn Any + Send + 'static>;
 
-/// A worker that executes a task in a separate thread
+/// A worker that executes a task in a separate thread. On the web there are no threads, so
+/// synchronous tasks run immediately and asynchronous ones on the browser's event loop.
 struct Worker<T> {
     finished: bool,
     result: WorkerResult<T>,
     panic: Arc<Mutex<Option<Panic>>>,
+    #[cfg(not(target_arch = "wasm32"))]
     handle: Option<thread::JoinHandle<()>>,
 }
 
 impl<T> Worker<T> {
     /// Create a new worker
+    #[cfg(not(target_arch = "wasm32"))]
     #[inline]
     fn new(result: WorkerResult<T>, closure: impl FnOnce() + Send + 'static) -> Self {
         let panic = Arc::new(Mutex::new(None));
@@ -40,6 +45,36 @@ impl<T> Worker<T> {
         }
     }
 
+    /// Create a new worker, the closure runs to completion before this returns
+    #[cfg(target_arch = "wasm32")]
+    #[inline]
+    fn new(result: WorkerResult<T>, closure: impl FnOnce() + Send + 'static) -> Self {
+        let panic = Arc::new(Mutex::new(None));
+
+        if let Err(e) = std::panic::catch_unwind(std::panic::AssertUnwindSafe(closure)) {
+            panic.lock().unwrap().replace(e);
+        }
+
+        Self {
+            finished: false,
+            result,
+            panic,
+        }
+    }
+
+    /// Create a new worker which polls `future` on the browser's event loop
+    #[cfg(target_arch = "wasm32")]
+    #[inline]
+    fn spawn_local(result: WorkerResult<T>, future: impl Future<Output = ()> + 'static) -> Self {
+        wasm_bindgen_futures::spawn_local(future);
+
+        Self {
+            finished: false,
+            result,
+            panic: Arc::new(Mutex::new(None)),
+        }
+    }
+
     /// Retrieve the result of the task if its ready
     #[inline]
     fn get_result(&mut self) -> Option<Result<T, Panic>> {
@@ -58,11 +93,13 @@ impl<T> Worker<T> {
         if let Ok(mut guard) = self.panic.lock()
             && let Some(panic) = guard.take()
         {
+            #[cfg(not(target_arch = "wasm32"))]
             self.handle.take().unwrap().join().unwrap();
             return Some(Err(panic));
         }
 
         // Early return if task is not finished
+        #[cfg(not(target_arch = "wasm32"))]
         if !self.handle.as_ref().unwrap().is_finished() {
             return None;
         }
@@ -71,6 +108,7 @@ impl<T> Worker<T> {
 
         // Return the result
         if let Some(result) = guard.take() {
+            #[cfg(not(target_arch = "wasm32"))]
             self.handle.take().unwrap().join().unwrap();
             return Some(Ok(result));
         }
@@ -160,7 +198,7 @@ impl<T: Send + 'static> AsyncTask<T> {
     pub fn restart<F, Fut>(&mut self, task: F) -> bool
     where
         F: FnOnce() -> Fut + Send + 'static,
-        Fut: Future<Output = T>,
+        Fut: Future<Output = T> + 'static,
     {
         if self.is_running() {
             return false;
@@ -175,18 +213,29 @@ impl<T: Send + 'static> AsyncTask<T> {
     pub fn execute_async<F, Fut>(task: F) -> Self
     where
         F: FnOnce() -> Fut + Send + 'static,
-        Fut: Future<Output = T>,
+        Fut: Future<Output = T> + 'static,
     {
         let result = Arc::new(Mutex::new(None));
         let r = Arc::clone(&result);
 
-        let closure = move || {
-            let res = pollster::block_on(task());
-            let mut lock = r.lock().unwrap();
-            *lock = Some(res);
+        #[cfg(not(target_arch = "wasm32"))]
+        let worker = {
+            let closure = move || {
+                let res = pollster::block_on(task());
+                let mut lock = r.lock().unwrap();
+                *lock = Some(res);
+            };
+
+            Worker::new(result, closure)
         };
 
-        let worker = Worker::new(result, closure);
+        // blocking would stall the browser, so the future is polled by its event loop
+        #[cfg(target_arch = "wasm32")]
+        let worker = Worker::spawn_local(result, async move {
+            let res = task().await;
+            let mut lock = r.lock().unwrap();
+            *lock = Some(res);
+        });
 
         AsyncTask(Some(worker))
     }
@@ -0,0 +1,152 @@
+use std::{fmt::Debug, path::Path};
+
+use glam::{UVec2, Vec2};
+
+use crate::{
+    assets::{AssetLoader, AsyncLoadableAsset, LoadableAsset, io},
+    prelude::{Image, Resources},
+};
+
+/// Grid of normalized heights in the `0.0..=1.0` range, used by a [`Terrain`](super::Terrain).
+/// Sample `(0, 0)` is the terrain's origin, `X` maps to the local `X` axis and `Y` to the local
+/// `Z` axis.
+///
+/// Loaded from grayscale images with 16-bit precision, other formats use their luminance.
+#[derive(Clone, Debug, crate::macros::Asset)]
+pub struct Heightmap {
+    size: UVec2,
+    heights: Vec<f32>,
+}
+
+impl Heightmap {
+    /// Create a new heightmap from `heights` stored row by row
+    ///
+    /// # Panics
+    /// Panics if the size is smaller than 2x2 or doesn't match the amount of heights
+    pub fn new(size: UVec2, heights: Vec<f32>) -> Self {
+        assert!(
+            size.x >= 2 && size.y >= 2,
+            "Heightmap must have at least 2x2 samples"
+        );
+        assert_eq!(
+            heights.len(),
+            (size.x * size.y) as usize,
+            "Heightmap size doesn't match the amount of heights"
+        );
+
+        Self { size, heights }
+    }
+
+    /// Create a new heightmap with the height of every sample returned by `f`
+    pub fn from_fn(size: UVec2, mut f: impl FnMut(UVec2) -> f32) -> Self {
+        let heights = (0..size.y)
+            .flat_map(|y| (0..size.x).map(move |x| UVec2::new(x, y)))
+            .map(&mut f)
+            .collect();
+
+        Self::new(size, heights)
+    }
+
+    /// Create a new flat heightmap
+    pub fn flat(size: UVec2, height: f32) -> Self {
+        Self::new(size, vec![height; (size.x * size.y) as usize])
+    }
+
+    /// Create a heightmap from the red channel of an RGBA8 image
+    pub fn from_image(image: &Image) -> Self {
+        let size = UVec2::new(image.size.width, image.size.height);
+        let heights = image
+            .data
+            .chunks_exact(4)
+            .map(|texel| texel[0] as f32 / u8::MAX as f32)
+            .collect();
+
+        Self::new(size, heights)
+    }
+
+    /// Returns the amount of samples in each axis
+    #[inline]
+    pub fn size(&self) -> UVec2 {
+        self.size
+    }
+
+    /// Returns the height of the sample at `position`, clamped to the heightmap
+    #[inline]
+    pub fn get(&self, position: UVec2) -> f32 {
+        let position = position.min(self.size - 1);
+        self.heights[(position.y * self.size.x + position.x) as usize]
+    }
+
+    /// Set the height of the sample at `position`
+    ///
+    /// # Panics
+    /// Panics if `position` is out of bounds
+    pub fn set(&mut self, position: UVec2, height: f32) {
+        assert!(
+            position.x < self.size.x && position.y < self.size.y,
+            "Heightmap position {} is out of bounds for size {}",
+            position,
+            self.size
+        );
+        self.heights[(position.y * self.size.x + position.x) as usize] = height;
+    }
+
+    /// Returns the bilinearly filtered height at normalized `uv` coordinates, clamped to the
+    /// heightmap
+    pub fn sample(&self, uv: Vec2) -> f32 {
+        let position = uv.clamp(Vec2::ZERO, Vec2::ONE) * (self.size - 1).as_vec2();
+        let min = position.floor().as_uvec2();
+        let t = position - min.as_vec2();
+
+        let h00 = self.get(min);
+        let h10 = self.get(min + UVec2::X);
+        let h01 = self.get(min + UVec2::Y);
+        let h11 = self.get(min + UVec2::ONE);
+
+        let top = h00 + (h10 - h00) * t.x;
+        let bottom = h01 + (h11 - h01) * t.x;
+        top + (bottom - top) * t.y
+    }
+
+    /// Returns the min and max height of the samples inside of the normalized `min..max` region
+    pub fn range(&self, min: Vec2, max: Vec2) -> (f32, f32) {
+        let last = (self.size - 1).as_vec2();
+        let start = (min.clamp(Vec2::ZERO, Vec2::ONE) * last).floor().as_uvec2();
+        let end = (max.clamp(Vec2::ZERO, Vec2::ONE) * last).ceil().as_uvec2();
+
+        let mut range = (f32::MAX, f32::MIN);
+        for y in start.y..=end.y {
+            for x in start.x..=end.x {
+                let height = self.get(UVec2::new(x, y));
+                range = (range.0.min(height), range.1.max(height));
+            }
+        }
+
+        range
+    }
+}
+
+impl LoadableAsset for Heightmap {
+    fn load<P: AsRef<Path> + Debug>(_: &mut AssetLoader, _: &mut Resources, path: P) -> Self {
+        let bytes = io::read(path.as_ref())
+            .unwrap_or_else(|_| panic!("Could not read heightmap at '{:?}'", path));
+        Self::decode(bytes, path.as_ref()).unwrap_or_else(|err| panic!("{}", err))
+    }
+}
+
+impl AsyncLoadableAsset for Heightmap {
+    fn decode(bytes: Vec<u8>, path: &Path) -> Result<Self, String> {
+        let image = image::load_from_memory(&bytes)
+            .map_err(|err| format!("Could not open heightmap at '{:?}': {}", path, err))?
+            .to_luma16();
+
+        let (width, height) = image.dimensions();
+        let heights = image
+            .into_raw()
+            .into_iter()
+            .map(|height| height as f32 / u16::MAX as f32)
+            .collect();
+
+        Ok(Self::new(UVec2::new(width, height), heights))
+    }
+}
@@ -0,0 +1,241 @@
+use glam::{Vec2, Vec3};
+use wgpu::{VertexAttribute, VertexFormat};
+
+use crate::math::bounding_volume::AABB;
+
+use super::{Heightmap, Terrain};
+
+/// Node of the terrain quadtree. Level 0 covers the whole terrain, every following level splits
+/// its parent into 4 children. `x` and `z` are the node coordinates inside of its level.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TerrainNode {
+    pub level: u32,
+    pub x: u32,
+    pub z: u32,
+}
+
+impl TerrainNode {
+    /// Node covering the whole terrain
+    pub const ROOT: Self = Self {
+        level: 0,
+        x: 0,
+        z: 0,
+    };
+
+    /// Returns the 4 nodes covering this node on the next level
+    pub fn children(&self) -> [Self; 4] {
+        let (x, z) = (self.x * 2, self.z * 2);
+        let level = self.level + 1;
+
+        [
+            Self { level, x, z },
+            Self { level, x: x + 1, z },
+            Self { level, x, z: z + 1 },
+            Self {
+                level,
+                x: x + 1,
+                z: z + 1,
+            },
+        ]
+    }
+
+    /// Returns the min and max normalized coordinates of the area covered by the node
+    pub fn region(&self) -> (Vec2, Vec2) {
+        let size = 1.0 / (1u32 << self.level) as f32;
+        let min = Vec2::new(self.x as f32, self.z as f32) * size;
+
+        (min, min + size)
+    }
+}
+
+/// Vertex of a terrain node mesh
+#[repr(C)]
+#[derive(Default, Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct TerrainVertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    /// Normalized terrain coordinates, used for the splat map
+    pub uv: [f32; 2],
+}
+
+impl TerrainVertex {
+    /// Returns the vertex buffer layout for TerrainVertex
+    pub fn vertex_descriptor() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<TerrainVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                // Position
+                VertexAttribute {
+                    format: VertexFormat::Float32x3,
+                    offset: 0,
+                    shader_location: 0,
+                },
+                // Normal
+                VertexAttribute {
+                    format: VertexFormat::Float32x3,
+                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                },
+                // UV
+                VertexAttribute {
+                    format: VertexFormat::Float32x2,
+                    offset: std::mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                },
+            ],
+        }
+    }
+}
+
+/// Returns the local space bounds of a node
+pub(crate) fn node_bounds(terrain: &Terrain, heightmap: &Heightmap, node: TerrainNode) -> AABB {
+    let (min, max) = node.region();
+    let (min_height, max_height) = heightmap.range(min, max);
+
+    AABB::new(
+        Vec3::new(
+            min.x * terrain.size.x,
+            min_height * terrain.height_scale - terrain.skirt_depth,
+            min.y * terrain.size.y,
+        ),
+        Vec3::new(
+            max.x * terrain.size.x,
+            max_height * terrain.height_scale,
+            max.y * terrain.size.y,
+        ),
+    )
+}
+
+/// Selects the nodes to draw for a camera at `camera` in the terrain's local space. A node is
+/// split while the camera is closer to it than [`TerrainLod::split_distance`](super::TerrainLod)
+/// times its size, so detail increases towards the camera. `bounds` returns the local bounds of
+/// a node.
+pub(crate) fn select_nodes(
+    terrain: &Terrain,
+    camera: Vec3,
+    nodes: &mut Vec<TerrainNode>,
+    mut bounds: impl FnMut(TerrainNode) -> AABB,
+) {
+    let mut stack = vec![TerrainNode::ROOT];
+    while let Some(node) = stack.pop() {
+        if node.level >= terrain.lod.max_level {
+            nodes.push(node);
+            continue;
+        }
+
+        let bounds = bounds(node);
+        let distance = (camera.clamp(bounds.min, bounds.max) - camera).length();
+        let node_size = terrain.size.max_element() / (1u32 << node.level) as f32;
+
+        if distance < node_size * terrain.lod.split_distance {
+            stack.extend(node.children());
+        } else {
+            nodes.push(node);
+        }
+    }
+}
+
+/// Builds the vertices and indices of a node. The grid has [`TerrainLod::resolution`] quads per
+/// side and is surrounded by skirts, which hide the cracks between nodes of different levels.
+///
+/// [`TerrainLod::resolution`]: super::TerrainLod::resolution
+pub(crate) fn build_node_mesh(
+    terrain: &Terrain,
+    heightmap: &Heightmap,
+    node: TerrainNode,
+) -> (Vec<TerrainVertex>, Vec<u32>) {
+    let resolution = terrain.lod.resolution.max(1);
+    let row = resolution + 1;
+    let (min, max) = node.region();
+
+    // spacing between heightmap samples in local units, used for the normals
+    let sample_spacing = terrain.size / (heightmap.size() - 1).as_vec2();
+    let sample_uv = 1.0 / (heightmap.size() - 1).as_vec2();
+
+    let vertex = |uv: Vec2| {
+        let height = heightmap.sample(uv) * terrain.height_scale;
+
+        let left = heightmap.sample(uv - Vec2::new(sample_uv.x, 0.0));
+        let right = heightmap.sample(uv + Vec2::new(sample_uv.x, 0.0));
+        let back = heightmap.sample(uv - Vec2::new(0.0, sample_uv.y));
+        let front = heightmap.sample(uv + Vec2::new(0.0, sample_uv.y));
+        let normal = Vec3::new(
+            (left - right) * terrain.height_scale / (2.0 * sample_spacing.x),
+            1.0,
+            (back - front) * terrain.height_scale / (2.0 * sample_spacing.y),
+        )
+        .normalize();
+
+        TerrainVertex {
+            position: [uv.x * terrain.size.x, height, uv.y * terrain.size.y],
+            normal: normal.to_array(),
+            uv: uv.to_array(),
+        }
+    };
+
+    // surface grid
+    let mut vertices = Vec::with_capacity((row * row + row * 4) as usize);
+    for z in 0..row {
+        for x in 0..row {
+            let t = Vec2::new(x as f32, z as f32) / resolution as f32;
+            vertices.push(vertex(min + (max - min) * t));
+        }
+    }
+
+    let mut indices = Vec::with_capacity((resolution * resolution * 6 + resolution * 24) as usize);
+    for z in 0..resolution {
+        for x in 0..resolution {
+            let i00 = z * row + x;
+            let i10 = i00 + 1;
+            let i01 = i00 + row;
+            let i11 = i01 + 1;
+
+            // counter clockwise when seen from above
+            indices.extend([i00, i01, i10, i10, i01, i11]);
+        }
+    }
+
+    // skirts, every edge as its first surface index, the index step along the edge, and the
+    // outward direction in the XZ plane
+    let edges = [
+        (0, 1, Vec2::NEG_Y),
+        (resolution * row, 1, Vec2::Y),
+        (0, row, Vec2::NEG_X),
+        (resolution, row, Vec2::X),
+    ];
+
+    for (first, step, outward) in edges {
+        let edge = |i: u32| first + i * step;
+        let start = vertices.len() as u32;
+        for i in 0..row {
+            let surface = vertices[edge(i) as usize];
+            vertices.push(TerrainVertex {
+                position: [
+                    surface.position[0],
+                    surface.position[1] - terrain.skirt_depth,
+                    surface.position[2],
+                ],
+                // keep the surface normal, so skirts visible through cracks blend in
+                ..surface
+            });
+        }
+
+        for i in 0..resolution {
+            let (top_a, top_b) = (edge(i), edge(i + 1));
+            let (bottom_a, bottom_b) = (start + i, start + i + 1);
+
+            // face the skirt outwards, edges run along +X or +Z
+            let along = if outward.x == 0.0 { Vec2::X } else { Vec2::Y };
+            let flip = along.perp_dot(outward) < 0.0;
+
+            if flip {
+                indices.extend([top_a, top_b, bottom_a, bottom_a, top_b, bottom_b]);
+            } else {
+                indices.extend([top_a, bottom_a, top_b, top_b, bottom_a, bottom_b]);
+            }
+        }
+    }
+
+    (vertices, indices)
+}
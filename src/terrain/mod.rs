@@ -0,0 +1,311 @@
+//! # Terrain plugin
+//! Renders large heightmap terrains with a dedicated render path, which splits the terrain into a
+//! quadtree of chunks and draws distant areas with less detail.
+//!
+//! ## Usage
+//!
+//! - Add the [`TerrainPlugin`] to the app, it is not part of the [`DefaultPlugins`].
+//! - Load a [`Heightmap`] from a grayscale image, 16-bit images keep their full precision.
+//! - Spawn an entity with a [`Terrain`] and a [`Transform`]. The terrain lies in the local `XZ`
+//!   plane, starting at the origin, with heights along `Y`.
+//! ```ignore
+//! let heightmap = asset_loader.load::<Heightmap>("assets/heightmap.png", &mut world.resources);
+//! let material = TerrainMaterial::new(splat_map, [grass, rock, sand, snow]);
+//! commands
+//!     .spawn_empty()
+//!     .insert(Terrain::new(heightmap, material, Vec2::splat(512.0), 80.0))
+//!     .insert(Transform::default());
+//! ```
+//!
+//! ## Level of detail
+//!
+//! Every frame, the quadtree is traversed from the root and nodes close to the active camera are
+//! split into 4 children, up to [`TerrainLod::max_level`]. Every selected node is a grid of
+//! [`TerrainLod::resolution`] quads, so nodes closer to the camera have denser vertices. Nodes
+//! of different levels don't share their edge vertices, the cracks between them are hidden by
+//! skirts hanging below every node's edges.
+//!
+//! Node meshes are built when first selected and kept while they are in use. If frustum culling
+//! is enabled, nodes outside of the active camera's
+//! [`Frustum`](crate::math::bounding_volume::Frustum) are not drawn.
+//!
+//! ## Material
+//!
+//! The [`TerrainMaterial`] blends up to 4 tiling layer textures, weighted by the red, green, blue
+//! and alpha channels of a splat map stretched over the whole terrain. Terrains are lit by all
+//! lights in the scene, but they don't cast or receive shadows.
+//!
+//! ## Collision
+//!
+//! The terrain has no collider of its own. [`Terrain::height_at`], [`Terrain::normal_at`] and
+//! [`Terrain::raycast`] query the heightmap directly, e.g. to keep characters on the ground or
+//! to place objects under the cursor.
+
+mod heightmap;
+mod mesh;
+mod render;
+
+pub mod prelude {
+    pub use super::{Heightmap, Terrain, TerrainLod, TerrainMaterial, TerrainPlugin};
+}
+
+use glam::{Vec2, Vec3};
+
+use crate::{
+    math::bounding_volume::AABB,
+    palette,
+    plugins::RenderPlugin,
+    prelude::*,
+    render_assets::{BindGroup, IntoRenderAsset},
+};
+
+pub use heightmap::Heightmap;
+pub use mesh::TerrainNode;
+pub use render::TerrainRenderCache;
+
+/// Plugin which adds the terrain render node and its preparation system.
+pub struct TerrainPlugin;
+
+impl Plugin for TerrainPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Assets<Heightmap>>()
+            .init_resource::<TerrainRenderCache>()
+            .add_startup_system(render::register_terrain_graph)
+            .register_system(render::prepare_terrains_system, phase::PreRender);
+    }
+
+    fn dependencies(&self) -> Vec<PluginId> {
+        vec![PluginId::of::<RenderPlugin>()]
+    }
+}
+
+/// Textures of a [`Terrain`], blended by a splat map
+#[derive(Clone, Debug)]
+pub struct TerrainMaterial {
+    /// Weights of the layers in the red, green, blue and alpha channels, stretched over the
+    /// whole terrain. None uses only the first layer.
+    pub splat_map: Option<Handle<Image>>,
+    /// Layer textures, None draws the layer in white
+    pub layers: [Option<Handle<Image>>; 4],
+    /// Amount of times the layer textures repeat across the terrain
+    pub layer_tiling: f32,
+}
+
+impl Default for TerrainMaterial {
+    fn default() -> Self {
+        Self {
+            splat_map: None,
+            layers: Default::default(),
+            layer_tiling: 32.0,
+        }
+    }
+}
+
+impl TerrainMaterial {
+    /// Create a new material with a splat map and 4 layers
+    pub fn new(splat_map: Handle<Image>, layers: [Handle<Image>; 4]) -> Self {
+        Self {
+            splat_map: Some(splat_map),
+            layers: layers.map(Some),
+            ..Default::default()
+        }
+    }
+
+    /// Returns self with new `layer_tiling`
+    #[inline]
+    #[must_use]
+    pub fn with_layer_tiling(mut self, layer_tiling: f32) -> Self {
+        self.layer_tiling = layer_tiling;
+        self
+    }
+}
+
+/// Level of detail settings of a [`Terrain`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TerrainLod {
+    /// Amount of quads per side of every quadtree node
+    pub resolution: u32,
+    /// Deepest quadtree level, the finest nodes cover `1 / 2^max_level` of the terrain per side
+    pub max_level: u32,
+    /// Nodes closer to the camera than their size times this factor are split
+    pub split_distance: f32,
+}
+
+impl Default for TerrainLod {
+    fn default() -> Self {
+        Self {
+            resolution: 32,
+            max_level: 4,
+            split_distance: 1.5,
+        }
+    }
+}
+
+/// Heightmap terrain rendered with a [`TerrainMaterial`]. Requires a [`Transform`].
+///
+/// # Note
+/// Node meshes are rebuilt when any field changes. Editing the [`Heightmap`] asset in place is
+/// not detected, call [`Self::mark_changed`] afterwards.
+#[derive(Component, Clone, Debug)]
+pub struct Terrain {
+    pub heightmap: Handle<Heightmap>,
+    pub material: TerrainMaterial,
+    /// Local size of the terrain in the `XZ` plane
+    pub size: Vec2,
+    /// Local height of a heightmap sample of 1.0
+    pub height_scale: f32,
+    pub lod: TerrainLod,
+    /// Local depth of the skirts below node edges, should cover the largest height difference
+    /// between neighbouring levels
+    pub skirt_depth: f32,
+    version: u64,
+}
+
+impl Terrain {
+    /// Create a new terrain with default level of detail settings
+    pub fn new(
+        heightmap: Handle<Heightmap>,
+        material: TerrainMaterial,
+        size: Vec2,
+        height_scale: f32,
+    ) -> Self {
+        Self {
+            heightmap,
+            material,
+            size,
+            height_scale,
+            lod: TerrainLod::default(),
+            skirt_depth: height_scale * 0.1,
+            version: 0,
+        }
+    }
+
+    /// Returns self with new level of detail settings
+    #[inline]
+    #[must_use]
+    pub fn with_lod(mut self, lod: TerrainLod) -> Self {
+        self.lod = lod;
+        self
+    }
+
+    /// Rebuild all node meshes, e.g. after editing the heightmap asset
+    #[inline]
+    pub fn mark_changed(&mut self) {
+        self.version += 1;
+    }
+
+    /// Returns the version which is incremented by [`Self::mark_changed`]
+    #[inline]
+    pub(crate) fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Returns the local height at `local` point in the `XZ` plane, if it's inside the terrain
+    pub fn height_at(&self, heightmap: &Heightmap, local: Vec2) -> Option<f32> {
+        let uv = local / self.size;
+        if uv.x < 0.0 || uv.y < 0.0 || uv.x > 1.0 || uv.y > 1.0 {
+            return None;
+        }
+
+        Some(heightmap.sample(uv) * self.height_scale)
+    }
+
+    /// Returns the local surface normal at `local` point in the `XZ` plane, if it's inside the
+    /// terrain
+    pub fn normal_at(&self, heightmap: &Heightmap, local: Vec2) -> Option<Vec3> {
+        self.height_at(heightmap, local)?;
+
+        let spacing = self.size / (heightmap.size() - 1).as_vec2();
+        let height = |offset: Vec2| heightmap.sample((local + offset) / self.size);
+        let dx = height(Vec2::new(spacing.x, 0.0)) - height(Vec2::new(-spacing.x, 0.0));
+        let dz = height(Vec2::new(0.0, spacing.y)) - height(Vec2::new(0.0, -spacing.y));
+
+        Some(
+            Vec3::new(
+                -dx * self.height_scale / (2.0 * spacing.x),
+                1.0,
+                -dz * self.height_scale / (2.0 * spacing.y),
+            )
+            .normalize(),
+        )
+    }
+
+    /// Returns the world position where `ray` first hits the terrain surface. The ray is marched
+    /// in steps of half a heightmap sample and refined with a binary search.
+    ///
+    /// # Note
+    /// The distance along a ray transformed into local space is only the same as in world space
+    /// if the terrain isn't scaled, so the march step is based on local units.
+    pub fn raycast(
+        &self,
+        heightmap: &Heightmap,
+        global_transform: &GlobalTransform,
+        ray: &Ray,
+    ) -> Option<Vec3> {
+        let local_ray = ray.transform(&global_transform.matrix.inverse());
+        let bounds = AABB::new(
+            Vec3::ZERO,
+            Vec3::new(self.size.x, self.height_scale, self.size.y),
+        );
+
+        // clip the ray to the terrain bounds
+        let start = if local_ray.origin.cmpge(bounds.min).all()
+            && local_ray.origin.cmple(bounds.max).all()
+        {
+            0.0
+        } else {
+            local_ray.intersect_aabb(&bounds)?
+        };
+        let end = start + (bounds.max - bounds.min).length();
+
+        let step = (self.size / heightmap.size().as_vec2()).min_element() * 0.5;
+        let above = |distance: f32| {
+            let point = local_ray.at(distance);
+            self.height_at(heightmap, Vec2::new(point.x, point.z))
+                .is_none_or(|height| point.y > height)
+        };
+
+        let mut previous = start;
+        let mut distance = start;
+        while distance <= end {
+            if !above(distance) {
+                // binary search between the last point above and the first point below
+                let (mut low, mut high) = (previous, distance);
+                for _ in 0..16 {
+                    let middle = (low + high) * 0.5;
+                    if above(middle) {
+                        low = middle;
+                    } else {
+                        high = middle;
+                    }
+                }
+
+                let hit = local_ray.at(high);
+                return Some(global_transform.matrix.transform_point3(hit));
+            }
+
+            previous = distance;
+            distance += step;
+        }
+
+        None
+    }
+}
+
+impl IntoRenderAsset<BindGroup> for Terrain {
+    fn create_render_asset(&self, world: &mut World, _: Option<EntityId>) -> BindGroup {
+        let material = &self.material;
+        let mut builder = BindGroup::build("terrain").add_texture(
+            &material.splat_map,
+            world,
+            palette::RED,
+            None,
+            None,
+        );
+        for layer in &material.layers {
+            builder = builder.add_texture(layer, world, palette::WHITE, None, None);
+        }
+
+        builder.finish(&world.resources.get())
+    }
+}
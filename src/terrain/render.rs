@@ -0,0 +1,454 @@
+use std::collections::HashMap;
+
+use glam::Vec2;
+
+use crate::{
+    assets::ShaderLoader,
+    core::{graph::*, lighting::LightAndShadowManager, standard::rendering::surface_size},
+    math::bounding_volume::{AABB, Frustum, ToWorldSpace, WorldBoundingVolume},
+    prelude::*,
+    render_assets::{BindGroup, Buffer, Pipeline, RenderAssets, pipeline::PipelineBuilder},
+    renderer::{
+        culling::FrustumCullingSettings,
+        newtype::{RenderCommandEncoder, RenderDevice, RenderSurfaceConfiguration},
+    },
+};
+
+use super::{
+    Heightmap, Terrain, TerrainLod,
+    mesh::{self, TerrainNode, TerrainVertex},
+};
+
+/// Amount of frames a node mesh is kept after it was last selected
+const NODE_CACHE_FRAMES: u64 = 120;
+
+/// Push constants of the terrain pipeline
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct TerrainConstants {
+    model: [[f32; 4]; 4],
+    layer_tiling: f32,
+    light_count: u32,
+    _padding: [u32; 2],
+}
+
+/// Fields of a [`Terrain`] which affect its node meshes
+#[derive(Clone, PartialEq)]
+struct MeshKey {
+    heightmap: Handle<Heightmap>,
+    size: Vec2,
+    height_scale: f32,
+    lod: TerrainLod,
+    skirt_depth: f32,
+    version: u64,
+}
+
+impl MeshKey {
+    fn new(terrain: &Terrain) -> Self {
+        Self {
+            heightmap: terrain.heightmap.clone(),
+            size: terrain.size,
+            height_scale: terrain.height_scale,
+            lod: terrain.lod,
+            skirt_depth: terrain.skirt_depth,
+            version: terrain.version(),
+        }
+    }
+}
+
+/// GPU side of a single quadtree node
+struct PreparedNode {
+    buffer: Buffer,
+    /// Frame in which the node was last selected
+    last_used: u64,
+}
+
+/// GPU side of a single terrain
+struct PreparedTerrain {
+    mesh_key: MeshKey,
+    /// Splat map and layer images of the current material bind group
+    textures: Vec<Option<Handle<Image>>>,
+    model: [[f32; 4]; 4],
+    layer_tiling: f32,
+    nodes: HashMap<TerrainNode, PreparedNode>,
+    /// Local bounds of nodes, computed once per mesh key
+    bounds: HashMap<TerrainNode, AABB>,
+    /// Nodes which were selected and passed culling this frame, by the active camera
+    visible: HashMap<EntityId, Vec<TerrainNode>>,
+}
+
+/// Render cache for every [`Terrain`], holds node buffers and the visible nodes of the current
+/// frame. Updated in [`prepare_terrains_system`].
+#[derive(Default, crate::macros::Resource)]
+pub struct TerrainRenderCache {
+    terrains: HashMap<EntityId, PreparedTerrain>,
+    frame: u64,
+}
+
+impl TerrainRenderCache {
+    /// Returns the amount of nodes which will be drawn this frame, summed over the active cameras
+    pub fn visible_nodes(&self) -> usize {
+        self.terrains
+            .values()
+            .flat_map(|t| t.visible.values())
+            .map(Vec::len)
+            .sum()
+    }
+
+    /// Returns the amount of node meshes currently kept on the GPU
+    pub fn cached_nodes(&self) -> usize {
+        self.terrains.values().map(|t| t.nodes.len()).sum()
+    }
+}
+
+/// Creates the vertex and index buffers of a node
+fn create_node_buffer(
+    terrain: &Terrain,
+    heightmap: &Heightmap,
+    node: TerrainNode,
+    device: &RenderDevice,
+) -> Buffer {
+    let (vertices, indices) = mesh::build_node_mesh(terrain, heightmap, node);
+
+    Buffer::new("terrain_node")
+        .create_vertex_buffer(&vertices, vertices.len(), None, device)
+        .create_index_buffer(&indices, None, device)
+}
+
+/// Pre-render system to select the quadtree nodes of every terrain for every active camera, cull
+/// them, and build missing node meshes.
+pub(crate) fn prepare_terrains_system(
+    device: Res<RenderDevice>,
+    culling: Option<Res<FrustumCullingSettings>>,
+    heightmaps: Res<Assets<Heightmap>>,
+    mut cache: ResMut<TerrainRenderCache>,
+    mut bind_groups: ResMut<RenderAssets<BindGroup>>,
+    mut query: Query<(EntityId, &Terrain, &GlobalTransform)>,
+) {
+    cache.frame += 1;
+    let frame = cache.frame;
+
+    // extract the active camera positions and frustums
+    let culling = culling.is_some_and(|settings| settings.enabled);
+    let cameras = query
+        .cast::<(EntityId, &Camera, &GlobalTransform, Option<&Frustum>), With<Camera3D>>()
+        .iter_mut()
+        .into_iter()
+        .filter(|(_, camera, ..)| camera.active)
+        .map(|(id, _, transform, frustum)| (id, transform.translation(), frustum.cloned()))
+        .collect::<Vec<_>>();
+
+    let mut alive = Vec::new();
+    let mut selected = Vec::new();
+    for (id, terrain, global_transform) in query.iter_mut() {
+        alive.push(id);
+
+        // heightmap might not be loaded yet
+        let Some(heightmap) = heightmaps.get(&terrain.heightmap) else {
+            continue;
+        };
+
+        // rebuild meshes if the terrain changed, and the bind group if the material changed
+        let mesh_key = MeshKey::new(terrain);
+        let textures = std::iter::once(terrain.material.splat_map.clone())
+            .chain(terrain.material.layers.iter().cloned())
+            .collect::<Vec<_>>();
+
+        let prepared = cache.terrains.entry(id).or_insert_with(|| PreparedTerrain {
+            mesh_key: mesh_key.clone(),
+            textures: textures.clone(),
+            model: Default::default(),
+            layer_tiling: 0.0,
+            nodes: HashMap::new(),
+            bounds: HashMap::new(),
+            visible: HashMap::new(),
+        });
+
+        if prepared.mesh_key != mesh_key {
+            prepared.mesh_key = mesh_key;
+            prepared.nodes.clear();
+            prepared.bounds.clear();
+        }
+        if prepared.textures != textures {
+            prepared.textures = textures;
+            bind_groups.remove_by_entity(id, terrain);
+        }
+
+        prepared.model = global_transform.matrix.to_cols_array_2d();
+        prepared.layer_tiling = terrain.material.layer_tiling;
+        prepared.visible.clear();
+
+        let bounds = &mut prepared.bounds;
+        let mut node_bounds = |node: TerrainNode| {
+            bounds
+                .entry(node)
+                .or_insert_with(|| mesh::node_bounds(terrain, heightmap, node))
+                .clone()
+        };
+
+        for (camera_id, camera_position, frustum) in &cameras {
+            // select nodes with the camera in local space
+            let local_camera = global_transform
+                .matrix
+                .inverse()
+                .transform_point3(*camera_position);
+            selected.clear();
+            mesh::select_nodes(terrain, local_camera, &mut selected, &mut node_bounds);
+
+            let mut visible = Vec::new();
+            for &node in &selected {
+                // cull the node
+                if culling && let Some(frustum) = frustum {
+                    let bounds = node_bounds(node).to_world_space(&global_transform.matrix);
+                    if !frustum.intersects(&WorldBoundingVolume::AABB(bounds)) {
+                        continue;
+                    }
+                }
+
+                let prepared_node = prepared.nodes.entry(node).or_insert_with(|| PreparedNode {
+                    buffer: create_node_buffer(terrain, heightmap, node, &device),
+                    last_used: frame,
+                });
+                prepared_node.last_used = frame;
+                visible.push(node);
+            }
+            prepared.visible.insert(*camera_id, visible);
+        }
+
+        // drop nodes which were not used for a while
+        prepared
+            .nodes
+            .retain(|_, node| frame - node.last_used <= NODE_CACHE_FRAMES);
+    }
+
+    // drop despawned terrains
+    cache.terrains.retain(|id, _| alive.contains(id));
+}
+
+/// Startup system to register the terrain graph node
+pub(crate) fn register_terrain_graph(
+    graph: &mut RenderGraph,
+    device: Res<RenderDevice>,
+    surface_config: Res<RenderSurfaceConfiguration>,
+    mut shader_loader: ResMut<ShaderLoader>,
+) {
+    let pipeline_builder =
+        create_terrain_pipeline_builder(&device, &surface_config, &mut shader_loader);
+
+    let node = GraphNodeBuilder::new("terrain")
+        .set_pipeline(pipeline_builder)
+        .set_custom_system(terrain_render_system)
+        .set_color_target(NodeColorTarget::Surface)
+        .set_depth_target(NodeDepthTarget::Node("main".to_string()))
+        .run_after("main")
+        .run_before("main_2d")
+        .build();
+
+    graph.add(node);
+}
+
+/// Terrain graph node rendering system, draws every visible node of every terrain
+fn terrain_render_system(
+    graph_ctx: Res<RenderContext>,
+
+    world: &mut World,
+    encoder: &mut RenderCommandEncoder,
+    mut bind_groups: ResMut<RenderAssets<BindGroup>>,
+    cache: Res<TerrainRenderCache>,
+    light_manager: Res<LightAndShadowManager>,
+
+    mut terrain_query: Query<&Terrain, Without<Hidden>>,
+    rendered: Res<RenderedWindow>,
+) {
+    if cache.visible_nodes() == 0 {
+        return;
+    }
+
+    let cameras = Camera::rendering_to(
+        terrain_query
+            .cast::<(EntityId, &Camera), With<Camera3D>>()
+            .iter_mut(),
+        &rendered,
+    );
+    if cameras.is_empty() {
+        return;
+    }
+
+    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("terrain render pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: unsafe {
+                &*graph_ctx
+                    .color_target
+                    .expect("terrain color target is None")
+            },
+            depth_slice: None,
+            resolve_target: graph_ctx.resolve_target.map(|view| unsafe { &*view }),
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+            view: unsafe {
+                &*graph_ctx
+                    .depth_target
+                    .expect("terrain depth target is None")
+            },
+            depth_ops: Some(wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: wgpu::StoreOp::Store,
+            }),
+            stencil_ops: None,
+        }),
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    });
+
+    render_pass.set_pipeline(
+        unsafe { &*graph_ctx.node }
+            .data
+            .pipeline
+            .as_ref()
+            .expect("Pipeline should have been generated by now")
+            .render_pipeline(),
+    );
+
+    render_pass.set_bind_group(2, light_manager.storage.bind_group(), &[]);
+
+    let target_size = surface_size(world);
+    let mut draw_calls = 0;
+    for (camera_id, camera) in cameras {
+        if !camera.set_render_pass_camera(
+            camera_id,
+            &mut render_pass,
+            1,
+            target_size,
+            &mut bind_groups,
+            world,
+        ) {
+            continue;
+        }
+
+        for (id, prepared) in &cache.terrains {
+            let Some(visible) = prepared.visible.get(&camera_id).filter(|v| !v.is_empty()) else {
+                continue;
+            };
+
+            let Some(terrain) = terrain_query.get(*id) else {
+                continue;
+            };
+
+            let material_bind_group = bind_groups.get_by_entity(*id, terrain, world);
+            render_pass.set_bind_group(0, &*material_bind_group, &[]);
+
+            let constants = TerrainConstants {
+                model: prepared.model,
+                layer_tiling: prepared.layer_tiling,
+                light_count: light_manager.storage.count() as u32,
+                _padding: [0; 2],
+            };
+            render_pass.set_push_constants(
+                wgpu::ShaderStages::VERTEX_FRAGMENT,
+                0,
+                bytemuck::bytes_of(&constants),
+            );
+
+            for node in visible {
+                let Some(buffer) = prepared.nodes.get(node).map(|node| &node.buffer) else {
+                    continue;
+                };
+                let (Some(vertex_buffer), Some(index_buffer)) = (&buffer.vertex, &buffer.index)
+                else {
+                    continue;
+                };
+
+                render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+                render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                render_pass.draw_indexed(0..buffer.num_indices, 0, 0..1);
+                draw_calls += 1;
+            }
+        }
+    }
+
+    graph_ctx.record_draw_calls(draw_calls);
+}
+
+fn create_terrain_pipeline_builder(
+    device: &RenderDevice,
+    surface_config: &RenderSurfaceConfiguration,
+    shader_loader: &mut ShaderLoader,
+) -> PipelineBuilder {
+    // Material bind group layout for the splat map and 4 layers, each a texture and a sampler
+    let material_entries = (0..5)
+        .flat_map(|i| {
+            [
+                wgpu::BindGroupLayoutEntry {
+                    binding: i * 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: i * 2 + 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ]
+        })
+        .collect::<Vec<_>>();
+    let material_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("terrain_material_bind_group_layout"),
+        entries: &material_entries,
+    });
+
+    // Camera bind group layout for uniform buffer
+    let camera_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("camera_bind_group_layout"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+    });
+
+    // Light bind group layout for storage buffer
+    let lights_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("lights_bind_group_layout"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: true },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+    });
+
+    // Load shader modules
+    shader_loader.load("terrain", include_str!("../shaders/terrain.wgsl"), device);
+
+    Pipeline::build("terrain_pipeline")
+        .set_bind_group_layouts(vec![material_layout, camera_layout, lights_layout])
+        .set_vertex_buffer_layouts(vec![TerrainVertex::vertex_descriptor()])
+        .set_vertex_shader("terrain", "vs_main")
+        .set_fragment_shader("terrain", "fs_main")
+        .add_color_format(surface_config.format)
+        .set_depth_format(wgpu::TextureFormat::Depth32Float)
+        .set_push_constant_ranges(vec![wgpu::PushConstantRange {
+            stages: wgpu::ShaderStages::VERTEX_FRAGMENT,
+            range: 0..std::mem::size_of::<TerrainConstants>() as u32,
+        }])
+}
@@ -0,0 +1,383 @@
+//! # Tilemap plugin
+//! Renders large 2D tile grids with a dedicated batched render path, instead of spawning one
+//! entity with a [`Mesh`] per tile.
+//!
+//! ## Usage
+//!
+//! - Add the [`TilemapPlugin`] to the app, it is not part of the [`DefaultPlugins`].
+//! - Spawn an entity with a [`Tilemap`] and a [`Transform`]. The tilemap lies in the local `XY`
+//!   plane, tile `(0, 0)` is at the origin and `Y` points up.
+//! ```ignore
+//! let atlas = TileAtlas::new(image_handle, 8, 8);
+//! let mut tilemap = Tilemap::new(atlas, UVec2::new(256, 256), Vec2::splat(1.0));
+//! tilemap.set(UVec2::new(3, 4), Tile::new(12));
+//! ```
+//!
+//! ## Chunks
+//!
+//! Tiles are stored in square chunks of [`Tilemap::chunk_size`] tiles. Each chunk has its own
+//! vertex buffer, which is only rewritten when a tile inside of it changes. If frustum culling is
+//! enabled, chunks outside of the active camera's [`Frustum`](crate::math::bounding_volume::Frustum)
+//! are not drawn.
+
+mod render;
+
+pub mod prelude {
+    pub use super::{Tile, TileAtlas, Tilemap, TilemapPlugin};
+}
+
+use glam::{UVec2, Vec2, Vec3};
+
+use crate::{
+    math::bounding_volume::AABB,
+    palette,
+    plugins::RenderPlugin,
+    prelude::*,
+    render_assets::{BindGroup, IntoRenderAsset},
+};
+
+pub use render::TilemapRenderCache;
+
+/// Plugin which adds the tilemap render node and its preparation system.
+pub struct TilemapPlugin;
+
+impl Plugin for TilemapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TilemapRenderCache>()
+            .add_startup_system(render::register_tilemap_graph)
+            .register_system(render::prepare_tilemaps_system, phase::PreRender);
+    }
+
+    fn dependencies(&self) -> Vec<PluginId> {
+        vec![PluginId::of::<RenderPlugin>()]
+    }
+}
+
+/// Texture atlas used by a [`Tilemap`], the image is split into a uniform grid of tiles.
+/// Tile indices go row by row, starting at the top left corner of the image.
+#[derive(Clone, Debug)]
+pub struct TileAtlas {
+    pub image: Handle<Image>,
+    pub columns: u32,
+    pub rows: u32,
+}
+
+impl TileAtlas {
+    /// Create a new atlas with `columns` x `rows` tiles
+    pub fn new(image: Handle<Image>, columns: u32, rows: u32) -> Self {
+        assert!(
+            columns > 0 && rows > 0,
+            "TileAtlas must have at least one column and row"
+        );
+
+        Self {
+            image,
+            columns,
+            rows,
+        }
+    }
+
+    /// Returns the amount of tiles in the atlas
+    #[inline]
+    pub fn len(&self) -> u32 {
+        self.columns * self.rows
+    }
+
+    /// Returns true if the atlas has no tiles
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the min and max uv coordinates of the tile at `index`
+    pub fn uv_rect(&self, index: u32) -> (Vec2, Vec2) {
+        let index = index % self.len();
+        let tile = Vec2::new(1.0 / self.columns as f32, 1.0 / self.rows as f32);
+        let min = Vec2::new(
+            (index % self.columns) as f32 * tile.x,
+            (index / self.columns) as f32 * tile.y,
+        );
+
+        (min, min + tile)
+    }
+}
+
+/// Single tile in a [`Tilemap`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tile {
+    /// Index of the tile in the [`TileAtlas`]
+    pub index: u32,
+    /// Color multiplied with the atlas texture
+    pub color: Color,
+    pub flip_x: bool,
+    pub flip_y: bool,
+}
+
+impl Tile {
+    /// Create a new white tile with atlas `index`
+    pub fn new(index: u32) -> Self {
+        Self {
+            index,
+            color: palette::WHITE,
+            flip_x: false,
+            flip_y: false,
+        }
+    }
+
+    /// Returns self with new `color`
+    #[inline]
+    #[must_use]
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Returns self with flipped axes
+    #[inline]
+    #[must_use]
+    pub fn with_flip(mut self, flip_x: bool, flip_y: bool) -> Self {
+        self.flip_x = flip_x;
+        self.flip_y = flip_y;
+        self
+    }
+}
+
+/// Square block of tiles, the unit of GPU uploads and culling
+#[derive(Clone, Debug)]
+struct TilemapChunk {
+    tiles: Vec<Option<Tile>>,
+    /// Incremented on every change, used by the renderer to detect stale buffers
+    version: u64,
+}
+
+/// Chunked grid of tiles rendered with a single [`TileAtlas`]. Requires a [`Transform`].
+///
+/// # Note
+/// Mutating tiles through [`Self::set`] only marks the affected chunk as changed, so only that
+/// chunk is uploaded to the GPU again.
+#[derive(Component, Clone, Debug)]
+pub struct Tilemap {
+    pub atlas: TileAtlas,
+    /// Size of a single tile in local units
+    pub tile_size: Vec2,
+    size: UVec2,
+    chunk_size: u32,
+    chunks: Vec<TilemapChunk>,
+}
+
+impl Tilemap {
+    /// Default amount of tiles per chunk side
+    pub const DEFAULT_CHUNK_SIZE: u32 = 16;
+
+    /// Create a new empty tilemap with `size` tiles
+    pub fn new(atlas: TileAtlas, size: UVec2, tile_size: Vec2) -> Self {
+        Self::with_chunk_size(atlas, size, tile_size, Self::DEFAULT_CHUNK_SIZE)
+    }
+
+    /// Create a new empty tilemap with a custom chunk size
+    ///
+    /// # Panics
+    /// Panics if `chunk_size` is 0
+    pub fn with_chunk_size(
+        atlas: TileAtlas,
+        size: UVec2,
+        tile_size: Vec2,
+        chunk_size: u32,
+    ) -> Self {
+        assert!(chunk_size > 0, "Tilemap chunk size must be greater than 0");
+
+        let chunks_x = size.x.div_ceil(chunk_size);
+        let chunks_y = size.y.div_ceil(chunk_size);
+        let chunk = TilemapChunk {
+            tiles: vec![None; (chunk_size * chunk_size) as usize],
+            version: 0,
+        };
+
+        Self {
+            atlas,
+            tile_size,
+            size,
+            chunk_size,
+            chunks: vec![chunk; (chunks_x * chunks_y) as usize],
+        }
+    }
+
+    /// Returns the size of the tilemap in tiles
+    #[inline]
+    pub fn size(&self) -> UVec2 {
+        self.size
+    }
+
+    /// Returns the amount of tiles per chunk side
+    #[inline]
+    pub fn chunk_size(&self) -> u32 {
+        self.chunk_size
+    }
+
+    /// Returns the amount of chunks in each axis
+    #[inline]
+    pub fn chunk_count(&self) -> UVec2 {
+        UVec2::new(
+            self.size.x.div_ceil(self.chunk_size),
+            self.size.y.div_ceil(self.chunk_size),
+        )
+    }
+
+    /// Returns the chunk index and the tile index inside of that chunk
+    #[inline]
+    fn locate(&self, position: UVec2) -> Option<(usize, usize)> {
+        if position.x >= self.size.x || position.y >= self.size.y {
+            return None;
+        }
+
+        let chunk = position / self.chunk_size;
+        let local = position % self.chunk_size;
+
+        let chunk_index = chunk.y * self.chunk_count().x + chunk.x;
+        let tile_index = local.y * self.chunk_size + local.x;
+        Some((chunk_index as usize, tile_index as usize))
+    }
+
+    /// Get the tile at `position`
+    pub fn get(&self, position: UVec2) -> Option<&Tile> {
+        let (chunk, tile) = self.locate(position)?;
+        self.chunks[chunk].tiles[tile].as_ref()
+    }
+
+    /// Set the tile at `position`, returns the previous tile
+    ///
+    /// # Panics
+    /// Panics if `position` is out of bounds
+    pub fn set(&mut self, position: UVec2, tile: impl Into<Option<Tile>>) -> Option<Tile> {
+        let (chunk, index) = self.locate(position).unwrap_or_else(|| {
+            panic!(
+                "Tile position {} is out of bounds for tilemap of size {}",
+                position, self.size
+            )
+        });
+
+        let tile = tile.into();
+        let chunk = &mut self.chunks[chunk];
+        if chunk.tiles[index] == tile {
+            return tile;
+        }
+
+        chunk.version += 1;
+        std::mem::replace(&mut chunk.tiles[index], tile)
+    }
+
+    /// Remove the tile at `position`, returns the removed tile
+    #[inline]
+    pub fn remove(&mut self, position: UVec2) -> Option<Tile> {
+        self.set(position, None)
+    }
+
+    /// Set every tile in the tilemap
+    pub fn fill(&mut self, tile: impl Into<Option<Tile>>) {
+        let tile = tile.into();
+        for y in 0..self.size.y {
+            for x in 0..self.size.x {
+                self.set(UVec2::new(x, y), tile);
+            }
+        }
+    }
+
+    /// Returns the local space size of the whole tilemap
+    #[inline]
+    pub fn local_size(&self) -> Vec2 {
+        self.size.as_vec2() * self.tile_size
+    }
+
+    /// Returns the tile position at `local` point, if it's inside the tilemap
+    pub fn local_to_tile(&self, local: Vec2) -> Option<UVec2> {
+        let tile = (local / self.tile_size).floor();
+        if tile.x < 0.0 || tile.y < 0.0 {
+            return None;
+        }
+
+        let tile = tile.as_uvec2();
+        (tile.x < self.size.x && tile.y < self.size.y).then_some(tile)
+    }
+
+    /// Returns the amount of chunks
+    #[inline]
+    pub(crate) fn chunks_len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Returns the version of a chunk
+    #[inline]
+    pub(crate) fn chunk_version(&self, chunk: usize) -> u64 {
+        self.chunks[chunk].version
+    }
+
+    /// Returns the local space bounds of a chunk
+    pub(crate) fn chunk_bounds(&self, chunk: usize) -> AABB {
+        let count = self.chunk_count();
+        let chunk = UVec2::new(chunk as u32 % count.x, chunk as u32 / count.x);
+
+        let min = (chunk * self.chunk_size).as_vec2() * self.tile_size;
+        let max = ((chunk + 1) * self.chunk_size).min(self.size).as_vec2() * self.tile_size;
+
+        AABB::new(min.extend(0.0), max.extend(0.0))
+    }
+
+    /// Returns the vertex data of a chunk, every tile slot has 4 vertices. Empty tiles are
+    /// degenerate quads, so the vertex count is the same for every chunk.
+    pub(crate) fn chunk_vertices(&self, chunk: usize) -> Vec<render::TileVertex> {
+        let count = self.chunk_count();
+        let origin = UVec2::new(chunk as u32 % count.x, chunk as u32 / count.x) * self.chunk_size;
+
+        let mut vertices = Vec::with_capacity(self.chunks[chunk].tiles.len() * 4);
+        for (i, tile) in self.chunks[chunk].tiles.iter().enumerate() {
+            let Some(tile) = tile else {
+                vertices.extend([render::TileVertex::default(); 4]);
+                continue;
+            };
+
+            let position =
+                origin + UVec2::new(i as u32 % self.chunk_size, i as u32 / self.chunk_size);
+            let min = position.as_vec2() * self.tile_size;
+            let max = min + self.tile_size;
+
+            let (mut uv_min, mut uv_max) = self.atlas.uv_rect(tile.index);
+            if tile.flip_x {
+                std::mem::swap(&mut uv_min.x, &mut uv_max.x);
+            }
+            if tile.flip_y {
+                std::mem::swap(&mut uv_min.y, &mut uv_max.y);
+            }
+
+            let color = [tile.color.r, tile.color.g, tile.color.b, tile.color.a];
+            let vertex = |x: f32, y: f32, u: f32, v: f32| render::TileVertex {
+                position: Vec3::new(x, y, 0.0).to_array(),
+                uv: [u, v],
+                color,
+            };
+
+            // uv y axis points down, tile y axis points up
+            vertices.extend([
+                vertex(min.x, min.y, uv_min.x, uv_max.y),
+                vertex(max.x, min.y, uv_max.x, uv_max.y),
+                vertex(max.x, max.y, uv_max.x, uv_min.y),
+                vertex(min.x, max.y, uv_min.x, uv_min.y),
+            ]);
+        }
+
+        vertices
+    }
+}
+
+impl IntoRenderAsset<BindGroup> for Tilemap {
+    fn create_render_asset(&self, world: &mut World, _: Option<EntityId>) -> BindGroup {
+        BindGroup::build("tilemap")
+            .add_texture(
+                &Some(self.atlas.image.clone()),
+                world,
+                palette::WHITE,
+                None,
+                None,
+            )
+            .finish(&world.resources.get())
+    }
+}
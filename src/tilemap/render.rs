@@ -0,0 +1,408 @@
+use std::collections::HashMap;
+
+use wgpu::{VertexAttribute, VertexFormat};
+
+use crate::{
+    assets::ShaderLoader,
+    core::{graph::*, standard::rendering::surface_size},
+    math::bounding_volume::{Frustum, ToWorldSpace, WorldBoundingVolume},
+    prelude::*,
+    render_assets::{
+        BindGroup, Buffer, Pipeline, RenderAssets, StagingBelt, pipeline::PipelineBuilder,
+    },
+    renderer::{
+        culling::FrustumCullingSettings,
+        newtype::{RenderCommandEncoder, RenderDevice, RenderSurfaceConfiguration},
+    },
+};
+
+use super::Tilemap;
+
+/// Vertex of a single tile corner
+#[repr(C)]
+#[derive(Default, Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct TileVertex {
+    pub position: [f32; 3],
+    pub uv: [f32; 2],
+    pub color: [f32; 4],
+}
+
+impl TileVertex {
+    /// Returns the vertex buffer layout for TileVertex
+    pub fn vertex_descriptor() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<TileVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                // Position
+                VertexAttribute {
+                    format: VertexFormat::Float32x3,
+                    offset: 0,
+                    shader_location: 0,
+                },
+                // UV
+                VertexAttribute {
+                    format: VertexFormat::Float32x2,
+                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                },
+                // Color
+                VertexAttribute {
+                    format: VertexFormat::Float32x4,
+                    offset: std::mem::size_of::<[f32; 5]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                },
+            ],
+        }
+    }
+}
+
+/// GPU side of a single tilemap chunk
+struct PreparedChunk {
+    buffer: Buffer,
+    /// Chunk version which is currently uploaded
+    version: u64,
+}
+
+/// GPU side of a single tilemap
+struct PreparedTilemap {
+    chunk_size: u32,
+    atlas: Handle<Image>,
+    model: [[f32; 4]; 4],
+    chunks: Vec<PreparedChunk>,
+    /// Indices of chunks which passed culling this frame, by the active camera
+    visible: HashMap<EntityId, Vec<usize>>,
+}
+
+/// Render cache for every [`Tilemap`], holds chunk buffers and the visible chunks of the current
+/// frame. Updated in [`prepare_tilemaps_system`].
+#[derive(Default, crate::macros::Resource)]
+pub struct TilemapRenderCache {
+    tilemaps: HashMap<EntityId, PreparedTilemap>,
+}
+
+impl TilemapRenderCache {
+    /// Returns the amount of chunks which will be drawn this frame, summed over the active cameras
+    pub fn visible_chunks(&self) -> usize {
+        self.tilemaps
+            .values()
+            .flat_map(|t| t.visible.values())
+            .map(Vec::len)
+            .sum()
+    }
+}
+
+/// Creates a chunk buffer, the vertex buffer is writable so tile changes don't need a new buffer
+fn create_chunk_buffer(tilemap: &Tilemap, chunk: usize, device: &RenderDevice) -> Buffer {
+    let vertices = tilemap.chunk_vertices(chunk);
+    let indices = (0..vertices.len() as u32 / 4)
+        .flat_map(|i| {
+            let i = i * 4;
+            [i, i + 1, i + 2, i + 2, i + 3, i]
+        })
+        .collect::<Vec<_>>();
+
+    Buffer::new("tilemap_chunk")
+        .create_vertex_buffer(
+            &vertices,
+            vertices.len(),
+            Some(wgpu::BufferUsages::COPY_DST),
+            device,
+        )
+        .create_index_buffer(&indices, None, device)
+}
+
+/// Pre-render system to upload changed tilemap chunks and cull them against every active camera.
+pub(crate) fn prepare_tilemaps_system(
+    device: Res<RenderDevice>,
+    mut belt: ResMut<StagingBelt>,
+    culling: Option<Res<FrustumCullingSettings>>,
+    mut cache: ResMut<TilemapRenderCache>,
+    mut bind_groups: ResMut<RenderAssets<BindGroup>>,
+    mut query: Query<(EntityId, &Tilemap, &GlobalTransform)>,
+) {
+    // extract the active camera frustums
+    let culling = culling.is_some_and(|settings| settings.enabled);
+    let cameras = query
+        .cast::<(EntityId, &Camera, Option<&Frustum>), ()>()
+        .iter_mut()
+        .into_iter()
+        .filter(|(_, camera, _)| camera.active)
+        .map(|(id, _, frustum)| (id, frustum.cloned()))
+        .collect::<Vec<_>>();
+
+    let mut alive = Vec::new();
+    for (id, tilemap, global_transform) in query.iter_mut() {
+        alive.push(id);
+
+        // recreate everything if the layout or the atlas changed
+        let stale = cache.tilemaps.get(&id).is_none_or(|prepared| {
+            prepared.chunk_size != tilemap.chunk_size()
+                || prepared.chunks.len() != tilemap.chunks_len()
+                || prepared.atlas != tilemap.atlas.image
+        });
+
+        if stale {
+            bind_groups.remove_by_entity(id, tilemap);
+
+            let chunks = (0..tilemap.chunks_len())
+                .map(|i| PreparedChunk {
+                    buffer: create_chunk_buffer(tilemap, i, &device),
+                    version: tilemap.chunk_version(i),
+                })
+                .collect();
+
+            cache.tilemaps.insert(
+                id,
+                PreparedTilemap {
+                    chunk_size: tilemap.chunk_size(),
+                    atlas: tilemap.atlas.image.clone(),
+                    model: Default::default(),
+                    chunks,
+                    visible: HashMap::new(),
+                },
+            );
+        }
+
+        let prepared = cache
+            .tilemaps
+            .get_mut(&id)
+            .expect("Tilemap should be prepared");
+        prepared.model = global_transform.matrix.to_cols_array_2d();
+        prepared.visible.clear();
+
+        for (i, chunk) in prepared.chunks.iter_mut().enumerate() {
+            // upload changed chunks
+            let version = tilemap.chunk_version(i);
+            if chunk.version != version {
+                let vertices = tilemap.chunk_vertices(i);
+                let vertex_buffer = chunk
+                    .buffer
+                    .vertex
+                    .as_ref()
+                    .expect("Tilemap chunk should have a vertex buffer");
+
+                belt.write(vertex_buffer, 0, bytemuck::cast_slice(&vertices), &device);
+                chunk.version = version;
+            }
+
+            // cull the chunk for every camera
+            let bounds = tilemap
+                .chunk_bounds(i)
+                .to_world_space(&global_transform.matrix);
+            for (camera_id, frustum) in &cameras {
+                if culling
+                    && let Some(frustum) = frustum
+                    && !frustum.intersects(&WorldBoundingVolume::AABB(bounds.clone()))
+                {
+                    continue;
+                }
+
+                prepared.visible.entry(*camera_id).or_default().push(i);
+            }
+        }
+    }
+
+    // drop despawned tilemaps
+    cache.tilemaps.retain(|id, _| alive.contains(id));
+}
+
+/// Startup system to register the tilemap graph node
+pub(crate) fn register_tilemap_graph(
+    graph: &mut RenderGraph,
+    device: Res<RenderDevice>,
+    surface_config: Res<RenderSurfaceConfiguration>,
+    mut shader_loader: ResMut<ShaderLoader>,
+) {
+    let pipeline_builder =
+        create_tilemap_pipeline_builder(&device, &surface_config, &mut shader_loader);
+
+    let node = GraphNodeBuilder::new("tilemap")
+        .set_pipeline(pipeline_builder)
+        .set_custom_system(tilemap_render_system)
+        .set_color_target(NodeColorTarget::Surface)
+        .set_depth_target(NodeDepthTarget::Node("main".to_string()))
+        .run_after("main")
+        .run_before("ui_image")
+        .build();
+
+    graph.add(node);
+}
+
+/// Tilemap graph node rendering system, draws every visible chunk of every tilemap
+fn tilemap_render_system(
+    graph_ctx: Res<RenderContext>,
+
+    world: &mut World,
+    encoder: &mut RenderCommandEncoder,
+    mut bind_groups: ResMut<RenderAssets<BindGroup>>,
+    cache: Res<TilemapRenderCache>,
+
+    mut tilemap_query: Query<&Tilemap, Without<Hidden>>,
+    mut camera_query: Query<
+        (EntityId, &Camera),
+        (With<Transform>, With<Projection>, With<Camera3D>),
+    >,
+    rendered: Res<RenderedWindow>,
+) {
+    if cache.visible_chunks() == 0 {
+        return;
+    }
+
+    let cameras = Camera::rendering_to(camera_query.iter_mut(), &rendered);
+    if cameras.is_empty() {
+        return;
+    }
+
+    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("tilemap render pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: unsafe {
+                &*graph_ctx
+                    .color_target
+                    .expect("tilemap color target is None")
+            },
+            depth_slice: None,
+            resolve_target: graph_ctx.resolve_target.map(|view| unsafe { &*view }),
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+            view: unsafe {
+                &*graph_ctx
+                    .depth_target
+                    .expect("tilemap depth target is None")
+            },
+            depth_ops: Some(wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: wgpu::StoreOp::Store,
+            }),
+            stencil_ops: None,
+        }),
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    });
+
+    render_pass.set_pipeline(
+        unsafe { &*graph_ctx.node }
+            .data
+            .pipeline
+            .as_ref()
+            .expect("Pipeline should have been generated by now")
+            .render_pipeline(),
+    );
+
+    let target_size = surface_size(world);
+    let mut draw_calls = 0;
+    for (camera_id, camera) in cameras {
+        if !camera.set_render_pass_camera(
+            camera_id,
+            &mut render_pass,
+            1,
+            target_size,
+            &mut bind_groups,
+            world,
+        ) {
+            continue;
+        }
+
+        for (id, prepared) in &cache.tilemaps {
+            let Some(visible) = prepared.visible.get(&camera_id).filter(|v| !v.is_empty()) else {
+                continue;
+            };
+
+            let Some(tilemap) = tilemap_query.get(*id) else {
+                continue;
+            };
+
+            let atlas_bind_group = bind_groups.get_by_entity(*id, tilemap, world);
+            render_pass.set_bind_group(0, &*atlas_bind_group, &[]);
+            render_pass.set_push_constants(
+                wgpu::ShaderStages::VERTEX,
+                0,
+                bytemuck::cast_slice(&prepared.model),
+            );
+
+            for &i in visible {
+                let buffer = &prepared.chunks[i].buffer;
+                let (Some(vertex_buffer), Some(index_buffer)) = (&buffer.vertex, &buffer.index)
+                else {
+                    continue;
+                };
+
+                render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+                render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                render_pass.draw_indexed(0..buffer.num_indices, 0, 0..1);
+                draw_calls += 1;
+            }
+        }
+    }
+
+    graph_ctx.record_draw_calls(draw_calls);
+}
+
+fn create_tilemap_pipeline_builder(
+    device: &RenderDevice,
+    surface_config: &RenderSurfaceConfiguration,
+    shader_loader: &mut ShaderLoader,
+) -> PipelineBuilder {
+    // Atlas bind group layout for texture and sampler
+    let atlas_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("tilemap_atlas_bind_group_layout"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ],
+    });
+
+    // Camera bind group layout for uniform buffer
+    let camera_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("camera_bind_group_layout"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+    });
+
+    // Load shader modules
+    shader_loader.load("tilemap", include_str!("../shaders/tilemap.wgsl"), device);
+
+    // Tilemaps are visible from both sides
+    let mut primitive_state = PipelineBuilder::default_primitive_state();
+    primitive_state.cull_mode = None;
+
+    Pipeline::build("tilemap_pipeline")
+        .set_bind_group_layouts(vec![atlas_layout, camera_layout])
+        .set_vertex_buffer_layouts(vec![TileVertex::vertex_descriptor()])
+        .set_vertex_shader("tilemap", "vs_main")
+        .set_fragment_shader("tilemap", "fs_main")
+        .add_color_format(surface_config.format)
+        .set_depth_format(wgpu::TextureFormat::Depth32Float)
+        .set_primitive_state(primitive_state)
+        .set_push_constant_ranges(vec![wgpu::PushConstantRange {
+            stages: wgpu::ShaderStages::VERTEX,
+            range: 0..64,
+        }])
+}
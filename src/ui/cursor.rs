@@ -0,0 +1,189 @@
+//! Cursor icon driven by the hovered UI node.
+//!
+//! While the cursor is over a node, its icon is picked from the node and its UI ancestors:
+//! 1. [`Node::cursor`] of the closest node which sets it, or resize arrows if the cursor is on one
+//!    of the [`ResizeEdges`] of the node
+//! 2. [`CursorIcon::Pointer`] if the node or one of its ancestors is a [`Button`]
+//! 3. [`CursorIcon::Text`] if the node has [`Text`]
+//!
+//! Otherwise the icon of the [`WindowConfig`] is used. The icon isn't changed while the window
+//! has a custom cursor.
+//!
+//! ```ignore
+//! commands
+//!     .spawn_empty()
+//!     .insert(Node {
+//!         cursor: Some(CursorIcon::Grab),
+//!         ..Default::default()
+//!     })
+//!     .insert(ResizeEdges::all(6.0));
+//! ```
+
+use std::collections::HashMap;
+
+use glam::Vec2;
+
+use crate::{
+    math::Rect,
+    prelude::*,
+    renderer::newtype::RenderWindow,
+    ui::{interactivity::padding_box, prelude::*},
+    window::config::{Cursor, CursorIcon, WindowConfig},
+};
+
+/// Edges of a node which show resize arrows when the cursor is on them. The node isn't resized,
+/// that's left to the [`UiEvent`] handlers of the node.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct ResizeEdges {
+    pub left: bool,
+    pub right: bool,
+    pub top: bool,
+    pub bottom: bool,
+    /// Width of the area along the edges inside the node, in physical pixels
+    pub thickness: f32,
+}
+
+impl Default for ResizeEdges {
+    fn default() -> Self {
+        Self::all(4.0)
+    }
+}
+
+impl ResizeEdges {
+    /// All edges are resizable
+    pub fn all(thickness: f32) -> Self {
+        Self {
+            left: true,
+            right: true,
+            top: true,
+            bottom: true,
+            thickness,
+        }
+    }
+
+    /// Only the left and right edges are resizable
+    pub fn horizontal(thickness: f32) -> Self {
+        Self {
+            top: false,
+            bottom: false,
+            ..Self::all(thickness)
+        }
+    }
+
+    /// Only the top and bottom edges are resizable
+    pub fn vertical(thickness: f32) -> Self {
+        Self {
+            left: false,
+            right: false,
+            ..Self::all(thickness)
+        }
+    }
+
+    /// Returns the resize icon of the edge or corner of `rect` under `position`, if there is one
+    pub fn icon_at(&self, rect: Rect, position: Vec2) -> Option<CursorIcon> {
+        if !rect.contains(position) {
+            return None;
+        }
+
+        let left = self.left && position.x <= rect.min.x + self.thickness;
+        let right = self.right && position.x >= rect.max.x - self.thickness;
+        let top = self.top && position.y <= rect.min.y + self.thickness;
+        let bottom = self.bottom && position.y >= rect.max.y - self.thickness;
+
+        match (left || right, top || bottom) {
+            (true, true) if (left && top) || (right && bottom) => Some(CursorIcon::NwseResize),
+            (true, true) => Some(CursorIcon::NeswResize),
+            (true, false) => Some(CursorIcon::EwResize),
+            (false, true) => Some(CursorIcon::NsResize),
+            (false, false) => None,
+        }
+    }
+}
+
+/// System to set the window cursor icon from the hovered UI node. For more information, see the
+/// [cursor module](crate::ui::cursor).
+pub fn update_ui_cursor(
+    pointer: Res<UiPointer>,
+    window: Res<Window>,
+    render_window: Res<RenderWindow>,
+    config: Option<Res<WindowConfig>>,
+    mut current: Local<Option<CursorIcon>>,
+    mut query: Query<(
+        EntityId,
+        &Node,
+        &ComputedNode,
+        &GlobalTransform,
+        Option<&Parent>,
+        Option<&Button>,
+        Option<&Text>,
+        Option<&ResizeEdges>,
+    )>,
+) {
+    let default = match config.as_ref().map(|config| &config.cursor) {
+        Some(Cursor::Icon(icon)) => *icon,
+        None => CursorIcon::default(),
+        // custom cursors can only be created with the event loop, so they can't be restored
+        Some(Cursor::Custom(_)) => return,
+    };
+
+    let icon = match (pointer.hovered(), window.cursor_position()) {
+        (Some(hovered), Some(position)) => {
+            hovered_icon(hovered, position, &mut query).unwrap_or(default)
+        }
+        _ => default,
+    };
+
+    if *current != Some(icon) {
+        *current = Some(icon);
+        render_window.set_cursor(icon);
+    }
+}
+
+/// Returns the icon picked by `hovered` and its ancestors, None for the default icon
+fn hovered_icon(
+    hovered: EntityId,
+    position: Vec2,
+    query: &mut Query<(
+        EntityId,
+        &Node,
+        &ComputedNode,
+        &GlobalTransform,
+        Option<&Parent>,
+        Option<&Button>,
+        Option<&Text>,
+        Option<&ResizeEdges>,
+    )>,
+) -> Option<CursorIcon> {
+    let nodes = query
+        .iter_mut()
+        .into_iter()
+        .map(|node| (node.0, node))
+        .collect::<HashMap<_, _>>();
+
+    // hovered node and its UI ancestors, up to the root
+    let mut path = Vec::new();
+    let mut current = nodes.get(&hovered);
+    while let Some(node) = current {
+        path.push(node);
+        current = node.4.and_then(|parent| nodes.get(&parent.id));
+    }
+
+    let explicit = path
+        .iter()
+        .find_map(|(_, node, computed, global_transform, _, _, _, edges)| {
+            let edge = edges
+                .and_then(|edges| edges.icon_at(padding_box(computed, global_transform), position));
+            edge.or(node.cursor)
+        });
+    if explicit.is_some() {
+        return explicit;
+    }
+
+    if path.iter().any(|node| node.5.is_some()) {
+        return Some(CursorIcon::Pointer);
+    }
+
+    path.first()
+        .and_then(|node| node.6)
+        .map(|_| CursorIcon::Text)
+}
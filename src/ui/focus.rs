@@ -0,0 +1,324 @@
+//! Focus navigation of UI nodes, for controller friendly menus.
+//!
+//! Nodes with [`Focusable`] can hold the focus, which is stored in the [`UiFocus`] resource. A
+//! [`UiNavigation::Move`] event moves the focus to the closest visible focusable node in its
+//! direction, measured between the centers of their padding boxes. [`UiNavigation::Activate`]
+//! dispatches a left [`UiEventKind::Click`] to the focused node, so buttons react the same as when
+//! clicked with the mouse. A focused [`Button`] is in the [`Interaction::Hover`] state until the
+//! mouse moves.
+//!
+//! Gamepads navigate out of the box, the D-pad and left stick move the focus and the south button
+//! activates, see [`ui_gamepad_navigation_system`]. Holding a direction repeats the move after the
+//! [`UiNavigationRepeat`] delay. The arrow keys and `Enter` are mapped as well, see
+//! [`ui_keyboard_navigation_system`]. Other bindings drive the focus by writing the same events,
+//! e.g. from an [`ActionMap`].
+//!
+//! ```ignore
+//! fn vim_navigation(keys: Res<Input<KeyCode>>, mut navigation: EventWriter<UiNavigation>) {
+//!     let bindings = [
+//!         (KeyCode::KeyK, UiNavigation::Move(NavDirection::Up)),
+//!         (KeyCode::KeyJ, UiNavigation::Move(NavDirection::Down)),
+//!         (KeyCode::KeyH, UiNavigation::Move(NavDirection::Left)),
+//!         (KeyCode::KeyL, UiNavigation::Move(NavDirection::Right)),
+//!         (KeyCode::Space, UiNavigation::Activate),
+//!     ];
+//!     for (key, nav) in bindings {
+//!         if keys.just_pressed(key) {
+//!             navigation.write(nav);
+//!         }
+//!     }
+//! }
+//! ```
+
+use std::collections::HashMap;
+
+use glam::Vec2;
+use winit::event::MouseButton;
+
+use crate::{
+    event::{EventReader, EventWriter},
+    prelude::*,
+    ui::{
+        interactivity::{dispatch_event, padding_box},
+        node::{VisibilityQuery, hidden_nodes},
+        prelude::*,
+        render_target::render_target_roots,
+    },
+};
+
+/// Marks an UI node which can hold the [`UiFocus`]
+#[derive(Component, Default, Debug, Clone, Copy)]
+pub struct Focusable;
+
+/// Direction of a [`UiNavigation::Move`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NavDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl NavDirection {
+    /// Returns the unit vector of the direction, with y pointing down like the UI
+    pub fn as_vec2(self) -> Vec2 {
+        match self {
+            Self::Up => Vec2::NEG_Y,
+            Self::Down => Vec2::Y,
+            Self::Left => Vec2::NEG_X,
+            Self::Right => Vec2::X,
+        }
+    }
+}
+
+/// Navigation input of the [`UiFocus`], sent from keyboard or gamepad input. For more
+/// information, see the [focus module](crate::ui::focus).
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UiNavigation {
+    /// Move the focus to the closest node in the direction, or focus the top left node if no node
+    /// is focused
+    Move(NavDirection),
+    /// Click the focused node
+    Activate,
+}
+
+/// Timing of repeated [`UiNavigation::Move`] events while a gamepad direction is held, in seconds.
+/// Used as a resource.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct UiNavigationRepeat {
+    /// Time a direction is held before it starts repeating
+    pub delay: f32,
+    /// Time between repeated moves
+    pub interval: f32,
+}
+
+impl Default for UiNavigationRepeat {
+    fn default() -> Self {
+        Self {
+            delay: 0.4,
+            interval: 0.12,
+        }
+    }
+}
+
+/// Currently focused [`Focusable`] node. Used as a resource.
+#[derive(Resource, Default, Debug)]
+pub struct UiFocus {
+    focused: Option<EntityId>,
+}
+
+impl UiFocus {
+    /// Returns the focused node
+    #[inline]
+    pub fn focused(&self) -> Option<EntityId> {
+        self.focused
+    }
+
+    /// Focus `node`, or clear the focus with `None`
+    #[inline]
+    pub fn set(&mut self, node: Option<EntityId>) {
+        self.focused = node;
+    }
+}
+
+/// System to write [`UiNavigation`] events for the D-pad, left stick and south button of gamepads,
+/// runs in the First phase before [`ui_focus_navigation_system`]
+pub fn ui_gamepad_navigation_system(
+    time: Res<Time>,
+    buttons: Res<Input<GamepadButton>>,
+    axes: Res<GamepadAxes>,
+    repeat: Res<UiNavigationRepeat>,
+    mut held: Local<(Option<NavDirection>, f32)>,
+    mut navigation: EventWriter<UiNavigation>,
+) {
+    if buttons.just_pressed(GamepadButton::South) {
+        navigation.write(UiNavigation::Activate);
+    }
+
+    let dpad = [
+        (GamepadButton::DPadUp, NavDirection::Up),
+        (GamepadButton::DPadDown, NavDirection::Down),
+        (GamepadButton::DPadLeft, NavDirection::Left),
+        (GamepadButton::DPadRight, NavDirection::Right),
+    ];
+    let direction = dpad
+        .into_iter()
+        .find(|(button, _)| buttons.pressed(*button))
+        .map(|(_, direction)| direction)
+        .or_else(|| stick_direction(axes.left_stick()));
+
+    // held direction and the time until it's repeated
+    let (held_direction, remaining) = &mut *held;
+    if direction != *held_direction {
+        *held_direction = direction;
+        *remaining = repeat.delay;
+        if let Some(direction) = direction {
+            navigation.write(UiNavigation::Move(direction));
+        }
+        return;
+    }
+
+    let Some(direction) = direction else {
+        return;
+    };
+    *remaining -= time.delta();
+    if *remaining <= 0.0 {
+        *remaining += repeat.interval;
+        navigation.write(UiNavigation::Move(direction));
+    }
+}
+
+/// Returns the direction the stick points to most, `None` if it's in the deadzone
+fn stick_direction(stick: Vec2) -> Option<NavDirection> {
+    if stick == Vec2::ZERO {
+        None
+    } else if stick.x.abs() > stick.y.abs() {
+        Some(if stick.x > 0.0 {
+            NavDirection::Right
+        } else {
+            NavDirection::Left
+        })
+    } else {
+        // stick y points up, unlike the UI
+        Some(if stick.y > 0.0 {
+            NavDirection::Up
+        } else {
+            NavDirection::Down
+        })
+    }
+}
+
+/// System to write [`UiNavigation`] events for the arrow keys and `Enter`, runs in the First phase
+/// before [`ui_focus_navigation_system`]
+pub fn ui_keyboard_navigation_system(
+    keys: Res<Input<KeyCode>>,
+    mut navigation: EventWriter<UiNavigation>,
+) {
+    let bindings = [
+        (KeyCode::ArrowUp, UiNavigation::Move(NavDirection::Up)),
+        (KeyCode::ArrowDown, UiNavigation::Move(NavDirection::Down)),
+        (KeyCode::ArrowLeft, UiNavigation::Move(NavDirection::Left)),
+        (KeyCode::ArrowRight, UiNavigation::Move(NavDirection::Right)),
+        (KeyCode::Enter, UiNavigation::Activate),
+        (KeyCode::NumpadEnter, UiNavigation::Activate),
+    ];
+    for (key, nav) in bindings {
+        if keys.just_pressed(key) {
+            navigation.write(nav);
+        }
+    }
+}
+
+/// System to move the [`UiFocus`] and activate the focused node by [`UiNavigation`] events, runs
+/// in the First phase after the mouse [`UiEvent`]s are dispatched
+pub fn ui_focus_navigation_system(
+    mut commands: Commands,
+    mut focus: ResMut<UiFocus>,
+    mut navigation: EventReader<UiNavigation>,
+    mut writer: EventWriter<UiEvent>,
+    mut query: Query<(EntityId, &ComputedNode, &GlobalTransform), With<Focusable>>,
+    mut listeners: Query<&UiEventListener>,
+    mut visibility_query: VisibilityQuery,
+) {
+    let navigation = navigation.read().copied().collect::<Vec<_>>();
+    if navigation.is_empty() {
+        return;
+    }
+
+    // nodes drawn into render targets are not navigated to
+    let mut hidden = hidden_nodes(&mut visibility_query);
+    hidden.extend(render_target_roots(&mut visibility_query).into_keys());
+    let centers = query
+        .iter_mut()
+        .into_iter()
+        .filter(|(id, ..)| !hidden.contains(id))
+        .map(|(id, computed, global_transform)| {
+            (id, padding_box(computed, global_transform).center())
+        })
+        .collect::<HashMap<_, _>>();
+
+    // hidden or despawned nodes lose the focus
+    let previous = focus.focused;
+    let mut focused = previous.filter(|id| centers.contains_key(id));
+
+    for nav in navigation {
+        match nav {
+            UiNavigation::Move(direction) => {
+                focused = match focused {
+                    Some(current) => closest_in_direction(current, direction, &centers),
+                    None => top_left(&centers),
+                }
+                .or(focused);
+            }
+            UiNavigation::Activate => {
+                let Some(target) = focused else {
+                    continue;
+                };
+                let parents = visibility_query
+                    .iter_mut()
+                    .into_iter()
+                    .map(|(id, _, _, parent)| (id, parent.map(|parent| parent.id)))
+                    .collect::<HashMap<_, _>>();
+                let event = UiEvent::new(
+                    UiEventKind::Click,
+                    Some(MouseButton::Left),
+                    target,
+                    centers[&target],
+                );
+                dispatch_event(event, &parents, &mut listeners, &mut commands, &mut writer);
+            }
+        }
+    }
+
+    if focused == previous {
+        return;
+    }
+    focus.focused = focused;
+
+    // the focused button is shown as hovered
+    let mut interaction_query = query.cast::<&mut Interaction, ()>();
+    if let Some(interaction) = previous.and_then(|id| interaction_query.get(id))
+        && *interaction == Interaction::Hover
+    {
+        *interaction = Interaction::None;
+    }
+    if let Some(interaction) = focused.and_then(|id| interaction_query.get(id))
+        && *interaction == Interaction::None
+    {
+        *interaction = Interaction::Hover;
+    }
+}
+
+/// Returns the node closest to `current` in `direction`, nodes off to the side are weighted as
+/// further away so the focus moves in a straight line when possible
+fn closest_in_direction(
+    current: EntityId,
+    direction: NavDirection,
+    centers: &HashMap<EntityId, Vec2>,
+) -> Option<EntityId> {
+    let origin = centers[&current];
+    let direction = direction.as_vec2();
+
+    centers
+        .iter()
+        .filter(|(id, _)| **id != current)
+        .filter_map(|(id, center)| {
+            let offset = *center - origin;
+            let along = offset.dot(direction);
+            if along <= 0.0 {
+                return None;
+            }
+            let across = offset.perp_dot(direction).abs();
+            Some((*id, along + across * 2.0))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(id, _)| id)
+}
+
+/// Returns the top left node, the first one focused
+fn top_left(centers: &HashMap<EntityId, Vec2>) -> Option<EntityId> {
+    centers
+        .iter()
+        .min_by(|(_, a), (_, b)| a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x)))
+        .map(|(id, _)| *id)
+}
@@ -4,7 +4,8 @@ use crate::event::EventReader;
 use crate::prelude::*;
 use crate::render_assets::RenderAssetEntry;
 use crate::ui::image::UiImage;
-use crate::ui::node::{ComputedNode, Node};
+use crate::ui::node::{ComputedNode, ComputedUiRect, Node, UiVisibility};
+use crate::ui::render_target::UiRenderTarget;
 use crate::ui::text::{Text, TextBuffer};
 
 use super::update::has_resized;
@@ -15,10 +16,13 @@ pub struct TempNode<'a> {
     pub computed: &'a mut ComputedNode,
     pub transform: &'a mut Transform,
     pub children: Vec<TempNode<'a>>,
+    /// Safe area insets added to the padding, only set on window root nodes with
+    /// [`Node::safe_area`]
+    pub safe_area: ComputedUiRect,
 
-    pub text: Option<&'a mut Text>,
-    /// Uninitialized when building the temp graph, will be populated in `resolve_z_index` when
-    /// recreating the [`text buffer`](crate::ui::text::TextBuffer) with the correct z-index
+    pub text: Option<&'a Text>,
+    /// Uninitialized when building the temp graph, will be populated in `resolve_text_buffers`
+    /// with the cached [`text buffer`](crate::ui::text::TextBuffer)
     pub text_rae: Option<RenderAssetEntry<TextBuffer>>,
 }
 
@@ -32,34 +36,49 @@ impl Debug for TempNode<'_> {
 }
 
 /// Returns temp nodes with populated children, or empty if zero nodes were updated.
-/// Runs on `Changed<Node | Text | UiImage | Transform>` filters, or `WindowEvent::Resized` event
+/// Runs on `Changed<Node | Text | UiImage | Transform>` filters, `WindowEvent::Resized` event,
+/// when a node is collapsed or uncollapsed with [`UiVisibility`], or when `relayout` is set.
+///
+/// Collapsed nodes are left out of the graph, so they don't take up space.
 pub fn nodes_to_temp_graph<'a>(
-    window_events: EventReader<WindowEvent>,
-    q: &mut Query<()>
+    mut window_events: EventReader<WindowEvent>,
+    q: &mut Query<()>,
+    relayout: bool,
 ) -> Vec<TempNode<'a>> {
     let mut check_updated = q.cast::<
         EntityId,
         (
             With<Node>, With<ComputedNode>, 
-            Or<(Changed<Node>, Changed<Text>, Changed<UiImage>, Changed<Transform>)>
+            Or<(Changed<Node>, Changed<Text>, Changed<UiImage>, Changed<Transform>, Changed<UiRenderTarget>)>
         )
     >();
 
+    // hiding a node doesn't change the layout, only collapsing does
+    let collapse_changed = q
+        .cast::<(&UiVisibility, &ComputedNode), (With<Node>, Changed<UiVisibility>)>()
+        .iter_mut()
+        .into_iter()
+        .any(|(visibility, computed)| {
+            (*visibility == UiVisibility::Collapsed) != computed.collapsed
+        });
+
     // if zero nodes where updated and window has not been resized,
     // do not run and return empty
-    if check_updated.iter_mut().is_empty() && !has_resized(&window_events) {
+    // read every run, so old resize events don't cause another layout later
+    let resized = has_resized(&mut window_events);
+    if check_updated.iter_mut().is_empty() && !resized && !collapse_changed && !relayout {
         return Vec::new();
     }
 
     // TODO: add other node types as options, like Image, Button, etc.
     let mut root_query = q.cast::<
-        (EntityId, &Node, &mut ComputedNode, &mut Transform, Option<&Children>, Option<&Parent>, Option<&mut Text>), 
+        (EntityId, &Node, &mut ComputedNode, &mut Transform, Option<&Children>, Option<&Parent>, Option<&Text>, Option<&UiVisibility>), 
         ()
     >();
     
     // populate with root nodes
     let mut root_nodes = Vec::new();
-    for (id, node, computed, transform, children, parent, text) in root_query.iter_mut() {
+    for (id, node, computed, transform, children, parent, text, visibility) in root_query.iter_mut() {
         if let Some(parent) = parent {
             // populate only with root nodes that have nonui parents
             if q.cast::<&Node, ()>().get(parent.id).is_some() {
@@ -67,12 +86,18 @@ pub fn nodes_to_temp_graph<'a>(
             }
         }
 
+        computed.collapsed = visibility == Some(&UiVisibility::Collapsed);
+        if computed.collapsed {
+            continue;
+        }
+
         let mut root = TempNode {
             id,
             node,
             computed,
             transform,
             children: Vec::new(),
+            safe_area: ComputedUiRect::default(),
 
             text,
             text_rae: None,
@@ -80,9 +105,8 @@ pub fn nodes_to_temp_graph<'a>(
 
         // populate with children
         if let Some(children) = children {
-            for child in &children.ids {
-                root.children.push(build_temp_node_for(*child, q))
-            }
+            root.children
+                .extend(children.ids.iter().filter_map(|child| build_temp_node_for(*child, q)));
         };
 
         root_nodes.push(root);
@@ -91,30 +115,38 @@ pub fn nodes_to_temp_graph<'a>(
     root_nodes
 }
 
-/// Returns a TempNode<'a> for a given EntityId, fully populated with children recursively
-fn build_temp_node_for<'a>(id: EntityId, query: &mut Query<()>) -> TempNode<'a> {
+/// Returns a TempNode<'a> for a given EntityId, fully populated with children recursively.
+/// Returns None if the node is collapsed.
+fn build_temp_node_for<'a>(id: EntityId, query: &mut Query<()>) -> Option<TempNode<'a>> {
     // root
-    let mut node_query = query.cast::<(&Node, &mut ComputedNode, &mut Transform, Option<&Children>, Option<&mut Text>), ()>();
-    let (node, computed, transform, children, text) = node_query.get(id).expect("Node not found");
+    let mut node_query = query.cast::<(&Node, &mut ComputedNode, &mut Transform, Option<&Children>, Option<&Text>, Option<&UiVisibility>), ()>();
+    let (node, computed, transform, children, text, visibility) = node_query.get(id).expect("Node not found");
+
+    // collapsed nodes keep their old computed values, they are not drawn anyway
+    if visibility == Some(&UiVisibility::Collapsed) {
+        computed.collapsed = true;
+        return None;
+    }
+
     // reset old computed
     *computed = ComputedNode::default();
 
     // children
     let mut built_children = Vec::new();
     if let Some(children) = children {
-        for child in &children.ids {
-            built_children.push(build_temp_node_for(*child, query))
-        }
+        built_children
+            .extend(children.ids.iter().filter_map(|child| build_temp_node_for(*child, query)));
     }
 
-    TempNode {
+    Some(TempNode {
         id,
         node,
         computed,
         transform,
         children: built_children,
+        safe_area: ComputedUiRect::default(),
 
         text,
         text_rae: None,
-    }
+    })
 }
@@ -20,71 +20,108 @@ pub fn compute_nodes_and_transforms(
     mut font_system: ResMut<FontSystem>,
     mut text_buffers: ResMut<RenderAssets<TextBuffer>>,
     window: Res<Window>,
+    safe_area: Option<Res<SafeAreaInsets>>,
 ) {
-    let mut root_temp_nodes = nodes_to_temp_graph(window_events, &mut q);
+    let safe_area = safe_area.map(|insets| (*insets, insets.has_changed()));
+    let relayout = safe_area.is_some_and(|(_, changed)| changed);
+    let mut root_temp_nodes = nodes_to_temp_graph(window_events, &mut q, relayout);
 
     if root_temp_nodes.is_empty() {
         return;
     }
 
-    resolve_z_index(world, &mut text_buffers, &mut root_temp_nodes, &mut 0);
+    resolve_z_index(&mut root_temp_nodes, &mut 0);
+    resolve_text_buffers(world, &mut font_system, &mut text_buffers, &mut root_temp_nodes);
 
-    let window_size = window.size();
-    let screen_width = window_size.width as f32;
-    let screen_height = window_size.height as f32;
+    // roots drawn into a render target are laid out in the size of its image
+    let images = world.resources.get::<Assets<Image>>();
+    let mut target_query = q.cast::<&UiRenderTarget, With<Node>>();
 
+    let window_size = window.size();
     for node in &mut root_temp_nodes {
-        node.measure_intrinsic_size(window_size);
-        node.compute_percent_size(screen_width, screen_height);
-        node.compute_auto_size();
-        node.compute_percent_size(screen_width, screen_height); // recompute after auto size
-
-        node.apply_constraints(window_size, None);
-        node.compute_gaps(window_size);
-        node.resolve_text_wrap(&mut font_system);
-        node.fit_auto_size();
-
-        node.resolve_flex();
-        node.recalculate_percent_size();
-        // TODO: wrap text after percent width change and readjust auto heights
-
-        node.compute_translation();
+        let size = match target_query.get(node.id) {
+            Some(target) => target.size(&images),
+            None => {
+                // only roots covering the window are inset
+                if node.node.safe_area
+                    && let Some((insets, _)) = safe_area
+                {
+                    node.safe_area = ComputedUiRect {
+                        left: insets.left,
+                        right: insets.right,
+                        top: insets.top,
+                        bottom: insets.bottom,
+                    };
+                }
+                None
+            }
+        };
+        node.compute_layout(size.unwrap_or(window_size), Some(&mut font_system));
     }
 }
 
 /// Sorts nodes by z_index and then computes the z_index with depth first search.
 /// Starts with layer 0, increments by 1 for each node.
 ///
-/// # Important
-/// When setting z_index on text, it will recreate the text buffer render asset with new metadata.
-fn resolve_z_index(
+/// Text buffers are not touched, their depth is looked up by metadata when preparing text areas.
+pub(super) fn resolve_z_index(nodes: &mut Vec<TempNode>, layer: &mut usize) {
+    nodes.sort_by(|a, b| a.node.z_index.cmp(&b.node.z_index));
+
+    for node in nodes {
+        node.computed.z_index = *layer as i32;
+        *layer += 1;
+
+        resolve_z_index(&mut node.children, layer);
+    }
+}
+
+/// Populates `text_rae` of every text node with its cached text buffer. The buffer is re-shaped
+/// only when the content, font or shaping changed, otherwise only its size is reset for measuring.
+fn resolve_text_buffers(
     world: &mut World,
+    font_system: &mut FontSystem,
     text_buffers: &mut RenderAssets<TextBuffer>,
-    nodes: &mut Vec<TempNode>,
-    layer: &mut usize,
+    nodes: &mut [TempNode],
 ) {
-    nodes.sort_by(|a, b| a.node.z_index.cmp(&b.node.z_index));
-
     for node in nodes {
-        if let Some(ref mut text) = node.text {
-            text.attrs.metadata = *layer + 1; // +1 to fix LessEqual depthmap issues 
-            
-
-            // simply remove the render asset, to recreate it with the new metadata, since buffer
-            // does not have a `set_attrs` method, bufferlines do, but it gets reset
-            text_buffers.remove_by_entity(node.id, &**text);
-            let text_rae = text_buffers.get_by_entity(node.id, &**text, world);
+        if let Some(text) = node.text {
+            let text_rae = text_buffers.get_by_entity(node.id, text, world);
+            text_rae.update(font_system, text);
             node.text_rae = Some(text_rae);
         }
 
-        node.computed.z_index = *layer as i32;
-        *layer += 1;
-
-        resolve_z_index(world, text_buffers, &mut node.children, layer);
+        resolve_text_buffers(world, font_system, text_buffers, &mut node.children);
     }
 }
 
 impl TempNode<'_> {
+    /// Runs every layout pass on a root node, computing the sizes and translations of the whole
+    /// tree. Text is wrapped only with a `font_system`.
+    pub(super) fn compute_layout(
+        &mut self,
+        window_size: PhysicalSize<u32>,
+        font_system: Option<&mut FontSystem>,
+    ) {
+        let screen_width = window_size.width as f32;
+        let screen_height = window_size.height as f32;
+
+        self.measure_intrinsic_size(window_size);
+        self.compute_percent_size(screen_width, screen_height);
+        self.compute_auto_size();
+        self.compute_percent_size(screen_width, screen_height); // recompute after auto size
+
+        self.apply_constraints(window_size, None);
+        self.compute_gaps(window_size);
+        self.resolve_text_wrap(font_system);
+        self.fit_auto_size();
+
+        self.resolve_flex();
+        self.recalculate_percent_size();
+        // TODO: wrap text after percent width change and readjust auto heights
+
+        self.compute_translation();
+    }
+
     /// Measures the intrinsic size of the node, and sets the computed content size
     /// Traversal: BOTTOM UP
     fn measure_intrinsic_size(&mut self, window_size: PhysicalSize<u32>) {
@@ -269,7 +306,7 @@ impl TempNode<'_> {
 
         let ws = window_size;
         let pcw = parent_content_width;
-        let padding = self.node.padding.compute_rect(pcw, ws);
+        let padding = self.node.padding.compute_rect(pcw, ws) + self.safe_area;
         let border = self.node.border.compute_rect(pcw, ws);
         let margin = self.node.margin.compute_rect(pcw, ws);
 
@@ -633,15 +670,16 @@ impl TempNode<'_> {
         (0..self.children.len()).map(|_| v).collect::<Vec<_>>()
     }
 
-    /// Resolves text wrapping and adjusts auto-sized elements
+    /// Resolves text wrapping and adjusts auto-sized elements, text is left as is without a
+    /// `font_system`
     /// Traversal: TOP DOWN
-    fn resolve_text_wrap(&mut self, font_system: &mut FontSystem) {
+    fn resolve_text_wrap(&mut self, mut font_system: Option<&mut FontSystem>) {
         if self.node.display == Display::None {
             return;
         }
 
         // wrap text
-        if let Some(ref mut rae) = self.text_rae {
+        if let (Some(rae), Some(font_system)) = (&mut self.text_rae, font_system.as_deref_mut()) {
             let max_width = self.computed.width.content;
             let prev_heigh = rae.height();
             rae.set_size(font_system, Some(max_width), None);
@@ -686,7 +724,7 @@ impl TempNode<'_> {
                 child.constrain_to_width();
             }
 
-            child.resolve_text_wrap(font_system);
+            child.resolve_text_wrap(font_system.as_deref_mut());
         }
     }
 
@@ -4,7 +4,7 @@ use crate::renderer::newtype::{RenderDevice, RenderSurfaceConfiguration};
 use crate::ui::image::render::ui_image_render_system;
 
 use super::pipeline::{create_ui_image_pipeline_builder, create_ui_pipeline_builder};
-use super::render::ui_render_system;
+use super::render::{ui_render_system, ui_target_render_system};
 
 /// Register graph UI node
 pub(crate) fn register_ui_graph(
@@ -15,9 +15,11 @@ pub(crate) fn register_ui_graph(
 ) {
     let ui_image_node = ui_image_node(&device, &surface_config, &mut shader_loader);
     let ui_node = ui_node(&device, &surface_config, &mut shader_loader);
+    let ui_target_node = ui_target_node(&device, &surface_config, &mut shader_loader);
 
     graph.add(ui_image_node);
     graph.add(ui_node);
+    graph.add(ui_target_node);
 }
 
 /// Create a graph UI node
@@ -71,3 +73,24 @@ fn ui_image_node(
         .run_after("main")
         .build()
 }
+
+/// Create a graph UI node for render targets
+fn ui_target_node(
+    device: &RenderDevice,
+    surface_config: &RenderSurfaceConfiguration,
+    shader_loader: &mut ShaderLoader,
+) -> GraphNode {
+    // Create pipeline builder
+    let ui_pipeline_builder =
+        create_ui_pipeline_builder(device, surface_config, shader_loader).set_label("ui_target");
+
+    // Targets are owned by the UI roots, the render system begins its own passes, before the
+    // images are sampled by the main pass
+    GraphNodeBuilder::new("ui_target")
+        .set_pipeline(ui_pipeline_builder)
+        .set_custom_system(ui_target_render_system)
+        .set_color_target(NodeColorTarget::None)
+        .set_depth_target(NodeDepthTarget::None)
+        .run_before("main")
+        .build()
+}
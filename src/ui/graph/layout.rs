@@ -0,0 +1,587 @@
+//! Layout of UI nodes without a world, used to test the layout passes and to measure nodes
+//! outside of the UI.
+//!
+//! Block and flex layouts are supported, text only with a font system, see
+//! [`compute_layout_with_text`]. Grid layouts are not implemented by the layout passes yet, trees
+//! with a grid node return [`LayoutError::GridUnsupported`].
+
+use std::fmt;
+
+use glyphon::FontSystem;
+use winit::dpi::PhysicalSize;
+
+use crate::{prelude::*, render_assets::RenderAssetEntry, ui::prelude::*};
+
+use super::{build_temp::TempNode, compute::resolve_z_index};
+
+/// Node tree for computing a layout without a world or a window, see [`compute_layout`]
+#[derive(Default, Debug, Clone)]
+pub struct LayoutNode {
+    pub node: Node,
+    pub children: Vec<LayoutNode>,
+    /// Text of the node, only measured by [`compute_layout_with_text`]
+    pub text: Option<Text>,
+}
+
+impl LayoutNode {
+    /// Create a new layout node without children
+    pub fn new(node: Node) -> Self {
+        Self {
+            node,
+            ..Default::default()
+        }
+    }
+
+    /// Returns self with `text`
+    #[must_use]
+    pub fn with_text(mut self, text: Text) -> Self {
+        self.text = Some(text);
+        self
+    }
+
+    /// Returns self with `child` added
+    #[must_use]
+    pub fn with_child(mut self, child: LayoutNode) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    /// Returns self with `children` added
+    #[must_use]
+    pub fn with_children(mut self, children: impl IntoIterator<Item = LayoutNode>) -> Self {
+        self.children.extend(children);
+        self
+    }
+
+    /// Returns the amount of nodes in the tree, including self
+    fn len(&self) -> usize {
+        1 + self.children.iter().map(LayoutNode::len).sum::<usize>()
+    }
+
+    /// Returns true if any node in the tree, including self, is a grid
+    fn has_grid(&self) -> bool {
+        self.node.display == Display::Grid || self.children.iter().any(LayoutNode::has_grid)
+    }
+}
+
+/// Computed layout of a [`LayoutNode`], its children are in the same order as the node's children
+#[derive(Debug, Clone)]
+pub struct LayoutRect {
+    /// Top left corner of the border box in screen space
+    pub position: Vec2,
+    /// Size of the border box
+    pub size: Vec2,
+    /// Every computed value of the node
+    pub computed: ComputedNode,
+    pub children: Vec<LayoutRect>,
+}
+
+impl LayoutRect {
+    /// Returns the bottom right corner of the border box
+    #[inline]
+    pub fn end(&self) -> Vec2 {
+        self.position + self.size
+    }
+}
+
+/// Error returned when a layout can't be computed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LayoutError {
+    /// A node has [`Display::Grid`], which the layout passes don't implement yet
+    GridUnsupported,
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::GridUnsupported => write!(f, "Grid layouts are not supported"),
+        }
+    }
+}
+
+impl std::error::Error for LayoutError {}
+
+/// Computes the layout of `roots` in a window of `window_size`, running the same passes as
+/// [`compute_nodes_and_transforms`](super::compute::compute_nodes_and_transforms). Returns one rect
+/// per root, in order.
+///
+/// Text is ignored, since it needs a font system to be measured, see [`compute_layout_with_text`].
+pub fn compute_layout(
+    roots: &[LayoutNode],
+    window_size: PhysicalSize<u32>,
+) -> Result<Vec<LayoutRect>, LayoutError> {
+    layout_roots(roots, window_size, None)
+}
+
+/// Same as [`compute_layout`], but the text of the nodes is shaped with `font_system` and wrapped
+/// to the width of its node.
+pub fn compute_layout_with_text(
+    roots: &[LayoutNode],
+    window_size: PhysicalSize<u32>,
+    font_system: &mut FontSystem,
+) -> Result<Vec<LayoutRect>, LayoutError> {
+    layout_roots(roots, window_size, Some(font_system))
+}
+
+/// Computes the layout of `roots`, text is shaped and wrapped only with a `font_system`
+fn layout_roots(
+    roots: &[LayoutNode],
+    window_size: PhysicalSize<u32>,
+    mut font_system: Option<&mut FontSystem>,
+) -> Result<Vec<LayoutRect>, LayoutError> {
+    if roots.iter().any(LayoutNode::has_grid) {
+        return Err(LayoutError::GridUnsupported);
+    }
+
+    let len = roots.iter().map(LayoutNode::len).sum();
+    let mut computed = vec![ComputedNode::default(); len];
+    let mut transforms = vec![Transform::default(); len];
+
+    {
+        // nodes borrow their computed values and transforms in depth first order
+        let mut slots = computed.iter_mut().zip(transforms.iter_mut());
+        let mut index = 0;
+        let mut temp_nodes = roots
+            .iter()
+            .map(|root| temp_node(root, &mut slots, &mut index))
+            .collect::<Vec<_>>();
+
+        resolve_z_index(&mut temp_nodes, &mut 0);
+        if let Some(font_system) = font_system.as_deref_mut() {
+            shape_text(&mut temp_nodes, font_system);
+        }
+        for node in &mut temp_nodes {
+            node.compute_layout(window_size, font_system.as_deref_mut());
+        }
+    }
+
+    let mut results = computed.into_iter().zip(transforms);
+    Ok(roots
+        .iter()
+        .map(|root| layout_rect(root, Vec2::ZERO, &mut results))
+        .collect())
+}
+
+/// Builds a temp node for `node` and its children, taking their slots in depth first order
+fn temp_node<'a>(
+    node: &'a LayoutNode,
+    slots: &mut impl Iterator<Item = (&'a mut ComputedNode, &'a mut Transform)>,
+    index: &mut u32,
+) -> TempNode<'a> {
+    let (computed, transform) = slots.next().expect("slot for every node");
+    let id = EntityId::new(*index, 0);
+    *index += 1;
+
+    TempNode {
+        id,
+        node: &node.node,
+        computed,
+        transform,
+        children: node
+            .children
+            .iter()
+            .map(|child| temp_node(child, slots, index))
+            .collect(),
+        safe_area: ComputedUiRect::default(),
+
+        text: node.text.as_ref(),
+        text_rae: None,
+    }
+}
+
+/// Populates `text_rae` of every text node with a newly shaped text buffer
+fn shape_text(nodes: &mut [TempNode], font_system: &mut FontSystem) {
+    for node in nodes {
+        if let Some(text) = node.text {
+            let buffer = TextBuffer::new(font_system, text);
+            node.text_rae = Some(RenderAssetEntry::new(buffer));
+        }
+
+        shape_text(&mut node.children, font_system);
+    }
+}
+
+/// Builds the rect of `node` and its children from the computed results in depth first order,
+/// translations are relative to the parent's border box
+fn layout_rect(
+    node: &LayoutNode,
+    parent_position: Vec2,
+    results: &mut impl Iterator<Item = (ComputedNode, Transform)>,
+) -> LayoutRect {
+    let (computed, transform) = results.next().expect("result for every node");
+    let position = parent_position + transform.translation.truncate();
+
+    LayoutRect {
+        position,
+        size: Vec2::new(computed.width.border, computed.height.border),
+        children: node
+            .children
+            .iter()
+            .map(|child| layout_rect(child, position, results))
+            .collect(),
+        computed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW: PhysicalSize<u32> = PhysicalSize::new(800, 600);
+
+    fn sized(width: Val, height: Val) -> LayoutNode {
+        LayoutNode::new(Node {
+            width,
+            height,
+            ..Default::default()
+        })
+    }
+
+    fn px(width: f32, height: f32) -> LayoutNode {
+        sized(Val::Px(width), Val::Px(height))
+    }
+
+    fn flex_row(node: Node) -> Node {
+        Node {
+            display: Display::Flex,
+            flex_direction: FlexDirection::Row,
+            ..node
+        }
+    }
+
+    fn layout(root: LayoutNode) -> LayoutRect {
+        compute_layout(&[root], WINDOW).unwrap().remove(0)
+    }
+
+    fn positions(rect: &LayoutRect) -> Vec<Vec2> {
+        rect.children.iter().map(|child| child.position).collect()
+    }
+
+    #[test]
+    fn block_stacks_children_vertically() {
+        let root = LayoutNode::new(Node {
+            width: Val::Px(200.0),
+            ..Default::default()
+        })
+        .with_children([px(100.0, 50.0), px(80.0, 30.0)]);
+
+        let rect = layout(root);
+        assert_eq!(rect.size, Vec2::new(200.0, 80.0));
+        assert_eq!(
+            positions(&rect),
+            [Vec2::new(0.0, 0.0), Vec2::new(0.0, 50.0)]
+        );
+    }
+
+    #[test]
+    fn percent_size_uses_window_for_roots() {
+        let rect = layout(sized(Val::Percent(50.0), Val::Percent(25.0)));
+        assert_eq!(rect.size, Vec2::new(400.0, 150.0));
+
+        let rect = layout(sized(Val::Vw(10.0), Val::Vh(10.0)));
+        assert_eq!(rect.size, Vec2::new(80.0, 60.0));
+    }
+
+    #[test]
+    fn padding_and_border_offset_children() {
+        let root = LayoutNode::new(Node {
+            width: Val::Px(200.0),
+            height: Val::Px(100.0),
+            padding: UiRect::all(Val::Px(10.0)),
+            border: UiRect::all(Val::Px(5.0)),
+            ..Default::default()
+        })
+        .with_child(sized(Val::Percent(100.0), Val::Px(20.0)));
+
+        let rect = layout(root);
+        assert_eq!(rect.size, Vec2::new(200.0, 100.0));
+        assert_eq!(rect.computed.width.content, 170.0);
+
+        let child = &rect.children[0];
+        assert_eq!(child.position, Vec2::new(15.0, 15.0));
+        assert_eq!(child.size, Vec2::new(170.0, 20.0));
+    }
+
+    #[test]
+    fn content_box_adds_padding_to_size() {
+        let rect = layout(LayoutNode::new(Node {
+            width: Val::Px(100.0),
+            height: Val::Px(50.0),
+            padding: UiRect::all(Val::Px(10.0)),
+            box_sizing: BoxSizing::ContentBox,
+            ..Default::default()
+        }));
+
+        assert_eq!(rect.size, Vec2::new(120.0, 70.0));
+        assert_eq!(rect.computed.width.content, 100.0);
+    }
+
+    #[test]
+    fn margin_offsets_node() {
+        let root = LayoutNode::new(Node {
+            width: Val::Px(200.0),
+            ..Default::default()
+        })
+        .with_children([
+            LayoutNode::new(Node {
+                width: Val::Px(50.0),
+                height: Val::Px(50.0),
+                margin: UiRect::vh(Val::Px(5.0), Val::Px(10.0)),
+                ..Default::default()
+            }),
+            px(50.0, 50.0),
+        ]);
+
+        let rect = layout(root);
+        assert_eq!(
+            positions(&rect),
+            [Vec2::new(10.0, 5.0), Vec2::new(0.0, 60.0)]
+        );
+    }
+
+    #[test]
+    fn min_max_constraints_clamp_size() {
+        let rect = layout(LayoutNode::new(Node {
+            width: Val::Px(500.0),
+            max_width: Val::Px(300.0),
+            height: Val::Px(10.0),
+            min_height: Val::Px(40.0),
+            ..Default::default()
+        }));
+
+        assert_eq!(rect.size, Vec2::new(300.0, 40.0));
+    }
+
+    #[test]
+    fn flex_row_places_children_with_gaps() {
+        let root = LayoutNode::new(flex_row(Node {
+            column_gap: Val::Px(10.0),
+            ..Default::default()
+        }))
+        .with_children([px(50.0, 20.0), px(50.0, 30.0), px(50.0, 20.0)]);
+
+        let rect = layout(root);
+        assert_eq!(rect.size, Vec2::new(170.0, 30.0));
+        assert_eq!(
+            positions(&rect),
+            [
+                Vec2::new(0.0, 0.0),
+                Vec2::new(60.0, 0.0),
+                Vec2::new(120.0, 0.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn flex_column_places_children_with_gaps() {
+        let root = LayoutNode::new(Node {
+            display: Display::Flex,
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(5.0),
+            ..Default::default()
+        })
+        .with_children([px(50.0, 20.0), px(50.0, 30.0)]);
+
+        let rect = layout(root);
+        assert_eq!(rect.size.y, 55.0);
+        assert_eq!(
+            positions(&rect),
+            [Vec2::new(0.0, 0.0), Vec2::new(0.0, 25.0)]
+        );
+    }
+
+    #[test]
+    fn flex_reverse_places_last_child_first() {
+        let root = LayoutNode::new(Node {
+            display: Display::Flex,
+            flex_direction: FlexDirection::ColumnReverse,
+            ..Default::default()
+        })
+        .with_children([px(50.0, 10.0), px(50.0, 20.0)]);
+
+        let rect = layout(root);
+        assert_eq!(
+            positions(&rect),
+            [Vec2::new(0.0, 20.0), Vec2::new(0.0, 0.0)]
+        );
+    }
+
+    #[test]
+    fn flex_justify_content_distributes_free_space() {
+        let cases = [
+            (JustifyContent::FlexStart, [0.0, 50.0]),
+            (JustifyContent::FlexEnd, [200.0, 250.0]),
+            (JustifyContent::Center, [100.0, 150.0]),
+            (JustifyContent::SpaceBetween, [0.0, 250.0]),
+            (JustifyContent::SpaceAround, [50.0, 200.0]),
+            (
+                JustifyContent::SpaceEvenly,
+                [200.0 / 3.0, 400.0 / 3.0 + 50.0],
+            ),
+        ];
+
+        for (justify_content, expected) in cases {
+            let root = LayoutNode::new(flex_row(Node {
+                width: Val::Px(300.0),
+                justify_content,
+                ..Default::default()
+            }))
+            .with_children([px(50.0, 10.0), px(50.0, 10.0)]);
+
+            let rect = layout(root);
+            let x = rect.children.iter().map(|child| child.position.x);
+            for (x, expected) in x.zip(expected) {
+                assert!(
+                    (x - expected).abs() < 1e-3,
+                    "{justify_content:?}: expected {expected}, got {x}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn flex_align_items_positions_cross_axis() {
+        let cases = [
+            (AlignItems::FlexStart, 0.0),
+            (AlignItems::Center, 40.0),
+            (AlignItems::FlexEnd, 80.0),
+        ];
+
+        for (align_items, expected) in cases {
+            let root = LayoutNode::new(flex_row(Node {
+                height: Val::Px(100.0),
+                align_items,
+                ..Default::default()
+            }))
+            .with_child(px(50.0, 20.0));
+
+            let rect = layout(root);
+            assert_eq!(rect.children[0].position.y, expected, "{align_items:?}");
+        }
+    }
+
+    #[test]
+    fn flex_stretch_fills_cross_axis() {
+        let root = LayoutNode::new(flex_row(Node {
+            width: Val::Px(300.0),
+            height: Val::Px(100.0),
+            ..Default::default()
+        }))
+        .with_children([sized(Val::Px(50.0), Val::Auto), px(50.0, 20.0)]);
+
+        let rect = layout(root);
+        assert_eq!(rect.children[0].size.y, 100.0);
+        assert_eq!(rect.children[1].size.y, 20.0);
+    }
+
+    #[test]
+    fn flex_row_shrinks_overflowing_children() {
+        let root = LayoutNode::new(flex_row(Node {
+            width: Val::Px(100.0),
+            ..Default::default()
+        }))
+        .with_children([px(80.0, 10.0), px(80.0, 10.0)]);
+
+        let rect = layout(root);
+        assert_eq!(rect.children[0].size.x, 50.0);
+        assert_eq!(
+            positions(&rect),
+            [Vec2::new(0.0, 0.0), Vec2::new(50.0, 0.0)]
+        );
+    }
+
+    #[test]
+    fn text_wraps_to_parent_width() {
+        let mut font_system = FontSystem::new();
+        // text can't be measured without any installed fonts
+        if font_system.db().faces().next().is_none() {
+            return;
+        }
+
+        let text = Text::new("the quick brown fox jumps over the lazy dog");
+        let mut layout_text = |width: f32| {
+            let root = LayoutNode::new(Node {
+                width: Val::Px(width),
+                ..Default::default()
+            })
+            .with_child(LayoutNode::default().with_text(text.clone()));
+            compute_layout_with_text(&[root], WINDOW, &mut font_system)
+                .unwrap()
+                .remove(0)
+        };
+
+        let line = layout_text(800.0);
+        let wrapped = layout_text(100.0);
+        assert!(line.children[0].size.x > 100.0);
+        assert!(wrapped.children[0].size.x <= 100.0);
+        assert!(wrapped.children[0].size.y > line.children[0].size.y);
+    }
+
+    #[test]
+    fn nested_positions_are_absolute() {
+        let root = LayoutNode::new(Node {
+            width: Val::Px(300.0),
+            padding: UiRect::all(Val::Px(10.0)),
+            ..Default::default()
+        })
+        .with_child(
+            LayoutNode::new(Node {
+                width: Val::Px(200.0),
+                padding: UiRect::all(Val::Px(5.0)),
+                margin: UiRect::top(Val::Px(20.0)),
+                ..Default::default()
+            })
+            .with_child(px(20.0, 20.0)),
+        );
+
+        let rect = layout(root);
+        let child = &rect.children[0];
+        assert_eq!(child.position, Vec2::new(10.0, 30.0));
+        assert_eq!(child.children[0].position, Vec2::new(15.0, 35.0));
+        assert_eq!(child.children[0].end(), Vec2::new(35.0, 55.0));
+    }
+
+    #[test]
+    fn display_none_takes_no_space() {
+        let rect = layout(
+            LayoutNode::new(Node {
+                display: Display::None,
+                ..Default::default()
+            })
+            .with_child(px(50.0, 50.0)),
+        );
+
+        assert_eq!(rect.size, Vec2::ZERO);
+    }
+
+    #[test]
+    fn roots_are_laid_out_independently() {
+        let rects = compute_layout(&[px(10.0, 10.0), px(20.0, 30.0)], WINDOW).unwrap();
+
+        assert_eq!(rects.len(), 2);
+        assert_eq!(rects[0].position, Vec2::ZERO);
+        assert_eq!(rects[1].position, Vec2::ZERO);
+        assert_eq!(rects[1].size, Vec2::new(20.0, 30.0));
+    }
+
+    #[test]
+    fn grid_returns_error() {
+        let grid = || {
+            LayoutNode::new(Node {
+                display: Display::Grid,
+                ..Default::default()
+            })
+        };
+
+        assert_eq!(
+            compute_layout(&[grid()], WINDOW).unwrap_err(),
+            LayoutError::GridUnsupported
+        );
+
+        let nested = px(100.0, 100.0).with_child(grid().with_child(px(10.0, 10.0)));
+        assert_eq!(
+            compute_layout(&[px(10.0, 10.0), nested], WINDOW).unwrap_err(),
+            LayoutError::GridUnsupported
+        );
+    }
+}
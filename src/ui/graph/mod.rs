@@ -2,6 +2,7 @@ mod pipeline;
 mod render;
 pub mod storage;
 pub mod compute;
+pub mod layout;
 pub mod update;
 mod build_temp;
 pub mod graph_nodes;
@@ -2,7 +2,7 @@ use crate::assets::ShaderLoader;
 use crate::prelude::*;
 use crate::render_assets::{Pipeline, pipeline::PipelineBuilder};
 use crate::renderer::newtype::{RenderDevice, RenderSurfaceConfiguration};
-use crate::ui::mesh::UiMesh;
+use crate::ui::{image::render::ImageConstants, mesh::UiMesh};
 
 pub fn create_ui_pipeline_builder(
     device: &RenderDevice,
@@ -63,7 +63,7 @@ pub fn create_ui_image_pipeline_builder(
 ) -> PipelineBuilder {
     let mut pipeline_builder = create_ui_pipeline_builder(device, surface_config, shader_loader);
 
-    // Image bind group layout for uniform buffer
+    // Image bind group layout, shared by all images of a texture atlas
     let image_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
         label: Some("image_bind_group_layout"),
         entries: &[
@@ -85,17 +85,6 @@ pub fn create_ui_image_pipeline_builder(
                 ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
                 count: None,
             },
-            // uniform
-            wgpu::BindGroupLayoutEntry {
-                binding: 2,
-                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    min_binding_size: None,
-                    has_dynamic_offset: false,
-                },
-                count: None,
-            },
         ],
     });
 
@@ -110,4 +99,8 @@ pub fn create_ui_image_pipeline_builder(
         .set_label("ui_image")
         .set_vertex_shader("ui", "vs_image")
         .set_fragment_shader("ui", "fs_image")
+        .set_push_constant_ranges(vec![wgpu::PushConstantRange {
+            stages: wgpu::ShaderStages::VERTEX,
+            range: 0..std::mem::size_of::<ImageConstants>() as u32,
+        }])
 }
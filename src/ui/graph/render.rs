@@ -7,6 +7,7 @@ use crate::prelude::*;
 use crate::render_assets::{BindGroup, Buffer, RenderAssets};
 use crate::renderer::newtype::RenderCommandEncoder;
 use crate::ui::mesh::{UiMesh, UiMeshTransparent};
+use crate::ui::render_target::UiRenderTargets;
 
 use super::storage::UiTransformStorage;
 
@@ -37,7 +38,13 @@ pub fn ui_render_system(
         (EntityId, &Camera),
         (With<Transform>, With<Projection>, With<Camera3D>),
     >,
+    rendered: Res<RenderedWindow>,
 ) {
+    // the UI is laid out for the primary window
+    if !rendered.primary {
+        return;
+    }
+
     let ui_mesh = buffers.get_by_resource(&ui_mesh, world, true);
     let ui_mesh_transparent = buffers.get_by_resource(&ui_mesh_transparent, world, true);
 
@@ -59,7 +66,7 @@ pub fn ui_render_system(
     let color_attachment = Some(wgpu::RenderPassColorAttachment {
         view: unsafe { &*graph_ctx.color_target.expect("ui color target is None") },
         depth_slice: None,
-        resolve_target: None,
+        resolve_target: graph_ctx.resolve_target.map(|view| unsafe { &*view }),
         ops: wgpu::Operations {
             load: wgpu::LoadOp::Load,
             store: wgpu::StoreOp::Store,
@@ -92,7 +99,7 @@ pub fn ui_render_system(
             timestamp_writes: None,
         });
 
-        draw_ui_render_pass(
+        let draw_calls = draw_ui_render_pass(
             &mut render_pass,
             pipeline,
             window.size(),
@@ -100,6 +107,7 @@ pub fn ui_render_system(
             &camera_bind_group,
             &ui_mesh,
         );
+        graph_ctx.record_draw_calls(draw_calls);
     } // necessary to drop render_pass before second pass, TODO: this may not be needed anymore
 
     // dont store depth for transparent objects
@@ -117,7 +125,7 @@ pub fn ui_render_system(
         timestamp_writes: None,
     });
 
-    draw_ui_render_pass(
+    let draw_calls = draw_ui_render_pass(
         &mut render_pass,
         pipeline,
         window.size(),
@@ -130,6 +138,141 @@ pub fn ui_render_system(
     text_renderer
         .render(&text_atlas, &viewport, &mut render_pass)
         .unwrap();
+
+    // text is drawn with a single instanced draw call
+    graph_ctx.record_draw_calls(draw_calls + 1);
+}
+
+/// Ui render target graph node rendering system, draws the UI of every
+/// [`UiRenderTarget`](crate::ui::render_target::UiRenderTarget) into its image
+pub fn ui_target_render_system(
+    graph_ctx: Res<RenderContext>,
+
+    world: &mut World,
+    encoder: &mut RenderCommandEncoder,
+
+    // resources
+    mut textures: ResMut<RenderAssets<Texture>>,
+    mut bind_groups: ResMut<RenderAssets<BindGroup>>,
+    text_atlas: Res<TextAtlas>,
+    ui_targets: Res<UiRenderTargets>,
+    ui_transforms: Res<UiTransformStorage>,
+
+    mut camera_query: Query<
+        (EntityId, &Camera),
+        (With<Transform>, With<Projection>, With<Camera3D>),
+    >,
+    rendered: Res<RenderedWindow>,
+) {
+    // images are drawn once per frame, with the primary window
+    if !rendered.primary {
+        return;
+    }
+
+    if ui_targets.targets.is_empty() {
+        return;
+    }
+
+    // find active camera
+    let Some((camera_id, camera)) = camera_query.iter_mut().into_iter().find(|(_, c)| c.active)
+    else {
+        return;
+    };
+    let camera_bind_group = bind_groups.get_by_entity(camera_id, camera, world);
+
+    let pipeline = unsafe { &*graph_ctx.node }
+        .data
+        .pipeline
+        .as_ref()
+        .expect("Pipeline should have been generated by now")
+        .render_pipeline();
+
+    let mut draw_calls = 0;
+    for prepared in ui_targets.targets.values() {
+        let (Some(buffer), Some(buffer_transparent)) =
+            (&prepared.buffer, &prepared.buffer_transparent)
+        else {
+            continue;
+        };
+        let target = textures.get_by_handle(&prepared.image, world);
+
+        // opaque render pass, clears the image
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("ui target opaque render pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &target.view,
+                    depth_slice: None,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(prepared.clear_color.into()),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &prepared.depth_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            draw_calls += draw_ui_render_pass(
+                &mut render_pass,
+                pipeline,
+                prepared.size,
+                ui_transforms.bind_group(),
+                &camera_bind_group,
+                buffer,
+            );
+        }
+
+        // transparent render pass, dont store depth for transparent objects
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("ui target transparent render pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &target.view,
+                depth_slice: None,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &prepared.depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Discard,
+                }),
+                stencil_ops: None,
+            }),
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+
+        draw_calls += draw_ui_render_pass(
+            &mut render_pass,
+            pipeline,
+            prepared.size,
+            ui_transforms.bind_group(),
+            &camera_bind_group,
+            buffer_transparent,
+        );
+
+        // render text
+        prepared
+            .text_renderer
+            .render(&text_atlas, &prepared.viewport, &mut render_pass)
+            .unwrap();
+        draw_calls += 1;
+    }
+
+    graph_ctx.record_draw_calls(draw_calls);
 }
 
 fn draw_ui_render_pass(
@@ -139,9 +282,9 @@ fn draw_ui_render_pass(
     ui_transforms_bind_group: &wgpu::BindGroup,
     camera_bind_group: &BindGroup,
     ui_mesh: &Buffer,
-) {
+) -> u32 {
     if ui_mesh.num_indices == 0 {
-        return;
+        return 0;
     }
 
     let vertex_buffer = ui_mesh
@@ -172,4 +315,5 @@ fn draw_ui_render_pass(
 
     // draw
     render_pass.draw_indexed(0..ui_mesh.num_indices, 0, 0..1);
+    1
 }
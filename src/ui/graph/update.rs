@@ -1,14 +1,25 @@
+use std::collections::HashMap;
+
 use glam::Vec2;
 use glyphon::{
-    FontSystem, Resolution, SwashCache, TextArea, TextAtlas, TextBounds, TextRenderer, Viewport,
+    Cache, FontSystem, Resolution, SwashCache, TextArea, TextAtlas, TextBounds, TextRenderer,
+    Viewport,
 };
 use winit::event::WindowEvent;
 
 use crate::event::EventReader;
 use crate::prelude::*;
-use crate::render_assets::RenderAssets;
+use crate::render_assets::{RenderAssets, StagingBelt};
 use crate::renderer::newtype::{RenderDevice, RenderQueue};
-use crate::ui::{graph::storage::UiTransformStorage, mesh::*, prelude::*, text::TextBuffer};
+use crate::ui::{
+    graph::storage::UiTransformStorage,
+    mesh::*,
+    node::{VisibilityQuery, hidden_nodes},
+    plugin::create_ui_text_renderer,
+    prelude::*,
+    render_target::{UiRenderTargets, render_target_roots},
+    text::TextBuffer,
+};
 
 /// System to update the glyphon text viewport resolution.
 /// Runs only if the window size has changed.
@@ -28,11 +39,10 @@ pub fn update_glyphon_viewport(
     )
 }
 
-/// Utility function to check for a window resize event.
-pub fn has_resized(window_events: &EventReader<WindowEvent>) -> bool {
+/// Utility function to check for a window resize event, marks the events as read.
+pub fn has_resized(window_events: &mut EventReader<WindowEvent>) -> bool {
     window_events
         .read()
-        .iter()
         .any(|event| matches!(event, WindowEvent::Resized(_)))
 }
 
@@ -62,16 +72,31 @@ fn clear_text_renderer(world: &mut World, device: &RenderDevice, queue: &RenderQ
 
 /// System to update the UI mesh and UI transform storage, runs only if some nodes have `Changed<Transform>` filter.
 /// The filter should return true after `compute_nodes_and_transforms` system which runs on `Changed<Node>` filter and updates transforms.
+/// It also runs on `Changed<UiVisibility>`, so hiding a node doesn't need a relayout.
+///
+/// Nodes which are hidden, collapsed or have `Display::None`, and all of their descendants, are
+/// left out of the mesh. Nodes whose border box is completely outside of the window are culled,
+/// they are left out of the mesh and their text is not prepared.
 ///
 /// # Resize
 /// It will run on window resize even if no nodes have changed. That is because glyphon text gets
 /// automatically clipped so we need to update prepared text areas.
 ///
+/// # Render targets
+/// Nodes under a root with [`UiRenderTarget`] are built into the meshes and text renderer of
+/// that target instead, and culled by the size of its image. It runs when a target is added,
+/// removed or its image is resized. [`UiImage`]s are not drawn into targets.
+///
+/// # Msaa
+/// The text renderer is recreated when the [`Msaa`] sample count changes, its pipeline has to
+/// match the UI pass. The text is prepared again even if no nodes have changed.
+///
 /// # Note
 /// Applies z-index to the z component of the global transfrom pushed to the transform storage.
 pub fn update_ui_mesh_and_transforms(
     world: &mut World,
-    window_events: EventReader<WindowEvent>,
+    mut window_events: EventReader<WindowEvent>,
+    mut text_samples: Local<u32>,
 
     mut changed_query: Query<
         EntityId,
@@ -83,6 +108,9 @@ pub fn update_ui_mesh_and_transforms(
             Changed<Transform>,
         ),
     >,
+    mut visibility_changed_query: Query<EntityId, (With<Node>, Changed<UiVisibility>)>,
+    mut visibility_query: VisibilityQuery,
+    mut target_query: Query<(EntityId, &UiRenderTarget), With<Node>>,
 
     mut nodes_query: Query<(
         EntityId,
@@ -100,16 +128,46 @@ pub fn update_ui_mesh_and_transforms(
     let mut ui_mesh_images = world.resources.get_mut::<UiMeshImages>();
     let device = world.resources.get::<RenderDevice>();
     let queue = world.resources.get::<RenderQueue>();
+    let mut belt = world.resources.get_mut::<StagingBelt>();
+    let mut ui_targets = world.resources.get_mut::<UiRenderTargets>();
+    let window_size = {
+        let size = world.resources.get::<Window>().size();
+        Vec2::new(size.width as f32, size.height as f32)
+    };
 
     // get the amount of changed nodes
-    let changed_len = changed_query.iter_mut().len();
+    let changed_len = changed_query.iter_mut().len() + visibility_changed_query.iter_mut().len();
+
+    // render targets with the size of their image, targets without an image are not drawn
+    let targets = {
+        let images = world.resources.get::<Assets<Image>>();
+        target_query
+            .iter_mut()
+            .into_iter()
+            .filter_map(|(id, target)| Some((id, (target.clone(), target.size(&images)?))))
+            .collect::<HashMap<_, _>>()
+    };
+    let targets_changed = ui_targets.is_outdated(&targets);
 
     // query all nodes
     let ui_nodes = nodes_query.iter_mut();
 
+    // recreate the text renderer with the current sample count
+    let samples = world
+        .resources
+        .try_get::<Msaa>()
+        .map_or(1, |msaa| msaa.samples());
+    let samples_changed = *text_samples != samples;
+    if samples_changed {
+        *text_samples = samples;
+        let mut text_atlas = world.resources.get_mut::<TextAtlas>();
+        *world.resources.get_mut::<TextRenderer>() =
+            create_ui_text_renderer(&mut text_atlas, &device, samples);
+    }
+
     // return if nothing changed
-    let resized = has_resized(&window_events);
-    if changed_len == 0 && !resized {
+    let resized = has_resized(&mut window_events);
+    if changed_len == 0 && !resized && !samples_changed && !targets_changed {
         // cleanup if all nodes were removed
         if ui_nodes.is_empty() && !ui_mesh.positions.is_empty() {
             ui_mesh.clear();
@@ -134,8 +192,19 @@ pub fn update_ui_mesh_and_transforms(
     let viewport = world.resources.get::<Viewport>();
     let mut swash_cache = world.resources.get_mut::<SwashCache>();
 
+    // prepare the targets, their meshes are cleared too
+    let cache = world.resources.get::<Cache>();
+    ui_targets.begin(targets, &device, &queue, &cache, &mut text_atlas);
+
+    // nodes which are not drawn
+    let hidden = hidden_nodes(&mut visibility_query);
+    // render target root of nodes drawn into a target
+    let target_roots = render_target_roots(&mut visibility_query);
+
     // intermediate storage for text buffer raes
     let mut intermediate_text_rae = Vec::new();
+    // text buffer metadata to node layer, used as the text depth
+    let mut text_layers = HashMap::new();
 
     // add other node types as options
     let ui_nodes = ui_nodes
@@ -145,6 +214,7 @@ pub fn update_ui_mesh_and_transforms(
             // lifetime issues, then later in code retrieve it and push its borrow to text_borrows
             if let Some(text) = text {
                 let text = text_buffers.get_by_entity(id, text, world);
+                text_layers.insert(text.metadata(), computed.z_index as f32 + 1.0); // +1 to fix LessEqual depthmap issues
                 intermediate_text_rae.push(Some(text));
             } else {
                 intermediate_text_rae.push(None);
@@ -164,6 +234,7 @@ pub fn update_ui_mesh_and_transforms(
         .collect::<Vec<_>>();
 
     let mut text_areas = Vec::new();
+    let mut target_text_areas: HashMap<EntityId, Vec<TextArea>> = HashMap::new();
     let mut ui_transforms = Vec::new();
     let mut transform_index = 0;
 
@@ -171,8 +242,21 @@ pub fn update_ui_mesh_and_transforms(
         // extract global translation
         let translation = global_transform.translation();
 
+        // nodes of a render target go to its meshes, if the target isn't prepared it's not drawn
+        let target_root = target_roots.get(&id).copied();
+        let (mesh, mesh_transparent, target_size) = match target_root {
+            Some(root) => {
+                let Some(target) = ui_targets.targets.get_mut(&root) else {
+                    continue;
+                };
+                let size = Vec2::new(target.size.width as f32, target.size.height as f32);
+                (&mut target.mesh, &mut target.mesh_transparent, size)
+            }
+            None => (&mut *ui_mesh, &mut **ui_mesh_transparent, window_size),
+        };
+
         // dont add node to mesh
-        if node.display == Display::None {
+        if hidden.contains(&id) || is_outside_window(global_transform, computed, target_size) {
             continue;
         }
 
@@ -232,7 +316,7 @@ pub fn update_ui_mesh_and_transforms(
         for (x, y, w, h, color, has_image) in quads {
             if w > 0.0 && h > 0.0 && color.a > 0.0 {
                 if color.a == 1.0 {
-                    ui_mesh.add_rect(
+                    mesh.add_rect(
                         x,
                         y,
                         computed.z_index as f32,
@@ -243,7 +327,7 @@ pub fn update_ui_mesh_and_transforms(
                         id,
                     );
                 } else {
-                    ui_mesh_transparent.add_rect(
+                    mesh_transparent.add_rect(
                         x,
                         y,
                         computed.z_index as f32,
@@ -256,7 +340,7 @@ pub fn update_ui_mesh_and_transforms(
                 }
             }
 
-            if w > 0.0 && h > 0.0 && has_image {
+            if w > 0.0 && h > 0.0 && has_image && target_root.is_none() {
                 ui_mesh_images.add_rect(
                     x,
                     y,
@@ -286,7 +370,7 @@ pub fn update_ui_mesh_and_transforms(
                 translation.y + computed.height.offset(),
             );
 
-            text_areas.push(TextArea {
+            let text_area = TextArea {
                 buffer: text,
                 left: content_translation.x,
                 top: content_translation.y,
@@ -299,28 +383,91 @@ pub fn update_ui_mesh_and_transforms(
                 },
                 default_color: computed.color.into(),
                 custom_glyphs: &[],
-            })
+            };
+
+            match target_root {
+                Some(root) => target_text_areas.entry(root).or_default().push(text_area),
+                None => text_areas.push(text_area),
+            }
         }
     }
 
+    // glyphs drawn by the ui are marked as used again by the prepare below, so only glyphs which
+    // are no longer drawn can be evicted
+    if world.resources.get::<UiFonts>().trim_atlas {
+        text_atlas.trim();
+    }
+
+    let text_depth = |md: usize| {
+        // TODO: do a better way to match with UI shader, this is copypasting
+        let mil = 1_000_000.0;
+        let layer = text_layers.get(&md).copied().unwrap_or(md as f32);
+        (mil - layer - 1.0) / mil
+    };
+
     // prepare text areas for rendering
-    text_renderer
-        .prepare_with_depth(
+    let prepared = text_renderer.prepare_with_depth(
+        &device,
+        &queue,
+        &mut font_system,
+        &mut text_atlas,
+        &viewport,
+        text_areas,
+        &mut swash_cache,
+        text_depth,
+    );
+
+    if let Err(err) = prepared {
+        tracing::error!("Failed to prepare UI text, glyph atlas is full: {err:?}");
+    }
+
+    // prepare the text and upload the meshes of render targets
+    for (root, target) in ui_targets.targets.iter_mut() {
+        let prepared = target.text_renderer.prepare_with_depth(
             &device,
             &queue,
             &mut font_system,
             &mut text_atlas,
-            &viewport,
-            text_areas,
+            &target.viewport,
+            target_text_areas.remove(root).unwrap_or_default(),
             &mut swash_cache,
-            |md| {
-                // TODO: do a better way to match with UI shader, this is copypasting
-                let mil = 1_000_000.0;
-                (mil - md as f32 - 1.0) / mil
-            },
-        )
-        .unwrap();
+            text_depth,
+        );
+
+        if let Err(err) = prepared {
+            tracing::error!("Failed to prepare UI target text, glyph atlas is full: {err:?}");
+        }
+
+        target.update_buffers(&device);
+    }
 
     // update transform storage with ui nodes
-    ui_transform_storage.update(&ui_transforms, ui_transforms.len(), &device, &queue);
+    ui_transform_storage.update(&ui_transforms, ui_transforms.len(), &device, &mut belt);
+}
+
+/// Returns true if the border box of a node is completely outside of the window. Nodes are
+/// placed in window pixels, so the box is transformed by the node's global transform.
+fn is_outside_window(
+    global_transform: &GlobalTransform,
+    computed: &ComputedNode,
+    window_size: Vec2,
+) -> bool {
+    let (width, height) = (computed.width.border, computed.height.border);
+    let corners = [
+        Vec2::ZERO,
+        Vec2::new(width, 0.0),
+        Vec2::new(0.0, height),
+        Vec2::new(width, height),
+    ]
+    .map(|corner| {
+        global_transform
+            .matrix
+            .transform_point3(corner.extend(0.0))
+            .truncate()
+    });
+
+    let min = corners.into_iter().reduce(Vec2::min).unwrap_or_default();
+    let max = corners.into_iter().reduce(Vec2::max).unwrap_or_default();
+
+    max.x < 0.0 || max.y < 0.0 || min.x > window_size.x || min.y > window_size.y
 }
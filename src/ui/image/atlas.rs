@@ -0,0 +1,79 @@
+use glam::Vec2;
+
+use crate::{
+    prelude::*,
+    render_assets::{BindGroup, IntoRenderAsset},
+};
+
+/// Image split into regions which can be drawn separately by [`UiImage`](super::UiImage) nodes.
+/// All nodes using the same atlas share one bind group, so icon-heavy UIs don't need one per
+/// icon.
+///
+/// Regions are stored in uv coordinates, with the origin in the top left corner of the image.
+#[derive(Clone, Debug, crate::macros::Asset)]
+pub struct TextureAtlas {
+    pub image: Handle<Image>,
+    /// Min and max uv coordinates of every region
+    pub rects: Vec<(Vec2, Vec2)>,
+}
+
+impl TextureAtlas {
+    /// Create a new atlas without any regions
+    pub fn new(image: Handle<Image>) -> Self {
+        Self {
+            image,
+            rects: Vec::new(),
+        }
+    }
+
+    /// Create a new atlas split into a uniform grid of `columns` x `rows` regions. Indices go row
+    /// by row, starting at the top left corner of the image.
+    pub fn from_grid(image: Handle<Image>, columns: u32, rows: u32) -> Self {
+        assert!(
+            columns > 0 && rows > 0,
+            "TextureAtlas grid must have at least one column and row"
+        );
+
+        let tile = Vec2::new(1.0 / columns as f32, 1.0 / rows as f32);
+        let rects = (0..rows)
+            .flat_map(|row| (0..columns).map(move |column| (column, row)))
+            .map(|(column, row)| {
+                let min = Vec2::new(column as f32 * tile.x, row as f32 * tile.y);
+                (min, min + tile)
+            })
+            .collect();
+
+        Self { image, rects }
+    }
+
+    /// Add a region from `min` to `max` in pixels of an image with `image_size`, returns its index
+    pub fn add_rect(&mut self, min: Vec2, max: Vec2, image_size: Vec2) -> usize {
+        self.rects.push((min / image_size, max / image_size));
+        self.rects.len() - 1
+    }
+
+    /// Returns the min and max uv coordinates of the region at `index`
+    pub fn uv_rect(&self, index: usize) -> Option<(Vec2, Vec2)> {
+        self.rects.get(index).copied()
+    }
+
+    /// Returns the amount of regions in the atlas
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.rects.len()
+    }
+
+    /// Returns true if the atlas has no regions
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.rects.is_empty()
+    }
+}
+
+impl IntoRenderAsset<BindGroup> for TextureAtlas {
+    fn create_render_asset(&self, world: &mut World, _: Option<EntityId>) -> BindGroup {
+        BindGroup::build("ui_texture_atlas")
+            .add_texture(&Some(self.image.clone()), world, color::WHITE, None, None)
+            .finish(&world.resources.get())
+    }
+}
@@ -1,4 +1,6 @@
+mod atlas;
 mod ui_image;
 pub mod render;
 
-pub use ui_image::UiImage;
+pub use atlas::TextureAtlas;
+pub use ui_image::{UiImage, UiImageSource};
@@ -3,6 +3,19 @@ use crate::prelude::*;
 use crate::render_assets::{BindGroup, Buffer, RenderAssets};
 use crate::ui::{graph::storage::UiTransformStorage, mesh::UiMeshImages, prelude::*};
 
+use super::{TextureAtlas, UiImageSource};
+
+/// Per image data, must match `ImageConstants` in `ui.wgsl`
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct ImageConstants {
+    window_size: [f32; 2],
+    flags: u32,
+    _padding: u32,
+    uv_rect: [f32; 4],
+    tint: Color,
+}
+
 pub fn ui_image_render_system(
     graph_ctx: Res<RenderContext>,
 
@@ -13,6 +26,7 @@ pub fn ui_image_render_system(
     mut buffers: ResMut<RenderAssets<Buffer>>,
     mut bind_groups: ResMut<RenderAssets<BindGroup>>,
     ui_mesh_images: Res<UiMeshImages>,
+    atlases: Res<Assets<TextureAtlas>>,
 
     // holds the transform of every ui node
     ui_transforms: Res<UiTransformStorage>,
@@ -22,7 +36,13 @@ pub fn ui_image_render_system(
         (With<Transform>, With<Projection>, With<Camera3D>),
     >,
     mut ui_image_query: Query<&UiImage, With<Node>>,
+    rendered: Res<RenderedWindow>,
 ) {
+    // the UI is laid out for the primary window
+    if !rendered.primary {
+        return;
+    }
+
     let ui_mesh_images_buffer = buffers.get_by_resource(&ui_mesh_images, world, true);
     if ui_mesh_images_buffer.num_vertices == 0 {
         return;
@@ -62,25 +82,42 @@ pub fn ui_image_render_system(
     render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
     render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
 
-    // push constants
-    let window_size = window.size();
-    render_pass.set_push_constants(
-        wgpu::ShaderStages::VERTEX,
-        0,
-        bytemuck::cast_slice(&[(window_size.width as f32), (window_size.height as f32)]),
-    );
-
     // loop through all ui nodes
+    let window_size = window.size();
+    let window_size = [window_size.width as f32, window_size.height as f32];
+    let mut bound_image = None;
     let mut current_indices = 0..6;
     for &entity_id in &ui_mesh_images.entity_ids {
         // get image
         let image = ui_image_query
             .get(entity_id)
             .expect("UiImage component not found");
-        let image_bind_group = bind_groups.get_by_entity(entity_id, image, world);
 
-        // per entity bind group
-        render_pass.set_bind_group(2, &*image_bind_group, &[]);
+        // images from the same atlas share one bind group, so it's only rebound when it changes
+        let image_bind_group = match &image.source {
+            UiImageSource::Image(_) => bind_groups.get_by_entity(entity_id, image, world),
+            UiImageSource::Atlas { atlas, .. } => bind_groups.get_by_handle(atlas, world),
+        };
+        let image_bind_group_ptr = &*image_bind_group as *const BindGroup;
+        if bound_image != Some(image_bind_group_ptr) {
+            render_pass.set_bind_group(2, &*image_bind_group, &[]);
+            bound_image = Some(image_bind_group_ptr);
+        }
+
+        // per entity push constants
+        let (uv_min, uv_max) = image.uv_rect(&atlases);
+        let constants = ImageConstants {
+            window_size,
+            flags: image.flags(),
+            _padding: 0,
+            uv_rect: [uv_min.x, uv_min.y, uv_max.x, uv_max.y],
+            tint: image.tint,
+        };
+        render_pass.set_push_constants(
+            wgpu::ShaderStages::VERTEX,
+            0,
+            bytemuck::bytes_of(&constants),
+        );
 
         // draw
         render_pass.draw_indexed(current_indices.clone(), 0, 0..1);
@@ -89,4 +126,6 @@ pub fn ui_image_render_system(
         current_indices.start = current_indices.end;
         current_indices.end = current_indices.start + 6;
     }
+
+    graph_ctx.record_draw_calls(ui_mesh_images.entity_ids.len() as u32);
 }
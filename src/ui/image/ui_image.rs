@@ -1,12 +1,28 @@
+use glam::Vec2;
+
 use crate::{
     prelude::*,
-    render_assets::{BindGroup, Buffer, IntoRenderAsset},
+    render_assets::{BindGroup, IntoRenderAsset},
 };
 
+use super::TextureAtlas;
+
+/// Texture drawn by a [`UiImage`]
+#[derive(Clone, Debug)]
+pub enum UiImageSource {
+    /// The whole image
+    Image(Handle<Image>),
+    /// Region at `index` of a [`TextureAtlas`]
+    Atlas {
+        atlas: Handle<TextureAtlas>,
+        index: usize,
+    },
+}
+
 /// An image UI node component.
 #[derive(Component, Clone, Debug)]
 pub struct UiImage {
-    pub image: Handle<Image>,
+    pub source: UiImageSource,
     /// Image color gets multiplied by this tint color, defaults to white
     pub tint: Color,
     pub flip_x: bool,
@@ -15,8 +31,17 @@ pub struct UiImage {
 
 impl UiImage {
     pub fn new(image: Handle<Image>) -> Self {
+        Self::from_source(UiImageSource::Image(image))
+    }
+
+    /// Create an image node drawing the region at `index` of `atlas`
+    pub fn from_atlas(atlas: Handle<TextureAtlas>, index: usize) -> Self {
+        Self::from_source(UiImageSource::Atlas { atlas, index })
+    }
+
+    fn from_source(source: UiImageSource) -> Self {
         Self {
-            image,
+            source,
             tint: color::WHITE,
             flip_x: false,
             flip_y: false,
@@ -41,38 +66,39 @@ impl UiImage {
         self
     }
 
-    fn uniform_data(&self) -> Vec<u8> {
-        let mut data = Vec::new();
-
-        data.extend_from_slice(bytemuck::bytes_of(&self.tint));
-
-        let booleans = self.flip_x as u32 | ((self.flip_y as u32) << 1);
-        data.extend_from_slice(bytemuck::cast_slice(&[booleans, 0, 0, 0]));
-
-        data
+    /// Returns the min and max uv coordinates drawn by this image. Missing atlases and regions
+    /// fall back to the whole texture.
+    pub(crate) fn uv_rect(&self, atlases: &Assets<TextureAtlas>) -> (Vec2, Vec2) {
+        match &self.source {
+            UiImageSource::Atlas { atlas, index } => atlases
+                .get(atlas)
+                .and_then(|atlas| atlas.uv_rect(*index))
+                .unwrap_or((Vec2::ZERO, Vec2::ONE)),
+            UiImageSource::Image(_) => (Vec2::ZERO, Vec2::ONE),
+        }
     }
-}
 
-impl IntoRenderAsset<Buffer> for UiImage {
-    fn create_render_asset(&self, world: &mut World, _: Option<EntityId>) -> Buffer {
-        Buffer::new("ui_image").create_uniform_buffer(
-            &self.uniform_data(),
-            None,
-            &world.resources.get(),
-        )
+    /// Flip flags passed to the shader
+    pub(crate) fn flags(&self) -> u32 {
+        self.flip_x as u32 | ((self.flip_y as u32) << 1)
     }
 }
 
+/// Bind group of a [`UiImageSource::Image`], atlas images share the bind group of their
+/// [`TextureAtlas`]
 impl IntoRenderAsset<BindGroup> for UiImage {
     fn create_render_asset(&self, world: &mut World, _: Option<EntityId>) -> BindGroup {
-        let image = Some(self.image.clone());
-
-        let buffer: Buffer = self.create_render_asset(world, None);
-        let uniform = buffer.uniform.expect("UiImage buffer should be uniform");
+        let image = match &self.source {
+            UiImageSource::Image(image) => Some(image.clone()),
+            UiImageSource::Atlas { atlas, .. } => world
+                .resources
+                .get::<Assets<TextureAtlas>>()
+                .get(atlas)
+                .map(|atlas| atlas.image.clone()),
+        };
 
         BindGroup::build("ui_image")
             .add_texture(&image, world, color::WHITE, None, None)
-            .add_uniform_buffer(&uniform, wgpu::ShaderStages::VERTEX_FRAGMENT)
             .finish(&world.resources.get())
     }
 }
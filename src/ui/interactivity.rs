@@ -1,8 +1,39 @@
-use std::collections::HashMap;
+//! Mouse interaction with UI nodes.
+//!
+//! Nodes with a [`Button`] get an [`Interaction`] component, which holds their current state and
+//! can be polled by systems.
+//!
+//! For an event model, every frame the visible node on top of the cursor is the target of
+//! [`UiEvent`]s, which are dispatched through the node tree like DOM events. The event first
+//! travels from the UI root down to the target (capture phase), then back up to the root (bubble
+//! phase). [`UiEventListener`] handlers are called on every node on the way, and can end the
+//! dispatch with [`UiEvent::stop_propagation`]. The target and bubble steps are also written as
+//! events, so they can be read with an [`EventReader`].
+//!
+//! ```ignore
+//! commands
+//!     .spawn(Node::default())
+//!     .insert(UiEventListener::new().on(UiEventKind::Click, |event, commands| {
+//!         // clicks on the children of the panel don't reach its parents
+//!         event.stop_propagation();
+//!         commands.entity(event.current).insert(Selected);
+//!     }));
+//! ```
 
-use winit::event::MouseButton;
+use std::collections::{HashMap, HashSet};
 
-use crate::{event::EventReader, prelude::*, ui::prelude::*};
+use glam::Vec2;
+use winit::event::{ElementState, MouseButton};
+
+use crate::{
+    event::{EventReader, EventWriter},
+    prelude::*,
+    ui::{
+        node::{VisibilityQuery, hidden_nodes},
+        prelude::*,
+        render_target::render_target_roots,
+    },
+};
 
 /// Marks an UI entity as interactive, enabling mouse events via `Interaction`
 #[derive(Component, Debug, Clone, Copy)]
@@ -19,6 +50,265 @@ pub enum Interaction {
     None,
 }
 
+/// Kind of a [`UiEvent`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UiEventKind {
+    /// Cursor moved onto the target, sent once when the target under the cursor changes
+    Hover,
+    /// Mouse button was pressed over the target
+    Pressed,
+    /// Mouse button was released over the target
+    Released,
+    /// Mouse button was pressed and released over the same target
+    Click,
+}
+
+/// Step of the [`UiEvent`] dispatch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UiEventPhase {
+    /// Travelling from the UI root down to the target's parent
+    Capture,
+    /// At the target node
+    Target,
+    /// Travelling from the target's parent up to the UI root
+    Bubble,
+}
+
+/// Mouse event on a UI node, dispatched from the UI root to the target node and back. For more
+/// information, see the [interactivity module](crate::ui::interactivity).
+#[derive(Event, Debug, Clone, Copy)]
+pub struct UiEvent {
+    pub kind: UiEventKind,
+    /// Mouse button of the event, `None` for [`UiEventKind::Hover`]
+    pub button: Option<MouseButton>,
+    /// Top node under the cursor, which the event is dispatched to
+    pub target: EntityId,
+    /// Node currently handling the event, the target or one of its ancestors
+    pub current: EntityId,
+    pub phase: UiEventPhase,
+    /// Cursor position in physical pixels
+    pub position: Vec2,
+    stopped: bool,
+}
+
+impl UiEvent {
+    /// Event of `kind` at its `target` node, before it's dispatched
+    pub(crate) fn new(
+        kind: UiEventKind,
+        button: Option<MouseButton>,
+        target: EntityId,
+        position: Vec2,
+    ) -> Self {
+        Self {
+            kind,
+            button,
+            target,
+            current: target,
+            phase: UiEventPhase::Target,
+            position,
+            stopped: false,
+        }
+    }
+
+    /// Stop the dispatch after the handlers of the current node, so the event doesn't reach the
+    /// remaining nodes on its path
+    #[inline]
+    pub fn stop_propagation(&mut self) {
+        self.stopped = true;
+    }
+
+    /// Returns true if a handler stopped the dispatch
+    #[inline]
+    pub fn is_propagation_stopped(&self) -> bool {
+        self.stopped
+    }
+}
+
+/// Handler of a [`UiEvent`], changes to the world are made through the commands
+pub type UiEventHandler = Box<dyn Fn(&mut UiEvent, &mut Commands) + Send + Sync>;
+
+/// Handlers called while [`UiEvent`]s pass through the node
+#[derive(Component, Default)]
+pub struct UiEventListener {
+    /// Kind, whether it's a capture handler, and the handler
+    handlers: Vec<(UiEventKind, bool, UiEventHandler)>,
+}
+
+impl UiEventListener {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns self with a handler of `kind` events, called when the node is the target or
+    /// during the bubble phase
+    pub fn on(
+        mut self,
+        kind: UiEventKind,
+        handler: impl Fn(&mut UiEvent, &mut Commands) + Send + Sync + 'static,
+    ) -> Self {
+        self.handlers.push((kind, false, Box::new(handler)));
+        self
+    }
+
+    /// Returns self with a handler of `kind` events, called during the capture phase, before
+    /// the event reaches the node's descendants, or when the node is the target
+    pub fn on_capture(
+        mut self,
+        kind: UiEventKind,
+        handler: impl Fn(&mut UiEvent, &mut Commands) + Send + Sync + 'static,
+    ) -> Self {
+        self.handlers.push((kind, true, Box::new(handler)));
+        self
+    }
+
+    /// Call the handlers matching the kind and phase of `event`
+    fn handle(&self, event: &mut UiEvent, commands: &mut Commands) {
+        for (kind, capture, handler) in &self.handlers {
+            let phase_matches = match event.phase {
+                UiEventPhase::Capture => *capture,
+                UiEventPhase::Target => true,
+                UiEventPhase::Bubble => !*capture,
+            };
+
+            if *kind == event.kind && phase_matches {
+                handler(event, commands);
+            }
+        }
+    }
+}
+
+/// Pointer state of the UI events. Used as a resource.
+#[derive(Resource, Default, Debug)]
+pub struct UiPointer {
+    hovered: Option<EntityId>,
+    /// Target of every held down button
+    pressed: HashMap<MouseButton, EntityId>,
+}
+
+impl UiPointer {
+    /// Returns the top node under the cursor
+    #[inline]
+    pub fn hovered(&self) -> Option<EntityId> {
+        self.hovered
+    }
+}
+
+/// System to dispatch [`UiEvent`]s, runs in the First phase like [`ui_interaction_update`]
+pub fn ui_event_dispatch_system(
+    mut commands: Commands,
+    mut pointer: ResMut<UiPointer>,
+    mut input_events: EventReader<MouseInput>,
+    window: Res<Window>,
+    mut writer: EventWriter<UiEvent>,
+    mut query: Query<(EntityId, &ComputedNode, &GlobalTransform, Option<&Parent>)>,
+    mut listeners: Query<&UiEventListener>,
+    mut visibility_query: VisibilityQuery,
+) {
+    let nodes = query.iter_mut();
+    let parents = nodes
+        .iter()
+        .map(|(id, _, _, parent)| (*id, parent.map(|parent| parent.id)))
+        .collect::<HashMap<_, _>>();
+    let mut hidden = hidden_nodes(&mut visibility_query);
+    // nodes drawn into render targets are not under the window cursor
+    hidden.extend(render_target_roots(&mut visibility_query).into_keys());
+
+    // top node under the cursor
+    let position = window.cursor_position();
+    let target = position.and_then(|position| {
+        nodes
+            .iter()
+            .filter(|(id, ..)| !hidden.contains(id))
+            .filter(|(_, computed, global_transform, _)| {
+                padding_box(computed, global_transform).contains(position)
+            })
+            .max_by_key(|(_, computed, ..)| computed.z_index)
+            .map(|(id, ..)| *id)
+    });
+    let position = position.unwrap_or_default();
+
+    let mut dispatch = |kind, button, target| {
+        let event = UiEvent::new(kind, button, target, position);
+        dispatch_event(event, &parents, &mut listeners, &mut commands, &mut writer);
+    };
+
+    if target != pointer.hovered
+        && let Some(target) = target
+    {
+        dispatch(UiEventKind::Hover, None, target);
+    }
+    pointer.hovered = target;
+
+    for input in input_events.read() {
+        match input.state {
+            ElementState::Pressed => {
+                let Some(target) = target else {
+                    continue;
+                };
+                pointer.pressed.insert(input.button, target);
+                dispatch(UiEventKind::Pressed, Some(input.button), target);
+            }
+            ElementState::Released => {
+                let pressed = pointer.pressed.remove(&input.button);
+                let Some(target) = target else {
+                    continue;
+                };
+                dispatch(UiEventKind::Released, Some(input.button), target);
+                if pressed == Some(target) {
+                    dispatch(UiEventKind::Click, Some(input.button), target);
+                }
+            }
+        }
+    }
+}
+
+/// Dispatches `event` from the UI root to its target and back, until a handler stops it
+pub(crate) fn dispatch_event(
+    mut event: UiEvent,
+    parents: &HashMap<EntityId, Option<EntityId>>,
+    listeners: &mut Query<&UiEventListener>,
+    commands: &mut Commands,
+    writer: &mut EventWriter<UiEvent>,
+) {
+    // target and its UI ancestors, up to the root
+    let mut path = vec![event.target];
+    while let Some(&Some(parent)) = path.last().and_then(|id| parents.get(id)) {
+        path.push(parent);
+    }
+
+    let capture = path[1..]
+        .iter()
+        .rev()
+        .map(|&id| (id, UiEventPhase::Capture));
+    let target = std::iter::once((event.target, UiEventPhase::Target));
+    let bubble = path[1..].iter().map(|&id| (id, UiEventPhase::Bubble));
+
+    for (id, phase) in capture.chain(target).chain(bubble) {
+        event.current = id;
+        event.phase = phase;
+        if phase != UiEventPhase::Capture {
+            writer.write(event);
+        }
+
+        if let Some(listener) = listeners.get(id) {
+            listener.handle(&mut event, commands);
+        }
+        if event.stopped {
+            break;
+        }
+    }
+}
+
+/// Returns the padding box of a node, the area which reacts to the cursor
+pub(crate) fn padding_box(computed: &ComputedNode, global_transform: &GlobalTransform) -> Rect {
+    let translation = global_transform.translation();
+    let left = translation.x + computed.margin.left + computed.border.left;
+    let top = translation.y + computed.margin.top + computed.border.top;
+    let right = left + computed.width.content + computed.padding.horizontal();
+    let bottom = top + computed.height.content + computed.padding.vertical();
+    Rect::new_min_max(left, top, right, bottom)
+}
+
 /// System to update UI interactions, runs in the First stage. So old computed values are used
 pub fn ui_interaction_update(
     mouse_inputs: Res<Input<MouseButton>>,
@@ -32,6 +322,7 @@ pub fn ui_interaction_update(
         &GlobalTransform,
         &Interaction,
     )>,
+    mut visibility_query: VisibilityQuery,
 ) {
     let nodes = query.iter_mut();
     if nodes.is_empty() {
@@ -52,9 +343,20 @@ pub fn ui_interaction_update(
         })
         .collect();
 
+    // nodes drawn into render targets are not under the window cursor
+    let mut hidden = hidden_nodes(&mut visibility_query);
+    hidden.extend(render_target_roots(&mut visibility_query).into_keys());
+
     // new interactions
     let (new_interactions, keep) =
-        match get_interactions(mouse_inputs, input_events, move_events, window, &nodes) {
+        match get_interactions(
+            mouse_inputs,
+            input_events,
+            move_events,
+            window,
+            &nodes,
+            &hidden,
+        ) {
             Some(interactions) => interactions,
             None => return,
         };
@@ -75,8 +377,8 @@ pub fn ui_interaction_update(
 /// Get nodes with new interactions
 fn get_interactions(
     mouse_inputs: Res<Input<MouseButton>>,
-    input_events: EventReader<MouseInput>,
-    move_events: EventReader<CursorMoved>,
+    mut input_events: EventReader<MouseInput>,
+    mut move_events: EventReader<CursorMoved>,
     window: Res<Window>,
     nodes: &[(
         EntityId,
@@ -85,11 +387,16 @@ fn get_interactions(
         &GlobalTransform,
         &Interaction,
     )],
+    hidden: &HashSet<EntityId>,
 ) -> Option<(
     Vec<(EntityId, Interaction)>, // new
     Vec<EntityId>,                // keep
 )> {
-    if input_events.is_empty() && move_events.is_empty() {
+    let no_events = input_events.is_empty() && move_events.is_empty();
+    // the events are only used to detect input, they don't have to be read
+    input_events.clear();
+    move_events.clear();
+    if no_events {
         // no events to process
         return None;
     }
@@ -107,20 +414,13 @@ fn get_interactions(
     let mut keep = Vec::new();
 
     // find intersections
-    for (id, node, computed, global_transform, interaction) in nodes {
+    for (id, _, computed, global_transform, interaction) in nodes {
         // check visibility
-        if node.display == Display::None {
+        if hidden.contains(id) {
             continue;
         }
 
-        // calculate padding bounding box
-        let translation = global_transform.translation();
-        let left = translation.x + computed.margin.left + computed.border.left;
-        let top = translation.y + computed.margin.top + computed.border.top;
-        let right = left + computed.width.content + computed.padding.horizontal();
-        let bottom = top + computed.height.content + computed.padding.vertical();
-        let padding_box = Rect::new_min_max(left, top, right, bottom);
-        let hovering = padding_box.contains(cursor_position);
+        let hovering = padding_box(computed, global_transform).contains(cursor_position);
 
         let state = match (**interaction, hovering, is_pressed, just_pressed) {
             // hovering
@@ -6,6 +6,7 @@ use wgpu::VertexFormat;
 
 use crate::prelude::*;
 use crate::render_assets::*;
+use crate::renderer::newtype::RenderDevice;
 
 /// Mesh for UI nodes, either 2d or 3d
 #[derive(Default, Resource, Debug)]
@@ -132,15 +133,19 @@ impl UiMesh {
             ],
         }
     }
+
+    /// Creates the vertex and index buffer of the mesh
+    pub(crate) fn create_buffer(&self, device: &RenderDevice) -> Buffer {
+        Buffer::new("ui_mesh")
+            .create_vertex_buffer(&self.vertex_data(), self.positions.len(), None, device)
+            .create_index_buffer(&self.indices, None, device)
+    }
 }
 
 impl IntoRenderAsset<Buffer> for UiMesh {
     fn create_render_asset(&self, world: &mut World, _: Option<EntityId>) -> Buffer {
-        let device = world.resources.get();
-
-        Buffer::new("ui_mesh")
-            .create_vertex_buffer(&self.vertex_data(), self.positions.len(), None, &device)
-            .create_index_buffer(&self.indices, None, &device)
+        let device = world.resources.get::<RenderDevice>();
+        self.create_buffer(&device)
     }
 }
 
@@ -1,9 +1,13 @@
 pub mod node;
 pub mod text;
 pub mod interactivity;
+pub mod cursor;
+pub mod focus;
+pub mod virtual_list;
 pub mod image;
 pub mod mesh;
 pub mod graph;
 pub mod plugin;
+pub mod render_target;
 
 pub mod prelude;
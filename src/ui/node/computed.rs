@@ -48,6 +48,19 @@ impl ComputedUiRect {
     }
 }
 
+impl std::ops::Add for ComputedUiRect {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            left: self.left + rhs.left,
+            right: self.right + rhs.right,
+            top: self.top + rhs.top,
+            bottom: self.bottom + rhs.bottom,
+        }
+    }
+}
+
 impl UiRect {
     /// Compute Rect fields based on parent width for padding and margin, self width for border
     pub fn compute_rect(&self, width: f32, window_size: PhysicalSize<u32>) -> ComputedUiRect {
@@ -127,4 +140,9 @@ pub struct ComputedNode {
 
     /// Base width of a node without text width
     pub base_width: f32,
+
+    /// True if the node was left out of the last layout because of [`UiVisibility::Collapsed`]
+    ///
+    /// [`UiVisibility::Collapsed`]: super::UiVisibility::Collapsed
+    pub collapsed: bool,
 }
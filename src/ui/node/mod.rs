@@ -1,5 +1,8 @@
 mod node;
 mod computed;
+mod visibility;
 
 pub use node::*;
 pub use computed::*;
+pub use visibility::UiVisibility;
+pub(crate) use visibility::{VisibilityQuery, hidden_nodes};
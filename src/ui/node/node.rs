@@ -1,4 +1,5 @@
 use crate::prelude::Color;
+use crate::window::config::CursorIcon;
 
 /// Defines the style properties of an Ui Entity in a similar fashion to CSS
 
@@ -174,6 +175,9 @@ pub struct Node {
     /// Some - override
     pub color: Option<Color>,
     pub border_color: Color,
+    /// Cursor icon while the node is hovered, None picks it by the node type. For more
+    /// information, see the [cursor module](crate::ui::cursor).
+    pub cursor: Option<CursorIcon>,
 
     pub display: Display,
     pub position: Position,
@@ -193,6 +197,9 @@ pub struct Node {
     pub padding: UiRect,
     pub margin: UiRect,
     pub border: UiRect,
+    /// Adds the [`SafeAreaInsets`](crate::window::SafeAreaInsets) to the padding of a root node,
+    /// so its content isn't clipped by notches or rounded corners on phones
+    pub safe_area: bool,
 
     pub width: Val,
     pub min_width: Val,
@@ -0,0 +1,53 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::prelude::*;
+
+use super::{Display, Node};
+
+/// Visibility of a UI node and its descendants, nodes without it are visible.
+///
+/// Toggling between `Visible` and `Hidden` doesn't recompute the layout, only the UI mesh is
+/// rebuilt. `Collapsed` changes the layout, so changing from or to it recomputes it.
+#[derive(crate::macros::Component, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UiVisibility {
+    #[default]
+    Visible,
+    /// Not drawn and not interactive, but still takes up space in the layout
+    Hidden,
+    /// Not drawn, not interactive and removed from the layout, like [`Display::None`]
+    Collapsed,
+}
+
+/// Query used by [`hidden_nodes`]
+pub(crate) type VisibilityQuery<'a> =
+    Query<(EntityId, &'a Node, Option<&'a UiVisibility>, Option<&'a Parent>)>;
+
+/// Returns the ids of nodes which are not drawn, because they or one of their UI ancestors are
+/// hidden, collapsed or have [`Display::None`]
+pub(crate) fn hidden_nodes(query: &mut VisibilityQuery) -> HashSet<EntityId> {
+    let nodes = query
+        .iter_mut()
+        .into_iter()
+        .map(|(id, node, visibility, parent)| {
+            let hidden = node.display == Display::None
+                || visibility.is_some_and(|visibility| *visibility != UiVisibility::Visible);
+            (id, (hidden, parent.map(|parent| parent.id)))
+        })
+        .collect::<HashMap<_, _>>();
+
+    nodes
+        .keys()
+        .copied()
+        .filter(|&id| {
+            // walk up until a hidden node or the UI root
+            let mut current = Some(id);
+            while let Some(&(hidden, parent)) = current.and_then(|id| nodes.get(&id)) {
+                if hidden {
+                    return true;
+                }
+                current = parent;
+            }
+            false
+        })
+        .collect()
+}
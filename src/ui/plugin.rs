@@ -1,17 +1,24 @@
 use glyphon::{Cache, FontSystem, SwashCache, TextAtlas, TextRenderer, Viewport};
 
 use super::{
+    cursor::update_ui_cursor,
+    focus::{
+        UiFocus, UiNavigation, UiNavigationRepeat, ui_focus_navigation_system,
+        ui_gamepad_navigation_system, ui_keyboard_navigation_system,
+    },
     graph::{
         compute::compute_nodes_and_transforms,
         graph_nodes::register_ui_graph,
         storage::UiTransformStorage,
         update::{update_glyphon_viewport, update_ui_mesh_and_transforms},
     },
-    interactivity::{Button, ui_interaction_update},
+    interactivity::{Button, UiEvent, UiPointer, ui_event_dispatch_system, ui_interaction_update},
     mesh::{UiMesh, UiMeshImages, UiMeshTransparent},
+    render_target::{UiRenderTargets, prepare_ui_render_target_images},
+    virtual_list::update_virtual_lists,
 };
 
-use super::text::TextBuffer;
+use super::text::{Font, TextBuffer, UiFonts, load_ui_fonts};
 use crate::{
     prelude::*,
     renderer::newtype::{RenderQueue, RenderSurfaceConfiguration},
@@ -48,6 +55,7 @@ fn insert_ui_text_resources(
     device: Res<RenderDevice>,
     queue: Res<RenderQueue>,
     surface_config: Res<RenderSurfaceConfiguration>,
+    msaa: Option<Res<Msaa>>,
 ) {
     let swapchain_format = surface_config.format;
 
@@ -56,28 +64,42 @@ fn insert_ui_text_resources(
     let cache = Cache::new(&device);
     let viewport = Viewport::new(&device, &cache);
     let mut atlas = TextAtlas::new(&device, &queue, &cache, swapchain_format);
-    let text_renderer = TextRenderer::new(
-        &mut atlas,
-        &device,
-        wgpu::MultisampleState::default(),
-        Some(wgpu::DepthStencilState {
-            format: wgpu::TextureFormat::Depth32Float,
-            depth_write_enabled: true,
-            depth_compare: wgpu::CompareFunction::Less,
-            stencil: wgpu::StencilState::default(),
-            bias: wgpu::DepthBiasState::default(),
-        }),
-    );
+    let samples = msaa.map_or(1, |msaa| msaa.samples());
+    let text_renderer = create_ui_text_renderer(&mut atlas, &device, samples);
 
     commands
         .insert_resource(font_system)
         .insert_resource(swash_cache)
         .insert_resource(viewport)
+        .insert_resource(cache)
         .insert_resource(atlas)
         .insert_resource(text_renderer)
         .insert_resource(RenderAssets::<TextBuffer>::new());
 }
 
+/// Create the text renderer of the UI pass, `samples` has to match the [`Msaa`] sample count
+pub(crate) fn create_ui_text_renderer(
+    atlas: &mut TextAtlas,
+    device: &RenderDevice,
+    samples: u32,
+) -> TextRenderer {
+    TextRenderer::new(
+        atlas,
+        device,
+        wgpu::MultisampleState {
+            count: samples,
+            ..Default::default()
+        },
+        Some(wgpu::DepthStencilState {
+            format: wgpu::TextureFormat::Depth32Float,
+            depth_write_enabled: true,
+            depth_compare: wgpu::CompareFunction::Less,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+    )
+}
+
 /// Inset necessary UI resources to app
 fn insert_ui_resources(mut commands: Commands, device: Res<RenderDevice>) {
     let node_transform_storage =
@@ -97,14 +119,34 @@ pub struct UiPlugin;
 
 impl Plugin for UiPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(insert_ui_resources)
+        app.init_resource::<Assets<TextureAtlas>>()
+            .init_resource::<Assets<Font>>()
+            .init_resource::<UiFonts>()
+            .init_resource::<UiPointer>()
+            .init_resource::<UiFocus>()
+            .init_resource::<UiNavigationRepeat>()
+            .init_resource::<UiRenderTargets>()
+            .register_event::<UiEvent>()
+            .register_event::<UiNavigation>()
+            .add_startup_system(insert_ui_resources)
             .add_startup_system(insert_ui_text_resources)
             .add_startup_system(register_ui_graph)
             .register_system(ui_interaction_update, phase::First)
+            .register_system(ui_event_dispatch_system, phase::First)
+            .register_system(ui_gamepad_navigation_system, phase::First)
+            .register_system(ui_keyboard_navigation_system, phase::First)
+            .register_system(ui_focus_navigation_system, phase::First)
+            .register_system(update_ui_cursor, phase::PreUpdate)
             .register_system(initialize_ui_nodes, phase::PreUpdate)
             .register_system(initialize_button_ui_nodes, phase::PreUpdate)
+            .register_system(load_ui_fonts, phase::PreUpdate)
+            .register_system(update_virtual_lists, phase::PreUpdate)
             .register_system(compute_nodes_and_transforms, phase::PostUpdate)
             .register_system(update_glyphon_viewport, phase::PreRender)
+            .register_system(
+                prepare_ui_render_target_images,
+                phase::PreRender.layer(layer::Pre),
+            )
             .register_system(update_ui_mesh_and_transforms, phase::PreRender);
     }
 }
@@ -3,9 +3,18 @@ pub use glyphon::{
     cosmic_text::Align,
 };
 
+pub use crate::window::config::CursorIcon;
+
 pub use super::{
     node::*,
-    text::Text,
-    interactivity::{Button, Interaction},
-    image::UiImage,
+    text::{Font, Text, TextBuffer, TextDraw, TextPass, UiFonts},
+    interactivity::{
+        Button, Interaction, UiEvent, UiEventKind, UiEventListener, UiEventPhase, UiPointer,
+    },
+    image::{TextureAtlas, UiImage, UiImageSource},
+    virtual_list::{RowBuilder, VirtualList},
+    render_target::UiRenderTarget,
+    cursor::ResizeEdges,
+    focus::{Focusable, NavDirection, UiFocus, UiNavigation, UiNavigationRepeat},
+    graph::layout::{LayoutError, LayoutNode, LayoutRect},
 };
@@ -0,0 +1,293 @@
+//! Rendering of UI trees into images instead of the window, e.g. for in-world screens,
+//! scoreboards or menus placed on 3D surfaces.
+//!
+//! A root node with a [`UiRenderTarget`] is laid out in the size of its image, and it's drawn
+//! with all of its children into the image by the `ui_target` graph node, before the `main` pass.
+//! The image can then be used by a [`Material`] like any other texture.
+//!
+//! ```ignore
+//! fn spawn_screen(
+//!     mut commands: Commands,
+//!     mut images: ResMut<Assets<Image>>,
+//!     mut materials: ResMut<Assets<Material>>,
+//!     mut meshes: ResMut<Assets<Mesh>>,
+//! ) {
+//!     let target = UiRenderTarget::new(&mut images, 512, 256);
+//!     let material = materials.add(Material {
+//!         base_color_texture: Some(target.image.clone()),
+//!         unlit: true,
+//!         ..Default::default()
+//!     });
+//!
+//!     commands
+//!         .spawn_empty()
+//!         .insert(Node {
+//!             width: Val::Percent(100.0),
+//!             height: Val::Percent(100.0),
+//!             ..Default::default()
+//!         })
+//!         .insert(target)
+//!         .with_children(|parent| {
+//!             parent.spawn_empty().insert(Node::default()).insert(Text::new("Score: 0"));
+//!         });
+//!
+//!     commands
+//!         .spawn_empty()
+//!         .insert(meshes.add(Plane::new(2.0, 1.0, false).mesh()))
+//!         .insert(material)
+//!         .insert(Transform::default());
+//! }
+//! ```
+//!
+//! The node with the target should have no parent. Nodes drawn into a render target don't receive
+//! pointer interactions and [`UiImage`](crate::ui::prelude::UiImage)s aren't drawn into targets.
+//! Resizing the image is applied with the next layout of the UI.
+
+use std::collections::{HashMap, HashSet};
+
+use glyphon::{Cache, Resolution, TextAtlas, TextRenderer, Viewport};
+use winit::dpi::PhysicalSize;
+
+use crate::{
+    prelude::*,
+    render_assets::{BindGroup, Buffer, RenderAssets},
+    renderer::newtype::{RenderDevice, RenderQueue, RenderSurfaceConfiguration},
+    ui::{
+        mesh::UiMesh,
+        node::{Node, VisibilityQuery},
+        plugin::create_ui_text_renderer,
+    },
+};
+
+/// Draws a root UI node and its children into [`Self::image`] instead of the window. For more
+/// information, see the [render target module](crate::ui::render_target).
+#[derive(Component, Clone, Debug)]
+pub struct UiRenderTarget {
+    /// Color target of the UI, must be created with [`Self::target_image`]
+    pub image: Handle<Image>,
+    /// Color the image is cleared with before the UI is drawn
+    pub clear_color: Color,
+}
+
+impl UiRenderTarget {
+    /// Create a new render target with a `width` x `height` image added to `images`
+    pub fn new(images: &mut Assets<Image>, width: u32, height: u32) -> Self {
+        Self {
+            image: images.add(Self::target_image(width, height)),
+            clear_color: color::TRANSPARENT,
+        }
+    }
+
+    /// Returns self with the clear color of the image
+    pub fn with_clear_color(mut self, clear_color: Color) -> Self {
+        self.clear_color = clear_color;
+        self
+    }
+
+    /// Returns an empty image which can be used as the color target of the UI and sampled by
+    /// materials. Its format is changed to the surface format before it's first drawn, since the
+    /// UI text is rendered in that format.
+    pub fn target_image(width: u32, height: u32) -> Image {
+        let mut image = Image::new_with_defaults(
+            vec![],
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        let descriptor = image.texture_descriptor.as_mut().unwrap();
+        descriptor.label = Some("UI Render Target Texture");
+        descriptor.usage =
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING;
+        image
+    }
+
+    /// Returns the size of the image, None if it doesn't exist
+    pub(crate) fn size(&self, images: &Assets<Image>) -> Option<PhysicalSize<u32>> {
+        images
+            .get(&self.image)
+            .map(|image| PhysicalSize::new(image.size.width, image.size.height))
+    }
+}
+
+/// UI of a single [`UiRenderTarget`], prepared in `update_ui_mesh_and_transforms`
+pub(crate) struct PreparedUiTarget {
+    pub(crate) image: Handle<Image>,
+    pub(crate) size: PhysicalSize<u32>,
+    pub(crate) clear_color: Color,
+    pub(crate) mesh: UiMesh,
+    pub(crate) mesh_transparent: UiMesh,
+    pub(crate) buffer: Option<Buffer>,
+    pub(crate) buffer_transparent: Option<Buffer>,
+    pub(crate) text_renderer: TextRenderer,
+    pub(crate) viewport: Viewport,
+    pub(crate) depth_view: wgpu::TextureView,
+}
+
+impl PreparedUiTarget {
+    /// Upload the meshes, after they were built
+    pub(crate) fn update_buffers(&mut self, device: &RenderDevice) {
+        self.buffer = Some(self.mesh.create_buffer(device));
+        self.buffer_transparent = Some(self.mesh_transparent.create_buffer(device));
+    }
+}
+
+/// Prepared UI of every [`UiRenderTarget`] by its root node. Used as a resource.
+#[derive(Default, Resource)]
+pub(crate) struct UiRenderTargets {
+    pub(crate) targets: HashMap<EntityId, PreparedUiTarget>,
+}
+
+impl UiRenderTargets {
+    /// Returns true if the prepared targets don't match `targets`
+    pub(crate) fn is_outdated(
+        &self,
+        targets: &HashMap<EntityId, (UiRenderTarget, PhysicalSize<u32>)>,
+    ) -> bool {
+        self.targets.len() != targets.len()
+            || targets.iter().any(|(id, (target, size))| {
+                self.targets
+                    .get(id)
+                    .is_none_or(|prepared| prepared.image != target.image || prepared.size != *size)
+            })
+    }
+
+    /// Keep only `targets`, prepare new ones and clear the meshes of all of them, so they can be
+    /// built again
+    pub(crate) fn begin(
+        &mut self,
+        targets: HashMap<EntityId, (UiRenderTarget, PhysicalSize<u32>)>,
+        device: &RenderDevice,
+        queue: &RenderQueue,
+        cache: &Cache,
+        atlas: &mut TextAtlas,
+    ) {
+        self.targets.retain(|id, _| targets.contains_key(id));
+
+        for (id, (target, size)) in targets {
+            let prepared = self.targets.entry(id).or_insert_with(|| PreparedUiTarget {
+                image: target.image.clone(),
+                size,
+                clear_color: target.clear_color,
+                mesh: UiMesh::new(),
+                mesh_transparent: UiMesh::new(),
+                buffer: None,
+                buffer_transparent: None,
+                // targets aren't multisampled
+                text_renderer: create_ui_text_renderer(atlas, device, 1),
+                viewport: Viewport::new(device, cache),
+                depth_view: create_depth_view(size, device),
+            });
+
+            if prepared.size != size {
+                prepared.depth_view = create_depth_view(size, device);
+            }
+            prepared.image = target.image;
+            prepared.size = size;
+            prepared.clear_color = target.clear_color;
+            prepared.mesh.clear();
+            prepared.mesh_transparent.clear();
+            prepared.viewport.update(
+                queue,
+                Resolution {
+                    width: size.width,
+                    height: size.height,
+                },
+            );
+        }
+    }
+}
+
+/// Creates a depth texture view matching a render target
+fn create_depth_view(size: PhysicalSize<u32>, device: &RenderDevice) -> wgpu::TextureView {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("ui_render_target_depth"),
+        size: wgpu::Extent3d {
+            width: size.width.max(1),
+            height: size.height.max(1),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Depth32Float,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    });
+
+    texture.create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+/// Returns the root node with a [`UiRenderTarget`] of every node drawn into a render target
+pub(crate) fn render_target_roots(query: &mut VisibilityQuery) -> HashMap<EntityId, EntityId> {
+    let targets = query
+        .cast::<EntityId, (With<Node>, With<UiRenderTarget>)>()
+        .iter_mut()
+        .into_iter()
+        .collect::<HashSet<_>>();
+    if targets.is_empty() {
+        return HashMap::new();
+    }
+
+    let parents = query
+        .iter_mut()
+        .into_iter()
+        .map(|(id, _, _, parent)| (id, parent.map(|parent| parent.id)))
+        .collect::<HashMap<_, _>>();
+
+    parents
+        .keys()
+        .copied()
+        .filter_map(|id| {
+            // walk up until a render target or the UI root
+            let mut current = Some(id);
+            while let Some(node) = current {
+                if targets.contains(&node) {
+                    return Some((id, node));
+                }
+                current = parents.get(&node).copied().flatten();
+            }
+            None
+        })
+        .collect()
+}
+
+/// Changes the format of render target images to the surface format, which the UI pipeline and
+/// text atlas use. Textures created with the old format are dropped.
+pub(crate) fn prepare_ui_render_target_images(
+    surface_config: Res<RenderSurfaceConfiguration>,
+    mut images: ResMut<Assets<Image>>,
+    mut textures: ResMut<RenderAssets<Texture>>,
+    mut bind_groups: ResMut<RenderAssets<BindGroup>>,
+    mut query: Query<&UiRenderTarget, With<Node>>,
+) {
+    let format = surface_config.format;
+    let mut changed = false;
+    for target in query.iter_mut() {
+        let Some(image) = images.get_mut(&target.image) else {
+            continue;
+        };
+        let Some(descriptor) = image.texture_descriptor.as_mut() else {
+            continue;
+        };
+        if descriptor.format == format {
+            continue;
+        }
+
+        descriptor.format = format;
+        descriptor.view_formats = &[];
+        if let Some(view_descriptor) = image.view_descriptor.as_mut() {
+            view_descriptor.format = Some(format);
+        }
+
+        textures.remove(&target.image);
+        changed = true;
+    }
+
+    // a material could have used the texture with the old format
+    if changed {
+        bind_groups.clear();
+    }
+}
@@ -0,0 +1,141 @@
+use std::{fmt::Debug, path::Path, sync::Arc};
+
+use glyphon::FontSystem;
+
+use crate::{
+    assets::{AssetLoader, AsyncLoadableAsset, LoadableAsset, io},
+    prelude::*,
+    render_assets::RenderAssets,
+};
+
+use super::{Text, TextBuffer};
+
+/// Font file data, `ttf`, `otf` or a font collection. Add it to [`UiFonts`] to use it for text.
+#[derive(Clone, Debug, crate::macros::Asset)]
+pub struct Font {
+    pub data: Arc<Vec<u8>>,
+}
+
+impl Font {
+    /// Create a new font from the raw file data
+    pub fn from_bytes(data: Vec<u8>) -> Self {
+        Self {
+            data: Arc::new(data),
+        }
+    }
+}
+
+impl LoadableAsset for Font {
+    fn load<P: AsRef<Path> + Debug>(_: &mut AssetLoader, _: &mut Resources, path: P) -> Self {
+        let bytes = io::read(path.as_ref())
+            .unwrap_or_else(|err| panic!("Failed to read font from '{:?}': {}", path, err));
+
+        Self::from_bytes(bytes)
+    }
+}
+
+impl AsyncLoadableAsset for Font {
+    fn decode(bytes: Vec<u8>, _: &Path) -> Result<Self, String> {
+        Ok(Self::from_bytes(bytes))
+    }
+}
+
+/// Fonts and glyph atlas settings used by UI text, on top of the system fonts.
+///
+/// Glyphs missing from a text's font are looked up in every loaded font, so adding CJK or emoji
+/// fonts to [`fonts`](Self::fonts) renders them instead of empty boxes. This is also the only way
+/// to get text on platforms without system fonts, like the web.
+#[derive(Resource, Debug)]
+pub struct UiFonts {
+    /// Fonts loaded into the font system once their assets are available
+    pub fonts: Vec<Handle<Font>>,
+    /// Family name used by text with the default sans serif family, e.g. "Noto Sans"
+    pub default_family: Option<String>,
+    /// Mark every glyph as unused before UI text is prepared, so glyphs which are no longer drawn
+    /// can be evicted when the atlas runs out of space, instead of growing it
+    pub trim_atlas: bool,
+    loaded: Vec<Handle<Font>>,
+    applied_family: Option<String>,
+}
+
+impl Default for UiFonts {
+    fn default() -> Self {
+        Self {
+            fonts: Vec::new(),
+            default_family: None,
+            trim_atlas: true,
+            loaded: Vec::new(),
+            applied_family: None,
+        }
+    }
+}
+
+impl UiFonts {
+    /// Returns self with `font` added
+    #[must_use]
+    pub fn with_font(mut self, font: Handle<Font>) -> Self {
+        self.fonts.push(font);
+        self
+    }
+
+    /// Returns self with new `default_family`
+    #[must_use]
+    pub fn with_default_family(mut self, family: impl Into<String>) -> Self {
+        self.default_family = Some(family.into());
+        self
+    }
+
+    /// Add `font` to the fonts
+    pub fn add_font(&mut self, font: Handle<Font>) {
+        if !self.fonts.contains(&font) {
+            self.fonts.push(font);
+        }
+    }
+
+    /// Whether every font has been loaded into the font system
+    pub fn is_loaded(&self) -> bool {
+        self.fonts.iter().all(|font| self.loaded.contains(font))
+    }
+}
+
+/// System which loads new [`UiFonts`] into the font system. Fonts whose assets are not available
+/// yet are retried every frame. When the fonts change, every text is shaped again.
+pub fn load_ui_fonts(
+    mut ui_fonts: ResMut<UiFonts>,
+    fonts: Res<Assets<Font>>,
+    mut font_system: ResMut<FontSystem>,
+    mut text_buffers: ResMut<RenderAssets<TextBuffer>>,
+    mut texts: Query<&mut Text>,
+) {
+    let ui_fonts = &mut *ui_fonts;
+    let mut changed = false;
+
+    for handle in &ui_fonts.fonts {
+        if ui_fonts.loaded.contains(handle) {
+            continue;
+        }
+
+        if let Some(font) = fonts.get(handle) {
+            font_system.db_mut().load_font_data(font.data.to_vec());
+            ui_fonts.loaded.push(handle.clone());
+            changed = true;
+        }
+    }
+
+    if ui_fonts.default_family != ui_fonts.applied_family {
+        if let Some(family) = &ui_fonts.default_family {
+            font_system.db_mut().set_sans_serif_family(family.clone());
+        }
+        ui_fonts.applied_family = ui_fonts.default_family.clone();
+        changed = true;
+    }
+
+    if !changed {
+        return;
+    }
+
+    // cached buffers were shaped with the old fonts, recreate them and relayout every text node,
+    // fetching the texts mutably marks them as changed
+    text_buffers.clear();
+    texts.iter_mut();
+}
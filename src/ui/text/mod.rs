@@ -1,6 +1,9 @@
+mod font;
+mod pass;
+
 use std::sync::Mutex;
 
-use glyphon::{Attrs, Buffer, FontSystem, Metrics, Shaping};
+use glyphon::{Attrs, AttrsOwned, Buffer, FontSystem, Metrics, Shaping};
 
 use crate::{
     macros::{Component, RenderAsset},
@@ -8,6 +11,9 @@ use crate::{
     render_assets::IntoRenderAsset,
 };
 
+pub use font::{Font, UiFonts, load_ui_fonts};
+pub use pass::{TextDraw, TextPass};
+
 // TODO: use newtype pattern and derive
 impl Resource for glyphon::FontSystem {}
 impl Resource for glyphon::TextRenderer {}
@@ -15,7 +21,7 @@ impl Resource for glyphon::TextAtlas {}
 impl Resource for glyphon::SwashCache {}
 impl Resource for glyphon::Viewport {}
 
-#[derive(Component)]
+#[derive(Component, Clone, Debug)]
 pub struct Text {
     pub content: String,
     pub font_size: f32,
@@ -24,12 +30,95 @@ pub struct Text {
     pub shaping: Shaping,
 }
 
+/// Shaped [`Text`], ready to be rendered. UI nodes create it as a render asset, custom graph
+/// nodes can build their own with [`TextBuffer::new`] and draw it with a [`TextPass`].
+///
+/// Every glyph carries the buffer's [`metadata`](Self::metadata), which is passed to the depth
+/// function when preparing text. It does not change when the text is re-shaped, so draw order can
+/// be resolved without touching the layout.
 #[derive(RenderAsset)]
 pub struct TextBuffer {
     pub buffer: Mutex<Buffer>,
+    metadata: usize,
+    /// Layout inputs of the last shaping
+    shaped: Mutex<Option<ShapedText>>,
+}
+
+/// Everything from [`Text`] which affects shaping
+#[derive(PartialEq)]
+struct ShapedText {
+    content: String,
+    font_size: f32,
+    line_height: f32,
+    attrs: AttrsOwned,
+    shaping: Shaping,
 }
 
 impl TextBuffer {
+    /// Shape `text` into a new buffer, glyphs use the metadata from its attributes
+    pub fn new(font_system: &mut FontSystem, text: &Text) -> Self {
+        Self::with_metadata(font_system, text, text.attrs.metadata)
+    }
+
+    /// Shape `text` into a new buffer, with `metadata` for every glyph
+    pub fn with_metadata(font_system: &mut FontSystem, text: &Text, metadata: usize) -> Self {
+        let metrics = Metrics::relative(text.font_size, text.line_height);
+        let buffer = Buffer::new(font_system, metrics);
+        let text_buffer = Self {
+            buffer: Mutex::new(buffer),
+            metadata,
+            shaped: Mutex::new(None),
+        };
+
+        text_buffer.set_text(font_system, text);
+        text_buffer
+    }
+
+    /// Returns the metadata of every glyph in the buffer
+    #[inline]
+    pub fn metadata(&self) -> usize {
+        self.metadata
+    }
+
+    /// Replace the content, font size and attributes of the buffer with `text`, and shape it again
+    pub fn set_text(&self, font_system: &mut FontSystem, text: &Text) {
+        let shaped = self.shaped_text(text);
+        let mut buffer = self.buffer.lock().unwrap();
+        let mut borrowed_buffer = buffer.borrow_with(font_system);
+
+        borrowed_buffer.set_metrics(Metrics::relative(text.font_size, text.line_height));
+        borrowed_buffer.set_size(None, None);
+        borrowed_buffer.set_text(&text.content, &shaped.attrs.as_attrs(), text.shaping);
+        borrowed_buffer.shape_until_scroll(true);
+
+        *self.shaped.lock().unwrap() = Some(shaped);
+    }
+
+    /// Reset the buffer size, and shape it again only if the content, font or shaping of `text`
+    /// changed. Returns true if the text was re-shaped.
+    pub fn update(&self, font_system: &mut FontSystem, text: &Text) -> bool {
+        let changed = self.shaped.lock().unwrap().as_ref() != Some(&self.shaped_text(text));
+
+        if changed {
+            self.set_text(font_system, text);
+        } else {
+            self.set_size(font_system, None, None);
+        }
+
+        changed
+    }
+
+    /// Returns the layout inputs of `text` with the buffer's metadata
+    fn shaped_text(&self, text: &Text) -> ShapedText {
+        ShapedText {
+            content: text.content.clone(),
+            font_size: text.font_size,
+            line_height: text.line_height,
+            attrs: AttrsOwned::new(&text.attrs.clone().metadata(self.metadata)),
+            shaping: text.shaping,
+        }
+    }
+
     /// Set buffer size
     pub fn set_size(&self, font_system: &mut FontSystem, width: Option<f32>, height: Option<f32>) {
         self.buffer
@@ -100,27 +189,20 @@ impl IntoRenderAsset<TextBuffer> for Text {
     fn create_render_asset(
         &self,
         world: &mut crate::prelude::World,
-        _: Option<crate::prelude::EntityId>,
+        entity_id: Option<crate::prelude::EntityId>,
     ) -> TextBuffer {
         let mut font_system = world.resources.get_mut::<FontSystem>();
 
-        let metrics = Metrics::relative(self.font_size, self.line_height);
-
-        let mut buffer = Buffer::new(&mut font_system, metrics);
-        let mut borrowed_buffer = buffer.borrow_with(&mut font_system);
-
-        borrowed_buffer.set_size(None, None);
-        borrowed_buffer.set_text(&self.content, &self.attrs, self.shaping);
-        borrowed_buffer.shape_until_scroll(true);
-
         // borrowed_buffer.set_wrap(Wrap::WordOrGlyph);
 
         // borrowed_buffer.lines.iter_mut().for_each(|line| {
         //     line.set_align(Some(Align::Center));
         // });
 
-        TextBuffer {
-            buffer: Mutex::new(buffer),
+        // ui text is keyed by its entity, the depth is looked up when preparing text areas
+        match entity_id {
+            Some(id) => TextBuffer::with_metadata(&mut font_system, self, id.index() as usize),
+            None => TextBuffer::new(&mut font_system, self),
         }
     }
 }
@@ -0,0 +1,202 @@
+use glam::Vec2;
+use glyphon::{FontSystem, SwashCache, TextArea, TextAtlas, TextBounds, TextRenderer, Viewport};
+
+use crate::{
+    macros::Resource,
+    math::Rect,
+    prelude::{Camera, Color, GlobalTransform, Msaa, Projection, World},
+    renderer::{
+        newtype::{RenderDevice, RenderQueue},
+        palette,
+    },
+};
+
+use super::TextBuffer;
+
+/// Single [`TextBuffer`] to draw with a [`TextPass`]
+pub struct TextDraw<'a> {
+    pub buffer: &'a TextBuffer,
+    /// Top left corner of the text in physical pixels, with the origin in the top left corner of
+    /// the viewport
+    pub position: Vec2,
+    /// Color of glyphs without a color attribute
+    pub color: Color,
+    pub scale: f32,
+    /// Clipping area in physical pixels, None to only clip to the viewport
+    pub bounds: Option<Rect>,
+}
+
+impl<'a> TextDraw<'a> {
+    /// Draw `buffer` with its top left corner at `position` in physical pixels
+    pub fn new(buffer: &'a TextBuffer, position: Vec2) -> Self {
+        Self {
+            buffer,
+            position,
+            color: palette::WHITE,
+            scale: 1.0,
+            bounds: None,
+        }
+    }
+
+    /// Draw `buffer` centered on `world_position`, as seen by a camera with `projection` and
+    /// `global_transform`. Useful for damage numbers and labels above entities.
+    ///
+    /// Returns `None` if the position is not in front of the camera, see
+    /// [`Camera::world_to_viewport`].
+    pub fn at_world_position(
+        buffer: &'a TextBuffer,
+        world_position: glam::Vec3,
+        projection: &Projection,
+        global_transform: &GlobalTransform,
+        viewport_size: Vec2,
+    ) -> Option<Self> {
+        let center =
+            Camera::world_to_viewport(projection, global_transform, viewport_size, world_position)?;
+        let size = Vec2::new(buffer.width(), buffer.height());
+
+        Some(Self::new(buffer, center - size / 2.0))
+    }
+
+    /// Set the default text color
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Set the scale of the text
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Set the clipping area in physical pixels
+    pub fn with_bounds(mut self, bounds: Rect) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+}
+
+/// Text renderer for custom graph nodes, independent of the UI layout. Every node which draws
+/// text needs its own pass, since prepared text is kept until the next [`prepare`](Self::prepare).
+///
+/// Text is drawn in screen space, on top of what the render pass already contains. Requires the
+/// [`UiPlugin`](crate::ui::plugin::UiPlugin), which provides the fonts and the glyph atlas.
+///
+/// ```ignore
+/// fn damage_numbers_render_system(world: &mut World, encoder: &mut RenderCommandEncoder, ...) {
+///     let draws = numbers
+///         .iter()
+///         .filter_map(|n| TextDraw::at_world_position(&n.buffer, n.position, projection, transform, size))
+///         .collect::<Vec<_>>();
+///
+///     let mut text_pass = world.resources.get_mut::<DamageNumbersPass>();
+///     text_pass.prepare(world, draws);
+///
+///     let mut render_pass = encoder.begin_render_pass(...);
+///     text_pass.render(world, &mut render_pass);
+/// }
+/// ```
+#[derive(Resource)]
+pub struct TextPass {
+    renderer: TextRenderer,
+}
+
+impl TextPass {
+    /// Create a new text pass, `depth_stencil` has to match the depth target of the render pass it
+    /// is used in, or be None if there is no depth target
+    ///
+    /// # Note
+    /// Uses the current [`Msaa`] sample count, so a pass rendering to the surface has to be
+    /// created again when it changes.
+    pub fn new(world: &mut World, depth_stencil: Option<wgpu::DepthStencilState>) -> Self {
+        let device = world.resources.get::<RenderDevice>();
+        let mut atlas = world.resources.get_mut::<TextAtlas>();
+        let samples = world
+            .resources
+            .try_get::<Msaa>()
+            .map_or(1, |msaa| msaa.samples());
+
+        let renderer = TextRenderer::new(
+            &mut atlas,
+            &device,
+            wgpu::MultisampleState {
+                count: samples,
+                ..Default::default()
+            },
+            depth_stencil,
+        );
+
+        Self { renderer }
+    }
+
+    /// Prepare `texts` for the next [`render`](Self::render), replacing the previously prepared
+    /// ones. Has to be called before the render pass is created.
+    pub fn prepare<'a>(
+        &mut self,
+        world: &mut World,
+        texts: impl IntoIterator<Item = TextDraw<'a>>,
+    ) {
+        let device = world.resources.get::<RenderDevice>();
+        let queue = world.resources.get::<RenderQueue>();
+        let mut font_system = world.resources.get_mut::<FontSystem>();
+        let mut atlas = world.resources.get_mut::<TextAtlas>();
+        let viewport = world.resources.get::<Viewport>();
+        let mut swash_cache = world.resources.get_mut::<SwashCache>();
+
+        // buffers stay locked until the text is prepared
+        let texts = texts.into_iter().collect::<Vec<_>>();
+        let buffers = texts
+            .iter()
+            .map(|text| text.buffer.buffer.lock().unwrap())
+            .collect::<Vec<_>>();
+
+        let text_areas = texts.iter().zip(&buffers).map(|(text, buffer)| {
+            let bounds = match &text.bounds {
+                Some(bounds) => TextBounds {
+                    left: bounds.min.x as i32,
+                    top: bounds.min.y as i32,
+                    right: bounds.max.x as i32,
+                    bottom: bounds.max.y as i32,
+                },
+                None => TextBounds {
+                    left: i32::MIN,
+                    top: i32::MIN,
+                    right: i32::MAX,
+                    bottom: i32::MAX,
+                },
+            };
+
+            TextArea {
+                buffer,
+                left: text.position.x,
+                top: text.position.y,
+                scale: text.scale,
+                bounds,
+                default_color: text.color.into(),
+                custom_glyphs: &[],
+            }
+        });
+
+        if let Err(err) = self.renderer.prepare(
+            &device,
+            &queue,
+            &mut font_system,
+            &mut atlas,
+            &viewport,
+            text_areas,
+            &mut swash_cache,
+        ) {
+            tracing::error!("Failed to prepare text: {err:?}");
+        }
+    }
+
+    /// Draw the prepared text into `render_pass`
+    pub fn render(&self, world: &World, render_pass: &mut wgpu::RenderPass) {
+        let atlas = world.resources.get::<TextAtlas>();
+        let viewport = world.resources.get::<Viewport>();
+
+        if let Err(err) = self.renderer.render(&atlas, &viewport, render_pass) {
+            tracing::error!("Failed to render text: {err:?}");
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use crate::{
+    event::{EventReader, MouseScrollDelta, MouseWheel},
+    prelude::*,
+    system::commands::EntityCommands,
+    ui::prelude::*,
+};
+
+/// Approximate number of pixels in one scroll line, used to normalize touchpad scrolling
+const PIXELS_PER_LINE: f32 = 20.0;
+
+/// Builds the content of a row for an index, see [`VirtualList`]
+pub type RowBuilder = Box<dyn Fn(EntityCommands, usize) + Send + Sync>;
+
+/// UI list which only spawns the rows that fit into its height. Rows are children of the list
+/// node, when scrolling, rows which leave the list are recycled for the rows that come into view.
+///
+/// The list node needs a fixed height, rows are `row_height` pixels tall. The builder is called
+/// with the row entity whenever the row is assigned to an index, it should insert the row's
+/// components, e.g. a [`Text`]. A recycled row keeps its components, and its children are
+/// despawned before it's built again, so builders can also spawn children.
+///
+/// Scrolling with the mouse wheel while hovering the list snaps to whole rows, since nodes are not
+/// clipped by their parent.
+///
+/// ```ignore
+/// commands.spawn_empty()
+///     .insert(Node { height: Val::Px(400.0), ..Default::default() })
+///     .insert(VirtualList::new(10_000, 20.0, |row, index| {
+///         row.insert(Text::new(format!("Entry {index}")));
+///     }));
+/// ```
+#[derive(Component)]
+pub struct VirtualList {
+    /// Total amount of rows
+    pub len: usize,
+    /// Height of a row in pixels
+    pub row_height: f32,
+    /// Rows scrolled per mouse wheel line
+    pub scroll_speed: f32,
+    /// Index of the first visible row
+    first: usize,
+    /// Spawned row entities and the index they are built for
+    rows: Vec<(EntityId, usize)>,
+    builder: RowBuilder,
+    /// Rebuild every row on the next update
+    dirty: bool,
+}
+
+impl VirtualList {
+    /// Create a new list of `len` rows, each `row_height` pixels tall, built by `builder`
+    pub fn new(
+        len: usize,
+        row_height: f32,
+        builder: impl Fn(EntityCommands, usize) + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            len,
+            row_height,
+            scroll_speed: 3.0,
+            first: 0,
+            rows: Vec::new(),
+            builder: Box::new(builder),
+            dirty: false,
+        }
+    }
+
+    /// Returns self with new `scroll_speed` in rows per mouse wheel line
+    #[inline]
+    #[must_use]
+    pub fn with_scroll_speed(mut self, scroll_speed: f32) -> Self {
+        self.scroll_speed = scroll_speed;
+        self
+    }
+
+    /// Returns the index of the first visible row
+    #[inline]
+    pub fn first_visible(&self) -> usize {
+        self.first
+    }
+
+    /// Returns the indices of the rows which are currently spawned
+    pub fn visible_range(&self) -> std::ops::Range<usize> {
+        self.first..self.first + self.rows.len()
+    }
+
+    /// Returns the row entity built for `index`, if it's visible
+    pub fn row_entity(&self, index: usize) -> Option<EntityId> {
+        self.rows
+            .iter()
+            .find(|(_, row_index)| *row_index == index)
+            .map(|(id, _)| *id)
+    }
+
+    /// Scroll so `index` is the first visible row, clamped at the end of the list
+    pub fn scroll_to(&mut self, index: usize) {
+        self.first = index;
+    }
+
+    /// Scroll by `rows`, negative values scroll towards the start
+    pub fn scroll_by(&mut self, rows: isize) {
+        self.first = self.first.saturating_add_signed(rows);
+    }
+
+    /// Build every visible row again on the next update, e.g. after the underlying data changed
+    pub fn refresh(&mut self) {
+        self.dirty = true;
+    }
+}
+
+/// Returns the vertical scroll of this frame in lines, positive when scrolling up
+fn scroll_lines<'a>(events: impl Iterator<Item = &'a MouseWheel>) -> f32 {
+    events
+        .map(|event| match event.delta {
+            MouseScrollDelta::LineDelta(_, y) => y,
+            MouseScrollDelta::PixelDelta(position) => position.y as f32 / PIXELS_PER_LINE,
+        })
+        .sum()
+}
+
+/// System to scroll [`VirtualList`] nodes and spawn, recycle or despawn their rows. Uses the
+/// computed height of the previous layout.
+pub fn update_virtual_lists(
+    mut commands: Commands,
+    mut mouse_wheel: EventReader<MouseWheel>,
+    mut q: Query<()>,
+) {
+    let lines = scroll_lines(mouse_wheel.read());
+
+    let mut list_query = q.cast::<(
+        EntityId,
+        &mut VirtualList,
+        &ComputedNode,
+        Option<&Interaction>,
+    ), ()>();
+
+    for (id, list, computed, interaction) in list_query.iter_mut() {
+        // lists need an interaction to know when they are hovered
+        let Some(interaction) = interaction else {
+            commands.entity(id).insert(Interaction::default());
+            continue;
+        };
+
+        if *interaction != Interaction::None && lines != 0.0 {
+            let rows = (-lines * list.scroll_speed).round() as isize;
+            list.scroll_by(rows);
+        }
+
+        let visible = if list.row_height > 0.0 {
+            ((computed.height.content / list.row_height).floor() as usize).min(list.len)
+        } else {
+            0
+        };
+        list.first = list.first.min(list.len - visible);
+        let range = list.first..list.first + visible;
+
+        // rows which left the list are free to be built for the rows which came into view
+        let dirty = std::mem::take(&mut list.dirty);
+        let (mut kept, mut free): (Vec<_>, Vec<_>) = list
+            .rows
+            .drain(..)
+            .partition(|(_, index)| !dirty && range.contains(index));
+
+        let missing = range
+            .clone()
+            .filter(|index| !kept.iter().any(|(_, kept_index)| kept_index == index))
+            .collect::<Vec<_>>();
+
+        if missing.is_empty() && free.is_empty() {
+            list.rows = kept;
+            continue;
+        }
+
+        for index in missing {
+            let row = match free.pop() {
+                Some((row, _)) => {
+                    // recycled rows lose the children of their previous build
+                    if let Some(children) = q.cast::<&Children, ()>().get(row) {
+                        for child in &children.ids {
+                            commands.entity(*child).despawn_recursive();
+                        }
+                    }
+                    row
+                }
+                None => {
+                    let mut row = None;
+                    commands.entity(id).with_children(|parent| {
+                        row = Some(parent.spawn_empty().entity_id());
+                    });
+                    row.expect("row is spawned")
+                }
+            };
+
+            let row_commands = commands.entity(row).insert(Node {
+                height: Val::Px(list.row_height),
+                ..Default::default()
+            });
+            (list.builder)(row_commands, index);
+            kept.push((row, index));
+        }
+
+        for (row, _) in free {
+            commands.entity(row).despawn_recursive();
+        }
+
+        // children are laid out in order, so sort the rows by their index
+        kept.sort_by_key(|(_, index)| *index);
+        let order = kept.iter().map(|(row, _)| *row).collect::<Vec<_>>();
+        commands
+            .entity(id)
+            .entry::<Children>()
+            .and_modify(move |children| {
+                children.ids.sort_by_key(|child| {
+                    order
+                        .iter()
+                        .position(|row| row == child)
+                        .unwrap_or(usize::MAX)
+                });
+            });
+
+        list.rows = kept;
+    }
+}
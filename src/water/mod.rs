@@ -0,0 +1,141 @@
+//! # Water plugin
+//! Renders reflective water surfaces. The scene is rendered a second time through a camera
+//! mirrored about the water plane into a texture, which the water material samples.
+//!
+//! ## Usage
+//!
+//! - Add the [`WaterPlugin`] to the app, it is not part of the [`DefaultPlugins`].
+//! - Spawn an entity with a [`Water`], a [`PlanarReflection`] and a [`Transform`]. The water
+//!   surface is a rectangle in the local `XZ` plane centered at the origin, facing `+Y`.
+//! ```ignore
+//! let reflection = PlanarReflection::new(&mut images, 1024, 1024);
+//! commands
+//!     .spawn_empty()
+//!     .insert(Water::new(Vec2::splat(200.0)))
+//!     .insert(reflection)
+//!     .insert(Transform::default());
+//! ```
+//!
+//! ## Planar reflections
+//!
+//! The reflection path is not tied to water. Every [`PlanarReflection`] renders the meshes of
+//! the standard pipeline through the active camera mirrored about the entity's local `XZ` plane,
+//! into its [`target`](PlanarReflection::target) image. Any custom material can bind the target
+//! and sample it at the screen position of a fragment on the plane.
+//!
+//! Geometry on the far side of the plane is clipped by an oblique near plane, so objects under
+//! water don't show up in the reflection. The reflection uses the visibility of the active
+//! camera, so meshes culled by its frustum are missing from the reflection as well. Terrains,
+//! sorted 2D meshes and other water surfaces are not reflected.
+
+mod reflection;
+mod render;
+
+pub mod prelude {
+    pub use super::{PlanarReflection, Water, WaterPlugin};
+}
+
+use glam::Vec2;
+
+use crate::{
+    palette,
+    plugins::RenderPlugin,
+    prelude::*,
+    render_assets::{BindGroup, IntoRenderAsset},
+};
+
+pub use reflection::{
+    PlanarReflection, REFLECTION_FORMAT, ReflectionRenderCache, oblique_projection,
+    reflection_matrix,
+};
+
+/// Plugin which adds the planar reflection and water render nodes
+pub struct WaterPlugin;
+
+impl Plugin for WaterPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ReflectionRenderCache>()
+            .add_startup_system(reflection::register_reflection_graph)
+            .add_startup_system(render::register_water_graph)
+            .register_system(reflection::prepare_reflections_system, phase::PreRender);
+    }
+
+    fn dependencies(&self) -> Vec<PluginId> {
+        vec![PluginId::of::<RenderPlugin>()]
+    }
+}
+
+/// Water surface material, requires a [`PlanarReflection`] and a [`Transform`] on the same
+/// entity.
+///
+/// The reflection is blended over the water color by a fresnel term, so the surface is more
+/// transparent when viewed from above and mirror-like at grazing angles.
+#[derive(Component, Clone, Debug)]
+pub struct Water {
+    /// Local size of the surface in the `XZ` plane
+    pub size: Vec2,
+    /// Color of the water, the alpha is its opacity where nothing is reflected
+    pub color: Color,
+    /// Minimum amount of reflection, 0.0 only uses the fresnel term and 1.0 is a mirror
+    pub reflectivity: f32,
+    /// Strength of the wave distortion of the reflection, in screen space units
+    pub distortion: f32,
+    /// Frequency of the waves in world units
+    pub wave_scale: f32,
+    /// Speed of the waves
+    pub wave_speed: f32,
+}
+
+impl Water {
+    /// Create a new water surface with default settings
+    pub fn new(size: Vec2) -> Self {
+        Self {
+            size,
+            color: Color::new(0.05, 0.2, 0.3, 0.8),
+            reflectivity: 0.1,
+            distortion: 0.01,
+            wave_scale: 0.5,
+            wave_speed: 1.0,
+        }
+    }
+
+    /// Returns self with new `color`
+    #[inline]
+    #[must_use]
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Returns self with new `reflectivity`
+    #[inline]
+    #[must_use]
+    pub fn with_reflectivity(mut self, reflectivity: f32) -> Self {
+        self.reflectivity = reflectivity;
+        self
+    }
+
+    /// Returns self with new wave settings
+    #[inline]
+    #[must_use]
+    pub fn with_waves(mut self, distortion: f32, wave_scale: f32, wave_speed: f32) -> Self {
+        self.distortion = distortion;
+        self.wave_scale = wave_scale;
+        self.wave_speed = wave_speed;
+        self
+    }
+}
+
+impl IntoRenderAsset<BindGroup> for Water {
+    fn create_render_asset(&self, world: &mut World, entity_id: Option<EntityId>) -> BindGroup {
+        let id = entity_id.expect("EntityId should be provided for Water BindGroup");
+        let target = world
+            .entities
+            .get_component::<PlanarReflection>(id)
+            .map(|reflection| reflection.target.clone());
+
+        BindGroup::build("water")
+            .add_texture(&target, world, palette::BLACK, None, None)
+            .finish(&world.resources.get())
+    }
+}
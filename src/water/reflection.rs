@@ -0,0 +1,394 @@
+use std::collections::HashMap;
+
+use glam::{Mat4, Vec3, Vec4};
+
+use crate::{
+    assets::ShaderLoader,
+    core::{
+        graph::*,
+        lighting::LightAndShadowManager,
+        standard::{
+            grouped::GroupedInstances,
+            rendering::{
+                DrawCounts, batched_draw_order, create_main_pipeline_builder, draw_instance_groups,
+            },
+        },
+    },
+    prelude::*,
+    render_assets::{
+        BindGroup, Buffer, RenderAssetEntry, RenderAssets, StagingBelt, TransformStorage,
+        pipeline::PipelineBuilder,
+    },
+    renderer::newtype::{RenderCommandEncoder, RenderDevice, RenderSurfaceConfiguration},
+};
+
+/// Texture format of planar reflection targets
+pub const REFLECTION_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+/// Renders the scene mirrored about the entity's local `XZ` plane into [`Self::target`], as seen
+/// by the first active camera. Requires a [`Transform`]. With several active cameras, like in
+/// split-screen, every view samples the reflection of the first one.
+///
+/// A fragment on the plane finds its reflection in the target at its own screen position, so
+/// materials sample it with the normalized device coordinates of the fragment.
+#[derive(Component, Clone, Debug)]
+pub struct PlanarReflection {
+    /// Color target of the reflection, must be created with [`Self::target_image`]
+    pub target: Handle<Image>,
+    /// Offset of the clip plane along the plane normal, geometry up to this distance under the
+    /// plane is still reflected to hide seams where objects intersect the plane
+    pub clip_offset: f32,
+}
+
+impl PlanarReflection {
+    /// Create a new planar reflection with a `width` x `height` target added to `images`
+    pub fn new(images: &mut Assets<Image>, width: u32, height: u32) -> Self {
+        Self {
+            target: images.add(Self::target_image(width, height)),
+            clip_offset: 0.05,
+        }
+    }
+
+    /// Returns an empty image which can be used as the color target of a reflection and sampled
+    /// by materials
+    pub fn target_image(width: u32, height: u32) -> Image {
+        let mut image = Image::new_with_defaults(
+            vec![],
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        let descriptor = image.texture_descriptor.as_mut().unwrap();
+        descriptor.label = Some("Planar Reflection Texture");
+        descriptor.format = REFLECTION_FORMAT;
+        descriptor.usage =
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING;
+        image
+    }
+
+    /// Returns the world space normal and distance of the reflection plane, `n · x + d = 0`
+    pub fn plane(&self, global_transform: &GlobalTransform) -> (Vec3, f32) {
+        let normal = global_transform
+            .matrix
+            .transform_vector3(Vec3::Y)
+            .normalize();
+        let d = -normal.dot(global_transform.translation());
+
+        (normal, d)
+    }
+}
+
+/// Returns the matrix which mirrors points about the plane `n · x + d = 0`, `normal` must be
+/// normalized
+pub fn reflection_matrix(normal: Vec3, d: f32) -> Mat4 {
+    let n = normal;
+    Mat4::from_cols(
+        Vec4::new(
+            1.0 - 2.0 * n.x * n.x,
+            -2.0 * n.x * n.y,
+            -2.0 * n.x * n.z,
+            0.0,
+        ),
+        Vec4::new(
+            -2.0 * n.y * n.x,
+            1.0 - 2.0 * n.y * n.y,
+            -2.0 * n.y * n.z,
+            0.0,
+        ),
+        Vec4::new(
+            -2.0 * n.z * n.x,
+            -2.0 * n.z * n.y,
+            1.0 - 2.0 * n.z * n.z,
+            0.0,
+        ),
+        (-2.0 * d * n).extend(1.0),
+    )
+}
+
+/// Returns `projection` with its near plane replaced by `clip_plane`, given in view space.
+/// Points on the positive side of the plane are kept, the camera must be on its negative side.
+///
+/// The far plane is adjusted to keep the depth range of `0.0..=1.0`, which reduces the depth
+/// precision the more the clip plane is tilted from the original near plane.
+pub fn oblique_projection(projection: Mat4, clip_plane: Vec4) -> Mat4 {
+    // view space corner of the frustum opposite to the clip plane
+    let corner =
+        projection.inverse() * Vec4::new(clip_plane.x.signum(), clip_plane.y.signum(), 1.0, 1.0);
+    let row = clip_plane / clip_plane.dot(corner);
+
+    let mut result = projection;
+    result.x_axis.z = row.x;
+    result.y_axis.z = row.y;
+    result.z_axis.z = row.z;
+    result.w_axis.z = row.w;
+    result
+}
+
+/// GPU side of a single planar reflection
+struct PreparedReflection {
+    target: RenderAssetEntry<Texture>,
+    target_size: wgpu::Extent3d,
+    depth_view: wgpu::TextureView,
+    camera_buffer: Buffer,
+    camera_bind_group: BindGroup,
+    clear_color: Color,
+    /// Whether the reflection was updated for the active camera this frame
+    active: bool,
+}
+
+/// Render cache for every [`PlanarReflection`], holds the mirrored camera buffers and depth
+/// targets. Updated in [`prepare_reflections_system`].
+#[derive(Default, crate::macros::Resource)]
+pub struct ReflectionRenderCache {
+    reflections: HashMap<EntityId, PreparedReflection>,
+}
+
+impl ReflectionRenderCache {
+    /// Returns the amount of reflections which will be rendered this frame
+    pub fn active_reflections(&self) -> usize {
+        self.reflections.values().filter(|r| r.active).count()
+    }
+}
+
+/// Creates a depth texture view matching a reflection target
+fn create_depth_view(size: wgpu::Extent3d, device: &RenderDevice) -> wgpu::TextureView {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("planar_reflection_depth"),
+        size,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Depth32Float,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    });
+
+    texture.create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+/// Pre-render system to mirror the active camera about every reflection plane, and update the
+/// reflection camera buffers and targets.
+pub(crate) fn prepare_reflections_system(
+    world: &mut World,
+    device: Res<RenderDevice>,
+    mut belt: ResMut<StagingBelt>,
+    images: Res<Assets<Image>>,
+    mut textures: ResMut<RenderAssets<Texture>>,
+    mut cache: ResMut<ReflectionRenderCache>,
+    mut query: Query<(EntityId, &PlanarReflection, &GlobalTransform), Without<Hidden>>,
+) {
+    // extract the active camera
+    let camera = query
+        .cast::<(&Camera, &Projection, &GlobalTransform, Option<&Exposure>), With<Camera3D>>()
+        .iter_mut()
+        .into_iter()
+        .find(|(camera, ..)| camera.active)
+        .map(|(camera, projection, transform, exposure)| {
+            let projection =
+                Mat4::from_cols_array_2d(&projection.get_view_projection_matrix(&Mat4::IDENTITY));
+            let exposure = exposure.map_or(1.0, Exposure::exposure);
+            (camera.clear_color, projection, transform.matrix, exposure)
+        });
+
+    for prepared in cache.reflections.values_mut() {
+        prepared.active = false;
+    }
+
+    let Some((clear_color, projection, camera_matrix, exposure)) = camera else {
+        return;
+    };
+    let camera_position = camera_matrix.w_axis.truncate();
+
+    let mut alive = Vec::new();
+    for (id, reflection, global_transform) in query.iter_mut() {
+        alive.push(id);
+
+        let Some(target_size) = images.get(&reflection.target).map(|image| image.size) else {
+            continue;
+        };
+
+        // mirror about the side of the plane the camera is on
+        let (mut normal, mut d) = reflection.plane(global_transform);
+        if normal.dot(camera_position) + d < 0.0 {
+            normal = -normal;
+            d = -d;
+        }
+
+        let mirror = reflection_matrix(normal, d);
+        let view = (mirror * camera_matrix).inverse();
+
+        // mirrored geometry from the camera side ends up behind the plane, keep only that side
+        let world_clip_plane = (-normal).extend(-d + reflection.clip_offset);
+        let view_clip_plane = camera_matrix.transpose() * world_clip_plane;
+        let view_projection = oblique_projection(projection, view_clip_plane) * view;
+
+        let mirrored_position = mirror.transform_point3(camera_position);
+        let mut data = view_projection.to_cols_array().to_vec();
+        data.extend([
+            mirrored_position.x,
+            mirrored_position.y,
+            mirrored_position.z,
+            exposure,
+        ]);
+
+        let target = textures.get_by_handle(&reflection.target, world);
+        let prepared = cache.reflections.entry(id).or_insert_with(|| {
+            let camera_buffer = Buffer::new("planar_reflection_camera").create_uniform_buffer(
+                &data,
+                Some(wgpu::BufferUsages::COPY_DST),
+                &device,
+            );
+            let camera_bind_group = BindGroup::build("planar_reflection_camera")
+                .add_uniform_buffer(
+                    camera_buffer
+                        .uniform
+                        .as_ref()
+                        .expect("Camera buffer should be uniform"),
+                    wgpu::ShaderStages::VERTEX_FRAGMENT,
+                )
+                .finish(&device);
+
+            PreparedReflection {
+                target: target.clone(),
+                target_size,
+                depth_view: create_depth_view(target_size, &device),
+                camera_buffer,
+                camera_bind_group,
+                clear_color,
+                active: false,
+            }
+        });
+
+        if prepared.target_size != target_size {
+            prepared.target_size = target_size;
+            prepared.depth_view = create_depth_view(target_size, &device);
+        }
+
+        let camera_buffer = prepared
+            .camera_buffer
+            .uniform
+            .as_ref()
+            .expect("Camera buffer should be uniform");
+        belt.write(camera_buffer, 0, bytemuck::cast_slice(&data), &device);
+
+        prepared.target = target;
+        prepared.clear_color = clear_color;
+        prepared.active = true;
+    }
+
+    // drop despawned reflections
+    cache.reflections.retain(|id, _| alive.contains(id));
+}
+
+/// Startup system to register the planar reflection graph node
+pub(crate) fn register_reflection_graph(
+    graph: &mut RenderGraph,
+    device: Res<RenderDevice>,
+    surface_config: Res<RenderSurfaceConfiguration>,
+    mut shader_loader: ResMut<ShaderLoader>,
+) {
+    // Mirroring flips the winding order, so back faces are the ones facing the mirrored camera
+    let mut pipeline_builder =
+        create_main_pipeline_builder(&device, &mut shader_loader, &surface_config)
+            .set_label("planar_reflection_pipeline")
+            .set_primitive_state(wgpu::PrimitiveState {
+                cull_mode: Some(wgpu::Face::Front),
+                ..PipelineBuilder::default_primitive_state()
+            });
+    pipeline_builder.color_targets.clear();
+    let pipeline_builder = pipeline_builder.add_color_format(REFLECTION_FORMAT);
+
+    // Targets are owned by the reflections, the render system begins its own passes
+    let node = GraphNodeBuilder::new("reflection")
+        .set_pipeline(pipeline_builder)
+        .set_custom_system(reflection_render_system)
+        .set_color_target(NodeColorTarget::None)
+        .set_depth_target(NodeDepthTarget::None)
+        .run_after("shadow")
+        .run_before("main")
+        .build();
+
+    graph.add(node);
+}
+
+/// Planar reflection graph node rendering system, draws the standard instance groups into every
+/// active reflection target
+fn reflection_render_system(
+    graph_ctx: Res<RenderContext>,
+
+    world: &mut World,
+    encoder: &mut RenderCommandEncoder,
+    mut buffers: ResMut<RenderAssets<Buffer>>,
+    mut bind_groups: ResMut<RenderAssets<BindGroup>>,
+    grouped: Res<GroupedInstances>,
+    cache: Res<ReflectionRenderCache>,
+) {
+    if cache.active_reflections() == 0 {
+        return;
+    }
+
+    let manager = world.resources.get::<LightAndShadowManager>();
+    let transforms_storage = world.resources.get::<TransformStorage>();
+    let manager_bind_group = bind_groups.get_by_resource(&manager, world, false);
+
+    let pipeline = unsafe { &*graph_ctx.node }
+        .data
+        .pipeline
+        .as_ref()
+        .expect("Pipeline should have been generated by now")
+        .render_pipeline();
+
+    let mut counts = DrawCounts::default();
+    for prepared in cache.reflections.values().filter(|r| r.active) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("planar reflection render pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &prepared.target.view,
+                depth_slice: None,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(prepared.clear_color.into()),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &prepared.depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: wgpu::StoreOp::Discard,
+                }),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        render_pass.set_pipeline(pipeline);
+
+        // Set light count push constant
+        render_pass.set_push_constants(
+            wgpu::ShaderStages::FRAGMENT,
+            0,
+            bytemuck::cast_slice(&[manager.storage.count() as u32]),
+        );
+
+        render_pass.set_bind_group(1, transforms_storage.bind_group(), &[]);
+        render_pass.set_bind_group(2, &prepared.camera_bind_group, &[]);
+        render_pass.set_bind_group(3, &*manager_bind_group, &[]);
+        counts.bind_group_changes += 3;
+
+        let groups = batched_draw_order(&grouped.groups, &mut bind_groups, world);
+        counts += draw_instance_groups(
+            &mut render_pass,
+            groups,
+            &mut buffers,
+            &mut bind_groups,
+            world,
+        );
+    }
+
+    counts.record(&graph_ctx);
+}
@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+
+use crate::{
+    assets::ShaderLoader,
+    core::{graph::*, standard::rendering::surface_size},
+    prelude::*,
+    render_assets::{BindGroup, Pipeline, RenderAssets, pipeline::PipelineBuilder},
+    renderer::newtype::{RenderCommandEncoder, RenderDevice, RenderSurfaceConfiguration},
+};
+
+use super::{PlanarReflection, Water};
+
+/// Push constants of the water pipeline
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct WaterConstants {
+    model: [[f32; 4]; 4],
+    color: [f32; 4],
+    size: [f32; 2],
+    time: f32,
+    reflectivity: f32,
+    distortion: f32,
+    wave_scale: f32,
+    wave_speed: f32,
+    _padding: f32,
+}
+
+/// Startup system to register the water graph node
+pub(crate) fn register_water_graph(
+    graph: &mut RenderGraph,
+    device: Res<RenderDevice>,
+    surface_config: Res<RenderSurfaceConfiguration>,
+    mut shader_loader: ResMut<ShaderLoader>,
+) {
+    let pipeline_builder =
+        create_water_pipeline_builder(&device, &surface_config, &mut shader_loader);
+
+    let node = GraphNodeBuilder::new("water")
+        .set_pipeline(pipeline_builder)
+        .set_custom_system(water_render_system)
+        .set_color_target(NodeColorTarget::Surface)
+        .set_depth_target(NodeDepthTarget::Node("main".to_string()))
+        .run_after("main")
+        .run_after("terrain")
+        .run_before("main_2d")
+        .build();
+
+    graph.add(node);
+}
+
+/// Water graph node rendering system, draws every water surface with its reflection
+fn water_render_system(
+    graph_ctx: Res<RenderContext>,
+
+    world: &mut World,
+    encoder: &mut RenderCommandEncoder,
+    mut bind_groups: ResMut<RenderAssets<BindGroup>>,
+    time: Res<Time>,
+    mut targets: Local<HashMap<EntityId, Handle<Image>>>,
+
+    mut water_query: Query<
+        (EntityId, &Water, &PlanarReflection, &GlobalTransform),
+        Without<Hidden>,
+    >,
+    rendered: Res<RenderedWindow>,
+) {
+    let cameras = Camera::rendering_to(
+        water_query
+            .cast::<(EntityId, &Camera), With<Camera3D>>()
+            .iter_mut(),
+        &rendered,
+    );
+    if cameras.is_empty() {
+        return;
+    }
+
+    let surfaces = water_query.iter_mut();
+    if surfaces.is_empty() {
+        targets.clear();
+        return;
+    }
+
+    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("water render pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: unsafe { &*graph_ctx.color_target.expect("water color target is None") },
+            depth_slice: None,
+            resolve_target: graph_ctx.resolve_target.map(|view| unsafe { &*view }),
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+            view: unsafe { &*graph_ctx.depth_target.expect("water depth target is None") },
+            depth_ops: Some(wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: wgpu::StoreOp::Store,
+            }),
+            stencil_ops: None,
+        }),
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    });
+
+    render_pass.set_pipeline(
+        unsafe { &*graph_ctx.node }
+            .data
+            .pipeline
+            .as_ref()
+            .expect("Pipeline should have been generated by now")
+            .render_pipeline(),
+    );
+
+    // rebind the materials whose reflection target changed
+    let mut alive = Vec::with_capacity(surfaces.len());
+    for (id, water, reflection, _) in &surfaces {
+        alive.push(*id);
+        if targets.get(id) != Some(&reflection.target) {
+            targets.insert(*id, reflection.target.clone());
+            bind_groups.remove_by_entity(*id, *water);
+        }
+    }
+
+    let target_size = surface_size(world);
+    let mut draw_calls = 0;
+    for (camera_id, camera) in cameras {
+        if !camera.set_render_pass_camera(
+            camera_id,
+            &mut render_pass,
+            1,
+            target_size,
+            &mut bind_groups,
+            world,
+        ) {
+            continue;
+        }
+
+        for (id, water, _, global_transform) in &surfaces {
+            let material_bind_group = bind_groups.get_by_entity(*id, *water, world);
+            render_pass.set_bind_group(0, &*material_bind_group, &[]);
+
+            let constants = WaterConstants {
+                model: global_transform.matrix.to_cols_array_2d(),
+                color: water.color.as_rgba_slice(),
+                size: water.size.to_array(),
+                time: time.elapsed(),
+                reflectivity: water.reflectivity,
+                distortion: water.distortion,
+                wave_scale: water.wave_scale,
+                wave_speed: water.wave_speed,
+                _padding: 0.0,
+            };
+            render_pass.set_push_constants(
+                wgpu::ShaderStages::VERTEX_FRAGMENT,
+                0,
+                bytemuck::bytes_of(&constants),
+            );
+
+            // the surface quad is generated in the vertex shader
+            render_pass.draw(0..6, 0..1);
+            draw_calls += 1;
+        }
+    }
+
+    targets.retain(|id, _| alive.contains(id));
+    graph_ctx.record_draw_calls(draw_calls);
+}
+
+fn create_water_pipeline_builder(
+    device: &RenderDevice,
+    surface_config: &RenderSurfaceConfiguration,
+    shader_loader: &mut ShaderLoader,
+) -> PipelineBuilder {
+    // Material bind group layout for the reflection texture and sampler
+    let material_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("water_material_bind_group_layout"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ],
+    });
+
+    // Camera bind group layout for uniform buffer
+    let camera_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("camera_bind_group_layout"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+    });
+
+    // Load shader modules
+    shader_loader.load("water", include_str!("../shaders/water.wgsl"), device);
+
+    // Water is blended over the scene, and visible from both sides
+    Pipeline::build("water_pipeline")
+        .set_bind_group_layouts(vec![material_layout, camera_layout])
+        .set_vertex_shader("water", "vs_main")
+        .set_fragment_shader("water", "fs_main")
+        .add_color_format(surface_config.format)
+        .set_depth_format(wgpu::TextureFormat::Depth32Float)
+        .set_primitive_state(wgpu::PrimitiveState {
+            cull_mode: None,
+            ..PipelineBuilder::default_primitive_state()
+        })
+        .set_depth_stencil(Some(wgpu::DepthStencilState {
+            depth_write_enabled: false,
+            ..PipelineBuilder::default_depth_stencil()
+        }))
+        .set_push_constant_ranges(vec![wgpu::PushConstantRange {
+            stages: wgpu::ShaderStages::VERTEX_FRAGMENT,
+            range: 0..std::mem::size_of::<WaterConstants>() as u32,
+        }])
+}
@@ -1,42 +1,85 @@
+use std::{any::TypeId, collections::HashSet};
+
 use glam::Vec2;
 use winit::{
     application::ApplicationHandler,
     dpi::PhysicalSize,
     event::*,
-    event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
+    event_loop::{ActiveEventLoop, ControlFlow, EventLoop, EventLoopProxy},
     keyboard::{KeyCode, PhysicalKey},
     window::WindowId,
 };
 
 use crate::{
     app::App,
-    event::{CursorMoved, MouseMotion, MouseWheel},
+    event::{CursorMoved, Events, MouseMotion, MouseWheel, RenderDeviceLost},
+    prelude::{EntityId, RunQuery, With, Without},
+    renderer::newtype::RenderDevice,
 };
 
-use super::{AppState, config::WindowConfig};
+use super::{
+    AppState, Window, WindowResized, WindowSurfaces, config::WindowConfig, primary_window,
+    update_window,
+};
+use crate::renderer::settings::RenderSettings;
 
+/// Handles winit events. The [`AppState`] is sent as a user event once it's created, since on the
+/// web it's created asynchronously.
 pub struct AppHandler<'a> {
     app: &'a mut App,
     state: Option<AppState>,
+    proxy: EventLoopProxy<AppState>,
+    /// Whether the plugins are finished and the startup systems ran
+    started: bool,
 }
 
 impl<'a> AppHandler<'a> {
-    pub fn init(app: &'a mut App) -> (EventLoop<()>, Self) {
-        let app = Self { app, state: None };
-
-        let event_loop = EventLoop::new().unwrap();
+    pub fn init(app: &'a mut App) -> (EventLoop<AppState>, Self) {
+        let event_loop = EventLoop::with_user_event().build().unwrap();
         event_loop.set_control_flow(ControlFlow::Poll);
 
+        let proxy = event_loop.create_proxy();
+        let app = Self {
+            app,
+            state: None,
+            proxy,
+            started: false,
+        };
+
         (event_loop, app)
     }
 
+    /// Create the [`AppState`] for `window` and send it to the event loop
+    fn create_state(&self, window: winit::window::Window, settings: RenderSettings) {
+        let proxy = self.proxy.clone();
+        let create = async move {
+            let state = AppState::new(window, settings)
+                .await
+                .unwrap_or_else(|err| panic!("Failed to initialize the renderer: {err}"));
+
+            // fails only if the event loop has already exited
+            _ = proxy.send_event(state);
+        };
+
+        #[cfg(not(target_arch = "wasm32"))]
+        pollster::block_on(create);
+        #[cfg(target_arch = "wasm32")]
+        wasm_bindgen_futures::spawn_local(create);
+    }
+
     #[inline]
     pub fn resize(&mut self, new_size: PhysicalSize<u32>) {
         self.state
             .as_mut()
             .unwrap()
-            .resize(new_size, &mut self.app.world.resources);
+            .resize(new_size, &mut self.app.world);
         self.app.resize(new_size);
+
+        let window = primary_window(&self.app.world);
+        self.app.create_event(WindowResized {
+            window,
+            size: new_size,
+        });
     }
 
     #[inline]
@@ -46,9 +89,178 @@ impl<'a> AppHandler<'a> {
             .unwrap()
             .reconfigure(&mut self.app.world.resources);
     }
+
+    /// Open a window for every entity with a [`WindowConfig`] but no [`Window`], and close the
+    /// windows whose entity was despawned or lost its [`Window`]
+    fn sync_windows(&mut self, event_loop: &ActiveEventLoop) {
+        let Some(state) = &self.state else {
+            return;
+        };
+        let world = &mut self.app.world;
+
+        let alive = world
+            .query_filtered::<EntityId, With<Window>>()
+            .iter_mut()
+            .into_iter()
+            .collect::<HashSet<_>>();
+        let mut surfaces = world.resources.get_mut::<WindowSurfaces>();
+        let closed = surfaces
+            .secondary
+            .keys()
+            .copied()
+            .filter(|entity| !alive.contains(entity))
+            .collect::<Vec<_>>();
+        for entity in closed {
+            surfaces.remove(entity);
+        }
+
+        let requested = world
+            .query_filtered::<(EntityId, &WindowConfig), Without<Window>>()
+            .iter_mut()
+            .into_iter()
+            .map(|(entity, config)| (entity, config.clone()))
+            .collect::<Vec<_>>();
+
+        for (entity, config) in requested {
+            let window = match event_loop.create_window(config.get_window_attributes()) {
+                Ok(window) => window,
+                Err(err) => {
+                    tracing::error!("Failed to create window: {err}");
+                    world
+                        .entities
+                        .remove_component(entity, TypeId::of::<WindowConfig>());
+                    continue;
+                }
+            };
+            config.post_apply(&window, event_loop);
+
+            let surface = match state.create_window_surface(window) {
+                Ok(surface) => surface,
+                Err(err) => {
+                    tracing::error!("Failed to create window surface: {err}");
+                    world
+                        .entities
+                        .remove_component(entity, TypeId::of::<WindowConfig>());
+                    continue;
+                }
+            };
+            let size = PhysicalSize::new(surface.config.width, surface.config.height);
+            world.insert_component(
+                entity,
+                Window {
+                    size,
+                    cursor_position: None,
+                },
+                false,
+            );
+            surfaces.insert(entity, surface, config);
+
+            // cameras of the window are fitted to its size
+            world
+                .resources
+                .get_mut::<Events<WindowResized>>()
+                .write(WindowResized {
+                    window: entity,
+                    size,
+                });
+        }
+    }
+
+    /// Handle an event of a secondary window, input is handled the same as in the primary window
+    fn secondary_window_event(&mut self, entity: EntityId, event: WindowEvent) {
+        let world = &mut self.app.world;
+
+        match event {
+            WindowEvent::CloseRequested => {
+                world.resources.get_mut::<WindowSurfaces>().remove(entity);
+                world.despawn(entity);
+            }
+
+            WindowEvent::KeyboardInput { event, .. } => {
+                self.app.handle_keyboard_input(event);
+            }
+            WindowEvent::MouseInput { state, button, .. } => {
+                self.app.handle_mouse_input(state, button);
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                self.app.create_event(MouseWheel { delta });
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                let position = Vec2::new(position.x as f32, position.y as f32);
+                update_window(world, entity, |window| {
+                    window.cursor_position = Some(position)
+                });
+            }
+            WindowEvent::CursorLeft { .. } => {
+                update_window(world, entity, |window| window.cursor_position = None);
+            }
+
+            WindowEvent::Resized(size) => {
+                let device = world.resources.get::<RenderDevice>();
+                if let Some(surface) = world
+                    .resources
+                    .get_mut::<WindowSurfaces>()
+                    .secondary
+                    .get_mut(&entity)
+                {
+                    surface.resize(size, &device);
+                }
+                update_window(world, entity, |window| window.size = size);
+                self.app.create_event(WindowResized {
+                    window: entity,
+                    size,
+                });
+            }
+            _ => (),
+        }
+    }
+
+    /// Finish the plugins and run the startup systems once all plugins are ready
+    fn try_start(&mut self) {
+        if self.started || self.state.is_none() || !self.app.plugins_ready() {
+            return;
+        }
+
+        self.app.setup();
+        self.started = true;
+    }
+
+    /// Try to recreate a lost device, returns false if it failed
+    pub fn recover_device(&mut self, reason: wgpu::DeviceLostReason, message: String) -> bool {
+        tracing::error!("Render device lost ({reason:?}): {message}");
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let result = self
+            .state
+            .as_mut()
+            .unwrap()
+            .recreate_device(&mut self.app.world.resources);
+        // the device can't be requested without blocking the browser
+        #[cfg(target_arch = "wasm32")]
+        let result: Result<(), &str> = Err("device recovery is not supported on the web");
+
+        let recovered = match result {
+            Ok(()) => {
+                tracing::info!("Render device recreated, reuploading render assets");
+                self.app.invalidate_render_assets();
+                true
+            }
+            Err(err) => {
+                tracing::error!("Failed to recreate render device: {err}");
+                false
+            }
+        };
+
+        self.app.create_event(RenderDeviceLost {
+            reason,
+            message,
+            recovered,
+        });
+        recovered
+    }
 }
 
-impl<'a> ApplicationHandler for AppHandler<'a> {
+impl<'a> ApplicationHandler<AppState> for AppHandler<'a> {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
         let window_config = self.app.world.resources.try_get::<WindowConfig>();
 
@@ -63,14 +275,21 @@ impl<'a> ApplicationHandler for AppHandler<'a> {
             config.post_apply(&window, event_loop);
         }
 
-        let mut state = AppState::new(window);
-        state.apply_to_resources(&mut self.app.world.resources);
+        let settings = self
+            .app
+            .world
+            .resources
+            .try_get::<RenderSettings>()
+            .map(|settings| settings.clone())
+            .unwrap_or_default();
 
-        if self.state.is_none() {
-            self.app.startup();
-        }
+        self.create_state(window, settings);
+    }
 
+    fn user_event(&mut self, _: &ActiveEventLoop, mut state: AppState) {
+        state.apply_to_world(&mut self.app.world);
         self.state = Some(state);
+        self.try_start();
     }
 
     fn device_event(
@@ -91,7 +310,15 @@ impl<'a> ApplicationHandler for AppHandler<'a> {
     }
 
     fn window_event(&mut self, event_loop: &ActiveEventLoop, id: WindowId, event: WindowEvent) {
-        if id != self.state.as_ref().unwrap().window().id() {
+        // events can arrive before the state is created
+        let Some(state) = &self.state else {
+            return;
+        };
+        if id != state.window().id() {
+            let entity = self.app.world.resources.get::<WindowSurfaces>().find(id);
+            if let Some(entity) = entity {
+                self.secondary_window_event(entity, event);
+            }
             return;
         }
 
@@ -124,26 +351,37 @@ impl<'a> ApplicationHandler for AppHandler<'a> {
                 self.state
                     .as_mut()
                     .unwrap()
-                    .update_cursor_position(Some(position), &mut self.app.world.resources);
+                    .update_cursor_position(Some(position), &mut self.app.world);
             }
 
             WindowEvent::Resized(physical_size) => self.resize(physical_size),
             WindowEvent::RedrawRequested => {
+                if !self.started {
+                    return;
+                }
+
+                let device_lost = self.state.as_ref().unwrap().take_device_lost();
+                if let Some((reason, message)) = device_lost
+                    && !self.recover_device(reason, message)
+                {
+                    event_loop.exit();
+                    return;
+                }
+
                 if let Err(err) = self.app.execute_scheduler() {
                     match err {
                         wgpu::SurfaceError::Lost
                         | wgpu::SurfaceError::Outdated
                         | wgpu::SurfaceError::Other => {
-                            eprintln!("Surface Lost or Outdated");
+                            tracing::warn!("Surface lost or outdated, reconfiguring");
                             self.reconfigure();
                         }
                         wgpu::SurfaceError::OutOfMemory => {
-                            eprintln!("Out Of Memory");
+                            tracing::error!("Out of memory, exiting");
                             event_loop.exit();
                         }
                         wgpu::SurfaceError::Timeout => {
-                            eprintln!("Surface Timeout");
-                            self.reconfigure();
+                            tracing::warn!("Surface timeout, skipping frame");
                         }
                     }
                 }
@@ -152,7 +390,20 @@ impl<'a> ApplicationHandler for AppHandler<'a> {
         }
     }
 
-    fn about_to_wait(&mut self, _: &ActiveEventLoop) {
-        self.state.as_ref().unwrap().window().request_redraw();
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        self.try_start();
+        if self.started {
+            self.sync_windows(event_loop);
+        }
+
+        if let Some(state) = &self.state {
+            state.window().request_redraw();
+        }
+    }
+
+    fn exiting(&mut self, _: &ActiveEventLoop) {
+        if self.started {
+            self.app.cleanup_plugins();
+        }
     }
 }
@@ -0,0 +1,44 @@
+use crate::{prelude::*, renderer::newtype::RenderWindow};
+
+use super::{WindowSurfaces, config::WindowConfig};
+
+/// System to apply changes of the [`WindowConfig`] resource to the primary window, and of the
+/// [`WindowConfig`] components to their windows, see [`WindowConfig::apply_changes`].
+pub(crate) fn apply_window_config_system(
+    config: Option<Res<WindowConfig>>,
+    render_window: Option<Res<RenderWindow>>,
+    surfaces: Option<ResMut<WindowSurfaces>>,
+    mut query: Query<(EntityId, &WindowConfig), (With<Window>, Changed<WindowConfig>)>,
+) {
+    // no windows in a custom runner
+    let Some(mut surfaces) = surfaces else {
+        return;
+    };
+
+    if let (Some(config), Some(window)) = (config, render_window)
+        && config.has_changed()
+    {
+        let primary = surfaces.primary;
+        apply_config(&mut surfaces, primary, &config, &window);
+    }
+
+    for (entity, config) in query.iter_mut() {
+        let Some(window) = surfaces.window(entity).map(|window| window.clone_wrapped()) else {
+            continue;
+        };
+        apply_config(&mut surfaces, entity, config, &window);
+    }
+}
+
+/// Apply the settings of `config` which changed since `entity` was last configured
+fn apply_config(
+    surfaces: &mut WindowSurfaces,
+    entity: EntityId,
+    config: &WindowConfig,
+    window: &winit::window::Window,
+) {
+    if let Some(previous) = surfaces.configs.get(&entity) {
+        config.apply_changes(previous, window);
+    }
+    surfaces.configs.insert(entity, config.clone());
+}
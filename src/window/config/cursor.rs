@@ -36,7 +36,8 @@ impl Cursor {
         hotspot_y: u16,
     ) -> Result<Cursor, image::ImageError> {
         let (rgba, width, height) = {
-            let image = image::open(path)?.into_rgba8();
+            let bytes = crate::assets::io::read(path).map_err(image::ImageError::IoError)?;
+            let image = image::load_from_memory(&bytes)?.into_rgba8();
             let (width, height) = image.dimensions();
             let rgba = image.into_raw();
             (rgba, width as u16, height as u16)
@@ -85,7 +86,7 @@ impl Cursor {
                 ) {
                     Ok(source) => event_loop.create_custom_cursor(source).into(),
                     Err(err) => {
-                        eprintln!("Failed to create custom cursor: {}", err);
+                        tracing::error!("Failed to create custom cursor: {}", err);
                         winit::window::Cursor::default()
                     }
                 }
@@ -31,7 +31,8 @@ impl Icon {
     /// Load custom cursor from image file.
     pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, image::ImageError> {
         let (rgba, width, height) = {
-            let image = image::open(path)?.into_rgba8();
+            let bytes = crate::assets::io::read(path).map_err(image::ImageError::IoError)?;
+            let image = image::load_from_memory(&bytes)?.into_rgba8();
             let (width, height) = image.dimensions();
             let rgba = image.into_raw();
             (rgba, width, height)
@@ -49,7 +50,7 @@ impl From<Icon> for Option<winit::window::Icon> {
                 match winit::window::Icon::from_rgba(ico.rgba, ico.width, ico.height) {
                     Ok(icon) => Some(icon),
                     Err(err) => {
-                        eprintln!("Failed to window create icon: {}", err);
+                        tracing::error!("Failed to create window icon: {}", err);
                         None
                     }
                 }
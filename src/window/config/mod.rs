@@ -10,8 +10,9 @@ use winit::{
     window::{Fullscreen, WindowAttributes, WindowButtons},
 };
 
-/// Configuration used when creating a window.
-#[derive(crate::macros::Resource, Debug, Clone)]
+/// Configuration used when creating a window. The resource configures the primary window, as a
+/// component it opens an additional [`Window`](super::Window).
+#[derive(crate::macros::Resource, crate::macros::Component, Debug, Clone)]
 pub struct WindowConfig {
     pub title: String,
     /// Size of the window.
@@ -44,10 +45,14 @@ pub struct WindowConfig {
     pub decorations: bool,
     pub content_protected: bool,
     pub active: bool,
+
+    /// Id of the `<canvas>` element to render into on the web. If None, a new canvas is appended
+    /// to the document body. Ignored on other platforms.
+    pub canvas: Option<String>,
 }
 
 /// See `inner_size` as defined in [`winit::window::WindowAttributes`]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct WindowResolution {
     pub physical_width: u32,
     pub physical_height: u32,
@@ -138,7 +143,7 @@ impl Default for WindowResizeConstraints {
 }
 
 /// See [`winit::window::Fullscreen`].
-#[derive(Default, Debug, Clone, Copy)]
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
 pub enum WindowMode {
     #[default]
     Windowed,
@@ -161,7 +166,7 @@ impl WindowMode {
                     (_, Some(monitor), _) => monitor,
                     (_, _, Some(monitor)) => monitor,
                     _ => {
-                        eprintln!("No monitor found, falling back to windowed mode");
+                        tracing::warn!("No monitor found, falling back to windowed mode");
                         return None;
                     }
                 };
@@ -177,7 +182,7 @@ impl WindowMode {
                         w.saturating_mul(h).saturating_mul(r)
                     })
                 else {
-                    eprintln!("No video mode found, falling back to windowed mode");
+                    tracing::warn!("No video mode found, falling back to windowed mode");
                     return None;
                 };
 
@@ -187,7 +192,7 @@ impl WindowMode {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct CursorMode {
     pub grab_mode: CursorGrabMode,
     pub visible: bool,
@@ -203,7 +208,7 @@ impl Default for CursorMode {
 }
 
 /// See [`winit::window::CursorGrabMode`].
-#[derive(Default, Debug, Clone, Copy)]
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
 pub enum CursorGrabMode {
     #[default]
     None,
@@ -350,6 +355,8 @@ impl Default for WindowConfig {
             decorations: true,
             content_protected: false,
             active: true,
+
+            canvas: None,
         }
     }
 }
@@ -378,9 +385,33 @@ impl WindowConfig {
         // attrs.cursor = self.cursor.clone().into();
         attrs.active = self.active;
 
+        #[cfg(target_arch = "wasm32")]
+        {
+            attrs = self.apply_canvas(attrs);
+        }
+
         attrs
     }
 
+    /// Sets the canvas the window renders into
+    #[cfg(target_arch = "wasm32")]
+    fn apply_canvas(&self, attrs: WindowAttributes) -> WindowAttributes {
+        use wasm_bindgen::JsCast;
+        use winit::platform::web::WindowAttributesExtWebSys;
+
+        let Some(id) = &self.canvas else {
+            return attrs.with_append(true);
+        };
+
+        let canvas = web_sys::window()
+            .and_then(|window| window.document())
+            .and_then(|document| document.get_element_by_id(id))
+            .and_then(|element| element.dyn_into::<web_sys::HtmlCanvasElement>().ok())
+            .unwrap_or_else(|| panic!("No canvas element with id '{id}' found"));
+
+        attrs.with_canvas(Some(canvas))
+    }
+
     pub fn post_apply(
         &self,
         window: &winit::window::Window,
@@ -394,10 +425,46 @@ impl WindowConfig {
         let cursor = self.cursor.into_winit_cursor(event_loop);
         window.set_cursor(cursor);
 
-        // cursor mode
+        self.apply_cursor_mode(window);
+    }
+
+    /// Apply the settings which differ from `previous` to a live `window`: title, resolution,
+    /// cursor mode, fullscreen mode and decorations. Other settings only take effect when a window
+    /// is created.
+    pub fn apply_changes(&self, previous: &WindowConfig, window: &winit::window::Window) {
+        if self.title != previous.title {
+            window.set_title(&self.title);
+        }
+
+        if self.decorations != previous.decorations {
+            window.set_decorations(self.decorations);
+        }
+
+        if self.mode != previous.mode {
+            let fullscreen = self.mode.into_winit_fullscreen(window);
+            window.set_fullscreen(fullscreen);
+        }
+
+        // the size of a fullscreen window is given by the monitor, the surface is resized with the
+        // resize event of the window
+        let resized = self.resolution != previous.resolution || self.mode != previous.mode;
+        if resized
+            && matches!(self.mode, WindowMode::Windowed)
+            && let Some(size) = Option::<Size>::from(self.resolution)
+        {
+            _ = window.request_inner_size(size);
+        }
+
+        if self.cursor_mode != previous.cursor_mode {
+            self.apply_cursor_mode(window);
+        }
+    }
+
+    /// Apply the cursor grab mode and visibility
+    fn apply_cursor_mode(&self, window: &winit::window::Window) {
         let grab_mode = self.cursor_mode.grab_mode.into();
         if let Err(err) = window.set_cursor_grab(grab_mode) {
-            eprintln!("Failed to set cursor grab mode: {}", err);
+            tracing::warn!("Failed to set cursor grab mode: {}", err);
         };
         window.set_cursor_visible(self.cursor_mode.visible);
     }
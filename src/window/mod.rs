@@ -1,14 +1,46 @@
 mod app_handler;
+mod apply;
 pub mod config;
+mod safe_area;
 mod state;
+mod surface;
 
 pub(crate) use app_handler::AppHandler;
+pub(crate) use apply::apply_window_config_system;
 pub(crate) use state::*;
+pub(crate) use surface::*;
 
-/// Resource holding basic window state information.
-/// TODO: This struct is temporary and will be expanded in the future. It may eventually replace
-/// [WindowConfig](config::WindowConfig).
-#[derive(crate::macros::Resource, Default, Debug, Clone)]
+pub use safe_area::SafeAreaInsets;
+pub use surface::{RenderedWindow, WindowResized};
+
+use crate::{
+    ecs::entities::EntityId,
+    query::{Query, RunQuery},
+};
+
+/// Basic state of a window. Every window is an entity with this component, the primary window is
+/// marked with [`PrimaryWindow`] and its state is also available as a resource.
+///
+/// The primary window is created from the [`WindowConfig`](config::WindowConfig) resource.
+/// Additional windows are opened by spawning an entity with a `WindowConfig` component, this
+/// component is inserted once the window exists. Despawning the entity closes the window, and
+/// closing the window despawns the entity. Keyboard and mouse input of all windows is handled the
+/// same, raw [`WindowEvent`](crate::event::WindowEvent)s are only sent for the primary window.
+///
+/// ```ignore
+/// let window = commands
+///     .spawn_empty()
+///     .insert(WindowConfig {
+///         title: "Map".to_string(),
+///         ..Default::default()
+///     })
+///     .entity_id();
+///
+/// let mut camera = Camera2D::new();
+/// camera.camera.window = Some(window);
+/// commands.spawn(camera);
+/// ```
+#[derive(crate::macros::Resource, crate::macros::Component, Default, Debug, Clone)]
 pub struct Window {
     pub(crate) size: winit::dpi::PhysicalSize<u32>,
     pub(crate) cursor_position: Option<glam::Vec2>,
@@ -28,6 +60,42 @@ impl Window {
     }
 }
 
+/// Marker of the [`Window`] entity created with the app, closing it exits the app
+#[derive(crate::macros::Component, Default, Debug, Clone, Copy)]
+pub struct PrimaryWindow;
+
+/// Window the cursor is over, used to find the camera under the cursor with
+/// [`Camera::cursor_in_viewport`](crate::prelude::Camera::cursor_in_viewport)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CursorWindow {
+    pub window: RenderedWindow,
+    /// Cursor position in physical pixels
+    pub position: glam::Vec2,
+    /// Window size in physical pixels
+    pub size: glam::Vec2,
+}
+
+impl CursorWindow {
+    /// Returns the window of `windows` the cursor is over
+    pub fn find(windows: &mut Query<(EntityId, &Window, Option<&PrimaryWindow>)>) -> Option<Self> {
+        windows
+            .iter_mut()
+            .into_iter()
+            .find_map(|(entity, window, primary)| {
+                Some(Self {
+                    window: RenderedWindow {
+                        entity,
+                        primary: primary.is_some(),
+                    },
+                    position: window.cursor_position?,
+                    size: glam::Vec2::new(window.size.width as f32, window.size.height as f32),
+                })
+            })
+    }
+}
+
 pub mod prelude {
-    pub use super::Window;
+    pub use super::{
+        CursorWindow, PrimaryWindow, RenderedWindow, SafeAreaInsets, Window, WindowResized,
+    };
 }
@@ -0,0 +1,73 @@
+use crate::macros::Resource;
+
+/// Insets of the primary window covered by the platform, like notches, rounded corners or system
+/// bars on phones, in physical pixels. Used as a resource.
+///
+/// On the web they are read from the `env(safe-area-inset-*)` CSS variables when the window is
+/// created or resized, which are only non-zero with `viewport-fit=cover` in the viewport meta
+/// tag. Other platforms don't expose them, so they stay zero unless the app inserts its own.
+///
+/// Root nodes with [`Node::safe_area`](crate::ui::node::Node::safe_area) add them to their
+/// padding, so their content isn't clipped.
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq)]
+pub struct SafeAreaInsets {
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub left: f32,
+}
+
+impl SafeAreaInsets {
+    /// Returns the insets of `window` reported by the platform
+    #[cfg(target_arch = "wasm32")]
+    pub(crate) fn from_window(window: &winit::window::Window) -> Self {
+        web::insets(window.scale_factor() as f32).unwrap_or_default()
+    }
+
+    /// Returns the insets of `window` reported by the platform
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn from_window(_window: &winit::window::Window) -> Self {
+        Self::default()
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod web {
+    use super::SafeAreaInsets;
+
+    /// Style of the element measuring the insets, CSS `env()` can only be read through a
+    /// computed property
+    const PROBE_STYLE: &str = "position: fixed; visibility: hidden; pointer-events: none; \
+        padding: env(safe-area-inset-top) env(safe-area-inset-right) \
+        env(safe-area-inset-bottom) env(safe-area-inset-left);";
+
+    /// Returns the safe area insets of the page in physical pixels
+    pub(super) fn insets(scale_factor: f32) -> Option<SafeAreaInsets> {
+        let window = web_sys::window()?;
+        let document = window.document()?;
+        let body = document.body()?;
+
+        let probe = document.create_element("div").ok()?;
+        probe.set_attribute("style", PROBE_STYLE).ok()?;
+        body.append_child(&probe).ok()?;
+
+        let style = window.get_computed_style(&probe).ok().flatten();
+        let inset = |side: &str| {
+            style
+                .as_ref()
+                .and_then(|style| style.get_property_value(&format!("padding-{side}")).ok())
+                .and_then(|value| value.trim_end_matches("px").parse::<f32>().ok())
+                .unwrap_or_default()
+                * scale_factor
+        };
+        let insets = SafeAreaInsets {
+            top: inset("top"),
+            right: inset("right"),
+            bottom: inset("bottom"),
+            left: inset("left"),
+        };
+
+        probe.remove();
+        Some(insets)
+    }
+}
@@ -1,10 +1,18 @@
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use glam::Vec2;
+#[cfg(not(target_arch = "wasm32"))]
 use pollster::FutureExt;
 use winit::{dpi::PhysicalSize, window::Window};
 
-use crate::{prelude::Resources, renderer::newtype::*};
+use super::{PrimaryWindow, SafeAreaInsets, WindowSurface, WindowSurfaces, config::WindowConfig};
+use crate::{
+    prelude::{EntityId, Resources, RunQuery, World},
+    renderer::{
+        newtype::*,
+        settings::{RenderInitError, RenderSettings},
+    },
+};
 
 /// Holds Window - GPU state for the application. Used by the AppHandler
 pub(crate) struct AppState {
@@ -15,21 +23,31 @@ pub(crate) struct AppState {
     device: RenderDevice,
     queue: RenderQueue,
     config: RenderSurfaceConfiguration,
+    settings: RenderSettings,
 
     size: PhysicalSize<u32>,
     cursor_position: Option<Vec2>,
+    /// Set by the device lost callback of the current device
+    device_lost: DeviceLost,
 }
 
+/// Reason and message of a lost device
+type DeviceLost = Arc<Mutex<Option<(wgpu::DeviceLostReason, String)>>>;
+
 impl AppState {
-    /// Create new AppState from a winit window.
+    /// Create new AppState from a winit window, with the GPU chosen by `settings`.
     /// You should call `apply_to_resources` to sync with ECS resources.
-    pub fn new(window: Window) -> Self {
-        let instance = Self::create_gpu_instance();
+    ///
+    /// It's async since the browser can't be blocked while the GPU is requested, native targets
+    /// can block on it.
+    pub async fn new(window: Window, settings: RenderSettings) -> Result<Self, RenderInitError> {
+        let instance = Self::create_gpu_instance(&settings);
         let window = Arc::new(window);
 
         let surface = instance.create_surface(window.clone()).unwrap();
-        let adapter = Self::create_adapter(&instance, &surface);
-        let (device, queue) = Self::create_device(&adapter);
+        let adapter = Self::create_adapter(&instance, &surface, &settings).await?;
+        let (device, queue) = Self::create_device(&adapter, &settings).await?;
+        let device_lost = Self::watch_device_lost(&device);
         let surface_caps = surface.get_capabilities(&adapter);
 
         let size = window.inner_size();
@@ -45,7 +63,7 @@ impl AppState {
         let queue = RenderQueue::new(queue);
         let config = RenderSurfaceConfiguration::new(config);
 
-        Self {
+        Ok(Self {
             instance,
             surface: Some(surface),
             window,
@@ -53,14 +71,38 @@ impl AppState {
             device,
             queue,
             config,
+            settings,
 
             size,
             cursor_position: None,
-        }
+            device_lost,
+        })
     }
 
-    /// Insert all GPU resources into ECS resources
-    pub fn apply_to_resources(&mut self, resources: &mut Resources) {
+    /// Spawn the primary window entity and insert all GPU resources into ECS resources
+    pub fn apply_to_world(&mut self, world: &mut World) {
+        let window = crate::prelude::Window {
+            size: self.size,
+            cursor_position: self.cursor_position,
+        };
+        let entity = world.spawn();
+        world.insert_component(entity, window.clone(), false);
+        world.insert_component(entity, PrimaryWindow, false);
+
+        // the window was created with the config, later changes are applied to it
+        let config = world
+            .resources
+            .try_get::<WindowConfig>()
+            .map(|config| config.clone())
+            .unwrap_or_default();
+
+        // insets inserted by the app are kept on platforms which don't report them
+        let resources = &mut world.resources;
+        if cfg!(target_arch = "wasm32") || !resources.contains::<SafeAreaInsets>() {
+            resources.insert(SafeAreaInsets::from_window(&self.window));
+        }
+        resources.insert(window);
+        resources.insert(WindowSurfaces::new(entity, config));
         resources.insert(self.instance.clone_wrapped());
         resources.insert(self.surface.take().unwrap());
         resources.insert(self.window.clone_wrapped());
@@ -68,11 +110,6 @@ impl AppState {
         resources.insert(self.device.clone_wrapped());
         resources.insert(self.queue.clone_wrapped());
         resources.insert(self.config.clone_wrapped());
-
-        let mut window = crate::prelude::Window::default();
-        window.size = self.size;
-        window.cursor_position = self.cursor_position;
-        resources.insert(window);
     }
 
     /// Get a reference to the winit window
@@ -83,12 +120,13 @@ impl AppState {
 
     /// Resize the surface and reconfigue it
     #[inline]
-    pub fn resize(&mut self, new_size: PhysicalSize<u32>, resources: &mut Resources) {
+    pub fn resize(&mut self, new_size: PhysicalSize<u32>, world: &mut World) {
         self.size = new_size;
 
         self.config.width = new_size.width;
         self.config.height = new_size.height;
 
+        let resources = &mut world.resources;
         let mut window = resources.get_mut::<crate::prelude::Window>();
         window.size = new_size;
         let mut config = resources.get_mut::<RenderSurfaceConfiguration>();
@@ -96,15 +134,54 @@ impl AppState {
         config.height = new_size.height;
 
         self.reconfigure(resources);
+
+        // the insets change with the orientation of the device
+        #[cfg(target_arch = "wasm32")]
+        {
+            let insets = SafeAreaInsets::from_window(&self.window);
+            if *resources.get::<SafeAreaInsets>() != insets {
+                resources.insert(insets);
+            }
+        }
+
+        let primary = primary_window(world);
+        update_window(world, primary, |window| window.size = new_size);
     }
 
     /// Update the cursor position
     #[inline]
-    pub fn update_cursor_position(&mut self, position: Option<Vec2>, resources: &mut Resources) {
+    pub fn update_cursor_position(&mut self, position: Option<Vec2>, world: &mut World) {
         self.cursor_position = position;
 
-        let mut window = resources.get_mut::<crate::prelude::Window>();
+        let mut window = world.resources.get_mut::<crate::prelude::Window>();
         window.cursor_position = position;
+        let primary = primary_window(world);
+        update_window(world, primary, |window| window.cursor_position = position);
+    }
+
+    /// Create the surface of a secondary window, in the format of the primary surface which the
+    /// pipelines are created for. Fails if the adapter can't present that format to the window.
+    pub fn create_window_surface(&self, window: Window) -> Result<WindowSurface, RenderInitError> {
+        let window = Arc::new(window);
+        let surface = self.instance.create_surface(window.clone()).unwrap();
+
+        let capabilities = surface.get_capabilities(&self.adapter);
+        if !capabilities.formats.contains(&self.config.format) {
+            return Err(RenderInitError::UnsupportedSurfaceFormat(
+                self.config.format,
+            ));
+        }
+        let mut config = Self::create_surface_config(capabilities, window.inner_size());
+        config.format = self.config.format;
+        surface.configure(&self.device, &config);
+
+        Ok(WindowSurface {
+            window: RenderWindow::new(window),
+            surface: RenderSurface::new(surface),
+            config: RenderSurfaceConfiguration::new(config),
+            texture: None,
+            view: None,
+        })
     }
 
     /// Reconfigure the surface with the current config
@@ -114,46 +191,145 @@ impl AppState {
         surface.configure(&self.device, &self.config);
     }
 
+    /// Returns the reason and message if the device was lost since the last call
     #[inline]
-    fn create_gpu_instance() -> wgpu::Instance {
+    pub fn take_device_lost(&self) -> Option<(wgpu::DeviceLostReason, String)> {
+        self.device_lost.lock().unwrap().take()
+    }
+
+    /// Request a new adapter and device, reconfigure the surfaces of all windows with them and
+    /// replace the GPU resources. Render assets created with the old device are not touched.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn recreate_device(&mut self, resources: &mut Resources) -> Result<(), RenderInitError> {
+        let surface = resources.get::<RenderSurface>();
+        let adapter = Self::create_adapter(&self.instance, &surface, &self.settings).block_on()?;
+        let (device, queue) = Self::create_device(&adapter, &self.settings).block_on()?;
+
+        // keep the current format if the new adapter supports it on every window, pipelines
+        // depend on it
+        let mut surfaces = resources.get_mut::<WindowSurfaces>();
+        let supported = std::iter::once(&*surface)
+            .chain(surfaces.secondary.values().map(|window| &window.surface))
+            .all(|surface| {
+                surface
+                    .get_capabilities(&adapter)
+                    .formats
+                    .contains(&self.config.format)
+            });
+        if !supported {
+            return Err(RenderInitError::UnsupportedSurfaceFormat(
+                self.config.format,
+            ));
+        }
+
+        surface.configure(&device, &self.config);
+        for window in surfaces.secondary.values_mut() {
+            // frames acquired with the lost device can't be presented
+            window.texture = None;
+            window.view = None;
+            window.surface.configure(&device, &window.config);
+        }
+
+        self.device_lost = Self::watch_device_lost(&device);
+        self.adapter = RenderAdapter::new(adapter);
+        self.device = RenderDevice::new(device);
+        self.queue = RenderQueue::new(queue);
+
+        resources.insert(self.adapter.clone_wrapped());
+        resources.insert(self.device.clone_wrapped());
+        resources.insert(self.queue.clone_wrapped());
+
+        Ok(())
+    }
+
+    /// Returns the flag set when `device` is lost
+    fn watch_device_lost(device: &wgpu::Device) -> DeviceLost {
+        let device_lost = DeviceLost::default();
+        let callback_device_lost = device_lost.clone();
+
+        device.set_device_lost_callback(move |reason, message| {
+            *callback_device_lost.lock().unwrap() = Some((reason, message));
+        });
+
+        device_lost
+    }
+
+    #[inline]
+    fn create_gpu_instance(settings: &RenderSettings) -> wgpu::Instance {
         wgpu::Instance::new(&wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::PRIMARY,
+            backends: settings.backends,
             ..Default::default()
         })
     }
 
-    #[inline]
-    fn create_adapter(instance: &wgpu::Instance, surface: &wgpu::Surface) -> wgpu::Adapter {
+    /// Returns the adapter named in `settings`, or the best one for its power preference
+    async fn create_adapter(
+        instance: &wgpu::Instance,
+        surface: &wgpu::Surface<'_>,
+        settings: &RenderSettings,
+    ) -> Result<wgpu::Adapter, RenderInitError> {
+        if let Some(name) = &settings.adapter_name {
+            let adapters = instance
+                .enumerate_adapters(settings.backends)
+                .into_iter()
+                .filter(|adapter| adapter.is_surface_supported(surface))
+                .collect::<Vec<_>>();
+
+            let needle = name.to_lowercase();
+            let position = adapters
+                .iter()
+                .position(|adapter| adapter.get_info().name.to_lowercase().contains(&needle));
+
+            return match position {
+                Some(index) => Ok(adapters.into_iter().nth(index).unwrap()),
+                None => Err(RenderInitError::AdapterNotFound {
+                    name: name.clone(),
+                    available: adapters
+                        .iter()
+                        .map(|adapter| {
+                            let info = adapter.get_info();
+                            format!("{} ({:?})", info.name, info.backend)
+                        })
+                        .collect(),
+                }),
+            };
+        }
+
         let adapter_options = wgpu::RequestAdapterOptions {
-            power_preference: wgpu::PowerPreference::default(),
+            power_preference: settings.power_preference,
             compatible_surface: Some(surface),
             force_fallback_adapter: false,
         };
 
         instance
             .request_adapter(&adapter_options)
-            .block_on()
-            .expect("Failed to create adapter")
+            .await
+            .map_err(|err| RenderInitError::NoAdapter {
+                backends: settings.backends,
+                source: err,
+            })
     }
 
-    #[inline]
-    fn create_device(adapter: &wgpu::Adapter) -> (wgpu::Device, wgpu::Queue) {
+    async fn create_device(
+        adapter: &wgpu::Adapter,
+        settings: &RenderSettings,
+    ) -> Result<(wgpu::Device, wgpu::Queue), RenderInitError> {
         let device_descriptor = wgpu::DeviceDescriptor {
             label: None,
-            required_features: wgpu::Features::PUSH_CONSTANTS,
-            required_limits: wgpu::Limits {
-                max_push_constant_size: 128,
-                ..wgpu::Limits::default()
-            },
+            required_features: settings.device_features(adapter)?,
+            required_limits: settings.device_limits(adapter)?,
             experimental_features: wgpu::ExperimentalFeatures::disabled(),
             memory_hints: Default::default(),
             trace: wgpu::Trace::Off,
         };
 
+        let info = adapter.get_info();
+        tracing::info!("Using adapter '{}' ({:?})", info.name, info.backend);
+
         adapter
             .request_device(&device_descriptor)
-            .block_on()
-            .expect("Failed to create device")
+            .await
+            .map_err(RenderInitError::Device)
     }
 
     #[inline]
@@ -181,3 +357,20 @@ impl AppState {
         }
     }
 }
+
+/// Returns the entity of the primary window
+#[inline]
+pub(crate) fn primary_window(world: &World) -> EntityId {
+    world.resources.get::<WindowSurfaces>().primary
+}
+
+/// Apply `update` to the [`Window`](crate::prelude::Window) component of `entity`
+pub(crate) fn update_window(
+    world: &mut World,
+    entity: EntityId,
+    update: impl FnOnce(&mut crate::prelude::Window),
+) {
+    if let Some(window) = world.query::<&mut crate::prelude::Window>().get(entity) {
+        update(window);
+    }
+}
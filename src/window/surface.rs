@@ -0,0 +1,240 @@
+use std::collections::{HashMap, HashSet};
+
+use winit::{dpi::PhysicalSize, window::WindowId};
+
+use super::config::WindowConfig;
+use crate::{
+    core::graph::RenderGraph,
+    macros::{Event, Resource},
+    prelude::{Camera, EntityId, RunQuery, World},
+    renderer::newtype::{
+        RenderDevice, RenderSurface, RenderSurfaceConfiguration, RenderSurfaceTexture,
+        RenderSurfaceTextureView, RenderWindow,
+    },
+};
+
+/// Event sent when a [`Window`](super::Window) was resized, by the window entity
+#[derive(Event, Debug, Clone, Copy)]
+pub struct WindowResized {
+    pub window: EntityId,
+    pub size: PhysicalSize<u32>,
+}
+
+/// Window the render graph is currently rendering to. The graph is executed once for the primary
+/// window, and once for every other window with an active camera. Used as a resource, inserted
+/// while the graph executes.
+///
+/// Render systems should only draw the cameras which [render](Camera::renders_to) to it.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderedWindow {
+    pub entity: EntityId,
+    /// Whether it's the [`PrimaryWindow`](super::PrimaryWindow)
+    pub primary: bool,
+}
+
+/// Surface of a window other than the primary one, whose surface is stored in the resources
+pub(crate) struct WindowSurface {
+    pub(crate) window: RenderWindow,
+    pub(crate) surface: RenderSurface,
+    pub(crate) config: RenderSurfaceConfiguration,
+    /// Texture of the current frame, acquired before the scheduler runs
+    pub(crate) texture: Option<RenderSurfaceTexture>,
+    pub(crate) view: Option<RenderSurfaceTextureView>,
+}
+
+impl WindowSurface {
+    /// Resize the surface and reconfigure it
+    pub(crate) fn resize(&mut self, size: PhysicalSize<u32>, device: &RenderDevice) {
+        self.config.width = size.width;
+        self.config.height = size.height;
+        self.surface.configure(device, &self.config);
+    }
+
+    /// Acquire the texture of the next frame, reconfigures the surface if it's outdated. The
+    /// window is skipped this frame if it fails.
+    fn acquire(&mut self, device: &RenderDevice) {
+        let texture = match self.surface.get_current_texture() {
+            Ok(texture) => texture,
+            Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                self.surface.configure(device, &self.config);
+                return;
+            }
+            Err(err) => {
+                tracing::warn!("Failed to acquire window surface texture: {err}");
+                return;
+            }
+        };
+
+        let view = texture.texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("Window Surface Texture View"),
+            format: Some(self.config.format),
+            ..Default::default()
+        });
+
+        self.texture = Some(RenderSurfaceTexture::new(texture));
+        self.view = Some(RenderSurfaceTextureView::new(view));
+    }
+}
+
+/// Surfaces of all windows by their entity. Used as a resource.
+#[derive(Resource)]
+pub(crate) struct WindowSurfaces {
+    pub(crate) primary: EntityId,
+    pub(crate) secondary: HashMap<EntityId, WindowSurface>,
+    /// Config every window was last configured with, to apply only the changed settings
+    pub(crate) configs: HashMap<EntityId, WindowConfig>,
+}
+
+impl WindowSurfaces {
+    pub(crate) fn new(primary: EntityId, config: WindowConfig) -> Self {
+        Self {
+            primary,
+            secondary: HashMap::new(),
+            configs: HashMap::from([(primary, config)]),
+        }
+    }
+
+    /// Add the surface of a secondary window created with `config`
+    pub(crate) fn insert(
+        &mut self,
+        entity: EntityId,
+        surface: WindowSurface,
+        config: WindowConfig,
+    ) {
+        self.secondary.insert(entity, surface);
+        self.configs.insert(entity, config);
+    }
+
+    /// Remove the surface of a secondary window, which closes it
+    pub(crate) fn remove(&mut self, entity: EntityId) {
+        self.secondary.remove(&entity);
+        self.configs.remove(&entity);
+    }
+
+    /// Returns the winit window of `entity`
+    pub(crate) fn window(&self, entity: EntityId) -> Option<&RenderWindow> {
+        self.secondary.get(&entity).map(|surface| &surface.window)
+    }
+
+    /// Returns the entity of the secondary window with winit `id`
+    pub(crate) fn find(&self, id: WindowId) -> Option<EntityId> {
+        self.secondary
+            .iter()
+            .find(|(_, surface)| surface.window.id() == id)
+            .map(|(entity, _)| *entity)
+    }
+
+    /// Acquire the frame textures of the secondary windows
+    pub(crate) fn acquire(&mut self, device: &RenderDevice) {
+        for surface in self.secondary.values_mut() {
+            surface.acquire(device);
+        }
+    }
+
+    /// Present the frame textures of the secondary windows
+    pub(crate) fn present(&mut self) {
+        for surface in self.secondary.values_mut() {
+            surface.view = None;
+            if let Some(texture) = surface.texture.take() {
+                texture.unwrap().present();
+            }
+        }
+    }
+}
+
+/// Execute the render graph for the primary window, then for every secondary window with an
+/// active camera. Every window keeps its own size dependent graph targets, the ones of the primary
+/// window are in the graph once all windows are rendered.
+pub(crate) fn render_windows(world: &mut World, graph: &mut RenderGraph) {
+    let (primary, secondary) = {
+        let surfaces = world.resources.get::<WindowSurfaces>();
+        graph.retain_windows(|entity| {
+            entity == surfaces.primary || surfaces.secondary.contains_key(&entity)
+        });
+        let secondary = surfaces
+            .secondary
+            .iter()
+            .filter(|(_, surface)| surface.view.is_some())
+            .map(|(entity, _)| *entity)
+            .collect::<Vec<_>>();
+        (surfaces.primary, secondary)
+    };
+
+    let size = surface_size(world);
+    render_window(world, graph, primary, true, size);
+
+    let targets = world
+        .query::<&Camera>()
+        .iter_mut()
+        .into_iter()
+        .filter(|camera| camera.active)
+        .filter_map(|camera| camera.window)
+        .collect::<HashSet<_>>();
+    let secondary = secondary
+        .into_iter()
+        .filter(|entity| targets.contains(entity))
+        .collect::<Vec<_>>();
+    if secondary.is_empty() {
+        return;
+    }
+
+    // commands of the primary window are submitted before its shared buffers are overwritten
+    world.flush_render_commands();
+
+    let primary_config = world.resources.remove::<RenderSurfaceConfiguration>();
+    let primary_view = world.resources.remove::<RenderSurfaceTextureView>();
+
+    for entity in secondary {
+        let (config, view) = {
+            let mut surfaces = world.resources.get_mut::<WindowSurfaces>();
+            let surface = surfaces.secondary.get_mut(&entity).unwrap();
+            (surface.config.clone_wrapped(), surface.view.take().unwrap())
+        };
+        let size = PhysicalSize::new(config.width, config.height);
+        world.resources.insert(config);
+        world.resources.insert(view);
+
+        render_window(world, graph, entity, false, size);
+        world.flush_render_commands();
+
+        let view = world.resources.remove::<RenderSurfaceTextureView>();
+        if let Some(surface) = world
+            .resources
+            .get_mut::<WindowSurfaces>()
+            .secondary
+            .get_mut(&entity)
+        {
+            surface.view = view;
+        }
+    }
+
+    if let Some(config) = primary_config {
+        world.resources.insert(config);
+    }
+    if let Some(view) = primary_view {
+        world.resources.insert(view);
+    }
+    graph.use_window(primary);
+}
+
+/// Execute the render graph for a single window, whose surface is in the resources
+fn render_window(
+    world: &mut World,
+    graph: &mut RenderGraph,
+    entity: EntityId,
+    primary: bool,
+    size: PhysicalSize<u32>,
+) {
+    graph.use_window(entity);
+    graph.fit(size);
+    world.resources.insert(RenderedWindow { entity, primary });
+    graph.execute(world);
+    world.flush_commands();
+    world.resources.remove::<RenderedWindow>();
+}
+
+/// Returns the size of the surface in the resources
+fn surface_size(world: &World) -> PhysicalSize<u32> {
+    let config = world.resources.get::<RenderSurfaceConfiguration>();
+    PhysicalSize::new(config.width, config.height)
+}